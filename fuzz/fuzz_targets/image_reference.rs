@@ -0,0 +1,20 @@
+//! 镜像引用校验的 fuzz target
+//!
+//! 任意输入都不能 panic；通过校验的引用必须能原样还原，且再次解析结果一致。
+
+#![no_main]
+
+use crius::image::reference::parse_image_reference;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    if let Ok(reference) = parse_image_reference(input) {
+        let whole = reference.whole();
+        assert_eq!(whole, input);
+        let reparsed = parse_image_reference(&whole).expect("accepted reference must reparse");
+        assert_eq!(reparsed.whole(), whole);
+    }
+});
@@ -0,0 +1,224 @@
+//! 变更类 RPC 的审计日志
+//!
+//! 每个变更请求结束后向审计文件追加一行 JSON：方法、涉及的 ID、unix socket 对端的
+//! uid/gid/pid（SO_PEERCRED）以及结果，用于区分请求来自 kubelet 还是其他客户端。
+//! 文件以追加模式打开，crius 不会改写已有内容。
+
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tonic::transport::server::UdsConnectInfo;
+use tonic::{Request, Response, Status};
+
+/// unix socket 对端进程的凭据
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerCredentials {
+    pub uid: u32,
+    pub gid: u32,
+    pub pid: Option<i32>,
+}
+
+impl PeerCredentials {
+    /// 取请求所在连接的对端凭据，TCP 连接返回 `None`
+    pub fn from_request<T>(request: &Request<T>) -> Option<Self> {
+        let credentials = request.extensions().get::<UdsConnectInfo>()?.peer_cred?;
+        Some(Self {
+            uid: credentials.uid(),
+            gid: credentials.gid(),
+            pid: credentials.pid(),
+        })
+    }
+}
+
+/// 一条尚未写入的审计记录
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    method: &'static str,
+    peer: Option<PeerCredentials>,
+    ids: BTreeMap<&'static str, String>,
+}
+
+impl AuditEntry {
+    pub fn new<T>(method: &'static str, request: &Request<T>) -> Self {
+        Self {
+            method,
+            peer: PeerCredentials::from_request(request),
+            ids: BTreeMap::new(),
+        }
+    }
+
+    /// 记录涉及的 ID，空值忽略
+    pub fn id(mut self, key: &'static str, value: &str) -> Self {
+        if !value.is_empty() {
+            self.ids.insert(key, value.to_string());
+        }
+        self
+    }
+
+    /// 请求成功时记录响应中的 ID（如新建的容器 ID）
+    pub fn response_id<R>(
+        self,
+        key: &'static str,
+        result: &Result<Response<R>, Status>,
+        value: impl FnOnce(&R) -> &str,
+    ) -> Self {
+        match result {
+            Ok(response) => self.id(key, value(response.get_ref())),
+            Err(_) => self,
+        }
+    }
+}
+
+/// 审计文件中的一行
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub timestamp: String,
+    pub method: String,
+    pub ids: BTreeMap<String, String>,
+    pub peer: Option<PeerCredentials>,
+    /// `OK` 或 gRPC 错误码名称
+    pub result: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub message: String,
+}
+
+/// 以 JSON lines 追加写入审计文件
+#[derive(Debug)]
+pub struct AuditLogger {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl AuditLogger {
+    /// 打开（必要时创建）审计文件，新文件权限为 0600
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent() {
+            crate::utils::create_dir_all_with_mode(parent, crate::utils::DEFAULT_DIR_MODE)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .mode(0o600)
+            .open(&path)?;
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 写入一条记录；写入失败只记录日志，不影响请求本身
+    pub fn record<R>(&self, entry: AuditEntry, result: &Result<Response<R>, Status>) {
+        let (result, message) = match result {
+            Ok(_) => ("OK".to_string(), String::new()),
+            Err(status) => (format!("{:?}", status.code()), status.message().to_string()),
+        };
+        let record = AuditRecord {
+            timestamp: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Nanos, true),
+            method: entry.method.to_string(),
+            ids: entry
+                .ids
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+            peer: entry.peer,
+            result,
+            message,
+        };
+        let mut line = match serde_json::to_vec(&record) {
+            Ok(line) => line,
+            Err(e) => {
+                log::error!("Failed to encode audit record: {}", e);
+                return;
+            }
+        };
+        line.push(b'\n');
+        let mut file = self
+            .file
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Err(e) = file.write_all(&line) {
+            log::error!(
+                "Failed to write audit record to {}: {}",
+                self.path.display(),
+                e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tonic::transport::server::Connected;
+
+    fn read_records(path: &Path) -> Vec<AuditRecord> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn audit_logger_appends_records_with_unix_peer_credentials() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit").join("audit.log");
+
+        let (client, _server) = tokio::net::UnixStream::pair().unwrap();
+        let mut request = Request::new(());
+        request.extensions_mut().insert(client.connect_info());
+
+        let logger = AuditLogger::open(&path).unwrap();
+        logger.record(
+            AuditEntry::new("/runtime.v1.RuntimeService/StopContainer", &request)
+                .id("container_id", "ctr-1")
+                .id("pod_sandbox_id", ""),
+            &Ok(Response::new(())),
+        );
+        drop(logger);
+        let logger = AuditLogger::open(&path).unwrap();
+        logger.record::<()>(
+            AuditEntry::new("/runtime.v1.ImageService/RemoveImage", &Request::new(())),
+            &Err(Status::not_found("image not found")),
+        );
+
+        let records = read_records(&path);
+        assert_eq!(records.len(), 2);
+        assert_eq!(
+            records[0].method,
+            "/runtime.v1.RuntimeService/StopContainer"
+        );
+        assert_eq!(
+            records[0].ids,
+            BTreeMap::from([("container_id".to_string(), "ctr-1".to_string())])
+        );
+        assert_eq!(
+            records[0].peer,
+            Some(PeerCredentials {
+                uid: nix::unistd::getuid().as_raw(),
+                gid: nix::unistd::getgid().as_raw(),
+                pid: Some(std::process::id() as i32),
+            })
+        );
+        assert_eq!(records[0].result, "OK");
+        assert_eq!(records[1].peer, None);
+        assert_eq!(records[1].result, "NotFound");
+        assert_eq!(records[1].message, "image not found");
+        assert_eq!(
+            std::fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+    }
+}
@@ -3,8 +3,9 @@
 //! 提供容器资源限制管理功能，支持cgroups v1和v2
 
 use anyhow::{Context, Result};
-use log::{debug, info};
-use std::path::PathBuf;
+use log::{debug, info, warn};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
 
 /// 资源限制配置
 #[derive(Debug, Clone, Default)]
@@ -19,6 +20,8 @@ pub struct ResourceLimits {
     pub network: Option<NetworkLimit>,
     /// PID限制
     pub pids: Option<PidsLimit>,
+    /// 大页限制
+    pub hugepages: Vec<HugepageLimit>,
 }
 
 /// CPU限制
@@ -109,10 +112,437 @@ pub struct NetworkLimit {
 /// PID限制
 #[derive(Debug, Clone)]
 pub struct PidsLimit {
-    /// 最大PID数量
+    /// 最大PID数量（-1表示不限制）
     pub max: Option<i64>,
 }
 
+/// 大页限制
+#[derive(Debug, Clone)]
+pub struct HugepageLimit {
+    /// 页大小（如"2MB"、"1GB"）
+    pub page_size: String,
+    /// 限制（字节）
+    pub limit: u64,
+}
+
+/// 宿主机大页信息目录
+const HOST_HUGEPAGES_DIR: &str = "/sys/kernel/mm/hugepages";
+
+/// 解析页大小字符串（<size><unit-prefix>B，按1024进制），返回字节数
+pub fn parse_hugepage_size(page_size: &str) -> Result<u64> {
+    let trimmed = page_size.trim();
+    let without_suffix = trimmed
+        .strip_suffix('B')
+        .or_else(|| trimmed.strip_suffix('b'))
+        .unwrap_or(trimmed);
+    let (number, multiplier) = match without_suffix.chars().last() {
+        Some('K') | Some('k') => (&without_suffix[..without_suffix.len() - 1], 1u64 << 10),
+        Some('M') | Some('m') => (&without_suffix[..without_suffix.len() - 1], 1u64 << 20),
+        Some('G') | Some('g') => (&without_suffix[..without_suffix.len() - 1], 1u64 << 30),
+        Some('T') | Some('t') => (&without_suffix[..without_suffix.len() - 1], 1u64 << 40),
+        _ => (without_suffix, 1u64),
+    };
+    let value = number
+        .parse::<u64>()
+        .with_context(|| format!("invalid hugepage size: {:?}", page_size))?;
+    value
+        .checked_mul(multiplier)
+        .filter(|bytes| *bytes > 0)
+        .ok_or_else(|| anyhow::anyhow!("invalid hugepage size: {:?}", page_size))
+}
+
+/// 将页大小格式化为 hugetlb 控制文件中使用的名称（如"2MB"）
+fn hugepage_size_label(bytes: u64) -> String {
+    const UNITS: [(u64, &str); 4] = [
+        (1 << 40, "TB"),
+        (1 << 30, "GB"),
+        (1 << 20, "MB"),
+        (1 << 10, "KB"),
+    ];
+    for (unit, suffix) in UNITS {
+        if bytes >= unit && (bytes / unit) * unit == bytes {
+            return format!("{}{}", bytes / unit, suffix);
+        }
+    }
+    format!("{}B", bytes)
+}
+
+/// 读取目录中的大页尺寸（hugepages-<size>kB），返回字节数
+pub fn hugepage_sizes_in(dir: &Path) -> Vec<u64> {
+    let mut sizes: Vec<u64> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| {
+                    let name = entry.file_name();
+                    let kb = name
+                        .to_str()?
+                        .strip_prefix("hugepages-")?
+                        .strip_suffix("kB")?
+                        .parse::<u64>()
+                        .ok()?;
+                    Some(kb * 1024)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    sizes.sort_unstable();
+    sizes
+}
+
+/// 宿主机支持的大页尺寸
+pub fn host_hugepage_sizes() -> Vec<u64> {
+    hugepage_sizes_in(Path::new(HOST_HUGEPAGES_DIR))
+}
+
+/// 校验页大小是否为宿主机支持的大页尺寸
+pub fn validate_hugepage_size(page_size: &str, supported: &[u64]) -> Result<()> {
+    let bytes = parse_hugepage_size(page_size)?;
+    if supported.contains(&bytes) {
+        return Ok(());
+    }
+    Err(anyhow::anyhow!(
+        "unsupported hugepage size {}: host supports [{}]",
+        page_size,
+        supported
+            .iter()
+            .map(|size| hugepage_size_label(*size))
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
+}
+
+/// 宿主机在线CPU列表（相对 sysfs 挂载点）
+const HOST_ONLINE_CPUS_PATH: &str = "devices/system/cpu/online";
+/// 宿主机在线内存节点列表（相对 sysfs 挂载点）
+const HOST_ONLINE_MEMS_PATH: &str = "devices/system/node/online";
+
+/// cpuset编号上限：内核 NR_CPUS 最大为 8192，更大的编号不可能存在于宿主机
+const MAX_CPUSET_ID: u32 = 8191;
+
+/// 解析cpuset列表（如"0-3,5"）
+///
+/// 编号超过 [`MAX_CPUSET_ID`] 时在展开范围之前拒绝，避免 "0-4294967295" 之类的输入耗尽内存。
+pub fn parse_cpuset_list(list: &str) -> Result<BTreeSet<u32>> {
+    let mut ids = BTreeSet::new();
+    for part in list.trim().split(',').map(str::trim) {
+        if part.is_empty() {
+            continue;
+        }
+        let parse = |value: &str| {
+            let id = value
+                .trim()
+                .parse::<u32>()
+                .with_context(|| format!("invalid cpuset list: {:?}", list))?;
+            if id > MAX_CPUSET_ID {
+                return Err(anyhow::anyhow!(
+                    "cpuset id {} in {:?} exceeds maximum {}",
+                    id,
+                    list,
+                    MAX_CPUSET_ID
+                ));
+            }
+            Ok(id)
+        };
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (parse(start)?, parse(end)?);
+                if start > end {
+                    return Err(anyhow::anyhow!(
+                        "invalid cpuset range {:?} in {:?}",
+                        part,
+                        list
+                    ));
+                }
+                ids.extend(start..=end);
+            }
+            None => {
+                ids.insert(parse(part)?);
+            }
+        }
+    }
+    Ok(ids)
+}
+
+fn read_host_cpuset_list(path: &Path) -> Option<BTreeSet<u32>> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| parse_cpuset_list(&content).ok())
+        .filter(|ids| !ids.is_empty())
+}
+
+/// 宿主机在线CPU，无法读取时返回None
+pub fn host_online_cpus(sysfs_root: &Path) -> Option<BTreeSet<u32>> {
+    read_host_cpuset_list(&sysfs_root.join(HOST_ONLINE_CPUS_PATH))
+}
+
+/// 宿主机在线内存节点，无法读取时返回None
+pub fn host_online_mems(sysfs_root: &Path) -> Option<BTreeSet<u32>> {
+    read_host_cpuset_list(&sysfs_root.join(HOST_ONLINE_MEMS_PATH))
+}
+
+/// 校验请求的cpuset均存在于宿主机
+pub fn validate_cpuset(kind: &str, requested: &str, available: &BTreeSet<u32>) -> Result<()> {
+    let missing: Vec<String> = parse_cpuset_list(requested)?
+        .difference(available)
+        .map(|id| id.to_string())
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    Err(anyhow::anyhow!(
+        "{} {:?} not available on host: missing [{}]",
+        kind,
+        requested,
+        missing.join(",")
+    ))
+}
+
+/// 未指定 CPU 周期时内核使用的默认值（微秒）
+pub const DEFAULT_CPU_PERIOD: i64 = 100_000;
+
+/// CRI 通过 unified 资源中的 `pids.max` 传递 PID 限制
+pub const PIDS_MAX_UNIFIED_KEY: &str = "pids.max";
+
+/// 校验 PID 限制：必须为正数或 -1（不限制）
+pub fn validate_pids_limit(limit: i64) -> Result<()> {
+    if limit > 0 || limit == -1 {
+        return Ok(());
+    }
+    Err(anyhow::anyhow!(
+        "invalid pids limit {}: must be positive or -1 (unlimited)",
+        limit
+    ))
+}
+
+/// 校验 CRI 的内存与 memory+swap 限制，0 表示未设置
+///
+/// swap 取 -1 表示不限制 swap；其余取值必须不小于内存限制，等于内存限制即禁用 swap。
+pub fn validate_memory_swap(limit: i64, swap: i64) -> Result<()> {
+    if swap == 0 || swap == -1 {
+        return Ok(());
+    }
+    if swap < -1 {
+        return Err(anyhow::anyhow!(
+            "invalid memory swap limit {}: must be positive, 0 (unset) or -1 (unlimited)",
+            swap
+        ));
+    }
+    if limit <= 0 {
+        return Err(anyhow::anyhow!(
+            "memory swap limit {} requires a memory limit",
+            swap
+        ));
+    }
+    if swap < limit {
+        return Err(anyhow::anyhow!(
+            "memory swap limit {} must not be smaller than memory limit {}",
+            swap,
+            limit
+        ));
+    }
+    Ok(())
+}
+
+/// 将 OCI 的 memory+swap 限制转换为 cgroup v2 memory.swap.max 内容
+///
+/// v2 只限制 swap 本身，因此取 swap 与内存限制之差；没有内存限制时无法换算。
+fn memory_swap_max_value(limit: Option<i64>, swap: i64) -> Option<String> {
+    if swap < 0 {
+        return Some("max".to_string());
+    }
+    limit
+        .filter(|limit| *limit > 0)
+        .map(|limit| swap.saturating_sub(limit).max(0).to_string())
+}
+
+/// 从 unified 资源中解析 PID 限制，`max` 解析为 -1
+pub fn pids_limit_from_unified(unified: &HashMap<String, String>) -> Result<Option<i64>> {
+    let Some(raw) = unified.get(PIDS_MAX_UNIFIED_KEY) else {
+        return Ok(None);
+    };
+    let raw = raw.trim();
+    let limit = if raw == "max" {
+        -1
+    } else {
+        raw.parse::<i64>()
+            .with_context(|| format!("invalid {} value: {:?}", PIDS_MAX_UNIFIED_KEY, raw))?
+    };
+    validate_pids_limit(limit)?;
+    Ok(Some(limit))
+}
+
+/// 将 PID 限制格式化为 pids.max 文件内容
+fn pids_max_value(limit: i64) -> String {
+    if limit < 0 {
+        "max".to_string()
+    } else {
+        limit.to_string()
+    }
+}
+
+/// 以配置的 cgroup root 为基准计算 OCI `cgroupsPath`
+///
+/// systemd 驱动的 `slice:prefix:name` 格式由 systemd 按 slice 名决定层级，原样使用；
+/// cgroupfs 路径放在 root 之下，已经位于 root 下的路径不重复拼接。没有 cgroup_parent 时
+/// 放在 `<root>/<container_id>`；未配置 root 时沿用 cgroup_parent。
+pub fn cgroups_path(
+    cgroup_root: Option<&str>,
+    cgroup_parent: Option<&str>,
+    container_id: &str,
+) -> Option<String> {
+    let parent = cgroup_parent.filter(|parent| !parent.is_empty());
+    let Some(root) = cgroup_root
+        .map(|root| root.trim_end_matches('/'))
+        .filter(|root| !root.is_empty())
+    else {
+        return parent.map(str::to_string);
+    };
+    match parent {
+        Some(parent) if parent.contains(':') => Some(parent.to_string()),
+        Some(parent) => {
+            let relative = parent.trim_start_matches('/');
+            let root_relative = root.trim_start_matches('/');
+            if relative == root_relative || relative.starts_with(&format!("{}/", root_relative)) {
+                Some(format!("/{}", relative))
+            } else {
+                Some(format!("{}/{}", root, relative))
+            }
+        }
+        None => Some(format!("{}/{}", root, container_id)),
+    }
+}
+
+/// Pod 级 cgroup 的路径：`<cgroup_parent>/crius-pod-<pod_id>`
+///
+/// pause 与业务容器的 cgroup 都嵌套在其下。systemd 格式（`slice:prefix:name`）的
+/// cgroup_parent 由 systemd 决定层级，不创建 Pod 级 cgroup。
+pub fn pod_cgroup_path(cgroup_parent: &str, pod_id: &str) -> Option<String> {
+    let parent = cgroup_parent.trim_end_matches('/');
+    if parent.is_empty() || parent.contains(':') {
+        return None;
+    }
+    Some(format!("{}/crius-pod-{}", parent, pod_id))
+}
+
+/// 校验 cgroup root：必须是以 `/` 开头的 cgroupfs 路径，且在挂载点下存在并可写
+///
+/// cgroup v2 直接位于挂载点下，v1 以 memory 层级为准；返回对应的目录。
+pub fn validate_cgroup_root(mount_point: &Path, root: &str) -> Result<PathBuf> {
+    if !root.starts_with('/') || root.contains(':') || root.split('/').any(|part| part == "..") {
+        return Err(anyhow::anyhow!(
+            "cgroup_root {:?} must be an absolute cgroupfs path such as /kubepods.slice",
+            root
+        ));
+    }
+    let hierarchy = if mount_point.join("cgroup.controllers").exists() {
+        mount_point.to_path_buf()
+    } else {
+        mount_point.join("memory")
+    };
+    let dir = hierarchy.join(root.trim_start_matches('/'));
+    if !dir.is_dir() {
+        return Err(anyhow::anyhow!(
+            "cgroup_root {} does not exist ({})",
+            root,
+            dir.display()
+        ));
+    }
+    nix::unistd::access(&dir, nix::unistd::AccessFlags::W_OK)
+        .with_context(|| format!("cgroup_root {} is not writable ({})", root, dir.display()))?;
+    Ok(dir)
+}
+
+/// 资源限制与统计依赖的 cgroup 控制器
+pub const REQUIRED_CONTROLLERS: [&str; 2] = ["cpu", "memory"];
+
+/// crius 可用的 cgroup 控制器
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct CgroupControllers {
+    /// 可用的控制器，已排序
+    pub available: Vec<String>,
+    /// 缺少的必需控制器
+    pub missing: Vec<String>,
+}
+
+/// 探测 `mount_point` 下的 cgroup 控制器，返回可用与缺少的必需控制器
+///
+/// cgroup v2 读取 crius 所在层级（配置了 cgroup_root 时为 root，否则为挂载点）的
+/// `cgroup.controllers`；配置了 cgroup_root 时由 crius 管理该层级，会把可用的必需控制器写入
+/// `cgroup.subtree_control` 供子 cgroup 使用，写入失败的也视为缺少。v1 以各控制器的挂载目录为准。
+pub fn probe_controllers(
+    mount_point: &Path,
+    cgroup_root: Option<&str>,
+    required: &[&str],
+) -> CgroupControllers {
+    let managed_dir = cgroup_root
+        .map(|root| root.trim_matches('/'))
+        .filter(|root| !root.is_empty())
+        .map(|root| mount_point.join(root));
+    let mut available: Vec<String> = if mount_point.join("cgroup.controllers").exists() {
+        let dir = managed_dir.as_deref().unwrap_or(mount_point);
+        std::fs::read_to_string(dir.join("cgroup.controllers"))
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(str::to_string)
+            .collect()
+    } else {
+        std::fs::read_dir(mount_point)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .flat_map(|name| name.split(',').map(str::to_string).collect::<Vec<_>>())
+            .filter(|name| !matches!(name.as_str(), "unified" | "systemd"))
+            .collect()
+    };
+    available.sort();
+    available.dedup();
+
+    let mut missing: Vec<String> = required
+        .iter()
+        .filter(|controller| !available.iter().any(|name| name == *controller))
+        .map(|controller| controller.to_string())
+        .collect();
+    if let Some(dir) = managed_dir.filter(|_| mount_point.join("cgroup.controllers").exists()) {
+        for controller in required
+            .iter()
+            .filter(|controller| available.iter().any(|name| name == *controller))
+        {
+            if let Err(err) = enable_subtree_controller(&dir, controller) {
+                warn!(
+                    "Failed to enable cgroup controller {} in {}: {:#}",
+                    controller,
+                    dir.display(),
+                    err
+                );
+                missing.push(controller.to_string());
+            }
+        }
+    }
+
+    CgroupControllers { available, missing }
+}
+
+/// 在 `dir` 的 `cgroup.subtree_control` 中启用控制器，已启用时不写入
+fn enable_subtree_controller(dir: &Path, controller: &str) -> Result<()> {
+    let path = dir.join("cgroup.subtree_control");
+    let enabled = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    if enabled.split_whitespace().any(|name| name == controller) {
+        return Ok(());
+    }
+    std::fs::write(&path, format!("+{}", controller))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    debug!(
+        "Enabled cgroup controller {} in {}",
+        controller,
+        dir.display()
+    );
+    Ok(())
+}
+
 /// Cgroups管理器
 pub struct CgroupManager {
     /// cgroups挂载点
@@ -121,6 +551,8 @@ pub struct CgroupManager {
     version: CgroupVersion,
     /// 容器ID
     container_id: String,
+    /// 挂载点（v1 为各子系统目录）下的相对路径
+    path: PathBuf,
 }
 
 /// Cgroups版本
@@ -140,10 +572,44 @@ impl CgroupManager {
         Ok(Self {
             mount_point,
             version,
+            path: Path::new("crius").join(&container_id),
             container_id,
         })
     }
 
+    /// 使用指定挂载点和版本创建cgroups管理器
+    pub fn with_mount_point(
+        container_id: String,
+        mount_point: PathBuf,
+        version: CgroupVersion,
+    ) -> Self {
+        Self {
+            mount_point,
+            version,
+            path: Path::new("crius").join(&container_id),
+            container_id,
+        }
+    }
+
+    /// 管理挂载点下指定相对路径的 cgroup，如 Pod 级 cgroup
+    pub fn with_path(path: &str, mount_point: PathBuf, version: CgroupVersion) -> Self {
+        Self {
+            mount_point,
+            version,
+            container_id: path.to_string(),
+            path: PathBuf::from(path.trim_start_matches('/')),
+        }
+    }
+
+    /// 按挂载点下是否有 `cgroup.controllers` 判断 cgroups 版本
+    pub fn version_at(mount_point: &Path) -> CgroupVersion {
+        if mount_point.join("cgroup.controllers").exists() {
+            CgroupVersion::V2
+        } else {
+            CgroupVersion::V1
+        }
+    }
+
     /// 检测cgroups版本
     fn detect_cgroup_version() -> Result<(PathBuf, CgroupVersion)> {
         // 检查cgroup v2
@@ -188,15 +654,11 @@ impl CgroupManager {
     /// 创建cgroup v1
     fn create_cgroup_v1(&self) -> Result<()> {
         let subsystems = [
-            "cpu", "cpuacct", "cpuset", "memory", "blkio", "pids", "devices",
+            "cpu", "cpuacct", "cpuset", "memory", "blkio", "pids", "devices", "hugetlb",
         ];
 
         for subsystem in &subsystems {
-            let path = self
-                .mount_point
-                .join(subsystem)
-                .join("crius")
-                .join(&self.container_id);
+            let path = self.mount_point.join(subsystem).join(&self.path);
             std::fs::create_dir_all(&path)
                 .with_context(|| format!("Failed to create cgroup v1 directory: {:?}", path))?;
             debug!("Created cgroup v1 directory: {:?}", path);
@@ -207,16 +669,22 @@ impl CgroupManager {
 
     /// 创建cgroup v2
     fn create_cgroup_v2(&self) -> Result<()> {
-        let path = self.mount_point.join("crius").join(&self.container_id);
+        let path = self.mount_point.join(&self.path);
         std::fs::create_dir_all(&path)
             .with_context(|| format!("Failed to create cgroup v2 directory: {:?}", path))?;
 
-        // 启用所有控制器
-        let cgroup_subtree = self.mount_point.join("cgroup.subtree_control");
-        if cgroup_subtree.exists() {
-            let controllers = ["cpu", "memory", "io", "pids"];
-            for controller in &controllers {
-                let _ = std::fs::write(&cgroup_subtree, format!("+{}", controller));
+        // 在各级父 cgroup 中启用所有控制器，嵌套的 cgroup 才能设置限制
+        let controllers = ["cpu", "cpuset", "memory", "io", "pids", "hugetlb"];
+        for parent in path
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(&self.mount_point))
+        {
+            let cgroup_subtree = parent.join("cgroup.subtree_control");
+            if cgroup_subtree.exists() {
+                for controller in &controllers {
+                    let _ = std::fs::write(&cgroup_subtree, format!("+{}", controller));
+                }
             }
         }
 
@@ -227,15 +695,11 @@ impl CgroupManager {
     /// 删除cgroup v1
     fn remove_cgroup_v1(&self) -> Result<()> {
         let subsystems = [
-            "cpu", "cpuacct", "cpuset", "memory", "blkio", "pids", "devices",
+            "cpu", "cpuacct", "cpuset", "memory", "blkio", "pids", "devices", "hugetlb",
         ];
 
         for subsystem in &subsystems {
-            let path = self
-                .mount_point
-                .join(subsystem)
-                .join("crius")
-                .join(&self.container_id);
+            let path = self.mount_point.join(subsystem).join(&self.path);
             if path.exists() {
                 let _ = std::fs::remove_dir(&path);
             }
@@ -247,7 +711,7 @@ impl CgroupManager {
 
     /// 删除cgroup v2
     fn remove_cgroup_v2(&self) -> Result<()> {
-        let path = self.mount_point.join("crius").join(&self.container_id);
+        let path = self.mount_point.join(&self.path);
         if path.exists() {
             // 先杀死所有进程
             let procs_file = path.join("cgroup.procs");
@@ -276,11 +740,7 @@ impl CgroupManager {
     fn set_resources_v1(&self, limits: &ResourceLimits) -> Result<()> {
         // 设置CPU限制
         if let Some(cpu) = &limits.cpu {
-            let cpu_path = self
-                .mount_point
-                .join("cpu")
-                .join("crius")
-                .join(&self.container_id);
+            let cpu_path = self.mount_point.join("cpu").join(&self.path);
 
             if let Some(shares) = cpu.shares {
                 self.write_file(&cpu_path.join("cpu.shares"), shares.to_string())?;
@@ -291,18 +751,19 @@ impl CgroupManager {
             if let Some(period) = cpu.period {
                 self.write_file(&cpu_path.join("cpu.cfs_period_us"), period.to_string())?;
             }
+
+            let cpuset_path = self.mount_point.join("cpuset").join(&self.path);
             if let Some(cpus) = &cpu.cpus {
-                self.write_file(&cpu_path.join("cpuset.cpus"), cpus.clone())?;
+                self.write_file(&cpuset_path.join("cpuset.cpus"), cpus.clone())?;
+            }
+            if let Some(mems) = &cpu.mems {
+                self.write_file(&cpuset_path.join("cpuset.mems"), mems.clone())?;
             }
         }
 
         // 设置内存限制
         if let Some(memory) = &limits.memory {
-            let mem_path = self
-                .mount_point
-                .join("memory")
-                .join("crius")
-                .join(&self.container_id);
+            let mem_path = self.mount_point.join("memory").join(&self.path);
 
             if let Some(limit) = memory.limit {
                 self.write_file(&mem_path.join("memory.limit_in_bytes"), limit.to_string())?;
@@ -329,14 +790,23 @@ impl CgroupManager {
 
         // 设置PID限制
         if let Some(pids) = &limits.pids {
-            let pids_path = self
-                .mount_point
-                .join("pids")
-                .join("crius")
-                .join(&self.container_id);
+            let pids_path = self.mount_point.join("pids").join(&self.path);
 
             if let Some(max) = pids.max {
-                self.write_file(&pids_path.join("pids.max"), max.to_string())?;
+                self.write_file(&pids_path.join("pids.max"), pids_max_value(max))?;
+            }
+        }
+
+        // 设置大页限制
+        if !limits.hugepages.is_empty() {
+            let hugetlb_path = self.mount_point.join("hugetlb").join(&self.path);
+
+            for hugepage in &limits.hugepages {
+                let label = hugepage_size_label(parse_hugepage_size(&hugepage.page_size)?);
+                self.write_file(
+                    &hugetlb_path.join(format!("hugetlb.{}.limit_in_bytes", label)),
+                    hugepage.limit.to_string(),
+                )?;
             }
         }
 
@@ -346,7 +816,7 @@ impl CgroupManager {
 
     /// 设置资源限制v2
     fn set_resources_v2(&self, limits: &ResourceLimits) -> Result<()> {
-        let cgroup_path = self.mount_point.join("crius").join(&self.container_id);
+        let cgroup_path = self.mount_point.join(&self.path);
 
         // 设置CPU限制
         if let Some(cpu) = &limits.cpu {
@@ -363,7 +833,7 @@ impl CgroupManager {
             if let Some(period) = cpu.period {
                 cpu_max.push_str(&period.to_string());
             } else {
-                cpu_max.push_str("100000");
+                cpu_max.push_str(&DEFAULT_CPU_PERIOD.to_string());
             }
 
             self.write_file(&cgroup_path.join("cpu.max"), cpu_max)?;
@@ -377,6 +847,9 @@ impl CgroupManager {
             if let Some(cpus) = &cpu.cpus {
                 self.write_file(&cgroup_path.join("cpuset.cpus"), cpus.clone())?;
             }
+            if let Some(mems) = &cpu.mems {
+                self.write_file(&cgroup_path.join("cpuset.mems"), mems.clone())?;
+            }
         }
 
         // 设置内存限制
@@ -385,7 +858,13 @@ impl CgroupManager {
                 self.write_file(&cgroup_path.join("memory.max"), limit.to_string())?;
             }
             if let Some(swap) = memory.swap {
-                self.write_file(&cgroup_path.join("memory.swap.max"), swap.to_string())?;
+                match memory_swap_max_value(memory.limit, swap) {
+                    Some(value) => self.write_file(&cgroup_path.join("memory.swap.max"), value)?,
+                    None => warn!(
+                        "Ignoring memory swap limit {} for container {} without a memory limit",
+                        swap, self.container_id
+                    ),
+                }
             }
             if let Some(reservation) = memory.reservation {
                 self.write_file(&cgroup_path.join("memory.high"), reservation.to_string())?;
@@ -395,10 +874,19 @@ impl CgroupManager {
         // 设置PID限制
         if let Some(pids) = &limits.pids {
             if let Some(max) = pids.max {
-                self.write_file(&cgroup_path.join("pids.max"), max.to_string())?;
+                self.write_file(&cgroup_path.join("pids.max"), pids_max_value(max))?;
             }
         }
 
+        // 设置大页限制
+        for hugepage in &limits.hugepages {
+            let label = hugepage_size_label(parse_hugepage_size(&hugepage.page_size)?);
+            self.write_file(
+                &cgroup_path.join(format!("hugetlb.{}.max", label)),
+                hugepage.limit.to_string(),
+            )?;
+        }
+
         info!(
             "Set resource limits (v2) for container {}",
             self.container_id
@@ -415,19 +903,14 @@ impl CgroupManager {
                     let procs_file = self
                         .mount_point
                         .join(subsystem)
-                        .join("crius")
-                        .join(&self.container_id)
+                        .join(&self.path)
                         .join("cgroup.procs");
 
                     self.write_file(&procs_file, pid.to_string())?;
                 }
             }
             CgroupVersion::V2 => {
-                let procs_file = self
-                    .mount_point
-                    .join("crius")
-                    .join(&self.container_id)
-                    .join("cgroup.procs");
+                let procs_file = self.mount_point.join(&self.path).join("cgroup.procs");
 
                 self.write_file(&procs_file, pid.to_string())?;
             }
@@ -569,7 +1052,16 @@ pub fn to_oci_resources(limits: &ResourceLimits) -> crate::oci::spec::LinuxResou
                     )
                 },
             }),
-        hugepage_limits: None,
+        hugepage_limits: (!limits.hugepages.is_empty()).then(|| {
+            limits
+                .hugepages
+                .iter()
+                .map(|hugepage| crate::oci::spec::LinuxHugepageLimit {
+                    page_size: hugepage.page_size.clone(),
+                    limit: hugepage.limit,
+                })
+                .collect()
+        }),
         devices: None,
         intel_rdt: None,
     }
@@ -579,6 +1071,117 @@ pub fn to_oci_resources(limits: &ResourceLimits) -> crate::oci::spec::LinuxResou
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_probe_controllers_reports_missing_and_enables_subtree() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("cgroup.controllers"), "cpu io memory pids").unwrap();
+        let root = dir.path().join("kubepods");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("cgroup.controllers"), "cpu pids").unwrap();
+        std::fs::write(root.join("cgroup.subtree_control"), "").unwrap();
+
+        let controllers = probe_controllers(dir.path(), Some("/kubepods"), &REQUIRED_CONTROLLERS);
+        assert_eq!(controllers.available, vec!["cpu", "pids"]);
+        assert_eq!(controllers.missing, vec!["memory"]);
+        assert_eq!(
+            std::fs::read_to_string(root.join("cgroup.subtree_control")).unwrap(),
+            "+cpu"
+        );
+
+        // 未配置 root 时只读取挂载点，不修改 subtree_control
+        let controllers = probe_controllers(dir.path(), None, &REQUIRED_CONTROLLERS);
+        assert!(controllers.missing.is_empty());
+        assert!(!dir.path().join("cgroup.subtree_control").exists());
+    }
+
+    #[test]
+    fn test_probe_controllers_v1_uses_mounted_hierarchies() {
+        let dir = tempfile::tempdir().unwrap();
+        for hierarchy in ["cpu,cpuacct", "pids", "systemd"] {
+            std::fs::create_dir_all(dir.path().join(hierarchy)).unwrap();
+        }
+
+        let controllers = probe_controllers(dir.path(), None, &REQUIRED_CONTROLLERS);
+        assert_eq!(controllers.available, vec!["cpu", "cpuacct", "pids"]);
+        assert_eq!(controllers.missing, vec!["memory"]);
+    }
+
+    #[test]
+    fn test_cgroups_path_places_parent_under_root() {
+        assert_eq!(
+            cgroups_path(Some("/kubepods.slice"), Some("/burstable/pod123"), "c1").as_deref(),
+            Some("/kubepods.slice/burstable/pod123")
+        );
+        assert_eq!(
+            cgroups_path(Some("/kubepods.slice/"), Some("burstable/pod123"), "c1").as_deref(),
+            Some("/kubepods.slice/burstable/pod123")
+        );
+        // 已经位于 root 下的 cgroup_parent 不重复拼接
+        assert_eq!(
+            cgroups_path(
+                Some("/kubepods.slice"),
+                Some("/kubepods.slice/pod123"),
+                "c1"
+            )
+            .as_deref(),
+            Some("/kubepods.slice/pod123")
+        );
+        assert_eq!(
+            cgroups_path(Some("/kubepods"), Some("/kubepods-besteffort/pod1"), "c1").as_deref(),
+            Some("/kubepods/kubepods-besteffort/pod1")
+        );
+        assert_eq!(
+            cgroups_path(Some("/kubepods.slice"), None, "c1").as_deref(),
+            Some("/kubepods.slice/c1")
+        );
+        assert_eq!(
+            cgroups_path(
+                Some("/kubepods.slice"),
+                Some("kubepods-pod1.slice:crio:c1"),
+                "c1"
+            )
+            .as_deref(),
+            Some("kubepods-pod1.slice:crio:c1")
+        );
+        assert_eq!(
+            cgroups_path(None, Some("/burstable/pod123"), "c1").as_deref(),
+            Some("/burstable/pod123")
+        );
+        assert_eq!(cgroups_path(Some("/"), Some(""), "c1"), None);
+    }
+
+    #[test]
+    fn test_validate_cgroup_root_requires_existing_hierarchy_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("memory/kubepods")).unwrap();
+        assert_eq!(
+            validate_cgroup_root(dir.path(), "/kubepods").unwrap(),
+            dir.path().join("memory/kubepods")
+        );
+
+        std::fs::write(dir.path().join("cgroup.controllers"), "cpu memory").unwrap();
+        std::fs::create_dir_all(dir.path().join("kubepods.slice")).unwrap();
+        assert_eq!(
+            validate_cgroup_root(dir.path(), "/kubepods.slice").unwrap(),
+            dir.path().join("kubepods.slice")
+        );
+        assert!(validate_cgroup_root(dir.path(), "/kubepods")
+            .unwrap_err()
+            .to_string()
+            .contains("does not exist"));
+        for invalid in [
+            "kubepods.slice",
+            "/kubepods/../system.slice",
+            "kubepods.slice:crio",
+        ] {
+            assert!(
+                validate_cgroup_root(dir.path(), invalid).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
     #[test]
     fn test_resource_limits_default() {
         let limits = ResourceLimits::default();
@@ -620,6 +1223,7 @@ mod tests {
             pids: Some(PidsLimit { max: Some(100) }),
             blkio: None,
             network: None,
+            hugepages: Vec::new(),
         };
 
         let resources = to_oci_resources(&limits);
@@ -627,4 +1231,294 @@ mod tests {
         assert!(resources.memory.is_some());
         assert!(resources.pids.is_some());
     }
+
+    #[test]
+    fn test_pids_limit_from_unified() {
+        let mut unified = HashMap::new();
+        assert_eq!(pids_limit_from_unified(&unified).unwrap(), None);
+
+        unified.insert(PIDS_MAX_UNIFIED_KEY.to_string(), "1024".to_string());
+        assert_eq!(pids_limit_from_unified(&unified).unwrap(), Some(1024));
+
+        unified.insert(PIDS_MAX_UNIFIED_KEY.to_string(), "max".to_string());
+        assert_eq!(pids_limit_from_unified(&unified).unwrap(), Some(-1));
+
+        for invalid in ["0", "-2", "abc"] {
+            unified.insert(PIDS_MAX_UNIFIED_KEY.to_string(), invalid.to_string());
+            assert!(pids_limit_from_unified(&unified).is_err());
+        }
+    }
+
+    #[test]
+    fn test_hugepage_sizes_and_validation() {
+        assert_eq!(parse_hugepage_size("2MB").unwrap(), 2 * 1024 * 1024);
+        assert_eq!(parse_hugepage_size("1GB").unwrap(), 1024 * 1024 * 1024);
+        assert!(parse_hugepage_size("abc").is_err());
+        assert_eq!(hugepage_size_label(2 * 1024 * 1024), "2MB");
+        assert_eq!(hugepage_size_label(1024 * 1024 * 1024), "1GB");
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(temp_dir.path().join("hugepages-2048kB")).unwrap();
+        std::fs::create_dir(temp_dir.path().join("hugepages-1048576kB")).unwrap();
+        let supported = hugepage_sizes_in(temp_dir.path());
+        assert_eq!(supported, vec![2 * 1024 * 1024, 1024 * 1024 * 1024]);
+
+        assert!(validate_hugepage_size("2MB", &supported).is_ok());
+        assert!(validate_hugepage_size("1GB", &supported).is_ok());
+        assert!(validate_hugepage_size("64KB", &supported).is_err());
+    }
+
+    #[test]
+    fn test_set_resources_writes_hugetlb_limits() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let limits = ResourceLimits {
+            hugepages: vec![
+                HugepageLimit {
+                    page_size: "2MB".to_string(),
+                    limit: 4 * 1024 * 1024,
+                },
+                HugepageLimit {
+                    page_size: "1GB".to_string(),
+                    limit: 1024 * 1024 * 1024,
+                },
+            ],
+            ..Default::default()
+        };
+
+        let v2 = CgroupManager::with_mount_point(
+            "hugetlb-test".to_string(),
+            temp_dir.path().join("v2"),
+            CgroupVersion::V2,
+        );
+        let v2_path = temp_dir
+            .path()
+            .join("v2")
+            .join("crius")
+            .join("hugetlb-test");
+        std::fs::create_dir_all(&v2_path).unwrap();
+        v2.set_resources(&limits).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(v2_path.join("hugetlb.2MB.max")).unwrap(),
+            "4194304"
+        );
+        assert_eq!(
+            std::fs::read_to_string(v2_path.join("hugetlb.1GB.max")).unwrap(),
+            "1073741824"
+        );
+
+        let v1 = CgroupManager::with_mount_point(
+            "hugetlb-test".to_string(),
+            temp_dir.path().join("v1"),
+            CgroupVersion::V1,
+        );
+        let v1_path = temp_dir
+            .path()
+            .join("v1")
+            .join("hugetlb")
+            .join("crius")
+            .join("hugetlb-test");
+        std::fs::create_dir_all(&v1_path).unwrap();
+        v1.set_resources(&limits).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(v1_path.join("hugetlb.2MB.limit_in_bytes")).unwrap(),
+            "4194304"
+        );
+        assert_eq!(
+            std::fs::read_to_string(v1_path.join("hugetlb.1GB.limit_in_bytes")).unwrap(),
+            "1073741824"
+        );
+
+        let resources = to_oci_resources(&limits);
+        let oci_limits = resources.hugepage_limits.unwrap();
+        assert_eq!(oci_limits.len(), 2);
+        assert_eq!(oci_limits[0].page_size, "2MB");
+        assert_eq!(oci_limits[1].limit, 1024 * 1024 * 1024);
+    }
+
+    #[test]
+    fn test_memory_swap_validation_and_cgroup_writes() {
+        let gib = 1024 * 1024 * 1024;
+        assert!(validate_memory_swap(gib, 2 * gib).is_ok());
+        assert!(validate_memory_swap(gib, gib).is_ok());
+        assert!(validate_memory_swap(gib, -1).is_ok());
+        assert!(validate_memory_swap(0, 0).is_ok());
+        assert!(validate_memory_swap(2 * gib, gib).is_err());
+        assert!(validate_memory_swap(0, gib).is_err());
+        assert!(validate_memory_swap(gib, -2).is_err());
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let limits = |swap| ResourceLimits {
+            memory: Some(MemoryLimit {
+                limit: Some(gib),
+                reservation: None,
+                swap: Some(swap),
+                kernel: None,
+                kernel_tcp: None,
+                swappiness: None,
+                disable_oom_killer: None,
+                use_hierarchy: None,
+            }),
+            ..Default::default()
+        };
+
+        let v2 = CgroupManager::with_mount_point(
+            "swap-test".to_string(),
+            temp_dir.path().join("v2"),
+            CgroupVersion::V2,
+        );
+        let v2_path = temp_dir.path().join("v2").join("crius").join("swap-test");
+        std::fs::create_dir_all(&v2_path).unwrap();
+        for (swap, expected) in [(3 * gib, "2147483648"), (gib, "0"), (-1, "max")] {
+            v2.set_resources(&limits(swap)).unwrap();
+            assert_eq!(
+                std::fs::read_to_string(v2_path.join("memory.swap.max")).unwrap(),
+                expected
+            );
+        }
+
+        let v1 = CgroupManager::with_mount_point(
+            "swap-test".to_string(),
+            temp_dir.path().join("v1"),
+            CgroupVersion::V1,
+        );
+        let v1_path = temp_dir
+            .path()
+            .join("v1")
+            .join("memory")
+            .join("crius")
+            .join("swap-test");
+        std::fs::create_dir_all(&v1_path).unwrap();
+        v1.set_resources(&limits(3 * gib)).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(v1_path.join("memory.limit_in_bytes")).unwrap(),
+            "1073741824"
+        );
+        assert_eq!(
+            std::fs::read_to_string(v1_path.join("memory.memsw.limit_in_bytes")).unwrap(),
+            "3221225472"
+        );
+    }
+
+    #[test]
+    fn test_cpuset_list_parsing_and_validation() {
+        assert_eq!(
+            parse_cpuset_list("0-2,5").unwrap(),
+            BTreeSet::from([0, 1, 2, 5])
+        );
+        assert!(parse_cpuset_list("3-1").is_err());
+        assert!(parse_cpuset_list("a").is_err());
+        assert_eq!(parse_cpuset_list("0-8191").unwrap().len(), 8192);
+        for list in ["0-4294967295", "8192", "1,0-100000"] {
+            let err = parse_cpuset_list(list).unwrap_err();
+            assert!(err.to_string().contains("exceeds maximum"), "{}", list);
+        }
+
+        let available = BTreeSet::from([0, 1, 2, 3]);
+        assert!(validate_cpuset("cpuset_cpus", "0-3", &available).is_ok());
+        let err = validate_cpuset("cpuset_cpus", "2-5", &available).unwrap_err();
+        assert!(err.to_string().contains("missing [4,5]"));
+    }
+
+    #[test]
+    fn test_set_resources_writes_and_updates_cpuset() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cpuset = |cpus: &str, mems: &str| ResourceLimits {
+            cpu: Some(CpuLimit {
+                shares: None,
+                quota: None,
+                period: None,
+                realtime_runtime: None,
+                realtime_period: None,
+                cpus: Some(cpus.to_string()),
+                mems: Some(mems.to_string()),
+            }),
+            ..Default::default()
+        };
+
+        let v1 = CgroupManager::with_mount_point(
+            "cpuset-test".to_string(),
+            temp_dir.path().join("v1"),
+            CgroupVersion::V1,
+        );
+        let v1_cpu_path = temp_dir
+            .path()
+            .join("v1")
+            .join("cpu")
+            .join("crius")
+            .join("cpuset-test");
+        let v1_cpuset_path = temp_dir
+            .path()
+            .join("v1")
+            .join("cpuset")
+            .join("crius")
+            .join("cpuset-test");
+        std::fs::create_dir_all(&v1_cpu_path).unwrap();
+        std::fs::create_dir_all(&v1_cpuset_path).unwrap();
+        v1.set_resources(&cpuset("0-1", "0")).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(v1_cpuset_path.join("cpuset.cpus")).unwrap(),
+            "0-1"
+        );
+        assert_eq!(
+            std::fs::read_to_string(v1_cpuset_path.join("cpuset.mems")).unwrap(),
+            "0"
+        );
+        assert!(!v1_cpu_path.join("cpuset.cpus").exists());
+        v1.set_resources(&cpuset("2", "1")).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(v1_cpuset_path.join("cpuset.cpus")).unwrap(),
+            "2"
+        );
+        assert_eq!(
+            std::fs::read_to_string(v1_cpuset_path.join("cpuset.mems")).unwrap(),
+            "1"
+        );
+
+        let v2 = CgroupManager::with_mount_point(
+            "cpuset-test".to_string(),
+            temp_dir.path().join("v2"),
+            CgroupVersion::V2,
+        );
+        let v2_path = temp_dir.path().join("v2").join("crius").join("cpuset-test");
+        std::fs::create_dir_all(&v2_path).unwrap();
+        v2.set_resources(&cpuset("0-1", "0")).unwrap();
+        v2.set_resources(&cpuset("3", "0")).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(v2_path.join("cpuset.cpus")).unwrap(),
+            "3"
+        );
+        assert_eq!(
+            std::fs::read_to_string(v2_path.join("cpuset.mems")).unwrap(),
+            "0"
+        );
+    }
+
+    #[test]
+    fn test_set_resources_writes_pids_max() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = CgroupManager::with_mount_point(
+            "pids-test".to_string(),
+            temp_dir.path().to_path_buf(),
+            CgroupVersion::V2,
+        );
+        let cgroup_path = temp_dir.path().join("crius").join("pids-test");
+        std::fs::create_dir_all(&cgroup_path).unwrap();
+
+        let mut limits = ResourceLimits {
+            pids: Some(PidsLimit { max: Some(256) }),
+            ..Default::default()
+        };
+        manager.set_resources(&limits).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(cgroup_path.join("pids.max")).unwrap(),
+            "256"
+        );
+
+        limits.pids = Some(PidsLimit { max: Some(-1) });
+        manager.set_resources(&limits).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(cgroup_path.join("pids.max")).unwrap(),
+            "max"
+        );
+    }
 }
@@ -0,0 +1,276 @@
+//! crius 客户端
+//!
+//! 通过 Unix socket 连接 crius 守护进程，对生成的 CRI gRPC 客户端做一层薄封装，
+//! 供集成测试和外部工具使用。
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use tokio::net::UnixStream;
+use tonic::transport::{Channel, Endpoint, Uri};
+
+use crate::image::FsckReport;
+use crate::proto::runtime::v1::image_service_client::ImageServiceClient;
+use crate::proto::runtime::v1::runtime_service_client::RuntimeServiceClient;
+use crate::proto::runtime::v1::{
+    Container, ContainerConfig, ContainerFilter, CreateContainerRequest, Image, ImageSpec,
+    ListContainersRequest, ListImagesRequest, ListPodSandboxRequest, PodSandbox, PodSandboxConfig,
+    PodSandboxFilter, PullImageRequest, RemoveContainerRequest, RemovePodSandboxRequest,
+    RunPodSandboxRequest, StartContainerRequest, StopContainerRequest, StopPodSandboxRequest,
+    VersionRequest, VersionResponse,
+};
+use crate::server::admin::{
+    AdminServiceClient, CheckImageStoreRequest, ExportContainerRequest, NodeSummaryRequest,
+    PauseContainerRequest, ResumeContainerRequest,
+};
+use crate::server::NodeSummary;
+
+/// CRI 客户端
+#[derive(Debug, Clone)]
+pub struct CriusClient {
+    runtime: RuntimeServiceClient<Channel>,
+    image: ImageServiceClient<Channel>,
+    admin: AdminServiceClient,
+}
+
+impl CriusClient {
+    /// 连接 Unix socket，支持 `unix://` 前缀
+    pub async fn connect(socket: impl AsRef<Path>) -> Result<Self> {
+        let socket = socket.as_ref();
+        let socket_path = socket
+            .to_str()
+            .and_then(|raw| raw.strip_prefix("unix://"))
+            .map(PathBuf::from)
+            .unwrap_or_else(|| socket.to_path_buf());
+
+        // Unix socket 下 URI 仅用于满足 tonic 接口，不会被解析
+        let channel = Endpoint::try_from("http://[::]:50051")?
+            .connect_with_connector(tower::service_fn(move |_: Uri| {
+                UnixStream::connect(socket_path.clone())
+            }))
+            .await
+            .with_context(|| format!("Failed to connect to {}", socket.display()))?;
+
+        Ok(Self {
+            runtime: RuntimeServiceClient::new(channel.clone()),
+            image: ImageServiceClient::new(channel.clone()),
+            admin: AdminServiceClient::new(channel),
+        })
+    }
+
+    /// 底层 RuntimeService 客户端
+    pub fn runtime(&mut self) -> &mut RuntimeServiceClient<Channel> {
+        &mut self.runtime
+    }
+
+    /// 底层 ImageService 客户端
+    pub fn image(&mut self) -> &mut ImageServiceClient<Channel> {
+        &mut self.image
+    }
+
+    /// crius 管理服务客户端
+    pub fn admin(&mut self) -> &mut AdminServiceClient {
+        &mut self.admin
+    }
+
+    /// 冻结容器的 cgroup
+    pub async fn pause_container(&mut self, container_id: &str) -> Result<()> {
+        self.admin
+            .pause_container(PauseContainerRequest {
+                container_id: container_id.to_string(),
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// 解冻暂停的容器
+    pub async fn resume_container(&mut self, container_id: &str) -> Result<()> {
+        self.admin
+            .resume_container(ResumeContainerRequest {
+                container_id: container_id.to_string(),
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// 校验并修复守护进程的镜像存储
+    pub async fn check_image_store(&mut self, full: bool) -> Result<FsckReport> {
+        let response = self
+            .admin
+            .check_image_store(CheckImageStoreRequest { full })
+            .await?
+            .into_inner();
+        Ok(FsckReport {
+            verified: response.verified,
+            repaired: response.repaired,
+            removed: response.removed,
+            failed: response.failed,
+        })
+    }
+
+    /// 节点上全部 Pod 与镜像存储的资源用量汇总
+    pub async fn node_summary(&mut self) -> Result<NodeSummary> {
+        let response = self
+            .admin
+            .node_summary(NodeSummaryRequest {})
+            .await?
+            .into_inner();
+        Ok(response.into())
+    }
+
+    /// 把容器 rootfs 的 tar 流写入 `writer`，返回写入的字节数
+    pub async fn export_container(
+        &mut self,
+        container_id: &str,
+        writer: &mut impl std::io::Write,
+    ) -> Result<u64> {
+        let mut stream = self
+            .admin
+            .export_container(ExportContainerRequest {
+                container_id: container_id.to_string(),
+            })
+            .await?
+            .into_inner();
+        let mut written = 0u64;
+        while let Some(chunk) = stream.message().await? {
+            writer.write_all(&chunk.data)?;
+            written += chunk.data.len() as u64;
+        }
+        Ok(written)
+    }
+
+    pub async fn version(&mut self) -> Result<VersionResponse> {
+        let response = self
+            .runtime
+            .version(VersionRequest {
+                version: "v1".to_string(),
+            })
+            .await?;
+        Ok(response.into_inner())
+    }
+
+    pub async fn pull_image(&mut self, image: &str) -> Result<String> {
+        let response = self
+            .image
+            .pull_image(PullImageRequest {
+                image: Some(ImageSpec {
+                    image: image.to_string(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+            .await?;
+        Ok(response.into_inner().image_ref)
+    }
+
+    pub async fn list_images(&mut self) -> Result<Vec<Image>> {
+        let response = self
+            .image
+            .list_images(ListImagesRequest { filter: None })
+            .await?;
+        Ok(response.into_inner().images)
+    }
+
+    /// 创建 Pod sandbox，返回 sandbox ID
+    pub async fn run_pod_sandbox(
+        &mut self,
+        config: PodSandboxConfig,
+        runtime_handler: &str,
+    ) -> Result<String> {
+        let response = self
+            .runtime
+            .run_pod_sandbox(RunPodSandboxRequest {
+                config: Some(config),
+                runtime_handler: runtime_handler.to_string(),
+            })
+            .await?;
+        Ok(response.into_inner().pod_sandbox_id)
+    }
+
+    pub async fn stop_pod_sandbox(&mut self, pod_sandbox_id: &str) -> Result<()> {
+        self.runtime
+            .stop_pod_sandbox(StopPodSandboxRequest {
+                pod_sandbox_id: pod_sandbox_id.to_string(),
+            })
+            .await?;
+        Ok(())
+    }
+
+    pub async fn remove_pod_sandbox(&mut self, pod_sandbox_id: &str) -> Result<()> {
+        self.runtime
+            .remove_pod_sandbox(RemovePodSandboxRequest {
+                pod_sandbox_id: pod_sandbox_id.to_string(),
+            })
+            .await?;
+        Ok(())
+    }
+
+    pub async fn list_pod_sandbox(
+        &mut self,
+        filter: Option<PodSandboxFilter>,
+    ) -> Result<Vec<PodSandbox>> {
+        let response = self
+            .runtime
+            .list_pod_sandbox(ListPodSandboxRequest { filter })
+            .await?;
+        Ok(response.into_inner().items)
+    }
+
+    /// 在 sandbox 中创建容器，返回容器 ID
+    pub async fn create_container(
+        &mut self,
+        pod_sandbox_id: &str,
+        config: ContainerConfig,
+        sandbox_config: PodSandboxConfig,
+    ) -> Result<String> {
+        let response = self
+            .runtime
+            .create_container(CreateContainerRequest {
+                pod_sandbox_id: pod_sandbox_id.to_string(),
+                config: Some(config),
+                sandbox_config: Some(sandbox_config),
+            })
+            .await?;
+        Ok(response.into_inner().container_id)
+    }
+
+    pub async fn start_container(&mut self, container_id: &str) -> Result<()> {
+        self.runtime
+            .start_container(StartContainerRequest {
+                container_id: container_id.to_string(),
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// 停止容器，`timeout` 单位为秒
+    pub async fn stop_container(&mut self, container_id: &str, timeout: i64) -> Result<()> {
+        self.runtime
+            .stop_container(StopContainerRequest {
+                container_id: container_id.to_string(),
+                timeout,
+            })
+            .await?;
+        Ok(())
+    }
+
+    pub async fn remove_container(&mut self, container_id: &str) -> Result<()> {
+        self.runtime
+            .remove_container(RemoveContainerRequest {
+                container_id: container_id.to_string(),
+            })
+            .await?;
+        Ok(())
+    }
+
+    pub async fn list_containers(
+        &mut self,
+        filter: Option<ContainerFilter>,
+    ) -> Result<Vec<Container>> {
+        let response = self
+            .runtime
+            .list_containers(ListContainersRequest { filter })
+            .await?;
+        Ok(response.into_inner().containers)
+    }
+}
@@ -1,16 +1,32 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::prelude::*;
 
 /// 主配置结构体
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
-    /// 根目录
+    /// 根目录（持久化数据）
     pub root: String,
 
+    /// 运行期状态目录（容器 rootfs、Pod 文件等），为空时与 root 相同
+    #[serde(default)]
+    pub state_dir: String,
+
+    /// 新建状态目录的权限（八进制），root、state_dir 与镜像存储目录会被收紧到该权限
+    #[serde(default = "default_dir_mode")]
+    pub dir_mode: String,
+
+    /// 进程 umask（八进制），为空时沿用继承的 umask
+    #[serde(default)]
+    pub umask: String,
+
+    /// 变更类 RPC 的审计日志文件（JSON lines），为空时不记录
+    #[serde(default)]
+    pub audit_log: String,
+
     /// 运行时配置
     pub runtime: RuntimeConfig,
 
@@ -23,6 +39,26 @@ pub struct Config {
     /// NRI 配置
     #[serde(default)]
     pub nri: NriConfig,
+
+    /// gRPC 服务端配置
+    #[serde(default)]
+    pub grpc: GrpcConfig,
+
+    /// exec/attach/port-forward 流式服务配置
+    #[serde(default)]
+    pub streaming: StreamingConfig,
+
+    /// rootless 模式配置
+    #[serde(default)]
+    pub rootless: RootlessModeConfig,
+
+    /// 开始监听前的依赖检查
+    #[serde(default)]
+    pub startup: StartupConfig,
+
+    /// 容器统计采集
+    #[serde(default)]
+    pub stats: StatsConfig,
 }
 
 /// 运行时配置
@@ -36,16 +72,481 @@ pub struct RuntimeConfig {
 
     /// 运行时根目录
     pub root: String,
+
+    /// 容器进程默认配置
+    #[serde(default)]
+    pub process_defaults: ProcessDefaultsConfig,
+
+    /// 与 runc 周期性对账的间隔（秒），0 表示关闭
+    #[serde(default = "default_reconcile_interval_secs")]
+    pub reconcile_interval_secs: u64,
+
+    /// `io.crius.env-file` 注解允许读取的目录，为空表示禁用该注解
+    #[serde(default)]
+    pub env_file_dir: String,
+
+    /// 追加到默认 maskedPaths/readonlyPaths 的路径
+    #[serde(default)]
+    pub protected_paths: ProtectedPathsConfig,
+
+    /// CRI 请求中宿主机路径挂载的准入策略
+    #[serde(default)]
+    pub mount_policy: MountPolicyConfig,
+
+    /// 容器日志轮转默认值，可被容器注解覆盖
+    #[serde(default)]
+    pub container_log: ContainerLogConfig,
+
+    /// 在生成 spec 时展开 env/command/args 中的 `$(VAR)` 引用；kubelet 已自行展开，默认关闭
+    #[serde(default)]
+    pub expand_env_references: bool,
+
+    /// sandbox 未提供 dns_config 时写入 resolv.conf 的默认 DNS
+    #[serde(default)]
+    pub default_dns: DefaultDnsConfig,
+
+    /// `io.crius.fsgroup` 递归设置卷属组时允许的最大条目数，超过则跳过该卷；0 表示不限制
+    #[serde(default = "default_fs_group_max_entries")]
+    pub fs_group_max_entries: u64,
+
+    /// 静态链接的内置 pause 程序（如 crius-pause），设置后 sandbox 不再拉取 pause 镜像；为空时使用镜像
+    #[serde(default)]
+    pub pause_binary: String,
+
+    /// 容器 `/dev/shm` tmpfs 的默认大小，如 `64Mi`；可被 `io.kubernetes.cri-o.ShmSize` 注解覆盖
+    #[serde(default = "default_shm_size")]
+    pub shm_size: String,
+
+    /// `io.crius.rootfs-path` 注解允许使用的预先解压 rootfs 所在目录，为空表示禁用该注解
+    #[serde(default)]
+    pub prepared_rootfs_dir: String,
+
+    /// 注入每个容器的 `KEY=VALUE` 环境变量，优先级低于镜像 Env 与 CRI 请求
+    #[serde(default)]
+    pub default_env: Vec<String>,
+
+    /// cgroupfs 驱动下 sandbox/容器 cgroupsPath 的基准目录，如 `/kubepods`；为空时直接使用 cgroup_parent
+    #[serde(default)]
+    pub cgroup_root: String,
+
+    /// RemoveContainer 停止仍在运行的容器时的优雅退出时间（秒）
+    #[serde(default = "default_remove_grace_period_secs")]
+    pub remove_grace_period_secs: u32,
+
+    /// SIGKILL 后等待容器退出的上限（秒）；RemoveContainer 超时后强制清理该容器
+    #[serde(default = "default_kill_timeout_secs")]
+    pub kill_timeout_secs: u64,
+}
+
+impl RuntimeConfig {
+    /// 解析后的 `/dev/shm` 默认大小（字节）
+    pub fn shm_size(&self) -> Result<u64> {
+        parse_shm_size(&self.shm_size)
+    }
+
+    /// 解析 `default_env` 为 (KEY, VALUE) 列表
+    pub fn default_env(&self) -> Result<Vec<(String, String)>> {
+        self.default_env
+            .iter()
+            .map(|item| match item.split_once('=') {
+                Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+                _ => Err(Error::Config(format!(
+                    "default_env entry {:?} is not KEY=VALUE",
+                    item
+                ))),
+            })
+            .collect()
+    }
+}
+
+fn default_reconcile_interval_secs() -> u64 {
+    10
+}
+
+fn default_remove_grace_period_secs() -> u32 {
+    30
+}
+
+fn default_kill_timeout_secs() -> u64 {
+    10
+}
+
+fn default_shm_size() -> String {
+    "64Mi".to_string()
+}
+
+fn default_fs_group_max_entries() -> u64 {
+    crate::storage::ownership::DEFAULT_FS_GROUP_MAX_ENTRIES
+}
+
+/// oom_score_adj 合法范围
+pub const OOM_SCORE_ADJ_MIN: i64 = -1000;
+pub const OOM_SCORE_ADJ_MAX: i64 = 1000;
+
+/// 校验 oom_score_adj 是否位于 [-1000, 1000]
+pub fn validate_oom_score_adj(value: i64) -> Result<()> {
+    if (OOM_SCORE_ADJ_MIN..=OOM_SCORE_ADJ_MAX).contains(&value) {
+        return Ok(());
+    }
+    Err(Error::Config(format!(
+        "oom_score_adj {} out of range [{}, {}]",
+        value, OOM_SCORE_ADJ_MIN, OOM_SCORE_ADJ_MAX
+    )))
+}
+
+/// 容器进程默认配置，CRI 请求未指定时生效
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct ProcessDefaultsConfig {
+    /// 未指定 oom_score_adj 时使用的默认值
+    pub oom_score_adj: Option<i64>,
+    /// 默认 rlimits
+    pub rlimits: Vec<RlimitConfig>,
+}
+
+/// 在 OCI 默认值之外追加的屏蔽/只读路径，特权容器不生效
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct ProtectedPathsConfig {
+    pub masked_paths: Vec<String>,
+    pub readonly_paths: Vec<String>,
+}
+
+impl ProtectedPathsConfig {
+    /// 路径必须为绝对路径
+    pub fn validate(&self) -> Result<()> {
+        for path in self.masked_paths.iter().chain(&self.readonly_paths) {
+            if !path.starts_with('/') {
+                return Err(Error::Config(format!(
+                    "protected path {:?} must be absolute",
+                    path
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 宿主机路径挂载策略，均按路径前缀（整段目录）匹配，符号链接解析后再比较
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct MountPolicyConfig {
+    /// 非空时只允许挂载这些路径之下的宿主机路径
+    pub allowed_host_paths: Vec<String>,
+    /// 禁止挂载的宿主机路径，优先于 `allowed_host_paths`
+    pub denied_host_paths: Vec<String>,
+    /// 这些路径之下的挂载总是以只读方式挂载
+    pub readonly_host_paths: Vec<String>,
+}
+
+impl MountPolicyConfig {
+    /// 路径必须为绝对路径
+    pub fn validate(&self) -> Result<()> {
+        for path in self
+            .allowed_host_paths
+            .iter()
+            .chain(&self.denied_host_paths)
+            .chain(&self.readonly_host_paths)
+        {
+            if !path.starts_with('/') {
+                return Err(Error::Config(format!(
+                    "mount policy path {:?} must be absolute",
+                    path
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.allowed_host_paths.is_empty()
+            && self.denied_host_paths.is_empty()
+            && self.readonly_host_paths.is_empty()
+    }
+}
+
+/// 容器日志轮转配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct ContainerLogConfig {
+    /// 单个日志文件大小上限，如 `10Mi`；为空或 `0` 表示不轮转
+    pub max_size: String,
+    /// 保留的日志文件数（含当前文件）
+    pub max_files: u32,
+    /// 同时把容器 stdout/stderr 按行转发到 crius 自身的日志，用于小规模调试
+    pub mirror: bool,
+    /// 转发使用的日志级别：`error`/`warn`/`info`/`debug`/`trace`
+    pub mirror_level: String,
+    /// 每个容器每秒最多转发的行数，超出的行丢弃并汇总提示；`0` 表示不限制
+    pub mirror_rate_limit: u32,
+}
+
+impl Default for ContainerLogConfig {
+    fn default() -> Self {
+        Self {
+            max_size: String::new(),
+            max_files: 5,
+            mirror: false,
+            mirror_level: "info".to_string(),
+            mirror_rate_limit: 100,
+        }
+    }
+}
+
+impl ContainerLogConfig {
+    /// 解析后的 (大小上限, 文件数)
+    pub fn limits(&self) -> Result<(u64, u32)> {
+        let max_size = parse_log_size(&self.max_size)?;
+        if max_size > 0 && self.max_files == 0 {
+            return Err(Error::Config(
+                "container_log.max_files must be at least 1".to_string(),
+            ));
+        }
+        Ok((max_size, self.max_files))
+    }
+
+    /// 解析后的日志转发级别
+    pub fn mirror_level(&self) -> Result<tracing::Level> {
+        self.mirror_level.parse().map_err(|_| {
+            Error::Config(format!(
+                "container_log.mirror_level {:?} is not a valid log level",
+                self.mirror_level
+            ))
+        })
+    }
+}
+
+/// 默认 DNS 配置，全部为空时沿用宿主机 resolv.conf
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct DefaultDnsConfig {
+    pub servers: Vec<String>,
+    pub searches: Vec<String>,
+    pub options: Vec<String>,
+}
+
+/// resolv.conf 最多生效的 nameserver 数量（glibc MAXNS）
+const MAX_DNS_SERVERS: usize = 3;
+
+impl DefaultDnsConfig {
+    pub fn is_empty(&self) -> bool {
+        self.servers.is_empty() && self.searches.is_empty() && self.options.is_empty()
+    }
+
+    /// 校验 server 为 IP 地址，search 与 option 不含空白
+    pub fn validate(&self) -> Result<()> {
+        if self.servers.len() > MAX_DNS_SERVERS {
+            return Err(Error::Config(format!(
+                "default_dns.servers has {} entries, at most {} are used by resolv.conf",
+                self.servers.len(),
+                MAX_DNS_SERVERS
+            )));
+        }
+        for server in &self.servers {
+            if server.parse::<std::net::IpAddr>().is_err() {
+                return Err(Error::Config(format!(
+                    "default_dns server {:?} is not an IP address",
+                    server
+                )));
+            }
+        }
+        for (name, values) in [("searches", &self.searches), ("options", &self.options)] {
+            if let Some(value) = values
+                .iter()
+                .find(|value| value.is_empty() || value.contains(char::is_whitespace))
+            {
+                return Err(Error::Config(format!(
+                    "default_dns.{} entry {:?} is empty or contains whitespace",
+                    name, value
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 解析日志大小，支持纯字节数以及 `Ki`/`Mi`/`Gi`、`K`/`M`/`G` 后缀；空串表示 0
+pub fn parse_log_size(value: &str) -> Result<u64> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(0);
+    }
+    parse_byte_size(value).ok_or_else(|| Error::Config(format!("invalid log size {:?}", value)))
+}
+
+/// 解析 `/dev/shm` 大小，格式同 [`parse_log_size`]，必须大于 0
+pub fn parse_shm_size(value: &str) -> Result<u64> {
+    let value = value.trim();
+    if value.starts_with('-') {
+        return Err(Error::Config(format!(
+            "shm size {:?} must not be negative",
+            value
+        )));
+    }
+    parse_byte_size(value)
+        .filter(|size| *size > 0)
+        .ok_or_else(|| Error::Config(format!("invalid shm size {:?}", value)))
+}
+
+fn parse_byte_size(value: &str) -> Option<u64> {
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let multiplier: u64 = match unit {
+        "" => 1,
+        "Ki" => 1 << 10,
+        "Mi" => 1 << 20,
+        "Gi" => 1 << 30,
+        "K" | "k" => 1_000,
+        "M" => 1_000_000,
+        "G" => 1_000_000_000,
+        _ => return None,
+    };
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|number| number.checked_mul(multiplier))
+}
+
+/// 解析八进制权限（如 `0700`、`077`），不允许超出 0777
+pub fn parse_file_mode(value: &str) -> Result<u32> {
+    let value = value.trim();
+    u32::from_str_radix(value, 8)
+        .ok()
+        .filter(|mode| !value.is_empty() && *mode <= 0o777)
+        .ok_or_else(|| Error::Config(format!("invalid octal file mode {:?}", value)))
+}
+
+fn default_dir_mode() -> String {
+    "0700".to_string()
+}
+
+/// rlimit 配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RlimitConfig {
+    /// 资源类型（如 RLIMIT_NOFILE）
+    #[serde(rename = "type")]
+    pub rlimit_type: String,
+    /// 硬限制
+    pub hard: u64,
+    /// 软限制
+    pub soft: u64,
+}
+
+impl ProcessDefaultsConfig {
+    /// 校验默认配置
+    pub fn validate(&self) -> Result<()> {
+        if let Some(value) = self.oom_score_adj {
+            validate_oom_score_adj(value)?;
+        }
+        for rlimit in &self.rlimits {
+            if !rlimit.rlimit_type.starts_with("RLIMIT_") {
+                return Err(Error::Config(format!(
+                    "invalid rlimit type {:?}",
+                    rlimit.rlimit_type
+                )));
+            }
+            if rlimit.soft > rlimit.hard {
+                return Err(Error::Config(format!(
+                    "rlimit {} soft limit {} exceeds hard limit {}",
+                    rlimit.rlimit_type, rlimit.soft, rlimit.hard
+                )));
+            }
+        }
+        Ok(())
+    }
 }
 
 /// 镜像配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImageConfig {
-    /// 镜像存储后端
+    /// 启动时探测的存储后端：overlay（回退到 fuse-overlayfs 或 vfs）、fuse-overlayfs、vfs，仅记录日志
     pub driver: String,
 
-    /// 镜像存储路径
+    /// 镜像存储路径，为空时使用 `<root>/storage`
+    #[serde(default)]
     pub root: String,
+
+    /// 拉取镜像的暂存目录，为空时使用 `<镜像存储路径>/tmp`；
+    /// 应与镜像存储位于同一文件系统，否则完成时需要复制
+    #[serde(default)]
+    pub tmp_dir: String,
+
+    /// pull 成功前是否 fsync 层文件、元数据及所在目录；
+    /// 镜像随节点重启丢弃的临时环境可关闭以提速
+    #[serde(default = "default_image_fsync")]
+    pub fsync: bool,
+
+    /// 镜像存储容量上限（字节），0 表示不限制；pull 超出时先清理未使用的镜像
+    #[serde(default)]
+    pub max_store_bytes: u64,
+
+    /// 拉取时接受的 manifest 媒体类型（按优先级），默认包含 OCI 与 Docker v2 的 manifest 及 index
+    #[serde(default = "default_manifest_media_types")]
+    pub manifest_media_types: Vec<String>,
+
+    /// Status 中 ImageReady 条件探测可达性的 registry（如 `registry.k8s.io`），为空表示不探测
+    #[serde(default)]
+    pub health_check_registry: String,
+
+    /// registry 探测结果的缓存时间（秒）
+    #[serde(default = "default_image_health_check_interval_secs")]
+    pub health_check_interval_secs: u64,
+
+    /// pull 标签引用时是否总是向 registry 重新解析；关闭时本地已有该标签即直接复用。
+    /// 标签解析到新镜像时总会从旧镜像上移走
+    #[serde(default)]
+    pub refresh_tags: bool,
+
+    /// 启动后在后台校验镜像存储：为空不校验，`quick` 只比较层文件大小，
+    /// `full` 还重新计算 sha256（代价与镜像总大小成正比）
+    #[serde(default)]
+    pub fsck_on_start: String,
+}
+
+fn default_image_fsync() -> bool {
+    true
+}
+
+fn default_image_health_check_interval_secs() -> u64 {
+    60
+}
+
+fn default_manifest_media_types() -> Vec<String> {
+    crate::image::DEFAULT_MANIFEST_MEDIA_TYPES
+        .iter()
+        .map(|media_type| media_type.to_string())
+        .collect()
+}
+
+impl ImageConfig {
+    /// 媒体类型须为已支持的 manifest/index 类型，且至少包含一种单平台 manifest
+    pub fn validate(&self) -> Result<()> {
+        for media_type in &self.manifest_media_types {
+            if !crate::image::DEFAULT_MANIFEST_MEDIA_TYPES.contains(&media_type.as_str()) {
+                return Err(Error::Config(format!(
+                    "unsupported manifest media type {:?}",
+                    media_type
+                )));
+            }
+        }
+        if self
+            .manifest_media_types
+            .iter()
+            .all(|media_type| crate::image::is_index_media_type(media_type))
+        {
+            return Err(Error::Config(
+                "manifest_media_types must include an image manifest type".to_string(),
+            ));
+        }
+        if !self.fsck_on_start.is_empty() {
+            self.fsck_on_start
+                .parse::<crate::image::FsckMode>()
+                .map_err(Error::Config)?;
+        }
+        Ok(())
+    }
 }
 
 /// 网络配置
@@ -56,6 +557,157 @@ pub struct NetworkConfig {
 
     /// 网络配置目录
     pub config_dir: String,
+
+    /// CNI 配置目录轮询间隔（秒），为 0 时不监听
+    #[serde(default)]
+    pub config_watch_interval_secs: u64,
+}
+
+/// gRPC 服务端配置
+///
+/// 秒数或数量为 0 表示关闭对应的设置。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GrpcConfig {
+    /// HTTP/2 keepalive ping 间隔（秒）
+    pub keepalive_interval_secs: u64,
+    /// 等待 keepalive ping 应答的超时（秒），超时后关闭连接
+    pub keepalive_timeout_secs: u64,
+    /// 单个连接上的最大并发 stream 数
+    pub max_concurrent_streams: u32,
+    /// 同时保持的最大连接数，超出的新连接直接关闭
+    pub max_connections: usize,
+    /// 允许连接 unix socket 的对端 uid，与 `allowed_gids` 均为空时不限制
+    pub allowed_uids: Vec<u32>,
+    /// 允许连接 unix socket 的对端 gid
+    pub allowed_gids: Vec<u32>,
+}
+
+impl Default for GrpcConfig {
+    fn default() -> Self {
+        Self {
+            keepalive_interval_secs: 60,
+            keepalive_timeout_secs: 20,
+            max_concurrent_streams: 1000,
+            max_connections: 128,
+            allowed_uids: Vec::new(),
+            allowed_gids: Vec::new(),
+        }
+    }
+}
+
+/// 流式服务配置
+///
+/// 秒数或数量为 0 表示关闭对应的设置。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StreamingConfig {
+    /// 同时存在的会话上限，包括已签发未连接的 token
+    pub max_sessions: usize,
+    /// 会话无数据收发超过该时长（秒）后断开，并杀掉对应的 exec 进程
+    pub idle_timeout_secs: u64,
+    /// 停止容器时杀掉仍在运行的 exec 进程；关闭后这些进程可能在容器停止后残留
+    pub kill_exec_on_stop: bool,
+}
+
+impl Default for StreamingConfig {
+    fn default() -> Self {
+        Self {
+            max_sessions: 0,
+            idle_timeout_secs: 4 * 60 * 60,
+            kill_exec_on_stop: true,
+        }
+    }
+}
+
+/// rootless 模式配置
+///
+/// 以非 root 用户运行时容器进入用户命名空间，Pod 网络由 slirp4netns/pasta 提供，
+/// 不支持宿主机网络与特权容器。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RootlessModeConfig {
+    /// 是否启用 rootless 模式
+    pub enabled: bool,
+    /// Pod 使用的用户态网络：slirp4netns、pasta 或 none
+    pub network: String,
+}
+
+impl Default for RootlessModeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            network: "slirp4netns".to_string(),
+        }
+    }
+}
+
+impl RootlessModeConfig {
+    /// 解析为运行时使用的 rootless 配置，未启用时为 `None`
+    pub fn rootless_config(&self) -> Result<Option<crate::rootless::RootlessConfig>> {
+        let network = self
+            .network
+            .parse::<crate::rootless::NetworkMode>()
+            .map_err(Error::Config)?;
+        Ok(self.enabled.then(|| {
+            crate::rootless::RootlessConfig::new()
+                .with_network_mode(network)
+                .enable()
+        }))
+    }
+}
+
+/// 启动检查配置
+///
+/// 开始监听 CRI 端点前等待启用的检查全部通过，超过 `timeout_secs` 仍未通过时 crius 报告
+/// 失败项并以非零状态退出。默认不启用任何检查。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StartupConfig {
+    /// 等待依赖就绪的最长时间（秒），0 表示只检查一次
+    pub timeout_secs: u64,
+    /// 要求 OCI 运行时二进制存在且可执行
+    pub require_runtime: bool,
+    /// 要求至少有一个可用的 CNI 配置及其插件（rootless 模式下不检查）
+    pub require_cni: bool,
+    /// 要求镜像存储可写
+    pub require_image_store: bool,
+    /// 要求 pause 镜像已在本地（配置了 `pause_binary` 时视为满足）
+    pub require_pause_image: bool,
+}
+
+impl Default for StartupConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 60,
+            require_runtime: false,
+            require_cni: false,
+            require_image_store: false,
+            require_pause_image: false,
+        }
+    }
+}
+
+/// 容器统计采集配置
+///
+/// ListContainerStats 需要读取每个容器的 cgroup，容器很多时频繁的全量扫描会造成 CPU 尖峰。
+/// 间隔内的请求复用最近一次扫描的结果，并发请求共享同一次扫描。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatsConfig {
+    /// 两次全量扫描之间的最小间隔（毫秒），0 表示每次请求都重新扫描
+    pub scan_interval_ms: u64,
+    /// 每批采集的容器数，批次之间让出执行权；0 表示不分批
+    pub batch_size: usize,
+}
+
+impl Default for StatsConfig {
+    fn default() -> Self {
+        Self {
+            scan_interval_ms: 1000,
+            batch_size: 100,
+        }
+    }
 }
 
 /// NRI 配置
@@ -150,28 +802,121 @@ impl Config {
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
         let content = fs::read_to_string(path)?;
         let config: Self = toml::from_str(&content)?;
+        config.runtime.process_defaults.validate()?;
+        config.runtime.protected_paths.validate()?;
+        config.runtime.mount_policy.validate()?;
+        config.runtime.container_log.limits()?;
+        config.runtime.container_log.mirror_level()?;
+        config.runtime.shm_size()?;
+        config.runtime.default_env()?;
+        config.runtime.default_dns.validate()?;
+        config.image.validate()?;
+        config.rootless.rootless_config()?;
+        config.dir_mode()?;
+        config.umask()?;
         Ok(config)
     }
+
+    /// 新建状态目录的权限
+    pub fn dir_mode(&self) -> Result<u32> {
+        let mode = parse_file_mode(&self.dir_mode)?;
+        if mode & 0o700 != 0o700 {
+            return Err(Error::Config(format!(
+                "dir_mode {:?} must grant the owner read, write and search permission",
+                self.dir_mode
+            )));
+        }
+        Ok(mode)
+    }
+
+    /// 启动时设置的进程 umask
+    pub fn umask(&self) -> Result<Option<u32>> {
+        if self.umask.trim().is_empty() {
+            return Ok(None);
+        }
+        parse_file_mode(&self.umask).map(Some)
+    }
+
+    /// 运行期状态目录
+    pub fn state_dir(&self) -> PathBuf {
+        if self.state_dir.is_empty() {
+            PathBuf::from(&self.root)
+        } else {
+            PathBuf::from(&self.state_dir)
+        }
+    }
+
+    /// 镜像存储根目录
+    pub fn image_root(&self) -> PathBuf {
+        if self.image.root.is_empty() {
+            Path::new(&self.root).join("storage")
+        } else {
+            PathBuf::from(&self.image.root)
+        }
+    }
+
+    /// 镜像下载暂存目录
+    pub fn image_tmp_dir(&self) -> PathBuf {
+        if self.image.tmp_dir.is_empty() {
+            self.image_root().join("tmp")
+        } else {
+            PathBuf::from(&self.image.tmp_dir)
+        }
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             root: "/var/lib/crius".to_string(),
+            state_dir: String::new(),
+            dir_mode: default_dir_mode(),
+            umask: String::new(),
+            audit_log: String::new(),
             runtime: RuntimeConfig {
                 runtime_type: "runc".to_string(),
                 runtime_path: "/usr/bin/runc".to_string(),
                 root: "/run/crius".to_string(),
+                process_defaults: ProcessDefaultsConfig::default(),
+                reconcile_interval_secs: default_reconcile_interval_secs(),
+                env_file_dir: String::new(),
+                protected_paths: ProtectedPathsConfig::default(),
+                mount_policy: MountPolicyConfig::default(),
+                container_log: ContainerLogConfig::default(),
+                expand_env_references: false,
+                default_dns: DefaultDnsConfig::default(),
+                fs_group_max_entries: default_fs_group_max_entries(),
+                pause_binary: String::new(),
+                shm_size: default_shm_size(),
+                prepared_rootfs_dir: String::new(),
+                default_env: Vec::new(),
+                cgroup_root: String::new(),
+                remove_grace_period_secs: default_remove_grace_period_secs(),
+                kill_timeout_secs: default_kill_timeout_secs(),
             },
             image: ImageConfig {
                 driver: "overlay".to_string(),
-                root: "/var/lib/containers/storage".to_string(),
+                root: "/var/lib/crius/storage".to_string(),
+                tmp_dir: String::new(),
+                fsync: default_image_fsync(),
+                max_store_bytes: 0,
+                manifest_media_types: default_manifest_media_types(),
+                health_check_registry: String::new(),
+                health_check_interval_secs: default_image_health_check_interval_secs(),
+                refresh_tags: false,
+                fsck_on_start: String::new(),
             },
             network: NetworkConfig {
                 plugin: "cni".to_string(),
                 config_dir: "/etc/cni/net.d/".to_string(),
+                config_watch_interval_secs: 0,
             },
             nri: NriConfig::default(),
+            grpc: GrpcConfig::default(),
+            streaming: StreamingConfig::default(),
+            rootless: RootlessModeConfig::default(),
+            startup: StartupConfig::default(),
+            stats: StatsConfig::default(),
         }
     }
 }
@@ -0,0 +1,428 @@
+//! 节点状态转储
+//!
+//! `crius dump` 通过 CRI socket 向运行中的守护进程查询全部 sandbox、容器、镜像的 verbose 状态
+//! 与生效的运行时配置，连同守护进程使用的 CNI 配置和配置文件写成单个 JSON 文档，
+//! 便于排查问题时整体附上。守护进程无需停止；registry 凭据等敏感字段替换为 [`REDACTED`]，
+//! 容器环境变量常携带密钥，默认只保留变量名，值同样替换为 [`REDACTED`]。
+
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde_json::{json, Map, Value};
+
+use crate::client::CriusClient;
+use crate::config::Config;
+use crate::proto::runtime::v1::{
+    ContainerState, ContainerStatusRequest, PodSandboxState, PodSandboxStatusRequest, StatusRequest,
+};
+
+/// 敏感字段被替换成的值
+pub const REDACTED: &str = "<redacted>";
+
+/// 字段名（忽略大小写与 `_`/`-`）包含这些片段时视为敏感
+const SECRET_KEY_FRAGMENTS: &[&str] = &[
+    "auth",
+    "password",
+    "passwd",
+    "token",
+    "secret",
+    "credential",
+    "privatekey",
+];
+
+/// 向守护进程收集状态，`config` 为本地读取的配置文件
+///
+/// `include_env` 为 false 时屏蔽 OCI spec、容器配置与 `default_env` 中的环境变量值。
+pub async fn collect(
+    client: &mut CriusClient,
+    config: Option<(&Path, &Config)>,
+    include_env: bool,
+) -> Result<Value> {
+    let version = client.version().await.context("Version request failed")?;
+    let status = client
+        .runtime()
+        .status(StatusRequest { verbose: true })
+        .await
+        .context("Status request failed")?
+        .into_inner();
+    let runtime_info = parse_info(status.info);
+    let conditions: Vec<Value> = status
+        .status
+        .map(|status| status.conditions)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|condition| {
+            json!({
+                "type": condition.r#type,
+                "status": condition.status,
+                "reason": condition.reason,
+                "message": condition.message,
+            })
+        })
+        .collect();
+
+    let mut pod_sandboxes = Vec::new();
+    for pod in client.list_pod_sandbox(None).await? {
+        let status = client
+            .runtime()
+            .pod_sandbox_status(PodSandboxStatusRequest {
+                pod_sandbox_id: pod.id.clone(),
+                verbose: true,
+            })
+            .await
+            .map(|response| response.into_inner());
+        let (ip, info) = match status {
+            Ok(status) => (
+                status
+                    .status
+                    .and_then(|status| status.network)
+                    .map(|network| network.ip),
+                parse_info(status.info),
+            ),
+            Err(e) => (None, json!({ "error": e.message() })),
+        };
+        pod_sandboxes.push(json!({
+            "id": pod.id,
+            "metadata": pod.metadata.map(|metadata| json!({
+                "name": metadata.name,
+                "uid": metadata.uid,
+                "namespace": metadata.namespace,
+                "attempt": metadata.attempt,
+            })),
+            "state": PodSandboxState::try_from(pod.state)
+                .map(|state| state.as_str_name().to_string())
+                .unwrap_or_else(|_| pod.state.to_string()),
+            "createdAt": pod.created_at,
+            "labels": pod.labels,
+            "annotations": pod.annotations,
+            "runtimeHandler": pod.runtime_handler,
+            "ip": ip,
+            "info": info,
+        }));
+    }
+
+    let mut containers = Vec::new();
+    for container in client.list_containers(None).await? {
+        let status = client
+            .runtime()
+            .container_status(ContainerStatusRequest {
+                container_id: container.id.clone(),
+                verbose: true,
+            })
+            .await
+            .map(|response| response.into_inner());
+        let (status, info) = match status {
+            Ok(response) => (
+                response.status.map(|status| {
+                    json!({
+                        "startedAt": status.started_at,
+                        "finishedAt": status.finished_at,
+                        "exitCode": status.exit_code,
+                        "reason": status.reason,
+                        "message": status.message,
+                        "logPath": status.log_path,
+                    })
+                }),
+                parse_info(response.info),
+            ),
+            Err(e) => (None, json!({ "error": e.message() })),
+        };
+        containers.push(json!({
+            "id": container.id,
+            "podSandboxId": container.pod_sandbox_id,
+            "metadata": container.metadata.map(|metadata| json!({
+                "name": metadata.name,
+                "attempt": metadata.attempt,
+            })),
+            "image": container.image.map(|image| image.image),
+            "imageRef": container.image_ref,
+            "state": ContainerState::try_from(container.state)
+                .map(|state| state.as_str_name().to_string())
+                .unwrap_or_else(|_| container.state.to_string()),
+            "createdAt": container.created_at,
+            "labels": container.labels,
+            "annotations": container.annotations,
+            "status": status,
+            "info": info,
+        }));
+    }
+
+    let images: Vec<Value> = client
+        .list_images()
+        .await?
+        .into_iter()
+        .map(|image| {
+            json!({
+                "id": image.id,
+                "repoTags": image.repo_tags,
+                "repoDigests": image.repo_digests,
+                "size": image.size,
+                "uid": image.uid.map(|uid| uid.value),
+                "username": image.username,
+                "pinned": image.pinned,
+            })
+        })
+        .collect();
+
+    // 优先使用守护进程上报的 CNI 目录，旧版本未上报时回退到本进程的环境变量
+    let cni_config_dirs: Vec<PathBuf> = runtime_info
+        .pointer("/config/cniConfigDirs")
+        .and_then(Value::as_array)
+        .map(|dirs| {
+            dirs.iter()
+                .filter_map(Value::as_str)
+                .map(PathBuf::from)
+                .collect()
+        })
+        .unwrap_or_else(|| crate::network::CniConfig::from_env().config_dirs().to_vec());
+
+    let config_file = config.map(|(path, config)| {
+        json!({
+            "path": path.display().to_string(),
+            "config": serde_json::to_value(config).unwrap_or(Value::Null),
+        })
+    });
+
+    let mut dump = json!({
+        "generatedAt": chrono::Utc::now().to_rfc3339(),
+        "version": {
+            "runtimeName": version.runtime_name,
+            "runtimeVersion": version.runtime_version,
+            "runtimeApiVersion": version.runtime_api_version,
+        },
+        "runtime": {
+            "conditions": conditions,
+            "info": runtime_info,
+        },
+        "configFile": config_file,
+        "cniConfigs": read_cni_configs(&cni_config_dirs),
+        "podSandboxes": pod_sandboxes,
+        "containers": containers,
+        "images": images,
+    });
+    redact_secrets(&mut dump);
+    if !include_env {
+        redact_env_values(&mut dump);
+    }
+    Ok(dump)
+}
+
+/// verbose info 的值大多是 JSON 文本，能解析的按 JSON 展开
+fn parse_info(info: HashMap<String, String>) -> Value {
+    let map: Map<String, Value> = info
+        .into_iter()
+        .map(|(key, raw)| {
+            let value = serde_json::from_str(&raw).unwrap_or(Value::String(raw));
+            (key, value)
+        })
+        .collect();
+    Value::Object(map)
+}
+
+/// 读取 CNI 配置目录中的 `.conf`/`.conflist`/`.json` 文件，无法解析的文件记录错误
+pub fn read_cni_configs(dirs: &[PathBuf]) -> Vec<Value> {
+    let mut configs = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension()
+                    .and_then(|ext| ext.to_str())
+                    .map(|ext| matches!(ext, "conf" | "conflist" | "json"))
+                    .unwrap_or(false)
+            })
+            .collect();
+        paths.sort();
+        for path in paths {
+            let parsed = std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|raw| serde_json::from_str::<Value>(&raw).map_err(|e| e.to_string()));
+            configs.push(match parsed {
+                Ok(config) => json!({ "path": path.display().to_string(), "config": config }),
+                Err(e) => json!({ "path": path.display().to_string(), "error": e }),
+            });
+        }
+    }
+    configs
+}
+
+/// 递归把敏感字段的值替换为 [`REDACTED`]
+pub fn redact_secrets(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_secret_key(key) && !value.is_null() && !value.is_object() {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_secrets(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_secrets),
+        _ => {}
+    }
+}
+
+/// 递归屏蔽环境变量的值，保留变量名
+///
+/// 覆盖 OCI spec 的 `env`（`KEY=VALUE` 字符串）、CRI 容器配置的 `envs`（`{key, value}`）
+/// 以及配置文件的 `default_env`。
+pub fn redact_env_values(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                match value {
+                    Value::Array(entries) if is_env_key(key) => {
+                        entries.iter_mut().for_each(redact_env_entry)
+                    }
+                    _ => redact_env_values(value),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_env_values),
+        _ => {}
+    }
+}
+
+fn redact_env_entry(entry: &mut Value) {
+    match entry {
+        Value::String(raw) => {
+            let name = raw.split_once('=').map(|(name, _)| name).unwrap_or(raw);
+            *entry = Value::String(format!("{}={}", name, REDACTED));
+        }
+        Value::Object(map) => {
+            if let Some(value) = map.get_mut("value") {
+                *value = Value::String(REDACTED.to_string());
+            }
+        }
+        _ => {}
+    }
+}
+
+fn normalize_key(key: &str) -> String {
+    key.chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn is_env_key(key: &str) -> bool {
+    let normalized = normalize_key(key);
+    normalized.ends_with("env") || normalized.ends_with("envs")
+}
+
+fn is_secret_key(key: &str) -> bool {
+    let normalized = normalize_key(key);
+    SECRET_KEY_FRAGMENTS
+        .iter()
+        .any(|fragment| normalized.contains(fragment))
+}
+
+/// 以 0600 权限写出转储文件，先写临时文件再 rename
+pub fn write(path: &Path, dump: &Value) -> Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&tmp_path)
+        .with_context(|| format!("Failed to create {}", tmp_path.display()))?;
+    serde_json::to_writer_pretty(&mut file, dump)?;
+    file.write_all(b"\n")?;
+    file.sync_all()?;
+    std::fs::rename(&tmp_path, path)
+        .with_context(|| format!("Failed to rename dump to {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redact_secrets_masks_credentials_at_any_depth() {
+        let mut value = json!({
+            "registry": {
+                "auth": "dXNlcjpwYXNz",
+                "username": "user",
+                "Password": "pass",
+                "identity_token": "id",
+                "registryToken": null,
+            },
+            "plugins": [{ "type": "calico", "etcd_key_file": "/etc/key", "client_secret": 1 }],
+            "annotations": { "app": "demo" },
+            "authorization": { "mode": "none" },
+        });
+
+        redact_secrets(&mut value);
+
+        assert_eq!(value["registry"]["auth"], REDACTED);
+        assert_eq!(value["registry"]["username"], "user");
+        assert_eq!(value["registry"]["Password"], REDACTED);
+        assert_eq!(value["registry"]["identity_token"], REDACTED);
+        assert_eq!(value["registry"]["registryToken"], Value::Null);
+        assert_eq!(value["plugins"][0]["etcd_key_file"], "/etc/key");
+        assert_eq!(value["plugins"][0]["client_secret"], REDACTED);
+        assert_eq!(value["annotations"]["app"], "demo");
+        assert_eq!(value["authorization"]["mode"], "none");
+    }
+
+    #[test]
+    fn redact_env_values_keeps_names_only() {
+        let mut value = json!({
+            "info": {
+                "runtimeSpec": { "process": { "env": ["PATH=/usr/bin", "DB_URL=postgres://u:p@db", "EMPTY"] } },
+                "config": { "envs": [{ "key": "API_KEY", "value": "s3cr3t" }] },
+            },
+            "configFile": { "config": { "runtime": { "default_env": ["HTTP_PROXY=http://proxy"] } } },
+            "labels": { "env": "prod" },
+        });
+
+        redact_env_values(&mut value);
+
+        let redacted = |name: &str| format!("{}={}", name, REDACTED);
+        assert_eq!(
+            value["info"]["runtimeSpec"]["process"]["env"],
+            json!([redacted("PATH"), redacted("DB_URL"), redacted("EMPTY")])
+        );
+        assert_eq!(value["info"]["config"]["envs"][0]["key"], "API_KEY");
+        assert_eq!(value["info"]["config"]["envs"][0]["value"], REDACTED);
+        assert_eq!(
+            value["configFile"]["config"]["runtime"]["default_env"][0],
+            redacted("HTTP_PROXY")
+        );
+        // 非数组的同名字段（如标签）不受影响
+        assert_eq!(value["labels"]["env"], "prod");
+    }
+
+    #[test]
+    fn read_cni_configs_reports_unparsable_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("10-bridge.conflist"),
+            r#"{"cniVersion":"1.0.0","name":"bridge","plugins":[{"type":"bridge"}]}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("20-broken.conf"), "{").unwrap();
+        std::fs::write(dir.path().join("README"), "ignored").unwrap();
+
+        let configs = read_cni_configs(&[dir.path().to_path_buf(), dir.path().join("missing")]);
+
+        assert_eq!(configs.len(), 2);
+        assert_eq!(configs[0]["config"]["name"], "bridge");
+        assert!(configs[1]["path"]
+            .as_str()
+            .unwrap()
+            .ends_with("20-broken.conf"));
+        assert!(configs[1]["error"].is_string());
+    }
+}
@@ -0,0 +1,358 @@
+//! 镜像存储校验与修复
+//!
+//! 逐个检查 `images/<id>` 下的层文件：快速模式只比较元数据记录的大小，完整模式还重新计算
+//! sha256 并与拉取时记录的 digest 比较（早期拉取的镜像没有 digest，只能比较大小）。
+//! 层文件损坏的镜像若已 pin 或仍被容器使用，则按其 digest/标签重新拉取，否则直接删除；
+//! 镜像目录中未被元数据引用的文件视为孤儿删除。
+
+use std::collections::HashSet;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use log::{error, info, warn};
+use oci_distribution::secrets::RegistryAuth;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use super::{CriusImage, ImageMeta, ImageServiceImpl};
+
+/// 校验深度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsckMode {
+    /// 只检查层文件是否存在及大小
+    Quick,
+    /// 额外重新计算每个层文件的 sha256，代价与镜像总大小成正比
+    Full,
+}
+
+impl FromStr for FsckMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "quick" => Ok(Self::Quick),
+            "full" => Ok(Self::Full),
+            other => Err(format!(
+                "unknown image fsck mode {:?}, expected \"quick\" or \"full\"",
+                other
+            )),
+        }
+    }
+}
+
+/// 一次校验的结果
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FsckReport {
+    /// 校验通过的层文件数
+    pub verified: u64,
+    /// 重新拉取修复的镜像 ID
+    pub repaired: Vec<String>,
+    /// 删除的损坏镜像 ID 与孤儿文件路径
+    pub removed: Vec<String>,
+    /// 未能修复的镜像及原因
+    pub failed: Vec<String>,
+}
+
+impl ImageServiceImpl {
+    /// 校验并修复镜像存储，返回汇总结果
+    pub async fn fsck(&self, mode: FsckMode) -> FsckReport {
+        let images_dir = self.storage_path.join("images");
+        let mut report = FsckReport::default();
+        let mut image_ids: Vec<String> = std::fs::read_dir(&images_dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| !name.starts_with('.'))
+            .collect();
+        image_ids.sort();
+        info!(
+            "Checking {} images in {:?} ({:?})",
+            image_ids.len(),
+            images_dir,
+            mode
+        );
+
+        for image_id in image_ids {
+            // 与同一镜像的 pull 串行，避免把正在写入的文件当作孤儿
+            let lock = self.image_dir_lock(&image_id).await;
+            let guard = lock.lock().await;
+            self.fsck_image(&image_id, mode, &mut report).await;
+            drop(guard);
+            self.release_image_dir_lock(&image_id, lock).await;
+        }
+
+        info!(
+            "Image store check finished: {} layers verified, {} images repaired, {} entries removed, {} failures",
+            report.verified,
+            report.repaired.len(),
+            report.removed.len(),
+            report.failed.len()
+        );
+        report
+    }
+
+    async fn fsck_image(&self, image_id: &str, mode: FsckMode, report: &mut FsckReport) {
+        let image_dir = self.storage_path.join("images").join(image_id);
+        let Some(meta) = self.load_image_metadata(image_id) else {
+            warn!("Removing image {} with unreadable metadata", image_id);
+            self.remove_corrupt_image(image_id, report).await;
+            return;
+        };
+
+        let layer_files: HashSet<&str> = meta
+            .layers
+            .iter()
+            .map(|layer| layer.file.as_str())
+            .collect();
+        for entry in std::fs::read_dir(&image_dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+        {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name == "metadata.json" || layer_files.contains(name.as_str()) {
+                continue;
+            }
+            let path = entry.path();
+            info!("Removing orphaned file {:?} from image store", path);
+            let removed = if path.is_dir() {
+                std::fs::remove_dir_all(&path)
+            } else {
+                std::fs::remove_file(&path)
+            };
+            match removed {
+                Ok(()) => report.removed.push(path.display().to_string()),
+                Err(e) => warn!("Failed to remove orphaned file {:?}: {}", path, e),
+            }
+        }
+
+        let mut problems = Vec::new();
+        for layer in &meta.layers {
+            let path = image_dir.join(&layer.file);
+            let digest =
+                (mode == FsckMode::Full && !layer.digest.is_empty()).then(|| layer.digest.clone());
+            let checked = tokio::task::spawn_blocking({
+                let path = path.clone();
+                let size = layer.size;
+                move || verify_layer_file(&path, size, digest.as_deref())
+            })
+            .await
+            .unwrap_or_else(|e| Err(format!("verification task failed: {}", e)));
+            match checked {
+                Ok(()) => report.verified += 1,
+                Err(reason) => problems.push(format!("{}: {}", layer.file, reason)),
+            }
+        }
+        if problems.is_empty() {
+            return;
+        }
+
+        warn!(
+            "Image {} has corrupt layers: {}",
+            image_id,
+            problems.join("; ")
+        );
+        let ids = HashSet::from([image_id.to_string()]);
+        let refs = meta.repo_tags.iter().cloned().collect();
+        let in_use = self.image_is_in_use(image_id, &ids, &refs).is_err();
+        if !meta.pinned && !in_use {
+            self.remove_corrupt_image(image_id, report).await;
+            return;
+        }
+        match self.repull_image(image_id, &meta).await {
+            Ok(()) => {
+                info!("Re-pulled corrupt image {}", image_id);
+                report.repaired.push(image_id.to_string());
+            }
+            Err(e) => {
+                error!("Failed to repair image {}: {}", image_id, e);
+                report
+                    .failed
+                    .push(format!("{}: {}; {}", image_id, problems.join("; "), e));
+            }
+        }
+    }
+
+    async fn remove_corrupt_image(&self, image_id: &str, report: &mut FsckReport) {
+        self.images
+            .write()
+            .await
+            .retain(|_, image| image.id != image_id);
+        let image_dir = self.storage_path.join("images").join(image_id);
+        match tokio::fs::remove_dir_all(&image_dir).await {
+            Ok(()) => report.removed.push(image_id.to_string()),
+            Err(e) => {
+                error!("Failed to remove image directory {:?}: {}", image_dir, e);
+                report.failed.push(format!("{}: {}", image_id, e));
+            }
+        }
+    }
+
+    /// 优先按 digest 重新拉取，保证得到同一镜像；标签可能已指向别的镜像
+    async fn repull_image(&self, image_id: &str, meta: &ImageMeta) -> Result<(), String> {
+        let reference = meta
+            .repo_digests
+            .iter()
+            .chain(meta.repo_tags.iter())
+            .chain(meta.source_reference.iter())
+            .find(|reference| !reference.is_empty())
+            .ok_or_else(|| "image has no reference to pull from".to_string())?;
+        let canonical_ref = Self::canonicalize_image_reference(reference);
+        let parsed = super::reference::parse_image_reference(&canonical_ref)
+            .map_err(|e| e.message().to_string())?;
+        let (pulled_id, size, layers, _) = self
+            .fetch_remote_image(&canonical_ref, &parsed, &RegistryAuth::Anonymous, None)
+            .await
+            .map_err(|e| format!("pull of {} failed: {}", canonical_ref, e.message()))?;
+        if pulled_id != image_id {
+            return Err(format!(
+                "{} now resolves to {}, not {}",
+                canonical_ref, pulled_id, image_id
+            ));
+        }
+        let image = CriusImage {
+            id: image_id.to_string(),
+            repo_tags: meta.repo_tags.clone(),
+            repo_digests: meta.repo_digests.clone(),
+            size,
+            pinned: meta.pinned,
+            pulled_at: meta.pulled_at,
+            source_reference: meta.source_reference.clone(),
+            os: meta.os.clone(),
+            architecture: meta.architecture.clone(),
+            config_user: meta.config_user.clone(),
+            annotations: meta.annotations.clone(),
+            manifest_media_type: meta.manifest_media_type.clone(),
+            runtime_handlers: meta.runtime_handlers.clone(),
+            exposed_ports: meta.exposed_ports.clone(),
+            stop_signal: meta.stop_signal.clone(),
+            env: meta.env.clone(),
+            layers: Vec::new(),
+        };
+        let image_dir = self.storage_path.join("images").join(image_id);
+        self.write_pulled_image(&image_dir, &layers, &image)
+            .map_err(|e| e.message().to_string())
+    }
+}
+
+/// 检查层文件大小，给出 `digest` 时还比较内容的 sha256
+fn verify_layer_file(path: &Path, size: u64, digest: Option<&str>) -> Result<(), String> {
+    let stat = std::fs::metadata(path).map_err(|e| format!("unavailable: {}", e))?;
+    if stat.len() != size {
+        return Err(format!("has {} bytes, expected {}", stat.len(), size));
+    }
+    let Some(expected) = digest else {
+        return Ok(());
+    };
+    let mut file = std::fs::File::open(path).map_err(|e| format!("unreadable: {}", e))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher).map_err(|e| format!("unreadable: {}", e))?;
+    let actual = format!("sha256:{:x}", hasher.finalize());
+    if actual != expected {
+        return Err(format!("digest {} does not match {}", actual, expected));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::ImageLayerFile;
+    use futures::FutureExt;
+    use std::sync::Arc;
+
+    fn write_image(
+        service: &ImageServiceImpl,
+        name: &str,
+        image_id: &str,
+        layer: &[u8],
+        pinned: bool,
+    ) {
+        let image = CriusImage {
+            id: image_id.to_string(),
+            repo_tags: vec![format!("docker.io/library/{}:latest", name)],
+            repo_digests: vec![format!("docker.io/library/{}@{}", name, image_id)],
+            pinned,
+            ..Default::default()
+        };
+        let image_dir = service.storage_path.join("images").join(image_id);
+        service
+            .write_pulled_image(&image_dir, &[layer.to_vec()], &image)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn full_fsck_detects_corrupt_layer_and_removes_orphans() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = ImageServiceImpl::new(dir.path()).unwrap();
+        let image_id = "sha256:corrupt";
+        write_image(&service, "corrupt", image_id, b"layer-data", false);
+        let image_dir = dir.path().join("images").join(image_id);
+        std::fs::write(image_dir.join("stray.tmp"), b"junk").unwrap();
+        assert_eq!(
+            service.load_image_metadata(image_id).unwrap().layers,
+            vec![ImageLayerFile {
+                file: "0.tar.gz".to_string(),
+                size: 10,
+                digest: format!("sha256:{:x}", Sha256::digest(b"layer-data")),
+            }]
+        );
+
+        // 同样长度的位翻转只有完整校验能发现
+        std::fs::write(image_dir.join("0.tar.gz"), b"layer-dat4").unwrap();
+        let quick = service.fsck(FsckMode::Quick).await;
+        assert_eq!(quick.verified, 1);
+        assert_eq!(
+            quick.removed,
+            vec![image_dir.join("stray.tmp").display().to_string()]
+        );
+        assert!(image_dir.exists());
+
+        let full = service.fsck(FsckMode::Full).await;
+        assert_eq!(full.verified, 0);
+        assert_eq!(full.removed, vec![image_id.to_string()]);
+        assert!(full.failed.is_empty());
+        assert!(!image_dir.exists());
+    }
+
+    #[tokio::test]
+    async fn fsck_repulls_corrupt_pinned_image() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut service = ImageServiceImpl::new(dir.path()).unwrap();
+        let image_id = "sha256:1111111111111111111111111111111111111111111111111111111111111111";
+        write_image(&service, "pinned", image_id, b"layer-data", true);
+        let pulled = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = pulled.clone();
+        service.fetch_override = Some(Arc::new(move |reference| {
+            seen.lock().unwrap().push(reference);
+            async move {
+                Ok((
+                    image_id.to_string(),
+                    10,
+                    vec![b"layer-data".to_vec()],
+                    Default::default(),
+                ))
+            }
+            .boxed()
+        }));
+        let layer_path = dir.path().join("images").join(image_id).join("0.tar.gz");
+        std::fs::write(&layer_path, b"truncated").unwrap();
+
+        let report = service.fsck(FsckMode::Quick).await;
+
+        assert_eq!(report.repaired, vec![image_id.to_string()]);
+        assert!(report.failed.is_empty());
+        assert_eq!(
+            *pulled.lock().unwrap(),
+            vec![format!("docker.io/library/pinned@{}", image_id)]
+        );
+        assert_eq!(std::fs::read(&layer_path).unwrap(), b"layer-data");
+        let meta = service.load_image_metadata(image_id).unwrap();
+        assert!(meta.pinned);
+        assert_eq!(meta.repo_tags, vec!["docker.io/library/pinned:latest"]);
+    }
+}
@@ -3,7 +3,6 @@ use std::io;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
 
 use anyhow::Context;
 use base64::Engine;
@@ -14,7 +13,9 @@ use sha2::{Digest, Sha256};
 use tokio::sync::{Mutex, Notify};
 use tonic::{Request, Response, Status};
 
+use crate::audit::{AuditEntry, AuditLogger};
 use crate::error::Error;
+use crate::metrics::{Operation, OperationTimer, Phase};
 use crate::proto::runtime::v1::{
     image_service_server::ImageService, AuthConfig, FilesystemIdentifier, FilesystemUsage, Image,
     ImageFsInfoRequest, ImageFsInfoResponse, ImageSpec, ImageStatusRequest, ImageStatusResponse,
@@ -22,6 +23,7 @@ use crate::proto::runtime::v1::{
     RemoveImageRequest, RemoveImageResponse, UInt64Value,
 };
 use crate::storage::StorageManager;
+use crate::utils::snapshot::{Snapshot, SnapshotCache};
 
 /// crius镜像
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -39,17 +41,176 @@ pub struct CriusImage {
     pub config_user: Option<String>,
     pub annotations: HashMap<String, String>,
     pub manifest_media_type: Option<String>,
+    /// 以非默认 runtime handler 拉取过该镜像的 handler 列表
+    pub runtime_handlers: Vec<String>,
+    /// image config 中声明的 ExposedPorts
+    pub exposed_ports: Vec<String>,
+    /// image config 中声明的 StopSignal
+    pub stop_signal: Option<String>,
+    /// image config 中声明的 Env（`KEY=VALUE`）
+    pub env: Vec<String>,
+    /// 镜像目录中的层文件，启动时据此判断镜像是否完整；旧版本写入的元数据为空
+    pub layers: Vec<ImageLayerFile>,
+}
+
+/// 镜像目录中的一个层文件
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct ImageLayerFile {
+    /// 相对镜像目录的文件名
+    pub file: String,
+    /// 文件字节数
+    pub size: u64,
+    /// 文件内容的 sha256 digest，早期拉取的镜像为空
+    pub digest: String,
+}
+
+/// 拉取暂存目录前缀
+const PULL_STAGING_PREFIX: &str = "pull-";
+/// 跨文件系统复制时在 images 目录中使用的临时目录前缀
+const PARTIAL_IMAGE_PREFIX: &str = ".partial-";
+/// 下载目录中已校验层 blob 的缓存，pull 中断后重试时只下载缺失的层
+const PULL_BLOB_CACHE_DIR: &str = "blobs";
+/// 缓存 blob 写入时使用的临时文件后缀
+const PULL_BLOB_PARTIAL_SUFFIX: &str = ".partial";
+/// 启动时清理超过该时长未被使用的缓存 blob
+const PULL_BLOB_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+/// OCI 镜像 manifest
+pub const OCI_MANIFEST_MEDIA_TYPE: &str = "application/vnd.oci.image.manifest.v1+json";
+/// OCI 镜像 index
+pub const OCI_INDEX_MEDIA_TYPE: &str = "application/vnd.oci.image.index.v1+json";
+/// Docker v2 schema2 manifest
+pub const DOCKER_MANIFEST_MEDIA_TYPE: &str = "application/vnd.docker.distribution.manifest.v2+json";
+/// Docker v2 manifest list
+pub const DOCKER_MANIFEST_LIST_MEDIA_TYPE: &str =
+    "application/vnd.docker.distribution.manifest.list.v2+json";
+/// 默认接受的 manifest 媒体类型，按优先级排列
+pub const DEFAULT_MANIFEST_MEDIA_TYPES: [&str; 4] = [
+    OCI_MANIFEST_MEDIA_TYPE,
+    DOCKER_MANIFEST_MEDIA_TYPE,
+    OCI_INDEX_MEDIA_TYPE,
+    DOCKER_MANIFEST_LIST_MEDIA_TYPE,
+];
+
+/// 是否为多平台 index（manifest list）媒体类型
+pub fn is_index_media_type(media_type: &str) -> bool {
+    media_type == OCI_INDEX_MEDIA_TYPE || media_type == DOCKER_MANIFEST_LIST_MEDIA_TYPE
+}
+
+/// 单个 blob 的最大下载次数，连接中断时只重试该 blob
+const BLOB_DOWNLOAD_ATTEMPTS: u32 = 4;
+/// blob 重试的初始退避时间，每次翻倍
+const BLOB_RETRY_BACKOFF: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// 写入文件，`sync` 为 true 时返回前 fsync
+fn write_file(path: &Path, data: &[u8], sync: bool) -> io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    io::Write::write_all(&mut file, data)?;
+    if sync {
+        file.sync_all()?;
+    }
+    Ok(())
+}
+
+/// fsync 目录，使其中的创建、rename 落盘
+fn sync_dir(path: &Path) -> io::Result<()> {
+    std::fs::File::open(path)?.sync_all()
+}
+
+/// 镜像记录表
+///
+/// 通过 `DerefMut` 的每次修改都会作废 ListImages 使用的快照。
+#[derive(Debug, Default)]
+struct ImageRecords {
+    records: HashMap<String, Image>,
+    snapshot: SnapshotCache<Image>,
+}
+
+impl std::ops::Deref for ImageRecords {
+    type Target = HashMap<String, Image>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.records
+    }
+}
+
+impl std::ops::DerefMut for ImageRecords {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.snapshot.invalidate();
+        &mut self.records
+    }
+}
+
+impl ImageRecords {
+    /// 全部记录的共享快照，记录未变化时重复调用不会复制镜像
+    fn snapshot(&self) -> Snapshot<Image> {
+        self.snapshot.get_or_build(|| self.records.values())
+    }
 }
 
 /// 镜像服务实现
 pub struct ImageServiceImpl {
     // 存储镜像信息的线程安全HashMap
-    images: std::sync::Arc<tokio::sync::Mutex<HashMap<String, Image>>>,
+    // 默认 handler 以镜像引用为 key，其余 handler 以 (引用, handler) 组合为 key
+    // list/status 远多于 pull/remove，使用读写锁让并发查询互不阻塞
+    images: std::sync::Arc<tokio::sync::RwLock<ImageRecords>>,
     storage_path: PathBuf,
-    oci_client: Arc<Mutex<oci_distribution::Client>>,
+    // 容器元数据库路径，未设置时从 storage_path 推导
+    database_path: Option<PathBuf>,
+    // 拉取时的暂存目录，写完后整体移动到 images/<id>
+    download_dir: PathBuf,
+    // 层文件、元数据及其目录是否 fsync
+    fsync: bool,
+    // 镜像存储容量上限（字节），0 表示不限制
+    max_store_bytes: u64,
+    // 串行化容量检查与写入，避免并发 pull 同时通过检查后超出上限
+    store_capacity_lock: Arc<Mutex<()>>,
     in_progress_pulls: Arc<Mutex<HashMap<String, Arc<Notify>>>>,
+    // 按镜像 ID 串行化镜像目录的写入
+    image_dir_locks: Arc<Mutex<HashMap<String, Arc<Mutex<()>>>>>,
+    // 变更类请求的审计日志，未配置时不记录
+    audit: Option<Arc<AuditLogger>>,
+    // 拉取时接受的 manifest 媒体类型（HTTP Accept），按优先级排列
+    manifest_media_types: Vec<String>,
+    // 标签引用是否每次 pull 都向 registry 重新解析
+    refresh_tags: bool,
+    #[cfg(test)]
+    fetch_override: Option<FetchOverride>,
+}
+
+/// 远端拉取结果：(镜像 ID, 大小, 层数据, config 元数据)
+type FetchedImage = (String, u64, Vec<Vec<u8>>, PulledImageMetadata);
+
+/// blob 下载失败原因
+#[derive(Debug)]
+enum BlobError {
+    /// 连接中断、超时、5xx 或内容不完整，可重新连接后重试
+    Retryable(String),
+    Fatal(Status),
+}
+
+impl BlobError {
+    fn from_reqwest(context: &str, err: reqwest::Error) -> Self {
+        if err.is_connect()
+            || err.is_timeout()
+            || err.is_request()
+            || err.is_body()
+            || err.is_decode()
+        {
+            Self::Retryable(format!("{}: {}", context, err))
+        } else {
+            Self::Fatal(Status::internal(format!("{}: {}", context, err)))
+        }
+    }
 }
 
+#[cfg(test)]
+type FetchOverride = Arc<
+    dyn Fn(String) -> futures::future::BoxFuture<'static, Result<FetchedImage, Status>>
+        + Send
+        + Sync,
+>;
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(default)]
 pub struct ImageMeta {
@@ -65,6 +226,11 @@ pub struct ImageMeta {
     pub config_user: Option<String>,
     pub annotations: HashMap<String, String>,
     pub manifest_media_type: Option<String>,
+    pub runtime_handlers: Vec<String>,
+    pub exposed_ports: Vec<String>,
+    pub stop_signal: Option<String>,
+    pub env: Vec<String>,
+    pub layers: Vec<ImageLayerFile>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -74,9 +240,65 @@ struct PulledImageMetadata {
     config_user: Option<String>,
     annotations: HashMap<String, String>,
     manifest_media_type: Option<String>,
+    exposed_ports: Vec<String>,
+    stop_signal: Option<String>,
+    env: Vec<String>,
 }
 
+/// (镜像引用, runtime handler) 组合 key 的分隔符，不会出现在合法镜像引用中
+const RUNTIME_HANDLER_KEY_SEPARATOR: char = '#';
+
 impl ImageServiceImpl {
+    fn image_record_key(reference: &str, runtime_handler: &str) -> String {
+        if runtime_handler.is_empty() {
+            reference.to_string()
+        } else {
+            format!(
+                "{}{}{}",
+                reference, RUNTIME_HANDLER_KEY_SEPARATOR, runtime_handler
+            )
+        }
+    }
+
+    fn is_record_key_for(key: &str, reference: &str) -> bool {
+        key.strip_prefix(reference)
+            .map(|rest| rest.is_empty() || rest.starts_with(RUNTIME_HANDLER_KEY_SEPARATOR))
+            .unwrap_or(false)
+    }
+
+    fn image_runtime_handler(image: &Image) -> &str {
+        image
+            .spec
+            .as_ref()
+            .map(|spec| spec.runtime_handler.as_str())
+            .unwrap_or_default()
+    }
+
+    /// 为镜像的每个 tag 写入默认记录，以及每个已记录 handler 的组合记录；
+    /// 没有 tag 的镜像（标签已被移走但仍被使用）以镜像 ID 为 key 记录
+    fn insert_image_records(
+        images: &mut HashMap<String, Image>,
+        image: &Image,
+        runtime_handlers: &[String],
+    ) {
+        if image.repo_tags.is_empty() {
+            images.insert(image.id.clone(), image.clone());
+        }
+        for tag in &image.repo_tags {
+            images.insert(tag.clone(), image.clone());
+            for handler in runtime_handlers
+                .iter()
+                .filter(|handler| !handler.is_empty())
+            {
+                let mut record = image.clone();
+                if let Some(spec) = record.spec.as_mut() {
+                    spec.runtime_handler = handler.clone();
+                }
+                images.insert(Self::image_record_key(tag, handler), record);
+            }
+        }
+    }
+
     fn has_registry_component(component: &str) -> bool {
         component.contains('.') || component.contains(':') || component == "localhost"
     }
@@ -145,6 +367,44 @@ impl ImageServiceImpl {
             })
     }
 
+    /// `ListImages` 的过滤：镜像 ID（及前缀）、tag、digest（含 digest 前缀与裸 `sha256:`），
+    /// 引用在比较前规范化；只给出仓库名时匹配该仓库的全部 tag 与 digest
+    fn image_matches_filter(image: &Image, filter: &str) -> bool {
+        if Self::image_id_matches(&image.id, filter) {
+            return true;
+        }
+        let canonical_filter = Self::canonicalize_image_reference(filter);
+        let repository_only = !filter.contains('@')
+            && !filter.starts_with("sha256:")
+            && !filter.rsplit('/').next().unwrap_or_default().contains(':');
+        let repository = Self::image_repository(&canonical_filter);
+        image.repo_tags.iter().any(|tag| {
+            let tag = Self::canonicalize_image_reference(tag);
+            tag == canonical_filter
+                || (repository_only && Self::image_repository(&tag) == repository)
+        }) || image.repo_digests.iter().any(|digest| {
+            let digest = Self::canonicalize_image_reference(digest);
+            if filter.starts_with("sha256:") {
+                return digest
+                    .split_once('@')
+                    .is_some_and(|(_, digest)| digest.starts_with(filter));
+            }
+            (canonical_filter.contains('@') && digest.starts_with(&canonical_filter))
+                || (repository_only && Self::image_repository(&digest) == repository)
+        })
+    }
+
+    /// 规范化引用去掉 tag 或 digest 后的仓库名
+    fn image_repository(canonical: &str) -> &str {
+        if let Some((repository, _)) = canonical.split_once('@') {
+            return repository;
+        }
+        match canonical.rsplit_once(':') {
+            Some((repository, tag)) if !tag.contains('/') => repository,
+            _ => canonical,
+        }
+    }
+
     fn normalized_image(mut image: Image) -> Image {
         if image.spec.is_none() {
             if let Some(tag) = image.repo_tags.first().cloned() {
@@ -359,10 +619,7 @@ impl ImageServiceImpl {
     }
 
     fn now_nanos() -> i64 {
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_nanos() as i64
+        crate::utils::now_nanos()
     }
 
     fn repo_digest_for_reference(reference: &Reference, image_id: &str) -> Option<String> {
@@ -378,10 +635,11 @@ impl ImageServiceImpl {
     }
 
     fn build_image_verbose_info(
+        &self,
         image: &Image,
-        storage_path: &Path,
+        runtime_handler: &str,
     ) -> Result<HashMap<String, String>, Status> {
-        let image_dir = storage_path.join("images").join(&image.id);
+        let image_dir = self.storage_path.join("images").join(&image.id);
         let meta: Option<ImageMeta> = std::fs::read(image_dir.join("metadata.json"))
             .ok()
             .and_then(|raw| serde_json::from_slice(&raw).ok());
@@ -419,8 +677,26 @@ impl ImageServiceImpl {
             "manifestMediaType": meta
                 .as_ref()
                 .and_then(|meta| meta.manifest_media_type.clone()),
+            "exposedPorts": meta
+                .as_ref()
+                .map(|meta| meta.exposed_ports.clone())
+                .unwrap_or_default(),
+            "stopSignal": meta.as_ref().and_then(|meta| meta.stop_signal.clone()),
+            "env": meta
+                .as_ref()
+                .map(|meta| meta.env.clone())
+                .unwrap_or_default(),
+            "runtimeHandler": runtime_handler,
+            "runtimeHandlers": meta
+                .as_ref()
+                .map(|meta| meta.runtime_handlers.clone())
+                .unwrap_or_default(),
             "storagePath": image_dir.display().to_string(),
             "layers": layer_files,
+            "imageStore": {
+                "usedBytes": self.image_store_usage(&HashSet::new())?,
+                "maxBytes": self.max_store_bytes,
+            },
         });
 
         let mut info = HashMap::new();
@@ -434,6 +710,13 @@ impl ImageServiceImpl {
     }
 
     fn collect_path_usage(path: &Path) -> io::Result<(u64, u64)> {
+        Self::collect_unique_usage(path, &mut HashSet::new())
+    }
+
+    /// 统计目录占用，硬链接共享的 blob 只计一次
+    fn collect_unique_usage(path: &Path, seen: &mut HashSet<(u64, u64)>) -> io::Result<(u64, u64)> {
+        use std::os::unix::fs::MetadataExt;
+
         if !path.exists() {
             return Ok((0, 0));
         }
@@ -444,9 +727,12 @@ impl ImageServiceImpl {
         for entry in std::fs::read_dir(path)? {
             let entry = entry?;
             let metadata = entry.metadata()?;
+            if !seen.insert((metadata.dev(), metadata.ino())) {
+                continue;
+            }
             inodes_used += 1;
             if metadata.is_dir() {
-                let (child_bytes, child_inodes) = Self::collect_path_usage(&entry.path())?;
+                let (child_bytes, child_inodes) = Self::collect_unique_usage(&entry.path(), seen)?;
                 used_bytes = used_bytes.saturating_add(child_bytes);
                 inodes_used = inodes_used.saturating_add(child_inodes);
             } else {
@@ -457,7 +743,122 @@ impl ImageServiceImpl {
         Ok((used_bytes, inodes_used))
     }
 
+    /// 镜像存储当前占用（字节），跳过 `excluded` 中的镜像目录
+    fn image_store_usage(&self, excluded: &HashSet<String>) -> Result<u64, Status> {
+        let images_dir = self.storage_path.join("images");
+        let mut seen = HashSet::new();
+        let mut used_bytes = 0u64;
+        let entries = match std::fs::read_dir(&images_dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => {
+                return Err(Status::internal(format!(
+                    "Failed to read image store {}: {}",
+                    images_dir.display(),
+                    e
+                )))
+            }
+        };
+        for entry in entries.flatten() {
+            if excluded.contains(entry.file_name().to_string_lossy().as_ref()) {
+                continue;
+            }
+            let (bytes, _) = Self::collect_unique_usage(&entry.path(), &mut seen).or_else(|e| {
+                if e.kind() == io::ErrorKind::NotFound {
+                    Ok((0, 0))
+                } else {
+                    Err(Status::internal(format!(
+                        "Failed to collect image store usage from {}: {}",
+                        entry.path().display(),
+                        e
+                    )))
+                }
+            })?;
+            used_bytes = used_bytes.saturating_add(bytes);
+        }
+        Ok(used_bytes)
+    }
+
+    /// 写入 `required` 字节前确保不超过容量上限
+    ///
+    /// 超出时按拉取时间从旧到新删除未被容器使用、未 pin 的镜像；即使全部删除仍放不下时
+    /// 不删除任何镜像，直接返回 `ResourceExhausted`。调用方须持有 `store_capacity_lock`。
+    async fn ensure_store_capacity(&self, image_id: &str, required: u64) -> Result<(), Status> {
+        if self.max_store_bytes == 0 {
+            return Ok(());
+        }
+        let fits = |usage: u64| usage.saturating_add(required) <= self.max_store_bytes;
+        let usage = self.image_store_usage(&HashSet::new())?;
+        if fits(usage) {
+            return Ok(());
+        }
+
+        let mut candidates = self.prunable_images(image_id);
+        let floor =
+            self.image_store_usage(&candidates.iter().map(|image| image.id.clone()).collect())?;
+        if !fits(floor) {
+            return Err(Status::resource_exhausted(format!(
+                "image store would exceed its {} byte cap: {} bytes in use, {} bytes needed",
+                self.max_store_bytes, usage, required
+            )));
+        }
+
+        info!(
+            "Image store usage {} + {} exceeds cap {}, pruning unused images",
+            usage, required, self.max_store_bytes
+        );
+        candidates.sort_by_key(|image| image.pulled_at);
+        for image in candidates {
+            self.prune_image(&image.id).await;
+            if fits(self.image_store_usage(&HashSet::new())?) {
+                return Ok(());
+            }
+        }
+        Err(Status::resource_exhausted(format!(
+            "image store would exceed its {} byte cap after pruning, {} bytes needed",
+            self.max_store_bytes, required
+        )))
+    }
+
+    /// 可被清理的镜像：未 pin 且没有容器引用
+    fn prunable_images(&self, exclude_id: &str) -> Vec<ImageMeta> {
+        std::fs::read_dir(self.storage_path.join("images"))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                let id = entry.file_name().to_string_lossy().to_string();
+                if id == exclude_id {
+                    return None;
+                }
+                self.load_image_metadata(&id)
+                    .map(|image| ImageMeta { id, ..image })
+            })
+            .filter(|image| !image.pinned)
+            .filter(|image| {
+                let ids = HashSet::from([image.id.clone()]);
+                let refs = image.repo_tags.iter().cloned().collect();
+                self.image_is_in_use(&image.id, &ids, &refs).is_ok()
+            })
+            .collect()
+    }
+
+    async fn prune_image(&self, image_id: &str) {
+        info!("Pruning image {} to free image store space", image_id);
+        self.images
+            .write()
+            .await
+            .retain(|_, image| image.id != image_id);
+        let image_dir = self.storage_path.join("images").join(image_id);
+        if let Err(e) = tokio::fs::remove_dir_all(&image_dir).await {
+            error!("Failed to remove image directory {:?}: {}", image_dir, e);
+        }
+    }
+
     fn database_path(&self) -> Option<PathBuf> {
+        if let Some(path) = self.database_path.as_ref() {
+            return Some(path.clone());
+        }
         if self.storage_path.file_name().and_then(|name| name.to_str()) == Some("storage") {
             self.storage_path
                 .parent()
@@ -516,152 +917,603 @@ impl ImageServiceImpl {
         let storage_path = storage_path.as_ref().to_path_buf();
 
         if !storage_path.exists() {
-            std::fs::create_dir_all(&storage_path).context("Failed to create storage directory")?;
+            crate::utils::create_dir_all_with_mode(&storage_path, crate::utils::DEFAULT_DIR_MODE)
+                .context("Failed to create storage directory")?;
         }
 
-        let client_config = oci_distribution::client::ClientConfig {
-            protocol: oci_distribution::client::ClientProtocol::Https,
-            ..Default::default()
-        };
-        let oci_client = oci_distribution::Client::new(client_config);
-        let images = std::sync::Arc::new(tokio::sync::Mutex::new(HashMap::new()));
+        let images = std::sync::Arc::new(tokio::sync::RwLock::new(ImageRecords::default()));
 
         Ok(Self {
             images,
+            download_dir: storage_path.join("tmp"),
+            fsync: true,
+            max_store_bytes: 0,
+            store_capacity_lock: Arc::new(Mutex::new(())),
             storage_path,
-            oci_client: Arc::new(Mutex::new(oci_client)),
+            database_path: None,
             in_progress_pulls: Arc::new(Mutex::new(HashMap::new())),
+            image_dir_locks: Arc::new(Mutex::new(HashMap::new())),
+            audit: None,
+            manifest_media_types: DEFAULT_MANIFEST_MEDIA_TYPES
+                .iter()
+                .map(|media_type| media_type.to_string())
+                .collect(),
+            refresh_tags: false,
+            #[cfg(test)]
+            fetch_override: None,
         })
     }
 
-    // 加载本地镜像
-    pub async fn load_local_images(&self) -> Result<(), Error> {
-        info!("load_local_images called");
-        let imaages_dir = self.storage_path.join("images");
-        info!("Images directory: {:?}", imaages_dir);
+    /// 指定容器元数据库路径，用于镜像存储与 root 分离的场景
+    pub fn set_database_path(&mut self, path: impl Into<PathBuf>) {
+        self.database_path = Some(path.into());
+    }
 
-        if !imaages_dir.exists() {
-            info!("Images directory does not exist, creating...");
-            std::fs::create_dir_all(&imaages_dir)?;
-            return Ok(());
-        }
+    /// 指定镜像下载暂存目录，与镜像存储位于同一文件系统时可原子 rename
+    pub fn set_download_dir(&mut self, path: impl Into<PathBuf>) {
+        self.download_dir = path.into();
+    }
 
-        let mut images = self.images.lock().await;
-        info!("Reading images from directory");
-        for entry in std::fs::read_dir(imaages_dir).context("Failed to read images directory")? {
-            let entry = entry.context("Failed to read entry")?;
+    /// 关闭后 pull 不再 fsync，掉电后可能留下空文件，仅适用于节点重启即丢弃镜像的场景
+    pub fn set_fsync(&mut self, fsync: bool) {
+        self.fsync = fsync;
+    }
 
-            let meta_path = entry.path().join("metadata.json");
-            if !meta_path.exists() {
-                continue;
-            }
+    /// 设置镜像存储容量上限（字节），0 表示不限制
+    pub fn set_max_store_bytes(&mut self, max_store_bytes: u64) {
+        self.max_store_bytes = max_store_bytes;
+    }
 
-            let meta_data = std::fs::read(meta_path).context("Failed to read metadata")?;
-            let meta: ImageMeta =
-                serde_json::from_slice(&meta_data).context("Failed to parse metadata")?;
+    /// 设置拉取时接受的 manifest 媒体类型，不含 index 类型时 registry 须直接返回单平台 manifest
+    pub fn set_manifest_media_types(&mut self, media_types: Vec<String>) {
+        self.manifest_media_types = media_types;
+    }
 
-            let path = entry.path();
-            if path.is_dir() {
-                let image = Self::image_from_meta(&meta);
-                for tag in &meta.repo_tags {
-                    images.insert(tag.clone(), image.clone());
-                }
-            }
-        }
+    /// 开启后 pull 标签引用时总是向 registry 重新解析，标签指向新镜像时从旧镜像上移走
+    pub fn set_refresh_tags(&mut self, refresh_tags: bool) {
+        self.refresh_tags = refresh_tags;
+    }
 
-        Ok(())
+    /// 开启 PullImage/RemoveImage 的审计日志
+    pub fn set_audit_logger(&mut self, logger: Arc<AuditLogger>) {
+        self.audit = Some(logger);
     }
 
-    async fn find_local_image(&self, image_ref: &str) -> Option<Image> {
-        let canonical_ref = Self::canonicalize_image_reference(image_ref);
-        {
-            let images = self.images.lock().await;
-            if let Some(image) = images.get(image_ref) {
-                return Some(image.clone());
-            }
-            if let Some(image) = images.get(&canonical_ref) {
-                return Some(image.clone());
+    pub(crate) fn audit<R>(&self, entry: AuditEntry, result: &Result<Response<R>, Status>) {
+        if let Some(audit) = &self.audit {
+            audit.record(entry, result);
+        }
+    }
+
+    /// 清理上次崩溃遗留的暂存目录和未完成的跨文件系统复制
+    fn remove_stale_pull_leftovers(&self, images_dir: &Path) {
+        let leftovers = std::fs::read_dir(&self.download_dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(PULL_STAGING_PREFIX)
+            })
+            .chain(
+                std::fs::read_dir(images_dir)
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                    .filter(|entry| {
+                        entry
+                            .file_name()
+                            .to_string_lossy()
+                            .starts_with(PARTIAL_IMAGE_PREFIX)
+                    }),
+            );
+        for entry in leftovers {
+            info!("Removing incomplete image download {:?}", entry.path());
+            if let Err(e) = std::fs::remove_dir_all(entry.path()) {
+                warn!(
+                    "Failed to remove incomplete image download {:?}: {}",
+                    entry.path(),
+                    e
+                );
             }
         }
+        self.prune_blob_cache();
+    }
 
-        let images_dir = self.storage_path.join("images");
-        if !images_dir.exists() {
-            return None;
+    /// 缓存的层 blob 路径；只接受 sha256 digest，避免 manifest 中的 digest 拼出任意路径
+    fn cached_blob_path(&self, digest: &str) -> Option<PathBuf> {
+        let hex = digest.strip_prefix("sha256:")?;
+        (hex.len() == 64 && hex.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))).then(
+            || {
+                self.download_dir
+                    .join(PULL_BLOB_CACHE_DIR)
+                    .join("sha256")
+                    .join(hex)
+            },
+        )
+    }
+
+    /// 读取缓存的层 blob；内容与 digest 不符（如写到一半）时删除缓存并返回 None
+    fn read_cached_blob(&self, digest: &str) -> Option<Vec<u8>> {
+        let path = self.cached_blob_path(digest)?;
+        let data = std::fs::read(&path).ok()?;
+        if format!("sha256:{:x}", Sha256::digest(&data)) == digest {
+            return Some(data);
         }
+        warn!(
+            "Cached blob {} does not match its digest, downloading it again",
+            digest
+        );
+        let _ = std::fs::remove_file(&path);
+        None
+    }
 
-        let entries = match std::fs::read_dir(&images_dir) {
-            Ok(v) => v,
-            Err(e) => {
-                warn!("Failed to read images directory {:?}: {}", images_dir, e);
-                return None;
-            }
+    /// 缓存已校验的层 blob，先写临时文件再 rename；失败只记录警告，不影响本次 pull
+    fn cache_blob(&self, digest: &str, data: &[u8]) {
+        let Some(path) = self.cached_blob_path(digest) else {
+            return;
         };
+        let partial = path.with_extension(format!(
+            "{}{}",
+            uuid::Uuid::new_v4().to_simple(),
+            PULL_BLOB_PARTIAL_SUFFIX
+        ));
+        let result = path
+            .parent()
+            .map_or(Ok(()), |dir| {
+                crate::utils::create_dir_all_with_mode(dir, crate::utils::DEFAULT_DIR_MODE)
+            })
+            .and_then(|()| write_file(&partial, data, self.fsync))
+            .and_then(|()| std::fs::rename(&partial, &path));
+        if let Err(e) = result {
+            warn!("Failed to cache blob {}: {}", digest, e);
+            let _ = std::fs::remove_file(&partial);
+        }
+    }
 
-        for entry in entries.flatten() {
-            let meta_path = entry.path().join("metadata.json");
-            if !meta_path.exists() {
-                continue;
+    /// 镜像落盘后删除其层在缓存中的副本
+    fn remove_cached_blobs(&self, layers: &[ImageLayerFile]) {
+        for layer in layers {
+            if let Some(path) = self.cached_blob_path(&layer.digest) {
+                let _ = std::fs::remove_file(path);
             }
-            let meta_data = match std::fs::read(&meta_path) {
-                Ok(v) => v,
-                Err(e) => {
-                    warn!("Failed to read image metadata {:?}: {}", meta_path, e);
-                    continue;
-                }
-            };
-            let meta: ImageMeta = match serde_json::from_slice(&meta_data) {
-                Ok(v) => v,
-                Err(e) => {
-                    warn!("Failed to parse image metadata {:?}: {}", meta_path, e);
-                    continue;
-                }
-            };
-            let image = Self::image_from_meta(&meta);
-            if Self::image_matches_ref(&image, image_ref) {
-                let mut images = self.images.lock().await;
-                for tag in &meta.repo_tags {
-                    images.insert(tag.clone(), image.clone());
+        }
+    }
+
+    /// 清理写到一半的缓存 blob 以及长时间没有 pull 复用的缓存
+    fn prune_blob_cache(&self) {
+        let cache_dir = self.download_dir.join(PULL_BLOB_CACHE_DIR).join("sha256");
+        for entry in std::fs::read_dir(&cache_dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+        {
+            let partial = entry
+                .file_name()
+                .to_string_lossy()
+                .ends_with(PULL_BLOB_PARTIAL_SUFFIX);
+            let expired = entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .is_some_and(|age| age > PULL_BLOB_CACHE_TTL);
+            if partial || expired {
+                info!("Removing cached blob {:?}", entry.path());
+                if let Err(e) = std::fs::remove_file(entry.path()) {
+                    warn!("Failed to remove cached blob {:?}: {}", entry.path(), e);
                 }
-                return Some(image);
             }
         }
-
-        None
     }
 
-    // 保存镜像元数据
-    async fn save_image_metadata(&self, image: &CriusImage) -> Result<(), Error> {
-        let meta_path = self
-            .storage_path
-            .join("images")
-            .join(&image.id)
-            .join("metadata.json");
-        if !meta_path.exists() {
-            std::fs::create_dir_all(meta_path.parent().unwrap())
-                .context("Failed to create metadata directory")?;
+    /// 将暂存目录移动为最终镜像目录，已存在的同 ID 目录会被替换；
+    /// `sync` 为 true 时 fsync 所在目录，返回后 rename 已落盘
+    fn publish_staged_image(staging: &Path, target: &Path, sync: bool) -> io::Result<()> {
+        if sync {
+            sync_dir(staging)?;
+        }
+        if target.exists() {
+            std::fs::remove_dir_all(target)?;
+        }
+        match std::fs::rename(staging, target) {
+            Err(e) if e.raw_os_error() == Some(nix::errno::Errno::EXDEV as i32) => {
+                warn!(
+                    "Image download dir {:?} is on a different filesystem than {:?}, copying",
+                    staging, target
+                );
+                Self::copy_staged_image_across_devices(staging, target, sync)?;
+            }
+            result => result?,
+        }
+        match target.parent() {
+            Some(parent) if sync => sync_dir(parent),
+            _ => Ok(()),
         }
-        let meta_data = serde_json::to_vec(image).context("Failed to serialize metadata")?;
-        std::fs::write(meta_path, meta_data).context("Failed to write metadata")?;
-        Ok(())
     }
 
-    fn load_image_metadata(&self, image_id: &str) -> Option<ImageMeta> {
-        let meta_path = self
-            .storage_path
-            .join("images")
-            .join(image_id)
-            .join("metadata.json");
-        std::fs::read(&meta_path)
-            .ok()
-            .and_then(|raw| serde_json::from_slice::<ImageMeta>(&raw).ok())
+    /// 先复制到目标文件系统上的隐藏目录，再在同一文件系统内 rename 到位
+    fn copy_staged_image_across_devices(
+        staging: &Path,
+        target: &Path,
+        sync: bool,
+    ) -> io::Result<()> {
+        let parent = target.parent().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "image directory has no parent")
+        })?;
+        let partial = parent.join(format!(
+            "{}{}",
+            PARTIAL_IMAGE_PREFIX,
+            uuid::Uuid::new_v4().to_simple()
+        ));
+        if let Err(e) =
+            Self::copy_dir(staging, &partial, sync).and_then(|()| std::fs::rename(&partial, target))
+        {
+            let _ = std::fs::remove_dir_all(&partial);
+            return Err(e);
+        }
+        std::fs::remove_dir_all(staging)
     }
 
-    async fn persist_image_from_proto(&self, image: &Image) -> Result<(), Error> {
-        let existing = self.load_image_metadata(&image.id).unwrap_or_default();
-        self.save_image_metadata(&CriusImage {
-            id: image.id.clone(),
-            repo_tags: image.repo_tags.clone(),
-            repo_digests: image.repo_digests.clone(),
+    fn copy_dir(from: &Path, to: &Path, sync: bool) -> io::Result<()> {
+        std::fs::create_dir_all(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            let dest = to.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                Self::copy_dir(&entry.path(), &dest, sync)?;
+            } else {
+                std::fs::copy(entry.path(), &dest)?;
+                if sync {
+                    std::fs::File::open(&dest)?.sync_all()?;
+                }
+            }
+        }
+        if sync {
+            sync_dir(to)?;
+        }
+        Ok(())
+    }
+
+    /// 获取镜像 ID 对应的目录锁
+    async fn image_dir_lock(&self, image_id: &str) -> Arc<Mutex<()>> {
+        self.image_dir_locks
+            .lock()
+            .await
+            .entry(image_id.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// 归还目录锁，没有其他 pull 持有时从表中移除
+    async fn release_image_dir_lock(&self, image_id: &str, lock: Arc<Mutex<()>>) {
+        let mut locks = self.image_dir_locks.lock().await;
+        drop(lock);
+        if locks
+            .get(image_id)
+            .is_some_and(|lock| Arc::strong_count(lock) == 1)
+        {
+            locks.remove(image_id);
+        }
+    }
+
+    /// 从 registry 下载镜像层和 config
+    async fn fetch_remote_image(
+        &self,
+        canonical_ref: &str,
+        reference: &Reference,
+        auth: &RegistryAuth,
+        supplied_bearer_token: Option<&str>,
+    ) -> Result<FetchedImage, Status> {
+        #[cfg(test)]
+        if let Some(fetch) = self.fetch_override.as_ref() {
+            return fetch(canonical_ref.to_string()).await;
+        }
+
+        // 按配置的 manifest 媒体类型协商，Docker schema2 与 OCI 镜像及其 index 走同一路径；
+        // 显式 bearer token 也在这里处理
+        info!("Fetching {} from registry", canonical_ref);
+        self.pull_via_registry_api(reference, auth, supplied_bearer_token)
+            .await
+    }
+
+    /// 写入暂存目录，完整后再整体移动到 images/<id>；失败时 TempDir 负责清理，
+    /// 崩溃后不会留下看似完整的半成品镜像
+    fn write_pulled_image(
+        &self,
+        image_dir: &Path,
+        layers: &[Vec<u8>],
+        image: &CriusImage,
+    ) -> Result<(), Status> {
+        let images_dir = image_dir.parent().unwrap_or(&self.storage_path);
+        for dir in [images_dir, self.download_dir.as_path()] {
+            crate::utils::create_dir_all_with_mode(dir, crate::utils::DEFAULT_DIR_MODE).map_err(
+                |e: io::Error| {
+                    Status::internal(format!("Failed to create directory {:?}: {}", dir, e))
+                },
+            )?;
+        }
+        let staging = tempfile::Builder::new()
+            .prefix(PULL_STAGING_PREFIX)
+            .tempdir_in(&self.download_dir)
+            .map_err(|e| {
+                Status::internal(format!("Failed to create image download directory: {}", e))
+            })?;
+        info!("Persisting {} layers to {:?}", layers.len(), staging.path());
+        let mut layer_files = Vec::with_capacity(layers.len());
+        for (i, layer) in layers.iter().enumerate() {
+            let file = format!("{}.tar.gz", i);
+            let layer_path = staging.path().join(&file);
+            write_file(&layer_path, layer, self.fsync).map_err(|e: io::Error| {
+                Status::internal(format!("Failed to write layer: {}", e))
+            })?;
+            info!("Saved layer {} to {:?}", i, layer_path);
+            layer_files.push(ImageLayerFile {
+                file,
+                size: layer.len() as u64,
+                digest: format!("sha256:{:x}", Sha256::digest(layer)),
+            });
+        }
+
+        let image = CriusImage {
+            layers: layer_files,
+            ..image.clone()
+        };
+        Self::write_image_metadata(&staging.path().join("metadata.json"), &image, self.fsync)
+            .map_err(|e| {
+                error!("Failed to save image metadata: {}", e);
+                Status::internal(format!("Failed to save image metadata: {}", e))
+            })?;
+        Self::publish_staged_image(staging.path(), image_dir, self.fsync).map_err(|e| {
+            Status::internal(format!("Failed to move image into {:?}: {}", image_dir, e))
+        })?;
+        self.remove_cached_blobs(&image.layers);
+        Ok(())
+    }
+
+    /// 镜像目录已由并发 pull 写入时，只把新的引用合并进元数据
+    async fn add_image_reference(
+        &self,
+        existing: ImageMeta,
+        pulled: &CriusImage,
+    ) -> Result<(), Status> {
+        let mut repo_tags = existing.repo_tags;
+        let mut repo_digests = existing.repo_digests;
+        let mut runtime_handlers = existing.runtime_handlers;
+        for tag in &pulled.repo_tags {
+            Self::push_unique(&mut repo_tags, tag);
+        }
+        for digest in &pulled.repo_digests {
+            Self::push_unique(&mut repo_digests, digest);
+        }
+        for handler in &pulled.runtime_handlers {
+            Self::push_unique(&mut runtime_handlers, handler);
+        }
+        self.save_image_metadata(&CriusImage {
+            id: existing.id,
+            repo_tags,
+            repo_digests,
+            size: existing.size,
+            pinned: existing.pinned,
+            pulled_at: existing.pulled_at,
+            source_reference: existing.source_reference,
+            os: existing.os,
+            architecture: existing.architecture,
+            config_user: existing.config_user,
+            annotations: existing.annotations,
+            manifest_media_type: existing.manifest_media_type,
+            runtime_handlers,
+            exposed_ports: existing.exposed_ports,
+            stop_signal: existing.stop_signal,
+            env: existing.env,
+            layers: existing.layers,
+        })
+        .await
+        .map_err(|e| {
+            error!("Failed to save image metadata: {}", e);
+            Status::internal(format!("Failed to save image metadata: {}", e))
+        })
+    }
+
+    // 加载本地镜像
+    pub async fn load_local_images(&self) -> Result<(), Error> {
+        info!("load_local_images called");
+        let imaages_dir = self.storage_path.join("images");
+        info!("Images directory: {:?}", imaages_dir);
+
+        if !imaages_dir.exists() {
+            info!("Images directory does not exist, creating...");
+            crate::utils::create_dir_all_with_mode(&imaages_dir, crate::utils::DEFAULT_DIR_MODE)?;
+            return Ok(());
+        }
+        self.remove_stale_pull_leftovers(&imaages_dir);
+
+        let mut images = self.images.write().await;
+        info!("Reading images from directory");
+        for entry in std::fs::read_dir(imaages_dir).context("Failed to read images directory")? {
+            let entry = entry.context("Failed to read entry")?;
+            let path = entry.path();
+            if entry.file_name().to_string_lossy().starts_with('.') || !path.is_dir() {
+                continue;
+            }
+
+            let meta = match Self::read_complete_image(&path) {
+                Ok(meta) => meta,
+                Err(reason) => {
+                    warn!("Removing incomplete image {:?}: {}", path, reason);
+                    if let Err(e) = std::fs::remove_dir_all(&path) {
+                        warn!("Failed to remove incomplete image {:?}: {}", path, e);
+                    }
+                    continue;
+                }
+            };
+            let image = Self::image_from_meta(&meta);
+            Self::insert_image_records(&mut images, &image, &meta.runtime_handlers);
+        }
+
+        Ok(())
+    }
+
+    /// 读取镜像元数据并确认其记录的层文件齐全，返回不完整的原因
+    fn read_complete_image(image_dir: &Path) -> Result<ImageMeta, String> {
+        let meta_path = image_dir.join("metadata.json");
+        let meta_data = match std::fs::read(&meta_path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err("metadata.json is missing".to_string())
+            }
+            Err(e) => return Err(format!("failed to read metadata.json: {}", e)),
+        };
+        let meta: ImageMeta = serde_json::from_slice(&meta_data)
+            .map_err(|e| format!("failed to parse metadata.json: {}", e))?;
+        if meta.id.is_empty() {
+            return Err("metadata.json has no image id".to_string());
+        }
+        for layer in &meta.layers {
+            match std::fs::metadata(image_dir.join(&layer.file)) {
+                Ok(stat) if stat.len() == layer.size => {}
+                Ok(stat) => {
+                    return Err(format!(
+                        "layer {} has {} bytes, expected {}",
+                        layer.file,
+                        stat.len(),
+                        layer.size
+                    ))
+                }
+                Err(e) => return Err(format!("layer {} is unavailable: {}", layer.file, e)),
+            }
+        }
+        Ok(meta)
+    }
+
+    /// 镜像是否已在本地存储中
+    pub async fn has_local_image(&self, image_ref: &str) -> bool {
+        self.find_local_image(image_ref).await.is_some()
+    }
+
+    /// 镜像不在本地时以匿名身份从 registry 拉取
+    pub async fn ensure_local_image(&self, image_ref: &str) -> Result<(), Status> {
+        if self.has_local_image(image_ref).await {
+            return Ok(());
+        }
+        info!("Image {} is not in the local store, pulling it", image_ref);
+        self.pull_image_impl(Request::new(PullImageRequest {
+            image: Some(ImageSpec {
+                image: image_ref.to_string(),
+                ..Default::default()
+            }),
+            auth: None,
+            sandbox_config: None,
+        }))
+        .await
+        .map(|_| ())
+    }
+
+    async fn find_local_image(&self, image_ref: &str) -> Option<Image> {
+        let canonical_ref = Self::canonicalize_image_reference(image_ref);
+        {
+            let images = self.images.read().await;
+            if let Some(image) = images.get(image_ref) {
+                return Some(image.clone());
+            }
+            if let Some(image) = images.get(&canonical_ref) {
+                return Some(image.clone());
+            }
+        }
+
+        let images_dir = self.storage_path.join("images");
+        if !images_dir.exists() {
+            return None;
+        }
+
+        let entries = match std::fs::read_dir(&images_dir) {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("Failed to read images directory {:?}: {}", images_dir, e);
+                return None;
+            }
+        };
+
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let meta_path = entry.path().join("metadata.json");
+            if !meta_path.exists() {
+                continue;
+            }
+            let meta_data = match std::fs::read(&meta_path) {
+                Ok(v) => v,
+                Err(e) => {
+                    warn!("Failed to read image metadata {:?}: {}", meta_path, e);
+                    continue;
+                }
+            };
+            let meta: ImageMeta = match serde_json::from_slice(&meta_data) {
+                Ok(v) => v,
+                Err(e) => {
+                    warn!("Failed to parse image metadata {:?}: {}", meta_path, e);
+                    continue;
+                }
+            };
+            let image = Self::image_from_meta(&meta);
+            if Self::image_matches_ref(&image, image_ref) {
+                let mut images = self.images.write().await;
+                Self::insert_image_records(&mut images, &image, &meta.runtime_handlers);
+                return Some(image);
+            }
+        }
+
+        None
+    }
+
+    // 保存镜像元数据
+    async fn save_image_metadata(&self, image: &CriusImage) -> Result<(), Error> {
+        let meta_path = self
+            .storage_path
+            .join("images")
+            .join(&image.id)
+            .join("metadata.json");
+        if !meta_path.exists() {
+            std::fs::create_dir_all(meta_path.parent().unwrap())
+                .context("Failed to create metadata directory")?;
+        }
+        Self::write_image_metadata(&meta_path, image, self.fsync)
+    }
+
+    /// 先写临时文件再 rename，掉电后只会看到旧的或完整的新元数据
+    fn write_image_metadata(meta_path: &Path, image: &CriusImage, sync: bool) -> Result<(), Error> {
+        let meta_data = serde_json::to_vec(image).context("Failed to serialize metadata")?;
+        let tmp_path = meta_path.with_extension("json.tmp");
+        write_file(&tmp_path, &meta_data, sync).context("Failed to write metadata")?;
+        std::fs::rename(&tmp_path, meta_path).context("Failed to write metadata")?;
+        if sync {
+            if let Some(parent) = meta_path.parent() {
+                sync_dir(parent).context("Failed to sync metadata directory")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn load_image_metadata(&self, image_id: &str) -> Option<ImageMeta> {
+        let meta_path = self
+            .storage_path
+            .join("images")
+            .join(image_id)
+            .join("metadata.json");
+        std::fs::read(&meta_path)
+            .ok()
+            .and_then(|raw| serde_json::from_slice::<ImageMeta>(&raw).ok())
+    }
+
+    async fn persist_image_from_proto(&self, image: &Image) -> Result<(), Error> {
+        let existing = self.load_image_metadata(&image.id).unwrap_or_default();
+        self.save_image_metadata(&CriusImage {
+            id: image.id.clone(),
+            repo_tags: image.repo_tags.clone(),
+            repo_digests: image.repo_digests.clone(),
             size: image.size,
             pinned: image.pinned,
             pulled_at: existing.pulled_at,
@@ -671,10 +1523,57 @@ impl ImageServiceImpl {
             config_user: existing.config_user,
             annotations: existing.annotations,
             manifest_media_type: existing.manifest_media_type,
+            runtime_handlers: existing.runtime_handlers,
+            exposed_ports: existing.exposed_ports,
+            stop_signal: existing.stop_signal,
+            env: existing.env,
+            layers: existing.layers,
         })
         .await
     }
 
+    /// 记录镜像已被指定 runtime handler 使用，并补充 (引用, handler) 记录
+    async fn register_runtime_handler(
+        &self,
+        image: &Image,
+        runtime_handler: &str,
+    ) -> Result<(), Error> {
+        if runtime_handler.is_empty() {
+            return Ok(());
+        }
+        let mut meta = self.load_image_metadata(&image.id).unwrap_or_default();
+        if !meta
+            .runtime_handlers
+            .iter()
+            .any(|handler| handler == runtime_handler)
+        {
+            meta.runtime_handlers.push(runtime_handler.to_string());
+            self.save_image_metadata(&CriusImage {
+                id: image.id.clone(),
+                repo_tags: image.repo_tags.clone(),
+                repo_digests: image.repo_digests.clone(),
+                size: image.size,
+                pinned: image.pinned,
+                pulled_at: meta.pulled_at,
+                source_reference: meta.source_reference,
+                os: meta.os,
+                architecture: meta.architecture,
+                config_user: meta.config_user,
+                annotations: meta.annotations,
+                manifest_media_type: meta.manifest_media_type,
+                runtime_handlers: meta.runtime_handlers,
+                exposed_ports: meta.exposed_ports,
+                stop_signal: meta.stop_signal,
+                env: meta.env,
+                layers: meta.layers,
+            })
+            .await?;
+        }
+        let mut images = self.images.write().await;
+        Self::insert_image_records(&mut images, image, &[runtime_handler.to_string()]);
+        Ok(())
+    }
+
     fn parse_bearer_challenge(header: &str) -> Option<(String, Option<String>)> {
         let raw = header.trim();
         if !raw.to_ascii_lowercase().starts_with("bearer ") {
@@ -696,21 +1595,41 @@ impl ImageServiceImpl {
         realm.map(|r| (r, service))
     }
 
+    /// 回环地址上的 registry 使用 HTTP（与 Docker 默认的 127.0.0.0/8 insecure registry 一致），其余使用 HTTPS
+    fn registry_scheme(registry: &str) -> &'static str {
+        let host = match registry.strip_prefix('[') {
+            Some(rest) => rest.split(']').next().unwrap_or_default(),
+            None => registry.split(':').next().unwrap_or_default(),
+        };
+        let loopback = host == "localhost"
+            || host
+                .parse::<std::net::IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback());
+        if loopback {
+            "http"
+        } else {
+            "https"
+        }
+    }
+
+    /// `<scheme>://<registry>/v2/<repository>/<kind>/<name>`
+    fn registry_url(reference: &Reference, kind: &str, name: &str) -> String {
+        let registry = reference.resolve_registry();
+        format!(
+            "{}://{}/v2/{}/{}/{}",
+            Self::registry_scheme(registry),
+            registry,
+            reference.repository(),
+            kind,
+            name
+        )
+    }
+
     fn manifest_url(reference: &Reference) -> String {
         if let Some(digest) = reference.digest() {
-            format!(
-                "https://{}/v2/{}/manifests/{}",
-                reference.resolve_registry(),
-                reference.repository(),
-                digest
-            )
+            Self::registry_url(reference, "manifests", digest)
         } else {
-            format!(
-                "https://{}/v2/{}/manifests/{}",
-                reference.resolve_registry(),
-                reference.repository(),
-                reference.tag().unwrap_or("latest")
-            )
+            Self::registry_url(reference, "manifests", reference.tag().unwrap_or("latest"))
         }
     }
 
@@ -724,6 +1643,82 @@ impl ImageServiceImpl {
         }
     }
 
+    /// 下载单个 blob，连接中断、5xx 或内容与 digest 不符时重新连接并重试该 blob
+    ///
+    /// 已下载完成的其他层不受影响；连接池会丢弃出错的连接，重试时建立新连接。
+    async fn download_blob(
+        http: &reqwest::Client,
+        url: &str,
+        digest: &str,
+        auth: &RegistryAuth,
+        token: Option<&str>,
+    ) -> Result<Vec<u8>, Status> {
+        let mut attempt = 1;
+        loop {
+            let error = match Self::try_download_blob(http, url, digest, auth, token).await {
+                Ok(bytes) => return Ok(bytes),
+                Err(BlobError::Fatal(status)) => return Err(status),
+                Err(BlobError::Retryable(message)) => message,
+            };
+            if attempt >= BLOB_DOWNLOAD_ATTEMPTS {
+                return Err(Status::unavailable(format!(
+                    "blob {} download failed after {} attempts: {}",
+                    digest, attempt, error
+                )));
+            }
+            let backoff = BLOB_RETRY_BACKOFF * 2u32.pow(attempt - 1);
+            warn!(
+                "Download of blob {} failed (attempt {}/{}), retrying in {:?}: {}",
+                digest, attempt, BLOB_DOWNLOAD_ATTEMPTS, backoff, error
+            );
+            tokio::time::sleep(backoff).await;
+            attempt += 1;
+        }
+    }
+
+    async fn try_download_blob(
+        http: &reqwest::Client,
+        url: &str,
+        digest: &str,
+        auth: &RegistryAuth,
+        token: Option<&str>,
+    ) -> Result<Vec<u8>, BlobError> {
+        let mut request = Self::apply_basic_auth(http.get(url), auth);
+        if let Some(t) = token {
+            request = request.bearer_auth(t);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| BlobError::from_reqwest("blob request failed", e))?;
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            let message = format!("blob request failed: {} {}", status, text);
+            return Err(if status.is_server_error() {
+                BlobError::Retryable(message)
+            } else {
+                BlobError::Fatal(Status::internal(message))
+            });
+        }
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| BlobError::from_reqwest("read blob failed", e))?
+            .to_vec();
+        if let Some(expected) = digest.strip_prefix("sha256:") {
+            let actual = format!("{:x}", Sha256::digest(&bytes));
+            if actual != expected {
+                return Err(BlobError::Retryable(format!(
+                    "blob digest mismatch: got sha256:{} after {} bytes",
+                    actual,
+                    bytes.len()
+                )));
+            }
+        }
+        Ok(bytes)
+    }
+
     async fn request_bearer_token(
         http: &reqwest::Client,
         challenge: &str,
@@ -767,10 +1762,14 @@ impl ImageServiceImpl {
         reference: &Reference,
         auth: &RegistryAuth,
         initial_bearer_token: Option<&str>,
-    ) -> Result<(String, u64, Vec<Vec<u8>>, PulledImageMetadata), Status> {
+    ) -> Result<FetchedImage, Status> {
         info!("Using registry API pull flow for {}", reference);
         let http = reqwest::Client::new();
-        let ping_url = format!("https://{}/v2/", reference.resolve_registry());
+        let ping_url = format!(
+            "{}://{}/v2/",
+            Self::registry_scheme(reference.resolve_registry()),
+            reference.resolve_registry()
+        );
         info!("Registry ping: {}", ping_url);
         let ping = Self::apply_basic_auth(http.get(&ping_url), auth)
             .send()
@@ -787,12 +1786,18 @@ impl ImageServiceImpl {
             token = Self::request_bearer_token(&http, challenge, reference, auth).await?;
         }
 
+        let accept = self.manifest_media_types.join(",");
+        let child_accept = self
+            .manifest_media_types
+            .iter()
+            .filter(|media_type| !is_index_media_type(media_type))
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(",");
         let manifest_url = Self::manifest_url(reference);
         info!("Fetching manifest: {}", manifest_url);
-        let mut manifest_req = Self::apply_basic_auth(http.get(&manifest_url), auth).header(
-            reqwest::header::ACCEPT,
-            "application/vnd.oci.image.manifest.v1+json,application/vnd.docker.distribution.manifest.v2+json",
-        );
+        let mut manifest_req = Self::apply_basic_auth(http.get(&manifest_url), auth)
+            .header(reqwest::header::ACCEPT, &accept);
         if let Some(t) = token.as_deref() {
             manifest_req = manifest_req.bearer_auth(t);
         }
@@ -807,10 +1812,8 @@ impl ImageServiceImpl {
                 .and_then(|h| h.to_str().ok())
                 .ok_or_else(|| Status::internal("missing WWW-Authenticate header"))?;
             token = Self::request_bearer_token(&http, challenge, reference, auth).await?;
-            let mut retry_manifest_req = Self::apply_basic_auth(http.get(&manifest_url), auth).header(
-                reqwest::header::ACCEPT,
-                "application/vnd.oci.image.manifest.v1+json,application/vnd.docker.distribution.manifest.v2+json",
-            );
+            let mut retry_manifest_req = Self::apply_basic_auth(http.get(&manifest_url), auth)
+                .header(reqwest::header::ACCEPT, &accept);
             if let Some(t) = token.as_deref() {
                 retry_manifest_req = retry_manifest_req.bearer_auth(t);
             }
@@ -892,16 +1895,9 @@ impl ImageServiceImpl {
                 selected_digest, target_arch
             );
 
-            let child_url = format!(
-                "https://{}/v2/{}/manifests/{}",
-                reference.resolve_registry(),
-                reference.repository(),
-                selected_digest
-            );
-            let mut child_req = Self::apply_basic_auth(http.get(child_url), auth).header(
-                reqwest::header::ACCEPT,
-                "application/vnd.oci.image.manifest.v1+json,application/vnd.docker.distribution.manifest.v2+json",
-            );
+            let child_url = Self::registry_url(reference, "manifests", selected_digest);
+            let mut child_req = Self::apply_basic_auth(http.get(child_url), auth)
+                .header(reqwest::header::ACCEPT, &child_accept);
             if let Some(t) = token.as_deref() {
                 child_req = child_req.bearer_auth(t);
             }
@@ -940,12 +1936,7 @@ impl ImageServiceImpl {
             .and_then(|config| config.get("digest"))
             .and_then(|value| value.as_str())
         {
-            let config_url = format!(
-                "https://{}/v2/{}/blobs/{}",
-                reference.resolve_registry(),
-                reference.repository(),
-                config_digest
-            );
+            let config_url = Self::registry_url(reference, "blobs", config_digest);
             let mut config_req = Self::apply_basic_auth(http.get(config_url), auth);
             if let Some(t) = token.as_deref() {
                 config_req = config_req.bearer_auth(t);
@@ -987,9 +1978,30 @@ impl ImageServiceImpl {
                     serde_json::from_value::<HashMap<String, String>>(value.clone()).ok()
                 })
                 .unwrap_or_default();
-        }
-
-        let layers = manifest_json
+            metadata.exposed_ports = config_json
+                .get("config")
+                .and_then(|config| config.get("ExposedPorts"))
+                .and_then(|value| value.as_object())
+                .map(|ports| {
+                    let mut ports: Vec<String> = ports.keys().cloned().collect();
+                    ports.sort();
+                    ports
+                })
+                .unwrap_or_default();
+            metadata.stop_signal = config_json
+                .get("config")
+                .and_then(|config| config.get("StopSignal"))
+                .and_then(|value| value.as_str())
+                .filter(|value| !value.is_empty())
+                .map(|value| value.to_string());
+            metadata.env = config_json
+                .get("config")
+                .and_then(|config| config.get("Env"))
+                .and_then(|value| serde_json::from_value::<Vec<String>>(value.clone()).ok())
+                .unwrap_or_default();
+        }
+
+        let layers = manifest_json
             .get("layers")
             .and_then(|v| v.as_array())
             .ok_or_else(|| Status::internal("manifest missing layers"))?;
@@ -1002,39 +2014,32 @@ impl ImageServiceImpl {
                 .get("digest")
                 .and_then(|v| v.as_str())
                 .ok_or_else(|| Status::internal("layer missing digest"))?;
-            info!(
-                "Downloading layer {}/{}: {}",
-                idx + 1,
-                layers.len(),
-                layer_digest
-            );
-            let blob_url = format!(
-                "https://{}/v2/{}/blobs/{}",
-                reference.resolve_registry(),
-                reference.repository(),
-                layer_digest
-            );
-            let mut blob_req = Self::apply_basic_auth(http.get(blob_url), auth);
-            if let Some(t) = token.as_deref() {
-                blob_req = blob_req.bearer_auth(t);
-            }
-            let blob_resp = blob_req
-                .send()
-                .await
-                .map_err(|e| Status::internal(format!("blob request failed: {}", e)))?;
-            if !blob_resp.status().is_success() {
-                let status = blob_resp.status();
-                let text = blob_resp.text().await.unwrap_or_default();
-                return Err(Status::internal(format!(
-                    "blob request failed: {} {}",
-                    status, text
-                )));
-            }
-            let bytes = blob_resp
-                .bytes()
-                .await
-                .map_err(|e| Status::internal(format!("read blob failed: {}", e)))?
-                .to_vec();
+            // 上次中断的 pull 已下载并校验过的层直接复用
+            let bytes = match self.read_cached_blob(layer_digest) {
+                Some(bytes) => {
+                    info!(
+                        "Layer {}/{} {} found in blob cache, skipping download",
+                        idx + 1,
+                        layers.len(),
+                        layer_digest
+                    );
+                    bytes
+                }
+                None => {
+                    info!(
+                        "Downloading layer {}/{}: {}",
+                        idx + 1,
+                        layers.len(),
+                        layer_digest
+                    );
+                    let blob_url = Self::registry_url(reference, "blobs", layer_digest);
+                    let bytes =
+                        Self::download_blob(&http, &blob_url, layer_digest, auth, token.as_deref())
+                            .await?;
+                    self.cache_blob(layer_digest, &bytes);
+                    bytes
+                }
+            };
             total_size += bytes.len() as u64;
             layer_data.push(bytes);
             info!(
@@ -1046,120 +2051,16 @@ impl ImageServiceImpl {
             );
         }
 
-        let image_id = if let Some(d) = effective_digest {
-            d
-        } else {
-            format!("sha256:{:x}", Sha256::digest(&manifest_bytes))
-        };
+        let image_id = Self::canonical_image_id(
+            effective_digest.as_deref().unwrap_or_default(),
+            &manifest_bytes,
+        );
 
         Ok((image_id, total_size, layer_data, metadata))
     }
-}
-
-#[tonic::async_trait]
-impl ImageService for ImageServiceImpl {
-    // 列出镜像
-    async fn list_images(
-        &self,
-        request: Request<ListImagesRequest>,
-    ) -> Result<Response<ListImagesResponse>, Status> {
-        let req = request.into_inner();
-        let requested_ref = req
-            .filter
-            .and_then(|filter| filter.image)
-            .map(|image| image.image)
-            .filter(|image| !image.is_empty());
-        let images: Vec<Image> = {
-            let images = self.images.lock().await;
-            info!("Number of images in memory: {}", images.len());
-            for (key, image) in images.iter() {
-                info!("Image: {} -> {}", key, image.id);
-            }
-            images.values().cloned().collect()
-        };
-        let mut grouped: HashMap<String, Vec<Image>> = HashMap::new();
-        for image in images {
-            grouped.entry(image.id.clone()).or_default().push(image);
-        }
-
-        let mut images_list = Vec::new();
-        for (image_id, group) in grouped {
-            let meta = self.load_image_metadata(&image_id);
-            let Some(image) = Self::aggregate_image_records(group.iter(), meta.as_ref()) else {
-                continue;
-            };
-            let matched = requested_ref
-                .as_ref()
-                .map(|requested_ref| Self::image_matches_ref(&image, requested_ref))
-                .unwrap_or(true);
-            if matched {
-                images_list.push(image);
-            }
-        }
-        images_list.sort_by(|left, right| left.id.cmp(&right.id));
-
-        Ok(Response::new(ListImagesResponse {
-            images: images_list,
-        }))
-    }
-
-    // 获取镜像状态
-    async fn image_status(
-        &self,
-        request: Request<ImageStatusRequest>,
-    ) -> Result<Response<ImageStatusResponse>, Status> {
-        let req = request.into_inner();
-        let image_spec = req
-            .image
-            .ok_or_else(|| Status::invalid_argument("Image not specified"))?;
-        let requested_ref = image_spec.image;
-        let images: Vec<Image> = {
-            let images = self.images.lock().await;
-            images.values().cloned().collect()
-        };
-
-        if let Some(matched_image) = images
-            .iter()
-            .find(|image| Self::image_matches_ref(image, &requested_ref))
-        {
-            let meta = self.load_image_metadata(&matched_image.id);
-            if let Some(mut image) = Self::aggregate_image_records(
-                images
-                    .iter()
-                    .filter(|candidate| candidate.id == matched_image.id),
-                meta.as_ref(),
-            ) {
-                let annotations = image
-                    .spec
-                    .as_ref()
-                    .map(|spec| spec.annotations.clone())
-                    .unwrap_or_default();
-                image.spec = Some(ImageSpec {
-                    image: requested_ref.clone(),
-                    user_specified_image: requested_ref.clone(),
-                    annotations,
-                    ..Default::default()
-                });
-
-                return Ok(Response::new(ImageStatusResponse {
-                    image: Some(image.clone()),
-                    info: if req.verbose {
-                        Self::build_image_verbose_info(&image, &self.storage_path)?
-                    } else {
-                        HashMap::new()
-                    },
-                }));
-            }
-        }
-
-        Ok(Response::new(ImageStatusResponse {
-            image: None,
-            info: HashMap::new(),
-        }))
-    }
 
     // 拉取镜像
-    async fn pull_image(
+    async fn pull_image_impl(
         &self,
         request: Request<PullImageRequest>,
     ) -> Result<Response<PullImageResponse>, Status> {
@@ -1168,6 +2069,7 @@ impl ImageService for ImageServiceImpl {
             .image
             .ok_or_else(|| Status::invalid_argument("Image spec not specified"))?;
         let requested_ref = image_spec.image.clone();
+        let runtime_handler = image_spec.runtime_handler.trim().to_string();
         let canonical_ref = Self::canonicalize_image_reference(&requested_ref);
         let supplied_bearer_token = req
             .auth
@@ -1179,41 +2081,71 @@ impl ImageService for ImageServiceImpl {
             None => RegistryAuth::Anonymous,
         };
 
-        // 解析镜像引用
-        let reference: Reference = canonical_ref
-            .parse()
-            .map_err(|e| Status::invalid_argument(format!("Invalid image reference: {}", e)))?;
+        // 解析镜像引用，规范化后的引用会作为镜像存储 key，先做严格校验
+        let reference = reference::parse_image_reference(&canonical_ref)?;
+        // `name:tag@digest` 按 digest 拉取与查找，tag 只作为展示用的 repo tag
+        let (canonical_ref, display_tag) = match reference::split_tagged_digest(&canonical_ref) {
+            Some((digest_ref, tag_ref)) => (digest_ref, Some(tag_ref)),
+            None => (canonical_ref, None),
+        };
+        let repo_tag = display_tag.clone().unwrap_or_else(|| canonical_ref.clone());
+        // 同一引用的并发 pull（不论 runtime handler）只下载一次，后到者等待并复用结果
         let pull_key = canonical_ref.clone();
+        // 按 digest 引用的内容不会变化，总是可以复用本地镜像
+        let refresh_tag = self.refresh_tags && reference.digest().is_none();
 
         loop {
-            let wait_for_existing = {
+            let notify = {
                 let mut in_progress = self.in_progress_pulls.lock().await;
-                if let Some(notify) = in_progress.get(&pull_key) {
-                    Some(notify.clone())
-                } else {
-                    in_progress.insert(pull_key.clone(), Arc::new(Notify::new()));
-                    None
+                match in_progress.get(&pull_key) {
+                    Some(notify) => notify.clone(),
+                    None => {
+                        in_progress.insert(pull_key.clone(), Arc::new(Notify::new()));
+                        break;
+                    }
                 }
             };
 
-            if let Some(notify) = wait_for_existing {
-                notify.notified().await;
-                if let Some(existing_image) = self.find_local_image(&pull_key).await {
-                    return Ok(Response::new(PullImageResponse {
-                        image_ref: existing_image.id,
-                    }));
-                }
-                continue;
+            // 先注册等待再复查，避免错过先行 pull 在此之前发出的 notify_waiters
+            let notified = notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            let still_pulling = self
+                .in_progress_pulls
+                .lock()
+                .await
+                .get(&pull_key)
+                .is_some_and(|current| Arc::ptr_eq(current, &notify));
+            if still_pulling {
+                notified.await;
+            }
+            if let Some(existing_image) = self.find_local_image(&canonical_ref).await {
+                self.register_runtime_handler(&existing_image, &runtime_handler)
+                    .await
+                    .map_err(|e| {
+                        Status::internal(format!("Failed to save image metadata: {}", e))
+                    })?;
+                return Ok(Response::new(PullImageResponse {
+                    image_ref: existing_image.id,
+                }));
             }
-
-            break;
         }
         info!("Pulling image: {}", canonical_ref);
         info!("Checking whether image exists locally: {}", canonical_ref);
-        if let Some(existing_image) = self.find_local_image(&canonical_ref).await {
+        let local_image = if refresh_tag {
+            None
+        } else {
+            self.find_local_image(&canonical_ref).await
+        };
+        if let Some(existing_image) = local_image {
+            let registered = self
+                .register_runtime_handler(&existing_image, &runtime_handler)
+                .await;
             if let Some(notify) = self.in_progress_pulls.lock().await.remove(&pull_key) {
                 notify.notify_waiters();
             }
+            registered
+                .map_err(|e| Status::internal(format!("Failed to save image metadata: {}", e)))?;
             info!(
                 "Image already exists locally: {} -> {}",
                 canonical_ref, existing_image.id
@@ -1228,92 +2160,29 @@ impl ImageService for ImageServiceImpl {
         );
 
         let pull_outcome = async {
-            // 显式 bearer token 需要走自定义 registry API 路径，
-            // oci-distribution 目前只支持 basic/anonymous。
-            let (image_id, image_size, layers_to_persist, pulled_metadata) =
-                if supplied_bearer_token.is_some() {
-                    self.pull_via_registry_api(
-                        &reference,
-                        &auth,
-                        supplied_bearer_token.as_deref(),
-                    )
-                    .await?
-                } else {
-                    // 拉取镜像（优先 OCI 库，失败时走标准 Registry API）
-                    let client = self.oci_client.lock().await;
-                    let pull_result = client
-                        .pull(
-                            &reference,
-                            &auth,
-                            vec![
-                                "application/vnd.oci.image.manifest.v1+json",
-                                "application/vnd.docker.distribution.manifest.v2+json",
-                            ],
-                        )
-                        .await;
-                    drop(client);
-
-                    match pull_result {
-                        Ok(image_data) => {
-                            let digest = image_data.digest.unwrap_or_default();
-                            let id = Self::canonical_image_id(&digest, canonical_ref.as_bytes());
-                            info!(
-                                "OCI library pull succeeded for {}, layers={}",
-                                canonical_ref,
-                                image_data.layers.len()
-                            );
-                            let layers = image_data
-                                .layers
-                                .into_iter()
-                                .map(|l| l.data)
-                                .collect::<Vec<Vec<u8>>>();
-                            (id, 0, layers, PulledImageMetadata::default())
-                        }
-                        Err(e) => {
-                            let err_text = e.to_string();
-                            if err_text.contains(
-                                "application/vnd.docker.distribution.manifest.list.v2+json",
-                            ) || err_text.contains("application/vnd.oci.image.index.v1+json")
-                            {
-                                info!(
-                                    "OCI pull returned manifest index for {}, using registry api fallback: {}",
-                                    reference, err_text
-                                );
-                            } else {
-                                warn!(
-                                    "OCI pull failed for {}, fallback to registry api: {}",
-                                    reference, err_text
-                                );
-                            }
-                            self.pull_via_registry_api(&reference, &auth, None).await?
-                        }
-                    }
-                };
+            let (image_id, image_size, layers_to_persist, pulled_metadata) = self
+                .fetch_remote_image(
+                    &canonical_ref,
+                    &reference,
+                    &auth,
+                    supplied_bearer_token.as_deref(),
+                )
+                .await?;
 
-            let repo_digests = Self::repo_digest_for_reference(&reference, &image_id)
+            let mut repo_digests = Self::repo_digest_for_reference(&reference, &image_id)
                 .into_iter()
                 .collect::<Vec<_>>();
-
-            let image_dir = self.storage_path.join("images").join(&image_id);
-            std::fs::create_dir_all(&image_dir).map_err(|e: io::Error| {
-                Status::internal(format!("Failed to create image directory: {}", e))
-            })?;
-            info!(
-                "Persisting {} layers to {:?}",
-                layers_to_persist.len(),
-                image_dir
-            );
-            for (i, layer) in layers_to_persist.iter().enumerate() {
-                let layer_path = image_dir.join(format!("{}.tar.gz", i));
-                std::fs::write(&layer_path, layer).map_err(|e: io::Error| {
-                    Status::internal(format!("Failed to write layer: {}", e))
-                })?;
-                info!("Saved layer {} to {:?}", i, layer_path);
+            if display_tag.is_some() && !repo_digests.contains(&canonical_ref) {
+                repo_digests.push(canonical_ref.clone());
             }
 
-            self.save_image_metadata(&CriusImage {
+            // 不同引用可能解析到同一镜像 ID，按 ID 串行写入镜像目录
+            let image_dir_lock = self.image_dir_lock(&image_id).await;
+            let image_dir_guard = image_dir_lock.lock().await;
+            let image_dir = self.storage_path.join("images").join(&image_id);
+            let pulled = CriusImage {
                 id: image_id.clone(),
-                repo_tags: vec![canonical_ref.clone()],
+                repo_tags: vec![repo_tag.clone()],
                 repo_digests: repo_digests.clone(),
                 size: image_size,
                 pinned: false,
@@ -1324,21 +2193,45 @@ impl ImageService for ImageServiceImpl {
                 config_user: pulled_metadata.config_user.clone(),
                 annotations: pulled_metadata.annotations.clone(),
                 manifest_media_type: pulled_metadata.manifest_media_type.clone(),
-            })
-            .await
-            .map_err(|e| {
-                error!("Failed to save image metadata: {}", e);
-                Status::internal(format!("Failed to save image metadata: {}", e))
-            })?;
+                runtime_handlers: (!runtime_handler.is_empty())
+                    .then(|| runtime_handler.clone())
+                    .into_iter()
+                    .collect(),
+                exposed_ports: pulled_metadata.exposed_ports.clone(),
+                stop_signal: pulled_metadata.stop_signal.clone(),
+                env: pulled_metadata.env.clone(),
+                layers: Vec::new(),
+            };
+            let stored = if let Some(existing) = self.load_image_metadata(&image_id) {
+                info!(
+                    "Image {} already written by a concurrent pull, adding reference {}",
+                    image_id, canonical_ref
+                );
+                self.add_image_reference(existing, &pulled).await
+            } else {
+                let required = layers_to_persist
+                    .iter()
+                    .map(|layer| layer.len() as u64)
+                    .sum();
+                let _capacity_guard = self.store_capacity_lock.lock().await;
+                match self.ensure_store_capacity(&image_id, required).await {
+                    Ok(()) => self.write_pulled_image(&image_dir, &layers_to_persist, &pulled),
+                    Err(status) => Err(status),
+                }
+            };
+            drop(image_dir_guard);
+            self.release_image_dir_lock(&image_id, image_dir_lock).await;
+            stored?;
+            self.reassign_tag(&repo_tag, &image_id).await?;
 
             let image = Image {
                 id: image_id.clone(),
-                repo_tags: vec![canonical_ref.clone()],
+                repo_tags: vec![repo_tag.clone()],
                 repo_digests,
                 size: image_size,
                 pinned: false,
                 spec: Some(ImageSpec {
-                    image: canonical_ref.clone(),
+                    image: repo_tag.clone(),
                     user_specified_image: requested_ref.clone(),
                     annotations: pulled_metadata.annotations.clone(),
                     ..Default::default()
@@ -1346,8 +2239,8 @@ impl ImageService for ImageServiceImpl {
                 ..Default::default()
             };
 
-            let mut images = self.images.lock().await;
-            images.insert(canonical_ref.clone(), image);
+            let mut images = self.images.write().await;
+            Self::insert_image_records(&mut images, &image, std::slice::from_ref(&runtime_handler));
             drop(images);
 
             info!("Image {} pulled successfully", image_id);
@@ -1365,8 +2258,108 @@ impl ImageService for ImageServiceImpl {
         pull_outcome
     }
 
+    /// 一个标签只指向一个镜像：把 `tag` 从其他镜像的 repo_tags 中移除，
+    /// 因此不再有标签、未 pin 且没有容器使用的旧镜像一并删除
+    async fn reassign_tag(&self, tag: &str, image_id: &str) -> Result<(), Status> {
+        let previous_ids: HashSet<String> = {
+            let mut images = self.images.write().await;
+            let previous_ids = images
+                .values()
+                .filter(|image| image.id != image_id && image.repo_tags.iter().any(|t| t == tag))
+                .map(|image| image.id.clone())
+                .collect::<HashSet<_>>();
+            images.retain(|key, image| {
+                !(previous_ids.contains(&image.id) && Self::is_record_key_for(key, tag))
+            });
+            for image in images.values_mut() {
+                if previous_ids.contains(&image.id) {
+                    image.repo_tags.retain(|t| t != tag);
+                    if image.spec.as_ref().is_some_and(|spec| spec.image == tag) {
+                        image.spec = image.repo_tags.first().cloned().map(|tag| ImageSpec {
+                            image: tag.clone(),
+                            user_specified_image: tag,
+                            ..Default::default()
+                        });
+                    }
+                }
+            }
+            previous_ids
+        };
+
+        for previous_id in previous_ids {
+            let mut meta = self.load_image_metadata(&previous_id).unwrap_or_default();
+            meta.repo_tags.retain(|t| t != tag);
+            info!(
+                "Moved tag {} from image {} to {}",
+                tag, previous_id, image_id
+            );
+            if !meta.repo_tags.is_empty() {
+                self.persist_image_meta(&previous_id, meta).await?;
+                continue;
+            }
+
+            let in_use = self.image_is_in_use(
+                &previous_id,
+                &HashSet::from([previous_id.clone()]),
+                &HashSet::from([tag.to_string()]),
+            );
+            if meta.pinned || in_use.is_err() {
+                info!(
+                    "Keeping untagged image {} because it is {}",
+                    previous_id,
+                    if meta.pinned { "pinned" } else { "in use" }
+                );
+                let image = Self::image_from_meta(&ImageMeta {
+                    id: previous_id.clone(),
+                    ..meta.clone()
+                });
+                self.persist_image_meta(&previous_id, meta).await?;
+                self.images.write().await.insert(previous_id.clone(), image);
+                continue;
+            }
+
+            info!("Removing image {} left without tags", previous_id);
+            self.images
+                .write()
+                .await
+                .retain(|_, image| image.id != previous_id);
+            let image_dir = self.storage_path.join("images").join(&previous_id);
+            if let Err(e) = tokio::fs::remove_dir_all(&image_dir).await {
+                error!("Failed to remove image directory {:?}: {}", image_dir, e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn persist_image_meta(&self, image_id: &str, meta: ImageMeta) -> Result<(), Status> {
+        self.save_image_metadata(&CriusImage {
+            id: image_id.to_string(),
+            repo_tags: meta.repo_tags,
+            repo_digests: meta.repo_digests,
+            size: meta.size,
+            pinned: meta.pinned,
+            pulled_at: meta.pulled_at,
+            source_reference: meta.source_reference,
+            os: meta.os,
+            architecture: meta.architecture,
+            config_user: meta.config_user,
+            annotations: meta.annotations,
+            manifest_media_type: meta.manifest_media_type,
+            runtime_handlers: meta.runtime_handlers,
+            exposed_ports: meta.exposed_ports,
+            stop_signal: meta.stop_signal,
+            env: meta.env,
+            layers: meta.layers,
+        })
+        .await
+        .map_err(|e| {
+            error!("Failed to save image metadata: {}", e);
+            Status::internal(format!("Failed to save image metadata: {}", e))
+        })
+    }
+
     // 删除镜像
-    async fn remove_image(
+    async fn remove_image_impl(
         &self,
         request: Request<RemoveImageRequest>,
     ) -> Result<Response<RemoveImageResponse>, Status> {
@@ -1377,7 +2370,7 @@ impl ImageService for ImageServiceImpl {
                 let requested_ref = image_spec.image;
 
                 let exact_tag_candidate = {
-                    let images = self.images.lock().await;
+                    let images = self.images.read().await;
                     images
                         .values()
                         .find(|image| image.repo_tags.iter().any(|tag| tag == &requested_ref))
@@ -1411,8 +2404,8 @@ impl ImageService for ImageServiceImpl {
                             ..Default::default()
                         });
 
-                        let mut images = self.images.lock().await;
-                        images.remove(&requested_ref);
+                        let mut images = self.images.write().await;
+                        images.retain(|key, _| !Self::is_record_key_for(key, &requested_ref));
                         for candidate in images.values_mut() {
                             if candidate.id == image.id {
                                 candidate.repo_tags = remaining_tags.clone();
@@ -1432,7 +2425,7 @@ impl ImageService for ImageServiceImpl {
                 }
 
                 let (candidate_ids, candidate_refs): (HashSet<String>, HashSet<String>) = {
-                    let images = self.images.lock().await;
+                    let images = self.images.read().await;
                     if let Some(image) = images.get(&requested_ref) {
                         let mut ids = HashSet::new();
                         ids.insert(image.id.clone());
@@ -1464,7 +2457,7 @@ impl ImageService for ImageServiceImpl {
                     self.image_is_in_use(&requested_ref, &candidate_ids, &candidate_refs)?;
 
                     let image_ids_to_remove: Vec<String> = {
-                        let mut images = self.images.lock().await;
+                        let mut images = self.images.write().await;
                         images.retain(|key, image| {
                             !(candidate_ids.contains(&image.id)
                                 || candidate_refs.contains(key)
@@ -1499,47 +2492,205 @@ impl ImageService for ImageServiceImpl {
             }
         }
     }
+}
 
-    // 获取镜像文件信息
-    async fn image_fs_info(
+#[tonic::async_trait]
+impl ImageService for ImageServiceImpl {
+    // 列出镜像
+    async fn list_images(
         &self,
-        _request: Request<ImageFsInfoRequest>,
-    ) -> Result<Response<ImageFsInfoResponse>, Status> {
-        let images_dir = self.storage_path.join("images");
-        let (used_bytes, inodes_used) =
-            Self::collect_path_usage(&images_dir).map_err(|e: io::Error| {
-                Status::internal(format!(
-                    "Failed to collect image filesystem usage from {}: {}",
-                    images_dir.display(),
-                    e
-                ))
-            })?;
-
-        let usage = FilesystemUsage {
-            timestamp: Self::now_nanos(),
-            fs_id: Some(FilesystemIdentifier {
-                mountpoint: images_dir.display().to_string(),
-            }),
-            used_bytes: Some(UInt64Value { value: used_bytes }),
-            inodes_used: Some(UInt64Value { value: inodes_used }),
+        request: Request<ListImagesRequest>,
+    ) -> Result<Response<ListImagesResponse>, Status> {
+        let req = request.into_inner();
+        let requested_ref = req
+            .filter
+            .and_then(|filter| filter.image)
+            .map(|image| image.image)
+            .filter(|image| !image.is_empty());
+        // 持锁期间只取共享快照，每个镜像只在聚合结果中复制一次
+        let images = {
+            let images = self.images.read().await;
+            info!("Number of images in memory: {}", images.len());
+            for (key, image) in images.iter() {
+                info!("Image: {} -> {}", key, image.id);
+            }
+            images.snapshot()
         };
+        let mut grouped: HashMap<&str, Vec<&Image>> = HashMap::new();
+        for image in images
+            .iter()
+            .filter(|image| Self::image_runtime_handler(image).is_empty())
+        {
+            grouped.entry(image.id.as_str()).or_default().push(image);
+        }
 
-        Ok(Response::new(ImageFsInfoResponse {
-            image_filesystems: vec![usage],
-            container_filesystems: Vec::new(),
-        }))
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::proto::runtime::v1::{ImageFilter, ImageFsInfoRequest, ImageSpec};
-    use crate::storage::{ContainerRecord, StorageManager};
-    use chrono::Utc;
-    use tempfile::{tempdir, TempDir};
-
-    async fn test_image_service() -> ImageServiceImpl {
+        let mut images_list = Vec::new();
+        for (image_id, group) in grouped {
+            let meta = self.load_image_metadata(image_id);
+            let Some(image) = Self::aggregate_image_records(group, meta.as_ref()) else {
+                continue;
+            };
+            let matched = requested_ref
+                .as_ref()
+                .map(|requested_ref| Self::image_matches_filter(&image, requested_ref))
+                .unwrap_or(true);
+            if matched {
+                images_list.push(image);
+            }
+        }
+        images_list.sort_by(|left, right| left.id.cmp(&right.id));
+
+        Ok(Response::new(ListImagesResponse {
+            images: images_list,
+        }))
+    }
+
+    // 获取镜像状态
+    async fn image_status(
+        &self,
+        request: Request<ImageStatusRequest>,
+    ) -> Result<Response<ImageStatusResponse>, Status> {
+        let req = request.into_inner();
+        let image_spec = req
+            .image
+            .ok_or_else(|| Status::invalid_argument("Image not specified"))?;
+        let requested_ref = image_spec.image;
+        let runtime_handler = image_spec.runtime_handler.trim().to_string();
+        let images: Vec<Image> = {
+            let images = self.images.read().await;
+            images
+                .values()
+                .filter(|image| Self::image_runtime_handler(image) == runtime_handler)
+                .cloned()
+                .collect()
+        };
+
+        if let Some(matched_image) = images
+            .iter()
+            .find(|image| Self::image_matches_ref(image, &requested_ref))
+        {
+            let meta = self.load_image_metadata(&matched_image.id);
+            if let Some(mut image) = Self::aggregate_image_records(
+                images
+                    .iter()
+                    .filter(|candidate| candidate.id == matched_image.id),
+                meta.as_ref(),
+            ) {
+                let annotations = image
+                    .spec
+                    .as_ref()
+                    .map(|spec| spec.annotations.clone())
+                    .unwrap_or_default();
+                image.spec = Some(ImageSpec {
+                    image: requested_ref.clone(),
+                    user_specified_image: requested_ref.clone(),
+                    annotations,
+                    runtime_handler: runtime_handler.clone(),
+                });
+
+                return Ok(Response::new(ImageStatusResponse {
+                    image: Some(image.clone()),
+                    info: if req.verbose {
+                        self.build_image_verbose_info(&image, &runtime_handler)?
+                    } else {
+                        HashMap::new()
+                    },
+                }));
+            }
+        }
+
+        Ok(Response::new(ImageStatusResponse {
+            image: None,
+            info: HashMap::new(),
+        }))
+    }
+
+    // 拉取镜像
+    async fn pull_image(
+        &self,
+        request: Request<PullImageRequest>,
+    ) -> Result<Response<PullImageResponse>, Status> {
+        let entry = AuditEntry::new("/runtime.v1.ImageService/PullImage", &request).id(
+            "image",
+            request
+                .get_ref()
+                .image
+                .as_ref()
+                .map(|image| image.image.as_str())
+                .unwrap_or_default(),
+        );
+        let mut timer = OperationTimer::start(Operation::PullImage);
+        let result = self.pull_image_impl(request).await;
+        if result.is_ok() {
+            timer.record(Phase::ImagePull);
+            timer.finish();
+        }
+        self.audit(
+            entry.response_id("image_ref", &result, |response| &response.image_ref),
+            &result,
+        );
+        result
+    }
+
+    // 删除镜像
+    async fn remove_image(
+        &self,
+        request: Request<RemoveImageRequest>,
+    ) -> Result<Response<RemoveImageResponse>, Status> {
+        let entry = AuditEntry::new("/runtime.v1.ImageService/RemoveImage", &request).id(
+            "image",
+            request
+                .get_ref()
+                .image
+                .as_ref()
+                .map(|image| image.image.as_str())
+                .unwrap_or_default(),
+        );
+        let result = self.remove_image_impl(request).await;
+        self.audit(entry, &result);
+        result
+    }
+
+    // 获取镜像文件信息
+    async fn image_fs_info(
+        &self,
+        _request: Request<ImageFsInfoRequest>,
+    ) -> Result<Response<ImageFsInfoResponse>, Status> {
+        let images_dir = self.storage_path.join("images");
+        let (used_bytes, inodes_used) =
+            Self::collect_path_usage(&images_dir).map_err(|e: io::Error| {
+                Status::internal(format!(
+                    "Failed to collect image filesystem usage from {}: {}",
+                    images_dir.display(),
+                    e
+                ))
+            })?;
+
+        let usage = FilesystemUsage {
+            timestamp: Self::now_nanos(),
+            fs_id: Some(FilesystemIdentifier {
+                mountpoint: images_dir.display().to_string(),
+            }),
+            used_bytes: Some(UInt64Value { value: used_bytes }),
+            inodes_used: Some(UInt64Value { value: inodes_used }),
+        };
+
+        Ok(Response::new(ImageFsInfoResponse {
+            image_filesystems: vec![usage],
+            container_filesystems: Vec::new(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::runtime::v1::{ImageFilter, ImageFsInfoRequest, ImageSpec};
+    use crate::storage::{ContainerRecord, StorageManager};
+    use chrono::Utc;
+    use tempfile::{tempdir, TempDir};
+
+    async fn test_image_service() -> ImageServiceImpl {
         let dir = tempdir().unwrap();
         let path = dir.path().to_path_buf();
         std::mem::forget(dir);
@@ -1553,7 +2704,7 @@ mod tests {
     }
 
     async fn insert_image(service: &ImageServiceImpl, image: Image) {
-        let mut images = service.images.lock().await;
+        let mut images = service.images.write().await;
         for tag in &image.repo_tags {
             images.insert(tag.clone(), image.clone());
         }
@@ -1616,6 +2767,88 @@ mod tests {
         assert_eq!(by_id_prefix.images[0].repo_tags, vec!["busybox:latest"]);
     }
 
+    #[tokio::test]
+    async fn list_images_filter_matches_tags_digests_and_nothing_else() {
+        let service = test_image_service().await;
+        let digest = "sha256:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+        insert_image(
+            &service,
+            Image {
+                id: "sha256:3333333333333333".to_string(),
+                repo_tags: vec!["docker.io/library/busybox:latest".to_string()],
+                repo_digests: vec![format!("docker.io/library/busybox@{}", digest)],
+                ..Default::default()
+            },
+        )
+        .await;
+        insert_image(
+            &service,
+            Image {
+                id: "sha256:4444444444444444".to_string(),
+                repo_tags: vec!["docker.io/library/busybox:stable".to_string()],
+                ..Default::default()
+            },
+        )
+        .await;
+        insert_image(
+            &service,
+            Image {
+                id: "sha256:5555555555555555".to_string(),
+                repo_tags: vec!["registry.k8s.io/pause:3.9".to_string()],
+                ..Default::default()
+            },
+        )
+        .await;
+
+        let list = |filter: &str| {
+            let request = Request::new(ListImagesRequest {
+                filter: Some(ImageFilter {
+                    image: Some(ImageSpec {
+                        image: filter.to_string(),
+                        ..Default::default()
+                    }),
+                }),
+            });
+            let service = &service;
+            async move {
+                ImageService::list_images(service, request)
+                    .await
+                    .unwrap()
+                    .into_inner()
+                    .images
+                    .into_iter()
+                    .map(|image| image.id)
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(
+            list("busybox:stable").await,
+            vec!["sha256:4444444444444444"]
+        );
+        assert_eq!(
+            list("registry.k8s.io/pause:3.9").await,
+            vec!["sha256:5555555555555555"]
+        );
+        assert_eq!(
+            list("busybox").await,
+            vec!["sha256:3333333333333333", "sha256:4444444444444444"]
+        );
+        assert_eq!(
+            list(&format!("busybox@{}", digest)).await,
+            vec!["sha256:3333333333333333"]
+        );
+        assert_eq!(list(&digest[..20]).await, vec!["sha256:3333333333333333"]);
+        assert_eq!(
+            list("docker.io/library/busybox@sha256:aaaa").await,
+            vec!["sha256:3333333333333333"]
+        );
+
+        assert!(list("busybox:1.36").await.is_empty());
+        assert!(list("bus").await.is_empty());
+        assert!(list(&format!("alpine@{}", digest)).await.is_empty());
+    }
+
     #[tokio::test]
     async fn image_status_returns_empty_response_when_missing() {
         let service = test_image_service().await;
@@ -1638,6 +2871,217 @@ mod tests {
         assert!(response.info.is_empty());
     }
 
+    #[tokio::test]
+    async fn image_queries_do_not_block_on_concurrent_readers() {
+        let service = test_image_service().await;
+        insert_image(
+            &service,
+            Image {
+                id: "sha256:1111111111111111".to_string(),
+                repo_tags: vec!["busybox:latest".to_string()],
+                ..Default::default()
+            },
+        )
+        .await;
+
+        // 模拟另一个仍持有读锁的查询
+        let _reader = service.images.read().await;
+        let timeout = std::time::Duration::from_secs(1);
+        let listed = tokio::time::timeout(
+            timeout,
+            ImageService::list_images(&service, Request::new(ListImagesRequest { filter: None })),
+        )
+        .await
+        .expect("list_images blocked behind a reader")
+        .unwrap()
+        .into_inner();
+        assert_eq!(listed.images.len(), 1);
+
+        let status = tokio::time::timeout(
+            timeout,
+            ImageService::image_status(
+                &service,
+                Request::new(ImageStatusRequest {
+                    image: Some(ImageSpec {
+                        image: "busybox:latest".to_string(),
+                        ..Default::default()
+                    }),
+                    verbose: false,
+                }),
+            ),
+        )
+        .await
+        .expect("image_status blocked behind a reader")
+        .unwrap()
+        .into_inner();
+        assert!(status.image.is_some());
+    }
+
+    #[test]
+    fn publish_staged_image_replaces_existing_image_dir() {
+        let dir = tempdir().unwrap();
+        let staging = dir.path().join("tmp").join("pull-1");
+        let target = dir.path().join("images").join("sha256:img");
+        std::fs::create_dir_all(&staging).unwrap();
+        std::fs::write(staging.join("0.tar.gz"), b"new").unwrap();
+        std::fs::write(staging.join("metadata.json"), b"{}").unwrap();
+        std::fs::create_dir_all(&target).unwrap();
+        std::fs::write(target.join("1.tar.gz"), b"old").unwrap();
+
+        ImageServiceImpl::publish_staged_image(&staging, &target, true).unwrap();
+
+        assert!(!staging.exists());
+        assert_eq!(std::fs::read(target.join("0.tar.gz")).unwrap(), b"new");
+        assert!(target.join("metadata.json").exists());
+        assert!(!target.join("1.tar.gz").exists());
+    }
+
+    #[test]
+    fn cross_device_publish_copies_then_removes_staging() {
+        let dir = tempdir().unwrap();
+        let staging = dir.path().join("tmp").join("pull-1");
+        let images_dir = dir.path().join("images");
+        let target = images_dir.join("sha256:img");
+        std::fs::create_dir_all(&staging).unwrap();
+        std::fs::create_dir_all(&images_dir).unwrap();
+        std::fs::write(staging.join("0.tar.gz"), b"layer").unwrap();
+        std::fs::write(staging.join("metadata.json"), b"{}").unwrap();
+
+        ImageServiceImpl::copy_staged_image_across_devices(&staging, &target, true).unwrap();
+
+        assert!(!staging.exists());
+        assert_eq!(std::fs::read(target.join("0.tar.gz")).unwrap(), b"layer");
+        assert!(target.join("metadata.json").exists());
+        let leftovers: Vec<_> = std::fs::read_dir(&images_dir)
+            .unwrap()
+            .flatten()
+            .map(|entry| entry.file_name())
+            .collect();
+        assert_eq!(leftovers, vec![std::ffi::OsString::from("sha256:img")]);
+    }
+
+    #[tokio::test]
+    async fn save_image_metadata_replaces_file_atomically() {
+        // 持久化约定：元数据经临时文件 + rename 写入，不会出现截断的 metadata.json
+        let (dir, mut service) = test_image_service_in_tempdir();
+        for fsync in [true, false] {
+            service.set_fsync(fsync);
+            let image = CriusImage {
+                id: "sha256:img".to_string(),
+                repo_tags: vec![format!("busybox:fsync-{}", fsync)],
+                ..Default::default()
+            };
+            service.save_image_metadata(&image).await.unwrap();
+
+            let image_dir = dir.path().join("images").join("sha256:img");
+            let meta = service.load_image_metadata("sha256:img").unwrap();
+            assert_eq!(meta.repo_tags, image.repo_tags);
+            let entries: Vec<_> = std::fs::read_dir(&image_dir)
+                .unwrap()
+                .flatten()
+                .map(|entry| entry.file_name())
+                .collect();
+            assert_eq!(entries, vec![std::ffi::OsString::from("metadata.json")]);
+        }
+    }
+
+    #[tokio::test]
+    async fn load_local_images_removes_incomplete_downloads() {
+        let (dir, service) = test_image_service_in_tempdir();
+        let stale_pull = dir.path().join("tmp").join("pull-stale");
+        let partial = dir.path().join("images").join(".partial-stale");
+        for path in [&stale_pull, &partial] {
+            std::fs::create_dir_all(path).unwrap();
+            std::fs::write(
+                path.join("metadata.json"),
+                serde_json::to_vec(&CriusImage {
+                    id: "sha256:partial".to_string(),
+                    repo_tags: vec!["docker.io/library/busybox:latest".to_string()],
+                    ..Default::default()
+                })
+                .unwrap(),
+            )
+            .unwrap();
+        }
+
+        service.load_local_images().await.unwrap();
+
+        assert!(!stale_pull.exists());
+        assert!(!partial.exists());
+        assert!(service.images.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn load_local_images_removes_half_written_images() {
+        let (dir, service) = test_image_service_in_tempdir();
+        let images_dir = dir.path().join("images");
+        let layer = |file: &str, size| ImageLayerFile {
+            file: file.to_string(),
+            size,
+            ..Default::default()
+        };
+        let write_image = |id: &str, layers: Vec<ImageLayerFile>, present: &[(&str, usize)]| {
+            let image_dir = images_dir.join(id);
+            std::fs::create_dir_all(&image_dir).unwrap();
+            for (file, size) in present {
+                std::fs::write(image_dir.join(file), vec![0u8; *size]).unwrap();
+            }
+            std::fs::write(
+                image_dir.join("metadata.json"),
+                serde_json::to_vec(&CriusImage {
+                    id: id.to_string(),
+                    repo_tags: vec![format!("docker.io/library/{}:latest", &id[7..])],
+                    layers,
+                    ..Default::default()
+                })
+                .unwrap(),
+            )
+            .unwrap();
+            image_dir
+        };
+
+        let complete = write_image(
+            "sha256:complete",
+            vec![layer("0.tar.gz", 4), layer("1.tar.gz", 8)],
+            &[("0.tar.gz", 4), ("1.tar.gz", 8)],
+        );
+        let missing_layer = write_image(
+            "sha256:missing",
+            vec![layer("0.tar.gz", 4), layer("1.tar.gz", 8)],
+            &[("0.tar.gz", 4)],
+        );
+        let truncated_layer = write_image(
+            "sha256:truncated",
+            vec![layer("0.tar.gz", 4)],
+            &[("0.tar.gz", 2)],
+        );
+        let no_metadata = images_dir.join("sha256:nometa");
+        std::fs::create_dir_all(&no_metadata).unwrap();
+        std::fs::write(no_metadata.join("0.tar.gz"), b"layer").unwrap();
+        let corrupt_metadata = images_dir.join("sha256:corrupt");
+        std::fs::create_dir_all(&corrupt_metadata).unwrap();
+        std::fs::write(
+            corrupt_metadata.join("metadata.json"),
+            b"{\"id\": \"sha256:cor",
+        )
+        .unwrap();
+
+        service.load_local_images().await.unwrap();
+
+        assert!(complete.exists());
+        for removed in [
+            &missing_layer,
+            &truncated_layer,
+            &no_metadata,
+            &corrupt_metadata,
+        ] {
+            assert!(!removed.exists(), "{:?} should be removed", removed);
+        }
+        let images = service.images.read().await;
+        let ids: HashSet<&str> = images.values().map(|image| image.id.as_str()).collect();
+        assert_eq!(ids, HashSet::from(["sha256:complete"]));
+    }
+
     #[tokio::test]
     async fn image_status_verbose_returns_structured_info_and_repo_digests() {
         let (dir, service) = test_image_service_in_tempdir();
@@ -1748,6 +3192,51 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn list_images_reuses_snapshot_until_records_change() {
+        let service = test_image_service().await;
+        insert_image(
+            &service,
+            Image {
+                id: "sha256:first".to_string(),
+                repo_tags: vec!["repo/first:latest".to_string()],
+                ..Default::default()
+            },
+        )
+        .await;
+        let list = || async {
+            ImageService::list_images(&service, Request::new(ListImagesRequest { filter: None }))
+                .await
+                .unwrap()
+                .into_inner()
+                .images
+        };
+
+        assert_eq!(list().await.len(), 1);
+        let snapshot = service.images.read().await.snapshot();
+        assert_eq!(list().await.len(), 1);
+        assert!(Arc::ptr_eq(
+            &snapshot,
+            &service.images.read().await.snapshot()
+        ));
+
+        insert_image(
+            &service,
+            Image {
+                id: "sha256:second".to_string(),
+                repo_tags: vec!["repo/second:latest".to_string()],
+                ..Default::default()
+            },
+        )
+        .await;
+        assert!(!Arc::ptr_eq(
+            &snapshot,
+            &service.images.read().await.snapshot()
+        ));
+        let ids: Vec<String> = list().await.into_iter().map(|image| image.id).collect();
+        assert_eq!(ids, vec!["sha256:first", "sha256:second"]);
+    }
+
     #[tokio::test]
     async fn image_status_aggregates_tags_and_user_for_same_id() {
         let (dir, service) = test_image_service_in_tempdir();
@@ -1765,6 +3254,11 @@ mod tests {
                 config_user: Some("1001".to_string()),
                 annotations: HashMap::new(),
                 manifest_media_type: None,
+                runtime_handlers: Vec::new(),
+                exposed_ports: Vec::new(),
+                stop_signal: None,
+                env: Vec::new(),
+                layers: Vec::new(),
             })
             .await
             .unwrap();
@@ -1809,55 +3303,355 @@ mod tests {
             image.repo_tags,
             vec!["repo/a:latest".to_string(), "repo/b:latest".to_string()]
         );
-        assert_eq!(image.repo_digests, vec!["repo/a@sha256:agg-id".to_string()]);
-        let spec = image.spec.expect("expected image spec");
-        assert_eq!(spec.image, "repo/b:latest");
-        assert_eq!(spec.user_specified_image, "repo/b:latest");
+        assert_eq!(image.repo_digests, vec!["repo/a@sha256:agg-id".to_string()]);
+        let spec = image.spec.expect("expected image spec");
+        assert_eq!(spec.image, "repo/b:latest");
+        assert_eq!(spec.user_specified_image, "repo/b:latest");
+        assert_eq!(
+            image.uid.expect("expected uid from image metadata").value,
+            1001
+        );
+        assert!(image.username.is_empty());
+    }
+
+    #[tokio::test]
+    async fn image_status_restores_spec_annotations_from_metadata() {
+        let service = test_image_service().await;
+        insert_image(
+            &service,
+            Image {
+                id: "sha256:anno-id".to_string(),
+                repo_tags: vec!["repo/anno:latest".to_string()],
+                ..Default::default()
+            },
+        )
+        .await;
+        service
+            .save_image_metadata(&CriusImage {
+                id: "sha256:anno-id".to_string(),
+                repo_tags: vec!["repo/anno:latest".to_string()],
+                repo_digests: Vec::new(),
+                size: 7,
+                pinned: false,
+                pulled_at: 0,
+                source_reference: None,
+                os: None,
+                architecture: None,
+                config_user: None,
+                annotations: HashMap::from([(
+                    "org.opencontainers.image.title".to_string(),
+                    "anno".to_string(),
+                )]),
+                manifest_media_type: None,
+                runtime_handlers: Vec::new(),
+                exposed_ports: Vec::new(),
+                stop_signal: None,
+                env: Vec::new(),
+                layers: Vec::new(),
+            })
+            .await
+            .unwrap();
+
+        let response = ImageService::image_status(
+            &service,
+            Request::new(ImageStatusRequest {
+                image: Some(ImageSpec {
+                    image: "repo/anno:latest".to_string(),
+                    ..Default::default()
+                }),
+                verbose: false,
+            }),
+        )
+        .await
+        .unwrap()
+        .into_inner();
+        let image = response.image.expect("expected image");
+        assert_eq!(
+            image
+                .spec
+                .expect("expected image spec")
+                .annotations
+                .get("org.opencontainers.image.title")
+                .map(String::as_str),
+            Some("anno")
+        );
+    }
+
+    #[tokio::test]
+    async fn pull_image_records_runtime_handler_for_local_image() {
+        let (_dir, service) = test_image_service_in_tempdir();
+        service
+            .save_image_metadata(&CriusImage {
+                id: "sha256:handler-id".to_string(),
+                repo_tags: vec!["docker.io/library/busybox:latest".to_string()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        service.load_local_images().await.unwrap();
+
+        let image_status = |runtime_handler: &str| {
+            ImageService::image_status(
+                &service,
+                Request::new(ImageStatusRequest {
+                    image: Some(ImageSpec {
+                        image: "busybox:latest".to_string(),
+                        runtime_handler: runtime_handler.to_string(),
+                        ..Default::default()
+                    }),
+                    verbose: true,
+                }),
+            )
+        };
+        assert!(image_status("kata")
+            .await
+            .unwrap()
+            .into_inner()
+            .image
+            .is_none());
+
+        let pulled = ImageService::pull_image(
+            &service,
+            Request::new(PullImageRequest {
+                image: Some(ImageSpec {
+                    image: "busybox:latest".to_string(),
+                    runtime_handler: "kata".to_string(),
+                    ..Default::default()
+                }),
+                auth: None,
+                sandbox_config: None,
+            }),
+        )
+        .await
+        .unwrap()
+        .into_inner();
+        assert_eq!(pulled.image_ref, "sha256:handler-id");
+        assert_eq!(
+            service
+                .load_image_metadata("sha256:handler-id")
+                .unwrap()
+                .runtime_handlers,
+            vec!["kata"]
+        );
+
+        let response = image_status("kata").await.unwrap().into_inner();
+        let image = response.image.expect("expected handler image");
+        assert_eq!(image.spec.unwrap().runtime_handler, "kata");
+        let info: serde_json::Value =
+            serde_json::from_str(response.info.get("info").unwrap()).unwrap();
+        assert_eq!(info["runtimeHandler"], "kata");
+        assert_eq!(info["runtimeHandlers"][0], "kata");
+
+        let default_image = image_status("").await.unwrap().into_inner().image.unwrap();
+        assert_eq!(default_image.spec.unwrap().runtime_handler, "");
+        assert!(image_status("gvisor")
+            .await
+            .unwrap()
+            .into_inner()
+            .image
+            .is_none());
+
+        let listed =
+            ImageService::list_images(&service, Request::new(ListImagesRequest { filter: None }))
+                .await
+                .unwrap()
+                .into_inner();
+        assert_eq!(listed.images.len(), 1);
+        assert_eq!(listed.images[0].spec.as_ref().unwrap().runtime_handler, "");
+    }
+
+    /// 以固定镜像 ID 代替 registry 下载，并统计下载次数
+    fn fake_fetch(
+        service: &mut ImageServiceImpl,
+        image_id: &'static str,
+    ) -> Arc<std::sync::atomic::AtomicUsize> {
+        use futures::FutureExt;
+
+        let downloads = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = downloads.clone();
+        service.fetch_override = Some(Arc::new(move |_reference| {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                Ok((
+                    image_id.to_string(),
+                    5,
+                    vec![b"layer".to_vec()],
+                    PulledImageMetadata::default(),
+                ))
+            }
+            .boxed()
+        }));
+        downloads
+    }
+
+    fn pull_request(image: &str, runtime_handler: &str) -> Request<PullImageRequest> {
+        Request::new(PullImageRequest {
+            image: Some(ImageSpec {
+                image: image.to_string(),
+                runtime_handler: runtime_handler.to_string(),
+                ..Default::default()
+            }),
+            auth: None,
+            sandbox_config: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn pull_tagged_digest_reference_fetches_digest_and_keeps_tag() {
+        use futures::FutureExt;
+
+        let (_dir, mut service) = test_image_service_in_tempdir();
+        let fetched = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = fetched.clone();
+        service.fetch_override = Some(Arc::new(move |reference| {
+            recorded.lock().unwrap().push(reference);
+            async move {
+                Ok((
+                    "sha256:pinned".to_string(),
+                    5,
+                    vec![b"layer".to_vec()],
+                    PulledImageMetadata::default(),
+                ))
+            }
+            .boxed()
+        }));
+        let digest = format!("sha256:{}", "a".repeat(64));
+        let requested = format!("busybox:1.36@{}", digest);
+        let digest_ref = format!("docker.io/library/busybox@{}", digest);
+
+        for _ in 0..2 {
+            let pulled = ImageService::pull_image(&service, pull_request(&requested, ""))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(pulled.image_ref, "sha256:pinned");
+        }
+
+        // 第二次按 digest 命中本地镜像，不再下载
+        assert_eq!(*fetched.lock().unwrap(), vec![digest_ref.clone()]);
+        let meta = service.load_image_metadata("sha256:pinned").unwrap();
+        assert_eq!(meta.repo_tags, vec!["docker.io/library/busybox:1.36"]);
+        assert!(
+            meta.repo_digests.contains(&digest_ref),
+            "{:?}",
+            meta.repo_digests
+        );
+    }
+
+    #[tokio::test]
+    async fn concurrent_pulls_of_one_image_download_once() {
+        let (dir, mut service) = test_image_service_in_tempdir();
+        let downloads = fake_fetch(&mut service, "sha256:concurrent");
+
+        let (first, second, third) = tokio::join!(
+            ImageService::pull_image(&service, pull_request("busybox:latest", "")),
+            ImageService::pull_image(&service, pull_request("busybox:latest", "")),
+            ImageService::pull_image(&service, pull_request("busybox:latest", "kata")),
+        );
+
+        assert_eq!(downloads.load(std::sync::atomic::Ordering::SeqCst), 1);
+        for pulled in [first, second, third] {
+            assert_eq!(pulled.unwrap().into_inner().image_ref, "sha256:concurrent");
+        }
+        let meta = service.load_image_metadata("sha256:concurrent").unwrap();
+        assert_eq!(meta.runtime_handlers, vec!["kata"]);
+        assert!(dir
+            .path()
+            .join("images")
+            .join("sha256:concurrent")
+            .join("0.tar.gz")
+            .exists());
+        assert!(service.image_dir_locks.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn concurrent_pulls_resolving_to_same_id_share_image_dir() {
+        let (dir, mut service) = test_image_service_in_tempdir();
+        let downloads = fake_fetch(&mut service, "sha256:shared");
+
+        let (latest, stable) = tokio::join!(
+            ImageService::pull_image(&service, pull_request("busybox:latest", "")),
+            ImageService::pull_image(&service, pull_request("busybox:stable", "")),
+        );
+        assert_eq!(latest.unwrap().into_inner().image_ref, "sha256:shared");
+        assert_eq!(stable.unwrap().into_inner().image_ref, "sha256:shared");
+        assert_eq!(downloads.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+        let mut tags = service
+            .load_image_metadata("sha256:shared")
+            .unwrap()
+            .repo_tags;
+        tags.sort();
         assert_eq!(
-            image.uid.expect("expected uid from image metadata").value,
-            1001
+            tags,
+            vec![
+                "docker.io/library/busybox:latest",
+                "docker.io/library/busybox:stable"
+            ]
         );
-        assert!(image.username.is_empty());
+        let image_dir = dir.path().join("images").join("sha256:shared");
+        assert_eq!(std::fs::read(image_dir.join("0.tar.gz")).unwrap(), b"layer");
+        assert!(std::fs::read_dir(dir.path().join("tmp"))
+            .unwrap()
+            .next()
+            .is_none());
     }
 
     #[tokio::test]
-    async fn image_status_restores_spec_annotations_from_metadata() {
-        let service = test_image_service().await;
-        insert_image(
-            &service,
-            Image {
-                id: "sha256:anno-id".to_string(),
-                repo_tags: vec!["repo/anno:latest".to_string()],
-                ..Default::default()
-            },
-        )
-        .await;
-        service
-            .save_image_metadata(&CriusImage {
-                id: "sha256:anno-id".to_string(),
-                repo_tags: vec!["repo/anno:latest".to_string()],
-                repo_digests: Vec::new(),
-                size: 7,
-                pinned: false,
-                pulled_at: 0,
-                source_reference: None,
-                os: None,
-                architecture: None,
-                config_user: None,
-                annotations: HashMap::from([(
-                    "org.opencontainers.image.title".to_string(),
-                    "anno".to_string(),
-                )]),
-                manifest_media_type: None,
-            })
-            .await
-            .unwrap();
+    async fn repulling_moved_tag_reassigns_it_to_new_image() {
+        use futures::FutureExt;
+
+        let (dir, mut service) = test_image_service_in_tempdir();
+        service.set_refresh_tags(true);
+        // registry 上 latest 与 stable 先指向 old，随后依次移动到 new
+        let resolved = Arc::new(std::sync::Mutex::new(vec![
+            "sha256:new",
+            "sha256:new",
+            "sha256:old",
+            "sha256:old",
+        ]));
+        service.fetch_override = Some(Arc::new(move |_reference| {
+            let image_id = resolved.lock().unwrap().pop().unwrap();
+            async move {
+                Ok((
+                    image_id.to_string(),
+                    5,
+                    vec![b"layer".to_vec()],
+                    PulledImageMetadata::default(),
+                ))
+            }
+            .boxed()
+        }));
+        let pull =
+            |image: &'static str| ImageService::pull_image(&service, pull_request(image, ""));
+        let tags = |image_id: &str| {
+            let mut tags = service.load_image_metadata(image_id).unwrap().repo_tags;
+            tags.sort();
+            tags
+        };
 
-        let response = ImageService::image_status(
+        assert_eq!(
+            pull("busybox:latest").await.unwrap().into_inner().image_ref,
+            "sha256:old"
+        );
+        assert_eq!(
+            pull("busybox:stable").await.unwrap().into_inner().image_ref,
+            "sha256:old"
+        );
+        assert_eq!(
+            pull("busybox:latest").await.unwrap().into_inner().image_ref,
+            "sha256:new"
+        );
+
+        assert_eq!(tags("sha256:new"), vec!["docker.io/library/busybox:latest"]);
+        assert_eq!(tags("sha256:old"), vec!["docker.io/library/busybox:stable"]);
+        let status = ImageService::image_status(
             &service,
             Request::new(ImageStatusRequest {
                 image: Some(ImageSpec {
-                    image: "repo/anno:latest".to_string(),
+                    image: "busybox:latest".to_string(),
                     ..Default::default()
                 }),
                 verbose: false,
@@ -1865,17 +3659,31 @@ mod tests {
         )
         .await
         .unwrap()
-        .into_inner();
-        let image = response.image.expect("expected image");
+        .into_inner()
+        .image
+        .unwrap();
+        assert_eq!(status.id, "sha256:new");
+
+        // old 失去最后一个标签且没有容器使用，随之删除
         assert_eq!(
-            image
-                .spec
-                .expect("expected image spec")
-                .annotations
-                .get("org.opencontainers.image.title")
-                .map(String::as_str),
-            Some("anno")
+            pull("busybox:stable").await.unwrap().into_inner().image_ref,
+            "sha256:new"
         );
+        assert_eq!(
+            tags("sha256:new"),
+            vec![
+                "docker.io/library/busybox:latest",
+                "docker.io/library/busybox:stable"
+            ]
+        );
+        assert!(!dir.path().join("images").join("sha256:old").exists());
+        let listed =
+            ImageService::list_images(&service, Request::new(ListImagesRequest { filter: None }))
+                .await
+                .unwrap()
+                .into_inner();
+        assert_eq!(listed.images.len(), 1);
+        assert_eq!(listed.images[0].id, "sha256:new");
     }
 
     #[test]
@@ -1972,6 +3780,115 @@ mod tests {
         );
     }
 
+    /// 写入一个本地镜像，层文件大小为 `layer_bytes`
+    async fn store_local_image(
+        service: &ImageServiceImpl,
+        image_id: &str,
+        tag: &str,
+        layer_bytes: usize,
+        pulled_at: i64,
+        pinned: bool,
+    ) {
+        service
+            .save_image_metadata(&CriusImage {
+                id: image_id.to_string(),
+                repo_tags: vec![tag.to_string()],
+                pinned,
+                pulled_at,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let image_dir = service.storage_path.join("images").join(image_id);
+        std::fs::write(image_dir.join("0.tar.gz"), vec![0u8; layer_bytes]).unwrap();
+        insert_image(
+            service,
+            Image {
+                id: image_id.to_string(),
+                repo_tags: vec![tag.to_string()],
+                pinned,
+                ..Default::default()
+            },
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn pull_prunes_unused_images_to_stay_under_store_cap() {
+        use futures::FutureExt;
+
+        let (dir, mut service) = test_image_service_in_tempdir();
+        service.set_max_store_bytes(8 * 1024);
+        service.fetch_override = Some(Arc::new(|reference: String| {
+            async move {
+                let (image_id, layer_bytes) = if reference.contains("huge") {
+                    ("sha256:huge", 20 * 1024)
+                } else {
+                    ("sha256:small", 4000)
+                };
+                Ok((
+                    image_id.to_string(),
+                    layer_bytes as u64,
+                    vec![vec![0u8; layer_bytes]],
+                    PulledImageMetadata::default(),
+                ))
+            }
+            .boxed()
+        }));
+        store_local_image(&service, "sha256:old", "old:latest", 3000, 1, false).await;
+        store_local_image(&service, "sha256:pinned", "pinned:latest", 2000, 0, true).await;
+
+        let pulled = ImageService::pull_image(&service, pull_request("small:latest", ""))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(pulled.image_ref, "sha256:small");
+        let images_dir = dir.path().join("images");
+        assert!(!images_dir.join("sha256:old").exists());
+        assert!(images_dir.join("sha256:pinned").exists());
+        assert!(service.find_local_image("old:latest").await.is_none());
+
+        // 删除全部可清理镜像仍放不下时直接失败，不删除任何镜像
+        let err = ImageService::pull_image(&service, pull_request("huge:latest", ""))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::ResourceExhausted);
+        assert!(images_dir.join("sha256:small").exists());
+        assert!(!images_dir.join("sha256:huge").exists());
+
+        let response = ImageService::image_status(
+            &service,
+            Request::new(ImageStatusRequest {
+                image: Some(ImageSpec {
+                    image: "small:latest".to_string(),
+                    ..Default::default()
+                }),
+                verbose: true,
+            }),
+        )
+        .await
+        .unwrap()
+        .into_inner();
+        let info: serde_json::Value =
+            serde_json::from_str(response.info.get("info").expect("missing verbose info")).unwrap();
+        assert_eq!(info["imageStore"]["maxBytes"], 8 * 1024);
+        let used = info["imageStore"]["usedBytes"].as_u64().unwrap();
+        assert!((6000..=8 * 1024).contains(&used), "used {}", used);
+    }
+
+    #[test]
+    fn image_store_usage_counts_hardlinked_blobs_once() {
+        let (dir, service) = test_image_service_in_tempdir();
+        let first = dir.path().join("images").join("sha256:first");
+        let second = dir.path().join("images").join("sha256:second");
+        std::fs::create_dir_all(&first).unwrap();
+        std::fs::create_dir_all(&second).unwrap();
+        std::fs::write(first.join("0.tar.gz"), vec![0u8; 1000]).unwrap();
+        std::fs::hard_link(first.join("0.tar.gz"), second.join("0.tar.gz")).unwrap();
+
+        assert_eq!(service.image_store_usage(&HashSet::new()).unwrap(), 1000);
+    }
+
     #[tokio::test]
     async fn image_fs_info_reports_real_usage() {
         let (dir, service) = test_image_service_in_tempdir();
@@ -2052,6 +3969,52 @@ mod tests {
         assert!(err.message().contains("in use"));
     }
 
+    #[tokio::test]
+    async fn remove_image_uses_configured_database_path() {
+        let dir = tempdir().unwrap();
+        let mut service = ImageServiceImpl::new(dir.path().join("image-root")).unwrap();
+        let db_path = dir.path().join("state-root").join("crius.db");
+        std::fs::create_dir_all(db_path.parent().unwrap()).unwrap();
+        service.set_database_path(&db_path);
+        insert_image(
+            &service,
+            Image {
+                id: "sha256:busybox-id".to_string(),
+                repo_tags: vec!["busybox:latest".to_string()],
+                ..Default::default()
+            },
+        )
+        .await;
+        StorageManager::new(&db_path)
+            .unwrap()
+            .save_container(&ContainerRecord {
+                id: "container-1".to_string(),
+                pod_id: "pod-1".to_string(),
+                state: "running".to_string(),
+                image: "busybox:latest".to_string(),
+                command: "sleep 60".to_string(),
+                created_at: Utc::now().timestamp(),
+                labels: "{}".to_string(),
+                annotations: "{}".to_string(),
+                exit_code: None,
+                exit_time: None,
+            })
+            .unwrap();
+
+        let err = ImageService::remove_image(
+            &service,
+            Request::new(RemoveImageRequest {
+                image: Some(ImageSpec {
+                    image: "busybox:latest".to_string(),
+                    ..Default::default()
+                }),
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+    }
+
     #[tokio::test]
     async fn remove_image_untags_single_reference_when_other_tags_remain() {
         let (dir, service) = test_image_service_in_tempdir();
@@ -2077,6 +4040,11 @@ mod tests {
                     "busybox".to_string(),
                 )]),
                 manifest_media_type: Some("application/vnd.oci.image.manifest.v1+json".to_string()),
+                runtime_handlers: Vec::new(),
+                exposed_ports: Vec::new(),
+                stop_signal: None,
+                env: Vec::new(),
+                layers: Vec::new(),
             })
             .await
             .unwrap();
@@ -2162,6 +4130,383 @@ mod tests {
         .into_inner();
         assert!(removed.image.is_none());
     }
+
+    #[tokio::test]
+    async fn download_blob_reconnects_after_mid_transfer_disconnect() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let blob: Vec<u8> = (0..64 * 1024).map(|i| (i % 251) as u8).collect();
+        let digest = format!("sha256:{:x}", Sha256::digest(&blob));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!(
+            "http://{}/v2/demo/blobs/{}",
+            listener.local_addr().unwrap(),
+            digest
+        );
+        let server_blob = blob.clone();
+        let server = tokio::spawn(async move {
+            for connection in 0..2 {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = [0u8; 1024];
+                let _ = stream.read(&mut request).await.unwrap();
+                let header = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n",
+                    server_blob.len()
+                );
+                stream.write_all(header.as_bytes()).await.unwrap();
+                // 第一次连接只发送一半内容后断开，模拟拉取过程中连接中断
+                let sent = if connection == 0 {
+                    server_blob.len() / 2
+                } else {
+                    server_blob.len()
+                };
+                stream.write_all(&server_blob[..sent]).await.unwrap();
+                stream.shutdown().await.unwrap();
+            }
+        });
+
+        let http = reqwest::Client::new();
+        let downloaded =
+            ImageServiceImpl::download_blob(&http, &url, &digest, &RegistryAuth::Anonymous, None)
+                .await
+                .unwrap();
+        assert_eq!(downloaded, blob);
+        server.await.unwrap();
+
+        // 非 5xx 的 HTTP 错误不重试
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!(
+            "http://{}/v2/demo/blobs/{}",
+            listener.local_addr().unwrap(),
+            digest
+        );
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n")
+                .await
+                .unwrap();
+            let _ = stream.read(&mut request).await;
+        });
+        let err =
+            ImageServiceImpl::download_blob(&http, &url, &digest, &RegistryAuth::Anonymous, None)
+                .await
+                .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::Internal);
+        assert!(err.message().contains("404"), "{}", err.message());
+    }
+
+    /// 在回环地址上提供一个 Docker schema2 镜像（经 manifest list 引用）的最小 registry，
+    /// `requests` 按顺序记录每个请求的路径与 Accept 头（没有时为空）；
+    /// `interrupted` 中的层在第一次请求时只发送一半内容就断开连接
+    async fn serve_docker_schema2_fixture(
+        layers: Vec<Vec<u8>>,
+        requests: Arc<std::sync::Mutex<Vec<(String, String)>>>,
+        interrupted: Vec<Vec<u8>>,
+    ) -> (std::net::SocketAddr, String) {
+        use hyper::service::{make_service_fn, service_fn};
+        use hyper::{Body, Response as HttpResponse};
+
+        let sha256 = |data: &[u8]| format!("sha256:{:x}", Sha256::digest(data));
+        let config = serde_json::to_vec(&serde_json::json!({
+            "os": "linux",
+            "architecture": "amd64",
+            "config": {
+                "User": "1000",
+                "StopSignal": "SIGQUIT",
+                "Env": ["PATH=/app/bin:/usr/bin"],
+                "Labels": {"app": "demo"}
+            },
+        }))
+        .unwrap();
+        let manifest = serde_json::to_vec(&serde_json::json!({
+            "schemaVersion": 2,
+            "mediaType": DOCKER_MANIFEST_MEDIA_TYPE,
+            "config": {
+                "mediaType": "application/vnd.docker.container.image.v1+json",
+                "size": config.len(),
+                "digest": sha256(&config),
+            },
+            "layers": layers.iter().map(|layer| serde_json::json!({
+                "mediaType": "application/vnd.docker.image.rootfs.diff.tar.gzip",
+                "size": layer.len(),
+                "digest": sha256(layer),
+            })).collect::<Vec<_>>(),
+        }))
+        .unwrap();
+        let manifest_digest = sha256(&manifest);
+        let arch = match std::env::consts::ARCH {
+            "x86_64" => "amd64",
+            "aarch64" => "arm64",
+            other => other,
+        };
+        let index = serde_json::to_vec(&serde_json::json!({
+            "schemaVersion": 2,
+            "mediaType": DOCKER_MANIFEST_LIST_MEDIA_TYPE,
+            "manifests": [{
+                "mediaType": DOCKER_MANIFEST_MEDIA_TYPE,
+                "size": manifest.len(),
+                "digest": manifest_digest,
+                "platform": {"os": "linux", "architecture": arch},
+            }],
+        }))
+        .unwrap();
+
+        let mut routes: HashMap<String, (&'static str, Vec<u8>)> = HashMap::new();
+        routes.insert("/v2/".to_string(), ("application/json", b"{}".to_vec()));
+        routes.insert(
+            "/v2/library/demo/manifests/v1".to_string(),
+            (DOCKER_MANIFEST_LIST_MEDIA_TYPE, index),
+        );
+        routes.insert(
+            format!("/v2/library/demo/manifests/{}", manifest_digest),
+            (DOCKER_MANIFEST_MEDIA_TYPE, manifest),
+        );
+        routes.insert(
+            format!("/v2/library/demo/blobs/{}", sha256(&config)),
+            ("application/octet-stream", config),
+        );
+        for layer in layers {
+            routes.insert(
+                format!("/v2/library/demo/blobs/{}", sha256(&layer)),
+                ("application/octet-stream", layer),
+            );
+        }
+        let routes = Arc::new(routes);
+        let interrupted: Arc<std::sync::Mutex<HashSet<String>>> = Arc::new(std::sync::Mutex::new(
+            interrupted
+                .iter()
+                .map(|layer| format!("/v2/library/demo/blobs/{}", sha256(layer)))
+                .collect(),
+        ));
+
+        let make_service = make_service_fn(move |_| {
+            let routes = routes.clone();
+            let requests = requests.clone();
+            let interrupted = interrupted.clone();
+            async move {
+                Ok::<_, std::convert::Infallible>(service_fn(move |request| {
+                    let path = request.uri().path().to_string();
+                    let accept = request
+                        .headers()
+                        .get("accept")
+                        .map(|accept| accept.to_str().unwrap().to_string())
+                        .unwrap_or_default();
+                    requests.lock().unwrap().push((path.clone(), accept));
+                    let interrupt = interrupted.lock().unwrap().remove(&path);
+                    let response = match routes.get(&path) {
+                        Some((content_type, body)) if interrupt => {
+                            let (mut sender, partial) = Body::channel();
+                            let half = body[..body.len() / 2].to_vec();
+                            tokio::spawn(async move {
+                                let _ = sender.send_data(half.into()).await;
+                                sender.abort();
+                            });
+                            HttpResponse::builder()
+                                .header("Content-Type", *content_type)
+                                .header("Content-Length", body.len())
+                                .body(partial)
+                        }
+                        Some((content_type, body)) => HttpResponse::builder()
+                            .header("Content-Type", *content_type)
+                            .header(
+                                "Docker-Content-Digest",
+                                format!("sha256:{:x}", Sha256::digest(body)),
+                            )
+                            .body(Body::from(body.clone())),
+                        None => HttpResponse::builder().status(404).body(Body::empty()),
+                    };
+                    async move { Ok::<_, std::convert::Infallible>(response.unwrap()) }
+                }))
+            }
+        });
+        let server = hyper::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(make_service);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        (addr, manifest_digest)
+    }
+
+    #[tokio::test]
+    async fn pull_image_fetches_docker_schema2_image_through_manifest_list() {
+        let (_dir, service) = test_image_service_in_tempdir();
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let layer = b"docker schema2 layer".to_vec();
+        let (addr, manifest_digest) =
+            serve_docker_schema2_fixture(vec![layer.clone()], requests.clone(), Vec::new()).await;
+
+        let image = format!("{}/library/demo:v1", addr);
+        let response = service
+            .pull_image(pull_request(&image, ""))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.image_ref, manifest_digest);
+
+        let metadata = service.load_image_metadata(&manifest_digest).unwrap();
+        assert_eq!(
+            metadata.manifest_media_type.as_deref(),
+            Some(DOCKER_MANIFEST_MEDIA_TYPE)
+        );
+        assert_eq!(metadata.config_user.as_deref(), Some("1000"));
+        assert_eq!(metadata.stop_signal.as_deref(), Some("SIGQUIT"));
+        assert_eq!(metadata.env, vec!["PATH=/app/bin:/usr/bin".to_string()]);
+        assert_eq!(metadata.layers.len(), 1);
+        assert_eq!(
+            std::fs::read(
+                service
+                    .storage_path
+                    .join("images")
+                    .join(&manifest_digest)
+                    .join(&metadata.layers[0].file)
+            )
+            .unwrap(),
+            layer
+        );
+
+        // 顶层请求接受 manifest 与 index，子 manifest 请求只接受单平台 manifest
+        let requests = requests.lock().unwrap().clone();
+        let accept_for = |path: &str| {
+            requests
+                .iter()
+                .find(|(requested, _)| requested == path)
+                .map(|(_, accept)| accept.clone())
+                .unwrap()
+        };
+        let top_accept = accept_for("/v2/library/demo/manifests/v1");
+        for media_type in DEFAULT_MANIFEST_MEDIA_TYPES {
+            assert!(top_accept.contains(media_type), "{}", top_accept);
+        }
+        let child_accept = accept_for(&format!("/v2/library/demo/manifests/{}", manifest_digest));
+        assert!(child_accept.contains(DOCKER_MANIFEST_MEDIA_TYPE));
+        assert!(!child_accept.contains(DOCKER_MANIFEST_LIST_MEDIA_TYPE));
+    }
+
+    #[tokio::test]
+    async fn pull_image_retries_layer_interrupted_mid_transfer() {
+        let (_dir, service) = test_image_service_in_tempdir();
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let first: Vec<u8> = (0..32 * 1024).map(|i| (i % 241) as u8).collect();
+        let flaky: Vec<u8> = (0..64 * 1024).map(|i| (i % 251) as u8).collect();
+        let (addr, manifest_digest) = serve_docker_schema2_fixture(
+            vec![first.clone(), flaky.clone()],
+            requests.clone(),
+            vec![flaky.clone()],
+        )
+        .await;
+
+        let image = format!("{}/library/demo:v1", addr);
+        let response = service
+            .pull_image(pull_request(&image, ""))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.image_ref, manifest_digest);
+
+        // 只有中断的那一层被重新请求，已完成的层不会重新下载
+        let blob_requests = |data: &[u8]| {
+            let path = format!("/v2/library/demo/blobs/sha256:{:x}", Sha256::digest(data));
+            requests
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(requested, _)| *requested == path)
+                .count()
+        };
+        assert_eq!(blob_requests(&first), 1);
+        assert_eq!(blob_requests(&flaky), 2);
+
+        let metadata = service.load_image_metadata(&manifest_digest).unwrap();
+        let image_dir = service.storage_path.join("images").join(&manifest_digest);
+        let stored: Vec<Vec<u8>> = metadata
+            .layers
+            .iter()
+            .map(|layer| std::fs::read(image_dir.join(&layer.file)).unwrap())
+            .collect();
+        assert_eq!(stored, vec![first, flaky]);
+    }
+
+    #[tokio::test]
+    async fn ensure_local_image_pulls_only_missing_images() {
+        let (_dir, service) = test_image_service_in_tempdir();
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let (addr, manifest_digest) =
+            serve_docker_schema2_fixture(vec![b"pause".to_vec()], requests.clone(), Vec::new())
+                .await;
+        let image = format!("{}/library/demo:v1", addr);
+
+        assert!(!service.has_local_image(&image).await);
+        service.ensure_local_image(&image).await.unwrap();
+        assert!(service.has_local_image(&image).await);
+        assert!(service.load_image_metadata(&manifest_digest).is_some());
+
+        let pulled_requests = requests.lock().unwrap().len();
+        service.ensure_local_image(&image).await.unwrap();
+        assert_eq!(requests.lock().unwrap().len(), pulled_requests);
+    }
+
+    #[tokio::test]
+    async fn pull_image_resumes_from_cached_layer_blobs() {
+        let (_dir, service) = test_image_service_in_tempdir();
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let cached = b"layer already downloaded".to_vec();
+        let missing = b"layer still missing".to_vec();
+        let corrupted = b"layer cut off mid-write".to_vec();
+        let (addr, manifest_digest) = serve_docker_schema2_fixture(
+            vec![cached.clone(), missing.clone(), corrupted.clone()],
+            requests.clone(),
+            Vec::new(),
+        )
+        .await;
+        let digest = |data: &[u8]| format!("sha256:{:x}", Sha256::digest(data));
+        let cache_path = |data: &[u8]| service.cached_blob_path(&digest(data)).unwrap();
+
+        // 模拟中断的 pull：第一层已完整缓存，第三层只写了一半
+        std::fs::create_dir_all(cache_path(&cached).parent().unwrap()).unwrap();
+        std::fs::write(cache_path(&cached), &cached).unwrap();
+        std::fs::write(cache_path(&corrupted), &corrupted[..8]).unwrap();
+
+        let image = format!("{}/library/demo:v1", addr);
+        let response = service
+            .pull_image(pull_request(&image, ""))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.image_ref, manifest_digest);
+
+        let blob_requests: Vec<String> = requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(path, _)| path.clone())
+            .filter(|path| path.contains("/blobs/"))
+            .collect();
+        let blob_path = |data: &[u8]| format!("/v2/library/demo/blobs/{}", digest(data));
+        assert!(!blob_requests.contains(&blob_path(&cached)));
+        assert!(blob_requests.contains(&blob_path(&missing)));
+        assert!(blob_requests.contains(&blob_path(&corrupted)));
+
+        let image_dir = service.storage_path.join("images").join(&manifest_digest);
+        let metadata = service.load_image_metadata(&manifest_digest).unwrap();
+        let stored: Vec<Vec<u8>> = metadata
+            .layers
+            .iter()
+            .map(|layer| std::fs::read(image_dir.join(&layer.file)).unwrap())
+            .collect();
+        assert_eq!(
+            stored,
+            vec![cached.clone(), missing.clone(), corrupted.clone()]
+        );
+        // 镜像落盘后缓存副本被清理
+        for layer in [&cached, &missing, &corrupted] {
+            assert!(!cache_path(layer).exists());
+        }
+    }
 }
 
+mod fsck;
 pub mod layer;
+pub mod reference;
+
+pub use fsck::{FsckMode, FsckReport};
@@ -0,0 +1,264 @@
+//! 镜像引用校验
+//!
+//! 请求中的镜像引用会被规范化后用作镜像存储的 key。这里在交给
+//! `oci_distribution` 解析前按 distribution 的引用语法逐段校验，并要求解析结果
+//! 能原样还原，拒绝非法字符、超长分段以及规范化后含义会改变的写法。
+
+use oci_distribution::Reference;
+use tonic::Status;
+
+/// 仓库名（含 registry）最大长度，与 distribution 的 NameTotalLengthMax 一致
+pub const MAX_REPOSITORY_LENGTH: usize = 255;
+/// tag 最大长度
+pub const MAX_TAG_LENGTH: usize = 128;
+/// registry 主机名最大长度
+pub const MAX_DOMAIN_LENGTH: usize = 253;
+
+fn invalid(reference: &str, reason: impl std::fmt::Display) -> Status {
+    Status::invalid_argument(format!(
+        "Invalid image reference {:?}: {}",
+        reference, reason
+    ))
+}
+
+/// 校验已规范化（带 registry）的镜像引用并解析
+pub fn parse_image_reference(reference: &str) -> Result<Reference, Status> {
+    if reference.is_empty() {
+        return Err(invalid(reference, "reference is empty"));
+    }
+    if let Some(c) = reference.chars().find(|c| !c.is_ascii_graphic()) {
+        return Err(invalid(reference, format!("invalid character {:?}", c)));
+    }
+
+    let (name_and_tag, digest) = match reference.split_once('@') {
+        Some((name, digest)) => (name, Some(digest)),
+        None => (reference, None),
+    };
+    let (name, tag) = match name_and_tag.rsplit_once(':') {
+        Some((name, tag)) if !tag.contains('/') => (name, Some(tag)),
+        _ => (name_and_tag, None),
+    };
+    validate_name(reference, name)?;
+    if let Some(tag) = tag {
+        validate_tag(reference, tag)?;
+    }
+    if let Some(digest) = digest {
+        validate_digest(reference, digest)?;
+    }
+
+    let parsed: Reference = reference.parse().map_err(|e| invalid(reference, e))?;
+    if parsed.whole() != reference {
+        return Err(invalid(
+            reference,
+            format!(
+                "reference is ambiguous and would be stored as {}",
+                parsed.whole()
+            ),
+        ));
+    }
+    Ok(parsed)
+}
+
+/// 拆分 `name:tag@digest`，返回按 digest 拉取和查找的 `name@digest` 与仅用于展示的 `name:tag`
+///
+/// 只有 tag 或只有 digest 的引用返回 `None`。
+pub fn split_tagged_digest(reference: &str) -> Option<(String, String)> {
+    let (name_and_tag, digest) = reference.split_once('@')?;
+    let (name, _) = name_and_tag
+        .rsplit_once(':')
+        .filter(|(_, tag)| !tag.contains('/'))?;
+    Some((format!("{}@{}", name, digest), name_and_tag.to_string()))
+}
+
+fn validate_name(reference: &str, name: &str) -> Result<(), Status> {
+    if name.len() > MAX_REPOSITORY_LENGTH {
+        return Err(invalid(
+            reference,
+            format!(
+                "repository name exceeds {} characters",
+                MAX_REPOSITORY_LENGTH
+            ),
+        ));
+    }
+    let Some((domain, path)) = name.split_once('/') else {
+        return Err(invalid(reference, "reference has no registry"));
+    };
+    validate_domain(reference, domain)?;
+    for component in path.split('/') {
+        if !is_path_component(component) {
+            return Err(invalid(
+                reference,
+                format!("invalid repository path component {:?}", component),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn validate_domain(reference: &str, domain: &str) -> Result<(), Status> {
+    let (host, port) = match domain.split_once(':') {
+        Some((host, port)) => (host, Some(port)),
+        None => (domain, None),
+    };
+    let valid_label = |label: &str| {
+        !label.is_empty()
+            && label.len() <= 63
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+    };
+    if host.is_empty() || host.len() > MAX_DOMAIN_LENGTH || !host.split('.').all(valid_label) {
+        return Err(invalid(reference, format!("invalid registry {:?}", domain)));
+    }
+    if let Some(port) = port {
+        if port.is_empty()
+            || port.len() > 5
+            || !port.chars().all(|c| c.is_ascii_digit())
+            || port
+                .parse::<u32>()
+                .map_or(true, |port| port == 0 || port > 65535)
+        {
+            return Err(invalid(
+                reference,
+                format!("invalid registry port {:?}", port),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// `[a-z0-9]+((\.|_|__|-+)[a-z0-9]+)*`
+fn is_path_component(component: &str) -> bool {
+    let bytes = component.as_bytes();
+    let alnum = |b: u8| b.is_ascii_lowercase() || b.is_ascii_digit();
+    if bytes.is_empty() || !alnum(bytes[0]) || !alnum(bytes[bytes.len() - 1]) {
+        return false;
+    }
+    let mut index = 0;
+    while index < bytes.len() {
+        let b = bytes[index];
+        if alnum(b) {
+            index += 1;
+            continue;
+        }
+        let start = index;
+        while index < bytes.len() && !alnum(bytes[index]) {
+            index += 1;
+        }
+        let separator = &component[start..index];
+        let valid = matches!(separator, "." | "_" | "__") || separator.bytes().all(|b| b == b'-');
+        if !valid {
+            return false;
+        }
+    }
+    true
+}
+
+fn validate_tag(reference: &str, tag: &str) -> Result<(), Status> {
+    let mut chars = tag.chars();
+    let valid = tag.len() <= MAX_TAG_LENGTH
+        && chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'));
+    if !valid {
+        return Err(invalid(reference, format!("invalid tag {:?}", tag)));
+    }
+    Ok(())
+}
+
+fn validate_digest(reference: &str, digest: &str) -> Result<(), Status> {
+    let Some((algorithm, hex)) = digest.split_once(':') else {
+        return Err(invalid(reference, format!("invalid digest {:?}", digest)));
+    };
+    let expected_len = match algorithm {
+        "sha256" => 64,
+        "sha384" => 96,
+        "sha512" => 128,
+        _ => {
+            return Err(invalid(
+                reference,
+                format!("unsupported digest algorithm {:?}", algorithm),
+            ))
+        }
+    };
+    if hex.len() != expected_len
+        || !hex
+            .chars()
+            .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+    {
+        return Err(invalid(reference, format!("invalid digest {:?}", digest)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_image_reference_accepts_canonical_forms() {
+        for reference in [
+            "docker.io/library/busybox:latest",
+            "registry.example.com:5000/team/app_name/web-ui:v1.2.3-rc_1",
+            "localhost:5000/a__b/c---d:latest",
+            &format!("quay.io/org/app@sha256:{}", "a".repeat(64)),
+            &format!("docker.io/library/busybox:1.36@sha256:{}", "a".repeat(64)),
+        ] {
+            let parsed = parse_image_reference(reference).unwrap();
+            assert_eq!(parsed.whole(), reference);
+        }
+    }
+
+    #[test]
+    fn tagged_digest_reference_pulls_by_digest_and_keeps_tag() {
+        let digest = format!("sha256:{}", "a".repeat(64));
+        let reference = format!("registry.example.com:5000/team/app:1.36@{}", digest);
+
+        let parsed = parse_image_reference(&reference).unwrap();
+        assert_eq!(parsed.tag(), Some("1.36"));
+        assert_eq!(parsed.digest(), Some(digest.as_str()));
+        assert_eq!(
+            split_tagged_digest(&reference),
+            Some((
+                format!("registry.example.com:5000/team/app@{}", digest),
+                "registry.example.com:5000/team/app:1.36".to_string(),
+            ))
+        );
+        assert_eq!(
+            split_tagged_digest(&format!("registry.example.com:5000/team/app@{}", digest)),
+            None
+        );
+        assert_eq!(
+            split_tagged_digest("registry.example.com:5000/team/app:1.36"),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_image_reference_rejects_malformed_or_ambiguous_input() {
+        let long_path = format!("docker.io/library/{}:latest", "a".repeat(250));
+        let long_tag = format!("docker.io/library/busybox:{}", "t".repeat(129));
+        for reference in [
+            "",
+            "docker.io/library/busy box:latest",
+            "docker.io/library/busybox:latest\n",
+            "docker.io/library/Busybox:latest",
+            "docker.io/library//busybox:latest",
+            "docker.io/library/busybox.:latest",
+            "docker.io/library/a...b:latest",
+            "docker.io/library/busybox:-bad",
+            "docker.io/library/busybox@sha256:abc",
+            "docker.io/library/busybox@md5:0123456789abcdef0123456789abcdef",
+            "-bad.example.com/app:latest",
+            "registry.example.com:99999/app:latest",
+            "index.docker.io/library/busybox:latest",
+            "docker.io/library/bü:latest",
+            long_path.as_str(),
+            long_tag.as_str(),
+        ] {
+            let err = parse_image_reference(reference).unwrap_err();
+            assert_eq!(err.code(), tonic::Code::InvalidArgument, "{:?}", reference);
+        }
+    }
+}
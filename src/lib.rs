@@ -3,8 +3,11 @@
 //! A Rust implementation of the Kubernetes Container Runtime Interface (CRI).
 
 pub mod attach;
+pub mod audit;
 pub mod cgroups;
+pub mod client;
 pub mod config;
+pub mod dump;
 pub mod error;
 pub mod image;
 pub mod metrics;
@@ -1,22 +1,30 @@
 use std::fs;
 use std::net::SocketAddr;
+use std::os::unix::fs::PermissionsExt;
 use std::os::unix::net::UnixListener;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use anyhow::Error;
+use anyhow::{Context, Error};
 use clap::Parser;
-use crius::config::Config;
+use crius::audit::AuditLogger;
+use crius::config::{Config, GrpcConfig};
 use crius::image::ImageServiceImpl;
 use crius::network::CniConfig;
 use crius::proto::runtime::v1::{
     image_service_server::ImageServiceServer, runtime_service_server::RuntimeServiceServer,
 };
-use crius::server::{RuntimeConfig, RuntimeServiceImpl};
+use crius::server::admin::AdminServiceServer;
+use crius::server::{
+    authorize_peers, limit_connections, server_builder, CatchPanicLayer, ReadOnlyLayer,
+    RuntimeConfig, RuntimeServiceImpl,
+};
+use crius::storage::overlay;
 use crius::streaming::StreamingServer;
 use tokio::net::UnixListener as TokioUnixListener;
+use tokio::sync::watch;
 use tokio_stream::wrappers::UnixListenerStream;
-use tonic::transport::Server;
+use tonic::transport::server::TcpIncoming;
 use tonic_reflection::server::Builder as ReflectionBuilder;
 use tracing::{debug, info};
 use tracing_subscriber::{fmt, EnvFilter};
@@ -45,6 +53,64 @@ struct Args {
     /// Listen address (IP:port or unix://path/to/socket)
     #[clap(long, default_value = "unix:///run/crius/crius.sock")]
     listen: String,
+
+    /// Additional read-only listen address for operator tooling; may be repeated
+    #[clap(long)]
+    read_only_listen: Vec<String>,
+
+    /// Serve every endpoint in read-only mode (mutating RPCs are rejected)
+    #[clap(long)]
+    read_only: bool,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Write the state of the running daemon to a JSON file for support bundles
+    Dump {
+        /// Output file
+        #[clap(short, long, default_value = "crius-dump.json")]
+        output: PathBuf,
+        /// Keep container environment variable values (redacted by default)
+        #[clap(long)]
+        include_env: bool,
+    },
+    /// Freeze a running container's cgroup for debugging
+    Pause {
+        /// Container ID or unique prefix
+        container_id: String,
+    },
+    /// Thaw a container frozen by `pause`
+    Resume {
+        /// Container ID or unique prefix
+        container_id: String,
+    },
+    /// Verify the image store and repair or remove corrupt images
+    Fsck {
+        /// Re-hash every layer file instead of only checking sizes
+        #[clap(long)]
+        full: bool,
+    },
+    /// Print node-level resource usage of all pods and the image store as JSON
+    Summary,
+    /// Export a container's current root filesystem as a tar archive
+    Export {
+        /// Container ID or unique prefix
+        container_id: String,
+        /// Output tar file
+        #[clap(short, long)]
+        output: PathBuf,
+    },
+}
+
+/// gRPC 监听端点
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ListenEndpoint {
+    address: String,
+    /// 只开放查询类方法
+    read_only: bool,
 }
 
 #[tokio::main]
@@ -54,6 +120,33 @@ async fn main() -> Result<(), Error> {
 
     // 解析命令行参数
     let args = Args::parse();
+    match &args.command {
+        Some(Command::Dump {
+            output,
+            include_env,
+        }) => return dump_state(&args, output, *include_env).await,
+        Some(Command::Pause { container_id }) => {
+            return set_container_paused(&args, container_id, true).await
+        }
+        Some(Command::Resume { container_id }) => {
+            return set_container_paused(&args, container_id, false).await
+        }
+        Some(Command::Export {
+            container_id,
+            output,
+        }) => return export_container(&args, container_id, output).await,
+        Some(Command::Fsck { full }) => return check_image_store(&args, *full).await,
+        Some(Command::Summary) => return node_summary(&args).await,
+        None => {}
+    }
+    let endpoints = listen_endpoints(&args.listen, &args.read_only_listen, args.read_only)?;
+
+    // crius 只回收自己启动的子进程；作为 PID 1 时被重新挂到它下面的孤儿进程无人回收
+    if nix::unistd::getpid().as_raw() == 1 {
+        log::warn!(
+            "crius is running as PID 1 and does not reap orphaned processes; run it under an init such as tini"
+        );
+    }
 
     let file_config = match Config::load(&args.config) {
         Ok(cfg) => cfg,
@@ -67,6 +160,13 @@ async fn main() -> Result<(), Error> {
         }
     };
 
+    // 在创建任何目录、文件前应用配置的 umask
+    if let Some(mask) = file_config.umask()? {
+        nix::sys::stat::umask(nix::sys::stat::Mode::from_bits_truncate(mask));
+        info!("Process umask set to {:04o}", mask);
+    }
+    let dir_mode = file_config.dir_mode()?;
+
     // 创建运行时配置
     let runtime_name = file_config.runtime.runtime_type.clone();
     let mut runtime_handlers: Vec<String> = std::env::var("CRIUS_RUNTIME_HANDLERS")
@@ -88,6 +188,8 @@ async fn main() -> Result<(), Error> {
 
     let runtime_config = RuntimeConfig {
         root_dir: PathBuf::from(&file_config.root),
+        state_dir: file_config.state_dir(),
+        image_root: file_config.image_root(),
         runtime: runtime_name,
         runtime_handlers,
         runtime_root: PathBuf::from(&file_config.runtime.root),
@@ -95,56 +197,349 @@ async fn main() -> Result<(), Error> {
         runtime_path: PathBuf::from(&file_config.runtime.runtime_path),
         pause_image: std::env::var("CRIUS_PAUSE_IMAGE")
             .unwrap_or_else(|_| "registry.k8s.io/pause:3.9".to_string()),
-        cni_config: CniConfig::from_env(),
+        cni_config: CniConfig::from_env().with_watch_interval(std::time::Duration::from_secs(
+            file_config.network.config_watch_interval_secs,
+        )),
+        process_defaults: file_config.runtime.process_defaults.clone(),
+        reconcile_interval: std::time::Duration::from_secs(
+            file_config.runtime.reconcile_interval_secs,
+        ),
+        env_file_dir: Some(file_config.runtime.env_file_dir.trim())
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from),
+        protected_paths: file_config.runtime.protected_paths.clone(),
+        mount_policy: file_config.runtime.mount_policy.clone(),
+        container_log: file_config.runtime.container_log.clone(),
+        expand_env_references: file_config.runtime.expand_env_references,
+        default_dns: file_config.runtime.default_dns.clone(),
+        dir_mode,
+        fs_group_max_entries: file_config.runtime.fs_group_max_entries,
+        pause_binary: Some(file_config.runtime.pause_binary.trim())
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from),
+        shm_size: file_config.runtime.shm_size()?,
+        prepared_rootfs_dir: Some(file_config.runtime.prepared_rootfs_dir.trim())
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from),
+        default_env: file_config.runtime.default_env()?,
+        cgroup_root: Some(file_config.runtime.cgroup_root.trim())
+            .filter(|root| !root.is_empty())
+            .map(str::to_string),
+        cgroup_mount: PathBuf::from("/sys/fs/cgroup"),
+        sysfs_root: PathBuf::from("/sys"),
+        remove_grace_period_secs: file_config.runtime.remove_grace_period_secs,
+        kill_timeout: std::time::Duration::from_secs(file_config.runtime.kill_timeout_secs),
+        image_health_registry: Some(file_config.image.health_check_registry.trim())
+            .filter(|registry| !registry.is_empty())
+            .map(str::to_string),
+        image_health_interval: std::time::Duration::from_secs(
+            file_config.image.health_check_interval_secs,
+        ),
+        rootless: file_config.rootless.rootless_config()?,
+        stats: file_config.stats.clone(),
     };
 
+    // 启动前校验配置，一次性报告全部问题
+    runtime_config.validate()?;
+
     // 创建服务实例
-    let runtime_service =
+    let mut runtime_service =
         RuntimeServiceImpl::new_with_nri_config(runtime_config.clone(), file_config.nri.clone());
-    let streaming_server =
-        StreamingServer::start("127.0.0.1:0", runtime_config.runtime_path.clone()).await?;
+    let audit_logger = if file_config.audit_log.trim().is_empty() {
+        None
+    } else {
+        let logger = AuditLogger::open(file_config.audit_log.trim()).map_err(|e| {
+            anyhow::anyhow!("Failed to open audit log {}: {}", file_config.audit_log, e)
+        })?;
+        info!("Audit log enabled at {}", logger.path().display());
+        Some(Arc::new(logger))
+    };
+    if let Some(logger) = &audit_logger {
+        runtime_service.set_audit_logger(logger.clone());
+    }
+    let streaming_server = StreamingServer::start(
+        "127.0.0.1:0",
+        runtime_config.runtime_path.clone(),
+        &file_config.streaming,
+    )
+    .await?;
     runtime_service
         .set_streaming_server(streaming_server.clone())
         .await;
 
     prepare_runtime_service(&runtime_service).await;
+    let _state_reconciler = runtime_service.start_state_reconciler();
+    let _container_log_mirror = runtime_service.start_container_log_mirror();
+    let _cni_config_watcher = runtime_service.start_cni_config_watcher().await;
     let shutdown_nri = runtime_service.nri_handle();
-    let image_service = ImageServiceImpl::new(runtime_config.root_dir.join("storage"))?;
-    let reflection_service = ReflectionBuilder::configure()
-        .register_encoded_file_descriptor_set(include_bytes!(concat!(
-            env!("OUT_DIR"),
-            "/file_descriptor_set.bin"
-        )))
-        .build()
-        .map_err(|e| anyhow::anyhow!("Failed to create reflection service: {}", e))?;
-
+    let mut image_service = ImageServiceImpl::new(&runtime_config.image_root)?;
+    image_service.set_database_path(runtime_config.root_dir.join("crius.db"));
+    image_service.set_download_dir(file_config.image_tmp_dir());
+    image_service.set_fsync(file_config.image.fsync);
+    image_service.set_max_store_bytes(file_config.image.max_store_bytes);
+    image_service.set_manifest_media_types(file_config.image.manifest_media_types.clone());
+    image_service.set_refresh_tags(file_config.image.refresh_tags);
+    if let Some(logger) = audit_logger {
+        image_service.set_audit_logger(logger);
+    }
+    // 探测内核 overlay 能力并写入启动日志，rootfs 仍逐层解包，探测结果只用于排查；
+    // rootless 模式下默认的 overlay 按 fuse-overlayfs 探测
+    let storage_driver = if runtime_config.rootless.is_some()
+        && file_config.image.driver == overlay::DRIVER_OVERLAY
+    {
+        overlay::DRIVER_FUSE_OVERLAYFS
+    } else {
+        file_config.image.driver.as_str()
+    };
+    if let Err(err) = overlay::detect_backend(storage_driver, &runtime_config.image_root) {
+        log::warn!("Storage backend probe failed: {:#}", err);
+    }
     // 加载本地镜像
     info!("About to load local images...");
     match image_service.load_local_images().await {
         Ok(_) => info!("Local images loaded successfully"),
         Err(e) => log::error!("Failed to load local images: {}", e),
     }
+    // 依赖未就绪时不开始监听，超时后以非零状态退出
+    runtime_service.probe_cgroup_controllers();
+    runtime_service
+        .wait_for_startup_checks(&file_config.startup, &image_service)
+        .await?;
+    runtime_service.probe_runtime_handler_features();
 
-    // 创建gRPC服务器
-    info!("Starting crius gRPC server on {}", args.listen);
+    // 创建gRPC服务器，各监听端点共享同一组服务实例
     debug!("Using configuration: {:?}", runtime_config);
     info!(
         "Streaming server listening on {}",
         streaming_server.base_url()
     );
 
-    let server = Server::builder()
-        .add_service(RuntimeServiceServer::new(runtime_service))
-        .add_service(ImageServiceServer::new(image_service))
+    let runtime_service = Arc::new(runtime_service);
+    let image_service = Arc::new(image_service);
+    if !file_config.image.fsck_on_start.is_empty() {
+        let mode = file_config
+            .image
+            .fsck_on_start
+            .parse::<crius::image::FsckMode>()
+            .map_err(|e| anyhow::anyhow!(e))?;
+        let image_service = image_service.clone();
+        tokio::spawn(async move {
+            image_service.fsck(mode).await;
+        });
+    }
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        let _ = shutdown_tx.send(true);
+    });
+    let serve_result = futures::future::try_join_all(endpoints.into_iter().map(|endpoint| {
+        serve_endpoint(
+            endpoint,
+            runtime_service.clone(),
+            image_service.clone(),
+            file_config.grpc.clone(),
+            dir_mode,
+            shutdown_rx.clone(),
+        )
+    }))
+    .await;
+    shutdown_runtime_service(shutdown_nri).await;
+    serve_result?;
+
+    Ok(())
+}
+
+/// 解析监听端点：`listen` 为完整 CRI 端点（为空表示不启用），`read_only` 为只读端点；
+/// `all_read_only` 为 true 时所有端点都以只读模式提供服务
+fn listen_endpoints(
+    listen: &str,
+    read_only: &[String],
+    all_read_only: bool,
+) -> Result<Vec<ListenEndpoint>, Error> {
+    let listen = listen.trim();
+    let endpoints: Vec<ListenEndpoint> = (!listen.is_empty())
+        .then(|| ListenEndpoint {
+            address: listen.to_string(),
+            read_only: all_read_only,
+        })
+        .into_iter()
+        .chain(read_only.iter().map(|address| ListenEndpoint {
+            address: address.trim().to_string(),
+            read_only: true,
+        }))
+        .collect();
+    if endpoints.is_empty() {
+        anyhow::bail!("no listen endpoint configured");
+    }
+    for (index, endpoint) in endpoints.iter().enumerate() {
+        match endpoint.address.strip_prefix("unix://") {
+            Some("") => anyhow::bail!("invalid listen address {:?}", endpoint.address),
+            Some(_) => {}
+            None => {
+                endpoint.address.parse::<SocketAddr>().map_err(|e| {
+                    anyhow::anyhow!("invalid listen address {:?}: {}", endpoint.address, e)
+                })?;
+            }
+        }
+        if endpoints[..index]
+            .iter()
+            .any(|other| other.address == endpoint.address)
+        {
+            anyhow::bail!("listen address {} is configured twice", endpoint.address);
+        }
+    }
+    Ok(endpoints)
+}
+
+/// 连接运行中的守护进程，把状态转储到 `output`
+async fn dump_state(args: &Args, output: &Path, include_env: bool) -> Result<(), Error> {
+    if !args.listen.starts_with("unix://") {
+        anyhow::bail!(
+            "dump requires a unix socket listen address, got {}",
+            args.listen
+        );
+    }
+    let config = Config::load(&args.config)
+        .map_err(|err| {
+            info!(
+                "Failed to load config from {}: {}. Dumping without it.",
+                args.config.display(),
+                err
+            )
+        })
+        .ok();
+    let mut client = crius::client::CriusClient::connect(&args.listen).await?;
+    let dump = crius::dump::collect(
+        &mut client,
+        config
+            .as_ref()
+            .map(|config| (args.config.as_path(), config)),
+        include_env,
+    )
+    .await?;
+    crius::dump::write(output, &dump)?;
+    println!("{}", output.display());
+    Ok(())
+}
+
+/// 通过管理服务暂停或恢复容器
+async fn set_container_paused(args: &Args, container_id: &str, paused: bool) -> Result<(), Error> {
+    if !args.listen.starts_with("unix://") {
+        anyhow::bail!(
+            "pause/resume requires a unix socket listen address, got {}",
+            args.listen
+        );
+    }
+    let mut client = crius::client::CriusClient::connect(&args.listen).await?;
+    if paused {
+        client.pause_container(container_id).await?;
+    } else {
+        client.resume_container(container_id).await?;
+    }
+    println!("{}", container_id);
+    Ok(())
+}
+
+/// 通过管理服务校验镜像存储，以 JSON 输出汇总
+async fn check_image_store(args: &Args, full: bool) -> Result<(), Error> {
+    if !args.listen.starts_with("unix://") {
+        anyhow::bail!(
+            "fsck requires a unix socket listen address, got {}",
+            args.listen
+        );
+    }
+    let mut client = crius::client::CriusClient::connect(&args.listen).await?;
+    let report = client.check_image_store(full).await?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+/// 通过管理服务获取节点资源汇总，以 JSON 输出
+async fn node_summary(args: &Args) -> Result<(), Error> {
+    if !args.listen.starts_with("unix://") {
+        anyhow::bail!(
+            "summary requires a unix socket listen address, got {}",
+            args.listen
+        );
+    }
+    let mut client = crius::client::CriusClient::connect(&args.listen).await?;
+    let summary = client.node_summary().await?;
+    println!("{}", serde_json::to_string_pretty(&summary.to_json())?);
+    Ok(())
+}
+
+/// 通过管理服务把容器 rootfs 导出为 tar 文件
+async fn export_container(args: &Args, container_id: &str, output: &Path) -> Result<(), Error> {
+    if !args.listen.starts_with("unix://") {
+        anyhow::bail!(
+            "export requires a unix socket listen address, got {}",
+            args.listen
+        );
+    }
+    let mut client = crius::client::CriusClient::connect(&args.listen).await?;
+    let mut file = std::fs::File::create(output)
+        .with_context(|| format!("Failed to create {}", output.display()))?;
+    client.export_container(container_id, &mut file).await?;
+    file.sync_all()?;
+    println!("{}", output.display());
+    Ok(())
+}
+
+/// 在一个端点上提供 CRI 服务，直到收到关闭信号
+async fn serve_endpoint(
+    endpoint: ListenEndpoint,
+    runtime_service: Arc<RuntimeServiceImpl>,
+    image_service: Arc<ImageServiceImpl>,
+    grpc: GrpcConfig,
+    dir_mode: u32,
+    mut shutdown: watch::Receiver<bool>,
+) -> Result<(), Error> {
+    let reflection_service = ReflectionBuilder::configure()
+        .register_encoded_file_descriptor_set(include_bytes!(concat!(
+            env!("OUT_DIR"),
+            "/file_descriptor_set.bin"
+        )))
+        .build()
+        .map_err(|e| anyhow::anyhow!("Failed to create reflection service: {}", e))?;
+    info!(
+        "Starting crius gRPC server on {}{}",
+        endpoint.address,
+        if endpoint.read_only {
+            " (read-only)"
+        } else {
+            ""
+        }
+    );
+
+    let server = server_builder(&grpc)
+        .layer(CatchPanicLayer)
+        .layer(ReadOnlyLayer::new(endpoint.read_only))
+        .add_service(AdminServiceServer::new(
+            runtime_service.clone(),
+            image_service.clone(),
+        ))
+        .add_service(RuntimeServiceServer::from_arc(runtime_service))
+        .add_service(ImageServiceServer::from_arc(image_service))
         .add_service(reflection_service);
+    let shutdown = async move {
+        let _ = shutdown.wait_for(|stop| *stop).await;
+    };
 
-    if args.listen.starts_with("unix://") {
+    if let Some(socket_path) = endpoint.address.strip_prefix("unix://") {
         // Unix domain socket
-        let socket_path = args.listen.trim_start_matches("unix://");
         let path = Path::new(socket_path);
 
         if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
+            // 新建的父目录不对其他用户开放，避免绕过 socket 自身的权限
+            crius::utils::create_dir_all_with_mode(parent, dir_mode)?;
+            let mode = fs::metadata(parent)?.permissions().mode();
+            if mode & 0o002 != 0 && mode & 0o1000 == 0 {
+                log::warn!(
+                    "socket directory {} is world-writable; other users can replace {}",
+                    parent.display(),
+                    path.display()
+                );
+            }
         }
 
         // 清理旧socket文件
@@ -154,17 +549,27 @@ async fn main() -> Result<(), Error> {
         let uds = UnixListener::bind(path)?;
         let uds_stream = UnixListenerStream::new(TokioUnixListener::from_std(uds)?);
 
-        // 启动服务
-        let serve_result = server
-            .serve_with_incoming_shutdown(uds_stream, shutdown_signal())
-            .await;
-        shutdown_runtime_service(shutdown_nri).await;
-        serve_result?;
+        server
+            .serve_with_incoming_shutdown(
+                limit_connections(authorize_peers(uds_stream, &grpc), grpc.max_connections),
+                shutdown,
+            )
+            .await?;
     } else {
-        let addr: SocketAddr = args.listen.parse()?;
-        let serve_result = server.serve_with_shutdown(addr, shutdown_signal()).await;
-        shutdown_runtime_service(shutdown_nri).await;
-        serve_result?;
+        let addr: SocketAddr = endpoint.address.parse()?;
+        if !grpc.allowed_uids.is_empty() || !grpc.allowed_gids.is_empty() {
+            log::warn!(
+                "allowed_uids/allowed_gids only apply to unix sockets; TCP endpoint {} accepts any peer",
+                endpoint.address
+            );
+        }
+        let incoming = TcpIncoming::new(addr, false, None).map_err(|e| anyhow::anyhow!(e))?;
+        server
+            .serve_with_incoming_shutdown(
+                limit_connections(incoming, grpc.max_connections),
+                shutdown,
+            )
+            .await?;
     }
 
     Ok(())
@@ -234,6 +639,7 @@ impl tracing_subscriber::fmt::time::FormatTime for LocalLogTimer {
 
 #[cfg(test)]
 mod tests {
+    use super::listen_endpoints;
     use super::prepare_runtime_service;
     use super::shutdown_runtime_service;
     use super::LocalLogTimer;
@@ -273,6 +679,8 @@ mod tests {
 
     fn test_runtime_config(root_dir: PathBuf) -> RuntimeConfig {
         RuntimeConfig {
+            state_dir: root_dir.clone(),
+            image_root: root_dir.join("storage"),
             root_dir,
             runtime: "runc".to_string(),
             runtime_handlers: vec!["runc".to_string()],
@@ -281,6 +689,29 @@ mod tests {
             runtime_path: PathBuf::from("/definitely/missing/runc"),
             pause_image: "registry.k8s.io/pause:3.9".to_string(),
             cni_config: CniConfig::default(),
+            process_defaults: Default::default(),
+            reconcile_interval: std::time::Duration::ZERO,
+            env_file_dir: None,
+            protected_paths: Default::default(),
+            mount_policy: Default::default(),
+            container_log: Default::default(),
+            expand_env_references: false,
+            default_dns: Default::default(),
+            dir_mode: crius::utils::DEFAULT_DIR_MODE,
+            fs_group_max_entries: crius::storage::ownership::DEFAULT_FS_GROUP_MAX_ENTRIES,
+            pause_binary: None,
+            shm_size: crius::oci::spec::DEFAULT_SHM_SIZE,
+            prepared_rootfs_dir: None,
+            default_env: Vec::new(),
+            cgroup_root: None,
+            cgroup_mount: PathBuf::from("/sys/fs/cgroup"),
+            sysfs_root: PathBuf::from("/sys"),
+            remove_grace_period_secs: 30,
+            kill_timeout: std::time::Duration::from_secs(10),
+            image_health_registry: None,
+            image_health_interval: std::time::Duration::from_secs(60),
+            rootless: None,
+            stats: Default::default(),
         }
     }
 
@@ -330,4 +761,34 @@ mod tests {
 
         assert_eq!(fake_nri.calls.lock().await.clone(), vec!["shutdown"]);
     }
+
+    #[test]
+    fn listen_endpoints_require_at_least_one_valid_unique_address() {
+        let endpoints = listen_endpoints(
+            "unix:///run/crius/crius.sock",
+            &["unix:///run/crius/crius-ro.sock".to_string()],
+            false,
+        )
+        .unwrap();
+        assert_eq!(endpoints.len(), 2);
+        assert!(!endpoints[0].read_only);
+        assert!(endpoints[1].read_only);
+
+        let read_only_only = listen_endpoints("", &["127.0.0.1:10010".to_string()], false).unwrap();
+        assert_eq!(read_only_only.len(), 1);
+        assert!(read_only_only[0].read_only);
+
+        let all_read_only = listen_endpoints("unix:///run/crius/crius.sock", &[], true).unwrap();
+        assert!(all_read_only[0].read_only);
+
+        assert!(listen_endpoints("", &[], false).is_err());
+        assert!(listen_endpoints("unix://", &[], false).is_err());
+        assert!(listen_endpoints("not-an-address", &[], false).is_err());
+        assert!(listen_endpoints(
+            "unix:///run/crius/crius.sock",
+            &["unix:///run/crius/crius.sock".to_string()],
+            false,
+        )
+        .is_err());
+    }
 }
@@ -0,0 +1,230 @@
+//! 镜像拉取、sandbox 与容器创建流程的分阶段耗时
+//!
+//! 各阶段耗时累计到进程级直方图，由 verbose `Status`（`crictl info`）输出；
+//! 单次操作的耗时另随容器/Pod 状态保存，用于区分镜像解包慢还是 runc 慢。
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// 直方图各桶的上限（秒），覆盖毫秒级的 spec 生成到分钟级的镜像拉取
+pub const DURATION_BUCKETS_SECS: [f64; 14] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0,
+];
+
+/// 被计时的 CRI 操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    PullImage,
+    RunPodSandbox,
+    CreateContainer,
+    StartContainer,
+}
+
+/// 操作中的阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    /// 拉取镜像（manifest 与层下载、解包）
+    ImagePull,
+    /// 从镜像准备容器 rootfs
+    Snapshot,
+    /// 生成 OCI spec（含 NRI 调整）并写入 bundle
+    Spec,
+    /// 创建网络命名空间并执行 CNI
+    Network,
+    /// runtime 创建容器（准备 rootfs、spec 与 bundle）
+    RuntimeCreate,
+    /// runtime 启动容器，shim 模式下包含 runc create 与 start
+    RuntimeStart,
+    /// 整个操作
+    Total,
+}
+
+impl Phase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Phase::ImagePull => "image_pull",
+            Phase::Snapshot => "snapshot",
+            Phase::Spec => "spec",
+            Phase::Network => "network",
+            Phase::RuntimeCreate => "runtime_create",
+            Phase::RuntimeStart => "runtime_start",
+            Phase::Total => "total",
+        }
+    }
+}
+
+/// 耗时直方图，桶计数为累计值（不大于桶上限的观测数）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Histogram {
+    /// 与 `DURATION_BUCKETS_SECS` 一一对应
+    pub buckets: Vec<u64>,
+    pub count: u64,
+    pub sum_secs: f64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            buckets: vec![0; DURATION_BUCKETS_SECS.len()],
+            count: 0,
+            sum_secs: 0.0,
+        }
+    }
+}
+
+impl Histogram {
+    pub fn observe(&mut self, duration: Duration) {
+        let secs = duration.as_secs_f64();
+        for (bucket, le) in self.buckets.iter_mut().zip(DURATION_BUCKETS_SECS) {
+            if secs <= le {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum_secs += secs;
+    }
+}
+
+/// 按 (操作, 阶段) 汇总的耗时直方图
+#[derive(Debug, Default)]
+pub struct CreationMetrics {
+    histograms: Mutex<BTreeMap<(Operation, Phase), Histogram>>,
+}
+
+impl CreationMetrics {
+    pub fn observe(&self, operation: Operation, phase: Phase, duration: Duration) {
+        let mut histograms = self
+            .histograms
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        histograms
+            .entry((operation, phase))
+            .or_default()
+            .observe(duration);
+    }
+
+    pub fn histogram(&self, operation: Operation, phase: Phase) -> Option<Histogram> {
+        self.histograms
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(&(operation, phase))
+            .cloned()
+    }
+
+    /// 以 `{操作: {阶段: 直方图}}` 形式输出，附带桶上限
+    pub fn snapshot(&self) -> serde_json::Value {
+        let histograms = self
+            .histograms
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut operations: BTreeMap<Operation, BTreeMap<&'static str, Histogram>> =
+            BTreeMap::new();
+        for ((operation, phase), histogram) in histograms.iter() {
+            operations
+                .entry(*operation)
+                .or_default()
+                .insert(phase.as_str(), histogram.clone());
+        }
+        serde_json::json!({
+            "bucketsSecs": DURATION_BUCKETS_SECS,
+            "operations": operations,
+        })
+    }
+}
+
+/// 进程级的创建耗时直方图
+pub fn creation_metrics() -> &'static CreationMetrics {
+    static METRICS: OnceLock<CreationMetrics> = OnceLock::new();
+    METRICS.get_or_init(CreationMetrics::default)
+}
+
+/// 一次操作的分阶段计时，每个阶段结束时即写入直方图
+#[derive(Debug)]
+pub struct OperationTimer {
+    operation: Operation,
+    started: Instant,
+    mark: Instant,
+    phases: BTreeMap<Phase, Duration>,
+}
+
+impl OperationTimer {
+    pub fn start(operation: Operation) -> Self {
+        let now = Instant::now();
+        Self {
+            operation,
+            started: now,
+            mark: now,
+            phases: BTreeMap::new(),
+        }
+    }
+
+    /// 结束自上次标记以来的阶段
+    pub fn record(&mut self, phase: Phase) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.mark);
+        self.mark = now;
+        *self.phases.entry(phase).or_default() += elapsed;
+        creation_metrics().observe(self.operation, phase, elapsed);
+    }
+
+    /// 开始新的区间，此前未记录的耗时只计入总耗时
+    pub fn mark(&mut self) {
+        self.mark = Instant::now();
+    }
+
+    /// 记录总耗时，返回各阶段耗时（秒）
+    pub fn finish(mut self) -> BTreeMap<String, f64> {
+        let total = self.started.elapsed();
+        self.phases.insert(Phase::Total, total);
+        creation_metrics().observe(self.operation, Phase::Total, total);
+        self.phases
+            .into_iter()
+            .map(|(phase, duration)| (phase.as_str().to_string(), duration.as_secs_f64()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_buckets_are_cumulative() {
+        let mut histogram = Histogram::default();
+        histogram.observe(Duration::from_millis(20));
+        histogram.observe(Duration::from_secs(3));
+
+        assert_eq!(histogram.count, 2);
+        assert!((histogram.sum_secs - 3.02).abs() < 1e-9);
+        // 0.025 秒桶只包含第一次观测，10 秒桶包含两次
+        assert_eq!(histogram.buckets[2], 1);
+        assert_eq!(histogram.buckets[10], 2);
+        assert_eq!(histogram.buckets[0], 0);
+    }
+
+    #[test]
+    fn timer_accumulates_phases_and_total() {
+        let mut timer = OperationTimer::start(Operation::StartContainer);
+        timer.record(Phase::RuntimeStart);
+        timer.mark();
+        timer.record(Phase::RuntimeStart);
+        let timings = timer.finish();
+
+        assert_eq!(
+            timings.keys().map(String::as_str).collect::<Vec<_>>(),
+            vec!["runtime_start", "total"]
+        );
+        assert!(timings["total"] >= timings["runtime_start"]);
+        assert!(
+            creation_metrics()
+                .histogram(Operation::StartContainer, Phase::RuntimeStart)
+                .unwrap()
+                .count
+                >= 2
+        );
+    }
+}
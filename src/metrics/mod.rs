@@ -6,6 +6,7 @@
 //! - 块IO统计
 //! - 网络IO统计
 //! - 进程数统计
+//! - 镜像拉取与容器/sandbox 创建的分阶段耗时
 
 use anyhow::{Context, Result};
 use log::debug;
@@ -13,6 +14,11 @@ use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
 
+pub mod creation;
+pub use creation::{
+    creation_metrics, CreationMetrics, Histogram, Operation, OperationTimer, Phase,
+};
+
 /// 容器性能统计
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ContainerStats {
@@ -51,6 +57,9 @@ pub struct CpuStats {
     pub system_usage: u64,
 }
 
+/// cgroup v1 未设置内存限制时读到的值
+const V1_UNLIMITED_MEMORY: u64 = 9223372036854771712;
+
 /// 内存统计
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct MemoryStats {
@@ -66,6 +75,9 @@ pub struct MemoryStats {
     pub rss: u64,
     /// Swap使用量（字节）
     pub swap: u64,
+    /// Swap限制（字节，不含内存），不限制或无法读取时为 None
+    #[serde(default)]
+    pub swap_limit: Option<u64>,
     /// Page faults
     pub pgfault: u64,
     /// Major page faults
@@ -490,6 +502,9 @@ impl MetricsCollector {
         } else {
             0
         };
+        let swap_limit = fs::read_to_string(cgroup_path.join("memory.swap.max"))
+            .ok()
+            .and_then(|value| value.trim().parse::<u64>().ok());
 
         Ok(MemoryStats {
             usage,
@@ -498,6 +513,7 @@ impl MetricsCollector {
             cache,
             rss,
             swap,
+            swap_limit,
             pgfault,
             pgmajfault,
             kernel_usage: 0,
@@ -514,11 +530,12 @@ impl MetricsCollector {
         // memory.limit_in_bytes
         let limit_path = cgroup_path.join("memory.limit_in_bytes");
         let limit_str = fs::read_to_string(&limit_path)?;
-        let limit = if limit_str.trim() == "max" || limit_str.trim() == "9223372036854771712" {
-            u64::MAX
-        } else {
-            limit_str.trim().parse::<u64>()?
-        };
+        let limit =
+            if limit_str.trim() == "max" || limit_str.trim() == V1_UNLIMITED_MEMORY.to_string() {
+                u64::MAX
+            } else {
+                limit_str.trim().parse::<u64>()?
+            };
 
         // memory.max_usage_in_bytes
         let max_usage_path = cgroup_path.join("memory.max_usage_in_bytes");
@@ -549,6 +566,14 @@ impl MetricsCollector {
             }
         }
 
+        // v1 的 memsw 限制为 memory+swap 总量，扣除内存限制得到 swap 限制
+        let swap_limit = fs::read_to_string(cgroup_path.join("memory.memsw.limit_in_bytes"))
+            .ok()
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .filter(|_| limit != u64::MAX)
+            .filter(|memsw| *memsw < V1_UNLIMITED_MEMORY)
+            .map(|memsw| memsw.saturating_sub(limit));
+
         Ok(MemoryStats {
             usage,
             max_usage,
@@ -556,6 +581,7 @@ impl MetricsCollector {
             cache,
             rss,
             swap,
+            swap_limit,
             pgfault,
             pgmajfault,
             kernel_usage: 0,
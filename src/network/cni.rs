@@ -2,8 +2,10 @@ use super::*;
 use anyhow::{Context, Result};
 use log::{debug, error, info};
 use serde_json::{json, Value};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 use tokio::process::Command;
 
 /// CNI网络配置
@@ -19,8 +21,11 @@ pub struct CniNetworkConfig {
     pub config: Value,
 }
 
+/// 配置目录内候选文件的 (路径, 修改时间, 大小)，用于判断配置是否变化
+type ConfigFingerprint = Vec<(PathBuf, Option<SystemTime>, u64)>;
+
 /// CNI插件管理器
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CniManager {
     /// CNI插件目录
     plugin_dirs: Vec<PathBuf>,
@@ -32,6 +37,8 @@ pub struct CniManager {
     network_configs: std::collections::HashMap<String, CniNetworkConfig>,
     /// 默认使用的网络配置名称
     default_network_name: Option<String>,
+    /// 上次加载时配置目录的状态
+    loaded_fingerprint: ConfigFingerprint,
 }
 
 impl CniManager {
@@ -84,6 +91,7 @@ impl CniManager {
             cache_dir: PathBuf::from(cache_dir),
             network_configs: std::collections::HashMap::new(),
             default_network_name: None,
+            loaded_fingerprint: Vec::new(),
         })
     }
 
@@ -103,8 +111,7 @@ impl CniManager {
     }
 
     async fn write_cached_result(&self, pod_id: &str, result: &Value) -> Result<()> {
-        tokio::fs::create_dir_all(&self.cache_dir)
-            .await
+        crate::utils::create_dir_all_with_mode(&self.cache_dir, crate::utils::DEFAULT_DIR_MODE)
             .context("Failed to create CNI cache directory")?;
         tokio::fs::write(
             self.cache_result_path(pod_id),
@@ -125,10 +132,104 @@ impl CniManager {
         let _ = tokio::fs::remove_file(self.cache_result_path(pod_id)).await;
     }
 
+    fn is_config_file(path: &Path) -> bool {
+        let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        file_name.ends_with(".conf")
+            || file_name.ends_with(".json")
+            || file_name.ends_with(".conflist")
+    }
+
+    fn config_fingerprint(config_dirs: &[PathBuf]) -> ConfigFingerprint {
+        let mut fingerprint = Vec::new();
+        for config_dir in config_dirs {
+            let Ok(entries) = std::fs::read_dir(config_dir) else {
+                continue;
+            };
+            for path in entries.flatten().map(|entry| entry.path()) {
+                if !Self::is_config_file(&path) {
+                    continue;
+                }
+                let metadata = std::fs::metadata(&path).ok();
+                fingerprint.push((
+                    path,
+                    metadata.as_ref().and_then(|m| m.modified().ok()),
+                    metadata.map(|m| m.len()).unwrap_or_default(),
+                ));
+            }
+        }
+        fingerprint.sort();
+        fingerprint
+    }
+
+    /// 已加载的网络名称（排序后）
+    pub fn network_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.network_configs.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// 新建 Pod 使用的默认网络名称
+    pub fn default_network_name(&self) -> Option<&str> {
+        self.default_network_name.as_deref()
+    }
+
+    /// 轮询配置目录，配置文件新增、修改或删除后重新加载网络配置
+    ///
+    /// 发现变化后等到下一次采样结果不变才重载，避免读到写了一半的文件；
+    /// 重载在副本上完成后再替换，不阻塞并发的 Pod 网络操作。
+    pub fn watch_configs(
+        manager: Arc<tokio::sync::RwLock<CniManager>>,
+        interval: Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let (config_dirs, mut loaded) = {
+                let manager = manager.read().await;
+                (
+                    manager.config_dirs.clone(),
+                    manager.loaded_fingerprint.clone(),
+                )
+            };
+            let mut pending: Option<ConfigFingerprint> = None;
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let current = Self::config_fingerprint(&config_dirs);
+                if current == loaded {
+                    pending = None;
+                    continue;
+                }
+                if pending.as_ref() != Some(&current) {
+                    pending = Some(current);
+                    continue;
+                }
+                pending = None;
+
+                let mut reloaded = manager.read().await.clone();
+                if let Err(e) = reloaded.load_network_configs().await {
+                    error!("Failed to reload CNI configs: {}", e);
+                    continue;
+                }
+                loaded = reloaded.loaded_fingerprint.clone();
+                info!(
+                    "Reloaded CNI configs: networks {:?}, default {:?}",
+                    reloaded.network_names(),
+                    reloaded.default_network_name()
+                );
+                let mut manager = manager.write().await;
+                manager.network_configs = reloaded.network_configs;
+                manager.default_network_name = reloaded.default_network_name;
+                manager.loaded_fingerprint = reloaded.loaded_fingerprint;
+            }
+        })
+    }
+
     /// 加载网络配置
     pub async fn load_network_configs(&mut self) -> Result<()> {
         self.network_configs.clear();
         self.default_network_name = None;
+        self.loaded_fingerprint = Self::config_fingerprint(&self.config_dirs);
 
         for config_dir in &self.config_dirs {
             if !config_dir.exists() {
@@ -143,12 +244,7 @@ impl CniManager {
 
             while let Some(entry) = entries.next_entry().await? {
                 let path = entry.path();
-                let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-
-                if file_name.ends_with(".conf")
-                    || file_name.ends_with(".json")
-                    || file_name.ends_with(".conflist")
-                {
+                if Self::is_config_file(&path) {
                     candidate_paths.push(path);
                 }
             }
@@ -240,7 +336,8 @@ impl CniManager {
                 pod_cidr,
             )
             .await?;
-        let network_status = self.parse_cni_result(result.as_ref())?;
+        let mut network_status = self.parse_cni_result(result.as_ref())?;
+        network_status.config = Some(config.config.clone());
 
         info!("Pod {} network setup completed", pod_id);
         Ok(network_status)
@@ -472,9 +569,7 @@ impl CniManager {
         let Some(value) = result else {
             return Ok(NetworkStatus {
                 name: "crius-net".to_string(),
-                ip: None,
-                mac: None,
-                interfaces: vec![],
+                ..Default::default()
             });
         };
 
@@ -498,8 +593,8 @@ impl CniManager {
         Ok(NetworkStatus {
             name: "crius-net".to_string(),
             ip,
-            mac: None,
-            interfaces: vec![],
+            cni_result: Some(value.clone()),
+            ..Default::default()
         })
     }
 }
@@ -546,6 +641,52 @@ mod tests {
         assert_eq!(manager.default_network_name.as_deref(), Some("test-net"));
     }
 
+    #[tokio::test]
+    async fn watch_configs_reloads_networks_added_and_removed_after_startup() {
+        let dir = tempdir().unwrap();
+        let config_dir = dir.path().join("net.d");
+        tokio::fs::create_dir_all(&config_dir).await.unwrap();
+
+        let mut manager = CniManager::new(
+            vec![dir.path().join("bin").display().to_string()],
+            vec![config_dir.display().to_string()],
+            dir.path().join("cache").display().to_string(),
+        )
+        .unwrap();
+        manager.load_network_configs().await.unwrap();
+        assert!(manager.network_names().is_empty());
+
+        let manager = Arc::new(tokio::sync::RwLock::new(manager));
+        let watcher = CniManager::watch_configs(manager.clone(), Duration::from_millis(20));
+        let wait_for = |expected: Option<&'static str>| {
+            let manager = manager.clone();
+            async move {
+                for _ in 0..200 {
+                    if manager.read().await.default_network_name() == expected {
+                        return;
+                    }
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+                panic!("CNI configs were not reloaded to {:?}", expected);
+            }
+        };
+
+        let config_path = config_dir.join("10-calico.conflist");
+        tokio::fs::write(
+            &config_path,
+            r#"{"cniVersion":"0.4.0","name":"calico","plugins":[{"type":"calico"}]}"#,
+        )
+        .await
+        .unwrap();
+        wait_for(Some("calico")).await;
+        assert_eq!(manager.read().await.network_names(), vec!["calico"]);
+
+        tokio::fs::remove_file(&config_path).await.unwrap();
+        wait_for(None).await;
+        assert!(manager.read().await.network_names().is_empty());
+        watcher.abort();
+    }
+
     #[test]
     fn primary_plugin_type_prefers_type_then_first_plugin() {
         let direct = serde_json::json!({
@@ -685,6 +826,11 @@ mod tests {
             .unwrap();
 
         assert_eq!(status.ip, Some("10.88.0.2".parse().unwrap()));
+        assert_eq!(
+            status.cni_result.as_ref().unwrap()["ips"][0]["address"],
+            "10.88.0.2/16"
+        );
+        assert_eq!(status.config.as_ref().unwrap()["name"], "test-net");
 
         let bridge_input = tokio::fs::read_to_string(record_dir.join("bridge.input"))
             .await
@@ -30,6 +30,10 @@ pub enum NetworkError {
     #[error("Network error: {0}")]
     Network(String),
 
+    /// 网络命名空间仍被进程或挂载占用，无法删除
+    #[error("network namespace {netns} is still in use: {holders}")]
+    NetnsBusy { netns: String, holders: String },
+
     /// 其他错误
     #[error("{0}")]
     Other(String),
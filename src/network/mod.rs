@@ -3,6 +3,8 @@
 //! 提供容器网络功能，包括网络命名空间管理、CNI 接口等。
 
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use tokio::process::Command;
@@ -10,6 +12,7 @@ use tokio::process::Command;
 pub mod cni;
 mod error;
 pub mod multi;
+mod netns;
 mod port_mapping;
 mod types;
 
@@ -19,6 +22,7 @@ pub use multi::{
     MultiNetworkConfig, MultiNetworkManager, NetworkInterfaceStatus, NetworkSelector,
     PodNetworkStatus,
 };
+pub use netns::{NetnsCleanup, NetnsHolders};
 pub use port_mapping::{PortMapping, PortMappingBackend, PortMappingManager, Protocol};
 pub use types::*;
 
@@ -28,6 +32,8 @@ pub struct CniConfig {
     config_dirs: Vec<PathBuf>,
     plugin_dirs: Vec<PathBuf>,
     cache_dir: PathBuf,
+    /// 配置目录轮询间隔，为 0 时不监听，每次网络操作重新读取配置
+    watch_interval: Duration,
 }
 
 impl Default for CniConfig {
@@ -43,6 +49,7 @@ impl Default for CniConfig {
                 PathBuf::from("/usr/libexec/cni"),
             ],
             cache_dir: PathBuf::from("/var/lib/cni/cache"),
+            watch_interval: Duration::ZERO,
         }
     }
 }
@@ -83,9 +90,20 @@ impl CniConfig {
             config_dirs,
             plugin_dirs,
             cache_dir,
+            watch_interval: defaults.watch_interval,
         }
     }
 
+    /// 设置配置目录监听间隔
+    pub fn with_watch_interval(mut self, interval: Duration) -> Self {
+        self.watch_interval = interval;
+        self
+    }
+
+    pub fn watch_interval(&self) -> Duration {
+        self.watch_interval
+    }
+
     pub fn config_dirs(&self) -> &[PathBuf] {
         &self.config_dirs
     }
@@ -155,14 +173,24 @@ pub struct DefaultNetworkManager {
     cni_plugin_dirs: Vec<String>,
     cni_config_dirs: Vec<String>,
     cni_cache_dir: String,
+    watch_interval: Duration,
+    /// 启用监听后常驻的 CNI 管理器，由后台任务随配置文件变化重载
+    watched: Option<Arc<tokio::sync::RwLock<CniManager>>>,
 }
 
 impl DefaultNetworkManager {
     fn netns_exists(ns: &str) -> bool {
-        let p = Path::new(ns);
-        p.exists()
-            || Path::new("/var/run/netns").join(ns).exists()
-            || Path::new("/run/netns").join(ns).exists()
+        Self::netns_path(ns).is_some()
+    }
+
+    fn netns_path(ns: &str) -> Option<PathBuf> {
+        [
+            PathBuf::from(ns),
+            Path::new("/var/run/netns").join(ns),
+            Path::new("/run/netns").join(ns),
+        ]
+        .into_iter()
+        .find(|path| path.exists())
     }
 
     async fn ensure_loopback_up(&self, netns: &str) -> Result<(), NetworkError> {
@@ -206,7 +234,47 @@ impl DefaultNetworkManager {
             cni_plugin_dirs: cni.plugin_dir_strings(),
             cni_config_dirs: cni.config_dir_strings(),
             cni_cache_dir: cni.cache_dir_string(),
+            watch_interval: cni.watch_interval,
+            watched: None,
+        }
+    }
+
+    /// 加载配置并启动配置目录监听；未配置监听间隔时返回 `None`
+    pub async fn start_config_watcher(
+        &mut self,
+    ) -> Result<Option<tokio::task::JoinHandle<()>>, NetworkError> {
+        if self.watch_interval.is_zero() || self.watched.is_some() {
+            return Ok(None);
+        }
+        let mut cni = self.cni_manager()?;
+        cni.load_network_configs()
+            .await
+            .map_err(|e| NetworkError::Other(e.to_string()))?;
+        let cni = Arc::new(tokio::sync::RwLock::new(cni));
+        let handle = CniManager::watch_configs(cni.clone(), self.watch_interval);
+        self.watched = Some(cni);
+        Ok(Some(handle))
+    }
+
+    fn cni_manager(&self) -> Result<CniManager, NetworkError> {
+        CniManager::new(
+            self.cni_plugin_dirs.clone(),
+            self.cni_config_dirs.clone(),
+            self.cni_cache_dir.clone(),
+        )
+        .map_err(|e| NetworkError::Other(e.to_string()))
+    }
+
+    /// 当前的 CNI 配置：监听时取常驻管理器的快照，否则重新读取配置目录
+    async fn load_cni(&self) -> Result<CniManager, NetworkError> {
+        if let Some(watched) = &self.watched {
+            return Ok(watched.read().await.clone());
         }
+        let mut cni = self.cni_manager()?;
+        cni.load_network_configs()
+            .await
+            .map_err(|e| NetworkError::Other(e.to_string()))?;
+        Ok(cni)
     }
 }
 
@@ -215,7 +283,10 @@ impl NetworkManager for DefaultNetworkManager {
     async fn init(&self) -> Result<(), NetworkError> {
         // 创建缓存目录
         if !Path::new(&self.cni_cache_dir).exists() {
-            tokio::fs::create_dir_all(&self.cni_cache_dir).await?;
+            crate::utils::create_dir_all_with_mode(
+                &self.cni_cache_dir,
+                crate::utils::DEFAULT_DIR_MODE,
+            )?;
         }
         Ok(())
     }
@@ -238,20 +309,24 @@ impl NetworkManager for DefaultNetworkManager {
     }
 
     async fn remove_network_namespace(&self, ns_path: &str) -> Result<(), NetworkError> {
-        if DefaultNetworkManager::netns_exists(ns_path) {
-            let status = Command::new("ip")
-                .args(["netns", "delete", ns_path])
-                .status()
-                .await?;
-
-            if !status.success() {
-                return Err(NetworkError::CommandExecutionError {
-                    command: format!("ip netns delete {}", ns_path),
-                    status,
-                });
-            }
-        }
-        Ok(())
+        let Some(path) = DefaultNetworkManager::netns_path(ns_path) else {
+            return Ok(());
+        };
+        NetnsCleanup::default()
+            .remove_with(ns_path, &path, || async {
+                let status = Command::new("ip")
+                    .args(["netns", "delete", ns_path])
+                    .status()
+                    .await?;
+                if !status.success() {
+                    return Err(NetworkError::CommandExecutionError {
+                        command: format!("ip netns delete {}", ns_path),
+                        status,
+                    });
+                }
+                Ok(())
+            })
+            .await
     }
 
     async fn setup_pod_network(
@@ -268,16 +343,7 @@ impl NetworkManager for DefaultNetworkManager {
             .unwrap_or(netns);
         self.ensure_loopback_up(netns_name).await?;
 
-        let mut cni = CniManager::new(
-            self.cni_plugin_dirs.clone(),
-            self.cni_config_dirs.clone(),
-            self.cni_cache_dir.clone(),
-        )
-        .map_err(|e| NetworkError::Other(e.to_string()))?;
-        cni.load_network_configs()
-            .await
-            .map_err(|e| NetworkError::Other(e.to_string()))?;
-
+        let cni = self.load_cni().await?;
         cni.setup_pod_network(pod_id, netns, pod_name, pod_namespace, pod_cidr)
             .await
             .map_err(|e| NetworkError::Other(e.to_string()))
@@ -290,15 +356,7 @@ impl NetworkManager for DefaultNetworkManager {
         pod_namespace: &str,
         pod_name: &str,
     ) -> Result<(), NetworkError> {
-        let mut cni = CniManager::new(
-            self.cni_plugin_dirs.clone(),
-            self.cni_config_dirs.clone(),
-            self.cni_cache_dir.clone(),
-        )
-        .map_err(|e| NetworkError::Other(e.to_string()))?;
-        cni.load_network_configs()
-            .await
-            .map_err(|e| NetworkError::Other(e.to_string()))?;
+        let cni = self.load_cni().await?;
         let _ = cni
             .teardown_pod_network(pod_id, netns, pod_namespace, pod_name)
             .await;
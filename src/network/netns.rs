@@ -0,0 +1,289 @@
+//! 网络命名空间删除
+//!
+//! 残留进程或额外的 bind 挂载会让 netns 无法删除（或删除后命名空间仍然存活）。
+//! 删除前先找出仍在使用 netns 的进程与挂载，杀掉进程、卸载挂载后重试，
+//! 最终失败时在错误中列出占用者。
+
+use std::fmt;
+use std::future::Future;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use log::warn;
+use nix::mount::{umount2, MntFlags};
+use nix::sys::signal::{kill, Signal};
+use nix::unistd::Pid;
+
+use super::NetworkError;
+
+/// 仍在使用某个 netns 的进程与挂载
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NetnsHolders {
+    pub pids: Vec<u32>,
+    pub mounts: Vec<PathBuf>,
+}
+
+impl NetnsHolders {
+    pub fn is_empty(&self) -> bool {
+        self.pids.is_empty() && self.mounts.is_empty()
+    }
+}
+
+impl fmt::Display for NetnsHolders {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if !self.pids.is_empty() {
+            let pids: Vec<String> = self.pids.iter().map(u32::to_string).collect();
+            parts.push(format!("processes [{}]", pids.join(", ")));
+        }
+        if !self.mounts.is_empty() {
+            let mounts: Vec<String> = self
+                .mounts
+                .iter()
+                .map(|mount| mount.display().to_string())
+                .collect();
+            parts.push(format!("mounts [{}]", mounts.join(", ")));
+        }
+        if parts.is_empty() {
+            return write!(f, "no known holders");
+        }
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+/// netns 删除与占用清理
+#[derive(Debug, Clone)]
+pub struct NetnsCleanup {
+    /// procfs 根目录，测试中可替换
+    pub proc_root: PathBuf,
+    /// 删除尝试次数
+    pub attempts: u32,
+    /// 两次尝试之间的等待，留给被杀进程退出
+    pub retry_delay: Duration,
+}
+
+impl Default for NetnsCleanup {
+    fn default() -> Self {
+        Self {
+            proc_root: PathBuf::from("/proc"),
+            attempts: 3,
+            retry_delay: Duration::from_millis(200),
+        }
+    }
+}
+
+impl NetnsCleanup {
+    /// 找出与 `netns_path` 处于同一网络命名空间的进程，以及该命名空间除 `netns_path` 外的挂载
+    pub fn holders(&self, netns_path: &Path) -> std::io::Result<NetnsHolders> {
+        let target = format!("net:[{}]", std::fs::metadata(netns_path)?.ino());
+        let own_path = std::fs::canonicalize(netns_path)?;
+        let own_pid = std::process::id();
+
+        let mut pids: Vec<u32> = std::fs::read_dir(&self.proc_root)?
+            .flatten()
+            .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+            .filter(|pid| *pid != own_pid)
+            .filter(|pid| {
+                std::fs::read_link(self.proc_root.join(pid.to_string()).join("ns/net"))
+                    .is_ok_and(|link| link.as_os_str() == target.as_str())
+            })
+            .collect();
+        pids.sort_unstable();
+
+        let mountinfo =
+            std::fs::read_to_string(self.proc_root.join("self/mountinfo")).unwrap_or_default();
+        let mut mounts: Vec<PathBuf> = mountinfo
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let root = fields.nth(3)?;
+                let mount_point = fields.next()?;
+                (root == target).then(|| PathBuf::from(mount_point.replace("\\040", " ")))
+            })
+            .filter(|mount_point| {
+                std::fs::canonicalize(mount_point).unwrap_or_else(|_| mount_point.clone())
+                    != own_path
+            })
+            .collect();
+        mounts.sort();
+        mounts.dedup();
+
+        Ok(NetnsHolders { pids, mounts })
+    }
+
+    /// SIGKILL 残留进程并 lazy 卸载多余挂载，失败只记录日志
+    pub fn release(&self, holders: &NetnsHolders) {
+        for pid in &holders.pids {
+            if let Err(err) = kill(Pid::from_raw(*pid as i32), Signal::SIGKILL) {
+                warn!("Failed to kill process {} holding netns: {}", pid, err);
+            }
+        }
+        for mount in &holders.mounts {
+            if let Err(err) = umount2(mount, MntFlags::MNT_DETACH) {
+                warn!(
+                    "Failed to unmount {} holding netns: {}",
+                    mount.display(),
+                    err
+                );
+            }
+        }
+    }
+
+    /// 清理占用后调用 `delete` 删除 netns，失败时重试；最终失败返回 [`NetworkError::NetnsBusy`]
+    pub async fn remove_with<F, Fut>(
+        &self,
+        netns: &str,
+        netns_path: &Path,
+        mut delete: F,
+    ) -> Result<(), NetworkError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<(), NetworkError>>,
+    {
+        let mut holders = NetnsHolders::default();
+        let mut last_error = None;
+        for attempt in 1..=self.attempts.max(1) {
+            // 删除成功后 netns 文件消失，只能在删除前检查占用
+            if let Ok(found) = self.holders(netns_path) {
+                holders = found;
+            }
+            if !holders.is_empty() {
+                warn!(
+                    "Network namespace {} is held by {} (attempt {}), releasing",
+                    netns, holders, attempt
+                );
+                self.release(&holders);
+                tokio::time::sleep(self.retry_delay).await;
+            }
+            match delete().await {
+                Ok(()) => return Ok(()),
+                Err(err) => last_error = Some(err.to_string()),
+            }
+            tokio::time::sleep(self.retry_delay).await;
+        }
+
+        Err(NetworkError::NetnsBusy {
+            netns: netns.to_string(),
+            holders: match last_error {
+                Some(err) => format!("{} ({})", holders, err),
+                None => holders.to_string(),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+    use std::sync::{Arc, Mutex};
+    use tempfile::tempdir;
+
+    fn fake_proc(root: &Path, pid: u32, netns: &str) {
+        let dir = root.join(pid.to_string()).join("ns");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::os::unix::fs::symlink(netns, dir.join("net")).unwrap();
+    }
+
+    fn cleanup(proc_root: &Path) -> NetnsCleanup {
+        NetnsCleanup {
+            proc_root: proc_root.to_path_buf(),
+            attempts: 3,
+            retry_delay: Duration::from_millis(20),
+        }
+    }
+
+    #[tokio::test]
+    async fn remove_with_kills_lingering_processes_before_retrying() {
+        let dir = tempdir().unwrap();
+        let netns_path = dir.path().join("crius-default-busy");
+        std::fs::write(&netns_path, "").unwrap();
+        let target = format!("net:[{}]", std::fs::metadata(&netns_path).unwrap().ino());
+        let proc_root = dir.path().join("proc");
+        let child = Arc::new(Mutex::new(Command::new("sleep").arg("60").spawn().unwrap()));
+        let child_pid = child.lock().unwrap().id();
+        fake_proc(&proc_root, child_pid, &target);
+        fake_proc(&proc_root, 1, "net:[1]");
+
+        let cleanup = cleanup(&proc_root);
+        assert_eq!(
+            cleanup.holders(&netns_path).unwrap(),
+            NetnsHolders {
+                pids: vec![child_pid],
+                mounts: Vec::new(),
+            }
+        );
+
+        // 进程退出前删除失败，模拟 netns 仍被占用
+        let busy_child = child.clone();
+        let busy_path = netns_path.clone();
+        let busy_proc = proc_root.clone();
+        cleanup
+            .remove_with("crius-default-busy", &netns_path, move || {
+                let exited = busy_child.lock().unwrap().try_wait().unwrap().is_some();
+                if exited {
+                    let _ = std::fs::remove_dir_all(busy_proc.join(child_pid.to_string()));
+                    let _ = std::fs::remove_file(&busy_path);
+                }
+                async move {
+                    if exited {
+                        Ok(())
+                    } else {
+                        Err(NetworkError::Other("Device or resource busy".to_string()))
+                    }
+                }
+            })
+            .await
+            .unwrap();
+
+        let status = child.lock().unwrap().wait().unwrap();
+        assert_eq!(
+            std::os::unix::process::ExitStatusExt::signal(&status),
+            Some(9)
+        );
+    }
+
+    #[tokio::test]
+    async fn remove_with_reports_holders_when_netns_stays_busy() {
+        let dir = tempdir().unwrap();
+        let netns_path = dir.path().join("crius-default-stuck");
+        std::fs::write(&netns_path, "").unwrap();
+        let target = format!("net:[{}]", std::fs::metadata(&netns_path).unwrap().ino());
+        let proc_root = dir.path().join("proc");
+        std::fs::create_dir_all(proc_root.join("self")).unwrap();
+        let stray = dir.path().join("stray mount");
+        std::fs::write(
+            proc_root.join("self/mountinfo"),
+            format!(
+                "600 25 0:4 {target} {} rw shared:5 - nsfs nsfs rw\n\
+                 601 25 0:4 {target} {} rw shared:5 - nsfs nsfs rw\n\
+                 602 25 0:4 net:[1] /run/netns/other rw shared:5 - nsfs nsfs rw\n",
+                netns_path.display(),
+                stray.display().to_string().replace(' ', "\\040"),
+            ),
+        )
+        .unwrap();
+
+        let cleanup = cleanup(&proc_root);
+        let attempts = Arc::new(Mutex::new(0));
+        let counter = attempts.clone();
+        let err = cleanup
+            .remove_with("crius-default-stuck", &netns_path, move || {
+                *counter.lock().unwrap() += 1;
+                async { Err(NetworkError::Other("Device or resource busy".to_string())) }
+            })
+            .await
+            .unwrap_err();
+
+        assert_eq!(*attempts.lock().unwrap(), 3);
+        let message = err.to_string();
+        assert!(message.contains("crius-default-stuck"), "{}", message);
+        assert!(
+            message.contains(&format!("mounts [{}]", stray.display())),
+            "{}",
+            message
+        );
+        assert!(message.contains("Device or resource busy"), "{}", message);
+    }
+}
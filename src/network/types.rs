@@ -15,6 +15,14 @@ pub struct NetworkStatus {
 
     /// 网络接口列表
     pub interfaces: Vec<NetworkInterface>,
+
+    /// 生效的 CNI 网络配置
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<serde_json::Value>,
+
+    /// CNI 插件链返回的原始结果
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cni_result: Option<serde_json::Value>,
 }
 
 /// 网络接口信息
@@ -643,6 +643,8 @@ fn ensure_linux(spec: &mut Spec) -> &mut Linux {
         sysctl: None,
         mount_label: None,
         intel_rdt: None,
+        masked_paths: None,
+        readonly_paths: None,
     })
 }
 
@@ -1703,6 +1705,8 @@ mod tests {
                 )])),
                 mount_label: None,
                 intel_rdt: None,
+                masked_paths: None,
+                readonly_paths: None,
             }),
             annotations: Some(HashMap::from([
                 ("keep".to_string(), "old".to_string()),
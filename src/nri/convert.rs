@@ -654,6 +654,8 @@ mod tests {
                     enable_cmt: Some(true),
                     enable_mbm: Some(true),
                 }),
+                masked_paths: None,
+                readonly_paths: None,
             }),
             annotations: None,
         };
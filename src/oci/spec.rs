@@ -5,6 +5,7 @@
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
 
 /// OCI运行时配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,12 +63,14 @@ pub struct Process {
 
 /// 用户配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct User {
     /// 用户UID
     pub uid: u32,
     /// 组GID
     pub gid: u32,
-    /// 附加组
+    /// 附加组（早期版本以 `additional_gids` 写入 bundle）
+    #[serde(alias = "additional_gids")]
     pub additional_gids: Option<Vec<u32>>,
     /// 用户名
     pub username: Option<String>,
@@ -177,7 +180,8 @@ pub struct Linux {
     pub devices: Option<Vec<Device>>,
     /// 网络设备
     pub net_devices: Option<HashMap<String, LinuxNetDevice>>,
-    /// Cgroups配置
+    /// Cgroups配置，旧版本写出的 bundle 使用 `cgroups_path`
+    #[serde(rename = "cgroupsPath", alias = "cgroups_path")]
     pub cgroups_path: Option<String>,
     /// 资源限制
     pub resources: Option<LinuxResources>,
@@ -191,6 +195,12 @@ pub struct Linux {
     pub mount_label: Option<String>,
     /// Intel RDT资源控制
     pub intel_rdt: Option<LinuxIntelRdt>,
+    /// 在容器内屏蔽的路径
+    #[serde(rename = "maskedPaths", skip_serializing_if = "Option::is_none")]
+    pub masked_paths: Option<Vec<String>>,
+    /// 在容器内以只读方式重新挂载的路径
+    #[serde(rename = "readonlyPaths", skip_serializing_if = "Option::is_none")]
+    pub readonly_paths: Option<Vec<String>>,
 }
 
 /// 命名空间配置
@@ -477,6 +487,12 @@ pub struct IntelRdt {
     pub mem_bw: Option<bool>,
 }
 
+/// `/dev/shm` 的默认大小
+pub const DEFAULT_SHM_SIZE: u64 = 64 << 20;
+
+/// 镜像和 CRI 请求都未设置 PATH 时使用的默认值，与 Docker/containerd 一致
+pub const DEFAULT_PATH_ENV: &str = "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+
 impl Spec {
     /// 创建新的OCI配置
     pub fn new(oci_version: impl Into<String>) -> Self {
@@ -566,18 +582,7 @@ impl Spec {
                     "gid=5".to_string(),
                 ]),
             },
-            Mount {
-                destination: "/dev/shm".to_string(),
-                source: Some("tmpfs".to_string()),
-                mount_type: Some("tmpfs".to_string()),
-                options: Some(vec![
-                    "nosuid".to_string(),
-                    "noexec".to_string(),
-                    "nodev".to_string(),
-                    "mode=1777".to_string(),
-                    "size=65536k".to_string(),
-                ]),
-            },
+            Self::shm_mount(DEFAULT_SHM_SIZE),
             Mount {
                 destination: "/dev/mqueue".to_string(),
                 source: Some("mqueue".to_string()),
@@ -615,6 +620,38 @@ impl Spec {
         ]
     }
 
+    /// 指定大小（字节）的 `/dev/shm` tmpfs 挂载
+    pub fn shm_mount(size: u64) -> Mount {
+        Mount {
+            destination: "/dev/shm".to_string(),
+            source: Some("tmpfs".to_string()),
+            mount_type: Some("tmpfs".to_string()),
+            options: Some(vec![
+                "nosuid".to_string(),
+                "noexec".to_string(),
+                "nodev".to_string(),
+                "mode=1777".to_string(),
+                format!("size={}", size),
+            ]),
+        }
+    }
+
+    /// 把 sandbox 级 `/dev/shm`（宿主机上已挂载的 tmpfs）bind 到容器内
+    pub fn sandbox_shm_mount(source: &Path) -> Mount {
+        Mount {
+            destination: "/dev/shm".to_string(),
+            source: Some(source.to_string_lossy().to_string()),
+            mount_type: Some("bind".to_string()),
+            options: Some(vec![
+                "rbind".to_string(),
+                "rw".to_string(),
+                "nosuid".to_string(),
+                "noexec".to_string(),
+                "nodev".to_string(),
+            ]),
+        }
+    }
+
     /// 默认设备
     pub fn default_devices() -> Vec<Device> {
         vec![
@@ -636,6 +673,15 @@ impl Spec {
                 uid: None,
                 gid: None,
             },
+            Device {
+                device_type: "c".to_string(),
+                path: "/dev/full".to_string(),
+                major: Some(1),
+                minor: Some(7),
+                file_mode: Some(0o666),
+                uid: None,
+                gid: None,
+            },
             Device {
                 device_type: "c".to_string(),
                 path: "/dev/random".to_string(),
@@ -666,6 +712,34 @@ impl Spec {
         ]
     }
 
+    /// 非特权容器的设备 cgroup 规则，与 runc 默认值一致：
+    /// 先拒绝全部设备，再放行 mknod 以及 null、zero、full、random、urandom、tty、
+    /// ptmx 和 /dev/pts/*
+    pub fn default_device_cgroup_rules() -> Vec<LinuxDeviceCgroup> {
+        let rule = |allow: bool, device_type: Option<&str>, major, minor, access: &str| {
+            LinuxDeviceCgroup {
+                allow,
+                device_type: device_type.map(str::to_string),
+                major,
+                minor,
+                access: Some(access.to_string()),
+            }
+        };
+        vec![
+            rule(false, None, None, None, "rwm"),
+            rule(true, Some("c"), None, None, "m"),
+            rule(true, Some("b"), None, None, "m"),
+            rule(true, Some("c"), Some(1), Some(3), "rwm"),
+            rule(true, Some("c"), Some(1), Some(5), "rwm"),
+            rule(true, Some("c"), Some(1), Some(7), "rwm"),
+            rule(true, Some("c"), Some(1), Some(8), "rwm"),
+            rule(true, Some("c"), Some(1), Some(9), "rwm"),
+            rule(true, Some("c"), Some(5), Some(0), "rwm"),
+            rule(true, Some("c"), Some(5), Some(2), "rwm"),
+            rule(true, Some("c"), Some(136), None, "rwm"),
+        ]
+    }
+
     /// 序列化为JSON字符串
     pub fn to_json(&self) -> Result<String, serde_json::Error> {
         serde_json::to_string_pretty(self)
@@ -839,6 +913,8 @@ mod tests {
             mount_label: None,
             intel_rdt: None,
             net_devices: None,
+            masked_paths: None,
+            readonly_paths: None,
         });
         spec.hooks = Some(Hooks {
             prestart: None,
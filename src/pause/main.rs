@@ -0,0 +1,60 @@
+//! crius-pause - 内置 pause 进程
+//!
+//! 作为 sandbox 的 PID 1 持有 Pod 的各个 namespace：阻塞等待信号，
+//! 收到 SIGCHLD 时收割所有已退出的子进程，收到 SIGINT/SIGTERM 时退出。
+//! 在没有任何镜像的空 rootfs 中运行，需静态链接构建，例如：
+//! `cargo build --release --target x86_64-unknown-linux-musl --bin crius-pause`。
+
+use nix::errno::Errno;
+use nix::sys::signal::{SigSet, Signal};
+use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
+
+fn main() {
+    if std::env::args()
+        .skip(1)
+        .any(|arg| arg == "-v" || arg == "--version")
+    {
+        println!("crius-pause {}", env!("CARGO_PKG_VERSION"));
+        return;
+    }
+    if std::process::id() != 1 {
+        eprintln!("crius-pause: warning: not running as PID 1");
+    }
+
+    let mut signals = SigSet::empty();
+    signals.add(Signal::SIGCHLD);
+    signals.add(Signal::SIGINT);
+    signals.add(Signal::SIGTERM);
+    if let Err(e) = signals.thread_block() {
+        eprintln!("crius-pause: failed to block signals: {}", e);
+        std::process::exit(1);
+    }
+
+    loop {
+        match signals.wait() {
+            Ok(Signal::SIGCHLD) => reap_children(),
+            Ok(signal) => {
+                eprintln!("crius-pause: shutting down on {}", signal);
+                std::process::exit(0);
+            }
+            Err(e) => {
+                eprintln!("crius-pause: sigwait failed: {}", e);
+                std::process::exit(2);
+            }
+        }
+    }
+}
+
+/// 收割全部已退出的子进程，多个子进程同时退出时 SIGCHLD 可能只投递一次
+fn reap_children() {
+    loop {
+        match waitpid(None, Some(WaitPidFlag::WNOHANG)) {
+            Ok(WaitStatus::StillAlive) | Err(Errno::ECHILD) => return,
+            Ok(_) | Err(Errno::EINTR) => {}
+            Err(e) => {
+                eprintln!("crius-pause: waitpid failed: {}", e);
+                return;
+            }
+        }
+    }
+}
@@ -3,17 +3,19 @@
 //! 提供Pod沙箱的创建、管理和清理功能
 
 use anyhow::{Context, Result};
-use log::{debug, error, info};
-use std::collections::HashMap;
+use log::{debug, error, info, warn};
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use tokio::process::Command;
 
+use crate::cgroups::CgroupManager;
+use crate::metrics::{Operation, OperationTimer, Phase};
 use crate::network::{
     CniConfig, DefaultNetworkManager, NetworkInterface, NetworkManager, NetworkStatus,
 };
-use crate::proto::runtime::v1::{LinuxContainerResources, NamespaceOption};
+use crate::proto::runtime::v1::{LinuxContainerResources, NamespaceMode, NamespaceOption};
 use crate::runtime::{
-    ContainerConfig, ContainerRuntime, ContainerStatus, NamespacePaths, SeccompProfile,
+    ContainerConfig, ContainerRuntime, ContainerStatus, NamespacePaths, RuncRuntime, SeccompProfile,
 };
 use std::collections::HashSet;
 use std::net::IpAddr;
@@ -35,6 +37,10 @@ const CONTAINERD_SANDBOX_NAMESPACE_ANNOTATION: &str = "io.kubernetes.cri.sandbox
 const CONTAINERD_SANDBOX_UID_ANNOTATION: &str = "io.kubernetes.cri.sandbox-uid";
 const CONTAINERD_RUNTIME_HANDLER_ANNOTATION: &str = "io.containerd.cri.runtime-handler";
 const CONTAINER_TYPE_SANDBOX: &str = "sandbox";
+/// 内置 pause 程序在 sandbox rootfs 中的路径
+const PAUSE_BINARY_DESTINATION: &str = "/pause";
+/// CRI 未指定 cgroup_parent 时 pause 容器使用的父 cgroup
+const DEFAULT_PAUSE_CGROUP_PARENT: &str = "crius";
 
 /// Pod沙箱配置
 #[derive(Debug, Clone)]
@@ -67,6 +73,8 @@ pub struct PodSandboxConfig {
     pub sysctls: HashMap<String, String>,
     /// Pod namespace配置
     pub namespace_options: Option<NamespaceOption>,
+    /// sandbox 级 `/dev/shm` 大小（字节）
+    pub shm_size: u64,
     /// 安全上下文
     pub privileged: bool,
     pub run_as_user: Option<String>,
@@ -124,6 +132,14 @@ pub struct PodSandbox {
     pub ip: String,
     /// 网络状态
     pub network_status: Option<NetworkStatus>,
+    /// Pod 级 cgroup 路径（不含 cgroup root），pause 与业务容器的 cgroup 嵌套其下
+    pub cgroup_path: Option<String>,
+    /// 创建时各阶段耗时（秒）
+    pub creation_timings: BTreeMap<String, f64>,
+    /// 成员容器共享的 `/dev/shm`（宿主机上的 tmpfs），Pod 使用宿主机 IPC 或 rootless 模式时为 `None`
+    pub shm_path: Option<PathBuf>,
+    /// rootless Pod 的 slirp4netns/pasta 进程，随 sandbox 持久化，重启后据此回收
+    pub network_helper: Option<crate::rootless::NetworkHelper>,
 }
 
 /// Pod沙箱状态
@@ -134,6 +150,72 @@ pub enum PodSandboxState {
     Terminated,
 }
 
+/// 检查内置 pause 程序：须为绝对路径下的可执行文件，且为静态链接
+///
+/// 内置 pause 运行在没有任何库文件的空 rootfs 中，动态链接的程序无法启动。
+pub fn validate_pause_binary(path: &Path) -> Result<()> {
+    if !path.is_absolute() {
+        anyhow::bail!("pause_binary {} must be an absolute path", path.display());
+    }
+    if !path.is_file() || nix::unistd::access(path, nix::unistd::AccessFlags::X_OK).is_err() {
+        anyhow::bail!("pause_binary {} is not an executable file", path.display());
+    }
+    let elf = std::fs::read(path)
+        .with_context(|| format!("Failed to read pause_binary {}", path.display()))?;
+    if elf_interpreter(&elf).with_context(|| format!("pause_binary {}", path.display()))? {
+        anyhow::bail!(
+            "pause_binary {} is dynamically linked; build it statically (e.g. for a musl target)",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// ELF 是否声明了动态链接器（PT_INTERP 段）
+fn elf_interpreter(elf: &[u8]) -> Result<bool> {
+    const PT_INTERP: u32 = 3;
+    if elf.len() < 0x34 || &elf[..4] != b"\x7fELF" {
+        anyhow::bail!("not an ELF executable");
+    }
+    let little_endian = match elf[5] {
+        1 => true,
+        2 => false,
+        other => anyhow::bail!("unknown ELF data encoding {}", other),
+    };
+    let read = |offset: usize, len: usize| -> Result<u64> {
+        let bytes = elf
+            .get(offset..offset + len)
+            .ok_or_else(|| anyhow::anyhow!("truncated ELF header"))?;
+        let fold = |value: u64, byte: &u8| (value << 8) | u64::from(*byte);
+        Ok(if little_endian {
+            bytes.iter().rev().fold(0, fold)
+        } else {
+            bytes.iter().fold(0, fold)
+        })
+    };
+    let (phoff, phentsize, phnum) = match elf[4] {
+        1 => (read(0x1c, 4)?, read(0x2a, 2)?, read(0x2c, 2)?),
+        2 => (read(0x20, 8)?, read(0x36, 2)?, read(0x38, 2)?),
+        other => anyhow::bail!("unknown ELF class {}", other),
+    };
+    for index in 0..phnum {
+        let offset = usize::try_from(phoff + index * phentsize)?;
+        if read(offset, 4)? == u64::from(PT_INTERP) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// 创建 sandbox 过程中已经生成的资源，创建失败时据此回滚
+#[derive(Debug, Default)]
+struct CreatedSandboxResources {
+    cgroup_path: Option<String>,
+    netns_path: Option<PathBuf>,
+    network_attached: bool,
+    shm_path: Option<PathBuf>,
+}
+
 /// Pod沙箱管理器
 pub struct PodSandboxManager<R: ContainerRuntime> {
     /// 运行时
@@ -146,6 +228,18 @@ pub struct PodSandboxManager<R: ContainerRuntime> {
     pause_image: String,
     /// 运行中的Pod沙箱
     pods: HashMap<String, PodSandbox>,
+    /// sandbox 未提供 DNS 时使用的默认配置
+    default_dns: Option<DNSConfig>,
+    /// 内置 pause 程序，设置后 pause 容器使用空 rootfs 而不是 pause 镜像
+    pause_binary: Option<PathBuf>,
+    /// rootless 模式使用的用户态网络，`None` 时通过 netns + CNI 配置网络
+    rootless_network: Option<crate::rootless::NetworkMode>,
+    /// rootless Pod 的 slirp4netns/pasta 进程，随 Pod 停止回收
+    network_helpers: HashMap<String, std::process::Child>,
+    /// cgroup 挂载点
+    cgroup_mount: PathBuf,
+    /// cgroupfs 驱动下 cgroup 路径的基准目录
+    cgroup_root: Option<String>,
 }
 
 impl<R: ContainerRuntime> std::fmt::Debug for PodSandboxManager<R> {
@@ -153,6 +247,8 @@ impl<R: ContainerRuntime> std::fmt::Debug for PodSandboxManager<R> {
         f.debug_struct("PodSandboxManager")
             .field("root_dir", &self.root_dir)
             .field("pause_image", &self.pause_image)
+            .field("pause_binary", &self.pause_binary)
+            .field("rootless_network", &self.rootless_network)
             .field("pods", &self.pods)
             .finish()
     }
@@ -163,26 +259,82 @@ impl<R: ContainerRuntime> PodSandboxManager<R> {
         self.root_dir.join(pod_id).join("resolv.conf")
     }
 
+    fn pod_shm_path(&self, pod_id: &str) -> PathBuf {
+        self.root_dir.join(pod_id).join("shm")
+    }
+
+    /// 挂载 sandbox 级 `/dev/shm`，供加入 Pod IPC 命名空间的容器共享
+    ///
+    /// Pod 使用宿主机 IPC 命名空间时不创建，返回 `None`。
+    fn mount_sandbox_shm(
+        &self,
+        pod_id: &str,
+        config: &PodSandboxConfig,
+    ) -> Result<Option<PathBuf>> {
+        let host_ipc = config
+            .namespace_options
+            .as_ref()
+            .is_some_and(|options| options.ipc == NamespaceMode::Node as i32);
+        if host_ipc {
+            return Ok(None);
+        }
+        let shm_path = self.pod_shm_path(pod_id);
+        std::fs::create_dir_all(&shm_path)
+            .with_context(|| format!("Failed to create {}", shm_path.display()))?;
+        nix::mount::mount(
+            Some("shm"),
+            &shm_path,
+            Some("tmpfs"),
+            nix::mount::MsFlags::MS_NOSUID
+                | nix::mount::MsFlags::MS_NODEV
+                | nix::mount::MsFlags::MS_NOEXEC,
+            Some(format!("mode=1777,size={}", config.shm_size).as_str()),
+        )
+        .with_context(|| format!("Failed to mount sandbox shm at {}", shm_path.display()))?;
+        debug!("Mounted sandbox shm for pod {} at {:?}", pod_id, shm_path);
+        Ok(Some(shm_path))
+    }
+
+    /// 卸载并删除 sandbox 级 `/dev/shm`
+    fn unmount_sandbox_shm(&self, pod_id: &str) {
+        let shm_path = self.pod_shm_path(pod_id);
+        if !shm_path.exists() {
+            return;
+        }
+        // EINVAL 表示已不是挂载点
+        match nix::mount::umount2(&shm_path, nix::mount::MntFlags::MNT_DETACH) {
+            Ok(()) | Err(nix::errno::Errno::EINVAL) => {}
+            Err(err) => {
+                warn!("Failed to unmount sandbox shm {:?}: {}", shm_path, err);
+                return;
+            }
+        }
+        if let Err(err) = std::fs::remove_dir(&shm_path) {
+            warn!("Failed to remove sandbox shm {:?}: {}", shm_path, err);
+        }
+    }
+
     async fn create_resolv_conf(
         &self,
         pod_id: &str,
         dns_config: Option<&DNSConfig>,
     ) -> Result<PathBuf> {
         let resolv_path = self.pod_resolv_path(pod_id);
-        let use_host_resolv = dns_config
-            .map(|config| {
-                config.servers.is_empty() && config.searches.is_empty() && config.options.is_empty()
+        let dns_config = dns_config
+            .filter(|config| {
+                !(config.servers.is_empty()
+                    && config.searches.is_empty()
+                    && config.options.is_empty())
             })
-            .unwrap_or(true);
+            .or(self.default_dns.as_ref());
 
-        if use_host_resolv {
+        let Some(dns_config) = dns_config else {
             tokio::fs::copy("/etc/resolv.conf", &resolv_path)
                 .await
                 .context("Failed to copy host resolv.conf")?;
             return Ok(resolv_path);
-        }
+        };
 
-        let dns_config = dns_config.expect("dns_config checked above");
         let mut contents = String::new();
         if !dns_config.searches.is_empty() {
             contents.push_str("search ");
@@ -288,42 +440,190 @@ impl<R: ContainerRuntime> PodSandboxManager<R> {
             root_dir,
             pause_image,
             pods: HashMap::new(),
+            default_dns: None,
+            pause_binary: None,
+            rootless_network: None,
+            network_helpers: HashMap::new(),
+            cgroup_mount: PathBuf::from("/sys/fs/cgroup"),
+            cgroup_root: None,
+        }
+    }
+
+    /// 设置 sandbox 未提供 DNS 时写入 resolv.conf 的默认配置，为空时沿用宿主机配置
+    pub fn set_default_dns(&mut self, dns: &crate::config::DefaultDnsConfig) {
+        self.default_dns = (!dns.is_empty()).then(|| DNSConfig {
+            servers: dns.servers.clone(),
+            searches: dns.searches.clone(),
+            options: dns.options.clone(),
+        });
+    }
+
+    /// 使用内置 pause 程序代替 pause 镜像，`None` 时恢复使用镜像
+    pub fn set_pause_binary(&mut self, pause_binary: Option<PathBuf>) {
+        self.pause_binary = pause_binary;
+    }
+
+    /// 开启 rootless 模式：Pod 不再创建具名 netns 与 CNI 网络，
+    /// 由 pause 容器创建网络命名空间后接入 `network` 指定的用户态网络
+    pub fn set_rootless_network(&mut self, network: Option<crate::rootless::NetworkMode>) {
+        self.rootless_network = network;
+    }
+
+    /// 设置 cgroup 文件系统挂载点
+    pub fn set_cgroup_mount(&mut self, cgroup_mount: PathBuf) {
+        self.cgroup_mount = cgroup_mount;
+    }
+
+    /// 设置 cgroup root，与运行时的 `set_cgroup_root` 一致
+    pub fn set_cgroup_root(&mut self, cgroup_root: Option<String>) {
+        self.cgroup_root = cgroup_root.filter(|root| !root.is_empty());
+    }
+
+    fn pod_cgroup_manager(&self, cgroup_path: &str) -> CgroupManager {
+        let path = crate::cgroups::cgroups_path(
+            self.cgroup_root.as_deref(),
+            Some(cgroup_path),
+            cgroup_path,
+        )
+        .unwrap_or_else(|| cgroup_path.to_string());
+        CgroupManager::with_path(
+            &path,
+            self.cgroup_mount.clone(),
+            CgroupManager::version_at(&self.cgroup_mount),
+        )
+    }
+
+    /// 在 cgroup_parent 下创建 Pod 级 cgroup 并写入 sandbox 的资源限制
+    ///
+    /// rootless 模式和 systemd 格式的 cgroup_parent 不创建，返回 `None`。
+    fn create_pod_cgroup(&self, pod_id: &str, config: &PodSandboxConfig) -> Result<Option<String>> {
+        if self.rootless_network.is_some() {
+            return Ok(None);
+        }
+        let Some(cgroup_path) = config
+            .cgroup_parent
+            .as_deref()
+            .and_then(|parent| crate::cgroups::pod_cgroup_path(parent, pod_id))
+        else {
+            return Ok(None);
+        };
+        let manager = self.pod_cgroup_manager(&cgroup_path);
+        manager.create_cgroup()?;
+        if let Some(resources) = config.linux_resources.as_ref() {
+            if let Err(err) = manager.set_resources(&RuncRuntime::cri_to_limits(resources)) {
+                let _ = manager.remove_cgroup();
+                return Err(err);
+            }
         }
+        debug!("Created pod cgroup {} for pod {}", cgroup_path, pod_id);
+        Ok(Some(cgroup_path))
+    }
+
+    /// 启动 CNI 配置目录监听，之后创建的 Pod 使用重载后的网络配置
+    pub async fn start_cni_config_watcher(
+        &mut self,
+    ) -> Result<Option<tokio::task::JoinHandle<()>>> {
+        Ok(self.network_manager.start_config_watcher().await?)
     }
 
     /// 创建Pod沙箱
     pub async fn create_pod_sandbox(&mut self, config: PodSandboxConfig) -> Result<String> {
+        let mut timer = OperationTimer::start(Operation::RunPodSandbox);
         let pod_id = uuid::Uuid::new_v4().to_simple().to_string();
         info!(
             "Creating pod sandbox {} (name: {}, namespace: {})",
             pod_id, config.name, config.namespace
         );
 
+        let mut created = CreatedSandboxResources::default();
+        let (netns_path, network_status, pause_container_id) = match self
+            .setup_pod_sandbox(&pod_id, &config, &mut timer, &mut created)
+            .await
+        {
+            Ok(result) => result,
+            Err(err) => {
+                self.rollback_pod_sandbox(&pod_id, &config, created).await;
+                return Err(err);
+            }
+        };
+
+        // 6. 创建Pod沙箱对象
+        let pod = PodSandbox {
+            id: pod_id.clone(),
+            config: config.clone(),
+            netns_path: netns_path.clone(),
+            pause_container_id: pause_container_id.clone(),
+            state: PodSandboxState::Ready,
+            created_at: crate::utils::now_nanos(),
+            ip: network_status
+                .ip
+                .as_ref()
+                .map(|ip| ip.to_string())
+                .unwrap_or_default(),
+            network_status: Some(network_status),
+            cgroup_path: created.cgroup_path,
+            creation_timings: timer.finish(),
+            shm_path: created.shm_path,
+            network_helper: self
+                .network_helpers
+                .get(&pod_id)
+                .and_then(crate::rootless::NetworkHelper::of),
+        };
+
+        self.pods.insert(pod_id.clone(), pod);
+
+        info!("Pod sandbox {} created successfully", pod_id);
+        Ok(pod_id)
+    }
+
+    /// 依次准备 Pod 目录、cgroup、网络、shm 与 pause 容器
+    ///
+    /// 已创建的资源记录在 `created` 中，失败时由调用方统一回滚。
+    async fn setup_pod_sandbox(
+        &mut self,
+        pod_id: &str,
+        config: &PodSandboxConfig,
+        timer: &mut OperationTimer,
+        created: &mut CreatedSandboxResources,
+    ) -> Result<(PathBuf, NetworkStatus, String)> {
         // 1. 创建Pod目录
-        let pod_dir = self.root_dir.join(&pod_id);
+        let pod_dir = self.root_dir.join(pod_id);
         tokio::fs::create_dir_all(&pod_dir).await?;
 
         // 1.1 为 Pod 准备 resolv.conf，参考 CRI-O 的 pod 级 DNS 文件做法。
-        self.create_resolv_conf(&pod_id, config.dns_config.as_ref())
+        self.create_resolv_conf(pod_id, config.dns_config.as_ref())
             .await
             .context("Failed to create pod resolv.conf")?;
 
+        // 1.2 创建 Pod 级 cgroup，约束整个 sandbox 的资源
+        created.cgroup_path = self
+            .create_pod_cgroup(pod_id, config)
+            .context("Failed to create pod cgroup")?;
+
+        if let Some(network) = self.rootless_network.clone() {
+            return self
+                .create_rootless_pod_network(pod_id, config, &network, timer)
+                .await;
+        }
+
         // 2. 创建网络命名空间
         let netns_name = format!("crius-{}-{}", config.namespace, config.name);
         let netns_path = PathBuf::from(format!("/var/run/netns/{}", netns_name));
 
         debug!("Creating network namespace: {}", netns_name);
+        timer.mark();
         self.network_manager
             .create_network_namespace(&netns_name)
             .await
             .context("Failed to create network namespace")?;
+        created.netns_path = Some(netns_path.clone());
 
         // 3. 设置Pod网络（CNI）
         debug!("Setting up pod network for {}", pod_id);
         let mut network_status = self
             .network_manager
             .setup_pod_network(
-                &pod_id,
+                pod_id,
                 &netns_path.to_string_lossy(),
                 &config.name,
                 &config.namespace,
@@ -333,6 +633,7 @@ impl<R: ContainerRuntime> PodSandboxManager<R> {
                     .map(|network| network.pod_cidr.as_str()),
             )
             .await?;
+        created.network_attached = true;
         let discovered_interfaces = self.discover_netns_interfaces(&netns_name).await;
         if network_status.ip.is_none() {
             network_status.ip = discovered_interfaces
@@ -342,36 +643,123 @@ impl<R: ContainerRuntime> PodSandboxManager<R> {
         if !discovered_interfaces.is_empty() {
             network_status.interfaces = discovered_interfaces;
         }
+        timer.record(Phase::Network);
+
+        // 4. 挂载 sandbox 级 /dev/shm，随后创建的 pause 容器与成员容器共享
+        created.shm_path = self
+            .mount_sandbox_shm(pod_id, config)
+            .context("Failed to mount sandbox shm")?;
 
-        // 4. 创建pause容器
+        // 5. 创建pause容器
         debug!("Creating pause container for pod {}", pod_id);
         let pause_container_id = self
-            .create_pause_container(&pod_id, &config, &netns_path)
+            .create_pause_container(
+                pod_id,
+                config,
+                Some(&netns_path),
+                created.cgroup_path.as_deref(),
+                timer,
+            )
             .await
             .context("Failed to create pause container")?;
+        Ok((netns_path, network_status, pause_container_id))
+    }
 
-        // 5. 创建Pod沙箱对象
-        let pod = PodSandbox {
-            id: pod_id.clone(),
-            config: config.clone(),
-            netns_path: netns_path.clone(),
-            pause_container_id: pause_container_id.clone(),
-            state: PodSandboxState::Ready,
-            created_at: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)?
-                .as_secs() as i64,
-            ip: network_status
-                .ip
-                .as_ref()
-                .map(|ip| ip.to_string())
-                .unwrap_or_default(),
-            network_status: Some(network_status),
-        };
+    /// 回滚创建失败的 sandbox，按与创建相反的顺序释放已创建的资源
+    async fn rollback_pod_sandbox(
+        &mut self,
+        pod_id: &str,
+        config: &PodSandboxConfig,
+        created: CreatedSandboxResources,
+    ) {
+        warn!("Rolling back failed pod sandbox {}", pod_id);
+        if created.shm_path.is_some() {
+            self.unmount_sandbox_shm(pod_id);
+        }
+        if let Some(netns_path) = created.netns_path.as_ref() {
+            if created.network_attached {
+                let _ = self
+                    .network_manager
+                    .teardown_pod_network(
+                        pod_id,
+                        &netns_path.to_string_lossy(),
+                        &config.namespace,
+                        &config.name,
+                    )
+                    .await;
+            }
+            if let Some(netns_name) = netns_path.file_name().and_then(|name| name.to_str()) {
+                if let Err(err) = self
+                    .network_manager
+                    .remove_network_namespace(netns_name)
+                    .await
+                {
+                    warn!(
+                        "Failed to remove network namespace {}: {:#}",
+                        netns_name, err
+                    );
+                }
+            }
+        }
+        if let Some(cgroup_path) = created.cgroup_path.as_deref() {
+            if let Err(err) = self.pod_cgroup_manager(cgroup_path).remove_cgroup() {
+                warn!("Failed to remove pod cgroup {}: {:#}", cgroup_path, err);
+            }
+        }
+        let pod_dir = self.root_dir.join(pod_id);
+        if let Err(err) = tokio::fs::remove_dir_all(&pod_dir).await {
+            if err.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to remove pod directory {:?}: {}", pod_dir, err);
+            }
+        }
+    }
 
-        self.pods.insert(pod_id.clone(), pod);
+    /// rootless 模式下由 pause 容器创建网络命名空间，再用 slirp4netns/pasta 接入网络
+    ///
+    /// 返回 `/proc/<pause pid>/ns/net` 作为 Pod 的 netns，业务容器据此加入。
+    async fn create_rootless_pod_network(
+        &mut self,
+        pod_id: &str,
+        config: &PodSandboxConfig,
+        network: &crate::rootless::NetworkMode,
+        timer: &mut OperationTimer,
+    ) -> Result<(PathBuf, NetworkStatus, String)> {
+        debug!("Creating rootless pause container for pod {}", pod_id);
+        let pause_container_id = self
+            .create_pause_container(pod_id, config, None, None, timer)
+            .await
+            .context("Failed to create pause container")?;
 
-        info!("Pod sandbox {} created successfully", pod_id);
-        Ok(pod_id)
+        let helper = self
+            .runtime
+            .init_pid(&pause_container_id)
+            .and_then(|pid| {
+                pid.ok_or_else(|| anyhow::anyhow!("pause container has no running process"))
+            })
+            .and_then(|pid| Ok((pid, network.spawn_helper(pid)?)));
+        let (pid, helper) = match helper {
+            Ok(helper) => helper,
+            Err(err) => {
+                let _ = self.runtime.stop_container(&pause_container_id, Some(0));
+                let _ = self.runtime.remove_container(&pause_container_id);
+                return Err(err.context("Failed to set up rootless pod network"));
+            }
+        };
+        if let Some(helper) = helper {
+            self.network_helpers.insert(pod_id.to_string(), helper);
+        }
+        timer.record(Phase::Network);
+
+        let network_status = NetworkStatus {
+            name: network.helper_binary().unwrap_or("none").to_string(),
+            ip: network.pod_ip(),
+            ..Default::default()
+        };
+        Ok((
+            PathBuf::from(format!("/proc/{}/ns/net", pid)),
+            network_status,
+            pause_container_id,
+        ))
     }
 
     /// 创建pause容器
@@ -379,10 +767,23 @@ impl<R: ContainerRuntime> PodSandboxManager<R> {
         &self,
         pod_id: &str,
         pod_config: &PodSandboxConfig,
-        netns_path: &Path,
+        netns_path: Option<&Path>,
+        cgroup_path: Option<&str>,
+        timer: &mut OperationTimer,
     ) -> Result<String> {
-        let pause_image = self.resolve_pause_image(pod_config)?;
         let mut pause_mounts = Vec::new();
+        // 内置 pause 程序挂载到空 rootfs 中运行，不需要任何镜像
+        let pause_image = match self.pause_binary.as_ref() {
+            Some(pause_binary) => {
+                pause_mounts.push(crate::runtime::MountConfig {
+                    source: pause_binary.clone(),
+                    destination: PathBuf::from(PAUSE_BINARY_DESTINATION),
+                    read_only: true,
+                });
+                String::new()
+            }
+            None => self.resolve_pause_image(pod_config)?,
+        };
         let pause_name = format!("pause-{}", pod_id);
         let mut pause_annotations = pod_config.annotations.clone();
         pause_annotations.push((CRIO_CONTAINER_ID_ANNOTATION.to_string(), pod_id.to_string()));
@@ -441,6 +842,7 @@ impl<R: ContainerRuntime> PodSandboxManager<R> {
                 log_directory.to_string_lossy().to_string(),
             ));
         }
+        let shm_path = self.pod_shm_path(pod_id);
         let resolv_path = self.pod_resolv_path(pod_id);
         if resolv_path.exists() {
             pause_mounts.push(crate::runtime::MountConfig {
@@ -464,7 +866,7 @@ impl<R: ContainerRuntime> PodSandboxManager<R> {
         let pause_config = ContainerConfig {
             name: pause_name.clone(),
             image: pause_image,
-            command: vec!["/pause".to_string()],
+            command: vec![PAUSE_BINARY_DESTINATION.to_string()],
             args: vec![],
             env: vec![],
             working_dir: None,
@@ -486,29 +888,40 @@ impl<R: ContainerRuntime> PodSandboxManager<R> {
             selinux_label: pod_config.selinux_label.clone(),
             seccomp_profile: pod_config.seccomp_profile.clone(),
             capabilities: None,
-            cgroup_parent: pod_config.cgroup_parent.clone(),
+            cgroup_parent: match cgroup_path {
+                Some(cgroup_path) => Some(format!("{}/{}", cgroup_path, pause_name)),
+                None => pod_config
+                    .cgroup_parent
+                    .clone()
+                    .or_else(|| Some(DEFAULT_PAUSE_CGROUP_PARENT.to_string())),
+            },
             sysctls: pod_config.sysctls.clone(),
             namespace_options: pod_config.namespace_options.clone(),
             namespace_paths: NamespacePaths {
-                network: Some(netns_path.to_path_buf()),
+                network: netns_path.map(Path::to_path_buf),
                 ..Default::default()
             },
             linux_resources: pod_config.linux_resources.clone(),
             devices: vec![],
+            shm_size: None,
+            shm_path: shm_path.exists().then_some(shm_path),
             // Pause容器使用自己的rootfs，实际应用中需要从镜像创建
             rootfs: self.root_dir.join(pod_id).join("pause-rootfs"),
         };
 
         // 创建 pause 容器，ID 由上层（pod 管理器）统一分配，避免 runtime 二次生成。
         let container_id = pause_name;
+        timer.mark();
         self.runtime
             .create_container(&container_id, &pause_config)
             .context("Failed to create pause container")?;
+        timer.record(Phase::RuntimeCreate);
 
         // 启动pause容器
         self.runtime
             .start_container(&container_id)
             .context("Failed to start pause container")?;
+        timer.record(Phase::RuntimeStart);
 
         // 验证pause容器运行状态
         match self.runtime.container_status(&container_id)? {
@@ -535,37 +948,42 @@ impl<R: ContainerRuntime> PodSandboxManager<R> {
     pub async fn stop_pod_sandbox(&mut self, pod_id: &str) -> Result<()> {
         info!("Stopping pod sandbox {}", pod_id);
 
-        if let Some(pod) = self.pods.get(pod_id) {
+        if let Some(pod) = self.pods.get(pod_id).cloned() {
             // 1. 停止pause容器
             debug!("Stopping pause container {}", pod.pause_container_id);
             let _ = self
                 .runtime
                 .stop_container(&pod.pause_container_id, Some(30));
             let _ = self.runtime.remove_container(&pod.pause_container_id);
+            self.unmount_sandbox_shm(pod_id);
 
-            // 2. 清理网络
-            debug!("Tearing down pod network for {}", pod_id);
-            let _ = self
-                .network_manager
-                .teardown_pod_network(
-                    pod_id,
-                    &pod.netns_path.to_string_lossy(),
-                    &pod.config.namespace,
-                    &pod.config.name,
-                )
-                .await;
-
-            // 3. 删除网络命名空间
-            let netns_name = pod
-                .netns_path
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("");
-            if !netns_name.is_empty() {
+            // 2. 清理网络；rootless Pod 的 netns 随 pause 容器消失，只需回收用户态网络进程
+            if self.rootless_network.is_some() || pod.network_helper.is_some() {
+                self.kill_network_helper(pod_id);
+            } else {
+                debug!("Tearing down pod network for {}", pod_id);
                 let _ = self
                     .network_manager
-                    .remove_network_namespace(netns_name)
+                    .teardown_pod_network(
+                        pod_id,
+                        &pod.netns_path.to_string_lossy(),
+                        &pod.config.namespace,
+                        &pod.config.name,
+                    )
                     .await;
+
+                // 3. 删除网络命名空间
+                let netns_name = pod
+                    .netns_path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or("");
+                if !netns_name.is_empty() {
+                    let _ = self
+                        .network_manager
+                        .remove_network_namespace(netns_name)
+                        .await;
+                }
             }
 
             // 4. 更新Pod状态
@@ -578,6 +996,26 @@ impl<R: ContainerRuntime> PodSandboxManager<R> {
         Ok(())
     }
 
+    /// 结束 rootless Pod 的用户态网络进程
+    ///
+    /// 本进程启动的辅助进程直接 kill 并回收；crius 重启后恢复的 Pod 只有持久化的
+    /// pid，确认进程未被替换后发送 SIGKILL，由 init 回收。
+    pub fn kill_network_helper(&mut self, pod_id: &str) {
+        if let Some(mut helper) = self.network_helpers.remove(pod_id) {
+            let _ = helper.kill();
+            let _ = helper.wait();
+        } else if let Some(helper) = self
+            .pods
+            .get(pod_id)
+            .and_then(|pod| pod.network_helper.as_ref())
+        {
+            helper.kill();
+        }
+        if let Some(pod) = self.pods.get_mut(pod_id) {
+            pod.network_helper = None;
+        }
+    }
+
     /// 删除Pod沙箱
     pub async fn remove_pod_sandbox(&mut self, pod_id: &str) -> Result<()> {
         info!("Removing pod sandbox {}", pod_id);
@@ -585,6 +1023,17 @@ impl<R: ContainerRuntime> PodSandboxManager<R> {
         // 确保Pod已停止
         let _ = self.stop_pod_sandbox(pod_id).await;
 
+        // 删除 Pod 级 cgroup，成员容器的 cgroup 已随容器删除
+        if let Some(cgroup_path) = self
+            .pods
+            .get(pod_id)
+            .and_then(|pod| pod.cgroup_path.as_deref())
+        {
+            if let Err(err) = self.pod_cgroup_manager(cgroup_path).remove_cgroup() {
+                warn!("Failed to remove pod cgroup {}: {:#}", cgroup_path, err);
+            }
+        }
+
         // 清理Pod目录
         let pod_dir = self.root_dir.join(pod_id);
         if pod_dir.exists() {
@@ -648,11 +1097,18 @@ impl<R: ContainerRuntime> PodSandboxManager<R> {
         pod_id: &str,
         resources: Option<LinuxContainerResources>,
     ) -> Result<()> {
-        let Some(pod) = self.pods.get_mut(pod_id) else {
+        let Some(pod) = self.pods.get(pod_id) else {
             return Err(anyhow::anyhow!("Pod sandbox {} not found", pod_id));
         };
 
-        pod.config.linux_resources = resources;
+        if let (Some(cgroup_path), Some(resources)) = (pod.cgroup_path.as_deref(), &resources) {
+            self.pod_cgroup_manager(cgroup_path)
+                .set_resources(&RuncRuntime::cri_to_limits(resources))
+                .with_context(|| format!("Failed to update pod cgroup {}", cgroup_path))?;
+        }
+        if let Some(pod) = self.pods.get_mut(pod_id) {
+            pod.config.linux_resources = resources;
+        }
         Ok(())
     }
 
@@ -735,6 +1191,10 @@ mod tests {
         ) -> Result<()> {
             Ok(())
         }
+
+        fn init_pid(&self, _container_id: &str) -> Result<Option<u32>> {
+            Ok(Some(std::process::id()))
+        }
     }
 
     #[tokio::test]
@@ -788,6 +1248,60 @@ mod tests {
         assert!(generated.contains("options ndots:5"));
     }
 
+    #[tokio::test]
+    async fn create_resolv_conf_uses_configured_default_dns_when_unspecified() {
+        let temp_dir = tempdir().unwrap();
+        let runtime = RuncRuntime::new(PathBuf::from("runc"), temp_dir.path().join("runtime"));
+        let mut manager = PodSandboxManager::new(
+            runtime,
+            temp_dir.path().join("pods"),
+            "registry.k8s.io/pause:3.9".to_string(),
+            CniConfig::default(),
+        );
+        manager.set_default_dns(&crate::config::DefaultDnsConfig {
+            servers: vec!["10.96.0.10".to_string()],
+            searches: vec!["svc.cluster.local".to_string(), "cluster.local".to_string()],
+            options: vec!["ndots:5".to_string()],
+        });
+        tokio::fs::create_dir_all(temp_dir.path().join("pods").join("pod-1"))
+            .await
+            .unwrap();
+
+        let empty = DNSConfig {
+            servers: vec![],
+            searches: vec![],
+            options: vec![],
+        };
+        for dns in [None, Some(&empty)] {
+            let resolv_path = manager.create_resolv_conf("pod-1", dns).await.unwrap();
+            assert_eq!(
+                tokio::fs::read_to_string(&resolv_path).await.unwrap(),
+                "search svc.cluster.local cluster.local\nnameserver 10.96.0.10\noptions ndots:5\n"
+            );
+        }
+
+        // sandbox 显式提供的 DNS 优先于默认值
+        let explicit = DNSConfig {
+            servers: vec!["1.1.1.1".to_string()],
+            searches: vec![],
+            options: vec![],
+        };
+        let resolv_path = manager
+            .create_resolv_conf("pod-1", Some(&explicit))
+            .await
+            .unwrap();
+        assert_eq!(
+            tokio::fs::read_to_string(&resolv_path).await.unwrap(),
+            "nameserver 1.1.1.1\n"
+        );
+
+        let invalid = crate::config::DefaultDnsConfig {
+            servers: vec!["dns.example.com".to_string()],
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
+
     #[tokio::test]
     async fn create_pause_container_propagates_pod_metadata_to_runtime() {
         let temp_dir = tempdir().unwrap();
@@ -823,6 +1337,7 @@ mod tests {
             cgroup_parent: Some("kubepods.slice".to_string()),
             sysctls: HashMap::from([("net.ipv4.ip_forward".to_string(), "1".to_string())]),
             namespace_options: None,
+            shm_size: crate::oci::spec::DEFAULT_SHM_SIZE,
             privileged: false,
             run_as_user: Some("1000".to_string()),
             run_as_group: Some(1000),
@@ -836,7 +1351,13 @@ mod tests {
         };
 
         let pause_id = manager
-            .create_pause_container("pod-1", &pod_config, Path::new("/var/run/netns/pod-1"))
+            .create_pause_container(
+                "pod-1",
+                &pod_config,
+                Some(Path::new("/var/run/netns/pod-1")),
+                None,
+                &mut OperationTimer::start(Operation::RunPodSandbox),
+            )
             .await
             .unwrap();
 
@@ -935,6 +1456,338 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn create_pause_container_with_pause_binary_needs_no_image() {
+        let temp_dir = tempdir().unwrap();
+        let runtime = RecordingRuntime::default();
+        let mut manager = PodSandboxManager::new(
+            runtime.clone(),
+            temp_dir.path().join("pods"),
+            String::new(),
+            CniConfig::default(),
+        );
+        manager.set_pause_binary(Some(PathBuf::from("/usr/libexec/crius/crius-pause")));
+        tokio::fs::create_dir_all(temp_dir.path().join("pods").join("pod-1"))
+            .await
+            .unwrap();
+        let pod_config = PodSandboxConfig {
+            name: "test-pod".to_string(),
+            namespace: "default".to_string(),
+            uid: "uid-1".to_string(),
+            hostname: "test-host".to_string(),
+            log_directory: None,
+            runtime_handler: "runc".to_string(),
+            labels: vec![],
+            annotations: vec![(
+                "io.kubernetes.cri.sandbox-image".to_string(),
+                "registry.example/pause:custom".to_string(),
+            )],
+            dns_config: None,
+            port_mappings: vec![],
+            network_config: None,
+            cgroup_parent: None,
+            sysctls: HashMap::new(),
+            namespace_options: None,
+            shm_size: crate::oci::spec::DEFAULT_SHM_SIZE,
+            privileged: false,
+            run_as_user: None,
+            run_as_group: None,
+            supplemental_groups: vec![],
+            readonly_rootfs: true,
+            no_new_privileges: None,
+            apparmor_profile: None,
+            selinux_label: None,
+            seccomp_profile: None,
+            linux_resources: None,
+        };
+
+        manager
+            .create_pause_container(
+                "pod-1",
+                &pod_config,
+                Some(Path::new("/var/run/netns/pod-1")),
+                None,
+                &mut OperationTimer::start(Operation::RunPodSandbox),
+            )
+            .await
+            .unwrap();
+
+        let created = runtime.take_created();
+        let (_, pause_config) = &created[0];
+        assert!(pause_config.image.is_empty());
+        assert_eq!(pause_config.command, vec!["/pause".to_string()]);
+        let pause_mount = pause_config
+            .mounts
+            .iter()
+            .find(|mount| mount.destination == Path::new("/pause"))
+            .unwrap();
+        assert_eq!(
+            pause_mount.source,
+            PathBuf::from("/usr/libexec/crius/crius-pause")
+        );
+        assert!(pause_mount.read_only);
+    }
+
+    #[tokio::test]
+    async fn pod_cgroup_applies_sandbox_limits_and_nests_pause_container() {
+        let temp_dir = tempdir().unwrap();
+        let runtime = RecordingRuntime::default();
+        let mut manager = PodSandboxManager::new(
+            runtime.clone(),
+            temp_dir.path().join("pods"),
+            "registry.k8s.io/pause:3.9".to_string(),
+            CniConfig::default(),
+        );
+        let cgroup_mount = temp_dir.path().join("cgroup");
+        std::fs::create_dir_all(&cgroup_mount).unwrap();
+        std::fs::write(cgroup_mount.join("cgroup.controllers"), "cpu memory pids").unwrap();
+        manager.cgroup_mount = cgroup_mount.clone();
+        manager.set_cgroup_root(Some("/kubepods".to_string()));
+        tokio::fs::create_dir_all(temp_dir.path().join("pods").join("pod-1"))
+            .await
+            .unwrap();
+        let mut pod_config = PodSandboxConfig {
+            name: "test-pod".to_string(),
+            namespace: "default".to_string(),
+            uid: "uid-1".to_string(),
+            hostname: "test-host".to_string(),
+            log_directory: None,
+            runtime_handler: "runc".to_string(),
+            labels: vec![],
+            annotations: vec![],
+            dns_config: None,
+            port_mappings: vec![],
+            network_config: None,
+            cgroup_parent: Some("burstable/pod-uid-1".to_string()),
+            sysctls: HashMap::new(),
+            namespace_options: None,
+            shm_size: crate::oci::spec::DEFAULT_SHM_SIZE,
+            privileged: false,
+            run_as_user: None,
+            run_as_group: None,
+            supplemental_groups: vec![],
+            readonly_rootfs: false,
+            no_new_privileges: None,
+            apparmor_profile: None,
+            selinux_label: None,
+            seccomp_profile: None,
+            linux_resources: Some(LinuxContainerResources {
+                memory_limit_in_bytes: 256 << 20,
+                ..Default::default()
+            }),
+        };
+
+        let cgroup_path = manager
+            .create_pod_cgroup("pod-1", &pod_config)
+            .unwrap()
+            .unwrap();
+        assert_eq!(cgroup_path, "burstable/pod-uid-1/crius-pod-pod-1");
+        let cgroup_dir = cgroup_mount
+            .join("kubepods")
+            .join("burstable")
+            .join("pod-uid-1")
+            .join("crius-pod-pod-1");
+        assert_eq!(
+            std::fs::read_to_string(cgroup_dir.join("memory.max")).unwrap(),
+            (256u64 << 20).to_string()
+        );
+
+        manager
+            .create_pause_container(
+                "pod-1",
+                &pod_config,
+                None,
+                Some(&cgroup_path),
+                &mut OperationTimer::start(Operation::RunPodSandbox),
+            )
+            .await
+            .unwrap();
+        let created = runtime.take_created();
+        assert_eq!(
+            created[0].1.cgroup_parent.as_deref(),
+            Some("burstable/pod-uid-1/crius-pod-pod-1/pause-pod-1")
+        );
+
+        // systemd 格式的 cgroup_parent 由 systemd 管理层级
+        pod_config.cgroup_parent = Some("kubepods-burstable.slice:crius:pod-1".to_string());
+        assert_eq!(
+            manager.create_pod_cgroup("pod-1", &pod_config).unwrap(),
+            None
+        );
+
+        // CRI 未指定 cgroup_parent 时不创建 Pod 级 cgroup，pause 容器落在默认父 cgroup 下
+        pod_config.cgroup_parent = None;
+        assert_eq!(
+            manager.create_pod_cgroup("pod-1", &pod_config).unwrap(),
+            None
+        );
+        manager
+            .create_pause_container(
+                "pod-1",
+                &pod_config,
+                None,
+                None,
+                &mut OperationTimer::start(Operation::RunPodSandbox),
+            )
+            .await
+            .unwrap();
+        let created = runtime.take_created();
+        assert_eq!(created[1].1.cgroup_parent.as_deref(), Some("crius"));
+    }
+
+    #[tokio::test]
+    async fn create_pod_sandbox_rolls_back_pod_cgroup_when_setup_fails() {
+        let temp_dir = tempdir().unwrap();
+        let mut manager = PodSandboxManager::new(
+            RecordingRuntime::default(),
+            temp_dir.path().join("pods"),
+            "registry.k8s.io/pause:3.9".to_string(),
+            CniConfig::default(),
+        );
+        let cgroup_mount = temp_dir.path().join("cgroup");
+        std::fs::create_dir_all(&cgroup_mount).unwrap();
+        std::fs::write(cgroup_mount.join("cgroup.controllers"), "cpu memory pids").unwrap();
+        manager.set_cgroup_mount(cgroup_mount.clone());
+        // 名称中的 `/` 使 `ip netns add` 失败，此时 Pod 级 cgroup 已经创建
+        let pod_config = PodSandboxConfig {
+            name: "bad/name".to_string(),
+            namespace: "default".to_string(),
+            uid: "uid-1".to_string(),
+            hostname: "test-host".to_string(),
+            log_directory: None,
+            runtime_handler: "runc".to_string(),
+            labels: vec![],
+            annotations: vec![],
+            dns_config: None,
+            port_mappings: vec![],
+            network_config: None,
+            cgroup_parent: Some("kubepods/pod-uid-1".to_string()),
+            sysctls: HashMap::new(),
+            namespace_options: None,
+            shm_size: crate::oci::spec::DEFAULT_SHM_SIZE,
+            privileged: false,
+            run_as_user: None,
+            run_as_group: None,
+            supplemental_groups: vec![],
+            readonly_rootfs: false,
+            no_new_privileges: None,
+            apparmor_profile: None,
+            selinux_label: None,
+            seccomp_profile: None,
+            linux_resources: None,
+        };
+
+        assert!(manager.create_pod_sandbox(pod_config).await.is_err());
+        let pod_cgroup_parent = cgroup_mount.join("kubepods").join("pod-uid-1");
+        assert_eq!(std::fs::read_dir(&pod_cgroup_parent).unwrap().count(), 0);
+        assert_eq!(
+            std::fs::read_dir(temp_dir.path().join("pods"))
+                .unwrap()
+                .count(),
+            0
+        );
+        assert!(manager.list_pod_sandboxes().is_empty());
+    }
+
+    fn elf_with_program_headers(types: &[u32]) -> Vec<u8> {
+        let mut elf = vec![0u8; 64];
+        elf[..4].copy_from_slice(b"\x7fELF");
+        elf[4] = 2;
+        elf[5] = 1;
+        elf[0x20..0x28].copy_from_slice(&64u64.to_le_bytes());
+        elf[0x36..0x38].copy_from_slice(&56u16.to_le_bytes());
+        elf[0x38..0x3a].copy_from_slice(&(types.len() as u16).to_le_bytes());
+        for p_type in types {
+            let mut header = vec![0u8; 56];
+            header[..4].copy_from_slice(&p_type.to_le_bytes());
+            elf.extend(header);
+        }
+        elf
+    }
+
+    #[test]
+    fn validate_pause_binary_requires_static_executable() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempdir().unwrap();
+        let write_executable = |name: &str, contents: &[u8]| {
+            let path = temp_dir.path().join(name);
+            std::fs::write(&path, contents).unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+            path
+        };
+
+        let static_pause = write_executable("static", &elf_with_program_headers(&[1, 4]));
+        validate_pause_binary(&static_pause).unwrap();
+
+        let dynamic_pause = write_executable("dynamic", &elf_with_program_headers(&[6, 3, 1]));
+        let err = validate_pause_binary(&dynamic_pause).unwrap_err();
+        assert!(err.to_string().contains("dynamically linked"), "{}", err);
+
+        let script = write_executable("script", b"#!/bin/sh\nsleep infinity\n");
+        assert!(validate_pause_binary(&script).is_err());
+        assert!(validate_pause_binary(Path::new("relative/pause")).is_err());
+        assert!(validate_pause_binary(&temp_dir.path().join("missing")).is_err());
+    }
+
+    #[tokio::test]
+    async fn rootless_pod_sandbox_lets_pause_container_create_netns() {
+        let temp_dir = tempdir().unwrap();
+        let runtime = RecordingRuntime::default();
+        let mut manager = PodSandboxManager::new(
+            runtime.clone(),
+            temp_dir.path().join("pods"),
+            "registry.k8s.io/pause:3.9".to_string(),
+            CniConfig::default(),
+        );
+        manager.set_rootless_network(Some(crate::rootless::NetworkMode::None));
+        let config = PodSandboxConfig {
+            name: "rootless-pod".to_string(),
+            namespace: "default".to_string(),
+            uid: "uid-1".to_string(),
+            hostname: "test-host".to_string(),
+            log_directory: None,
+            runtime_handler: "runc".to_string(),
+            labels: vec![],
+            annotations: vec![],
+            dns_config: None,
+            port_mappings: vec![],
+            network_config: None,
+            cgroup_parent: None,
+            sysctls: HashMap::new(),
+            namespace_options: None,
+            shm_size: crate::oci::spec::DEFAULT_SHM_SIZE,
+            privileged: false,
+            run_as_user: None,
+            run_as_group: None,
+            supplemental_groups: vec![],
+            readonly_rootfs: false,
+            no_new_privileges: None,
+            apparmor_profile: None,
+            selinux_label: None,
+            seccomp_profile: None,
+            linux_resources: None,
+        };
+
+        let pod_id = manager.create_pod_sandbox(config).await.unwrap();
+
+        let created = runtime.take_created();
+        assert_eq!(created.len(), 1);
+        assert_eq!(created[0].0, format!("pause-{}", pod_id));
+        assert_eq!(created[0].1.namespace_paths.network, None);
+        let pod = manager.get_pod_sandbox_cloned(&pod_id).unwrap();
+        assert_eq!(
+            pod.netns_path,
+            PathBuf::from(format!("/proc/{}/ns/net", std::process::id()))
+        );
+        assert_eq!(pod.state, PodSandboxState::Ready);
+        assert!(pod.ip.is_empty());
+        assert!(!Path::new("/var/run/netns/crius-default-rootless-pod").exists());
+
+        manager.remove_pod_sandbox(&pod_id).await.unwrap();
+        assert!(manager.get_pod_sandbox_cloned(&pod_id).is_none());
+    }
+
     // 注意：这些测试需要root权限和runc环境
     #[tokio::test]
     #[ignore = "requires root and runc"]
@@ -964,6 +1817,7 @@ mod tests {
             cgroup_parent: None,
             sysctls: HashMap::new(),
             namespace_options: None,
+            shm_size: crate::oci::spec::DEFAULT_SHM_SIZE,
             privileged: false,
             run_as_user: None,
             run_as_group: None,
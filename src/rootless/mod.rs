@@ -63,6 +63,129 @@ pub enum NetworkMode {
     None,
 }
 
+/// slirp4netns 分配给 Pod 的固定地址
+pub const SLIRP4NETNS_POD_IP: &str = "10.0.2.100";
+
+/// Pod 的用户态网络进程（slirp4netns/pasta），随 sandbox 持久化
+///
+/// crius 重启后这些进程不再是它的子进程，只能按 pid 回收；`start_time` 用于确认
+/// pid 没有被其他进程复用。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkHelper {
+    pub pid: u32,
+    pub start_time: u64,
+}
+
+impl NetworkHelper {
+    /// 记录刚启动的辅助进程
+    pub fn of(child: &std::process::Child) -> Option<Self> {
+        let pid = child.id();
+        Some(Self {
+            pid,
+            start_time: crate::utils::process_start_time(i32::try_from(pid).ok()?)?,
+        })
+    }
+
+    /// 进程仍在运行且 pid 未被复用
+    pub fn is_running(&self) -> bool {
+        i32::try_from(self.pid)
+            .ok()
+            .and_then(crate::utils::process_start_time)
+            == Some(self.start_time)
+    }
+
+    /// 进程仍在运行时发送 SIGKILL，返回是否发送
+    pub fn kill(&self) -> bool {
+        if !self.is_running() {
+            return false;
+        }
+        match nix::sys::signal::kill(
+            nix::unistd::Pid::from_raw(self.pid as i32),
+            nix::sys::signal::Signal::SIGKILL,
+        ) {
+            Ok(()) => {
+                debug!("Killed network helper {}", self.pid);
+                true
+            }
+            Err(e) => {
+                warn!("Failed to kill network helper {}: {}", self.pid, e);
+                false
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for NetworkMode {
+    type Err = String;
+
+    /// 配置文件中可选的 rootless 网络：slirp4netns、pasta 或 none
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value {
+            "slirp4netns" => Ok(Self::Slirp4netns),
+            "pasta" => Ok(Self::Pasta),
+            "none" => Ok(Self::None),
+            other => Err(format!(
+                "unsupported rootless network {:?}, expected slirp4netns, pasta or none",
+                other
+            )),
+        }
+    }
+}
+
+impl NetworkMode {
+    /// 为 Pod 提供用户态网络的程序，无网络时为 `None`
+    pub fn helper_binary(&self) -> Option<&'static str> {
+        match self {
+            Self::Slirp4netns => Some("slirp4netns"),
+            Self::Pasta => Some("pasta"),
+            Self::Rootlesskit | Self::None => None,
+        }
+    }
+
+    /// 用户态网络为 Pod 配置的地址，无法预知时为 `None`
+    pub fn pod_ip(&self) -> Option<std::net::IpAddr> {
+        match self {
+            Self::Slirp4netns => SLIRP4NETNS_POD_IP.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// 把 `pid` 所在的网络命名空间接入用户态网络，返回需要随 Pod 回收的辅助进程
+    ///
+    /// 辅助进程以 crius 的身份运行，通过 `/proc/<pid>/ns/net` 进入 pause 容器创建的
+    /// 网络命名空间，不需要 `ip netns add` 等特权操作。
+    pub fn spawn_helper(&self, pid: u32) -> Result<Option<std::process::Child>> {
+        let Some(binary) = self.helper_binary() else {
+            return Ok(None);
+        };
+        let pid = pid.to_string();
+        let args: Vec<&str> = match self {
+            Self::Slirp4netns => vec![
+                "--configure",
+                "--mtu=65520",
+                "--disable-host-loopback",
+                &pid,
+                "tap0",
+            ],
+            _ => vec!["--config-net", "--foreground", &pid],
+        };
+        let child = Command::new(binary)
+            .args(&args)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .with_context(|| format!("Failed to start {}", binary))?;
+        debug!(
+            "Started {} (pid {}) for network namespace of pid {}",
+            binary,
+            child.id(),
+            pid
+        );
+        Ok(Some(child))
+    }
+}
+
 impl Default for RootlessConfig {
     fn default() -> Self {
         Self {
@@ -94,7 +217,7 @@ impl RootlessConfig {
             self.uid_mappings = vec![
                 IdMapping {
                     container_id: 0,
-                    host_id: std::process::id(),
+                    host_id: RootlessManager::current_uid(),
                     size: 1,
                 },
                 IdMapping {
@@ -108,7 +231,7 @@ impl RootlessConfig {
             self.gid_mappings = vec![
                 IdMapping {
                     container_id: 0,
-                    host_id: std::process::id(),
+                    host_id: RootlessManager::current_gid(),
                     size: 1,
                 },
                 IdMapping {
@@ -143,6 +266,7 @@ impl RootlessConfig {
 }
 
 /// Rootless管理器
+#[derive(Debug, Clone)]
 pub struct RootlessManager {
     /// 配置
     config: RootlessConfig,
@@ -308,6 +432,8 @@ impl RootlessManager {
                 sysctl: None,
                 mount_label: None,
                 intel_rdt: None,
+                masked_paths: None,
+                readonly_paths: None,
             });
         }
 
@@ -564,5 +690,22 @@ mod tests {
         let config = RootlessConfig::new().with_network_mode(NetworkMode::Slirp4netns);
 
         assert_eq!(config.network_mode, NetworkMode::Slirp4netns);
+        assert_eq!("pasta".parse(), Ok(NetworkMode::Pasta));
+        assert_eq!("none".parse::<NetworkMode>().unwrap().helper_binary(), None);
+        assert!("rootlesskit".parse::<NetworkMode>().is_err());
+    }
+
+    #[test]
+    fn enable_maps_container_root_to_current_user() {
+        let config = RootlessConfig::new().enable();
+
+        assert_eq!(
+            config.uid_mappings[0].host_id,
+            RootlessManager::current_uid()
+        );
+        assert_eq!(
+            config.gid_mappings[0].host_id,
+            RootlessManager::current_gid()
+        );
     }
 }
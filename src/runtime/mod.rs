@@ -1,5 +1,6 @@
 use anyhow::{Context, Result};
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
+use nix::sys::signal::Signal;
 use nix::sys::stat::{major, makedev, minor, mknod, stat, Mode, SFlag};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -10,7 +11,13 @@ use std::path::{Path, PathBuf};
 use std::process::{Command, Output, Stdio};
 use std::sync::Arc;
 
-use crate::cgroups::{to_oci_resources, CgroupManager, CpuLimit, MemoryLimit, ResourceLimits};
+use crate::cgroups::{
+    pids_limit_from_unified, to_oci_resources, CgroupManager, CpuLimit, HugepageLimit, MemoryLimit,
+    PidsLimit, ResourceLimits,
+};
+use crate::config::{
+    ProcessDefaultsConfig, ProtectedPathsConfig, OOM_SCORE_ADJ_MAX, OOM_SCORE_ADJ_MIN,
+};
 use crate::oci::spec::{
     Device as OciDevice, Linux, LinuxCapabilities, LinuxDeviceCgroup, LinuxResources, Mount,
     Namespace as OciNamespace, Process, Root, Spec, User,
@@ -22,6 +29,15 @@ use crate::proto::runtime::v1::{
 pub mod shim_manager;
 pub use shim_manager::{default_shim_work_dir, ShimConfig, ShimManager, ShimProcess};
 
+/// 未指定停止超时时的默认优雅退出时间（秒）
+pub const DEFAULT_STOP_TIMEOUT_SECS: u32 = 10;
+/// 等待容器退出时的轮询间隔
+const STOP_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+/// SIGKILL 之后等待进程被回收的默认上限
+pub const DEFAULT_KILL_WAIT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+/// 记录容器优雅停止信号的 OCI annotation，值来自 image config 的 StopSignal
+pub const STOP_SIGNAL_ANNOTATION: &str = "org.opencontainers.image.stopSignal";
+
 const INTERNAL_CHECKPOINT_RESTORE_KEY: &str = "io.crius.internal/checkpoint-restore";
 const INTERNAL_CONTAINER_STATE_KEY: &str = "io.crius.internal/container-state";
 
@@ -34,6 +50,10 @@ pub trait ContainerRuntime {
     fn start_container(&self, container_id: &str) -> Result<()>;
 
     /// 停止容器
+    ///
+    /// `timeout` 为优雅退出等待时间（秒）：`Some(0)` 直接发送 SIGKILL；
+    /// `Some(n)` 发送 SIGTERM 并最多等待 n 秒后 SIGKILL；`None` 使用运行时默认值
+    /// （`DEFAULT_STOP_TIMEOUT_SECS`）。CRI 请求中的 timeout 总是以 `Some` 传入，不做截断。
     fn stop_container(&self, container_id: &str, timeout: Option<u32>) -> Result<()>;
 
     /// 删除容器
@@ -54,6 +74,11 @@ pub trait ContainerRuntime {
         container_id: &str,
         resources: &LinuxContainerResources,
     ) -> Result<()>;
+
+    /// 获取容器 init 进程的 PID，运行时无法提供时为 `None`
+    fn init_pid(&self, _container_id: &str) -> Result<Option<u32>> {
+        Ok(None)
+    }
 }
 
 /// 容器配置
@@ -89,6 +114,10 @@ pub struct ContainerConfig {
     pub namespace_paths: NamespacePaths,
     pub linux_resources: Option<LinuxContainerResources>,
     pub devices: Vec<DeviceMapping>,
+    /// `/dev/shm` 大小（字节），未设置时使用 [`crate::oci::spec::DEFAULT_SHM_SIZE`]
+    pub shm_size: Option<u64>,
+    /// sandbox 级 `/dev/shm` 在宿主机上的路径，设置后 bind 挂载它而不是新建 tmpfs
+    pub shm_path: Option<PathBuf>,
     pub rootfs: PathBuf,
 }
 
@@ -125,6 +154,53 @@ pub struct DeviceMapping {
     pub permissions: String,
 }
 
+/// 解析 StopSignal，接受 `SIGQUIT`、`quit` 形式的名称或信号编号
+pub fn parse_stop_signal(raw: &str) -> Result<Signal> {
+    let raw = raw.trim();
+    let signal = match raw.parse::<i32>() {
+        Ok(number) => Signal::try_from(number).ok(),
+        Err(_) => {
+            let name = raw.to_ascii_uppercase();
+            if name.starts_with("SIG") {
+                name.parse().ok()
+            } else {
+                format!("SIG{}", name).parse().ok()
+            }
+        }
+    };
+    signal.ok_or_else(|| anyhow::anyhow!("unknown stop signal {:?}", raw))
+}
+
+/// 读取普通文件末尾至多 `max_bytes` 字节，非普通文件（FIFO、设备等）视为空
+fn read_file_tail(path: &Path, max_bytes: u64) -> std::io::Result<Vec<u8>> {
+    use std::io::{Seek, SeekFrom};
+
+    if !std::fs::metadata(path)?.is_file() {
+        return Ok(Vec::new());
+    }
+    let mut file = std::fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(max_bytes)))?;
+    let mut tail = Vec::new();
+    file.take(max_bytes).read_to_end(&mut tail)?;
+    Ok(tail)
+}
+
+/// 本地镜像元数据中与容器创建相关的 image config 字段
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ImageConfigMetadata {
+    /// image config 中的 Labels（镜像元数据以 annotations 保存）
+    #[serde(rename = "annotations")]
+    pub labels: HashMap<String, String>,
+    /// image config 中的 ExposedPorts，如 `80/tcp`
+    pub exposed_ports: Vec<String>,
+    /// image config 中的 StopSignal
+    pub stop_signal: Option<String>,
+    /// image config 中的 Env（`KEY=VALUE`）
+    pub env: Vec<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct RestoreCheckpointMetadata {
     checkpoint_location: String,
@@ -147,8 +223,40 @@ pub enum ContainerStatus {
     Unknown,
 }
 
+/// 容器在 SIGKILL 后仍未在限定时间内退出（如进程处于不可中断睡眠、runc 卡住）
+#[derive(Debug, thiserror::Error)]
+#[error("Container {container_id} did not exit within {timeout:?} after SIGKILL")]
+pub struct KillTimeoutError {
+    pub container_id: String,
+    pub timeout: std::time::Duration,
+}
+
 const CRIO_LABELS_ANNOTATION: &str = "io.kubernetes.cri-o.Labels";
 
+/// 与 runc/containerd 默认值一致的屏蔽路径
+pub const DEFAULT_MASKED_PATHS: &[&str] = &[
+    "/proc/asound",
+    "/proc/acpi",
+    "/proc/kcore",
+    "/proc/keys",
+    "/proc/latency_stats",
+    "/proc/timer_list",
+    "/proc/timer_stats",
+    "/proc/sched_debug",
+    "/proc/scsi",
+    "/sys/firmware",
+    "/sys/devices/virtual/powercap",
+];
+
+/// 与 runc/containerd 默认值一致的只读路径
+pub const DEFAULT_READONLY_PATHS: &[&str] = &[
+    "/proc/bus",
+    "/proc/fs",
+    "/proc/irq",
+    "/proc/sys",
+    "/proc/sysrq-trigger",
+];
+
 /// runc容器状态
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct RuncState {
@@ -163,6 +271,12 @@ struct RuncState {
     owner: String,
 }
 
+/// runc list 输出条目
+#[derive(Debug, Clone, Deserialize)]
+struct RuncListEntry {
+    id: String,
+}
+
 /// 使用 runc 作为容器运行时
 #[derive(Debug, Clone)]
 pub struct RuncRuntime {
@@ -170,6 +284,16 @@ pub struct RuncRuntime {
     root: PathBuf,
     image_storage_root: PathBuf,
     shim_manager: Option<Arc<ShimManager>>,
+    process_defaults: ProcessDefaultsConfig,
+    protected_paths: ProtectedPathsConfig,
+    /// bundle 目录权限
+    dir_mode: u32,
+    /// rootless 模式下为每个容器加入用户命名空间与 ID 映射
+    rootless: Option<crate::rootless::RootlessManager>,
+    /// cgroupfs 驱动下 cgroupsPath 的基准目录
+    cgroup_root: Option<String>,
+    /// SIGKILL 之后等待进程被回收的上限
+    kill_wait_timeout: std::time::Duration,
 }
 
 impl RuncRuntime {
@@ -263,6 +387,27 @@ impl RuncRuntime {
             .with_context(|| format!("Failed to parse OCI config {}", config_path.display()))
     }
 
+    /// 优雅停止时发送的信号：bundle 中记录的 StopSignal，未记录或无法解析时为 SIGTERM
+    fn container_stop_signal(&self, container_id: &str) -> Signal {
+        let raw = self
+            .load_bundle_config_value(container_id)
+            .ok()
+            .and_then(|config| {
+                config
+                    .get("annotations")?
+                    .get(STOP_SIGNAL_ANNOTATION)?
+                    .as_str()
+                    .map(str::to_string)
+            });
+        match raw {
+            Some(raw) => parse_stop_signal(&raw).unwrap_or_else(|err| {
+                warn!("Container {}: {}, using SIGTERM", container_id, err);
+                Signal::SIGTERM
+            }),
+            None => Signal::SIGTERM,
+        }
+    }
+
     fn container_uses_terminal(&self, container_id: &str) -> Result<bool> {
         let config = self.load_bundle_config_value(container_id)?;
         let from_process = config
@@ -370,12 +515,23 @@ impl RuncRuntime {
     }
 
     fn build_user(config: &ContainerConfig) -> Option<User> {
-        let user = config.user.as_ref()?;
         let additional_gids = if config.supplemental_groups.is_empty() {
             None
         } else {
             Some(config.supplemental_groups.clone())
         };
+        // 未指定用户时仍以 root 运行，但要保留 run_as_group 与附加组
+        let Some(user) = config.user.as_ref() else {
+            if config.run_as_group.is_none() && additional_gids.is_none() {
+                return None;
+            }
+            return Some(User {
+                uid: 0,
+                gid: config.run_as_group.unwrap_or(0),
+                additional_gids,
+                username: None,
+            });
+        };
 
         if let Ok(uid) = user.parse::<u32>() {
             Some(User {
@@ -487,50 +643,38 @@ impl RuncRuntime {
         }
     }
 
+    /// CRI 指定的 oom_score_adj 优先，未指定（0）时使用默认配置
+    fn effective_oom_score_adj(&self, config: &ContainerConfig) -> Option<i32> {
+        config
+            .linux_resources
+            .as_ref()
+            .map(|resources| resources.oom_score_adj)
+            .filter(|value| *value != 0)
+            .or(self.process_defaults.oom_score_adj)
+            .map(|value| value.clamp(OOM_SCORE_ADJ_MIN, OOM_SCORE_ADJ_MAX) as i32)
+    }
+
+    fn default_rlimits(&self) -> Option<Vec<crate::oci::spec::Rlimit>> {
+        (!self.process_defaults.rlimits.is_empty()).then(|| {
+            self.process_defaults
+                .rlimits
+                .iter()
+                .map(|rlimit| crate::oci::spec::Rlimit {
+                    rtype: rlimit.rlimit_type.clone(),
+                    hard: rlimit.hard,
+                    soft: rlimit.soft,
+                })
+                .collect()
+        })
+    }
+
     fn linux_resources_to_oci(resources: &LinuxContainerResources) -> LinuxResources {
-        let limits = ResourceLimits {
-            cpu: Some(CpuLimit {
-                shares: (resources.cpu_shares > 0).then_some(resources.cpu_shares as u64),
-                quota: (resources.cpu_quota > 0).then_some(resources.cpu_quota),
-                period: (resources.cpu_period > 0).then_some(resources.cpu_period as u64),
-                realtime_runtime: None,
-                realtime_period: None,
-                cpus: (!resources.cpuset_cpus.is_empty()).then(|| resources.cpuset_cpus.clone()),
-                mems: (!resources.cpuset_mems.is_empty()).then(|| resources.cpuset_mems.clone()),
-            }),
-            memory: Some(MemoryLimit {
-                limit: (resources.memory_limit_in_bytes > 0)
-                    .then_some(resources.memory_limit_in_bytes),
-                reservation: None,
-                swap: (resources.memory_swap_limit_in_bytes > 0)
-                    .then_some(resources.memory_swap_limit_in_bytes),
-                kernel: None,
-                kernel_tcp: None,
-                swappiness: None,
-                disable_oom_killer: None,
-                use_hierarchy: None,
-            }),
-            blkio: None,
-            network: None,
-            pids: None,
-        };
+        let limits = Self::cri_to_limits(resources);
 
         let mut oci_resources = to_oci_resources(&limits);
         if !resources.unified.is_empty() {
             oci_resources.unified = Some(resources.unified.clone());
         }
-        if !resources.hugepage_limits.is_empty() {
-            oci_resources.hugepage_limits = Some(
-                resources
-                    .hugepage_limits
-                    .iter()
-                    .map(|limit| crate::oci::spec::LinuxHugepageLimit {
-                        page_size: limit.page_size.clone(),
-                        limit: limit.limit,
-                    })
-                    .collect(),
-            );
-        }
 
         oci_resources
     }
@@ -671,6 +815,15 @@ impl RuncRuntime {
         ))
     }
 
+    /// 读取本地镜像的 Labels 与 ExposedPorts
+    pub fn image_config_metadata(&self, image_ref: &str) -> Result<ImageConfigMetadata> {
+        let metadata_path = self.resolve_image_dir(image_ref)?.join("metadata.json");
+        let metadata_bytes = std::fs::read(&metadata_path)
+            .with_context(|| format!("Failed to read image metadata: {:?}", metadata_path))?;
+        serde_json::from_slice(&metadata_bytes)
+            .with_context(|| format!("Failed to parse image metadata: {:?}", metadata_path))
+    }
+
     fn prepare_rootfs_from_image(
         &self,
         image_ref: &str,
@@ -710,6 +863,28 @@ impl RuncRuntime {
                 .with_context(|| format!("Failed to unpack layer archive: {:?}", layer_file))?;
         }
 
+        Self::prepare_minimal_rootfs(rootfs_dir)?;
+        info!(
+            "Prepared rootfs for container {} from image {}",
+            container_id, image_ref
+        );
+        Ok(())
+    }
+
+    /// 不依赖镜像的空 rootfs，供内置 pause 进程使用
+    fn prepare_scratch_rootfs(rootfs_dir: &Path, container_id: &str) -> Result<()> {
+        if rootfs_dir.exists() {
+            std::fs::remove_dir_all(rootfs_dir)
+                .with_context(|| format!("Failed to clean rootfs directory: {:?}", rootfs_dir))?;
+        }
+        std::fs::create_dir_all(rootfs_dir)
+            .with_context(|| format!("Failed to create rootfs directory: {:?}", rootfs_dir))?;
+        Self::prepare_minimal_rootfs(rootfs_dir)?;
+        info!("Prepared scratch rootfs for container {}", container_id);
+        Ok(())
+    }
+
+    fn prepare_minimal_rootfs(rootfs_dir: &Path) -> Result<()> {
         // Ensure minimum runtime paths exist for scratch-like images (e.g. pause).
         std::fs::create_dir_all(rootfs_dir.join("dev"))
             .context("Failed to create /dev in rootfs")?;
@@ -729,16 +904,12 @@ impl RuncRuntime {
             makedev(1, 3),
         )
         .context("Failed to create /dev/null char device in rootfs")?;
-
-        info!(
-            "Prepared rootfs for container {} from image {}",
-            container_id, image_ref
-        );
         Ok(())
     }
 
     fn spec_from_restore_template(
         &self,
+        container_id: &str,
         config: &ContainerConfig,
         restore: &RestoreCheckpointMetadata,
     ) -> Result<Spec> {
@@ -778,7 +949,7 @@ impl RuncRuntime {
 
         if let Some(linux) = spec.linux.as_mut() {
             linux.namespaces = Some(self.build_namespaces(config));
-            linux.cgroups_path = config.cgroup_parent.clone();
+            linux.cgroups_path = self.cgroups_path(config, container_id);
             linux.seccomp = self.load_seccomp_profile(config.seccomp_profile.as_ref())?;
             linux.mount_label = config.selinux_label.clone();
             if !config.sysctls.is_empty() {
@@ -932,6 +1103,12 @@ impl RuncRuntime {
             root,
             image_storage_root,
             shim_manager: None,
+            process_defaults: ProcessDefaultsConfig::default(),
+            protected_paths: ProtectedPathsConfig::default(),
+            dir_mode: crate::utils::DEFAULT_DIR_MODE,
+            rootless: None,
+            cgroup_root: None,
+            kill_wait_timeout: DEFAULT_KILL_WAIT_TIMEOUT,
         }
     }
 
@@ -956,9 +1133,73 @@ impl RuncRuntime {
             root,
             image_storage_root,
             shim_manager: Some(shim_manager),
+            process_defaults: ProcessDefaultsConfig::default(),
+            protected_paths: ProtectedPathsConfig::default(),
+            dir_mode: crate::utils::DEFAULT_DIR_MODE,
+            rootless: None,
+            cgroup_root: None,
+            kill_wait_timeout: DEFAULT_KILL_WAIT_TIMEOUT,
         }
     }
 
+    /// 设置容器进程默认配置（oom_score_adj、rlimits）
+    pub fn set_process_defaults(&mut self, defaults: ProcessDefaultsConfig) {
+        self.process_defaults = defaults;
+    }
+
+    /// 设置追加的屏蔽/只读路径
+    pub fn set_protected_paths(&mut self, paths: ProtectedPathsConfig) {
+        self.protected_paths = paths;
+    }
+
+    /// 设置 bundle 目录权限
+    pub fn set_dir_mode(&mut self, mode: u32) {
+        self.dir_mode = mode;
+    }
+
+    /// 设置 rootless 模式，`None` 时按特权模式生成 spec
+    pub fn set_rootless(&mut self, rootless: Option<crate::rootless::RootlessManager>) {
+        self.rootless = rootless;
+    }
+
+    /// 设置 cgroup root，空字符串表示直接使用 CRI 下发的 cgroup_parent
+    pub fn set_cgroup_root(&mut self, cgroup_root: &str) {
+        self.cgroup_root = Some(cgroup_root.to_string()).filter(|root| !root.is_empty());
+    }
+
+    /// 设置 SIGKILL 之后等待容器退出的上限
+    pub fn set_kill_wait_timeout(&mut self, timeout: std::time::Duration) {
+        self.kill_wait_timeout = timeout;
+    }
+
+    fn cgroups_path(&self, config: &ContainerConfig, container_id: &str) -> Option<String> {
+        crate::cgroups::cgroups_path(
+            self.cgroup_root.as_deref(),
+            config.cgroup_parent.as_deref(),
+            container_id,
+        )
+    }
+
+    /// 非特权容器的 maskedPaths/readonlyPaths：OCI 默认值加上配置追加项
+    fn protected_paths(&self, privileged: bool) -> (Option<Vec<String>>, Option<Vec<String>>) {
+        if privileged {
+            return (None, None);
+        }
+        let merge = |defaults: &[&str], extra: &[String]| {
+            let mut paths: Vec<String> = defaults.iter().map(|path| path.to_string()).collect();
+            for path in extra {
+                if !paths.contains(path) {
+                    paths.push(path.clone());
+                }
+            }
+            Some(paths)
+        };
+        (
+            merge(DEFAULT_MASKED_PATHS, &self.protected_paths.masked_paths),
+            merge(DEFAULT_READONLY_PATHS, &self.protected_paths.readonly_paths),
+        )
+    }
+
     /// 启用shim支持
     pub fn enable_shim(&mut self, config: ShimConfig) {
         self.shim_manager = Some(Arc::new(ShimManager::new(config)));
@@ -1035,7 +1276,7 @@ impl RuncRuntime {
     }
 
     /// 创建OCI配置
-    fn create_spec(&self, config: &ContainerConfig, _container_id: &str) -> Result<Spec> {
+    fn create_spec(&self, config: &ContainerConfig, container_id: &str) -> Result<Spec> {
         let mut spec = Spec::new("1.0.2");
 
         // 设置root配置
@@ -1076,8 +1317,8 @@ impl RuncRuntime {
                 &Self::default_capabilities(),
                 config.capabilities.as_ref(),
             )),
-            rlimits: None,
-            oom_score_adj: None,
+            rlimits: self.default_rlimits(),
+            oom_score_adj: self.effective_oom_score_adj(config),
             scheduler: None,
             no_new_privileges: Some(config.no_new_privileges.unwrap_or(!config.privileged)),
             apparmor_profile: config.apparmor_profile.clone(),
@@ -1122,6 +1363,12 @@ impl RuncRuntime {
                 m.destination != "/sys/fs/cgroup"
                     && (keep_hugepages_mount || m.destination != "/dev/hugepages")
             })
+            .map(|m| match (&config.shm_path, config.shm_size) {
+                _ if m.destination != "/dev/shm" => m,
+                (Some(path), _) => Spec::sandbox_shm_mount(path),
+                (None, Some(size)) => Spec::shm_mount(size),
+                (None, None) => m,
+            })
             .collect();
         all_mounts.extend(custom_mounts);
         spec.mounts = Some(all_mounts);
@@ -1150,30 +1397,36 @@ impl RuncRuntime {
                 unified: None,
             });
 
-        if !config.devices.is_empty() {
-            let (extra_devices, extra_cgroup_rules) =
-                Self::device_mappings_to_oci(&config.devices)?;
-            devices.extend(extra_devices);
-            resources.devices = Some(extra_cgroup_rules);
-        }
-
-        if resources.devices.is_none() {
-            resources.devices = Some(vec![LinuxDeviceCgroup {
+        // 特权容器放行全部设备，其余容器默认拒绝，仅放行标准设备与显式映射的设备
+        let mut device_rules = if config.privileged {
+            vec![LinuxDeviceCgroup {
                 allow: true,
                 device_type: None,
                 major: None,
                 minor: None,
                 access: Some("rwm".to_string()),
-            }]);
+            }]
+        } else {
+            Spec::default_device_cgroup_rules()
+        };
+        if !config.devices.is_empty() {
+            let (extra_devices, extra_cgroup_rules) =
+                Self::device_mappings_to_oci(&config.devices)?;
+            devices.extend(extra_devices);
+            if !config.privileged {
+                device_rules.extend(extra_cgroup_rules);
+            }
         }
+        resources.devices = Some(device_rules);
 
+        let (masked_paths, readonly_paths) = self.protected_paths(config.privileged);
         spec.linux = Some(Linux {
             namespaces: Some(self.build_namespaces(config)),
             uid_mappings: None,
             gid_mappings: None,
             devices: Some(devices),
             net_devices: None,
-            cgroups_path: config.cgroup_parent.clone(),
+            cgroups_path: self.cgroups_path(config, container_id),
             resources: Some(resources),
             rootfs_propagation: None,
             seccomp: self.load_seccomp_profile(config.seccomp_profile.as_ref())?,
@@ -1184,6 +1437,8 @@ impl RuncRuntime {
             },
             mount_label: config.selinux_label.clone(),
             intel_rdt: None,
+            masked_paths,
+            readonly_paths,
         });
 
         // 设置注解
@@ -1206,7 +1461,8 @@ impl RuncRuntime {
         let bundle_path = self.bundle_path(container_id);
 
         // 创建bundle目录
-        std::fs::create_dir_all(&bundle_path).context("Failed to create bundle directory")?;
+        crate::utils::ensure_private_dir(&bundle_path, self.dir_mode)
+            .context("Failed to create bundle directory")?;
 
         // 保存config.json
         spec.save(self.config_path(container_id))?;
@@ -1214,6 +1470,19 @@ impl RuncRuntime {
         // 当前运行时使用 OCI spec.root.path 作为 rootfs 来源，bundle 内不再强制准备 rootfs 目录。
         let _ = rootfs;
 
+        // 与 Docker 一致，缺失的工作目录在 rootfs 内创建，避免 runc 启动时报难以理解的 chdir 错误
+        if let (Some(root), Some(process)) = (spec.root.as_ref(), spec.process.as_ref()) {
+            let root_path = bundle_path.join(&root.path);
+            if root_path.is_dir() {
+                let (uid, gid) = process
+                    .user
+                    .as_ref()
+                    .map(|user| (user.uid, user.gid))
+                    .unwrap_or((0, 0));
+                Self::ensure_working_dir(&root_path, &process.cwd, uid, gid)?;
+            }
+        }
+
         info!(
             "Created bundle for container {} at {:?}",
             container_id, bundle_path
@@ -1221,13 +1490,101 @@ impl RuncRuntime {
         Ok(())
     }
 
-    /// 分步创建：准备 rootfs（NRI 可在后续步骤介入 spec）。
+    /// 在 rootfs 内解析工作目录并创建缺失的部分，新建的目录归进程用户所有
+    ///
+    /// 与 securejoin 相同，符号链接在 rootfs 内解析：绝对目标以 rootfs 为根，`..` 不会越过 rootfs，
+    /// 因此镜像中的 merged /usr、`/var/run -> /run` 等链接照常生效，只有缺失的尾部目录会被创建。
+    fn ensure_working_dir(rootfs: &Path, cwd: &str, uid: u32, gid: u32) -> Result<()> {
+        use std::ffi::OsString;
+        use std::path::Component;
+        const MAX_SYMLINKS: usize = 255;
+
+        let mut pending = std::collections::VecDeque::new();
+        for component in Path::new(cwd).components() {
+            match component {
+                Component::Normal(name) => pending.push_back(name.to_os_string()),
+                Component::RootDir | Component::CurDir => {}
+                _ => anyhow::bail!("Working directory {:?} must not contain '..'", cwd),
+            }
+        }
+
+        let parent_dir = OsString::from("..");
+        let mut resolved: Vec<OsString> = Vec::new();
+        let mut symlinks = 0;
+        while let Some(name) = pending.pop_front() {
+            if name == parent_dir {
+                resolved.pop();
+                continue;
+            }
+            let mut current = rootfs.to_path_buf();
+            current.extend(&resolved);
+            current.push(&name);
+            match std::fs::symlink_metadata(&current) {
+                Ok(metadata) if metadata.file_type().is_symlink() => {
+                    symlinks += 1;
+                    if symlinks > MAX_SYMLINKS {
+                        anyhow::bail!(
+                            "Working directory {:?} traverses too many symlinks in rootfs",
+                            cwd
+                        );
+                    }
+                    let target = std::fs::read_link(&current)
+                        .with_context(|| format!("Failed to read symlink {:?}", current))?;
+                    for component in target.components().rev() {
+                        match component {
+                            Component::Normal(name) => pending.push_front(name.to_os_string()),
+                            Component::ParentDir => pending.push_front(parent_dir.clone()),
+                            Component::CurDir | Component::RootDir | Component::Prefix(_) => {}
+                        }
+                    }
+                    if target.is_absolute() {
+                        resolved.clear();
+                    }
+                }
+                Ok(metadata) if metadata.is_dir() => resolved.push(name),
+                Ok(_) => anyhow::bail!(
+                    "Working directory {:?} conflicts with non-directory {:?} in rootfs",
+                    cwd,
+                    current
+                ),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                    std::fs::create_dir(&current).with_context(|| {
+                        format!("Failed to create working directory {:?}", current)
+                    })?;
+                    // rootless 模式下无权 chown，目录保持当前用户所有
+                    if nix::unistd::geteuid().is_root() {
+                        nix::unistd::chown(
+                            &current,
+                            Some(nix::unistd::Uid::from_raw(uid)),
+                            Some(nix::unistd::Gid::from_raw(gid)),
+                        )
+                        .with_context(|| {
+                            format!("Failed to chown working directory {:?}", current)
+                        })?;
+                    }
+                    resolved.push(name);
+                }
+                Err(err) => {
+                    return Err(err).with_context(|| {
+                        format!("Failed to inspect working directory {:?}", current)
+                    })
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 分步创建：准备 rootfs（NRI 可在后续步骤介入 spec）。镜像为空时只创建空 rootfs。
     pub fn prepare_rootfs(&self, container_id: &str, config: &ContainerConfig) -> Result<()> {
         let checkpoint_restore = Self::checkpoint_restore_from_annotations(&config.annotations);
         let image_ref = checkpoint_restore
             .as_ref()
             .map(|restore| restore.image_ref.as_str())
             .unwrap_or(config.image.as_str());
+        if image_ref.is_empty() {
+            return Self::prepare_scratch_rootfs(&config.rootfs, container_id)
+                .context("Failed to prepare scratch rootfs");
+        }
         self.prepare_rootfs_from_image(image_ref, &config.rootfs, container_id)
             .context("Failed to prepare rootfs from image")
     }
@@ -1235,13 +1592,19 @@ impl RuncRuntime {
     /// 分步创建：构建 pristine OCI spec。
     pub fn build_spec(&self, container_id: &str, config: &ContainerConfig) -> Result<Spec> {
         let checkpoint_restore = Self::checkpoint_restore_from_annotations(&config.annotations);
-        if let Some(checkpoint_restore) = checkpoint_restore.as_ref() {
-            self.spec_from_restore_template(config, checkpoint_restore)
-                .context("Failed to create OCI spec from checkpoint artifact")
+        let mut spec = if let Some(checkpoint_restore) = checkpoint_restore.as_ref() {
+            self.spec_from_restore_template(container_id, config, checkpoint_restore)
+                .context("Failed to create OCI spec from checkpoint artifact")?
         } else {
             self.create_spec(config, container_id)
-                .context("Failed to create OCI spec")
+                .context("Failed to create OCI spec")?
+        };
+        if let Some(rootless) = self.rootless.as_ref() {
+            rootless
+                .configure_oci_spec(&mut spec)
+                .context("Failed to configure OCI spec for rootless mode")?;
         }
+        Ok(spec)
     }
 
     /// 分步创建：落盘 bundle（config.json + bundle 目录）。
@@ -1255,6 +1618,23 @@ impl RuncRuntime {
             .map_err(|e| anyhow::anyhow!("Failed to load OCI spec for {}: {}", container_id, e))
     }
 
+    /// 轮询直到容器停止或消失，超时返回 false
+    fn wait_for_stopped(&self, container_id: &str, timeout: std::time::Duration) -> Result<bool> {
+        let deadline = std::time::Instant::now() + timeout;
+        loop {
+            match self.get_runc_state(container_id)? {
+                None => return Ok(true),
+                Some(s) if s.status == "stopped" => return Ok(true),
+                _ => {}
+            }
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            if remaining.is_zero() {
+                return Ok(false);
+            }
+            std::thread::sleep(remaining.min(STOP_POLL_INTERVAL));
+        }
+    }
+
     /// 获取runc容器状态
     fn get_runc_state(&self, container_id: &str) -> Result<Option<RuncState>> {
         let output = self.run_command_output(&["state", container_id])?;
@@ -1270,6 +1650,140 @@ impl RuncRuntime {
         Ok(Some(state))
     }
 
+    /// 列出 runc 当前已知的全部容器ID
+    pub fn list_container_ids(&self) -> Result<std::collections::HashSet<String>> {
+        let output = self.run_command_output(&["list", "--format", "json"])?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "runc list failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stdout = stdout.trim();
+        if stdout.is_empty() || stdout == "null" {
+            return Ok(std::collections::HashSet::new());
+        }
+        let entries: Vec<RuncListEntry> =
+            serde_json::from_str(stdout).context("Failed to parse runc list output")?;
+        Ok(entries.into_iter().map(|entry| entry.id).collect())
+    }
+
+    /// 强制清理 SIGKILL 后仍未退出的容器：`runc delete --force`、cgroup 与 bundle 均尽力删除
+    ///
+    /// 不返回错误，未能清理的资源以描述列表返回，由调用方记录泄漏。
+    pub fn force_remove_container(&self, container_id: &str) -> Vec<String> {
+        let mut leaked = Vec::new();
+
+        if let Some(ref shim_manager) = self.shim_manager {
+            if shim_manager.is_shim_running(container_id) {
+                if let Err(e) = shim_manager.stop_shim(container_id) {
+                    leaked.push(format!("shim: {:#}", e));
+                }
+            }
+        }
+
+        // bundle 删除前读取 cgroupsPath；systemd 格式（slice:prefix:name）的 cgroup 由 systemd 回收
+        let cgroups_path = self
+            .load_spec(container_id)
+            .ok()
+            .and_then(|spec| spec.linux)
+            .and_then(|linux| linux.cgroups_path)
+            .filter(|path| !path.is_empty() && !path.contains(':'));
+
+        match self.run_command_output(&["delete", "--force", container_id]) {
+            Ok(output) if output.status.success() => {}
+            Ok(output) => {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                if !stderr.contains("does not exist") {
+                    leaked.push(format!("runc state: {}", stderr.trim()));
+                }
+            }
+            Err(e) => leaked.push(format!("runc state: {:#}", e)),
+        }
+
+        if let Some(path) = cgroups_path {
+            let mount_point = PathBuf::from("/sys/fs/cgroup");
+            let version = CgroupManager::version_at(&mount_point);
+            if let Err(e) = CgroupManager::with_path(&path, mount_point, version).remove_cgroup() {
+                leaked.push(format!("cgroup {}: {:#}", path, e));
+            }
+        }
+
+        let bundle_path = self.bundle_path(container_id);
+        if bundle_path.exists() {
+            if let Err(e) = std::fs::remove_dir_all(&bundle_path) {
+                leaked.push(format!("bundle {}: {}", bundle_path.display(), e));
+            }
+        }
+
+        leaked
+    }
+
+    /// shim 记录的容器启动错误（runc create/run 失败时的输出）
+    pub fn container_start_error(&self, container_id: &str) -> Option<String> {
+        let shim_manager = self.shim_manager.as_ref()?;
+        match shim_manager.get_start_error(container_id) {
+            Ok(message) => message,
+            Err(e) => {
+                debug!("Failed to read start error of {}: {:#}", container_id, e);
+                None
+            }
+        }
+    }
+
+    /// 读取容器写入的终止消息，只保留末尾 `max_bytes` 字节
+    ///
+    /// `path` 是容器内路径：有挂载以它为目标时读取挂载源（kubelet 挂入的宿主机文件），
+    /// 否则在 rootfs 中查找，解析后落在 rootfs 之外的路径会被忽略。文件不存在或为空时返回 None。
+    pub fn termination_message(
+        &self,
+        container_id: &str,
+        path: &str,
+        max_bytes: u64,
+    ) -> Option<String> {
+        let spec = match self.load_spec(container_id) {
+            Ok(spec) => spec,
+            Err(e) => {
+                debug!(
+                    "Failed to read termination message of {}: {:#}",
+                    container_id, e
+                );
+                return None;
+            }
+        };
+        let host_path = spec
+            .mounts
+            .iter()
+            .flatten()
+            .find(|mount| Path::new(&mount.destination) == Path::new(path))
+            .and_then(|mount| mount.source.as_ref().map(PathBuf::from))
+            .or_else(|| {
+                let root = Path::new(&spec.root.as_ref()?.path);
+                let root = std::fs::canonicalize(self.bundle_path(container_id).join(root)).ok()?;
+                let candidate =
+                    std::fs::canonicalize(root.join(path.trim_start_matches('/'))).ok()?;
+                candidate.starts_with(&root).then_some(candidate)
+            })?;
+        match read_file_tail(&host_path, max_bytes) {
+            Ok(message) if !message.is_empty() => {
+                Some(String::from_utf8_lossy(&message).into_owned())
+            }
+            Ok(_) => None,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                debug!(
+                    "Failed to read termination message {} of {}: {}",
+                    host_path.display(),
+                    container_id,
+                    e
+                );
+                None
+            }
+        }
+    }
+
     /// 获取容器 init 进程 PID
     pub fn container_pid(&self, container_id: &str) -> Result<Option<i32>> {
         match self.get_runc_state(container_id)? {
@@ -1279,7 +1793,7 @@ impl RuncRuntime {
     }
 
     /// 将 CRI LinuxContainerResources 转换为 ResourceLimits
-    fn cri_to_limits(resources: &LinuxContainerResources) -> ResourceLimits {
+    pub(crate) fn cri_to_limits(resources: &LinuxContainerResources) -> ResourceLimits {
         ResourceLimits {
             cpu: Some(CpuLimit {
                 shares: (resources.cpu_shares > 0).then_some(resources.cpu_shares as u64),
@@ -1294,8 +1808,12 @@ impl RuncRuntime {
                 limit: (resources.memory_limit_in_bytes > 0)
                     .then_some(resources.memory_limit_in_bytes),
                 reservation: None,
-                swap: (resources.memory_swap_limit_in_bytes > 0)
-                    .then_some(resources.memory_swap_limit_in_bytes),
+                // OCI 的 swap 为 memory+swap 总量，-1 表示不限制 swap
+                swap: match resources.memory_swap_limit_in_bytes {
+                    -1 => Some(-1),
+                    swap if swap > 0 => Some(swap),
+                    _ => None,
+                },
                 kernel: None,
                 kernel_tcp: None,
                 swappiness: None,
@@ -1304,10 +1822,26 @@ impl RuncRuntime {
             }),
             blkio: None,
             network: None,
-            pids: None,
+            pids: Self::cri_pids_limit(resources),
+            hugepages: resources
+                .hugepage_limits
+                .iter()
+                .map(|limit| HugepageLimit {
+                    page_size: limit.page_size.clone(),
+                    limit: limit.limit,
+                })
+                .collect(),
         }
     }
 
+    /// 从 CRI unified 资源中提取 PID 限制，非法值由服务层提前拒绝
+    fn cri_pids_limit(resources: &LinuxContainerResources) -> Option<PidsLimit> {
+        pids_limit_from_unified(&resources.unified)
+            .ok()
+            .flatten()
+            .map(|max| PidsLimit { max: Some(max) })
+    }
+
     fn notify_shim_to_reopen_log(&self, container_id: &str) -> Result<()> {
         let shim_manager = self
             .shim_manager
@@ -1443,28 +1977,29 @@ impl ContainerRuntime for RuncRuntime {
                     info!("Container {} already stopped", container_id);
                     return Ok(());
                 }
+                // 冻结的进程无法处理停止信号，先解冻
+                if s.status == "paused" {
+                    info!("Resuming paused container {} before stopping", container_id);
+                    self.runc_exec(&["resume", container_id])?;
+                }
             }
         }
 
-        // 发送SIGTERM信号
-        let signal = "TERM";
-        self.runc_exec(&["kill", container_id, signal])?;
-
-        // 等待容器停止
-        let timeout_secs = timeout.unwrap_or(10);
-        for _ in 0..timeout_secs {
-            std::thread::sleep(std::time::Duration::from_secs(1));
-
-            match self.get_runc_state(container_id)? {
-                None => break,
-                Some(s) if s.status == "stopped" => break,
-                _ => continue,
-            }
-        }
-
-        // 如果还在运行，发送SIGKILL
-        if let Ok(Some(state)) = self.get_runc_state(container_id) {
-            if state.status != "stopped" {
+        let timeout_secs = timeout.unwrap_or(DEFAULT_STOP_TIMEOUT_SECS);
+        if timeout_secs == 0 {
+            // CRI 语义：timeout 为 0 时立即强制终止
+            info!("Stop timeout is 0, killing container {}", container_id);
+            self.runc_exec(&["kill", container_id, "KILL"])?;
+        } else {
+            // 发送镜像声明的 StopSignal（默认 SIGTERM），最多等待请求的优雅退出时间
+            let stop_signal = self.container_stop_signal(container_id);
+            self.runc_exec(&[
+                "kill",
+                container_id,
+                stop_signal.as_str().trim_start_matches("SIG"),
+            ])?;
+            let grace = std::time::Duration::from_secs(u64::from(timeout_secs));
+            if !self.wait_for_stopped(container_id, grace)? {
                 info!(
                     "Container {} did not stop gracefully, sending SIGKILL",
                     container_id
@@ -1473,6 +2008,15 @@ impl ContainerRuntime for RuncRuntime {
             }
         }
 
+        // SIGKILL 后同样等到 runc 报告 stopped 才返回，避免后续 delete 抢在进程退出之前
+        if !self.wait_for_stopped(container_id, self.kill_wait_timeout)? {
+            return Err(KillTimeoutError {
+                container_id: container_id.to_string(),
+                timeout: self.kill_wait_timeout,
+            }
+            .into());
+        }
+
         info!("Container {} stopped", container_id);
         Ok(())
     }
@@ -1526,7 +2070,8 @@ impl ContainerRuntime for RuncRuntime {
             Some(state) => {
                 let status = match state.status.as_str() {
                     "created" => ContainerStatus::Created,
-                    "running" => ContainerStatus::Running,
+                    // CRI 没有暂停状态，暂停的容器仍视为运行中
+                    "running" | "paused" => ContainerStatus::Running,
                     "stopped" => ContainerStatus::Stopped(0),
                     _ => ContainerStatus::Unknown,
                 };
@@ -1601,6 +2146,12 @@ impl ContainerRuntime for RuncRuntime {
         info!("Container {} resources updated successfully", container_id);
         Ok(())
     }
+
+    fn init_pid(&self, container_id: &str) -> Result<Option<u32>> {
+        Ok(self
+            .container_pid(container_id)?
+            .and_then(|pid| u32::try_from(pid).ok()))
+    }
 }
 
 #[cfg(test)]
@@ -1651,6 +2202,8 @@ mod tests {
             namespace_paths: NamespacePaths::default(),
             linux_resources: None,
             devices: vec![],
+            shm_size: None,
+            shm_path: None,
             rootfs: PathBuf::from("/tmp/rootfs"),
         }
     }
@@ -1668,6 +2221,117 @@ mod tests {
         assert!(spec.linux.is_some());
     }
 
+    #[test]
+    fn test_prepare_rootfs_without_image_creates_scratch_rootfs() {
+        // 创建 /dev/null 需要 mknod 权限
+        if !nix::unistd::geteuid().is_root() {
+            return;
+        }
+        let (runtime, temp) = create_test_runtime();
+        let mut config = create_test_config();
+        config.image = String::new();
+        config.rootfs = temp.path().join("pause-rootfs");
+        fs::create_dir_all(config.rootfs.join("stale")).unwrap();
+
+        runtime.prepare_rootfs("pause-pod", &config).unwrap();
+
+        assert!(!config.rootfs.join("stale").exists());
+        for dir in ["dev", "proc", "sys"] {
+            assert!(config.rootfs.join(dir).is_dir(), "{}", dir);
+        }
+        use std::os::unix::fs::FileTypeExt;
+        assert!(fs::metadata(config.rootfs.join("dev/null"))
+            .unwrap()
+            .file_type()
+            .is_char_device());
+    }
+
+    #[test]
+    fn test_write_bundle_creates_missing_working_dir() {
+        let (runtime, temp) = create_test_runtime();
+        let rootfs = temp.path().join("rootfs");
+        fs::create_dir_all(rootfs.join("srv")).unwrap();
+        let mut config = create_test_config();
+        config.rootfs = rootfs.clone();
+        config.working_dir = Some(PathBuf::from("/srv/app/data"));
+        config.user = Some("1000".to_string());
+        config.run_as_group = Some(2000);
+
+        let spec = runtime.create_spec(&config, "cwd-id").unwrap();
+        runtime.write_bundle("cwd-id", &rootfs, &spec).unwrap();
+
+        let created = fs::metadata(rootfs.join("srv/app/data")).unwrap();
+        assert!(created.is_dir());
+        if nix::unistd::geteuid().is_root() {
+            use std::os::unix::fs::MetadataExt;
+            assert_eq!((created.uid(), created.gid()), (1000, 2000));
+            // 已存在的目录保持原属主
+            assert_eq!(fs::metadata(rootfs.join("srv")).unwrap().uid(), 0);
+        }
+    }
+
+    #[test]
+    fn test_write_bundle_resolves_working_dir_symlinks_inside_rootfs() {
+        let (runtime, temp) = create_test_runtime();
+        let rootfs = temp.path().join("rootfs");
+        fs::create_dir_all(rootfs.join("run")).unwrap();
+        fs::create_dir_all(rootfs.join("var")).unwrap();
+        fs::create_dir_all(rootfs.join("usr/lib")).unwrap();
+        std::os::unix::fs::symlink("/run", rootfs.join("var/run")).unwrap();
+        std::os::unix::fs::symlink("usr/lib", rootfs.join("lib")).unwrap();
+        std::os::unix::fs::symlink(temp.path(), rootfs.join("escape")).unwrap();
+        std::os::unix::fs::symlink("../../..", rootfs.join("run/up")).unwrap();
+        let mut config = create_test_config();
+        config.rootfs = rootfs.clone();
+
+        for (index, (cwd, expected)) in [
+            ("/var/run/app", rootfs.join("run/app")),
+            ("/lib/app", rootfs.join("usr/lib/app")),
+            ("/run/up/work", rootfs.join("work")),
+            (
+                "/escape/work",
+                rootfs
+                    .join(temp.path().strip_prefix("/").unwrap())
+                    .join("work"),
+            ),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            config.working_dir = Some(PathBuf::from(cwd));
+            let id = format!("cwd-link-{}", index);
+            let spec = runtime.create_spec(&config, &id).unwrap();
+            runtime.write_bundle(&id, &rootfs, &spec).unwrap();
+            assert!(expected.is_dir(), "{} -> {:?}", cwd, expected);
+        }
+        // 链接目标始终在 rootfs 内解析，不会在宿主机上创建目录
+        assert!(!temp.path().join("work").exists());
+    }
+
+    #[test]
+    fn test_create_spec_sizes_dev_shm_tmpfs() {
+        let (runtime, _temp) = create_test_runtime();
+        let mut config = create_test_config();
+        let shm_options = |spec: Spec| {
+            let mounts = spec.mounts.unwrap();
+            let shm: Vec<_> = mounts
+                .iter()
+                .filter(|mount| mount.destination == "/dev/shm")
+                .collect();
+            assert_eq!(shm.len(), 1);
+            assert_eq!(shm[0].mount_type.as_deref(), Some("tmpfs"));
+            shm[0].options.clone().unwrap()
+        };
+
+        let options = shm_options(runtime.create_spec(&config, "test-id").unwrap());
+        assert!(options.contains(&format!("size={}", crate::oci::spec::DEFAULT_SHM_SIZE)));
+
+        config.shm_size = Some(256 << 20);
+        let options = shm_options(runtime.create_spec(&config, "test-id").unwrap());
+        assert!(options.contains(&"size=268435456".to_string()));
+        assert!(options.contains(&"mode=1777".to_string()));
+    }
+
     #[test]
     fn test_create_spec_encodes_labels_annotation_for_nri() {
         let (runtime, _temp) = create_test_runtime();
@@ -1714,6 +2378,43 @@ mod tests {
         assert_eq!(resolved, storage_root);
     }
 
+    #[test]
+    fn test_image_config_metadata_reads_labels_and_exposed_ports() {
+        let temp_dir = tempdir().unwrap();
+        let image_dir = temp_dir
+            .path()
+            .join("storage")
+            .join("images")
+            .join("sha256:labeled");
+        fs::create_dir_all(&image_dir).unwrap();
+        fs::write(
+            image_dir.join("metadata.json"),
+            serde_json::json!({
+                "id": "sha256:labeled",
+                "repo_tags": ["nginx:latest"],
+                "annotations": {"maintainer": "nginx"},
+                "exposed_ports": ["80/tcp"],
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let runtime = RuncRuntime::new(PathBuf::from("runc"), temp_dir.path().join("containers"));
+        let metadata = runtime.image_config_metadata("nginx:latest").unwrap();
+        assert_eq!(metadata.labels.get("maintainer").unwrap(), "nginx");
+        assert_eq!(metadata.exposed_ports, vec!["80/tcp".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_stop_signal_accepts_names_and_numbers() {
+        assert_eq!(parse_stop_signal("SIGQUIT").unwrap(), Signal::SIGQUIT);
+        assert_eq!(parse_stop_signal("quit").unwrap(), Signal::SIGQUIT);
+        assert_eq!(parse_stop_signal("15").unwrap(), Signal::SIGTERM);
+        assert!(parse_stop_signal("SIGNOPE").is_err());
+        assert!(parse_stop_signal("0").is_err());
+        assert!(parse_stop_signal("").is_err());
+    }
+
     #[test]
     fn test_spec_with_custom_mounts() {
         let (runtime, _temp) = create_test_runtime();
@@ -1770,6 +2471,63 @@ mod tests {
         assert_eq!(user.username.as_deref(), Some("nobody"));
     }
 
+    #[test]
+    fn test_spec_sets_additional_gids_from_supplemental_groups() {
+        let (runtime, _temp) = create_test_runtime();
+        let mut config = create_test_config();
+        config.user = Some("1000".to_string());
+        config.run_as_group = Some(3000);
+        config.supplemental_groups = vec![2000, 4000];
+
+        let spec = runtime.create_spec(&config, "test-id").unwrap();
+        let user = spec.process.unwrap().user.unwrap();
+        assert_eq!(user.gid, 3000);
+        assert_eq!(user.additional_gids, Some(vec![2000, 4000]));
+        let encoded = serde_json::to_value(&user).unwrap();
+        assert_eq!(encoded["additionalGids"], serde_json::json!([2000, 4000]));
+
+        config.user = None;
+        config.run_as_group = None;
+        let spec = runtime.create_spec(&config, "test-id").unwrap();
+        let user = spec.process.unwrap().user.unwrap();
+        assert_eq!((user.uid, user.gid), (0, 0));
+        assert_eq!(user.additional_gids, Some(vec![2000, 4000]));
+    }
+
+    #[test]
+    fn test_spec_masks_default_paths_for_unprivileged_containers() {
+        let (mut runtime, _temp) = create_test_runtime();
+        runtime.set_protected_paths(ProtectedPathsConfig {
+            masked_paths: vec!["/proc/custom".to_string(), "/proc/kcore".to_string()],
+            readonly_paths: vec!["/sys/custom".to_string()],
+        });
+        let mut config = create_test_config();
+
+        let spec = runtime.create_spec(&config, "test-id").unwrap();
+        let linux = spec.linux.as_ref().unwrap();
+        let masked = linux.masked_paths.as_ref().unwrap();
+        for path in DEFAULT_MASKED_PATHS {
+            assert!(masked.iter().any(|masked| masked == path), "{}", path);
+        }
+        assert_eq!(masked.len(), DEFAULT_MASKED_PATHS.len() + 1);
+        assert_eq!(masked.last().unwrap(), "/proc/custom");
+        let readonly = linux.readonly_paths.as_ref().unwrap();
+        assert!(readonly.iter().any(|path| path == "/proc/sys"));
+        assert!(readonly.iter().any(|path| path == "/sys/custom"));
+
+        let json = serde_json::to_value(&spec).unwrap();
+        assert!(json["linux"]["maskedPaths"]
+            .as_array()
+            .unwrap()
+            .contains(&serde_json::json!("/proc/kcore")));
+
+        config.privileged = true;
+        let spec = runtime.create_spec(&config, "test-id").unwrap();
+        let linux = spec.linux.as_ref().unwrap();
+        assert!(linux.masked_paths.is_none());
+        assert!(linux.readonly_paths.is_none());
+    }
+
     #[test]
     fn test_spec_privileged() {
         let (runtime, _temp) = create_test_runtime();
@@ -1849,6 +2607,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_spec_cgroups_path_uses_cgroup_root() {
+        let (mut runtime, _temp) = create_test_runtime();
+        runtime.set_cgroup_root("/kubepods.slice");
+        let mut config = create_test_config();
+        config.cgroup_parent = Some("/burstable/pod123".to_string());
+
+        let spec = runtime.build_spec("test-id", &config).unwrap();
+        assert_eq!(
+            spec.linux.unwrap().cgroups_path.as_deref(),
+            Some("/kubepods.slice/burstable/pod123")
+        );
+
+        config.cgroup_parent = None;
+        let spec = runtime.build_spec("test-id", &config).unwrap();
+        assert_eq!(
+            spec.linux.unwrap().cgroups_path.as_deref(),
+            Some("/kubepods.slice/test-id")
+        );
+    }
+
     #[test]
     fn test_spec_with_device_mappings() {
         let (runtime, _temp) = create_test_runtime();
@@ -1872,6 +2651,65 @@ mod tests {
         }));
     }
 
+    #[test]
+    fn test_spec_device_cgroup_defaults_deny_with_standard_allows() {
+        let (runtime, _temp) = create_test_runtime();
+        let mut config = create_test_config();
+        config.devices = vec![DeviceMapping {
+            source: PathBuf::from("/dev/null"),
+            destination: PathBuf::from("/dev/custom-null"),
+            permissions: "rw".to_string(),
+        }];
+
+        let spec = runtime.create_spec(&config, "test-id").unwrap();
+        let rules = spec.linux.unwrap().resources.unwrap().devices.unwrap();
+        let first = &rules[0];
+        assert!(!first.allow);
+        assert_eq!(first.device_type, None);
+        assert_eq!(first.access.as_deref(), Some("rwm"));
+
+        let allowed: Vec<String> = rules
+            .iter()
+            .filter(|rule| rule.allow)
+            .map(|rule| {
+                let number = |value: Option<i64>| {
+                    value.map_or_else(|| "*".to_string(), |value| value.to_string())
+                };
+                format!(
+                    "{} {}:{} {}",
+                    rule.device_type.as_deref().unwrap_or("a"),
+                    number(rule.major),
+                    number(rule.minor),
+                    rule.access.as_deref().unwrap_or_default()
+                )
+            })
+            .collect();
+        assert_eq!(
+            allowed,
+            vec![
+                "c *:* m",
+                "b *:* m",
+                "c 1:3 rwm",
+                "c 1:5 rwm",
+                "c 1:7 rwm",
+                "c 1:8 rwm",
+                "c 1:9 rwm",
+                "c 5:0 rwm",
+                "c 5:2 rwm",
+                "c 136:* rwm",
+                "c 1:3 rw",
+            ]
+        );
+
+        config.privileged = true;
+        let spec = runtime.create_spec(&config, "test-id").unwrap();
+        let rules = spec.linux.unwrap().resources.unwrap().devices.unwrap();
+        assert_eq!(rules.len(), 1);
+        assert!(rules[0].allow);
+        assert_eq!(rules[0].device_type, None);
+        assert_eq!(rules[0].access.as_deref(), Some("rwm"));
+    }
+
     #[test]
     fn test_spec_with_selinux_and_localhost_seccomp() {
         let (runtime, temp) = create_test_runtime();
@@ -1941,6 +2779,131 @@ mod tests {
         assert_eq!(memory.swap, Some(2147483648));
     }
 
+    #[test]
+    fn test_create_spec_sets_memory_swap() {
+        let (runtime, _temp) = create_test_runtime();
+        let mut config = create_test_config();
+        let swap_in_spec = |config: &ContainerConfig| {
+            runtime
+                .create_spec(config, "test-id")
+                .unwrap()
+                .linux
+                .and_then(|linux| linux.resources)
+                .and_then(|resources| resources.memory)
+                .and_then(|memory| memory.swap)
+        };
+
+        for (swap, expected) in [
+            (768 * 1024 * 1024, Some(768 * 1024 * 1024)),
+            // 与内存限制相等即禁用 swap
+            (512 * 1024 * 1024, Some(512 * 1024 * 1024)),
+            (-1, Some(-1)),
+            (0, None),
+        ] {
+            config.linux_resources = Some(LinuxContainerResources {
+                memory_limit_in_bytes: 512 * 1024 * 1024,
+                memory_swap_limit_in_bytes: swap,
+                ..Default::default()
+            });
+            assert_eq!(swap_in_spec(&config), expected, "swap {}", swap);
+        }
+    }
+
+    #[test]
+    fn test_create_spec_sets_pids_limit_from_unified() {
+        let (runtime, _temp) = create_test_runtime();
+        let mut config = create_test_config();
+        config.linux_resources = Some(LinuxContainerResources {
+            unified: HashMap::from([("pids.max".to_string(), "512".to_string())]),
+            ..Default::default()
+        });
+
+        let spec = runtime.create_spec(&config, "test-id").unwrap();
+        let pids = spec
+            .linux
+            .and_then(|linux| linux.resources)
+            .and_then(|resources| resources.pids)
+            .expect("pids limit should be set");
+        assert_eq!(pids.limit, 512);
+
+        let limits = RuncRuntime::cri_to_limits(config.linux_resources.as_ref().unwrap());
+        assert_eq!(limits.pids.and_then(|pids| pids.max), Some(512));
+    }
+
+    #[test]
+    fn test_create_spec_translates_hugepage_limits() {
+        let (runtime, _temp) = create_test_runtime();
+        let mut config = create_test_config();
+        config.linux_resources = Some(LinuxContainerResources {
+            hugepage_limits: vec![
+                crate::proto::runtime::v1::HugepageLimit {
+                    page_size: "2MB".to_string(),
+                    limit: 8 * 1024 * 1024,
+                },
+                crate::proto::runtime::v1::HugepageLimit {
+                    page_size: "1GB".to_string(),
+                    limit: 1024 * 1024 * 1024,
+                },
+            ],
+            ..Default::default()
+        });
+
+        let spec = runtime.create_spec(&config, "test-id").unwrap();
+        let hugepages = spec
+            .linux
+            .and_then(|linux| linux.resources)
+            .and_then(|resources| resources.hugepage_limits)
+            .expect("hugepage limits should be set");
+        assert_eq!(hugepages.len(), 2);
+        assert_eq!(hugepages[0].page_size, "2MB");
+        assert_eq!(hugepages[0].limit, 8 * 1024 * 1024);
+        assert_eq!(hugepages[1].page_size, "1GB");
+        assert_eq!(hugepages[1].limit, 1024 * 1024 * 1024);
+
+        let limits = RuncRuntime::cri_to_limits(config.linux_resources.as_ref().unwrap());
+        assert_eq!(limits.hugepages.len(), 2);
+    }
+
+    #[test]
+    fn test_create_spec_applies_oom_score_adj_and_process_defaults() {
+        let (mut runtime, _temp) = create_test_runtime();
+        let mut config = create_test_config();
+        config.no_new_privileges = Some(true);
+
+        let spec = runtime.create_spec(&config, "test-id").unwrap();
+        let process = spec.process.unwrap();
+        assert_eq!(process.oom_score_adj, None);
+        assert!(process.rlimits.is_none());
+        assert_eq!(process.no_new_privileges, Some(true));
+
+        runtime.set_process_defaults(ProcessDefaultsConfig {
+            oom_score_adj: Some(-500),
+            rlimits: vec![crate::config::RlimitConfig {
+                rlimit_type: "RLIMIT_NOFILE".to_string(),
+                hard: 4096,
+                soft: 1024,
+            }],
+        });
+        let spec = runtime.create_spec(&config, "test-id").unwrap();
+        let process = spec.process.unwrap();
+        assert_eq!(process.oom_score_adj, Some(-500));
+        let rlimits = process.rlimits.unwrap();
+        assert_eq!(rlimits.len(), 1);
+        assert_eq!(rlimits[0].rtype, "RLIMIT_NOFILE");
+        assert_eq!(rlimits[0].hard, 4096);
+        assert_eq!(rlimits[0].soft, 1024);
+
+        config.linux_resources = Some(LinuxContainerResources {
+            oom_score_adj: 1000,
+            ..Default::default()
+        });
+        config.no_new_privileges = Some(false);
+        let spec = runtime.create_spec(&config, "test-id").unwrap();
+        let process = spec.process.unwrap();
+        assert_eq!(process.oom_score_adj, Some(1000));
+        assert_eq!(process.no_new_privileges, Some(false));
+    }
+
     #[test]
     fn test_cri_to_limits_zero_values() {
         // 测试零值被过滤（不设置）
@@ -12,6 +12,10 @@ use std::sync::{Arc, Mutex};
 
 const DEFAULT_SHIM_WORK_DIR: &str = "/var/run/crius/shims";
 const SHIM_METADATA_FILE: &str = "shim.json";
+/// shim 未能启动容器时写入 runc 错误信息的文件，位于 shim 工作目录
+pub const START_ERROR_FILE: &str = "start_error";
+/// 容器未能启动时记录的退出码
+pub const START_ERROR_EXIT_CODE: i32 = 128;
 
 pub fn default_shim_work_dir() -> PathBuf {
     std::env::var("CRIUS_SHIM_DIR")
@@ -174,6 +178,7 @@ impl ShimManager {
         let exit_code_file = shim_dir.join("exit_code");
         let log_file = shim_dir.join("shim.log");
         let socket_path = shim_dir.join("attach.sock");
+        let _ = fs::remove_file(shim_dir.join(START_ERROR_FILE));
 
         // 构建shim命令
         let mut cmd = Command::new(&self.config.shim_path);
@@ -251,6 +256,20 @@ impl ShimManager {
         Ok(None)
     }
 
+    /// 获取 shim 记录的容器启动错误
+    pub fn get_start_error(&self, container_id: &str) -> Result<Option<String>> {
+        let path = self
+            .config
+            .work_dir
+            .join(container_id)
+            .join(START_ERROR_FILE);
+        match fs::read_to_string(&path) {
+            Ok(message) => Ok(Some(message.trim().to_string())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
     /// 停止shim进程
     pub fn stop_shim(&self, container_id: &str) -> Result<()> {
         info!("Stopping shim for container {}", container_id);
@@ -327,6 +327,8 @@ impl SecurityManager {
             sysctl: None,
             mount_label: None,
             intel_rdt: None,
+            masked_paths: None,
+            readonly_paths: None,
         };
 
         // 配置SELinux - 使用mount_label字段
@@ -0,0 +1,616 @@
+//! crius 管理接口
+//!
+//! CRI 之外供运维调试使用的 gRPC 服务 `crius.admin.v1.AdminService`，与 CRI 服务共用 socket，
+//! 只读端点不开放。消息较少，直接用 prost derive 定义，不经过 proto 生成。
+
+use std::future::Future;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::codec::Streaming;
+use tonic::codegen::{empty_body, http, Body, BoxFuture, StdError};
+use tonic::transport::Channel;
+use tonic::{Request, Response, Status};
+
+use super::RuntimeServiceImpl;
+use crate::audit::AuditEntry;
+use crate::image::{FsckMode, ImageServiceImpl};
+
+pub const ADMIN_SERVICE_NAME: &str = "crius.admin.v1.AdminService";
+pub const PAUSE_CONTAINER_METHOD: &str = "/crius.admin.v1.AdminService/PauseContainer";
+pub const RESUME_CONTAINER_METHOD: &str = "/crius.admin.v1.AdminService/ResumeContainer";
+pub const EXPORT_CONTAINER_METHOD: &str = "/crius.admin.v1.AdminService/ExportContainer";
+pub const CHECK_IMAGE_STORE_METHOD: &str = "/crius.admin.v1.AdminService/CheckImageStore";
+pub const NODE_SUMMARY_METHOD: &str = "/crius.admin.v1.AdminService/NodeSummary";
+
+/// 导出时每个分片的最大字节数
+const EXPORT_CHUNK_SIZE: usize = 64 * 1024;
+/// OCI 层中的 whiteout 文件名前缀
+const WHITEOUT_PREFIX: &str = ".wh.";
+/// 以此开头的是 whiteout 元数据（如不透明目录标记 `.wh..wh..opq`），不对应具体文件
+const WHITEOUT_META_PREFIX: &str = ".wh..wh.";
+
+pub type ExportContainerStream = ReceiverStream<Result<ExportContainerChunk, Status>>;
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PauseContainerRequest {
+    /// 容器 ID，可为唯一前缀
+    #[prost(string, tag = "1")]
+    pub container_id: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PauseContainerResponse {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ResumeContainerRequest {
+    /// 容器 ID，可为唯一前缀
+    #[prost(string, tag = "1")]
+    pub container_id: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ResumeContainerResponse {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ExportContainerRequest {
+    /// 容器 ID，可为唯一前缀
+    #[prost(string, tag = "1")]
+    pub container_id: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct CheckImageStoreRequest {
+    /// 是否重新计算每个层文件的 sha256，否则只比较大小
+    #[prost(bool, tag = "1")]
+    pub full: bool,
+}
+
+/// 镜像存储校验结果，字段含义见 [`crate::image::FsckReport`]
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct CheckImageStoreResponse {
+    #[prost(uint64, tag = "1")]
+    pub verified: u64,
+    #[prost(string, repeated, tag = "2")]
+    pub repaired: Vec<String>,
+    #[prost(string, repeated, tag = "3")]
+    pub removed: Vec<String>,
+    #[prost(string, repeated, tag = "4")]
+    pub failed: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct NodeSummaryRequest {}
+
+/// 节点级资源汇总，字段含义见 [`super::NodeSummary`]
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct NodeSummaryResponse {
+    #[prost(int64, tag = "1")]
+    pub timestamp: i64,
+    #[prost(uint64, tag = "2")]
+    pub cpu_usage_core_nano_seconds: u64,
+    #[prost(uint64, tag = "3")]
+    pub memory_working_set_bytes: u64,
+    #[prost(uint64, tag = "4")]
+    pub writable_layer_bytes: u64,
+    #[prost(uint64, tag = "5")]
+    pub writable_layer_inodes: u64,
+    #[prost(message, optional, tag = "6")]
+    pub image_fs: Option<crate::proto::runtime::v1::FilesystemUsage>,
+    #[prost(message, repeated, tag = "7")]
+    pub pods: Vec<crate::proto::runtime::v1::PodSandboxStats>,
+}
+
+impl From<super::NodeSummary> for NodeSummaryResponse {
+    fn from(summary: super::NodeSummary) -> Self {
+        Self {
+            timestamp: summary.timestamp,
+            cpu_usage_core_nano_seconds: summary.cpu_usage_core_nano_seconds,
+            memory_working_set_bytes: summary.memory_working_set_bytes,
+            writable_layer_bytes: summary.writable_layer_bytes,
+            writable_layer_inodes: summary.writable_layer_inodes,
+            image_fs: summary.image_fs,
+            pods: summary.pods,
+        }
+    }
+}
+
+impl From<NodeSummaryResponse> for super::NodeSummary {
+    fn from(response: NodeSummaryResponse) -> Self {
+        Self {
+            timestamp: response.timestamp,
+            cpu_usage_core_nano_seconds: response.cpu_usage_core_nano_seconds,
+            memory_working_set_bytes: response.memory_working_set_bytes,
+            writable_layer_bytes: response.writable_layer_bytes,
+            writable_layer_inodes: response.writable_layer_inodes,
+            image_fs: response.image_fs,
+            pods: response.pods,
+        }
+    }
+}
+
+/// tar 流的一个分片，按顺序拼接即为完整归档
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ExportContainerChunk {
+    #[prost(bytes = "vec", tag = "1")]
+    pub data: Vec<u8>,
+}
+
+impl RuntimeServiceImpl {
+    /// 冻结运行中的容器；已暂停时直接返回成功
+    pub async fn pause_container(
+        &self,
+        request: Request<PauseContainerRequest>,
+    ) -> Result<Response<PauseContainerResponse>, Status> {
+        let entry = AuditEntry::new(PAUSE_CONTAINER_METHOD, &request)
+            .id("container_id", &request.get_ref().container_id);
+        let result = self
+            .set_container_paused(&request.get_ref().container_id, true)
+            .await
+            .map(|()| Response::new(PauseContainerResponse {}));
+        self.audit(entry, &result);
+        result
+    }
+
+    /// 解冻暂停的容器；未暂停时直接返回成功
+    pub async fn resume_container(
+        &self,
+        request: Request<ResumeContainerRequest>,
+    ) -> Result<Response<ResumeContainerResponse>, Status> {
+        let entry = AuditEntry::new(RESUME_CONTAINER_METHOD, &request)
+            .id("container_id", &request.get_ref().container_id);
+        let result = self
+            .set_container_paused(&request.get_ref().container_id, false)
+            .await
+            .map(|()| Response::new(ResumeContainerResponse {}));
+        self.audit(entry, &result);
+        result
+    }
+
+    /// 把容器当前的 rootfs 以 tar 流导出；运行中的容器同样读取其合并后的视图
+    /// 节点级资源汇总，只读，不记审计日志
+    pub async fn get_node_summary(
+        &self,
+        _request: Request<NodeSummaryRequest>,
+    ) -> Result<Response<NodeSummaryResponse>, Status> {
+        Ok(Response::new(self.node_summary().await.into()))
+    }
+
+    pub async fn export_container(
+        &self,
+        request: Request<ExportContainerRequest>,
+    ) -> Result<Response<ExportContainerStream>, Status> {
+        let entry = AuditEntry::new(EXPORT_CONTAINER_METHOD, &request)
+            .id("container_id", &request.get_ref().container_id);
+        let result = self
+            .export_container_rootfs(&request.get_ref().container_id)
+            .await
+            .map(Response::new);
+        self.audit(entry, &result);
+        result
+    }
+
+    async fn export_container_rootfs(
+        &self,
+        requested_id: &str,
+    ) -> Result<ExportContainerStream, Status> {
+        let container_id = self.resolve_container_id(requested_id).await?;
+        let rootfs = self.container_rootfs_path(&container_id)?;
+        let entries = tokio::task::spawn_blocking({
+            let rootfs = rootfs.clone();
+            move || export_entries(&rootfs)
+        })
+        .await
+        .map_err(|e| Status::internal(format!("Failed to spawn blocking task: {}", e)))?
+        .map_err(|e| {
+            Status::internal(format!("Failed to walk rootfs {}: {}", rootfs.display(), e))
+        })?;
+
+        let mut child = tokio::process::Command::new("tar")
+            .arg("-cf")
+            .arg("-")
+            .arg("--null")
+            .arg("--no-recursion")
+            .arg("-C")
+            .arg(&rootfs)
+            .arg("-T")
+            .arg("-")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| Status::internal(format!("Failed to execute tar for export: {}", e)))?;
+        let mut stdin = child.stdin.take().expect("tar stdin is piped");
+        let mut stdout = child.stdout.take().expect("tar stdout is piped");
+
+        let mut file_list = Vec::new();
+        for entry in &entries {
+            file_list.extend_from_slice(entry.as_os_str().as_encoded_bytes());
+            file_list.push(0);
+        }
+        tokio::spawn(async move {
+            // tar 提前退出时写入失败，错误由读取端通过退出码报告
+            let _ = stdin.write_all(&file_list).await;
+        });
+
+        let (tx, rx) = mpsc::channel(4);
+        log::info!(
+            "Exporting rootfs of container {} ({} entries)",
+            container_id,
+            entries.len()
+        );
+        tokio::spawn(async move {
+            let mut buffer = vec![0u8; EXPORT_CHUNK_SIZE];
+            loop {
+                match stdout.read(&mut buffer).await {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        let chunk = ExportContainerChunk {
+                            data: buffer[..n].to_vec(),
+                        };
+                        if tx.send(Ok(chunk)).await.is_err() {
+                            // 客户端已断开，终止 tar
+                            let _ = child.kill().await;
+                            return;
+                        }
+                    }
+                    Err(e) => {
+                        let _ = child.kill().await;
+                        let _ = tx
+                            .send(Err(Status::internal(format!(
+                                "Failed to read tar output: {}",
+                                e
+                            ))))
+                            .await;
+                        return;
+                    }
+                }
+            }
+            drop(stdout);
+            match child.wait_with_output().await {
+                Ok(output) if output.status.success() => {
+                    log::info!("Exported rootfs of container {}", container_id);
+                }
+                Ok(output) => {
+                    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+                    let detail = if stderr.is_empty() {
+                        format!("status={}", output.status)
+                    } else {
+                        stderr
+                    };
+                    log::warn!(
+                        "Failed to export rootfs of container {}: {}",
+                        container_id,
+                        detail
+                    );
+                    let _ = tx
+                        .send(Err(Status::internal(format!(
+                            "Failed to export rootfs: {}",
+                            detail
+                        ))))
+                        .await;
+                }
+                Err(e) => {
+                    let _ = tx
+                        .send(Err(Status::internal(format!(
+                            "Failed to wait for tar: {}",
+                            e
+                        ))))
+                        .await;
+                }
+            }
+        });
+
+        Ok(ReceiverStream::new(rx))
+    }
+
+    /// 从 bundle 的 config.json 读取 root.path，相对路径相对 bundle 目录
+    pub(super) fn container_rootfs_path(&self, container_id: &str) -> Result<PathBuf, Status> {
+        let root = self
+            .runtime_spec_snapshot(container_id)
+            .and_then(|spec| {
+                spec.get("root")
+                    .and_then(|root| root.get("path"))
+                    .and_then(|path| path.as_str())
+                    .filter(|path| !path.is_empty())
+                    .map(PathBuf::from)
+            })
+            .ok_or_else(|| {
+                Status::failed_precondition(format!(
+                    "container {} has no rootfs recorded in its bundle",
+                    container_id
+                ))
+            })?;
+        let root = if root.is_absolute() {
+            root
+        } else {
+            self.checkpoint_bundle_path(container_id).join(root)
+        };
+        if !root.is_dir() {
+            return Err(Status::failed_precondition(format!(
+                "rootfs {} of container {} does not exist",
+                root.display(),
+                container_id
+            )));
+        }
+        Ok(root)
+    }
+}
+
+/// 列出 rootfs 中需要导出的条目（相对路径，以 `.` 开头，父目录在前）。
+///
+/// 层按顺序解压到同一目录时 whiteout 文件会与被删除的文件并存：`.wh.<name>` 本身和同级的
+/// `<name>` 都不导出；`.wh..wh.` 开头的元数据和 overlay 风格的 0/0 字符设备 whiteout 同样跳过。
+/// 读取已挂载的 overlay 合并目录时内核已隐藏 whiteout，这些规则不会生效。
+fn export_entries(rootfs: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut entries = vec![PathBuf::from(".")];
+    collect_export_entries(rootfs, Path::new("."), &mut entries)?;
+    Ok(entries)
+}
+
+fn collect_export_entries(
+    dir: &Path,
+    relative: &Path,
+    entries: &mut Vec<PathBuf>,
+) -> std::io::Result<()> {
+    let mut children: Vec<_> = std::fs::read_dir(dir)?.collect::<Result<_, _>>()?;
+    children.sort_by_key(|entry| entry.file_name());
+    let whiteouts: Vec<_> = children
+        .iter()
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let name = name.to_str()?;
+            if name.starts_with(WHITEOUT_META_PREFIX) {
+                return None;
+            }
+            name.strip_prefix(WHITEOUT_PREFIX).map(str::to_string)
+        })
+        .collect();
+
+    for child in children {
+        let name = child.file_name();
+        if let Some(name) = name.to_str() {
+            if name.starts_with(WHITEOUT_PREFIX) || whiteouts.iter().any(|w| w == name) {
+                continue;
+            }
+        }
+        let metadata = child.metadata()?;
+        if metadata.file_type().is_char_device() && metadata.rdev() == 0 {
+            continue;
+        }
+        let path = relative.join(&name);
+        entries.push(path.clone());
+        if metadata.is_dir() {
+            collect_export_entries(&child.path(), &path, entries)?;
+        }
+    }
+    Ok(())
+}
+
+/// 校验并修复镜像存储
+pub async fn check_image_store(
+    image_service: &ImageServiceImpl,
+    request: Request<CheckImageStoreRequest>,
+) -> Result<Response<CheckImageStoreResponse>, Status> {
+    let entry = AuditEntry::new(CHECK_IMAGE_STORE_METHOD, &request);
+    let mode = if request.get_ref().full {
+        FsckMode::Full
+    } else {
+        FsckMode::Quick
+    };
+    let report = image_service.fsck(mode).await;
+    let result = Ok(Response::new(CheckImageStoreResponse {
+        verified: report.verified,
+        repaired: report.repaired,
+        removed: report.removed,
+        failed: report.failed,
+    }));
+    image_service.audit(entry, &result);
+    result
+}
+
+/// 管理服务的 tonic server
+#[derive(Clone)]
+pub struct AdminServiceServer {
+    inner: Arc<RuntimeServiceImpl>,
+    images: Arc<ImageServiceImpl>,
+}
+
+impl AdminServiceServer {
+    pub fn new(inner: Arc<RuntimeServiceImpl>, images: Arc<ImageServiceImpl>) -> Self {
+        Self { inner, images }
+    }
+}
+
+impl tonic::server::NamedService for AdminServiceServer {
+    const NAME: &'static str = ADMIN_SERVICE_NAME;
+}
+
+/// 把异步闭包适配为 tonic 的一元或服务端流方法
+struct UnaryMethod<F>(F);
+
+impl<F, Fut, Req, Resp> tonic::server::UnaryService<Req> for UnaryMethod<F>
+where
+    F: FnMut(Request<Req>) -> Fut,
+    Fut: Future<Output = Result<Response<Resp>, Status>> + Send + 'static,
+{
+    type Response = Resp;
+    type Future = BoxFuture<Response<Resp>, Status>;
+
+    fn call(&mut self, request: Request<Req>) -> Self::Future {
+        Box::pin((self.0)(request))
+    }
+}
+
+impl<F, Fut, Req, Resp, S> tonic::server::ServerStreamingService<Req> for UnaryMethod<F>
+where
+    F: FnMut(Request<Req>) -> Fut,
+    Fut: Future<Output = Result<Response<S>, Status>> + Send + 'static,
+    S: tokio_stream::Stream<Item = Result<Resp, Status>> + Send + 'static,
+{
+    type Response = Resp;
+    type ResponseStream = S;
+    type Future = BoxFuture<Response<S>, Status>;
+
+    fn call(&mut self, request: Request<Req>) -> Self::Future {
+        Box::pin((self.0)(request))
+    }
+}
+
+impl<B> tower::Service<http::Request<B>> for AdminServiceServer
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<tonic::body::BoxBody>;
+    type Error = std::convert::Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let inner = self.inner.clone();
+        match req.uri().path() {
+            PAUSE_CONTAINER_METHOD => Box::pin(async move {
+                let method = UnaryMethod(move |request| {
+                    let inner = inner.clone();
+                    async move { inner.pause_container(request).await }
+                });
+                let mut grpc = tonic::server::Grpc::new(tonic::codec::ProstCodec::default());
+                Ok(grpc.unary(method, req).await)
+            }),
+            RESUME_CONTAINER_METHOD => Box::pin(async move {
+                let method = UnaryMethod(move |request| {
+                    let inner = inner.clone();
+                    async move { inner.resume_container(request).await }
+                });
+                let mut grpc = tonic::server::Grpc::new(tonic::codec::ProstCodec::default());
+                Ok(grpc.unary(method, req).await)
+            }),
+            NODE_SUMMARY_METHOD => Box::pin(async move {
+                let method = UnaryMethod(move |request| {
+                    let inner = inner.clone();
+                    async move { inner.get_node_summary(request).await }
+                });
+                let mut grpc = tonic::server::Grpc::new(tonic::codec::ProstCodec::default());
+                Ok(grpc.unary(method, req).await)
+            }),
+            EXPORT_CONTAINER_METHOD => Box::pin(async move {
+                let method = UnaryMethod(move |request| {
+                    let inner = inner.clone();
+                    async move { inner.export_container(request).await }
+                });
+                let mut grpc = tonic::server::Grpc::new(tonic::codec::ProstCodec::default());
+                Ok(grpc.server_streaming(method, req).await)
+            }),
+            CHECK_IMAGE_STORE_METHOD => {
+                let images = self.images.clone();
+                Box::pin(async move {
+                    let method = UnaryMethod(move |request| {
+                        let images = images.clone();
+                        async move { check_image_store(&images, request).await }
+                    });
+                    let mut grpc = tonic::server::Grpc::new(tonic::codec::ProstCodec::default());
+                    Ok(grpc.unary(method, req).await)
+                })
+            }
+            _ => Box::pin(async move {
+                Ok(http::Response::builder()
+                    .status(200)
+                    .header("grpc-status", "12")
+                    .header("content-type", "application/grpc")
+                    .body(empty_body())
+                    .unwrap())
+            }),
+        }
+    }
+}
+
+/// 管理服务客户端
+#[derive(Debug, Clone)]
+pub struct AdminServiceClient {
+    inner: tonic::client::Grpc<Channel>,
+}
+
+impl AdminServiceClient {
+    pub fn new(channel: Channel) -> Self {
+        Self {
+            inner: tonic::client::Grpc::new(channel),
+        }
+    }
+
+    pub async fn pause_container(
+        &mut self,
+        request: PauseContainerRequest,
+    ) -> Result<Response<PauseContainerResponse>, Status> {
+        self.unary(PAUSE_CONTAINER_METHOD, request).await
+    }
+
+    pub async fn resume_container(
+        &mut self,
+        request: ResumeContainerRequest,
+    ) -> Result<Response<ResumeContainerResponse>, Status> {
+        self.unary(RESUME_CONTAINER_METHOD, request).await
+    }
+
+    pub async fn check_image_store(
+        &mut self,
+        request: CheckImageStoreRequest,
+    ) -> Result<Response<CheckImageStoreResponse>, Status> {
+        self.unary(CHECK_IMAGE_STORE_METHOD, request).await
+    }
+
+    pub async fn node_summary(
+        &mut self,
+        request: NodeSummaryRequest,
+    ) -> Result<Response<NodeSummaryResponse>, Status> {
+        self.unary(NODE_SUMMARY_METHOD, request).await
+    }
+
+    pub async fn export_container(
+        &mut self,
+        request: ExportContainerRequest,
+    ) -> Result<Response<Streaming<ExportContainerChunk>>, Status> {
+        self.inner
+            .ready()
+            .await
+            .map_err(|e| Status::unknown(format!("Service was not ready: {}", e)))?;
+        self.inner
+            .server_streaming(
+                Request::new(request),
+                http::uri::PathAndQuery::from_static(EXPORT_CONTAINER_METHOD),
+                tonic::codec::ProstCodec::default(),
+            )
+            .await
+    }
+
+    async fn unary<Req, Resp>(
+        &mut self,
+        method: &'static str,
+        request: Req,
+    ) -> Result<Response<Resp>, Status>
+    where
+        Req: prost::Message + Send + Sync + 'static,
+        Resp: prost::Message + Default + Send + Sync + 'static,
+    {
+        self.inner
+            .ready()
+            .await
+            .map_err(|e| Status::unknown(format!("Service was not ready: {}", e)))?;
+        self.inner
+            .unary(
+                Request::new(request),
+                http::uri::PathAndQuery::from_static(method),
+                tonic::codec::ProstCodec::default(),
+            )
+            .await
+    }
+}
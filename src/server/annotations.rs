@@ -128,6 +128,22 @@ impl RuntimeServiceImpl {
         key.starts_with(INTERNAL_ANNOTATION_PREFIX)
     }
 
+    /// 拒绝占用内部保留前缀的用户 annotation，避免覆盖运行时内部状态
+    pub(super) fn validate_external_annotations(
+        annotations: &HashMap<String, String>,
+    ) -> Result<(), Status> {
+        if let Some(key) = annotations
+            .keys()
+            .find(|key| Self::is_internal_annotation_key(key))
+        {
+            return Err(Status::invalid_argument(format!(
+                "annotation {} uses reserved prefix {}",
+                key, INTERNAL_ANNOTATION_PREFIX
+            )));
+        }
+        Ok(())
+    }
+
     pub(super) fn external_annotations(
         annotations: &HashMap<String, String>,
     ) -> HashMap<String, String> {
@@ -183,47 +199,111 @@ impl RuntimeServiceImpl {
         })
     }
 
-    pub(super) fn enrich_container_annotations(context: ContainerAnnotationContext<'_>) {
-        context.annotations.insert(
-            CRIO_CONTAINER_ID_ANNOTATION.to_string(),
+    /// 写入运行时拥有的 annotation
+    ///
+    /// 始终覆盖调用方提供的同名 key，避免伪造容器 ID、类型、日志路径等；
+    /// 被覆盖的用户原值记入 `shadowed`，供 status 原样返回。
+    pub(super) fn set_runtime_annotation(
+        annotations: &mut HashMap<String, String>,
+        shadowed: &mut HashMap<String, String>,
+        key: &str,
+        value: String,
+    ) {
+        if let Some(previous) = annotations.insert(key.to_string(), value) {
+            if annotations.get(key) != Some(&previous) {
+                shadowed.entry(key.to_string()).or_insert(previous);
+            }
+        }
+    }
+
+    /// 记录被运行时覆盖的用户 annotation 原值
+    pub(super) fn record_shadowed_annotations(
+        annotations: &mut HashMap<String, String>,
+        shadowed: &HashMap<String, String>,
+    ) -> Result<(), Status> {
+        if shadowed.is_empty() {
+            return Ok(());
+        }
+        Self::insert_internal_state(annotations, INTERNAL_SHADOWED_ANNOTATIONS_KEY, shadowed)
+    }
+
+    /// status/list/stats 对外返回的 annotation：剔除内部 key，并还原被运行时覆盖的用户原值
+    pub(super) fn reported_annotations(
+        annotations: &HashMap<String, String>,
+    ) -> HashMap<String, String> {
+        let mut reported = Self::external_annotations(annotations);
+        if let Some(shadowed) = Self::read_internal_state::<HashMap<String, String>>(
+            annotations,
+            INTERNAL_SHADOWED_ANNOTATIONS_KEY,
+        ) {
+            reported.extend(shadowed);
+        }
+        reported
+    }
+
+    /// 补充运行时元数据 annotation，运行时拥有的 key 总是覆盖调用方提供的同名值
+    pub(super) fn enrich_container_annotations(
+        context: ContainerAnnotationContext<'_>,
+    ) -> Result<(), Status> {
+        let mut shadowed = HashMap::new();
+        Self::set_runtime_annotation(
+            context.annotations,
+            &mut shadowed,
+            CRIO_CONTAINER_ID_ANNOTATION,
             context.container_id.to_string(),
         );
-        context.annotations.insert(
-            CRIO_SANDBOX_ID_ANNOTATION.to_string(),
+        Self::set_runtime_annotation(
+            context.annotations,
+            &mut shadowed,
+            CRIO_SANDBOX_ID_ANNOTATION,
             context.pod_sandbox_id.to_string(),
         );
-        context.annotations.insert(
-            CRIO_CONTAINER_NAME_ANNOTATION.to_string(),
+        Self::set_runtime_annotation(
+            context.annotations,
+            &mut shadowed,
+            CRIO_CONTAINER_NAME_ANNOTATION,
             context
                 .metadata_name
                 .unwrap_or(context.container_id)
                 .to_string(),
         );
-        context.annotations.insert(
-            CRIO_CONTAINER_TYPE_ANNOTATION.to_string(),
+        Self::set_runtime_annotation(
+            context.annotations,
+            &mut shadowed,
+            CRIO_CONTAINER_TYPE_ANNOTATION,
             CONTAINER_TYPE_CONTAINER.to_string(),
         );
-        context.annotations.insert(
-            CONTAINERD_SANDBOX_ID_ANNOTATION.to_string(),
+        Self::set_runtime_annotation(
+            context.annotations,
+            &mut shadowed,
+            CONTAINERD_SANDBOX_ID_ANNOTATION,
             context.pod_sandbox_id.to_string(),
         );
-        context.annotations.insert(
-            CONTAINERD_CONTAINER_TYPE_ANNOTATION.to_string(),
+        Self::set_runtime_annotation(
+            context.annotations,
+            &mut shadowed,
+            CONTAINERD_CONTAINER_TYPE_ANNOTATION,
             CONTAINER_TYPE_CONTAINER.to_string(),
         );
         if let Some(name) = context.metadata_name {
-            context.annotations.insert(
-                CONTAINERD_CONTAINER_NAME_ANNOTATION.to_string(),
+            Self::set_runtime_annotation(
+                context.annotations,
+                &mut shadowed,
+                CONTAINERD_CONTAINER_NAME_ANNOTATION,
                 name.to_string(),
             );
-            context.annotations.insert(
-                KUBERNETES_CONTAINER_NAME_ANNOTATION.to_string(),
+            Self::set_runtime_annotation(
+                context.annotations,
+                &mut shadowed,
+                KUBERNETES_CONTAINER_NAME_ANNOTATION,
                 name.to_string(),
             );
         }
         if let Some(image) = context.requested_image.filter(|image| !image.is_empty()) {
-            context.annotations.insert(
-                CRIO_USER_REQUESTED_IMAGE_ANNOTATION.to_string(),
+            Self::set_runtime_annotation(
+                context.annotations,
+                &mut shadowed,
+                CRIO_USER_REQUESTED_IMAGE_ANNOTATION,
                 image.to_string(),
             );
         }
@@ -231,12 +311,16 @@ impl RuntimeServiceImpl {
             .resolved_image_name
             .filter(|image| !image.is_empty())
         {
-            context.annotations.insert(
-                CRIO_IMAGE_NAME_ANNOTATION.to_string(),
+            Self::set_runtime_annotation(
+                context.annotations,
+                &mut shadowed,
+                CRIO_IMAGE_NAME_ANNOTATION,
                 image_name.to_string(),
             );
-            context.annotations.insert(
-                CONTAINERD_IMAGE_NAME_ANNOTATION.to_string(),
+            Self::set_runtime_annotation(
+                context.annotations,
+                &mut shadowed,
+                CONTAINERD_IMAGE_NAME_ANNOTATION,
                 image_name.to_string(),
             );
         }
@@ -245,20 +329,27 @@ impl RuntimeServiceImpl {
             .map(|state| state.runtime_handler.clone())
             .filter(|handler| !handler.is_empty())
             .unwrap_or_else(|| context.default_runtime.to_string());
-        context.annotations.insert(
-            CRIO_RUNTIME_HANDLER_ANNOTATION.to_string(),
+        Self::set_runtime_annotation(
+            context.annotations,
+            &mut shadowed,
+            CRIO_RUNTIME_HANDLER_ANNOTATION,
             runtime_handler.clone(),
         );
-        context.annotations.insert(
-            CONTAINERD_RUNTIME_HANDLER_ANNOTATION.to_string(),
+        Self::set_runtime_annotation(
+            context.annotations,
+            &mut shadowed,
+            CONTAINERD_RUNTIME_HANDLER_ANNOTATION,
             runtime_handler,
         );
         if let Some(path) = context.log_path {
-            context.annotations.insert(
-                CRIO_LOG_PATH_ANNOTATION.to_string(),
+            Self::set_runtime_annotation(
+                context.annotations,
+                &mut shadowed,
+                CRIO_LOG_PATH_ANNOTATION,
                 path.to_string_lossy().to_string(),
             );
         }
+        Self::record_shadowed_annotations(context.annotations, &shadowed)
     }
 
     pub(super) fn insert_internal_state<T: Serialize>(
@@ -272,6 +363,22 @@ impl RuntimeServiceImpl {
         Ok(())
     }
 
+    /// `state` 与已记录的内部状态是否不同；按 JSON 值比较，不受 map 字段序列化顺序影响
+    pub(super) fn internal_state_changed<T: Serialize>(
+        annotations: &HashMap<String, String>,
+        key: &str,
+        state: &T,
+    ) -> bool {
+        let Ok(encoded) = serde_json::to_value(state) else {
+            return true;
+        };
+        annotations
+            .get(key)
+            .and_then(|raw| serde_json::from_str::<serde_json::Value>(raw).ok())
+            .as_ref()
+            != Some(&encoded)
+    }
+
     pub(super) fn read_internal_state<T: for<'de> Deserialize<'de>>(
         annotations: &HashMap<String, String>,
         key: &str,
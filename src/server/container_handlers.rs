@@ -54,17 +54,569 @@ impl RuntimeServiceImpl {
         }
     }
 
+    pub(super) fn mount_manager(&self) -> MountManager {
+        MountManager::new(self.config.state_dir.join("mounts"))
+    }
+
+    /// 读取 subPath 声明，并确认每一项都能在对应卷内安全解析
+    pub(super) fn validate_mount_subpaths(
+        config: &crate::proto::runtime::v1::ContainerConfig,
+    ) -> Result<HashMap<String, String>, Status> {
+        let Some(raw) = config.annotations.get(MOUNT_SUBPATHS_ANNOTATION_KEY) else {
+            return Ok(HashMap::new());
+        };
+        let subpaths: HashMap<String, String> = serde_json::from_str(raw).map_err(|e| {
+            Status::invalid_argument(format!(
+                "Invalid {} annotation: {}",
+                MOUNT_SUBPATHS_ANNOTATION_KEY, e
+            ))
+        })?;
+        for (container_path, sub_path) in &subpaths {
+            let mount = config
+                .mounts
+                .iter()
+                .find(|mount| &mount.container_path == container_path)
+                .ok_or_else(|| {
+                    Status::invalid_argument(format!(
+                        "subPath declared for unknown mount {}",
+                        container_path
+                    ))
+                })?;
+            resolve_subpath(Path::new(&mount.host_path), sub_path).map_err(|e| {
+                Status::invalid_argument(format!(
+                    "Invalid subPath for mount {}: {:#}",
+                    container_path, e
+                ))
+            })?;
+        }
+        Ok(subpaths)
+    }
+
+    /// 按 `[runtime.mount_policy]` 检查 CRI 请求中的宿主机路径挂载
+    ///
+    /// 命中 `denied_host_paths` 或不在非空的 `allowed_host_paths` 内时返回 `PermissionDenied`；
+    /// 命中 `readonly_host_paths` 时把挂载改为只读。每个决定都记录日志。
+    pub(super) fn apply_mount_policy(
+        &self,
+        config: &mut crate::proto::runtime::v1::ContainerConfig,
+    ) -> Result<(), Status> {
+        let policy = &self.config.mount_policy;
+        if policy.is_empty() {
+            return Ok(());
+        }
+        let container_name = config
+            .metadata
+            .as_ref()
+            .map(|metadata| metadata.name.clone())
+            .unwrap_or_default();
+        for mount in &mut config.mounts {
+            let requested = Self::normalize_policy_path(Path::new(&mount.host_path));
+            let resolved = std::fs::canonicalize(&requested).unwrap_or(requested.clone());
+            let candidates = [requested.as_path(), resolved.as_path()];
+
+            if let Some(prefix) = Self::mount_policy_match(&policy.denied_host_paths, &candidates) {
+                log::warn!(
+                    "Mount policy denied host path {} for container {}: matches denied prefix {}",
+                    mount.host_path,
+                    container_name,
+                    prefix
+                );
+                return Err(Status::permission_denied(format!(
+                    "host path {} is denied by mount policy ({})",
+                    mount.host_path, prefix
+                )));
+            }
+            if !policy.allowed_host_paths.is_empty()
+                && Self::mount_policy_match(&policy.allowed_host_paths, &[resolved.as_path()])
+                    .is_none()
+            {
+                log::warn!(
+                    "Mount policy denied host path {} for container {}: not under an allowed prefix",
+                    mount.host_path,
+                    container_name
+                );
+                return Err(Status::permission_denied(format!(
+                    "host path {} is not allowed by mount policy",
+                    mount.host_path
+                )));
+            }
+            match Self::mount_policy_match(&policy.readonly_host_paths, &candidates) {
+                Some(prefix) if !mount.readonly => {
+                    log::info!(
+                        "Mount policy forces host path {} read-only for container {} ({})",
+                        mount.host_path,
+                        container_name,
+                        prefix
+                    );
+                    mount.readonly = true;
+                }
+                _ => log::info!(
+                    "Mount policy allowed host path {} for container {}",
+                    mount.host_path,
+                    container_name
+                ),
+            }
+        }
+        Ok(())
+    }
+
+    /// 第一个包含任一候选路径的前缀；前缀同样解析符号链接
+    fn mount_policy_match<'a>(prefixes: &'a [String], candidates: &[&Path]) -> Option<&'a str> {
+        prefixes
+            .iter()
+            .find(|prefix| {
+                let lexical = Self::normalize_policy_path(Path::new(prefix));
+                let resolved = std::fs::canonicalize(&lexical).unwrap_or(lexical.clone());
+                candidates
+                    .iter()
+                    .any(|path| path.starts_with(&lexical) || path.starts_with(&resolved))
+            })
+            .map(String::as_str)
+    }
+
+    /// 按字面消去 `.` 与 `..`，路径不存在时也能与前缀比较
+    fn normalize_policy_path(path: &Path) -> PathBuf {
+        let mut normalized = PathBuf::from("/");
+        for component in path.components() {
+            match component {
+                std::path::Component::ParentDir => {
+                    normalized.pop();
+                }
+                std::path::Component::Normal(part) => normalized.push(part),
+                _ => {}
+            }
+        }
+        normalized
+    }
+
+    /// 容器日志路径：`log_path` 相对于 sandbox 的 `log_directory`，结果按字面规范化，
+    /// 不得逃出该目录；sandbox 没有日志目录时原样使用
+    pub(super) fn resolve_container_log_path(
+        log_directory: Option<&str>,
+        log_path: &str,
+    ) -> Result<Option<PathBuf>, Status> {
+        if log_path.is_empty() {
+            return Ok(None);
+        }
+        let Some(log_directory) = log_directory else {
+            return Ok(Some(PathBuf::from(log_path)));
+        };
+        let directory = Self::normalize_policy_path(Path::new(log_directory));
+        let resolved = Self::normalize_policy_path(&directory.join(log_path));
+        if resolved == directory || !resolved.starts_with(&directory) {
+            return Err(Status::invalid_argument(format!(
+                "log_path {:?} escapes the pod log directory {}",
+                log_path,
+                directory.display()
+            )));
+        }
+        Ok(Some(resolved))
+    }
+
+    /// 日志文件的父目录创建后再按真实路径检查一次，防止通过符号链接逃出 Pod 日志目录
+    fn ensure_log_path_within(log_directory: &Path, log_path: &Path) -> Result<(), Status> {
+        let (Ok(directory), Some(Ok(parent))) = (
+            std::fs::canonicalize(log_directory),
+            log_path.parent().map(std::fs::canonicalize),
+        ) else {
+            return Ok(());
+        };
+        if parent.starts_with(&directory) {
+            return Ok(());
+        }
+        Err(Status::invalid_argument(format!(
+            "log_path {} resolves outside the pod log directory {}",
+            log_path.display(),
+            directory.display()
+        )))
+    }
+
+    /// 读取 `io.crius.fsgroup`，值必须是数字 gid
+    pub(super) fn fs_group(annotations: &HashMap<String, String>) -> Result<Option<u32>, Status> {
+        let Some(raw) = annotations.get(FS_GROUP_ANNOTATION_KEY) else {
+            return Ok(None);
+        };
+        raw.trim().parse::<u32>().map(Some).map_err(|_| {
+            Status::invalid_argument(format!(
+                "{} annotation {:?} is not a numeric gid",
+                FS_GROUP_ANNOTATION_KEY, raw
+            ))
+        })
+    }
+
+    /// 校验 exec 覆盖注解的格式，用户和工作目录是否存在推迟到 exec 时在容器 rootfs 中检查
+    pub(super) fn validate_exec_overrides(
+        annotations: &HashMap<String, String>,
+    ) -> Result<(), Status> {
+        if let Some(raw) = annotations.get(EXEC_USER_ANNOTATION_KEY) {
+            let (user, group) = match raw.split_once(':') {
+                Some((user, group)) => (user, Some(group)),
+                None => (raw.as_str(), None),
+            };
+            let valid_part = |part: &str| !part.is_empty() && !part.contains(['/', ':']);
+            if !valid_part(user) || group.is_some_and(|group| !valid_part(group)) {
+                return Err(Status::invalid_argument(format!(
+                    "{} annotation {:?} must be user[:group]",
+                    EXEC_USER_ANNOTATION_KEY, raw
+                )));
+            }
+        }
+        if let Some(raw) = annotations.get(EXEC_CWD_ANNOTATION_KEY) {
+            if !raw.starts_with('/') {
+                return Err(Status::invalid_argument(format!(
+                    "{} annotation {:?} must be an absolute path",
+                    EXEC_CWD_ANNOTATION_KEY, raw
+                )));
+            }
+        }
+        Self::exec_env(annotations)?;
+        Ok(())
+    }
+
+    /// 读取 `io.crius.exec-env`，按变量名排序转换为 `KEY=VALUE`
+    pub(super) fn exec_env(annotations: &HashMap<String, String>) -> Result<Vec<String>, Status> {
+        let Some(raw) = annotations.get(EXEC_ENV_ANNOTATION_KEY) else {
+            return Ok(Vec::new());
+        };
+        let env: std::collections::BTreeMap<String, String> =
+            serde_json::from_str(raw).map_err(|e| {
+                Status::invalid_argument(format!(
+                    "Invalid {} annotation: {}",
+                    EXEC_ENV_ANNOTATION_KEY, e
+                ))
+            })?;
+        env.into_iter()
+            .map(|(key, value)| {
+                if key.is_empty() || key.contains('=') {
+                    return Err(Status::invalid_argument(format!(
+                        "{} annotation has invalid variable name {:?}",
+                        EXEC_ENV_ANNOTATION_KEY, key
+                    )));
+                }
+                Ok(format!("{}={}", key, value))
+            })
+            .collect()
+    }
+
+    /// `/dev/shm` 大小：`io.kubernetes.cri-o.ShmSize` 注解覆盖 `[runtime].shm_size`
+    pub(super) fn shm_size(&self, annotations: &HashMap<String, String>) -> Result<u64, Status> {
+        let Some(raw) = annotations.get(CRIO_SHM_SIZE_ANNOTATION) else {
+            return Ok(self.config.shm_size);
+        };
+        crate::config::parse_shm_size(raw).map_err(|e| {
+            Status::invalid_argument(format!(
+                "invalid {} annotation: {}",
+                CRIO_SHM_SIZE_ANNOTATION, e
+            ))
+        })
+    }
+
+    /// 把可写的 Pod 卷递归改为 fsGroup 属组，条目过多的卷按配置跳过
+    pub(super) async fn apply_fs_group_to_mounts(
+        &self,
+        mounts: &[crate::proto::runtime::v1::Mount],
+        gid: u32,
+    ) -> Result<(), Status> {
+        use crate::storage::ownership::{apply_fs_group, is_pod_volume, FsGroupOutcome};
+
+        let mut volumes: Vec<PathBuf> = mounts
+            .iter()
+            .filter(|mount| !mount.readonly && is_pod_volume(Path::new(&mount.host_path)))
+            .map(|mount| PathBuf::from(&mount.host_path))
+            .collect();
+        volumes.sort();
+        volumes.dedup();
+        let max_entries = self.config.fs_group_max_entries;
+        for volume in volumes {
+            let path = volume.clone();
+            let outcome =
+                tokio::task::spawn_blocking(move || apply_fs_group(&path, gid, max_entries))
+                    .await
+                    .map_err(|e| Status::internal(format!("fsGroup task failed: {}", e)))?
+                    .map_err(|e| {
+                        Status::internal(format!(
+                            "Failed to apply fsGroup {} to {}: {:#}",
+                            gid,
+                            volume.display(),
+                            e
+                        ))
+                    })?;
+            match outcome {
+                FsGroupOutcome::Applied(entries) => log::info!(
+                    "Applied fsGroup {} to {} entries of {}",
+                    gid,
+                    entries,
+                    volume.display()
+                ),
+                FsGroupOutcome::AlreadyOwned => {}
+                FsGroupOutcome::TooLarge => log::warn!(
+                    "Skipping fsGroup {} for {}: more than {} entries",
+                    gid,
+                    volume.display(),
+                    max_entries
+                ),
+            }
+        }
+        Ok(())
+    }
+
+    /// 读取 `io.crius.env-file` 指向的 env 文件
+    ///
+    /// 文件必须位于配置的 `env_file_dir` 内，`..` 和指向目录外的符号链接都会被拒绝。
+    pub(super) fn load_env_file(
+        &self,
+        config: &crate::proto::runtime::v1::ContainerConfig,
+    ) -> Result<Vec<(String, String)>, Status> {
+        let Some(raw) = config.annotations.get(ENV_FILE_ANNOTATION_KEY) else {
+            return Ok(Vec::new());
+        };
+        let Some(allowed_dir) = self.config.env_file_dir.as_ref() else {
+            return Err(Status::invalid_argument(format!(
+                "{} annotation is not allowed: env_file_dir is not configured",
+                ENV_FILE_ANNOTATION_KEY
+            )));
+        };
+        let requested = Path::new(raw);
+        if requested
+            .components()
+            .any(|component| matches!(component, std::path::Component::ParentDir))
+        {
+            return Err(Status::invalid_argument(format!(
+                "Env file {:?} must not contain '..'",
+                raw
+            )));
+        }
+
+        let allowed_dir = std::fs::canonicalize(allowed_dir).map_err(|e| {
+            Status::failed_precondition(format!(
+                "Failed to resolve env_file_dir {}: {}",
+                allowed_dir.display(),
+                e
+            ))
+        })?;
+        let path = std::fs::canonicalize(allowed_dir.join(requested)).map_err(|e| {
+            Status::invalid_argument(format!("Failed to resolve env file {:?}: {}", raw, e))
+        })?;
+        if !path.starts_with(&allowed_dir) {
+            return Err(Status::invalid_argument(format!(
+                "Env file {:?} is outside {}",
+                raw,
+                allowed_dir.display()
+            )));
+        }
+
+        let contents = std::fs::read_to_string(&path).map_err(|e| {
+            Status::invalid_argument(format!("Failed to read env file {}: {}", path.display(), e))
+        })?;
+        Self::parse_env_file(&contents).map_err(|line| {
+            Status::invalid_argument(format!(
+                "Invalid env file {}: line {} is not KEY=VALUE",
+                path.display(),
+                line
+            ))
+        })
+    }
+
+    /// `io.crius.rootfs-path` 指定的 rootfs 目录，必须位于 `prepared_rootfs_dir` 内
+    pub(super) fn prepared_rootfs(
+        &self,
+        config: &crate::proto::runtime::v1::ContainerConfig,
+    ) -> Result<Option<PathBuf>, Status> {
+        let Some(raw) = config.annotations.get(ROOTFS_PATH_ANNOTATION_KEY) else {
+            return Ok(None);
+        };
+        let Some(allowed_dir) = self.config.prepared_rootfs_dir.as_ref() else {
+            return Err(Status::invalid_argument(format!(
+                "{} annotation is not allowed: prepared_rootfs_dir is not configured",
+                ROOTFS_PATH_ANNOTATION_KEY
+            )));
+        };
+        let requested = Path::new(raw);
+        if requested
+            .components()
+            .any(|component| matches!(component, std::path::Component::ParentDir))
+        {
+            return Err(Status::invalid_argument(format!(
+                "Rootfs path {:?} must not contain '..'",
+                raw
+            )));
+        }
+
+        let allowed_dir = std::fs::canonicalize(allowed_dir).map_err(|e| {
+            Status::failed_precondition(format!(
+                "Failed to resolve prepared_rootfs_dir {}: {}",
+                allowed_dir.display(),
+                e
+            ))
+        })?;
+        let path = std::fs::canonicalize(allowed_dir.join(requested)).map_err(|e| {
+            Status::invalid_argument(format!("Failed to resolve rootfs path {:?}: {}", raw, e))
+        })?;
+        if !path.starts_with(&allowed_dir) || path == allowed_dir {
+            return Err(Status::invalid_argument(format!(
+                "Rootfs path {:?} is outside {}",
+                raw,
+                allowed_dir.display()
+            )));
+        }
+        if !path.is_dir() {
+            return Err(Status::invalid_argument(format!(
+                "Rootfs path {} is not a directory",
+                path.display()
+            )));
+        }
+        Ok(Some(path))
+    }
+
+    /// 解析 KEY=VALUE 行，跳过空行和 `#` 注释；出错时返回行号
+    fn parse_env_file(contents: &str) -> Result<Vec<(String, String)>, usize> {
+        let mut vars = Vec::new();
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.split_once('=') {
+                Some((key, value)) if !key.trim().is_empty() => {
+                    vars.push((key.trim().to_string(), value.to_string()));
+                }
+                _ => return Err(index + 1),
+            }
+        }
+        Ok(vars)
+    }
+
+    /// 按优先级从低到高合并容器 env，同名变量保留首次出现的位置、取后者的值
+    ///
+    /// 顺序为：`default_env` 配置、镜像 Env、`HOSTNAME`、env 文件、CRI 请求；
+    /// 合并后仍没有 PATH 时追加 [`DEFAULT_PATH_ENV`](crate::oci::spec::DEFAULT_PATH_ENV)。
+    pub(super) fn merge_container_env(
+        &self,
+        image_ref: &str,
+        hostname: &str,
+        file_env: Vec<(String, String)>,
+        envs: &[crate::proto::runtime::v1::KeyValue],
+    ) -> Vec<(String, String)> {
+        let image_env = if image_ref.is_empty() {
+            Vec::new()
+        } else {
+            self.runtime
+                .image_config_metadata(image_ref)
+                .map(|image_config| image_config.env)
+                .unwrap_or_default()
+        };
+
+        let mut merged = Vec::new();
+        Self::overlay_env(&mut merged, self.config.default_env.iter().cloned());
+        Self::overlay_env(&mut merged, file_env);
+        Self::overlay_env(
+            &mut merged,
+            image_env.iter().filter_map(|item| {
+                item.split_once('=')
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+            }),
+        );
+        if !hostname.is_empty() {
+            Self::overlay_env(
+                &mut merged,
+                [("HOSTNAME".to_string(), hostname.to_string())],
+            );
+        }
+        Self::overlay_env(
+            &mut merged,
+            envs.iter().map(|env| (env.key.clone(), env.value.clone())),
+        );
+        if !merged.iter().any(|(key, _)| key == "PATH") {
+            merged.push((
+                "PATH".to_string(),
+                crate::oci::spec::DEFAULT_PATH_ENV.to_string(),
+            ));
+        }
+        merged
+    }
+
+    fn overlay_env(
+        merged: &mut Vec<(String, String)>,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) {
+        for (key, value) in vars {
+            match merged.iter_mut().find(|(existing, _)| existing == &key) {
+                Some(existing) => existing.1 = value,
+                None => merged.push((key, value)),
+            }
+        }
+    }
+
+    /// 按 Kubernetes 规则展开 `$(NAME)` 引用
+    ///
+    /// `$$` 转义为 `$`，`env` 中没有的名字以及不完整的引用原样保留。
+    pub(super) fn expand_env_references(input: &str, env: &[(String, String)]) -> String {
+        let mut expanded = String::with_capacity(input.len());
+        let mut rest = input;
+        while let Some(pos) = rest.find('$') {
+            expanded.push_str(&rest[..pos]);
+            let after = &rest[pos + 1..];
+            if let Some(tail) = after.strip_prefix('$') {
+                expanded.push('$');
+                rest = tail;
+            } else if let Some((name, tail)) = after
+                .strip_prefix('(')
+                .and_then(|reference| reference.split_once(')'))
+            {
+                match env.iter().rev().find(|(key, _)| key == name) {
+                    Some((_, value)) => expanded.push_str(value),
+                    None => {
+                        expanded.push_str("$(");
+                        expanded.push_str(name);
+                        expanded.push(')');
+                    }
+                }
+                rest = tail;
+            } else {
+                expanded.push('$');
+                rest = after;
+            }
+        }
+        expanded.push_str(rest);
+        expanded
+    }
+
+    /// 依次展开每个 env 值（只能引用排在前面的变量），再用最终 env 展开 command/args
+    pub(super) fn expand_container_env_references(
+        env: &mut [(String, String)],
+        command: &mut [String],
+        args: &mut [String],
+    ) {
+        for index in 0..env.len() {
+            let value = Self::expand_env_references(&env[index].1, &env[..index]);
+            env[index].1 = value;
+        }
+        for item in command.iter_mut().chain(args.iter_mut()) {
+            *item = Self::expand_env_references(item, env);
+        }
+    }
+
     pub(super) async fn rollback_failed_container_create(
         &self,
         container_id: &str,
         nri_event: NriContainerEvent,
     ) {
         self.undo_failed_nri_create_container(nri_event).await;
+        if let Err(err) = self.mount_manager().cleanup_container(container_id) {
+            log::warn!(
+                "Failed to clean up mounts of {} during create rollback: {:#}",
+                container_id,
+                err
+            );
+        }
 
         {
-            let mut containers = self.containers.lock().await;
+            let mut containers = self.containers.write().await;
             containers.remove(container_id);
         }
+        self.tasks
+            .shutdown(container_id, TASK_SHUTDOWN_TIMEOUT)
+            .await;
 
         {
             let mut persistence = self.persistence.lock().await;
@@ -101,7 +653,7 @@ impl RuntimeServiceImpl {
         };
 
         let updated_container = {
-            let mut containers = self.containers.lock().await;
+            let mut containers = self.containers.write().await;
             let Some(container) = containers.get_mut(container_id) else {
                 return Ok(None);
             };
@@ -116,12 +668,13 @@ impl RuntimeServiceImpl {
                 &container.annotations,
                 INTERNAL_CONTAINER_STATE_KEY,
             ) {
-                state.finished_at = Some(Self::now_nanos());
+                // 重复 stop 不应刷新首次退出时间
+                state.finished_at.get_or_insert(Self::now_nanos());
                 if resolved_exit_code.is_none() {
                     resolved_exit_code = state.exit_code;
                 }
                 if let Some(code) = resolved_exit_code {
-                    state.exit_code = Some(code);
+                    Self::set_exit_code(&self.runtime, container_id, &mut state, code);
                 }
                 if let Err(err) = Self::insert_internal_state(
                     &mut container.annotations,
@@ -202,13 +755,114 @@ impl RuntimeServiceImpl {
         }
     }
 
+    /// 获取容器（或创建幂等键）的生命周期锁，stop 与 remove 在其下串行执行
+    pub(super) async fn lock_container_lifecycle(
+        &self,
+        container_id: &str,
+    ) -> tokio::sync::OwnedMutexGuard<()> {
+        let lock = self
+            .container_lifecycle_locks
+            .lock()
+            .await
+            .entry(container_id.to_string())
+            .or_default()
+            .clone();
+        lock.lock_owned().await
+    }
+
+    /// 释放生命周期锁，没有其他等待者时从表中移除
+    pub(super) async fn release_container_lifecycle_lock(
+        &self,
+        container_id: &str,
+        guard: tokio::sync::OwnedMutexGuard<()>,
+    ) {
+        let mut locks = self.container_lifecycle_locks.lock().await;
+        drop(guard);
+        if locks
+            .get(container_id)
+            .is_some_and(|lock| Arc::strong_count(lock) == 1)
+        {
+            locks.remove(container_id);
+        }
+    }
+
+    /// 冻结或解冻运行中容器的 cgroup（runc pause/resume 写入 cgroup.freeze 或 freezer.state），
+    /// 并记录 paused 标记
+    pub(super) async fn set_container_paused(
+        &self,
+        requested_id: &str,
+        paused: bool,
+    ) -> Result<(), Status> {
+        let container_id = self.resolve_container_id(requested_id).await?;
+        let guard = self.lock_container_lifecycle(&container_id).await;
+        let result = async {
+            if !matches!(
+                self.runtime_container_status_checked(&container_id).await,
+                ContainerStatus::Running
+            ) {
+                return Err(Status::failed_precondition(format!(
+                    "container {} is not running",
+                    container_id
+                )));
+            }
+            let runtime = self.runtime.clone();
+            let id = container_id.clone();
+            tokio::task::spawn_blocking(move || {
+                if paused {
+                    runtime.pause_container(&id)
+                } else {
+                    runtime.resume_container(&id)
+                }
+            })
+            .await
+            .map_err(|e| Status::internal(format!("Failed to spawn blocking task: {}", e)))?
+            .map_err(|e| {
+                Status::internal(format!(
+                    "Failed to {} container {}: {:#}",
+                    if paused { "pause" } else { "resume" },
+                    container_id,
+                    e
+                ))
+            })?;
+            self.mutate_container_internal_state(&container_id, |state| state.paused = paused)
+                .await?;
+            log::info!(
+                "Container {} {}",
+                container_id,
+                if paused { "paused" } else { "resumed" }
+            );
+            Ok(())
+        }
+        .await;
+        self.release_container_lifecycle_lock(&container_id, guard)
+            .await;
+        result
+    }
+
     pub(super) async fn stop_container_internal(
         &self,
         actual_container_id: &str,
         timeout: u32,
     ) -> Result<Option<Container>, Status> {
+        let guard = self.lock_container_lifecycle(actual_container_id).await;
+        let result = self
+            .stop_container_locked(actual_container_id, timeout)
+            .await;
+        self.release_container_lifecycle_lock(actual_container_id, guard)
+            .await;
+        result.map_err(Status::from)
+    }
+
+    /// 停止容器：SIGTERM → 宽限期 → SIGKILL，等到进程退出后才更新为 EXITED
+    ///
+    /// 调用方须持有该容器的生命周期锁。
+    async fn stop_container_locked(
+        &self,
+        actual_container_id: &str,
+        timeout: u32,
+    ) -> Result<Option<Container>, StopError> {
         let (container_pod_id, container_annotations) = {
-            let containers = self.containers.lock().await;
+            let containers = self.containers.read().await;
             let container = containers
                 .get(actual_container_id)
                 .ok_or_else(|| Status::not_found("Container not found"))?;
@@ -237,7 +891,21 @@ impl RuntimeServiceImpl {
         })
         .await
         .map_err(|e| Status::internal(format!("Failed to spawn blocking task: {}", e)))?
-        .map_err(|e| Status::internal(format!("Failed to stop container: {}", e)))?;
+        .map_err(|e| match e.downcast::<KillTimeoutError>() {
+            Ok(err) => StopError::KillTimeout(err),
+            Err(e) => Status::internal(format!("Failed to stop container: {}", e)).into(),
+        })?;
+        // 主进程退出不一定结束 exec 进程（如共享 Pod 的 PID 命名空间），由 crius 清理
+        if let Some(streaming) = self.streaming.lock().await.clone() {
+            let killed = streaming.kill_exec_processes(actual_container_id);
+            if killed > 0 {
+                log::info!(
+                    "Killed {} exec process(es) left in stopped container {}",
+                    killed,
+                    actual_container_id
+                );
+            }
+        }
 
         let final_runtime_status = {
             let runtime = self.runtime.clone();
@@ -270,9 +938,21 @@ impl RuntimeServiceImpl {
     pub(super) async fn remove_container_internal(
         &self,
         actual_container_id: &str,
+    ) -> Result<Option<Container>, Status> {
+        let guard = self.lock_container_lifecycle(actual_container_id).await;
+        let result = self.remove_container_locked(actual_container_id).await;
+        self.release_container_lifecycle_lock(actual_container_id, guard)
+            .await;
+        result
+    }
+
+    /// 调用方须持有该容器的生命周期锁
+    async fn remove_container_locked(
+        &self,
+        actual_container_id: &str,
     ) -> Result<Option<Container>, Status> {
         let (container_pod_id, container_annotations) = {
-            let containers = self.containers.lock().await;
+            let containers = self.containers.read().await;
             let container = containers
                 .get(actual_container_id)
                 .ok_or_else(|| Status::not_found("Container not found"))?;
@@ -293,17 +973,27 @@ impl RuntimeServiceImpl {
             runtime_status,
             ContainerStatus::Created | ContainerStatus::Running
         );
+        let mut force_remove = false;
         if needs_nri_stop_before_remove && !stop_notified {
-            let updated_container = self
-                .stop_container_internal(actual_container_id, 30)
-                .await?;
-            if let Some(container) = updated_container {
-                self.emit_container_event(
-                    ContainerEventType::ContainerStoppedEvent,
-                    &container,
-                    Some(container.state),
-                )
-                .await;
+            match self
+                .stop_container_locked(actual_container_id, self.config.remove_grace_period_secs)
+                .await
+            {
+                Ok(Some(container)) => {
+                    self.emit_container_event(
+                        ContainerEventType::ContainerStoppedEvent,
+                        &container,
+                        Some(container.state),
+                    )
+                    .await;
+                }
+                Ok(None) => {}
+                // 进程在 SIGKILL 后仍未退出（如不可中断睡眠、runc 卡住）时不再阻塞 kubelet 的清理
+                Err(StopError::KillTimeout(err)) => {
+                    log::warn!("{}, forcing removal", err);
+                    force_remove = true;
+                }
+                Err(StopError::Status(status)) => return Err(status),
             }
         }
 
@@ -338,22 +1028,63 @@ impl RuntimeServiceImpl {
                 .await?;
         }
 
+        if force_remove {
+            self.mutate_container_internal_state(actual_container_id, |state| {
+                state.finished_at.get_or_insert(Self::now_nanos());
+                state.exit_code.get_or_insert(FORCE_REMOVED_EXIT_CODE);
+                state.exit_reason = Some(FORCE_REMOVED_REASON.to_string());
+                state.exit_message =
+                    Some("container did not exit after SIGKILL and was force removed".to_string());
+            })
+            .await?;
+            if let Some(container) = self.containers.write().await.get_mut(actual_container_id) {
+                container.state = ContainerState::ContainerExited as i32;
+            }
+        }
+
         let deleted_container = {
-            let containers = self.containers.lock().await;
+            let containers = self.containers.read().await;
             containers.get(actual_container_id).cloned()
         };
 
         let runtime = self.runtime.clone();
         let actual_container_id_owned = actual_container_id.to_string();
-        tokio::task::spawn_blocking(move || runtime.remove_container(&actual_container_id_owned))
+        if force_remove {
+            let leaked = tokio::task::spawn_blocking(move || {
+                runtime.force_remove_container(&actual_container_id_owned)
+            })
+            .await
+            .map_err(|e| Status::internal(format!("Failed to spawn blocking task: {}", e)))?;
+            if !leaked.is_empty() {
+                log::error!(
+                    "Force removed container {} leaked: {}",
+                    actual_container_id,
+                    leaked.join("; ")
+                );
+            }
+        } else {
+            tokio::task::spawn_blocking(move || {
+                runtime.remove_container(&actual_container_id_owned)
+            })
             .await
             .map_err(|e| Status::internal(format!("Failed to spawn blocking task: {}", e)))?
             .map_err(|e| Status::internal(format!("Failed to remove container: {}", e)))?;
+        }
+        if let Err(err) = self.mount_manager().cleanup_container(actual_container_id) {
+            log::warn!(
+                "Failed to clean up mounts of {}: {:#}",
+                actual_container_id,
+                err
+            );
+        }
 
         {
-            let mut containers = self.containers.lock().await;
+            let mut containers = self.containers.write().await;
             containers.remove(actual_container_id);
         }
+        self.tasks
+            .shutdown(actual_container_id, TASK_SHUTDOWN_TIMEOUT)
+            .await;
 
         let mut persistence = self.persistence.lock().await;
         if let Err(err) = persistence.delete_container(actual_container_id) {
@@ -369,6 +1100,103 @@ impl RuntimeServiceImpl {
         Ok(deleted_container)
     }
 
+    /// 以镜像 Labels 为默认值合并 CRI labels，同名时 CRI 优先
+    pub(super) fn container_labels_with_image_defaults(
+        &self,
+        container_id: &str,
+        image_ref: &str,
+        cri_labels: &HashMap<String, String>,
+    ) -> HashMap<String, String> {
+        if image_ref.is_empty() {
+            return cri_labels.clone();
+        }
+        let image_config = match self.runtime.image_config_metadata(image_ref) {
+            Ok(image_config) => image_config,
+            Err(err) => {
+                log::debug!("No local image config for {}: {:#}", image_ref, err);
+                return cri_labels.clone();
+            }
+        };
+        if !image_config.exposed_ports.is_empty() {
+            log::info!(
+                "Container {} image {} exposes ports {:?}",
+                container_id,
+                image_ref,
+                image_config.exposed_ports
+            );
+        }
+        let mut labels = image_config.labels;
+        labels.extend(cri_labels.iter().map(|(k, v)| (k.clone(), v.clone())));
+        labels
+    }
+
+    /// 容器的优雅停止信号：CRI annotation 优先，其次为镜像 StopSignal，返回规范化的信号名
+    pub(super) fn container_stop_signal(
+        &self,
+        image_ref: &str,
+        cri_annotations: &HashMap<String, String>,
+    ) -> Result<Option<String>, Status> {
+        let raw = cri_annotations
+            .get(STOP_SIGNAL_ANNOTATION)
+            .cloned()
+            .or_else(|| {
+                if image_ref.is_empty() {
+                    return None;
+                }
+                self.runtime
+                    .image_config_metadata(image_ref)
+                    .ok()
+                    .and_then(|image_config| image_config.stop_signal)
+            });
+        raw.map(|raw| {
+            parse_stop_signal(&raw)
+                .map(|signal| signal.as_str().to_string())
+                .map_err(|e| Status::invalid_argument(e.to_string()))
+        })
+        .transpose()
+    }
+
+    /// 容器日志轮转上限：注解覆盖全局 `[runtime.container_log]`
+    pub(super) fn container_log_limits(
+        &self,
+        cri_annotations: &HashMap<String, String>,
+    ) -> Result<(u64, u32), Status> {
+        let (default_size, default_files) = self
+            .config
+            .container_log
+            .limits()
+            .map_err(|e| Status::internal(e.to_string()))?;
+        let max_size = cri_annotations
+            .get(CONTAINER_LOG_MAX_SIZE_ANNOTATION)
+            .map(|raw| {
+                crate::config::parse_log_size(raw).map_err(|e| {
+                    Status::invalid_argument(format!(
+                        "invalid {} annotation: {}",
+                        CONTAINER_LOG_MAX_SIZE_ANNOTATION, e
+                    ))
+                })
+            })
+            .transpose()?
+            .unwrap_or(default_size);
+        let max_files = cri_annotations
+            .get(CONTAINER_LOG_MAX_FILES_ANNOTATION)
+            .map(|raw| {
+                raw.trim()
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|files| *files > 0)
+                    .ok_or_else(|| {
+                        Status::invalid_argument(format!(
+                            "invalid {} annotation {:?}: must be a positive integer",
+                            CONTAINER_LOG_MAX_FILES_ANNOTATION, raw
+                        ))
+                    })
+            })
+            .transpose()?
+            .unwrap_or(default_files);
+        Ok((max_size, max_files))
+    }
+
     pub(super) fn apply_adjusted_annotations(
         annotations: &mut HashMap<String, String>,
         adjustment: &crate::nri_proto::api::ContainerAdjustment,
@@ -382,6 +1210,36 @@ impl RuntimeServiceImpl {
         }
     }
 
+    /// 用 NRI 调整和 cgroup 裁剪后的资源覆盖内部状态中的请求值，
+    /// 使 ContainerStatus.resources 反映实际下发给运行时的限制
+    pub(super) fn record_applied_linux_resources(
+        annotations: &mut HashMap<String, String>,
+        adjustment: &crate::nri_proto::api::ContainerAdjustment,
+        nri_config: &NriConfig,
+    ) -> Result<(), Status> {
+        let Some(mut state) = Self::read_internal_state::<StoredContainerState>(
+            annotations,
+            INTERNAL_CONTAINER_STATE_KEY,
+        ) else {
+            return Ok(());
+        };
+        let adjusted = adjustment
+            .linux
+            .as_ref()
+            .and_then(|linux| linux.resources.as_ref());
+        if state.linux_resources.is_none() && adjusted.is_none() {
+            return Ok(());
+        }
+
+        let mut resources = state.linux_resources.take().unwrap_or_default();
+        if let Some(adjusted) = adjusted {
+            resources.apply_nri(adjusted);
+        }
+        Self::sanitize_stored_runtime_resources_for_nri_config(&mut resources, nri_config);
+        state.linux_resources = Some(resources);
+        Self::insert_internal_state(annotations, INTERNAL_CONTAINER_STATE_KEY, &state)
+    }
+
     pub(super) fn refresh_nri_event_container_from_spec(
         event: &mut NriContainerEvent,
         spec: &crate::oci::spec::Spec,
@@ -470,12 +1328,12 @@ impl RuntimeServiceImpl {
         annotations: &HashMap<String, String>,
     ) -> NriContainerEvent {
         let pod = {
-            let pods = self.pod_sandboxes.lock().await;
+            let pods = self.pod_sandboxes.read().await;
             pods.get(pod_id)
                 .map(|pod| Self::build_nri_pod_from_proto(&self.runtime, pod))
         };
         let container = {
-            let containers = self.containers.lock().await;
+            let containers = self.containers.read().await;
             containers
                 .get(container_id)
                 .map(|container| Self::build_nri_container_from_proto(&self.runtime, container))
@@ -502,7 +1360,8 @@ impl RuntimeServiceImpl {
         let _sync_block = self.nri.block_plugin_sync().await;
         let req = request.into_inner();
         let container_id = req.container_id;
-        let timeout = req.timeout as u32;
+        // 使用请求中的宽限期原样停止：0 表示立即 SIGKILL，负值按 0 处理
+        let timeout = u32::try_from(req.timeout.max(0)).unwrap_or(u32::MAX);
 
         log::info!("Stopping container {}", container_id);
 
@@ -510,9 +1369,14 @@ impl RuntimeServiceImpl {
         else {
             return Ok(Response::new(StopContainerResponse {}));
         };
-        let updated_container = self
+        // 等锁期间容器可能已被并发的 RemoveContainer 删除，此时 stop 视为成功
+        let updated_container = match self
             .stop_container_internal(&actual_container_id, timeout)
-            .await?;
+            .await
+        {
+            Err(status) if status.code() == tonic::Code::NotFound => None,
+            result => result?,
+        };
 
         log::info!("Container {} stopped", actual_container_id);
         if let Some(container) = updated_container {
@@ -568,12 +1432,14 @@ impl RuntimeServiceImpl {
 
         let container_id = self.resolve_container_id(&req.container_id).await?;
         let container = {
-            let containers = self.containers.lock().await;
+            let containers = self.containers.read().await;
             containers
                 .get(&container_id)
                 .cloned()
                 .ok_or_else(|| Status::not_found("Container not found"))?
         };
+        self.ensure_checkpoint_supported(&container.pod_sandbox_id)
+            .await?;
 
         let runtime_status = self.runtime_container_status_checked(&container_id).await;
         if matches!(runtime_status, ContainerStatus::Unknown) {
@@ -746,7 +1612,7 @@ impl RuntimeServiceImpl {
                             .as_ref()
                             .map(|metadata| metadata.attempt)
                     })
-                    .unwrap_or(1),
+                    .unwrap_or(DEFAULT_METADATA_ATTEMPT),
             }
         });
 
@@ -767,17 +1633,24 @@ impl RuntimeServiceImpl {
     ) -> Result<Response<UpdateContainerResourcesResponse>, Status> {
         let _sync_block = self.nri.block_plugin_sync().await;
         let req = request.into_inner();
+        if req.windows.is_some() {
+            return Err(super::validation::windows_unsupported(
+                "UpdateContainerResourcesRequest.windows",
+            ));
+        }
         let container_id = self.resolve_container_id(&req.container_id).await?;
 
         {
-            let containers = self.containers.lock().await;
+            let containers = self.containers.read().await;
             if !containers.contains_key(&container_id) {
                 return Err(Status::not_found("Container not found"));
             }
         }
 
         let linux = req.linux;
-        let _windows = req.windows;
+        if let Some(resources) = linux.as_ref() {
+            self.validate_linux_resources(resources)?;
+        }
 
         let runtime_status = self.runtime_container_status_checked(&container_id).await;
         if !matches!(
@@ -792,13 +1665,13 @@ impl RuntimeServiceImpl {
 
         let observed_state = Self::map_runtime_container_state(runtime_status);
         {
-            let mut containers = self.containers.lock().await;
+            let mut containers = self.containers.write().await;
             if let Some(container) = containers.get_mut(&container_id) {
                 container.state = observed_state;
             }
         }
         let (container_pod_id, container_annotations) = {
-            let containers = self.containers.lock().await;
+            let containers = self.containers.read().await;
             let container = containers
                 .get(&container_id)
                 .ok_or_else(|| Status::not_found("Container not found"))?;
@@ -858,7 +1731,7 @@ impl RuntimeServiceImpl {
             .await?;
         }
         let post_update_annotations = {
-            let containers = self.containers.lock().await;
+            let containers = self.containers.read().await;
             containers
                 .get(&container_id)
                 .map(|container| container.annotations.clone())
@@ -879,18 +1752,80 @@ impl RuntimeServiceImpl {
         Ok(Response::new(UpdateContainerResourcesResponse {}))
     }
 
+    /// 同一 sandbox 内名字与 attempt 都相同的创建视为重试，返回已有容器；
+    /// attempt 变化时创建新容器。同一幂等键的并发创建在锁下串行执行
     pub(super) async fn create_container_impl(
         &self,
         request: Request<CreateContainerRequest>,
     ) -> Result<Response<CreateContainerResponse>, Status> {
+        let Some(metadata) = request
+            .get_ref()
+            .config
+            .as_ref()
+            .and_then(|config| config.metadata.clone())
+            .filter(|metadata| !metadata.name.is_empty())
+        else {
+            return self.create_new_container(request).await;
+        };
+        let pod_sandbox_id = self
+            .resolve_pod_sandbox_id(&request.get_ref().pod_sandbox_id)
+            .await?;
+        let key = format!(
+            "create/{}/{}/{}",
+            pod_sandbox_id, metadata.name, metadata.attempt
+        );
+        let guard = self.lock_container_lifecycle(&key).await;
+        let existing = {
+            let containers = self.containers.read().await;
+            let existing = containers
+                .in_sandbox(&pod_sandbox_id)
+                .find(|container| container.metadata.as_ref() == Some(&metadata))
+                .map(|container| container.id.clone());
+            existing
+        };
+        let result = match existing {
+            Some(container_id) => {
+                log::info!(
+                    "CreateContainer for {} attempt {} in pod {} matches existing container {}",
+                    metadata.name,
+                    metadata.attempt,
+                    pod_sandbox_id,
+                    container_id
+                );
+                Ok(Response::new(CreateContainerResponse { container_id }))
+            }
+            None => self.create_new_container(request).await,
+        };
+        self.release_container_lifecycle_lock(&key, guard).await;
+        result
+    }
+
+    async fn create_new_container(
+        &self,
+        request: Request<CreateContainerRequest>,
+    ) -> Result<Response<CreateContainerResponse>, Status> {
+        let mut timer = OperationTimer::start(Operation::CreateContainer);
         let _sync_block = self.nri.block_plugin_sync().await;
         log::info!("CreateContainer called");
         let req = request.into_inner();
         let pod_sandbox_id = self.resolve_pod_sandbox_id(&req.pod_sandbox_id).await?;
-        let config = req
+        self.ensure_pod_sandbox_ready(&pod_sandbox_id).await?;
+        let mut config = req
             .config
             .ok_or_else(|| Status::invalid_argument("Container config not specified"))?;
         let sandbox_config = req.sandbox_config;
+        Self::validate_external_annotations(&config.annotations)?;
+        self.apply_mount_policy(&mut config)?;
+        if let Some(resources) = config
+            .linux
+            .as_ref()
+            .and_then(|linux| linux.resources.as_ref())
+        {
+            self.validate_linux_resources(resources)?;
+        }
+        let mount_subpaths = Self::validate_mount_subpaths(&config)?;
+        let env_file_vars = self.load_env_file(&config)?;
+        let prepared_rootfs = self.prepared_rootfs(&config)?;
 
         let container_id = uuid::Uuid::new_v4().to_simple().to_string();
 
@@ -898,7 +1833,7 @@ impl RuntimeServiceImpl {
         log::debug!("Container config: {:?}", config);
 
         let pod_state = {
-            let pod_sandboxes = self.pod_sandboxes.lock().await;
+            let pod_sandboxes = self.pod_sandboxes.read().await;
             pod_sandboxes.get(&pod_sandbox_id).and_then(|pod| {
                 Self::read_internal_state::<StoredPodState>(
                     &pod.annotations,
@@ -906,9 +1841,37 @@ impl RuntimeServiceImpl {
                 )
             })
         };
+        let sandbox_security = sandbox_config
+            .as_ref()
+            .and_then(|config| config.linux.as_ref())
+            .and_then(|linux| linux.security_context.as_ref());
+        let sandbox_privileged = sandbox_security
+            .map(|security| security.privileged)
+            .or_else(|| pod_state.as_ref().map(|state| state.privileged));
+        let sandbox_namespaces = sandbox_security
+            .and_then(|security| security.namespace_options.clone())
+            .or_else(|| {
+                pod_state
+                    .as_ref()
+                    .and_then(|state| state.namespace_options.as_ref())
+                    .map(StoredNamespaceOptions::to_proto)
+            });
+        Self::validate_container_spec(&config, sandbox_privileged, sandbox_namespaces.as_ref())?;
+        if self.config.rootless.is_some() {
+            let security = config
+                .linux
+                .as_ref()
+                .and_then(|linux| linux.security_context.as_ref());
+            Self::validate_rootless_security(
+                "container",
+                security.is_some_and(|security| security.privileged),
+                security.and_then(|security| security.namespace_options.as_ref()),
+            )?;
+        }
+
         let nri_activation_annotations = {
             let mut annotations = {
-                let pod_sandboxes = self.pod_sandboxes.lock().await;
+                let pod_sandboxes = self.pod_sandboxes.write().await;
                 pod_sandboxes
                     .get(&pod_sandbox_id)
                     .map(|pod| Self::external_annotations(&pod.annotations))
@@ -924,6 +1887,9 @@ impl RuntimeServiceImpl {
             }
             annotations
         };
+        let fs_group = Self::fs_group(&nri_activation_annotations)?;
+        Self::validate_exec_overrides(&config.annotations)?;
+        let shm_size = self.shm_size(&nri_activation_annotations)?;
 
         let sandbox_linux = sandbox_config
             .as_ref()
@@ -932,7 +1898,12 @@ impl RuntimeServiceImpl {
             .linux
             .as_ref()
             .and_then(|linux| linux.security_context.as_ref());
-        let namespace_options = security.and_then(|security| security.namespace_options.clone());
+        // 未声明命名空间选项的容器沿用 sandbox 的选项，两者都没有时按 CRI 默认值（POD）
+        // 加入 sandbox 的 network/IPC/PID 命名空间
+        let namespace_options = security
+            .and_then(|security| security.namespace_options.clone())
+            .or_else(|| sandbox_namespaces.clone())
+            .or_else(|| Some(NamespaceOption::default()));
 
         let pod_log_directory = sandbox_config
             .as_ref()
@@ -944,20 +1915,17 @@ impl RuntimeServiceImpl {
                     .as_ref()
                     .and_then(|state| state.log_directory.clone())
             });
-        let log_path = if config.log_path.is_empty() {
-            None
-        } else if let Some(log_directory) = pod_log_directory {
-            Some(PathBuf::from(log_directory).join(&config.log_path))
-        } else {
-            Some(PathBuf::from(&config.log_path))
-        };
-
+        let log_path =
+            Self::resolve_container_log_path(pod_log_directory.as_deref(), &config.log_path)?;
         if let Some(path) = &log_path {
             if let Some(parent) = path.parent() {
                 tokio::fs::create_dir_all(parent).await.map_err(|e| {
                     Status::internal(format!("Failed to prepare log directory: {}", e))
                 })?;
             }
+            if let Some(log_directory) = pod_log_directory.as_deref() {
+                Self::ensure_log_path_within(Path::new(log_directory), path)?;
+            }
         }
 
         let network_namespace_path = {
@@ -1014,6 +1982,26 @@ impl RuntimeServiceImpl {
         } else {
             None
         };
+        // 加入 Pod IPC 命名空间的容器共享 sandbox 级 /dev/shm，其余容器使用各自的 tmpfs
+        let joins_pod_ipc = ipc_namespace_path.is_some()
+            && namespace_options
+                .as_ref()
+                .is_some_and(|options| options.ipc == NamespaceMode::Pod as i32);
+        let sandbox_shm_path = if joins_pod_ipc {
+            {
+                let pod_manager = self.pod_manager.lock().await;
+                pod_manager
+                    .get_pod_sandbox_cloned(&pod_sandbox_id)
+                    .and_then(|pod| pod.shm_path)
+            }
+            .or_else(|| {
+                pod_state
+                    .as_ref()
+                    .and_then(|state| state.shm_path.as_ref().map(PathBuf::from))
+            })
+        } else {
+            None
+        };
 
         let apparmor_profile = Self::security_profile_name(
             security.and_then(|security| security.apparmor.as_ref()),
@@ -1080,6 +2068,12 @@ impl RuntimeServiceImpl {
                 })
             })
             .transpose()?;
+        if checkpoint_restore.is_some() && prepared_rootfs.is_some() {
+            return Err(Status::invalid_argument(format!(
+                "{} cannot be combined with a checkpoint restore",
+                ROOTFS_PATH_ANNOTATION_KEY
+            )));
+        }
         let container_image_ref = checkpoint_restore
             .as_ref()
             .map(|restore| restore.image_ref.clone())
@@ -1091,11 +2085,18 @@ impl RuntimeServiceImpl {
                     .filter(|image| !image.trim().is_empty())
             })
             .unwrap_or_default();
+        let container_labels = self.container_labels_with_image_defaults(
+            &container_id,
+            &container_image_ref,
+            &config.labels,
+        );
+        let stop_signal = self.container_stop_signal(&container_image_ref, &config.annotations)?;
+        let (log_max_size, log_max_files) = self.container_log_limits(&config.annotations)?;
 
         let run_as_group = security
             .and_then(|security| security.run_as_group.as_ref())
             .and_then(|group| u32::try_from(group.value).ok());
-        let supplemental_groups: Vec<u32> = security
+        let mut supplemental_groups: Vec<u32> = security
             .map(|security| {
                 security
                     .supplemental_groups
@@ -1104,6 +2105,12 @@ impl RuntimeServiceImpl {
                     .collect()
             })
             .unwrap_or_default();
+        if let Some(gid) = fs_group {
+            if !supplemental_groups.contains(&gid) {
+                supplemental_groups.push(gid);
+            }
+            self.apply_fs_group_to_mounts(&config.mounts, gid).await?;
+        }
 
         let linux_resources = config
             .linux
@@ -1128,20 +2135,41 @@ impl RuntimeServiceImpl {
             log_path: log_path.as_deref(),
             pod_state: pod_state.as_ref(),
             default_runtime: &self.config.runtime,
-        });
-
-        let mut runtime_mounts: Vec<MountConfig> = config
-            .mounts
-            .iter()
-            .map(|m| MountConfig {
-                source: PathBuf::from(&m.host_path),
+        })?;
+        if let Some(stop_signal) = stop_signal {
+            stored_annotations.insert(STOP_SIGNAL_ANNOTATION.to_string(), stop_signal);
+        }
+
+        let mount_manager = self.mount_manager();
+        let staged_mounts = mount_manager.stage(&container_id);
+        let mut runtime_mounts: Vec<MountConfig> = Vec::with_capacity(config.mounts.len());
+        for (index, m) in config.mounts.iter().enumerate() {
+            let source = match mount_subpaths.get(&m.container_path) {
+                Some(sub_path) => mount_manager
+                    .bind_subpath(
+                        &container_id,
+                        index,
+                        Path::new(&m.host_path),
+                        sub_path,
+                        m.readonly,
+                    )
+                    .map_err(|e| {
+                        Status::internal(format!(
+                            "Failed to prepare subPath mount {}: {:#}",
+                            m.container_path, e
+                        ))
+                    })?,
+                None => PathBuf::from(&m.host_path),
+            };
+            runtime_mounts.push(MountConfig {
+                source,
                 destination: PathBuf::from(&m.container_path),
                 read_only: m.readonly,
-            })
-            .collect();
+            });
+        }
         let pod_resolv_path = self
             .config
-            .root_dir
+            .state_dir
             .join("pods")
             .join(&pod_sandbox_id)
             .join("resolv.conf");
@@ -1157,16 +2185,23 @@ impl RuntimeServiceImpl {
             });
         }
 
-        let container_state = StoredContainerState {
-            cgroup_parent: sandbox_linux
-                .and_then(|linux| {
+        // 有 Pod 级 cgroup 时容器的 cgroup 嵌套在其下
+        let cgroup_parent = pod_state
+            .as_ref()
+            .and_then(|state| state.cgroup_path.as_ref())
+            .map(|cgroup_path| format!("{}/{}", cgroup_path, container_id))
+            .or_else(|| {
+                sandbox_linux.and_then(|linux| {
                     (!linux.cgroup_parent.is_empty()).then(|| linux.cgroup_parent.clone())
                 })
-                .or_else(|| {
-                    pod_state
-                        .as_ref()
-                        .and_then(|state| state.cgroup_parent.clone())
-                }),
+            })
+            .or_else(|| {
+                pod_state
+                    .as_ref()
+                    .and_then(|state| state.cgroup_parent.clone())
+            });
+        let container_state = StoredContainerState {
+            cgroup_parent: cgroup_parent.clone(),
             log_path: log_path.as_ref().map(|path| path.display().to_string()),
             tty: config.tty,
             stdin: config.stdin,
@@ -1205,8 +2240,20 @@ impl RuntimeServiceImpl {
             started_at: None,
             finished_at: None,
             exit_code: None,
+            exit_reason: None,
+            exit_message: None,
+            termination_message_path: config
+                .annotations
+                .get(KUBERNETES_TERMINATION_MESSAGE_PATH_ANNOTATION)
+                .filter(|path| !path.is_empty())
+                .cloned(),
             nri_stop_notified: false,
             nri_remove_notified: false,
+            paused: false,
+            log_max_size,
+            log_max_files,
+            create_timings: BTreeMap::new(),
+            start_timings: BTreeMap::new(),
             linux_resources,
             mounts: config
                 .mounts
@@ -1233,6 +2280,37 @@ impl RuntimeServiceImpl {
             )?;
         }
 
+        let hostname = match sandbox_config
+            .as_ref()
+            .map(|config| config.hostname.clone())
+            .filter(|hostname| !hostname.is_empty())
+        {
+            Some(hostname) => hostname,
+            None => {
+                let pod_manager = self.pod_manager.lock().await;
+                pod_manager
+                    .get_pod_sandbox_cloned(&pod_sandbox_id)
+                    .map(|pod| pod.config.hostname)
+                    .filter(|hostname| !hostname.is_empty())
+            }
+            .or_else(|| {
+                nix::unistd::gethostname()
+                    .ok()
+                    .and_then(|hostname| hostname.into_string().ok())
+            })
+            .unwrap_or_default(),
+        };
+        let mut container_env =
+            self.merge_container_env(&container_image_ref, &hostname, env_file_vars, &config.envs);
+        let mut container_command = config.command.clone();
+        let mut container_args = config.args.clone();
+        if self.config.expand_env_references {
+            Self::expand_container_env_references(
+                &mut container_env,
+                &mut container_command,
+                &mut container_args,
+            );
+        }
         let container_config = ContainerConfig {
             name: config
                 .metadata
@@ -1240,21 +2318,16 @@ impl RuntimeServiceImpl {
                 .map(|m| m.name.clone())
                 .unwrap_or_else(|| container_id.clone()),
             image: container_image_ref.clone(),
-            command: config.command.clone(),
-            args: config.args.clone(),
-            env: config
-                .envs
-                .iter()
-                .map(|e| (e.key.clone(), e.value.clone()))
-                .collect(),
+            command: container_command,
+            args: container_args,
+            env: container_env,
             working_dir: if config.working_dir.is_empty() {
                 None
             } else {
                 Some(PathBuf::from(&config.working_dir))
             },
             mounts: runtime_mounts,
-            labels: config
-                .labels
+            labels: container_labels
                 .iter()
                 .map(|(k, v)| (k.clone(), v.clone()))
                 .collect(),
@@ -1304,15 +2377,7 @@ impl RuntimeServiceImpl {
             selinux_label,
             seccomp_profile,
             capabilities: security.and_then(|security| security.capabilities.clone()),
-            cgroup_parent: sandbox_linux
-                .and_then(|linux| {
-                    (!linux.cgroup_parent.is_empty()).then(|| linux.cgroup_parent.clone())
-                })
-                .or_else(|| {
-                    pod_state
-                        .as_ref()
-                        .and_then(|state| state.cgroup_parent.clone())
-                }),
+            cgroup_parent: cgroup_parent.clone(),
             sysctls: HashMap::new(),
             namespace_options: namespace_options.clone(),
             namespace_paths: NamespacePaths {
@@ -1338,22 +2403,46 @@ impl RuntimeServiceImpl {
                     },
                 })
                 .collect(),
-            rootfs: self
-                .config
-                .root_dir
-                .join("containers")
-                .join(&container_id)
-                .join("rootfs"),
+            shm_size: Some(shm_size),
+            shm_path: sandbox_shm_path,
+            rootfs: prepared_rootfs.clone().unwrap_or_else(|| {
+                self.config
+                    .state_dir
+                    .join("containers")
+                    .join(&container_id)
+                    .join("rootfs")
+            }),
         };
-        let runtime = self.runtime.clone();
-        let requested_container_id = container_id.clone();
-        let container_config_clone = container_config.clone();
-        tokio::task::spawn_blocking(move || {
-            runtime.prepare_rootfs(&requested_container_id, &container_config_clone)
-        })
-        .await
-        .map_err(|e| Status::internal(format!("Failed to spawn blocking task: {}", e)))?
-        .map_err(|e| Status::internal(format!("Failed to prepare container rootfs: {}", e)))?;
+        // 预先准备的 rootfs 直接使用，不从镜像解压
+        timer.mark();
+        if prepared_rootfs.is_none() {
+            let runtime = self.runtime.clone();
+            let requested_container_id = container_id.clone();
+            let container_config_clone = container_config.clone();
+            let prepared = tokio::task::spawn_blocking(move || {
+                runtime.prepare_rootfs(&requested_container_id, &container_config_clone)
+            })
+            .await
+            .map_err(|e| Status::internal(format!("Failed to spawn blocking task: {}", e)))?;
+            if let Err(err) = prepared {
+                // 镜像缺失或解包失败时不留下半成品 rootfs，已挂载的 subPath 随 staged_mounts 卸载
+                let container_dir = self.config.state_dir.join("containers").join(&container_id);
+                if let Err(cleanup_err) = std::fs::remove_dir_all(&container_dir) {
+                    if cleanup_err.kind() != std::io::ErrorKind::NotFound {
+                        log::warn!(
+                            "Failed to remove {} after rootfs preparation failed: {}",
+                            container_dir.display(),
+                            cleanup_err
+                        );
+                    }
+                }
+                return Err(Status::internal(format!(
+                    "Failed to prepare container rootfs: {:#}",
+                    err
+                )));
+            }
+        }
+        timer.record(Phase::Snapshot);
 
         let runtime = self.runtime.clone();
         let requested_container_id = container_id.clone();
@@ -1370,7 +2459,7 @@ impl RuntimeServiceImpl {
             .await;
         nri_event.container.name = container_config.name.clone();
         nri_event.container.state = crate::nri_proto::api::ContainerState::CONTAINER_CREATED.into();
-        nri_event.container.labels = config.labels.clone();
+        nri_event.container.labels = container_labels.clone();
         nri_event.container.created_at = Self::now_nanos();
         nri_event.container.args = oci_args(&pristine_spec);
         nri_event.container.env = oci_env(&pristine_spec);
@@ -1448,6 +2537,15 @@ impl RuntimeServiceImpl {
         .map_err(|e| Status::internal(format!("NRI CreateContainer failed: {}", e)))?;
         Self::sanitize_spec_runtime_resources(&mut adjusted_spec);
         Self::apply_adjusted_annotations(&mut stored_annotations, &nri_create_result.adjustment);
+        if let Err(status) = Self::record_applied_linux_resources(
+            &mut stored_annotations,
+            &nri_create_result.adjustment,
+            &self.nri_config,
+        ) {
+            self.rollback_failed_container_create(&container_id, nri_event.clone())
+                .await;
+            return Err(status);
+        }
         Self::refresh_nri_event_container_from_spec(
             &mut nri_event,
             &adjusted_spec,
@@ -1470,17 +2568,27 @@ impl RuntimeServiceImpl {
                 .await;
             return Err(status);
         }
+        timer.record(Phase::Spec);
+        let create_timings = timer.finish();
+        if let Some(mut state) = Self::read_internal_state::<StoredContainerState>(
+            &stored_annotations,
+            INTERNAL_CONTAINER_STATE_KEY,
+        ) {
+            state.create_timings = create_timings;
+            Self::insert_internal_state(
+                &mut stored_annotations,
+                INTERNAL_CONTAINER_STATE_KEY,
+                &state,
+            )?;
+        }
 
         let created_id = container_id.clone();
         let container = Container {
             id: created_id.clone(),
             pod_sandbox_id: pod_sandbox_id.clone(),
             state: ContainerState::ContainerCreated as i32,
-            created_at: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_nanos() as i64,
-            labels: config.labels.clone(),
+            created_at: Self::now_nanos(),
+            labels: container_labels.clone(),
             metadata: config.metadata.clone(),
             annotations: stored_annotations.clone(),
             image: config.image.clone().or_else(|| {
@@ -1493,13 +2601,14 @@ impl RuntimeServiceImpl {
             image_ref: container_image_ref.clone(),
         };
 
-        let mut containers = self.containers.lock().await;
+        let mut containers = self.containers.write().await;
         containers.insert(created_id.clone(), container.clone());
         log::info!(
             "Container stored in memory, total containers: {}",
             containers.len()
         );
         drop(containers);
+        staged_mounts.keep();
 
         let mut persistence = self.persistence.lock().await;
         if let Err(e) = persistence.save_container(
@@ -1508,7 +2617,7 @@ impl RuntimeServiceImpl {
             crate::runtime::ContainerStatus::Created,
             &container_image_ref,
             &container_config.command,
-            &config.labels,
+            &container_labels,
             &stored_annotations,
         ) {
             log::error!("Failed to persist container {}: {}", created_id, e);
@@ -1540,7 +2649,7 @@ impl RuntimeServiceImpl {
 
         log::info!("Starting container {}", container_id);
 
-        let containers = self.containers.lock().await;
+        let containers = self.containers.read().await;
         log::info!(
             "Current containers in memory: {:?}",
             containers.keys().collect::<Vec<_>>()
@@ -1567,8 +2676,104 @@ impl RuntimeServiceImpl {
             }
         };
         drop(containers);
+
+        let guard = self.lock_container_lifecycle(&actual_container_id).await;
+        let result = self
+            .start_container_locked(&container_id, &actual_container_id)
+            .await;
+        self.release_container_lifecycle_lock(&actual_container_id, guard)
+            .await;
+        result
+    }
+
+    /// runc 未能启动容器：记为 EXITED 并保留错误信息，而不是停留在 CREATED
+    async fn record_container_start_failure(&self, container_id: &str, message: &str) {
+        {
+            let mut containers = self.containers.write().await;
+            let Some(container) = containers.get_mut(container_id) else {
+                return;
+            };
+            container.state = ContainerState::ContainerExited as i32;
+        }
+        let updated = self
+            .mutate_container_internal_state(container_id, |state| {
+                state.finished_at = Some(Self::now_nanos());
+                state.exit_code = Some(START_ERROR_EXIT_CODE);
+                state.exit_reason = Some(START_ERROR_REASON.to_string());
+                state.exit_message = Some(message.to_string());
+            })
+            .await;
+        match updated {
+            Ok(Some(container)) => {
+                if let Err(e) =
+                    self.persist_bundle_annotations(container_id, &container.annotations)
+                {
+                    log::warn!("Failed to persist start failure of {}: {}", container_id, e);
+                }
+                self.emit_container_event(
+                    ContainerEventType::ContainerStoppedEvent,
+                    &container,
+                    Some(container.state),
+                )
+                .await;
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!("Failed to record start failure of {}: {}", container_id, e),
+        }
+
+        let mut persistence = self.persistence.lock().await;
+        if let Err(e) = persistence.update_container_state(
+            container_id,
+            crate::runtime::ContainerStatus::Stopped(START_ERROR_EXIT_CODE),
+        ) {
+            log::error!(
+                "Failed to update container {} state in database: {}",
+                container_id,
+                e
+            );
+        }
+    }
+
+    /// 启动 CREATED 状态的容器；已在运行的容器直接返回成功，便于 kubelet 超时重试
+    ///
+    /// 调用方须持有该容器的生命周期锁。
+    async fn start_container_locked(
+        &self,
+        container_id: &str,
+        actual_container_id: &str,
+    ) -> Result<Response<StartContainerResponse>, Status> {
+        let actual_container_id = actual_container_id.to_string();
+        let state = self
+            .containers
+            .read()
+            .await
+            .get(&actual_container_id)
+            .map(|container| container.state)
+            .ok_or_else(|| Status::not_found("Container not found"))?;
+        if state == ContainerState::ContainerRunning as i32 {
+            let alive = self
+                .runtime_container_pid_checked(&actual_container_id)
+                .await
+                .is_some_and(|pid| {
+                    nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid), None).is_ok()
+                });
+            if alive {
+                log::info!(
+                    "Container {} is already running, skipping start",
+                    actual_container_id
+                );
+                return Ok(Response::new(StartContainerResponse {}));
+            }
+        }
+        if state != ContainerState::ContainerCreated as i32 {
+            return Err(Status::failed_precondition(format!(
+                "Container {} is not in created state",
+                actual_container_id
+            )));
+        }
+
         let (container_pod_id, container_annotations) = {
-            let containers = self.containers.lock().await;
+            let containers = self.containers.read().await;
             let container = containers
                 .get(&actual_container_id)
                 .ok_or_else(|| Status::not_found("Container not found"))?;
@@ -1590,7 +2795,7 @@ impl RuntimeServiceImpl {
             .map_err(|e| Status::internal(format!("NRI StartContainer failed: {}", e)))?;
 
         let checkpoint_restore = {
-            let containers = self.containers.lock().await;
+            let containers = self.containers.read().await;
             containers.get(&actual_container_id).and_then(|container| {
                 Self::read_internal_state::<StoredCheckpointRestore>(
                     &container.annotations,
@@ -1599,6 +2804,7 @@ impl RuntimeServiceImpl {
             })
         };
 
+        let mut timer = OperationTimer::start(Operation::StartContainer);
         let runtime = self.runtime.clone();
         let actual_container_id_clone = actual_container_id.clone();
         let checkpoint_restore_for_runtime = checkpoint_restore.clone();
@@ -1613,17 +2819,22 @@ impl RuntimeServiceImpl {
             }
         })
         .await
-        .map_err(|e| Status::internal(format!("Failed to spawn blocking task: {}", e)))
-        .and_then(|result| {
-            result.map_err(|e| Status::internal(format!("Failed to start container: {}", e)))
-        });
-        if let Err(status) = start_result {
+        .map_err(|e| format!("Failed to spawn blocking task: {}", e))
+        .and_then(|result| result.map_err(|e| format!("{:#}", e)));
+        if let Err(message) = start_result {
+            self.record_container_start_failure(&actual_container_id, &message)
+                .await;
             self.undo_failed_nri_start_container(nri_event.clone())
                 .await;
-            return Err(status);
+            return Err(Status::internal(format!(
+                "Failed to start container: {}",
+                message
+            )));
         }
+        timer.record(Phase::RuntimeStart);
 
         let mut observed_state = ContainerState::ContainerUnknown as i32;
+        let mut observed_exit_code = 0;
         let mut reached_known_state = false;
         for _ in 0..20 {
             let runtime = self.runtime.clone();
@@ -1637,6 +2848,11 @@ impl RuntimeServiceImpl {
 
             observed_state = Self::map_runtime_container_state(current_status.clone());
             match current_status {
+                ContainerStatus::Stopped(exit_code) => {
+                    observed_exit_code = exit_code;
+                    reached_known_state = true;
+                    break;
+                }
                 ContainerStatus::Unknown => {
                     tokio::time::sleep(std::time::Duration::from_millis(100)).await;
                 }
@@ -1655,25 +2871,37 @@ impl RuntimeServiceImpl {
             ));
         }
 
+        let start_timings = timer.finish();
         {
-            let mut containers = self.containers.lock().await;
+            let mut containers = self.containers.write().await;
             if let Some(container) = containers.get_mut(&actual_container_id) {
                 container.state = observed_state;
             }
         }
+        // 启动后立即退出的容器不会再注册 exit monitor，在这里记录 shim 上报的退出码
         let updated_container = self
-            .mutate_container_internal_state(&actual_container_id, |state| match observed_state {
-                x if x == ContainerState::ContainerRunning as i32 => {
-                    state.started_at = Some(Self::now_nanos());
-                    state.finished_at = None;
-                    state.exit_code = None;
-                    state.nri_stop_notified = false;
-                }
-                x if x == ContainerState::ContainerExited as i32 => {
-                    state.finished_at = Some(Self::now_nanos());
-                    state.exit_code.get_or_insert(0);
+            .mutate_container_internal_state(&actual_container_id, |state| {
+                state.start_timings = start_timings;
+                match observed_state {
+                    x if x == ContainerState::ContainerRunning as i32 => {
+                        state.started_at = Some(Self::now_nanos());
+                        state.finished_at = None;
+                        state.exit_code = None;
+                        state.exit_reason = None;
+                        state.exit_message = None;
+                        state.nri_stop_notified = false;
+                    }
+                    x if x == ContainerState::ContainerExited as i32 => {
+                        state.finished_at = Some(Self::now_nanos());
+                        Self::set_exit_code(
+                            &self.runtime,
+                            &actual_container_id,
+                            state,
+                            observed_exit_code,
+                        );
+                    }
+                    _ => {}
                 }
-                _ => {}
             })
             .await?;
         if let Some(container) = updated_container.as_ref() {
@@ -1691,7 +2919,7 @@ impl RuntimeServiceImpl {
                     crate::runtime::ContainerStatus::Created
                 }
                 x if x == ContainerState::ContainerExited as i32 => {
-                    crate::runtime::ContainerStatus::Stopped(0)
+                    crate::runtime::ContainerStatus::Stopped(observed_exit_code)
                 }
                 _ => crate::runtime::ContainerStatus::Unknown,
             },
@@ -1705,7 +2933,7 @@ impl RuntimeServiceImpl {
         drop(persistence);
         if checkpoint_restore.is_some() {
             let updated_annotations = {
-                let mut containers = self.containers.lock().await;
+                let mut containers = self.containers.write().await;
                 containers.get_mut(&actual_container_id).map(|container| {
                     container
                         .annotations
@@ -1727,7 +2955,7 @@ impl RuntimeServiceImpl {
             self.ensure_exit_monitor_registered(&actual_container_id);
         }
         if let Some(container) = {
-            let containers = self.containers.lock().await;
+            let containers = self.containers.read().await;
             containers.get(&actual_container_id).cloned()
         } {
             let post_start_event = self
@@ -0,0 +1,87 @@
+use super::*;
+use crate::utils::snapshot::{Snapshot, SnapshotCache};
+
+/// 容器表
+///
+/// 在容器 map 之外维护 sandbox → 容器 ID 的反向索引。索引与 map 处于同一把锁下，
+/// 只能通过 `insert`/`remove` 等方法修改，因此并发的创建、删除不会让两者不一致。
+/// 同样的理由，List 使用的快照在这些方法中作废。
+#[derive(Debug, Clone, Default)]
+pub(super) struct ContainerStore {
+    containers: HashMap<String, Container>,
+    by_sandbox: HashMap<String, HashSet<String>>,
+    snapshot: SnapshotCache<Container>,
+}
+
+impl std::ops::Deref for ContainerStore {
+    type Target = HashMap<String, Container>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.containers
+    }
+}
+
+impl ContainerStore {
+    /// 插入或替换容器，同步更新所属 sandbox 的索引
+    pub(super) fn insert(&mut self, id: String, container: Container) -> Option<Container> {
+        self.snapshot.invalidate();
+        let pod_sandbox_id = container.pod_sandbox_id.clone();
+        let previous = self.containers.insert(id.clone(), container);
+        if let Some(previous) = &previous {
+            self.unindex(&previous.pod_sandbox_id, &id);
+        }
+        self.by_sandbox
+            .entry(pod_sandbox_id)
+            .or_default()
+            .insert(id);
+        previous
+    }
+
+    /// 删除容器并从索引中移除
+    pub(super) fn remove(&mut self, id: &str) -> Option<Container> {
+        let removed = self.containers.remove(id)?;
+        self.snapshot.invalidate();
+        self.unindex(&removed.pod_sandbox_id, id);
+        Some(removed)
+    }
+
+    /// 获取可变引用；调用方不得修改 `pod_sandbox_id`
+    pub(super) fn get_mut(&mut self, id: &str) -> Option<&mut Container> {
+        self.snapshot.invalidate();
+        self.containers.get_mut(id)
+    }
+
+    /// 全部容器的共享快照，容器未变化时重复调用不会复制容器
+    pub(super) fn snapshot(&self) -> Snapshot<Container> {
+        self.snapshot.get_or_build(|| self.containers.values())
+    }
+
+    /// 某个 sandbox 下的容器 ID，O(k)
+    pub(super) fn ids_in_sandbox(&self, pod_sandbox_id: &str) -> Vec<String> {
+        self.by_sandbox
+            .get(pod_sandbox_id)
+            .map(|ids| ids.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// 某个 sandbox 下的容器，O(k)
+    pub(super) fn in_sandbox<'a>(
+        &'a self,
+        pod_sandbox_id: &str,
+    ) -> impl Iterator<Item = &'a Container> + 'a {
+        self.by_sandbox
+            .get(pod_sandbox_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.containers.get(id))
+    }
+
+    fn unindex(&mut self, pod_sandbox_id: &str, id: &str) {
+        if let Some(ids) = self.by_sandbox.get_mut(pod_sandbox_id) {
+            ids.remove(id);
+            if ids.is_empty() {
+                self.by_sandbox.remove(pod_sandbox_id);
+            }
+        }
+    }
+}
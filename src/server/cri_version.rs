@@ -0,0 +1,112 @@
+//! VersionRequest 中客户端声明的 CRI 版本
+//!
+//! kubelet 发送运行时 API 版本（语义化版本，目前为 `0.1.0`），crictl 等工具发送 CRI
+//! 包版本（`v1`）。能确定与本服务的 `runtime.v1` 不兼容的版本直接拒绝，无法识别的
+//! 版本照常应答，但在响应元数据和日志中标记为不受支持。
+
+use tonic::Status;
+
+/// 服务端实现的 CRI API 包版本
+pub const SUPPORTED_API_VERSION: &str = "v1";
+/// VersionResponse.version：kubelet 运行时 API 版本
+pub const KUBE_RUNTIME_API_VERSION: &str = "0.1.0";
+/// 响应元数据：请求中的版本
+pub const REQUESTED_VERSION_METADATA: &str = "crius-requested-version";
+/// 响应元数据：请求中的版本是否受支持
+pub const VERSION_SUPPORTED_METADATA: &str = "crius-requested-version-supported";
+
+/// 客户端声明的版本
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CriVersion {
+    /// 未声明版本
+    Unspecified,
+    /// kubelet 运行时 API 版本，如 `0.1.0`
+    KubeRuntime { major: u64, minor: u64, patch: u64 },
+    /// CRI API 包版本，如 `v1`、`v1alpha2`
+    Api(String),
+    /// 无法识别的版本字符串
+    Unknown(String),
+}
+
+/// 请求版本与服务端的兼容性
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compatibility {
+    Supported,
+    /// 无法判断，照常应答
+    Unknown,
+    Incompatible,
+}
+
+impl CriVersion {
+    pub fn parse(raw: &str) -> Self {
+        let raw = raw.trim();
+        if raw.is_empty() {
+            return Self::Unspecified;
+        }
+        let numbers: Vec<Option<u64>> = raw
+            .strip_prefix('v')
+            .unwrap_or(raw)
+            .split('.')
+            .map(|part| part.parse().ok())
+            .collect();
+        if let [Some(major), Some(minor), Some(patch)] = numbers[..] {
+            return Self::KubeRuntime {
+                major,
+                minor,
+                patch,
+            };
+        }
+        let is_api_version = raw.strip_prefix('v').is_some_and(|rest| {
+            let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+            let (major, suffix) = rest.split_at(digits);
+            !major.is_empty()
+                && (suffix.is_empty()
+                    || ["alpha", "beta"].iter().any(|stage| {
+                        suffix
+                            .strip_prefix(stage)
+                            .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+                    }))
+        });
+        if is_api_version {
+            Self::Api(raw.to_string())
+        } else {
+            Self::Unknown(raw.to_string())
+        }
+    }
+
+    pub fn compatibility(&self) -> Compatibility {
+        match self {
+            Self::Unspecified => Compatibility::Supported,
+            Self::KubeRuntime { major: 0, .. } => Compatibility::Supported,
+            Self::KubeRuntime { .. } => Compatibility::Incompatible,
+            Self::Api(version) if version == SUPPORTED_API_VERSION => Compatibility::Supported,
+            Self::Api(_) => Compatibility::Incompatible,
+            Self::Unknown(_) => Compatibility::Unknown,
+        }
+    }
+
+    /// 不兼容时返回 `FailedPrecondition`
+    pub fn negotiate(&self) -> Result<Compatibility, Status> {
+        match self.compatibility() {
+            Compatibility::Incompatible => Err(Status::failed_precondition(format!(
+                "CRI version {} is not supported, this runtime serves runtime.{} (runtime API {})",
+                self, SUPPORTED_API_VERSION, KUBE_RUNTIME_API_VERSION
+            ))),
+            compatibility => Ok(compatibility),
+        }
+    }
+}
+
+impl std::fmt::Display for CriVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unspecified => write!(f, "<unspecified>"),
+            Self::KubeRuntime {
+                major,
+                minor,
+                patch,
+            } => write!(f, "{}.{}.{}", major, minor, patch),
+            Self::Api(version) | Self::Unknown(version) => write!(f, "{}", version),
+        }
+    }
+}
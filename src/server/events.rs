@@ -10,20 +10,22 @@ impl RuntimeServiceImpl {
         nri_config: NriConfig,
         runtime: RuncRuntime,
         nri: Arc<dyn NriApi>,
-        containers: Arc<Mutex<HashMap<String, Container>>>,
-        pod_sandboxes: Arc<Mutex<HashMap<String, crate::proto::runtime::v1::PodSandbox>>>,
+        containers: Arc<RwLock<ContainerStore>>,
+        pod_sandboxes: Arc<RwLock<HashMap<String, crate::proto::runtime::v1::PodSandbox>>>,
         persistence: Arc<Mutex<PersistenceManager>>,
         events: tokio::sync::broadcast::Sender<ContainerEventResponse>,
         exit_monitors: Arc<Mutex<HashSet<String>>>,
+        tasks: Arc<TaskRegistry>,
     ) {
-        let Ok(handle) = tokio::runtime::Handle::try_current() else {
-            return;
-        };
-
-        handle.spawn(async move {
+        let owner = container_id.clone();
+        tasks.spawn(&owner, move |mut cancellation| async move {
             let exit_code = loop {
+                if cancellation.is_cancelled() {
+                    break None;
+                }
+
                 let current_state = {
-                    let containers = containers.lock().await;
+                    let containers = containers.read().await;
                     containers
                         .get(&container_id)
                         .map(|container| container.state)
@@ -59,7 +61,10 @@ impl RuntimeServiceImpl {
                     }
                 }
 
-                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                tokio::select! {
+                    _ = cancellation.cancelled() => break None,
+                    _ = tokio::time::sleep(std::time::Duration::from_millis(100)) => {}
+                }
             };
 
             if let Some(exit_code) = exit_code {
@@ -106,6 +111,7 @@ impl RuntimeServiceImpl {
         let persistence = self.persistence.clone();
         let events = self.events.clone();
         let exit_monitors = self.exit_monitors.clone();
+        let tasks = self.tasks.clone();
 
         let Ok(handle) = tokio::runtime::Handle::try_current() else {
             return;
@@ -134,6 +140,7 @@ impl RuntimeServiceImpl {
                 persistence,
                 events,
                 exit_monitors,
+                tasks,
             );
         });
     }
@@ -154,78 +161,83 @@ impl RuntimeServiceImpl {
         let events = self.events.clone();
         let exit_monitors = self.exit_monitors.clone();
 
-        let Ok(handle) = tokio::runtime::Handle::try_current() else {
-            return;
-        };
+        let owner = pod_id.clone();
+        self.tasks
+            .spawn(&owner, move |mut cancellation| async move {
+                let should_start = {
+                    let mut exit_monitors = exit_monitors.lock().await;
+                    exit_monitors.insert(monitor_key.clone())
+                };
 
-        handle.spawn(async move {
-            let should_start = {
-                let mut exit_monitors = exit_monitors.lock().await;
-                exit_monitors.insert(monitor_key.clone())
-            };
+                if !should_start {
+                    return;
+                }
 
-            if !should_start {
-                return;
-            }
+                let maybe_exit_code = loop {
+                    if cancellation.is_cancelled() {
+                        break None;
+                    }
 
-            let maybe_exit_code = loop {
-                let current_state = {
-                    let pod_sandboxes = pod_sandboxes.lock().await;
-                    pod_sandboxes.get(&pod_id).map(|pod| pod.state)
-                };
+                    let current_state = {
+                        let pod_sandboxes = pod_sandboxes.read().await;
+                        pod_sandboxes.get(&pod_id).map(|pod| pod.state)
+                    };
 
-                match current_state {
-                    Some(state) if state == PodSandboxState::SandboxReady as i32 => {}
-                    _ => break None,
-                }
+                    match current_state {
+                        Some(state) if state == PodSandboxState::SandboxReady as i32 => {}
+                        _ => break None,
+                    }
 
-                match tokio::fs::read_to_string(&exit_code_path).await {
-                    Ok(raw) => match raw.trim().parse::<i32>() {
-                        Ok(exit_code) => break Some(exit_code),
+                    match tokio::fs::read_to_string(&exit_code_path).await {
+                        Ok(raw) => match raw.trim().parse::<i32>() {
+                            Ok(exit_code) => break Some(exit_code),
+                            Err(err) => {
+                                log::warn!(
+                                    "Ignoring invalid pause exit code file {} for pod {}: {}",
+                                    exit_code_path.display(),
+                                    pod_id,
+                                    err
+                                );
+                            }
+                        },
+                        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
                         Err(err) => {
-                            log::warn!(
-                                "Ignoring invalid pause exit code file {} for pod {}: {}",
+                            log::debug!(
+                                "Pause exit monitor could not read {} for pod {}: {}",
                                 exit_code_path.display(),
                                 pod_id,
                                 err
                             );
                         }
-                    },
-                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
-                    Err(err) => {
-                        log::debug!(
-                            "Pause exit monitor could not read {} for pod {}: {}",
-                            exit_code_path.display(),
-                            pod_id,
-                            err
-                        );
                     }
-                }
 
-                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-            };
+                    tokio::select! {
+                        _ = cancellation.cancelled() => break None,
+                        _ = tokio::time::sleep(std::time::Duration::from_millis(100)) => {}
+                    }
+                };
 
-            if let Some(exit_code) = maybe_exit_code {
-                Self::record_pod_exit_from_monitor(
-                    &pod_id,
-                    exit_code,
-                    &config,
-                    &containers,
-                    &pod_sandboxes,
-                    &persistence,
-                    &events,
-                )
-                .await;
-            }
+                if let Some(exit_code) = maybe_exit_code {
+                    Self::record_pod_exit_from_monitor(
+                        &pod_id,
+                        exit_code,
+                        &config,
+                        &containers,
+                        &pod_sandboxes,
+                        &persistence,
+                        &events,
+                    )
+                    .await;
+                }
 
-            let mut exit_monitors = exit_monitors.lock().await;
-            exit_monitors.remove(&monitor_key);
-        });
+                let mut exit_monitors = exit_monitors.lock().await;
+                exit_monitors.remove(&monitor_key);
+            });
     }
 
     pub(super) async fn ensure_exit_monitors_for_active_containers(&self) {
         let active_container_ids: Vec<String> = {
-            let containers = self.containers.lock().await;
+            let containers = self.containers.read().await;
             containers
                 .values()
                 .filter(|container| {
@@ -241,7 +253,7 @@ impl RuntimeServiceImpl {
         }
 
         let active_pods: Vec<(String, String)> = {
-            let pod_sandboxes = self.pod_sandboxes.lock().await;
+            let pod_sandboxes = self.pod_sandboxes.read().await;
             pod_sandboxes
                 .values()
                 .filter(|pod| pod.state == PodSandboxState::SandboxReady as i32)
@@ -264,10 +276,10 @@ impl RuntimeServiceImpl {
 
     pub(super) async fn cleanup_orphaned_shim_artifacts(&self) {
         let known_container_ids: HashSet<String> =
-            self.containers.lock().await.keys().cloned().collect();
+            self.containers.read().await.keys().cloned().collect();
         let known_pause_ids: HashSet<String> = self
             .pod_sandboxes
-            .lock()
+            .read()
             .await
             .values()
             .filter_map(|pod| {
@@ -337,7 +349,7 @@ impl RuntimeServiceImpl {
         container_id: &str,
     ) -> Option<CriContainerStatus> {
         let container = {
-            let containers = self.containers.lock().await;
+            let containers = self.containers.read().await;
             containers.get(container_id).cloned()
         }?;
         let runtime_state = Self::map_runtime_container_state(
@@ -354,7 +366,7 @@ impl RuntimeServiceImpl {
         pod_id: &str,
     ) -> Option<PodSandboxStatus> {
         let pod = {
-            let pod_sandboxes = self.pod_sandboxes.lock().await;
+            let pod_sandboxes = self.pod_sandboxes.read().await;
             pod_sandboxes.get(pod_id).cloned()
         }?;
         Some(self.build_pod_sandbox_status_snapshot(&pod))
@@ -365,12 +377,8 @@ impl RuntimeServiceImpl {
         pod_id: &str,
     ) -> Vec<CriContainerStatus> {
         let containers: Vec<Container> = {
-            let containers = self.containers.lock().await;
-            containers
-                .values()
-                .filter(|container| container.pod_sandbox_id == pod_id)
-                .cloned()
-                .collect()
+            let containers = self.containers.read().await;
+            containers.in_sandbox(pod_id).cloned().collect()
         };
 
         let mut snapshots = Vec::with_capacity(containers.len());
@@ -452,14 +460,14 @@ impl RuntimeServiceImpl {
         nri_config: &NriConfig,
         runtime: &RuncRuntime,
         nri: &dyn NriApi,
-        containers: &Arc<Mutex<HashMap<String, Container>>>,
-        pod_sandboxes: &Arc<Mutex<HashMap<String, crate::proto::runtime::v1::PodSandbox>>>,
+        containers: &Arc<RwLock<ContainerStore>>,
+        pod_sandboxes: &Arc<RwLock<HashMap<String, crate::proto::runtime::v1::PodSandbox>>>,
         persistence: &Arc<Mutex<PersistenceManager>>,
         events: &tokio::sync::broadcast::Sender<ContainerEventResponse>,
     ) {
         let now = Self::now_nanos();
         let (updated_container, should_notify_nri_stop) = {
-            let mut containers = containers.lock().await;
+            let mut containers = containers.write().await;
             let Some(container) = containers.get_mut(container_id) else {
                 return;
             };
@@ -476,7 +484,7 @@ impl RuntimeServiceImpl {
             .unwrap_or_default();
             let should_notify_nri_stop = !state.nri_stop_notified;
             state.finished_at.get_or_insert(now);
-            state.exit_code = Some(exit_code);
+            Self::set_exit_code(runtime, container_id, &mut state, exit_code);
             let _ = Self::insert_internal_state(
                 &mut container.annotations,
                 INTERNAL_CONTAINER_STATE_KEY,
@@ -495,7 +503,7 @@ impl RuntimeServiceImpl {
 
         if should_notify_nri_stop {
             let pod = {
-                let pod_sandboxes = pod_sandboxes.lock().await;
+                let pod_sandboxes = pod_sandboxes.read().await;
                 pod_sandboxes
                     .get(&updated_container.pod_sandbox_id)
                     .map(|pod| Self::build_nri_pod_from_proto(runtime, pod))
@@ -540,7 +548,7 @@ impl RuntimeServiceImpl {
                         }
                     }
                     let updated_annotations = {
-                        let mut containers = containers.lock().await;
+                        let mut containers = containers.write().await;
                         containers.get_mut(container_id).and_then(|container| {
                             let mut state = Self::read_internal_state::<StoredContainerState>(
                                 &container.annotations,
@@ -599,7 +607,7 @@ impl RuntimeServiceImpl {
 
         let mut updated_pod = None;
         {
-            let mut pod_sandboxes = pod_sandboxes.lock().await;
+            let mut pod_sandboxes = pod_sandboxes.write().await;
             if let Some(pod) = pod_sandboxes.get_mut(&updated_container.pod_sandbox_id) {
                 let is_pause_container = Self::read_internal_state::<StoredPodState>(
                     &pod.annotations,
@@ -630,7 +638,7 @@ impl RuntimeServiceImpl {
         }
 
         let pod_status = {
-            let pod_sandboxes = pod_sandboxes.lock().await;
+            let pod_sandboxes = pod_sandboxes.read().await;
             pod_sandboxes
                 .get(&updated_container.pod_sandbox_id)
                 .cloned()
@@ -654,10 +662,9 @@ impl RuntimeServiceImpl {
 
         if let Some(updated_pod) = updated_pod {
             let container_snapshots = {
-                let containers = containers.lock().await;
+                let containers = containers.read().await;
                 containers
-                    .values()
-                    .filter(|container| container.pod_sandbox_id == updated_pod.id)
+                    .in_sandbox(&updated_pod.id)
                     .cloned()
                     .map(|container| {
                         Self::build_container_status_snapshot(&container, container.state)
@@ -725,14 +732,14 @@ impl RuntimeServiceImpl {
         pod_id: &str,
         _exit_code: i32,
         config: &RuntimeConfig,
-        containers: &Arc<Mutex<HashMap<String, Container>>>,
-        pod_sandboxes: &Arc<Mutex<HashMap<String, crate::proto::runtime::v1::PodSandbox>>>,
+        containers: &Arc<RwLock<ContainerStore>>,
+        pod_sandboxes: &Arc<RwLock<HashMap<String, crate::proto::runtime::v1::PodSandbox>>>,
         persistence: &Arc<Mutex<PersistenceManager>>,
         events: &tokio::sync::broadcast::Sender<ContainerEventResponse>,
     ) {
         let now = Self::now_nanos();
         let updated_pod = {
-            let mut pod_sandboxes = pod_sandboxes.lock().await;
+            let mut pod_sandboxes = pod_sandboxes.write().await;
             let Some(pod) = pod_sandboxes.get_mut(pod_id) else {
                 return;
             };
@@ -757,10 +764,9 @@ impl RuntimeServiceImpl {
         }
 
         let container_snapshots = {
-            let containers = containers.lock().await;
+            let containers = containers.read().await;
             containers
-                .values()
-                .filter(|container| container.pod_sandbox_id == pod_id)
+                .in_sandbox(pod_id)
                 .cloned()
                 .map(|container| Self::build_container_status_snapshot(&container, container.state))
                 .collect::<Vec<_>>()
@@ -781,17 +787,36 @@ impl RuntimeServiceImpl {
         );
     }
 
-    #[allow(dead_code)]
     pub(super) async fn refresh_runtime_state_and_publish_events(
         runtime: &RuncRuntime,
         config: &RuntimeConfig,
-        containers: &Arc<Mutex<HashMap<String, Container>>>,
-        pod_sandboxes: &Arc<Mutex<HashMap<String, crate::proto::runtime::v1::PodSandbox>>>,
+        containers: &Arc<RwLock<ContainerStore>>,
+        pod_sandboxes: &Arc<RwLock<HashMap<String, crate::proto::runtime::v1::PodSandbox>>>,
         persistence: &Arc<Mutex<PersistenceManager>>,
         events: &tokio::sync::broadcast::Sender<ContainerEventResponse>,
     ) {
+        let runtime_for_list = runtime.clone();
+        let runtime_known_ids = match tokio::task::spawn_blocking(move || {
+            runtime_for_list.list_container_ids()
+        })
+        .await
+        {
+            Ok(Ok(ids)) => Some(ids),
+            Ok(Err(err)) => {
+                log::debug!(
+                    "Background state refresh failed to list runtime containers: {}",
+                    err
+                );
+                None
+            }
+            Err(err) => {
+                log::debug!("Background state refresh list join error: {}", err);
+                None
+            }
+        };
+
         let container_ids: Vec<String> = {
-            let containers = containers.lock().await;
+            let containers = containers.read().await;
             containers.keys().cloned().collect()
         };
 
@@ -822,6 +847,15 @@ impl RuntimeServiceImpl {
                 }
             };
 
+            // runc 已不再记录该容器：视为在 exit monitor 之外消失，标记为已退出
+            let runtime_status = match (runtime_status, runtime_known_ids.as_ref()) {
+                (ContainerStatus::Unknown, Some(known_ids))
+                    if !known_ids.contains(&container_id) =>
+                {
+                    ContainerStatus::Stopped(VANISHED_CONTAINER_EXIT_CODE)
+                }
+                (status, _) => status,
+            };
             if matches!(runtime_status, ContainerStatus::Unknown) {
                 continue;
             }
@@ -830,7 +864,7 @@ impl RuntimeServiceImpl {
             let mut next_container = None;
             let mut emitted_event = None;
             {
-                let mut containers = containers.lock().await;
+                let mut containers = containers.write().await;
                 if let Some(container) = containers.get_mut(&container_id) {
                     if container.state == next_state {
                         continue;
@@ -848,7 +882,7 @@ impl RuntimeServiceImpl {
                             }
                             ContainerStatus::Stopped(code) => {
                                 state.finished_at.get_or_insert(Self::now_nanos());
-                                state.exit_code = Some(code);
+                                Self::set_exit_code(runtime, &container_id, &mut state, code);
                             }
                             ContainerStatus::Created | ContainerStatus::Unknown => {}
                         }
@@ -901,7 +935,7 @@ impl RuntimeServiceImpl {
 
             if let Some(event_type) = emitted_event {
                 let pod_status = {
-                    let pod_sandboxes = pod_sandboxes.lock().await;
+                    let pod_sandboxes = pod_sandboxes.read().await;
                     pod_sandboxes
                         .get(&container_after_update.pod_sandbox_id)
                         .cloned()
@@ -925,13 +959,13 @@ impl RuntimeServiceImpl {
         }
 
         let pod_ids: Vec<String> = {
-            let pods = pod_sandboxes.lock().await;
+            let pods = pod_sandboxes.read().await;
             pods.keys().cloned().collect()
         };
 
         for pod_id in pod_ids {
             let current_pod = {
-                let pods = pod_sandboxes.lock().await;
+                let pods = pod_sandboxes.read().await;
                 pods.get(&pod_id).cloned()
             };
             let Some(current_pod) = current_pod else {
@@ -966,7 +1000,7 @@ impl RuntimeServiceImpl {
             }
 
             let updated_pod = {
-                let mut pods = pod_sandboxes.lock().await;
+                let mut pods = pod_sandboxes.write().await;
                 if let Some(pod) = pods.get_mut(&pod_id) {
                     pod.state = next_pod_state;
                     Some(pod.clone())
@@ -996,12 +1030,8 @@ impl RuntimeServiceImpl {
 
             let container_snapshots = {
                 let containers_snapshot: Vec<Container> = {
-                    let containers = containers.lock().await;
-                    containers
-                        .values()
-                        .filter(|container| container.pod_sandbox_id == pod_id)
-                        .cloned()
-                        .collect()
+                    let containers = containers.read().await;
+                    containers.in_sandbox(&pod_id).cloned().collect()
                 };
                 let mut snapshots = Vec::with_capacity(containers_snapshot.len());
                 for container in containers_snapshot {
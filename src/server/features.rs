@@ -0,0 +1,156 @@
+//! 运行时 handler 特性探测
+//!
+//! 不同运行时和内核支持的特性不同（checkpoint/CRIU、用户命名空间、cgroup 控制器）。
+//! 启动时探测一次并缓存，通过 Status 的 verbose 信息报告，checkpoint 等请求据此拒绝不支持的操作。
+
+use super::*;
+use std::ffi::OsString;
+
+/// 单个运行时 handler 可用的特性
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct RuntimeHandlerFeatures {
+    /// 运行时二进制提供 `checkpoint` 子命令
+    pub(super) checkpoint: bool,
+    /// PATH 中存在可执行的 `criu`，checkpoint/restore 依赖它
+    pub(super) criu: bool,
+    /// 内核允许创建用户命名空间
+    pub(super) user_namespaces: bool,
+    /// 可用的 cgroup 控制器，已排序
+    pub(super) cgroup_controllers: Vec<String>,
+}
+
+impl RuntimeHandlerFeatures {
+    /// checkpoint/restore 需要运行时子命令和 CRIU 同时可用
+    pub(super) fn supports_checkpoint(&self) -> bool {
+        self.checkpoint && self.criu
+    }
+}
+
+/// 探测所需的宿主机输入，测试中可替换为临时目录
+#[derive(Debug, Clone)]
+pub(super) struct FeatureProbe {
+    pub(super) runtime_path: PathBuf,
+    pub(super) search_path: Option<OsString>,
+    pub(super) max_user_namespaces: PathBuf,
+    pub(super) cgroup_root: PathBuf,
+}
+
+impl FeatureProbe {
+    pub(super) fn host(runtime_path: &Path, cgroup_mount: &Path) -> Self {
+        Self {
+            runtime_path: runtime_path.to_path_buf(),
+            search_path: std::env::var_os("PATH"),
+            max_user_namespaces: PathBuf::from("/proc/sys/user/max_user_namespaces"),
+            cgroup_root: cgroup_mount.to_path_buf(),
+        }
+    }
+
+    pub(super) fn probe(&self) -> RuntimeHandlerFeatures {
+        RuntimeHandlerFeatures {
+            checkpoint: self.runtime_supports_checkpoint(),
+            criu: self.find_executable("criu").is_some(),
+            user_namespaces: std::fs::read_to_string(&self.max_user_namespaces)
+                .ok()
+                .and_then(|raw| raw.trim().parse::<u64>().ok())
+                .is_some_and(|max| max > 0),
+            // 只读探测：不指定受管层级，不会写 cgroup.subtree_control
+            cgroup_controllers: crate::cgroups::probe_controllers(&self.cgroup_root, None, &[])
+                .available,
+        }
+    }
+
+    fn runtime_supports_checkpoint(&self) -> bool {
+        Command::new(&self.runtime_path)
+            .args(["checkpoint", "--help"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+
+    fn find_executable(&self, name: &str) -> Option<PathBuf> {
+        use std::os::unix::fs::PermissionsExt;
+        let search_path = self.search_path.as_ref()?;
+        std::env::split_paths(search_path)
+            .map(|dir| dir.join(name))
+            .find(|path| {
+                std::fs::metadata(path)
+                    .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+            })
+    }
+}
+
+impl RuntimeServiceImpl {
+    /// 各 handler 的特性，首次调用时探测并缓存；所有 handler 共用同一个运行时二进制
+    pub(super) fn runtime_handler_features(&self) -> &HashMap<String, RuntimeHandlerFeatures> {
+        self.handler_features.get_or_init(|| {
+            let features =
+                FeatureProbe::host(&self.config.runtime_path, &self.config.cgroup_mount).probe();
+            self.config
+                .runtime_handlers
+                .iter()
+                .map(|handler| (handler.clone(), features.clone()))
+                .collect()
+        })
+    }
+
+    /// 启动时探测并记录各 handler 的特性
+    pub fn probe_runtime_handler_features(&self) {
+        for (handler, features) in self.runtime_handler_features() {
+            log::info!("Runtime handler {} features: {:?}", handler, features);
+        }
+    }
+
+    /// crius 可用的 cgroup 控制器，首次调用时探测（并在受管层级中启用）后缓存
+    pub(super) fn runtime_cgroup_controllers(&self) -> &crate::cgroups::CgroupControllers {
+        self.cgroup_controllers.get_or_init(|| {
+            crate::cgroups::probe_controllers(
+                &self.config.cgroup_mount,
+                self.config.cgroup_root.as_deref(),
+                &crate::cgroups::REQUIRED_CONTROLLERS,
+            )
+        })
+    }
+
+    /// 启动时探测 cgroup 控制器，缺少必需控制器时记录警告
+    pub fn probe_cgroup_controllers(&self) {
+        let controllers = self.runtime_cgroup_controllers();
+        if controllers.missing.is_empty() {
+            log::info!("Available cgroup controllers: {:?}", controllers.available);
+        } else {
+            log::warn!(
+                "Required cgroup controllers are not available: {} (available: {:?})",
+                controllers.missing.join(", "),
+                controllers.available
+            );
+        }
+    }
+
+    /// 容器所属 Pod 的 handler 不支持 CRIU 时拒绝 checkpoint
+    pub(super) async fn ensure_checkpoint_supported(
+        &self,
+        pod_sandbox_id: &str,
+    ) -> Result<(), Status> {
+        let handler = {
+            let pod_sandboxes = self.pod_sandboxes.read().await;
+            pod_sandboxes
+                .get(pod_sandbox_id)
+                .map(|pod| pod.runtime_handler.clone())
+                .filter(|handler| !handler.is_empty())
+        }
+        .unwrap_or_else(|| self.config.runtime.clone());
+        let supported = self
+            .runtime_handler_features()
+            .get(&handler)
+            .is_some_and(RuntimeHandlerFeatures::supports_checkpoint);
+        if supported {
+            return Ok(());
+        }
+        Err(Status::unimplemented(format!(
+            "runtime handler {} does not support checkpoint/restore (CRIU unavailable)",
+            handler
+        )))
+    }
+}
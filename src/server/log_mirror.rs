@@ -0,0 +1,223 @@
+//! 容器日志转发
+//!
+//! 容器输出由 shim 写入 CRI 格式的日志文件，crius 自身看不到。开启
+//! `[runtime.container_log].mirror` 后，后台循环跟随各容器的日志文件，把新写入的记录
+//! 按行转发到 crius 的日志（target `crius::container`），每个容器按行数限速。
+
+use super::*;
+use std::io::{Read, Seek, SeekFrom};
+use std::os::unix::fs::MetadataExt;
+use std::time::{Duration, Instant};
+use tracing::Level;
+
+/// 轮询日志文件的间隔
+const MIRROR_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// 限速窗口
+const MIRROR_RATE_WINDOW: Duration = Duration::from_secs(1);
+/// 单次轮询从一个文件读取的上限，避免积压的大文件长时间占用循环
+const MIRROR_MAX_READ_BYTES: u64 = 1 << 20;
+
+/// 单个容器日志文件的跟随位置与限速状态
+#[derive(Debug)]
+struct MirroredLog {
+    path: PathBuf,
+    inode: u64,
+    offset: u64,
+    /// 尚未读到换行的记录片段
+    pending: Vec<u8>,
+    window_start: Instant,
+    lines_in_window: u32,
+    suppressed: u64,
+}
+
+impl MirroredLog {
+    fn new(path: PathBuf, inode: u64, offset: u64) -> Self {
+        Self {
+            path,
+            inode,
+            offset,
+            pending: Vec::new(),
+            window_start: Instant::now(),
+            lines_in_window: 0,
+            suppressed: 0,
+        }
+    }
+}
+
+pub(super) struct ContainerLogMirror {
+    level: Level,
+    rate_limit: u32,
+    logs: HashMap<String, MirroredLog>,
+    /// 首次轮询时已存在的日志从末尾开始跟随，不回放历史输出
+    started: bool,
+}
+
+impl ContainerLogMirror {
+    pub(super) fn new(level: Level, rate_limit: u32) -> Self {
+        Self {
+            level,
+            rate_limit,
+            logs: HashMap::new(),
+            started: false,
+        }
+    }
+
+    /// 读取所有带日志路径的容器新写入的记录并转发
+    pub(super) async fn poll(&mut self, containers: &RwLock<ContainerStore>) {
+        let paths: Vec<(String, PathBuf)> = {
+            let containers = containers.read().await;
+            containers
+                .values()
+                .filter_map(|container| {
+                    RuntimeServiceImpl::read_internal_state::<StoredContainerState>(
+                        &container.annotations,
+                        INTERNAL_CONTAINER_STATE_KEY,
+                    )
+                    .and_then(|state| state.log_path)
+                    .filter(|path| !path.is_empty())
+                    .map(|path| (container.id.clone(), PathBuf::from(path)))
+                })
+                .collect()
+        };
+        self.logs
+            .retain(|id, _| paths.iter().any(|(container_id, _)| container_id == id));
+
+        let from_end = !self.started;
+        self.started = true;
+        for (container_id, path) in paths {
+            self.poll_container(&container_id, path, from_end);
+        }
+    }
+
+    fn poll_container(&mut self, container_id: &str, path: PathBuf, from_end: bool) {
+        let Ok(mut file) = std::fs::File::open(&path) else {
+            return;
+        };
+        let Ok(metadata) = file.metadata() else {
+            return;
+        };
+        let log = self
+            .logs
+            .entry(container_id.to_string())
+            .or_insert_with(|| {
+                let offset = if from_end { metadata.len() } else { 0 };
+                MirroredLog::new(path.clone(), metadata.ino(), offset)
+            });
+        // 轮转或截断后从新文件开头继续
+        if log.path != path || log.inode != metadata.ino() || metadata.len() < log.offset {
+            *log = MirroredLog::new(path, metadata.ino(), 0);
+        }
+        if metadata.len() == log.offset {
+            Self::flush_suppressed(self.level, container_id, log);
+            return;
+        }
+
+        let mut chunk = Vec::new();
+        let read = file.seek(SeekFrom::Start(log.offset)).and_then(|_| {
+            (&mut file)
+                .take(MIRROR_MAX_READ_BYTES)
+                .read_to_end(&mut chunk)
+        });
+        if let Err(err) = read {
+            log::debug!(
+                "Failed to read log {} of container {}: {}",
+                log.path.display(),
+                container_id,
+                err
+            );
+            return;
+        }
+        log.offset += chunk.len() as u64;
+        log.pending.extend_from_slice(&chunk);
+
+        let Some(end) = log.pending.iter().rposition(|byte| *byte == b'\n') else {
+            return;
+        };
+        let complete: Vec<u8> = log.pending.drain(..=end).collect();
+        for record in complete.split(|byte| *byte == b'\n') {
+            let Some((stream, content)) = Self::parse_cri_record(record) else {
+                continue;
+            };
+            Self::flush_suppressed(self.level, container_id, log);
+            if self.rate_limit > 0 && log.lines_in_window >= self.rate_limit {
+                log.suppressed += 1;
+                continue;
+            }
+            log.lines_in_window += 1;
+            Self::emit(
+                self.level,
+                container_id,
+                stream,
+                &String::from_utf8_lossy(content),
+            );
+        }
+    }
+
+    /// `<时间> <stream> <tag> <内容>`，返回 stream 与内容
+    fn parse_cri_record(record: &[u8]) -> Option<(&str, &[u8])> {
+        let mut parts = record.splitn(4, |byte| *byte == b' ');
+        let _timestamp = parts.next()?;
+        let stream = std::str::from_utf8(parts.next()?).ok()?;
+        let _tag = parts.next()?;
+        Some((stream, parts.next().unwrap_or_default()))
+    }
+
+    /// 限速窗口结束时汇总被丢弃的行数并开始新窗口
+    fn flush_suppressed(level: Level, container_id: &str, log: &mut MirroredLog) {
+        if log.window_start.elapsed() < MIRROR_RATE_WINDOW {
+            return;
+        }
+        if log.suppressed > 0 {
+            Self::emit(
+                level,
+                container_id,
+                "mirror",
+                &format!("suppressed {} log lines (rate limit)", log.suppressed),
+            );
+        }
+        log.window_start = Instant::now();
+        log.lines_in_window = 0;
+        log.suppressed = 0;
+    }
+
+    fn emit(level: Level, container_id: &str, stream: &str, line: &str) {
+        match level {
+            Level::ERROR => {
+                tracing::error!(target: "crius::container", "[{} {}] {}", container_id, stream, line)
+            }
+            Level::WARN => {
+                tracing::warn!(target: "crius::container", "[{} {}] {}", container_id, stream, line)
+            }
+            Level::INFO => {
+                tracing::info!(target: "crius::container", "[{} {}] {}", container_id, stream, line)
+            }
+            Level::DEBUG => {
+                tracing::debug!(target: "crius::container", "[{} {}] {}", container_id, stream, line)
+            }
+            Level::TRACE => {
+                tracing::trace!(target: "crius::container", "[{} {}] {}", container_id, stream, line)
+            }
+        }
+    }
+}
+
+impl RuntimeServiceImpl {
+    /// 启动容器日志转发循环；未开启 `mirror` 时不启动
+    pub fn start_container_log_mirror(&self) -> Option<tokio::task::JoinHandle<()>> {
+        let config = &self.config.container_log;
+        if !config.mirror {
+            return None;
+        }
+        let level = config.mirror_level().unwrap_or(Level::INFO);
+        let mut mirror = ContainerLogMirror::new(level, config.mirror_rate_limit);
+        let containers = self.containers.clone();
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(MIRROR_POLL_INTERVAL);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                mirror.poll(&containers).await;
+            }
+        }))
+    }
+}
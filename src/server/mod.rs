@@ -3,7 +3,7 @@ use async_trait::async_trait;
 use log;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io::Write;
 use std::net::IpAddr;
 use std::path::{Path, PathBuf};
@@ -12,10 +12,11 @@ use std::sync::Arc;
 use tempfile::NamedTempFile;
 use tokio::io::AsyncReadExt;
 use tokio::process::Command as TokioCommand;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, RwLock};
 use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Status};
 
+use crate::audit::AuditEntry;
 use crate::proto::runtime::v1::{
     runtime_service_server::RuntimeService, Container, ContainerState,
     ContainerStatus as CriContainerStatus, ExecRequest, ExecResponse, ExecSyncRequest,
@@ -44,9 +45,13 @@ use crate::proto::runtime::v1::{
     UpdateRuntimeConfigRequest, UpdateRuntimeConfigResponse,
 };
 use crate::storage::persistence::{PersistenceConfig, PersistenceManager};
+use crate::storage::{resolve_subpath, MountManager};
 
-use crate::config::{NriAnnotationWorkloadConfig, NriConfig};
-use crate::metrics::MetricsCollector;
+use crate::config::{
+    ContainerLogConfig, DefaultDnsConfig, MountPolicyConfig, NriAnnotationWorkloadConfig,
+    NriConfig, ProcessDefaultsConfig, ProtectedPathsConfig, StatsConfig,
+};
+use crate::metrics::{MetricsCollector, Operation, OperationTimer, Phase};
 use crate::network::{CniConfig, DefaultNetworkManager, NetworkManager};
 use crate::nri::{
     apply_container_adjustment_with_blockio_config, disallowed_annotation_adjustment_keys,
@@ -58,30 +63,69 @@ use crate::nri::{
     NriManagerConfig, NriPodEvent, NriStopContainerResult, RuntimeSnapshot,
 };
 use crate::pod::{PodSandboxConfig, PodSandboxManager};
+use crate::runtime::shim_manager::START_ERROR_EXIT_CODE;
 use crate::runtime::{
-    default_shim_work_dir, ContainerConfig, ContainerRuntime, ContainerStatus, DeviceMapping,
-    MountConfig, NamespacePaths, RuncRuntime, SeccompProfile, ShimConfig, ShimProcess,
+    default_shim_work_dir, parse_stop_signal, ContainerConfig, ContainerRuntime, ContainerStatus,
+    DeviceMapping, KillTimeoutError, MountConfig, NamespacePaths, RuncRuntime, SeccompProfile,
+    ShimConfig, ShimProcess, STOP_SIGNAL_ANNOTATION,
 };
-use crate::streaming::StreamingServer;
+use crate::streaming::{ExecOptions, StreamingServer};
 
+pub mod admin;
 mod annotations;
 mod container_handlers;
+mod container_store;
+mod cri_version;
 mod events;
+mod features;
+mod log_mirror;
+mod panic_boundary;
 mod pod_handlers;
+mod read_only;
 mod recovery;
 mod responses;
 mod service;
+mod startup;
 mod stats;
 mod status;
 mod streaming_handlers;
+mod tasks;
+mod transport;
+mod validation;
 
+use container_store::ContainerStore;
+pub use panic_boundary::{CatchPanic, CatchPanicLayer};
+pub use read_only::{ReadOnly, ReadOnlyLayer, READ_ONLY_METHODS};
 pub use service::{RuntimeConfig, RuntimeServiceImpl};
+pub use stats::NodeSummary;
+use tasks::{TaskRegistry, TASK_SHUTDOWN_TIMEOUT};
+pub use transport::{authorize_peers, limit_connections, server_builder, LimitedConnection};
 
 const INTERNAL_ANNOTATION_PREFIX: &str = "io.crius.internal/";
 const INTERNAL_POD_STATE_KEY: &str = "io.crius.internal/pod-state";
 const INTERNAL_CONTAINER_STATE_KEY: &str = "io.crius.internal/container-state";
 const INTERNAL_CHECKPOINT_RESTORE_KEY: &str = "io.crius.internal/checkpoint-restore";
+const INTERNAL_SHADOWED_ANNOTATIONS_KEY: &str = "io.crius.internal/shadowed-annotations";
+/// 未记录 metadata 时上报的 attempt，List/Status 与重启恢复共用，避免同一对象前后不一致
+const DEFAULT_METADATA_ATTEMPT: u32 = 1;
 const CHECKPOINT_LOCATION_ANNOTATION_KEY: &str = "io.crius.checkpoint.location";
+/// 容器 subPath 挂载声明，JSON 对象：容器内路径 -> 卷内相对路径
+const MOUNT_SUBPATHS_ANNOTATION_KEY: &str = "io.crius.mount.subpaths";
+/// 指向宿主机 env 文件的注解，文件内容按 KEY=VALUE 合并进容器环境变量
+const ENV_FILE_ANNOTATION_KEY: &str = "io.crius.env-file";
+/// 直接使用宿主机上预先解压好的目录作为容器 rootfs，不从镜像准备
+const ROOTFS_PATH_ANNOTATION_KEY: &str = "io.crius.rootfs-path";
+/// Pod 的 fsGroup：可写 Pod 卷的属组会被递归设置为该 gid，并加入容器附加组
+const FS_GROUP_ANNOTATION_KEY: &str = "io.crius.fsgroup";
+/// exec/exec_sync 使用的用户，格式 `user[:group]`，名字按容器 rootfs 内的 passwd/group 解析
+const EXEC_USER_ANNOTATION_KEY: &str = "io.crius.exec-user";
+/// exec/exec_sync 的容器内工作目录，必须是绝对路径
+const EXEC_CWD_ANNOTATION_KEY: &str = "io.crius.exec-cwd";
+/// exec/exec_sync 追加的环境变量，JSON 对象：变量名 -> 值
+const EXEC_ENV_ANNOTATION_KEY: &str = "io.crius.exec-env";
+/// 单容器日志轮转覆盖，取值同 `[runtime.container_log]`
+const CONTAINER_LOG_MAX_SIZE_ANNOTATION: &str = "io.kubernetes.cri.container-log-max-size";
+const CONTAINER_LOG_MAX_FILES_ANNOTATION: &str = "io.kubernetes.cri.container-log-max-files";
 const CRIO_LABELS_ANNOTATION: &str = "io.kubernetes.cri-o.Labels";
 const CRIO_CONTAINER_ID_ANNOTATION: &str = "io.kubernetes.cri-o.ContainerID";
 const CRIO_CONTAINER_NAME_ANNOTATION: &str = "io.kubernetes.cri-o.ContainerName";
@@ -90,6 +134,7 @@ const CRIO_USER_REQUESTED_IMAGE_ANNOTATION: &str = "io.kubernetes.cri-o.Image";
 const CRIO_IMAGE_NAME_ANNOTATION: &str = "io.kubernetes.cri-o.ImageName";
 const CRIO_LOG_PATH_ANNOTATION: &str = "io.kubernetes.cri-o.LogPath";
 const CRIO_RUNTIME_HANDLER_ANNOTATION: &str = "io.kubernetes.cri-o.RuntimeHandler";
+const CRIO_SHM_SIZE_ANNOTATION: &str = "io.kubernetes.cri-o.ShmSize";
 const CRIO_SANDBOX_ID_ANNOTATION: &str = "io.kubernetes.cri-o.SandboxID";
 const CRIO_SANDBOX_NAME_ANNOTATION: &str = "io.kubernetes.cri-o.SandboxName";
 const CRIO_POD_NAME_ANNOTATION: &str = "io.kubernetes.cri-o.Name";
@@ -103,9 +148,28 @@ const CONTAINERD_SANDBOX_UID_ANNOTATION: &str = "io.kubernetes.cri.sandbox-uid";
 const CONTAINERD_CONTAINER_NAME_ANNOTATION: &str = "io.kubernetes.cri.container-name";
 const CONTAINERD_RUNTIME_HANDLER_ANNOTATION: &str = "io.containerd.cri.runtime-handler";
 const KUBERNETES_CONTAINER_NAME_ANNOTATION: &str = "io.kubernetes.container.name";
+/// kubelet 记录的容器内终止消息文件路径，容器退出时读取其内容作为状态消息
+const KUBERNETES_TERMINATION_MESSAGE_PATH_ANNOTATION: &str =
+    "io.kubernetes.container.terminationMessagePath";
+/// 终止消息最多保留的字节数，与 kubelet 的单容器上限一致
+const TERMINATION_MESSAGE_MAX_BYTES: u64 = 4096;
+/// 容器未能启动时 ContainerStatus 中的 reason，与 containerd 一致
+const START_ERROR_REASON: &str = "StartError";
+/// 经管理接口暂停的运行中容器在 ContainerStatus 中的 reason
+const PAUSED_REASON: &str = "Paused";
+/// SIGKILL 后仍未退出、被 RemoveContainer 强制清理的容器的 reason
+const FORCE_REMOVED_REASON: &str = "ForceRemoved";
+/// 强制清理的容器记录的退出码（128 + SIGKILL）
+const FORCE_REMOVED_EXIT_CODE: i32 = 137;
 const CONTAINER_TYPE_CONTAINER: &str = "container";
 const NRI_ALLOWED_ANNOTATION_PREFIXES_ENV: &str = "CRIUS_NRI_ALLOWED_ANNOTATION_PREFIXES";
 const NRI_MIN_MEMORY_LIMIT_ENV: &str = "CRIUS_NRI_CONTAINER_MIN_MEMORY_BYTES";
+/// 容器在 runc 中消失时记录的退出码
+const VANISHED_CONTAINER_EXIT_CODE: i32 = 255;
+/// 容器事件广播的缓冲区；发布不阻塞，订阅者落后超过该数量时丢弃其最旧的事件
+const CONTAINER_EVENTS_BUFFER: usize = 256;
+/// 每个 GetContainerEvents 订阅转发到 gRPC 流的缓冲区
+const CONTAINER_EVENTS_STREAM_BUFFER: usize = 128;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(default)]
@@ -215,11 +279,27 @@ impl StoredLinuxResources {
                     limit: limit.limit,
                 })
                 .collect(),
-            unified: self.unified.clone(),
+            unified: self.unified_with_pids_limit(),
             memory_swap_limit_in_bytes: self.memory_swap_limit_in_bytes,
         }
     }
 
+    fn unified_with_pids_limit(&self) -> HashMap<String, String> {
+        let mut unified = self.unified.clone();
+        if let Some(limit) = self.pids_limit {
+            unified
+                .entry(crate::cgroups::PIDS_MAX_UNIFIED_KEY.to_string())
+                .or_insert_with(|| {
+                    if limit < 0 {
+                        "max".to_string()
+                    } else {
+                        limit.to_string()
+                    }
+                });
+        }
+        unified
+    }
+
     fn optional_int64(value: i64) -> protobuf::MessageField<crate::nri_proto::api::OptionalInt64> {
         let mut result = crate::nri_proto::api::OptionalInt64::new();
         result.value = value;
@@ -459,7 +539,9 @@ impl From<&crate::proto::runtime::v1::LinuxContainerResources> for StoredLinuxRe
             memory_use_hierarchy: None,
             cpu_realtime_runtime: None,
             cpu_realtime_period: None,
-            pids_limit: None,
+            pids_limit: crate::cgroups::pids_limit_from_unified(&value.unified)
+                .ok()
+                .flatten(),
             devices: Vec::new(),
             blockio_class: None,
             rdt_class: None,
@@ -545,6 +627,7 @@ impl From<&crate::proto::runtime::v1::SecurityProfile> for StoredSecurityProfile
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(default)]
 struct StoredPodState {
+    metadata_attempt: Option<u32>,
     log_directory: Option<String>,
     runtime_handler: String,
     runtime_pod_cidr: Option<String>,
@@ -552,7 +635,11 @@ struct StoredPodState {
     pause_container_id: Option<String>,
     ip: Option<String>,
     additional_ips: Vec<String>,
+    network_config: Option<serde_json::Value>,
+    cni_result: Option<serde_json::Value>,
     cgroup_parent: Option<String>,
+    /// Pod 级 cgroup 路径，成员容器的 cgroup 嵌套其下
+    cgroup_path: Option<String>,
     sysctls: HashMap<String, String>,
     namespace_options: Option<StoredNamespaceOptions>,
     privileged: bool,
@@ -566,6 +653,12 @@ struct StoredPodState {
     seccomp_profile: Option<StoredSecurityProfile>,
     overhead_linux_resources: Option<StoredLinuxResources>,
     linux_resources: Option<StoredLinuxResources>,
+    /// RunPodSandbox 各阶段耗时（秒）
+    creation_timings: BTreeMap<String, f64>,
+    /// 成员容器共享的 sandbox 级 `/dev/shm` 路径
+    shm_path: Option<String>,
+    /// rootless Pod 的 slirp4netns/pasta 进程
+    network_helper: Option<crate::rootless::NetworkHelper>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -592,8 +685,22 @@ struct StoredContainerState {
     started_at: Option<i64>,
     finished_at: Option<i64>,
     exit_code: Option<i32>,
+    /// 退出原因，设置后优先于按退出码推断的 reason
+    exit_reason: Option<String>,
+    exit_message: Option<String>,
+    /// 容器内的终止消息文件路径
+    termination_message_path: Option<String>,
     nri_stop_notified: bool,
     nri_remove_notified: bool,
+    /// 日志文件大小上限，0 表示不轮转
+    log_max_size: u64,
+    log_max_files: u32,
+    /// 经管理接口暂停（cgroup 冻结）
+    paused: bool,
+    /// 最近一次 CreateContainer 各阶段耗时（秒）
+    create_timings: BTreeMap<String, f64>,
+    /// 最近一次 StartContainer 各阶段耗时（秒）
+    start_timings: BTreeMap<String, f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -620,10 +727,33 @@ struct StoredMount {
     propagation: i32,
 }
 
+/// 停止容器失败的原因：单独区分 SIGKILL 后进程仍未退出，删除容器时只有这种情况才强制清理
+enum StopError {
+    KillTimeout(KillTimeoutError),
+    Status(Status),
+}
+
+impl From<Status> for StopError {
+    fn from(status: Status) -> Self {
+        StopError::Status(status)
+    }
+}
+
+impl From<StopError> for Status {
+    fn from(err: StopError) -> Self {
+        match err {
+            StopError::KillTimeout(err) => {
+                Status::internal(format!("Failed to stop container: {}", err))
+            }
+            StopError::Status(status) => status,
+        }
+    }
+}
+
 #[derive(Clone)]
 struct NriRuntimeDomain {
-    containers: Arc<Mutex<HashMap<String, Container>>>,
-    pod_sandboxes: Arc<Mutex<HashMap<String, crate::proto::runtime::v1::PodSandbox>>>,
+    containers: Arc<RwLock<ContainerStore>>,
+    pod_sandboxes: Arc<RwLock<HashMap<String, crate::proto::runtime::v1::PodSandbox>>>,
     config: RuntimeConfig,
     nri_config: NriConfig,
     runtime: RuncRuntime,
@@ -903,6 +1033,47 @@ impl RuntimeServiceImpl {
             .filter(|value| *value > 0)
     }
 
+    /// 校验 CRI 下发的容器资源，非法取值返回 InvalidArgument
+    fn validate_linux_resources(
+        &self,
+        resources: &crate::proto::runtime::v1::LinuxContainerResources,
+    ) -> Result<(), Status> {
+        crate::config::validate_oom_score_adj(resources.oom_score_adj)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        crate::cgroups::pids_limit_from_unified(&resources.unified)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        crate::cgroups::validate_memory_swap(
+            resources.memory_limit_in_bytes,
+            resources.memory_swap_limit_in_bytes,
+        )
+        .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        // 格式与编号上限不依赖宿主机，读不到在线列表时同样拒绝
+        if !resources.cpuset_cpus.is_empty() {
+            crate::cgroups::parse_cpuset_list(&resources.cpuset_cpus)
+                .map_err(|e| Status::invalid_argument(e.to_string()))?;
+            if let Some(available) = crate::cgroups::host_online_cpus(&self.config.sysfs_root) {
+                crate::cgroups::validate_cpuset("cpuset_cpus", &resources.cpuset_cpus, &available)
+                    .map_err(|e| Status::invalid_argument(e.to_string()))?;
+            }
+        }
+        if !resources.cpuset_mems.is_empty() {
+            crate::cgroups::parse_cpuset_list(&resources.cpuset_mems)
+                .map_err(|e| Status::invalid_argument(e.to_string()))?;
+            if let Some(available) = crate::cgroups::host_online_mems(&self.config.sysfs_root) {
+                crate::cgroups::validate_cpuset("cpuset_mems", &resources.cpuset_mems, &available)
+                    .map_err(|e| Status::invalid_argument(e.to_string()))?;
+            }
+        }
+        if !resources.hugepage_limits.is_empty() {
+            let supported = crate::cgroups::host_hugepage_sizes();
+            for limit in &resources.hugepage_limits {
+                crate::cgroups::validate_hugepage_size(&limit.page_size, &supported)
+                    .map_err(|e| Status::invalid_argument(e.to_string()))?;
+            }
+        }
+        Ok(())
+    }
+
     fn validate_nri_adjustment_runtime_resources(
         adjustment: &crate::nri_proto::api::ContainerAdjustment,
     ) -> Result<(), Status> {
@@ -982,10 +1153,7 @@ impl RuntimeServiceImpl {
     }
 
     fn now_nanos() -> i64 {
-        std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_nanos() as i64
+        crate::utils::now_nanos()
     }
 
     fn nri_container_state(state: i32) -> crate::nri_proto::api::ContainerState {
@@ -1170,8 +1338,11 @@ impl RuntimeServiceImpl {
             .as_ref()
             .and_then(|state| state.exit_code)
             .unwrap_or_default();
-        let (reason, message) =
-            Self::container_reason_message(container.state, nri_container.exit_code);
+        let (reason, message) = Self::container_status_reason(
+            container.state,
+            nri_container.exit_code,
+            stored_state.as_ref(),
+        );
         nri_container.status_reason = reason;
         nri_container.status_message = message;
         nri_container.pid = runtime
@@ -1316,7 +1487,7 @@ impl RuntimeServiceImpl {
         F: FnOnce(&mut StoredContainerState),
     {
         let updated = {
-            let mut containers = self.containers.lock().await;
+            let mut containers = self.containers.write().await;
             let Some(container) = containers.get_mut(container_id) else {
                 return Ok(None);
             };
@@ -1343,7 +1514,7 @@ impl RuntimeServiceImpl {
     }
 
     async fn container_internal_state(&self, container_id: &str) -> Option<StoredContainerState> {
-        let containers = self.containers.lock().await;
+        let containers = self.containers.read().await;
         containers.get(container_id).and_then(|container| {
             Self::read_internal_state::<StoredContainerState>(
                 &container.annotations,
@@ -1501,7 +1672,7 @@ impl RuntimeServiceImpl {
         container_id: &str,
         resources: &StoredLinuxResources,
     ) -> Result<(), Status> {
-        let mut resource_file = NamedTempFile::new_in(&self.config.root_dir)
+        let mut resource_file = NamedTempFile::new_in(&self.config.state_dir)
             .or_else(|_| NamedTempFile::new())
             .map_err(|e| {
                 Status::internal(format!("Failed to create temporary resource file: {}", e))
@@ -2036,6 +2207,57 @@ impl RuntimeServiceImpl {
         }
     }
 
+    /// 记录退出码；shim 记录了启动错误时同时保存为退出原因，否则读取容器的终止消息
+    fn set_exit_code(
+        runtime: &RuncRuntime,
+        container_id: &str,
+        state: &mut StoredContainerState,
+        exit_code: i32,
+    ) {
+        state.exit_code = Some(exit_code);
+        state.paused = false;
+        if let Some(message) = runtime.container_start_error(container_id) {
+            state.exit_reason = Some(START_ERROR_REASON.to_string());
+            state.exit_message = Some(message);
+        } else if let (None, Some(path)) = (
+            state.exit_reason.as_ref(),
+            state.termination_message_path.as_deref(),
+        ) {
+            state.exit_message =
+                runtime.termination_message(container_id, path, TERMINATION_MESSAGE_MAX_BYTES);
+        }
+    }
+
+    /// 已退出容器记录的退出原因（如启动失败）优先于按退出码推断的原因，终止消息替代默认的 message
+    fn container_status_reason(
+        runtime_state: i32,
+        exit_code: i32,
+        stored_state: Option<&StoredContainerState>,
+    ) -> (String, String) {
+        if runtime_state == ContainerState::ContainerExited as i32 {
+            if let Some(state) = stored_state {
+                if let Some(reason) = state.exit_reason.clone() {
+                    return (reason, state.exit_message.clone().unwrap_or_default());
+                }
+                if let Some(message) = state.exit_message.clone() {
+                    return (
+                        Self::container_reason_message(runtime_state, exit_code).0,
+                        message,
+                    );
+                }
+            }
+        }
+        if runtime_state == ContainerState::ContainerRunning as i32
+            && stored_state.is_some_and(|state| state.paused)
+        {
+            return (
+                PAUSED_REASON.to_string(),
+                "container processes are frozen".to_string(),
+            );
+        }
+        Self::container_reason_message(runtime_state, exit_code)
+    }
+
     fn map_runtime_container_state(status: crate::runtime::ContainerStatus) -> i32 {
         match status {
             ContainerStatus::Created => ContainerState::ContainerCreated as i32,
@@ -2151,7 +2373,7 @@ impl RuntimeServiceImpl {
 
         let rootfs_path = self
             .config
-            .root_dir
+            .state_dir
             .join("containers")
             .join(container_id)
             .join("rootfs");
@@ -2254,7 +2476,7 @@ impl RuntimeServiceImpl {
     }
 
     async fn resolve_pod_sandbox_id(&self, requested_id: &str) -> Result<String, Status> {
-        let pod_sandboxes = self.pod_sandboxes.lock().await;
+        let pod_sandboxes = self.pod_sandboxes.read().await;
         if pod_sandboxes.contains_key(requested_id) {
             return Ok(requested_id.to_string());
         }
@@ -2275,8 +2497,23 @@ impl RuntimeServiceImpl {
         }
     }
 
+    /// 只允许在 SANDBOX_READY 的 sandbox 中创建容器，已停止的 sandbox 返回 `FailedPrecondition`
+    async fn ensure_pod_sandbox_ready(&self, pod_sandbox_id: &str) -> Result<(), Status> {
+        let pod_sandboxes = self.pod_sandboxes.read().await;
+        let pod = pod_sandboxes
+            .get(pod_sandbox_id)
+            .ok_or_else(|| Status::not_found("Pod sandbox not found"))?;
+        if pod.state != PodSandboxState::SandboxReady as i32 {
+            return Err(Status::failed_precondition(format!(
+                "pod sandbox {} is not ready",
+                pod_sandbox_id
+            )));
+        }
+        Ok(())
+    }
+
     async fn resolve_container_id(&self, requested_id: &str) -> Result<String, Status> {
-        let containers = self.containers.lock().await;
+        let containers = self.containers.read().await;
         if containers.contains_key(requested_id) {
             return Ok(requested_id.to_string());
         }
@@ -2395,7 +2632,7 @@ impl RuntimeServiceImpl {
 
 impl NriRuntimeDomain {
     async fn resolve_container_id(&self, requested_id: &str) -> crate::nri::Result<Option<String>> {
-        let containers = self.containers.lock().await;
+        let containers = self.containers.read().await;
         if containers.contains_key(requested_id) {
             return Ok(Some(requested_id.to_string()));
         }
@@ -2416,12 +2653,12 @@ impl NriRuntimeDomain {
     }
 
     async fn get_container(&self, container_id: &str) -> Option<Container> {
-        let containers = self.containers.lock().await;
+        let containers = self.containers.read().await;
         containers.get(container_id).cloned()
     }
 
     async fn get_pod(&self, pod_id: &str) -> Option<crate::proto::runtime::v1::PodSandbox> {
-        let pods = self.pod_sandboxes.lock().await;
+        let pods = self.pod_sandboxes.read().await;
         pods.get(pod_id).cloned()
     }
 
@@ -2430,7 +2667,7 @@ impl NriRuntimeDomain {
         container_id: &str,
         resources: &StoredLinuxResources,
     ) -> crate::nri::Result<()> {
-        let mut resource_file = NamedTempFile::new_in(&self.config.root_dir)
+        let mut resource_file = NamedTempFile::new_in(&self.config.state_dir)
             .or_else(|_| NamedTempFile::new())
             .map_err(|e| {
                 crate::nri::NriError::Plugin(format!(
@@ -2595,7 +2832,7 @@ impl NriRuntimeDomain {
 
         let mut updated_container = None;
         {
-            let mut containers = self.containers.lock().await;
+            let mut containers = self.containers.write().await;
             if let Some(container) = containers.get_mut(&container_id) {
                 container.state =
                     RuntimeServiceImpl::map_runtime_container_state(runtime_status.clone());
@@ -2691,7 +2928,7 @@ impl NriRuntimeDomain {
         };
 
         {
-            let mut containers = self.containers.lock().await;
+            let mut containers = self.containers.write().await;
             if let Some(entry) = containers.get_mut(&container_id) {
                 entry.state = match final_runtime_status {
                     ContainerStatus::Created => ContainerState::ContainerCreated as i32,
@@ -2707,7 +2944,12 @@ impl NriRuntimeDomain {
                 state.finished_at = Some(RuntimeServiceImpl::now_nanos());
                 state.nri_stop_notified = true;
                 if let Some(code) = exit_code {
-                    state.exit_code = Some(code);
+                    RuntimeServiceImpl::set_exit_code(
+                        &self.runtime,
+                        &container_id,
+                        &mut state,
+                        code,
+                    );
                 }
                 RuntimeServiceImpl::insert_internal_state(
                     &mut entry.annotations,
@@ -2767,11 +3009,11 @@ impl NriRuntimeDomain {
 impl NriDomain for NriRuntimeDomain {
     async fn snapshot(&self) -> crate::nri::Result<RuntimeSnapshot> {
         let pods: Vec<crate::proto::runtime::v1::PodSandbox> = {
-            let pods = self.pod_sandboxes.lock().await;
+            let pods = self.pod_sandboxes.read().await;
             pods.values().cloned().collect()
         };
         let containers: Vec<Container> = {
-            let containers = self.containers.lock().await;
+            let containers = self.containers.read().await;
             containers.values().cloned().collect()
         };
         let mut snapshot_containers = Vec::new();
@@ -2831,22 +3073,118 @@ impl NriDomain for NriRuntimeDomain {
     }
 }
 
+impl RuntimeServiceImpl {
+    // 重新打开容器日志
+    async fn reopen_container_log_impl(
+        &self,
+        request: Request<ReopenContainerLogRequest>,
+    ) -> Result<Response<ReopenContainerLogResponse>, Status> {
+        let req = request.into_inner();
+        let container_id = self.resolve_container_id(&req.container_id).await?;
+        let container = {
+            let containers = self.containers.read().await;
+            containers.get(&container_id).cloned()
+        }
+        .ok_or_else(|| Status::not_found("Container not found"))?;
+
+        let runtime_state = self.runtime_container_status_checked(&container_id).await;
+        if !matches!(runtime_state, ContainerStatus::Running) {
+            return Err(Status::failed_precondition(format!(
+                "container {} is not running",
+                container_id
+            )));
+        }
+
+        let log_path = Self::read_internal_state::<StoredContainerState>(
+            &container.annotations,
+            INTERNAL_CONTAINER_STATE_KEY,
+        )
+        .and_then(|state| state.log_path)
+        .filter(|path| !path.is_empty())
+        .ok_or_else(|| {
+            Status::failed_precondition(format!(
+                "container {} does not have a configured log path",
+                container_id
+            ))
+        })?;
+
+        let log_path = PathBuf::from(log_path);
+        if let Some(parent) = log_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                Status::internal(format!(
+                    "Failed to create log directory {}: {}",
+                    parent.display(),
+                    e
+                ))
+            })?;
+        }
+        self.runtime
+            .reopen_container_log(&container_id)
+            .map_err(|e| Status::internal(format!("Failed to reopen container log: {}", e)))?;
+
+        Ok(Response::new(ReopenContainerLogResponse {}))
+    }
+
+    // 更新运行时配置
+    async fn update_runtime_config_impl(
+        &self,
+        request: Request<UpdateRuntimeConfigRequest>,
+    ) -> Result<Response<UpdateRuntimeConfigResponse>, Status> {
+        let req = request.into_inner();
+        let next_network_config = req
+            .runtime_config
+            .and_then(|runtime_config| runtime_config.network_config)
+            .filter(|network_config| !network_config.pod_cidr.trim().is_empty());
+
+        Self::persist_runtime_network_config(&self.config.root_dir, next_network_config.as_ref())
+            .map_err(|e| Status::internal(format!("Failed to persist runtime config: {}", e)))?;
+
+        let mut stored = self.runtime_network_config.lock().await;
+        *stored = next_network_config;
+        Ok(Response::new(UpdateRuntimeConfigResponse {}))
+    }
+}
+
 #[tonic::async_trait]
 impl RuntimeService for RuntimeServiceImpl {
     // 获取运行时版本
     async fn version(
         &self,
-        _request: Request<VersionRequest>,
+        request: Request<VersionRequest>,
     ) -> Result<Response<VersionResponse>, Status> {
+        let requested = cri_version::CriVersion::parse(&request.get_ref().version);
+        let compatibility = requested.negotiate().inspect_err(|status| {
+            log::warn!("Rejecting Version request: {}", status.message());
+        })?;
+        let supported = compatibility == cri_version::Compatibility::Supported;
+        if supported {
+            log::debug!("Client requested CRI version {}", requested);
+        } else {
+            log::warn!(
+                "Client requested unrecognized CRI version {}, answering with runtime.{}",
+                requested,
+                cri_version::SUPPORTED_API_VERSION
+            );
+        }
+
         let runtime_version = self
             .runtime_binary_version()
             .unwrap_or_else(|| env!("CARGO_PKG_VERSION").to_string());
-        Ok(Response::new(VersionResponse {
-            version: env!("CARGO_PKG_VERSION").to_string(),
+        let mut response = Response::new(VersionResponse {
+            version: cri_version::KUBE_RUNTIME_API_VERSION.to_string(),
             runtime_name: self.config.runtime.clone(),
             runtime_version,
-            runtime_api_version: "v1".to_string(),
-        }))
+            runtime_api_version: cri_version::SUPPORTED_API_VERSION.to_string(),
+        });
+        let metadata = response.metadata_mut();
+        if let Ok(value) = requested.to_string().parse() {
+            metadata.insert(cri_version::REQUESTED_VERSION_METADATA, value);
+        }
+        metadata.insert(
+            cri_version::VERSION_SUPPORTED_METADATA,
+            tonic::metadata::MetadataValue::from_static(if supported { "true" } else { "false" }),
+        );
+        Ok(response)
     }
 
     async fn container_status(
@@ -2927,84 +3265,151 @@ impl RuntimeService for RuntimeServiceImpl {
     }
 
     async fn exec(&self, request: Request<ExecRequest>) -> Result<Response<ExecResponse>, Status> {
-        RuntimeServiceImpl::exec(self, request).await
+        let entry = AuditEntry::new("/runtime.v1.RuntimeService/Exec", &request)
+            .id("container_id", &request.get_ref().container_id);
+        let result = RuntimeServiceImpl::exec(self, request).await;
+        self.audit(entry, &result);
+        result
     }
 
     async fn exec_sync(
         &self,
         request: Request<ExecSyncRequest>,
     ) -> Result<Response<ExecSyncResponse>, Status> {
-        RuntimeServiceImpl::exec_sync(self, request).await
+        let entry = AuditEntry::new("/runtime.v1.RuntimeService/ExecSync", &request)
+            .id("container_id", &request.get_ref().container_id);
+        let result = RuntimeServiceImpl::exec_sync(self, request).await;
+        self.audit(entry, &result);
+        result
     }
 
     async fn attach(
         &self,
         request: Request<AttachRequest>,
     ) -> Result<Response<AttachResponse>, Status> {
-        RuntimeServiceImpl::attach(self, request).await
+        let entry = AuditEntry::new("/runtime.v1.RuntimeService/Attach", &request)
+            .id("container_id", &request.get_ref().container_id);
+        let result = RuntimeServiceImpl::attach(self, request).await;
+        self.audit(entry, &result);
+        result
     }
 
     async fn port_forward(
         &self,
         request: Request<PortForwardRequest>,
     ) -> Result<Response<PortForwardResponse>, Status> {
-        RuntimeServiceImpl::port_forward(self, request).await
+        let entry = AuditEntry::new("/runtime.v1.RuntimeService/PortForward", &request)
+            .id("pod_sandbox_id", &request.get_ref().pod_sandbox_id);
+        let result = RuntimeServiceImpl::port_forward(self, request).await;
+        self.audit(entry, &result);
+        result
     }
 
     async fn run_pod_sandbox(
         &self,
         request: Request<RunPodSandboxRequest>,
     ) -> Result<Response<RunPodSandboxResponse>, Status> {
-        RuntimeServiceImpl::run_pod_sandbox(self, request).await
+        let metadata = request
+            .get_ref()
+            .config
+            .as_ref()
+            .and_then(|config| config.metadata.clone())
+            .unwrap_or_default();
+        let entry = AuditEntry::new("/runtime.v1.RuntimeService/RunPodSandbox", &request)
+            .id("pod_name", &metadata.name)
+            .id("pod_namespace", &metadata.namespace)
+            .id("pod_uid", &metadata.uid);
+        let result = RuntimeServiceImpl::run_pod_sandbox(self, request).await;
+        self.audit(
+            entry.response_id("pod_sandbox_id", &result, |response| {
+                &response.pod_sandbox_id
+            }),
+            &result,
+        );
+        result
     }
 
     async fn update_pod_sandbox_resources(
         &self,
         request: Request<UpdatePodSandboxResourcesRequest>,
     ) -> Result<Response<UpdatePodSandboxResourcesResponse>, Status> {
-        RuntimeServiceImpl::update_pod_sandbox_resources(self, request).await
+        let entry = AuditEntry::new(
+            "/runtime.v1.RuntimeService/UpdatePodSandboxResources",
+            &request,
+        )
+        .id("pod_sandbox_id", &request.get_ref().pod_sandbox_id);
+        let result = RuntimeServiceImpl::update_pod_sandbox_resources(self, request).await;
+        self.audit(entry, &result);
+        result
     }
 
     async fn stop_pod_sandbox(
         &self,
         request: Request<StopPodSandboxRequest>,
     ) -> Result<Response<StopPodSandboxResponse>, Status> {
-        RuntimeServiceImpl::stop_pod_sandbox(self, request).await
+        let entry = AuditEntry::new("/runtime.v1.RuntimeService/StopPodSandbox", &request)
+            .id("pod_sandbox_id", &request.get_ref().pod_sandbox_id);
+        let result = RuntimeServiceImpl::stop_pod_sandbox(self, request).await;
+        self.audit(entry, &result);
+        result
     }
 
     async fn remove_pod_sandbox(
         &self,
         request: Request<RemovePodSandboxRequest>,
     ) -> Result<Response<RemovePodSandboxResponse>, Status> {
-        RuntimeServiceImpl::remove_pod_sandbox(self, request).await
+        let entry = AuditEntry::new("/runtime.v1.RuntimeService/RemovePodSandbox", &request)
+            .id("pod_sandbox_id", &request.get_ref().pod_sandbox_id);
+        let result = RuntimeServiceImpl::remove_pod_sandbox(self, request).await;
+        self.audit(entry, &result);
+        result
     }
 
     async fn stop_container(
         &self,
         request: Request<StopContainerRequest>,
     ) -> Result<Response<StopContainerResponse>, Status> {
-        RuntimeServiceImpl::stop_container(self, request).await
+        let entry = AuditEntry::new("/runtime.v1.RuntimeService/StopContainer", &request)
+            .id("container_id", &request.get_ref().container_id);
+        let result = RuntimeServiceImpl::stop_container(self, request).await;
+        self.audit(entry, &result);
+        result
     }
 
     async fn remove_container(
         &self,
         request: Request<RemoveContainerRequest>,
     ) -> Result<Response<RemoveContainerResponse>, Status> {
-        RuntimeServiceImpl::remove_container(self, request).await
+        let entry = AuditEntry::new("/runtime.v1.RuntimeService/RemoveContainer", &request)
+            .id("container_id", &request.get_ref().container_id);
+        let result = RuntimeServiceImpl::remove_container(self, request).await;
+        self.audit(entry, &result);
+        result
     }
 
     async fn checkpoint_container(
         &self,
         request: Request<CheckpointContainerRequest>,
     ) -> Result<Response<CheckpointContainerResponse>, Status> {
-        RuntimeServiceImpl::checkpoint_container(self, request).await
+        let entry = AuditEntry::new("/runtime.v1.RuntimeService/CheckpointContainer", &request)
+            .id("container_id", &request.get_ref().container_id);
+        let result = RuntimeServiceImpl::checkpoint_container(self, request).await;
+        self.audit(entry, &result);
+        result
     }
 
     async fn update_container_resources(
         &self,
         request: Request<UpdateContainerResourcesRequest>,
     ) -> Result<Response<UpdateContainerResourcesResponse>, Status> {
-        RuntimeServiceImpl::update_container_resources(self, request).await
+        let entry = AuditEntry::new(
+            "/runtime.v1.RuntimeService/UpdateContainerResources",
+            &request,
+        )
+        .id("container_id", &request.get_ref().container_id);
+        let result = RuntimeServiceImpl::update_container_resources(self, request).await;
+        self.audit(entry, &result);
+        result
     }
 
     #[allow(unreachable_code)]
@@ -3013,7 +3418,14 @@ impl RuntimeService for RuntimeServiceImpl {
         &self,
         request: Request<CreateContainerRequest>,
     ) -> Result<Response<CreateContainerResponse>, Status> {
-        RuntimeServiceImpl::create_container_impl(self, request).await
+        let entry = AuditEntry::new("/runtime.v1.RuntimeService/CreateContainer", &request)
+            .id("pod_sandbox_id", &request.get_ref().pod_sandbox_id);
+        let result = RuntimeServiceImpl::create_container_impl(self, request).await;
+        self.audit(
+            entry.response_id("container_id", &result, |response| &response.container_id),
+            &result,
+        );
+        result
     }
 
     #[allow(unreachable_code)]
@@ -3022,7 +3434,11 @@ impl RuntimeService for RuntimeServiceImpl {
         &self,
         request: Request<StartContainerRequest>,
     ) -> Result<Response<StartContainerResponse>, Status> {
-        RuntimeServiceImpl::start_container_impl(self, request).await
+        let entry = AuditEntry::new("/runtime.v1.RuntimeService/StartContainer", &request)
+            .id("container_id", &request.get_ref().container_id);
+        let result = RuntimeServiceImpl::start_container_impl(self, request).await;
+        self.audit(entry, &result);
+        result
     }
 
     //重新打开容器日志
@@ -3030,50 +3446,11 @@ impl RuntimeService for RuntimeServiceImpl {
         &self,
         request: Request<ReopenContainerLogRequest>,
     ) -> Result<Response<ReopenContainerLogResponse>, Status> {
-        let req = request.into_inner();
-        let container_id = self.resolve_container_id(&req.container_id).await?;
-        let container = {
-            let containers = self.containers.lock().await;
-            containers.get(&container_id).cloned()
-        }
-        .ok_or_else(|| Status::not_found("Container not found"))?;
-
-        let runtime_state = self.runtime_container_status_checked(&container_id).await;
-        if !matches!(runtime_state, ContainerStatus::Running) {
-            return Err(Status::failed_precondition(format!(
-                "container {} is not running",
-                container_id
-            )));
-        }
-
-        let log_path = Self::read_internal_state::<StoredContainerState>(
-            &container.annotations,
-            INTERNAL_CONTAINER_STATE_KEY,
-        )
-        .and_then(|state| state.log_path)
-        .filter(|path| !path.is_empty())
-        .ok_or_else(|| {
-            Status::failed_precondition(format!(
-                "container {} does not have a configured log path",
-                container_id
-            ))
-        })?;
-
-        let log_path = PathBuf::from(log_path);
-        if let Some(parent) = log_path.parent() {
-            std::fs::create_dir_all(parent).map_err(|e| {
-                Status::internal(format!(
-                    "Failed to create log directory {}: {}",
-                    parent.display(),
-                    e
-                ))
-            })?;
-        }
-        self.runtime
-            .reopen_container_log(&container_id)
-            .map_err(|e| Status::internal(format!("Failed to reopen container log: {}", e)))?;
-
-        Ok(Response::new(ReopenContainerLogResponse {}))
+        let entry = AuditEntry::new("/runtime.v1.RuntimeService/ReopenContainerLog", &request)
+            .id("container_id", &request.get_ref().container_id);
+        let result = self.reopen_container_log_impl(request).await;
+        self.audit(entry, &result);
+        result
     }
 
     // 更新运行时配置
@@ -3081,18 +3458,10 @@ impl RuntimeService for RuntimeServiceImpl {
         &self,
         request: Request<UpdateRuntimeConfigRequest>,
     ) -> Result<Response<UpdateRuntimeConfigResponse>, Status> {
-        let req = request.into_inner();
-        let next_network_config = req
-            .runtime_config
-            .and_then(|runtime_config| runtime_config.network_config)
-            .filter(|network_config| !network_config.pod_cidr.trim().is_empty());
-
-        Self::persist_runtime_network_config(&self.config.root_dir, next_network_config.as_ref())
-            .map_err(|e| Status::internal(format!("Failed to persist runtime config: {}", e)))?;
-
-        let mut stored = self.runtime_network_config.lock().await;
-        *stored = next_network_config;
-        Ok(Response::new(UpdateRuntimeConfigResponse {}))
+        let entry = AuditEntry::new("/runtime.v1.RuntimeService/UpdateRuntimeConfig", &request);
+        let result = self.update_runtime_config_impl(request).await;
+        self.audit(entry, &result);
+        result
     }
 
     type GetContainerEventsStream = ReceiverStream<Result<ContainerEventResponse, Status>>;
@@ -3104,7 +3473,9 @@ impl RuntimeService for RuntimeServiceImpl {
     ) -> Result<Response<Self::GetContainerEventsStream>, Status> {
         log::info!("Get container events");
 
-        let (tx, rx) = tokio::sync::mpsc::channel(128);
+        // 生命周期操作只向广播通道发布事件，从不等待订阅者；消费过慢的订阅者落后后
+        // 收到 ResourceExhausted 并结束流，由客户端重新 List 后再订阅
+        let (tx, rx) = tokio::sync::mpsc::channel(CONTAINER_EVENTS_STREAM_BUFFER);
         let mut subscriber = self.events.subscribe();
         tokio::spawn(async move {
             loop {
@@ -3116,16 +3487,18 @@ impl RuntimeService for RuntimeServiceImpl {
                         }
                     }
                     Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
-                        if let Err(e) = tx
+                        log::warn!(
+                            "Container events subscriber fell behind, dropped {} events",
+                            skipped
+                        );
+                        let _ = tx
                             .send(Err(Status::resource_exhausted(format!(
-                                "missed {} container events due to slow consumer",
+                                "missed {} container events due to slow consumer; \
+                                 relist containers and pod sandboxes to resync",
                                 skipped
                             ))))
-                            .await
-                        {
-                            log::debug!("Container events subscriber disconnected: {}", e);
-                            break;
-                        }
+                            .await;
+                        break;
                     }
                     Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
                 }
@@ -0,0 +1,75 @@
+//! gRPC handler 的 panic 边界
+//!
+//! 单个请求处理中的 panic 会被转换为 `Status::internal`，连接和守护进程继续服务。
+//! tokio 的 Mutex 不会因 panic 中毒，guard 在栈展开时正常释放。
+
+use std::any::Any;
+use std::panic::AssertUnwindSafe;
+use std::task::{Context, Poll};
+
+use futures::future::{BoxFuture, FutureExt};
+use tonic::body::BoxBody;
+use tonic::codegen::http;
+use tonic::Status;
+use tower::{Layer, Service};
+
+/// 捕获 handler panic 的 tower layer
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CatchPanicLayer;
+
+impl<S> Layer<S> for CatchPanicLayer {
+    type Service = CatchPanic<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CatchPanic { inner }
+    }
+}
+
+/// 见 [`CatchPanicLayer`]
+#[derive(Debug, Clone)]
+pub struct CatchPanic<S> {
+    inner: S,
+}
+
+impl<S, ReqBody> Service<http::Request<ReqBody>> for CatchPanic<S>
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<BoxBody>>,
+    S::Error: Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<ReqBody>) -> Self::Future {
+        let path = request.uri().path().to_string();
+        let future = match std::panic::catch_unwind(AssertUnwindSafe(|| self.inner.call(request))) {
+            Ok(future) => future,
+            Err(panic) => {
+                return futures::future::ready(Ok(panic_response(&path, panic))).boxed();
+            }
+        };
+
+        AssertUnwindSafe(future)
+            .catch_unwind()
+            .map(move |result| match result {
+                Ok(response) => response,
+                Err(panic) => Ok(panic_response(&path, panic)),
+            })
+            .boxed()
+    }
+}
+
+fn panic_response(path: &str, panic: Box<dyn Any + Send>) -> http::Response<BoxBody> {
+    let detail = panic
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic payload".to_string());
+    log::error!("gRPC handler {} panicked: {}", path, detail);
+    Status::internal("internal error while handling request").to_http()
+}
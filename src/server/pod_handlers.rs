@@ -3,7 +3,7 @@ use super::*;
 impl RuntimeServiceImpl {
     pub(super) async fn nri_pod_event(&self, pod_id: &str) -> NriPodEvent {
         let pod = {
-            let pods = self.pod_sandboxes.lock().await;
+            let pods = self.pod_sandboxes.read().await;
             pods.get(pod_id)
                 .map(|pod| Self::build_nri_pod_from_proto(&self.runtime, pod))
         };
@@ -14,11 +14,58 @@ impl RuntimeServiceImpl {
         }
     }
 
+    /// Pod 级 cgroup 的实际限制：容器资源总和加上 RuntimeClass overhead
+    ///
+    /// 与 containerd 一致，未设置（不限制）的项加上 overhead 后仍不限制；
+    /// 只有 overhead 时直接使用 overhead。
     pub(super) fn effective_pod_linux_resources(
         overhead: Option<&crate::proto::runtime::v1::LinuxContainerResources>,
         resources: Option<&crate::proto::runtime::v1::LinuxContainerResources>,
     ) -> Option<crate::proto::runtime::v1::LinuxContainerResources> {
-        resources.cloned().or_else(|| overhead.cloned())
+        let (Some(overhead), Some(resources)) = (overhead, resources) else {
+            return resources.or(overhead).cloned();
+        };
+        let mut effective = resources.clone();
+        if effective.cpu_shares > 0 || overhead.cpu_shares > 0 {
+            effective.cpu_shares = effective.cpu_shares.saturating_add(overhead.cpu_shares);
+        }
+        if effective.cpu_quota > 0 && overhead.cpu_quota > 0 {
+            // overhead 的配额按 pod 的周期换算后再累加
+            let period = if effective.cpu_period > 0 {
+                effective.cpu_period
+            } else {
+                crate::cgroups::DEFAULT_CPU_PERIOD
+            };
+            let overhead_period = if overhead.cpu_period > 0 {
+                overhead.cpu_period
+            } else {
+                crate::cgroups::DEFAULT_CPU_PERIOD
+            };
+            let overhead_quota = (overhead.cpu_quota as i128 * period as i128
+                / overhead_period as i128)
+                .clamp(0, i64::MAX as i128) as i64;
+            effective.cpu_quota = effective.cpu_quota.saturating_add(overhead_quota);
+        }
+        if effective.memory_limit_in_bytes > 0 {
+            effective.memory_limit_in_bytes = effective
+                .memory_limit_in_bytes
+                .saturating_add(overhead.memory_limit_in_bytes);
+            if effective.memory_swap_limit_in_bytes > 0 {
+                effective.memory_swap_limit_in_bytes = effective
+                    .memory_swap_limit_in_bytes
+                    .saturating_add(overhead.memory_limit_in_bytes);
+            }
+        }
+        for limit in &mut effective.hugepage_limits {
+            if let Some(extra) = overhead
+                .hugepage_limits
+                .iter()
+                .find(|extra| extra.page_size == limit.page_size)
+            {
+                limit.limit = limit.limit.saturating_add(extra.limit);
+            }
+        }
+        Some(effective)
     }
 
     #[allow(dead_code)]
@@ -59,9 +106,10 @@ impl RuntimeServiceImpl {
         }
 
         {
-            let mut pod_sandboxes = self.pod_sandboxes.lock().await;
+            let mut pod_sandboxes = self.pod_sandboxes.write().await;
             pod_sandboxes.remove(pod_id);
         }
+        self.tasks.shutdown(pod_id, TASK_SHUTDOWN_TIMEOUT).await;
 
         let mut persistence = self.persistence.lock().await;
         if let Err(err) = persistence.delete_pod_sandbox(pod_id) {
@@ -73,20 +121,73 @@ impl RuntimeServiceImpl {
         }
     }
 
+    /// 名字、命名空间、uid 与 attempt 都相同的 RunPodSandbox 视为重试，返回已有 sandbox；
+    /// attempt 变化时创建新 sandbox
     pub(super) async fn run_pod_sandbox(
         &self,
         request: Request<RunPodSandboxRequest>,
+    ) -> Result<Response<RunPodSandboxResponse>, Status> {
+        let Some(metadata) = request
+            .get_ref()
+            .config
+            .as_ref()
+            .and_then(|config| config.metadata.clone())
+            .filter(|metadata| !metadata.name.is_empty())
+        else {
+            return self.run_new_pod_sandbox(request).await;
+        };
+        let key = format!(
+            "create/pod/{}/{}/{}/{}",
+            metadata.namespace, metadata.name, metadata.uid, metadata.attempt
+        );
+        let guard = self.lock_container_lifecycle(&key).await;
+        let existing = {
+            let pod_sandboxes = self.pod_sandboxes.read().await;
+            pod_sandboxes
+                .values()
+                .find(|pod| pod.metadata.as_ref() == Some(&metadata))
+                .map(|pod| pod.id.clone())
+        };
+        let result = match existing {
+            Some(pod_sandbox_id) => {
+                log::info!(
+                    "RunPodSandbox for {}/{} attempt {} matches existing sandbox {}",
+                    metadata.namespace,
+                    metadata.name,
+                    metadata.attempt,
+                    pod_sandbox_id
+                );
+                Ok(Response::new(RunPodSandboxResponse { pod_sandbox_id }))
+            }
+            None => self.run_new_pod_sandbox(request).await,
+        };
+        self.release_container_lifecycle_lock(&key, guard).await;
+        result
+    }
+
+    async fn run_new_pod_sandbox(
+        &self,
+        request: Request<RunPodSandboxRequest>,
     ) -> Result<Response<RunPodSandboxResponse>, Status> {
         let _sync_block = self.nri.block_plugin_sync().await;
         let req = request.into_inner();
         let pod_config = req
             .config
             .ok_or_else(|| Status::invalid_argument("Pod config not specified"))?;
+        Self::validate_external_annotations(&pod_config.annotations)?;
+        Self::validate_pod_sandbox_spec(&pod_config)?;
         let runtime_handler = self.resolve_runtime_handler(req.runtime_handler.trim())?;
         let linux_config = pod_config.linux.clone();
         let sandbox_security = linux_config
             .as_ref()
             .and_then(|linux| linux.security_context.as_ref());
+        if self.config.rootless.is_some() {
+            Self::validate_rootless_security(
+                "pod sandbox",
+                sandbox_security.is_some_and(|security| security.privileged),
+                sandbox_security.and_then(|security| security.namespace_options.as_ref()),
+            )?;
+        }
         let pod_overhead = linux_config
             .as_ref()
             .and_then(|linux| linux.overhead.clone());
@@ -117,48 +218,61 @@ impl RuntimeServiceImpl {
             .iter()
             .map(|(k, v)| (k.clone(), v.clone()))
             .collect();
-        sandbox_annotations.insert(
-            CRIO_SANDBOX_NAME_ANNOTATION.to_string(),
+        let mut shadowed_annotations = HashMap::new();
+        Self::set_runtime_annotation(
+            &mut sandbox_annotations,
+            &mut shadowed_annotations,
+            CRIO_SANDBOX_NAME_ANNOTATION,
             pod_config
                 .metadata
                 .as_ref()
                 .map(|m| m.name.clone())
                 .unwrap_or_default(),
         );
-        sandbox_annotations.insert(
-            CRIO_POD_NAME_ANNOTATION.to_string(),
+        Self::set_runtime_annotation(
+            &mut sandbox_annotations,
+            &mut shadowed_annotations,
+            CRIO_POD_NAME_ANNOTATION,
             pod_config
                 .metadata
                 .as_ref()
                 .map(|m| m.name.clone())
                 .unwrap_or_default(),
         );
-        sandbox_annotations.insert(
-            CRIO_POD_NAMESPACE_ANNOTATION.to_string(),
+        Self::set_runtime_annotation(
+            &mut sandbox_annotations,
+            &mut shadowed_annotations,
+            CRIO_POD_NAMESPACE_ANNOTATION,
             pod_config
                 .metadata
                 .as_ref()
                 .map(|m| m.namespace.clone())
                 .unwrap_or_else(|| "default".to_string()),
         );
-        sandbox_annotations.insert(
-            CONTAINERD_SANDBOX_NAME_ANNOTATION.to_string(),
+        Self::set_runtime_annotation(
+            &mut sandbox_annotations,
+            &mut shadowed_annotations,
+            CONTAINERD_SANDBOX_NAME_ANNOTATION,
             pod_config
                 .metadata
                 .as_ref()
                 .map(|m| m.name.clone())
                 .unwrap_or_default(),
         );
-        sandbox_annotations.insert(
-            CONTAINERD_SANDBOX_NAMESPACE_ANNOTATION.to_string(),
+        Self::set_runtime_annotation(
+            &mut sandbox_annotations,
+            &mut shadowed_annotations,
+            CONTAINERD_SANDBOX_NAMESPACE_ANNOTATION,
             pod_config
                 .metadata
                 .as_ref()
                 .map(|m| m.namespace.clone())
                 .unwrap_or_else(|| "default".to_string()),
         );
-        sandbox_annotations.insert(
-            CONTAINERD_SANDBOX_UID_ANNOTATION.to_string(),
+        Self::set_runtime_annotation(
+            &mut sandbox_annotations,
+            &mut shadowed_annotations,
+            CONTAINERD_SANDBOX_UID_ANNOTATION,
             pod_config
                 .metadata
                 .as_ref()
@@ -166,6 +280,7 @@ impl RuntimeServiceImpl {
                 .unwrap_or_default(),
         );
 
+        let shm_size = self.shm_size(&sandbox_annotations)?;
         let sandbox_config = PodSandboxConfig {
             name: pod_config
                 .metadata
@@ -240,18 +355,16 @@ impl RuntimeServiceImpl {
                     ),
                     pod_cidr: cfg.pod_cidr.clone(),
                 }),
-            cgroup_parent: linux_config
-                .as_ref()
-                .and_then(|linux| {
-                    (!linux.cgroup_parent.is_empty()).then(|| linux.cgroup_parent.clone())
-                })
-                .or_else(|| Some("crius".to_string())),
+            cgroup_parent: linux_config.as_ref().and_then(|linux| {
+                (!linux.cgroup_parent.is_empty()).then(|| linux.cgroup_parent.clone())
+            }),
             sysctls: linux_config
                 .as_ref()
                 .map(|linux| linux.sysctls.clone())
                 .unwrap_or_default(),
             namespace_options: sandbox_security
                 .and_then(|security| security.namespace_options.clone()),
+            shm_size,
             privileged: sandbox_security
                 .map(|security| security.privileged)
                 .unwrap_or(false),
@@ -294,6 +407,10 @@ impl RuntimeServiceImpl {
         let pod_state = created_pod
             .as_ref()
             .map(|pod| StoredPodState {
+                metadata_attempt: pod_config
+                    .metadata
+                    .as_ref()
+                    .map(|metadata| metadata.attempt),
                 log_directory: if pod_config.log_directory.is_empty() {
                     None
                 } else {
@@ -321,9 +438,18 @@ impl RuntimeServiceImpl {
                             .collect()
                     })
                     .unwrap_or_default(),
+                network_config: pod
+                    .network_status
+                    .as_ref()
+                    .and_then(|status| status.config.clone()),
+                cni_result: pod
+                    .network_status
+                    .as_ref()
+                    .and_then(|status| status.cni_result.clone()),
                 cgroup_parent: linux_config.as_ref().and_then(|linux| {
                     (!linux.cgroup_parent.is_empty()).then(|| linux.cgroup_parent.clone())
                 }),
+                cgroup_path: pod.cgroup_path.clone(),
                 sysctls: linux_config
                     .as_ref()
                     .map(|linux| linux.sysctls.clone())
@@ -361,8 +487,18 @@ impl RuntimeServiceImpl {
                 seccomp_profile: stored_seccomp_profile.clone(),
                 overhead_linux_resources: pod_overhead.as_ref().map(StoredLinuxResources::from),
                 linux_resources: pod_linux_resources.as_ref().map(StoredLinuxResources::from),
+                creation_timings: pod.creation_timings.clone(),
+                shm_path: pod
+                    .shm_path
+                    .as_ref()
+                    .map(|path| path.to_string_lossy().to_string()),
+                network_helper: pod.network_helper,
             })
             .unwrap_or_else(|| StoredPodState {
+                metadata_attempt: pod_config
+                    .metadata
+                    .as_ref()
+                    .map(|metadata| metadata.attempt),
                 log_directory: if pod_config.log_directory.is_empty() {
                     None
                 } else {
@@ -413,7 +549,27 @@ impl RuntimeServiceImpl {
                 ..Default::default()
             });
         let mut stored_annotations = sandbox_annotations;
-        Self::insert_internal_state(&mut stored_annotations, INTERNAL_POD_STATE_KEY, &pod_state)?;
+        let encoded = Self::insert_internal_state(
+            &mut stored_annotations,
+            INTERNAL_POD_STATE_KEY,
+            &pod_state,
+        )
+        .and(Self::record_shadowed_annotations(
+            &mut stored_annotations,
+            &shadowed_annotations,
+        ));
+        if let Err(err) = encoded {
+            // sandbox 尚未登记，直接释放 pause 容器、网络、shm 与 Pod cgroup
+            let mut pod_manager = self.pod_manager.lock().await;
+            if let Err(remove_err) = pod_manager.remove_pod_sandbox(&pod_id).await {
+                log::warn!(
+                    "Failed to remove pod sandbox {} after state encoding failed: {}",
+                    pod_id,
+                    remove_err
+                );
+            }
+            return Err(err);
+        }
 
         let pod_sandbox = crate::proto::runtime::v1::PodSandbox {
             id: pod_id.clone(),
@@ -422,18 +578,13 @@ impl RuntimeServiceImpl {
             created_at: created_pod
                 .as_ref()
                 .map(|pod| Self::normalize_timestamp_nanos(pod.created_at))
-                .unwrap_or_else(|| {
-                    std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_nanos() as i64
-                }),
+                .unwrap_or_else(Self::now_nanos),
             labels: pod_config.labels.clone(),
             annotations: stored_annotations.clone(),
             runtime_handler: runtime_handler.clone(),
         };
 
-        let mut pod_sandboxes = self.pod_sandboxes.lock().await;
+        let mut pod_sandboxes = self.pod_sandboxes.write().await;
         pod_sandboxes.insert(pod_id.clone(), pod_sandbox.clone());
         drop(pod_sandboxes);
 
@@ -522,6 +673,9 @@ impl RuntimeServiceImpl {
         let pod_id = self.resolve_pod_sandbox_id(&req.pod_sandbox_id).await?;
         let overhead = req.overhead;
         let resources = req.resources;
+        if let Some(overhead) = overhead.as_ref() {
+            Self::validate_pod_overhead(overhead)?;
+        }
         let effective_resources =
             Self::effective_pod_linux_resources(overhead.as_ref(), resources.as_ref());
         self.nri
@@ -532,7 +686,7 @@ impl RuntimeServiceImpl {
             .await
             .map_err(|e| Status::internal(format!("NRI UpdatePodSandbox failed: {}", e)))?;
         let pause_container_id = {
-            let pod_sandboxes = self.pod_sandboxes.lock().await;
+            let pod_sandboxes = self.pod_sandboxes.read().await;
             let pod = pod_sandboxes
                 .get(&pod_id)
                 .ok_or_else(|| Status::not_found("Pod sandbox not found"))?;
@@ -560,7 +714,7 @@ impl RuntimeServiceImpl {
         }
 
         let updated_pod = {
-            let mut pod_sandboxes = self.pod_sandboxes.lock().await;
+            let mut pod_sandboxes = self.pod_sandboxes.write().await;
             let pod = pod_sandboxes
                 .get_mut(&pod_id)
                 .ok_or_else(|| Status::not_found("Pod sandbox not found"))?;
@@ -650,12 +804,8 @@ impl RuntimeServiceImpl {
 
         log::info!("Stopping pod sandbox {}", pod_id);
         let container_ids: Vec<String> = {
-            let containers = self.containers.lock().await;
-            containers
-                .values()
-                .filter(|c| c.pod_sandbox_id == pod_id)
-                .map(|c| c.id.clone())
-                .collect()
+            let containers = self.containers.read().await;
+            containers.ids_in_sandbox(&pod_id)
         };
 
         let mut stopped_containers = Vec::new();
@@ -671,7 +821,7 @@ impl RuntimeServiceImpl {
             .await
             .map_err(|e| Status::internal(format!("Failed to stop pod sandbox: {}", e)))?;
 
-        let mut pod_sandboxes = self.pod_sandboxes.lock().await;
+        let mut pod_sandboxes = self.pod_sandboxes.write().await;
         if let Some(pod) = pod_sandboxes.get_mut(&pod_id) {
             pod.state = PodSandboxState::SandboxNotready as i32;
         }
@@ -697,7 +847,7 @@ impl RuntimeServiceImpl {
             .await;
         }
         if let Some(pod) = {
-            let pod_sandboxes = self.pod_sandboxes.lock().await;
+            let pod_sandboxes = self.pod_sandboxes.read().await;
             pod_sandboxes.get(&pod_id).cloned()
         } {
             let container_statuses = self.current_pod_container_snapshots(&pod_id).await;
@@ -726,27 +876,25 @@ impl RuntimeServiceImpl {
 
         log::info!("Removing pod sandbox {}", pod_id);
         let existing_pod = {
-            let pod_sandboxes = self.pod_sandboxes.lock().await;
+            let pod_sandboxes = self.pod_sandboxes.read().await;
             pod_sandboxes.get(&pod_id).cloned()
         };
         let pod_remove_event = self.nri_pod_event(&pod_id).await;
         let existing_container_statuses = self.current_pod_container_snapshots(&pod_id).await;
 
+        // rootless Pod 没有具名 netns，无需兜底清理
         let fallback_netns_name = {
-            let pod_sandboxes = self.pod_sandboxes.lock().await;
+            let pod_sandboxes = self.pod_sandboxes.read().await;
             pod_sandboxes
                 .get(&pod_id)
                 .and_then(|p| p.metadata.as_ref())
+                .filter(|_| self.config.rootless.is_none())
                 .map(|m| format!("crius-{}-{}", m.namespace, m.name))
         };
 
         let containers_in_pod: Vec<Container> = {
-            let containers = self.containers.lock().await;
-            containers
-                .values()
-                .filter(|c| c.pod_sandbox_id == pod_id)
-                .cloned()
-                .collect()
+            let containers = self.containers.read().await;
+            containers.in_sandbox(&pod_id).cloned().collect()
         };
         let container_ids: Vec<String> = containers_in_pod
             .iter()
@@ -766,17 +914,21 @@ impl RuntimeServiceImpl {
             .await
             .map_err(|e| Status::internal(format!("Failed to remove pod sandbox: {}", e)))?;
 
+        // netns 删除失败时仍然移除 sandbox 状态，避免 sandbox 永久卡住，最后再报告失败
+        let mut netns_error = None;
         if let Some(netns_name) = fallback_netns_name {
             let network_manager =
                 DefaultNetworkManager::from_cni_config(self.config.cni_config.clone());
             if let Err(e) = network_manager.remove_network_namespace(&netns_name).await {
                 log::warn!("Fallback netns cleanup failed for {}: {}", netns_name, e);
+                netns_error = Some(e);
             }
         }
 
-        let mut pod_sandboxes = self.pod_sandboxes.lock().await;
+        let mut pod_sandboxes = self.pod_sandboxes.write().await;
         pod_sandboxes.remove(&pod_id);
         drop(pod_sandboxes);
+        self.tasks.shutdown(&pod_id, TASK_SHUTDOWN_TIMEOUT).await;
 
         let mut persistence = self.persistence.lock().await;
         if let Err(e) = persistence.delete_pod_sandbox(&pod_id) {
@@ -806,6 +958,12 @@ impl RuntimeServiceImpl {
             )
             .await;
         }
+        if let Some(err) = netns_error {
+            return Err(Status::internal(format!(
+                "pod sandbox {} was removed but its network namespace could not be deleted: {}",
+                pod_id, err
+            )));
+        }
         Ok(Response::new(RemovePodSandboxResponse {}))
     }
 }
@@ -0,0 +1,163 @@
+//! 只读 gRPC 端点
+//!
+//! 供运维工具使用的辅助 socket 只开放查询类方法，其余请求返回 `PermissionDenied`，
+//! 避免工具误操作影响 kubelet 管理的容器。
+
+use std::task::{Context, Poll};
+
+use futures::future::{BoxFuture, FutureExt};
+use tonic::body::BoxBody;
+use tonic::codegen::http;
+use tonic::Status;
+use tower::{Layer, Service};
+
+/// 只读端点允许调用的方法
+pub const READ_ONLY_METHODS: &[&str] = &[
+    "/runtime.v1.RuntimeService/Version",
+    "/runtime.v1.RuntimeService/Status",
+    "/runtime.v1.RuntimeService/ListPodSandbox",
+    "/runtime.v1.RuntimeService/PodSandboxStatus",
+    "/runtime.v1.RuntimeService/PodSandboxStats",
+    "/runtime.v1.RuntimeService/ListPodSandboxStats",
+    "/runtime.v1.RuntimeService/ListContainers",
+    "/runtime.v1.RuntimeService/ContainerStatus",
+    "/runtime.v1.RuntimeService/ContainerStats",
+    "/runtime.v1.RuntimeService/ListContainerStats",
+    "/runtime.v1.RuntimeService/ListMetricDescriptors",
+    "/runtime.v1.RuntimeService/ListPodSandboxMetrics",
+    "/runtime.v1.RuntimeService/GetContainerEvents",
+    "/runtime.v1.ImageService/ListImages",
+    "/runtime.v1.ImageService/ImageStatus",
+    "/runtime.v1.ImageService/ImageFsInfo",
+];
+
+/// gRPC reflection 同样只读，保留给 grpcurl 等工具
+const REFLECTION_PREFIX: &str = "/grpc.reflection.";
+
+/// 限制端点可调用方法的 tower layer；`enabled` 为 false 时不做限制
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReadOnlyLayer {
+    enabled: bool,
+}
+
+impl ReadOnlyLayer {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled }
+    }
+}
+
+impl<S> Layer<S> for ReadOnlyLayer {
+    type Service = ReadOnly<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ReadOnly {
+            inner,
+            enabled: self.enabled,
+        }
+    }
+}
+
+/// 见 [`ReadOnlyLayer`]
+#[derive(Debug, Clone)]
+pub struct ReadOnly<S> {
+    inner: S,
+    enabled: bool,
+}
+
+impl<S, ReqBody> Service<http::Request<ReqBody>> for ReadOnly<S>
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<BoxBody>>,
+    S::Error: Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<ReqBody>) -> Self::Future {
+        let path = request.uri().path();
+        if self.enabled
+            && !READ_ONLY_METHODS.contains(&path)
+            && !path.starts_with(REFLECTION_PREFIX)
+        {
+            let status = Status::permission_denied(format!(
+                "{} is not allowed on a read-only endpoint",
+                path
+            ));
+            return futures::future::ready(Ok(status.to_http())).boxed();
+        }
+        self.inner.call(request).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower::ServiceExt;
+
+    fn request(path: &str) -> http::Request<()> {
+        http::Request::builder().uri(path).body(()).unwrap()
+    }
+
+    fn inner() -> impl Service<
+        http::Request<()>,
+        Response = http::Response<BoxBody>,
+        Error = std::convert::Infallible,
+        Future = impl Send + 'static,
+    > + Clone {
+        tower::service_fn(|_: http::Request<()>| async {
+            Ok::<_, std::convert::Infallible>(http::Response::new(tonic::body::empty_body()))
+        })
+    }
+
+    fn grpc_status(response: &http::Response<BoxBody>) -> Option<tonic::Code> {
+        Status::from_header_map(response.headers()).map(|status| status.code())
+    }
+
+    #[tokio::test]
+    async fn read_only_layer_rejects_mutating_methods() {
+        let service = ReadOnlyLayer::new(true).layer(inner());
+
+        for path in [
+            "/runtime.v1.RuntimeService/RunPodSandbox",
+            "/runtime.v1.RuntimeService/CreateContainer",
+            "/runtime.v1.RuntimeService/StopContainer",
+            "/runtime.v1.ImageService/PullImage",
+            "/runtime.v1.ImageService/RemoveImage",
+        ] {
+            let response = service.clone().oneshot(request(path)).await.unwrap();
+            assert_eq!(
+                grpc_status(&response),
+                Some(tonic::Code::PermissionDenied),
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn read_only_layer_passes_read_methods_and_disabled_mode() {
+        let service = ReadOnlyLayer::new(true).layer(inner());
+        for path in [
+            "/runtime.v1.RuntimeService/Version",
+            "/runtime.v1.RuntimeService/ContainerStatus",
+            "/runtime.v1.RuntimeService/GetContainerEvents",
+            "/runtime.v1.ImageService/ListImages",
+            "/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo",
+        ] {
+            let response = service.clone().oneshot(request(path)).await.unwrap();
+            assert_eq!(grpc_status(&response), None, "{}", path);
+        }
+
+        let response = ReadOnlyLayer::new(false)
+            .layer(inner())
+            .oneshot(request("/runtime.v1.RuntimeService/RemoveContainer"))
+            .await
+            .unwrap();
+        assert_eq!(grpc_status(&response), None);
+    }
+}
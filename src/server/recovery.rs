@@ -3,7 +3,7 @@ use super::*;
 impl RuntimeServiceImpl {
     pub(super) async fn reconcile_recovered_state(&self) -> Result<(), Status> {
         let container_ids: Vec<String> = {
-            let containers = self.containers.lock().await;
+            let containers = self.containers.read().await;
             containers.keys().cloned().collect()
         };
 
@@ -22,13 +22,13 @@ impl RuntimeServiceImpl {
             };
 
             {
-                let mut containers = self.containers.lock().await;
-                if let Some(container) = containers.get_mut(&container_id) {
-                    container.state = runtime_state;
-                    if let Some(mut state) = Self::read_internal_state::<StoredContainerState>(
+                let mut containers = self.containers.write().await;
+                if let Some(container) = containers.get(&container_id) {
+                    let state = Self::read_internal_state::<StoredContainerState>(
                         &container.annotations,
                         INTERNAL_CONTAINER_STATE_KEY,
-                    ) {
+                    )
+                    .map(|mut state| {
                         match &persistence_status {
                             crate::runtime::ContainerStatus::Running => {
                                 state.started_at.get_or_insert(Self::now_nanos());
@@ -37,15 +37,37 @@ impl RuntimeServiceImpl {
                             }
                             crate::runtime::ContainerStatus::Stopped(code) => {
                                 state.finished_at.get_or_insert(Self::now_nanos());
-                                state.exit_code = Some(*code);
+                                Self::set_exit_code(
+                                    &self.runtime,
+                                    &container_id,
+                                    &mut state,
+                                    *code,
+                                );
                             }
                             _ => {}
                         }
-                        let _ = Self::insert_internal_state(
-                            &mut container.annotations,
-                            INTERNAL_CONTAINER_STATE_KEY,
-                            &state,
-                        );
+                        state
+                    });
+                    // 没有变化时不取可变引用，避免每次 List 前的刷新都作废容器快照
+                    let changed = container.state != runtime_state
+                        || state.as_ref().is_some_and(|state| {
+                            Self::internal_state_changed(
+                                &container.annotations,
+                                INTERNAL_CONTAINER_STATE_KEY,
+                                state,
+                            )
+                        });
+                    if let Some(container) =
+                        changed.then(|| containers.get_mut(&container_id)).flatten()
+                    {
+                        container.state = runtime_state;
+                        if let Some(state) = state {
+                            let _ = Self::insert_internal_state(
+                                &mut container.annotations,
+                                INTERNAL_CONTAINER_STATE_KEY,
+                                &state,
+                            );
+                        }
                     }
                 }
             }
@@ -61,13 +83,13 @@ impl RuntimeServiceImpl {
         }
 
         let pod_ids: Vec<String> = {
-            let pod_sandboxes = self.pod_sandboxes.lock().await;
+            let pod_sandboxes = self.pod_sandboxes.read().await;
             pod_sandboxes.keys().cloned().collect()
         };
 
         for pod_id in pod_ids {
             let pause_container_id = {
-                let pod_sandboxes = self.pod_sandboxes.lock().await;
+                let pod_sandboxes = self.pod_sandboxes.read().await;
                 pod_sandboxes.get(&pod_id).and_then(|pod| {
                     Self::read_internal_state::<StoredPodState>(
                         &pod.annotations,
@@ -85,7 +107,7 @@ impl RuntimeServiceImpl {
                 .runtime_container_status_checked(&pause_container_id)
                 .await;
             let pod_has_required_netns = {
-                let pod_sandboxes = self.pod_sandboxes.lock().await;
+                let pod_sandboxes = self.pod_sandboxes.read().await;
                 pod_sandboxes
                     .get(&pod_id)
                     .map(Self::pod_has_required_netns)
@@ -105,11 +127,15 @@ impl RuntimeServiceImpl {
             };
 
             {
-                let mut pod_sandboxes = self.pod_sandboxes.lock().await;
+                let mut pod_sandboxes = self.pod_sandboxes.write().await;
                 if let Some(pod) = pod_sandboxes.get_mut(&pod_id) {
                     pod.state = next_state;
                 }
             }
+            // pause 容器已不在运行，netns 随之消失，回收残留的用户态网络进程
+            if next_state != PodSandboxState::SandboxReady as i32 {
+                self.pod_manager.lock().await.kill_network_helper(&pod_id);
+            }
 
             let mut persistence = self.persistence.lock().await;
             if let Err(e) = persistence.update_pod_state(
@@ -131,6 +157,40 @@ impl RuntimeServiceImpl {
         Ok(())
     }
 
+    /// 启动后台对账循环，按配置间隔与 runc 同步容器/沙箱状态并发布事件。
+    /// 间隔为 0 时不启动。
+    pub fn start_state_reconciler(&self) -> Option<tokio::task::JoinHandle<()>> {
+        let interval = self.config.reconcile_interval;
+        if interval.is_zero() {
+            return None;
+        }
+
+        let runtime = self.runtime.clone();
+        let config = self.config.clone();
+        let containers = self.containers.clone();
+        let pod_sandboxes = self.pod_sandboxes.clone();
+        let persistence = self.persistence.clone();
+        let events = self.events.clone();
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            // 首次 tick 立即返回，启动时的状态已由 recover_state 对账
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                Self::refresh_runtime_state_and_publish_events(
+                    &runtime,
+                    &config,
+                    &containers,
+                    &pod_sandboxes,
+                    &persistence,
+                    &events,
+                )
+                .await;
+            }
+        }))
+    }
+
     pub(super) async fn best_effort_refresh_runtime_state(&self) {
         if let Err(err) = self.reconcile_recovered_state().await {
             log::warn!("Best-effort runtime state refresh failed: {}", err);
@@ -145,7 +205,7 @@ impl RuntimeServiceImpl {
 
         match recovered_containers {
             Ok(containers) => {
-                let mut memory_containers = self.containers.lock().await;
+                let mut memory_containers = self.containers.write().await;
                 for (_id, status, record) in containers {
                     let annotations: HashMap<String, String> =
                         serde_json::from_str(&record.annotations).unwrap_or_default();
@@ -172,7 +232,7 @@ impl RuntimeServiceImpl {
                             attempt: container_state
                                 .as_ref()
                                 .and_then(|state| state.metadata_attempt)
-                                .unwrap_or(1),
+                                .unwrap_or(DEFAULT_METADATA_ATTEMPT),
                         }),
                         state: match status {
                             crate::runtime::ContainerStatus::Created => {
@@ -196,12 +256,13 @@ impl RuntimeServiceImpl {
                         image_ref: record.image,
                         labels: serde_json::from_str(&record.labels).unwrap_or_default(),
                         annotations,
-                        created_at: record.created_at,
+                        created_at: Self::normalize_timestamp_nanos(record.created_at),
                     };
                     let mut container = container;
                     if let Some(mut state) = container_state.clone() {
                         if state.finished_at.is_none() {
-                            state.finished_at = record.exit_time;
+                            state.finished_at =
+                                record.exit_time.map(Self::normalize_timestamp_nanos);
                         }
                         if state.exit_code.is_none() {
                             state.exit_code = record.exit_code;
@@ -236,7 +297,7 @@ impl RuntimeServiceImpl {
 
         match recovered_pods {
             Ok(pods) => {
-                let mut memory_pods = self.pod_sandboxes.lock().await;
+                let mut memory_pods = self.pod_sandboxes.write().await;
                 let mut pod_manager = self.pod_manager.lock().await;
                 for record in pods {
                     let annotations: HashMap<String, String> =
@@ -265,14 +326,16 @@ impl RuntimeServiceImpl {
                             name: record.name.clone(),
                             uid: record.uid.clone(),
                             namespace: record.namespace.clone(),
-                            attempt: 1,
+                            attempt: pod_state
+                                .metadata_attempt
+                                .unwrap_or(DEFAULT_METADATA_ATTEMPT),
                         }),
                         state: match record.state.as_str() {
                             "ready" => PodSandboxState::SandboxReady as i32,
                             "notready" => PodSandboxState::SandboxNotready as i32,
                             _ => PodSandboxState::SandboxNotready as i32,
                         },
-                        created_at: record.created_at,
+                        created_at: Self::normalize_timestamp_nanos(record.created_at),
                         labels: labels.clone(),
                         annotations: annotations.clone(),
                         runtime_handler: recovered_runtime_handler.clone(),
@@ -307,6 +370,8 @@ impl RuntimeServiceImpl {
                                     gateway: None,
                                 })
                                 .collect(),
+                            config: pod_state.network_config.clone(),
+                            cni_result: pod_state.cni_result.clone(),
                         }
                     });
 
@@ -333,6 +398,7 @@ impl RuntimeServiceImpl {
                                 .namespace_options
                                 .as_ref()
                                 .map(StoredNamespaceOptions::to_proto),
+                            shm_size: self.config.shm_size,
                             privileged: pod_state.privileged,
                             run_as_user: pod_state.run_as_user.clone(),
                             run_as_group: pod_state.run_as_group,
@@ -367,7 +433,7 @@ impl RuntimeServiceImpl {
                             "notready" => crate::pod::PodSandboxState::NotReady,
                             _ => crate::pod::PodSandboxState::Terminated,
                         },
-                        created_at: record.created_at,
+                        created_at: Self::normalize_timestamp_nanos(record.created_at),
                         ip: pod_state
                             .ip
                             .or_else(|| {
@@ -380,6 +446,10 @@ impl RuntimeServiceImpl {
                             .or(record.ip.clone())
                             .unwrap_or_default(),
                         network_status,
+                        cgroup_path: pod_state.cgroup_path.clone(),
+                        creation_timings: pod_state.creation_timings.clone(),
+                        shm_path: pod_state.shm_path.as_ref().map(PathBuf::from),
+                        network_helper: pod_state.network_helper,
                     });
                 }
                 log::info!(
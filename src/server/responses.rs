@@ -39,7 +39,8 @@ impl RuntimeServiceImpl {
             .as_ref()
             .and_then(|state| state.exit_code)
             .unwrap_or_default();
-        let (reason, message) = Self::container_reason_message(runtime_state, exit_code);
+        let (reason, message) =
+            Self::container_status_reason(runtime_state, exit_code, container_state.as_ref());
         let mounts = Self::stored_mounts_to_proto(
             container_state
                 .as_ref()
@@ -51,7 +52,7 @@ impl RuntimeServiceImpl {
             id: container.id.clone(),
             metadata: Some(container.metadata.clone().unwrap_or(ContainerMetadata {
                 name: container.id.clone(),
-                attempt: 1,
+                attempt: DEFAULT_METADATA_ATTEMPT,
             })),
             state: runtime_state,
             created_at: Self::normalize_timestamp_nanos(container.created_at),
@@ -66,7 +67,7 @@ impl RuntimeServiceImpl {
             reason,
             message,
             labels: container.labels.clone(),
-            annotations: Self::external_annotations(&container.annotations),
+            annotations: Self::reported_annotations(&container.annotations),
             mounts,
             log_path: container_state
                 .as_ref()
@@ -113,13 +114,20 @@ impl RuntimeServiceImpl {
             INTERNAL_CONTAINER_STATE_KEY,
         );
         let runtime_spec = self.runtime_spec_snapshot(&container.id);
+        let config = Self::container_config_snapshot(
+            container,
+            container_state.as_ref(),
+            runtime_spec.as_ref(),
+        );
         let payload = json!({
             "id": container.id.clone(),
             "sandboxID": container.pod_sandbox_id.clone(),
             "podSandboxId": container.pod_sandbox_id.clone(),
             "runtimeState": Self::runtime_state_name(runtime_state),
             "pid": self.runtime_container_pid_checked(&container.id).await,
+            "paused": container_state.as_ref().map(|state| state.paused).unwrap_or(false),
             "runtimeSpec": runtime_spec,
+            "config": config,
             "privileged": container_state.as_ref().map(|state| state.privileged).unwrap_or(false),
             "logPath": container_state.as_ref().and_then(|state| state.log_path.clone()),
             "tty": container_state.as_ref().map(|state| state.tty).unwrap_or(false),
@@ -159,10 +167,77 @@ impl RuntimeServiceImpl {
                     }).collect::<Vec<_>>()
                 })
                 .unwrap_or_default(),
+            "timings": {
+                "createContainer": container_state
+                    .as_ref()
+                    .map(|state| state.create_timings.clone())
+                    .unwrap_or_default(),
+                "startContainer": container_state
+                    .as_ref()
+                    .map(|state| state.start_timings.clone())
+                    .unwrap_or_default(),
+            },
         });
         Self::encode_info_payload(payload)
     }
 
+    /// 按 CRI ContainerConfig 的 JSON 形状还原容器配置，供 `crictl inspect` 展示
+    ///
+    /// 原始请求未完整保存，env 与工作目录取自生成的 OCI spec。
+    fn container_config_snapshot(
+        container: &Container,
+        state: Option<&StoredContainerState>,
+        runtime_spec: Option<&serde_json::Value>,
+    ) -> serde_json::Value {
+        let process = runtime_spec.and_then(|spec| spec.get("process"));
+        let envs = process
+            .and_then(|process| process.get("env"))
+            .and_then(|env| env.as_array())
+            .map(|env| {
+                env.iter()
+                    .filter_map(|entry| entry.as_str()?.split_once('='))
+                    .map(|(key, value)| json!({ "key": key, "value": value }))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        json!({
+            "metadata": container.metadata.as_ref().map(|metadata| json!({
+                "name": metadata.name.clone(),
+                "attempt": metadata.attempt,
+            })),
+            "image": {
+                "image": container
+                    .image
+                    .as_ref()
+                    .map(|image| image.image.clone())
+                    .unwrap_or_else(|| container.image_ref.clone()),
+            },
+            "envs": envs,
+            "workingDir": process.and_then(|process| process.get("cwd")).cloned(),
+            "labels": container.labels.clone(),
+            "annotations": Self::reported_annotations(&container.annotations),
+            "logPath": state.and_then(|state| state.log_path.clone()),
+            "tty": state.map(|state| state.tty).unwrap_or(false),
+            "stdin": state.map(|state| state.stdin).unwrap_or(false),
+            "stdinOnce": state.map(|state| state.stdin_once).unwrap_or(false),
+            "linux": {
+                "resources": state.and_then(|state| state.linux_resources.clone()),
+                "securityContext": {
+                    "privileged": state.map(|state| state.privileged).unwrap_or(false),
+                    "readonlyRootfs": state.map(|state| state.readonly_rootfs).unwrap_or(false),
+                    "runAsUsername": state.and_then(|state| state.run_as_user.clone()),
+                    "runAsGroup": state.and_then(|state| state.run_as_group),
+                    "supplementalGroups": state
+                        .map(|state| state.supplemental_groups.clone())
+                        .unwrap_or_default(),
+                    "noNewPrivs": state.and_then(|state| state.no_new_privileges),
+                    "apparmorProfile": state.and_then(|state| state.apparmor_profile.clone()),
+                },
+            },
+        })
+    }
+
     pub(super) async fn build_pod_verbose_info(
         &self,
         pod_sandbox: &crate::proto::runtime::v1::PodSandbox,
@@ -203,6 +278,8 @@ impl RuntimeServiceImpl {
                 .as_ref()
                 .map(|state| state.additional_ips.clone())
                 .unwrap_or_default(),
+            "networkConfig": pod_state.as_ref().and_then(|state| state.network_config.clone()),
+            "cniResult": pod_state.as_ref().and_then(|state| state.cni_result.clone()),
             "cgroupParent": pod_state.as_ref().and_then(|state| state.cgroup_parent.clone()),
             "sysctls": pod_state
                 .as_ref()
@@ -222,6 +299,10 @@ impl RuntimeServiceImpl {
                     .map(|profile| profile.localhost_ref.clone())
                     .unwrap_or_default(),
             })),
+            "timings": pod_state
+                .as_ref()
+                .map(|state| state.creation_timings.clone())
+                .unwrap_or_default(),
         });
         Self::encode_info_payload(payload)
     }
@@ -241,32 +322,44 @@ impl RuntimeServiceImpl {
                 name: pod_sandbox.id.clone(),
                 uid: pod_sandbox.id.clone(),
                 namespace: "default".to_string(),
-                attempt: 1,
+                attempt: DEFAULT_METADATA_ATTEMPT,
             })),
             state: pod_sandbox.state,
             created_at: Self::normalize_timestamp_nanos(pod_sandbox.created_at),
             network: Self::pod_network_status_from_state(pod_state.as_ref()),
             linux: Self::pod_linux_status_from_state(pod_state.as_ref()),
             labels: pod_sandbox.labels.clone(),
-            annotations: Self::external_annotations(&pod_sandbox.annotations),
-            runtime_handler: if pod_sandbox.runtime_handler.is_empty() {
-                let restored = pod_state
-                    .as_ref()
-                    .map(|state| state.runtime_handler.clone())
-                    .filter(|handler| !handler.is_empty())
-                    .unwrap_or_else(|| config.runtime.clone());
-                if config
-                    .runtime_handlers
-                    .iter()
-                    .any(|handler| handler == &restored)
-                {
-                    restored
+            annotations: Self::reported_annotations(&pod_sandbox.annotations),
+            runtime_handler: Self::reported_runtime_handler(
+                config,
+                &if pod_sandbox.runtime_handler.is_empty() {
+                    let restored = pod_state
+                        .as_ref()
+                        .map(|state| state.runtime_handler.clone())
+                        .filter(|handler| !handler.is_empty())
+                        .unwrap_or_else(|| config.runtime.clone());
+                    if config
+                        .runtime_handlers
+                        .iter()
+                        .any(|handler| handler == &restored)
+                    {
+                        restored
+                    } else {
+                        config.runtime.clone()
+                    }
                 } else {
-                    config.runtime.clone()
-                }
-            } else {
-                pod_sandbox.runtime_handler.clone()
-            },
+                    pod_sandbox.runtime_handler.clone()
+                },
+            ),
+        }
+    }
+
+    /// 对 kubelet 报告的 runtime handler，默认 handler 报告为空串
+    pub(super) fn reported_runtime_handler(config: &RuntimeConfig, handler: &str) -> String {
+        if handler == config.runtime {
+            String::new()
+        } else {
+            handler.to_string()
         }
     }
 }
@@ -1,9 +1,19 @@
 use super::*;
 
+/// registry 探测的缓存状态
+#[derive(Debug, Default)]
+pub(super) struct RegistryProbe {
+    /// 最近一次探测的时间与结果
+    pub(super) last: Option<(std::time::Instant, Result<(), String>)>,
+    /// 是否有探测正在进行，避免并发的 Status 请求重复探测
+    pub(super) in_flight: bool,
+}
+
 /// 运行时服务实现
 pub struct RuntimeServiceImpl {
-    pub(super) containers: Arc<Mutex<HashMap<String, Container>>>,
-    pub(super) pod_sandboxes: Arc<Mutex<HashMap<String, crate::proto::runtime::v1::PodSandbox>>>,
+    /// 容器与 Pod 表以读为主（kubelet 周期性 list/status），用读写锁使并发读互不阻塞，写路径仍互斥
+    pub(super) containers: Arc<RwLock<ContainerStore>>,
+    pub(super) pod_sandboxes: Arc<RwLock<HashMap<String, crate::proto::runtime::v1::PodSandbox>>>,
     pub(super) config: RuntimeConfig,
     pub(super) nri_config: NriConfig,
     pub(super) nri: Arc<dyn NriApi>,
@@ -15,12 +25,32 @@ pub struct RuntimeServiceImpl {
     pub(super) shim_work_dir: PathBuf,
     pub(super) runtime_network_config: Arc<Mutex<Option<crate::proto::runtime::v1::NetworkConfig>>>,
     pub(super) exit_monitors: Arc<Mutex<HashSet<String>>>,
+    pub(super) tasks: Arc<TaskRegistry>,
+    /// 按容器串行化 stop/remove，remove 不会越过仍在终止中的 stop；
+    /// 也以 `create/...` 幂等键串行化同名同 attempt 的 Pod 与容器创建
+    pub(super) container_lifecycle_locks: Arc<Mutex<HashMap<String, Arc<Mutex<()>>>>>,
+    /// 变更类请求的审计日志，未配置时不记录
+    pub(super) audit: Option<Arc<crate::audit::AuditLogger>>,
+    /// 最近一次 registry 探测的时间与结果，锁只在读写缓存时持有
+    pub(super) registry_probe: Arc<Mutex<RegistryProbe>>,
+    /// ListContainerStats 最近一次全量扫描的结果
+    pub(super) container_stats_cache: Arc<Mutex<super::stats::ContainerStatsCache>>,
+    /// 各运行时 handler 的特性，首次使用时探测后不再变化
+    pub(super) handler_features:
+        Arc<std::sync::OnceLock<HashMap<String, super::features::RuntimeHandlerFeatures>>>,
+    /// crius 可用的 cgroup 控制器，首次使用时探测
+    pub(super) cgroup_controllers: Arc<std::sync::OnceLock<crate::cgroups::CgroupControllers>>,
 }
 
 /// 运行时配置
 #[derive(Debug, Clone)]
 pub struct RuntimeConfig {
+    /// 持久化数据根目录（数据库、网络配置）
     pub root_dir: PathBuf,
+    /// 容器/Pod 运行期状态目录，可放在 tmpfs 上
+    pub state_dir: PathBuf,
+    /// 镜像存储根目录
+    pub image_root: PathBuf,
     pub runtime: String,
     pub runtime_handlers: Vec<String>,
     pub runtime_root: PathBuf,
@@ -28,6 +58,234 @@ pub struct RuntimeConfig {
     pub runtime_path: PathBuf,
     pub pause_image: String,
     pub cni_config: CniConfig,
+    pub process_defaults: ProcessDefaultsConfig,
+    pub reconcile_interval: std::time::Duration,
+    /// `io.crius.env-file` 注解允许引用的目录，未配置时拒绝该注解
+    pub env_file_dir: Option<PathBuf>,
+    pub protected_paths: ProtectedPathsConfig,
+    /// CRI 请求中宿主机路径挂载的准入策略
+    pub mount_policy: MountPolicyConfig,
+    /// 容器日志轮转默认值
+    pub container_log: ContainerLogConfig,
+    /// 是否展开 `$(VAR)` 引用，默认关闭以与 kubelet 行为一致
+    pub expand_env_references: bool,
+    /// sandbox 未提供 dns_config 时使用的默认 DNS
+    pub default_dns: DefaultDnsConfig,
+    /// root_dir、state_dir、镜像存储与 bundle 等目录的权限
+    pub dir_mode: u32,
+    /// fsGroup 递归设置属组的卷条目上限，0 表示不限制
+    pub fs_group_max_entries: u64,
+    /// 静态链接的内置 pause 程序，设置后 sandbox 不再需要 pause 镜像
+    pub pause_binary: Option<PathBuf>,
+    /// 容器 `/dev/shm` 的默认大小（字节），可被注解覆盖
+    pub shm_size: u64,
+    /// `io.crius.rootfs-path` 注解允许使用的 rootfs 所在目录，未配置时拒绝该注解
+    pub prepared_rootfs_dir: Option<PathBuf>,
+    /// 注入每个容器的环境变量，优先级最低
+    pub default_env: Vec<(String, String)>,
+    /// cgroupsPath 的基准目录，`None` 时直接使用 CRI 下发的 cgroup_parent
+    pub cgroup_root: Option<String>,
+    /// cgroup 文件系统挂载点，Pod 级 cgroup 在其下创建
+    pub cgroup_mount: PathBuf,
+    /// sysfs 挂载点，cpuset 校验从中读取宿主机在线的 CPU 与内存节点
+    pub sysfs_root: PathBuf,
+    /// RemoveContainer 停止仍在运行的容器时的优雅退出时间（秒）
+    pub remove_grace_period_secs: u32,
+    /// SIGKILL 后等待容器退出的上限，RemoveContainer 超时后强制清理
+    pub kill_timeout: std::time::Duration,
+    /// ImageReady 条件探测的 registry，未配置时只检查镜像存储
+    pub image_health_registry: Option<String>,
+    /// registry 探测结果的缓存时间
+    pub image_health_interval: std::time::Duration,
+    /// rootless 模式配置，`None` 时以特权模式运行
+    pub rootless: Option<crate::rootless::RootlessConfig>,
+    /// ListContainerStats 全量扫描的间隔与批大小
+    pub stats: StatsConfig,
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            root_dir: PathBuf::from("/var/lib/crius"),
+            state_dir: PathBuf::from("/var/lib/crius"),
+            image_root: PathBuf::from("/var/lib/crius/storage"),
+            runtime: "runc".to_string(),
+            runtime_handlers: vec!["runc".to_string()],
+            runtime_root: PathBuf::from("/run/crius"),
+            log_dir: PathBuf::from("/var/log/crius"),
+            runtime_path: PathBuf::from("/usr/bin/runc"),
+            pause_image: "registry.k8s.io/pause:3.9".to_string(),
+            cni_config: CniConfig::default(),
+            process_defaults: ProcessDefaultsConfig::default(),
+            reconcile_interval: std::time::Duration::from_secs(10),
+            env_file_dir: None,
+            protected_paths: ProtectedPathsConfig::default(),
+            mount_policy: MountPolicyConfig::default(),
+            container_log: ContainerLogConfig::default(),
+            expand_env_references: false,
+            default_dns: DefaultDnsConfig::default(),
+            dir_mode: crate::utils::DEFAULT_DIR_MODE,
+            fs_group_max_entries: crate::storage::ownership::DEFAULT_FS_GROUP_MAX_ENTRIES,
+            pause_binary: None,
+            shm_size: crate::oci::spec::DEFAULT_SHM_SIZE,
+            prepared_rootfs_dir: None,
+            default_env: Vec::new(),
+            cgroup_root: None,
+            cgroup_mount: PathBuf::from("/sys/fs/cgroup"),
+            sysfs_root: PathBuf::from("/sys"),
+            remove_grace_period_secs: 30,
+            kill_timeout: crate::runtime::DEFAULT_KILL_WAIT_TIMEOUT,
+            image_health_registry: None,
+            image_health_interval: std::time::Duration::from_secs(60),
+            rootless: None,
+            stats: StatsConfig::default(),
+        }
+    }
+}
+
+impl RuntimeConfig {
+    /// 启动前校验配置：目录可创建且可写、runtime 二进制可解析
+    ///
+    /// 会收集全部问题后一次性返回，便于一次修正。
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let mut problems = Vec::new();
+
+        if self.dir_mode & !0o777 != 0 || self.dir_mode & 0o700 != 0o700 {
+            problems.push(format!(
+                "dir_mode {:04o} must be within 0777 and grant the owner rwx",
+                self.dir_mode
+            ));
+        }
+
+        // 持久化、状态与镜像目录由 crius 独占，已存在时也收紧到 dir_mode；
+        // runtime_root 与 log_dir 可能与其他组件共享，只约束新建的目录
+        for (name, dir, private) in [
+            ("root_dir", &self.root_dir, true),
+            ("state_dir", &self.state_dir, true),
+            ("image_root", &self.image_root, true),
+            ("runtime_root", &self.runtime_root, false),
+            ("log_dir", &self.log_dir, false),
+        ] {
+            if dir.as_os_str().is_empty() {
+                problems.push(format!("{} is empty", name));
+                continue;
+            }
+            let created = if private {
+                crate::utils::ensure_private_dir(dir, self.dir_mode)
+            } else {
+                crate::utils::create_dir_all_with_mode(dir, self.dir_mode)
+            };
+            if let Err(err) = created {
+                problems.push(format!(
+                    "{} {} cannot be created: {}",
+                    name,
+                    dir.display(),
+                    err
+                ));
+                continue;
+            }
+            if let Err(err) = nix::unistd::access(
+                dir.as_path(),
+                nix::unistd::AccessFlags::W_OK | nix::unistd::AccessFlags::X_OK,
+            ) {
+                problems.push(format!(
+                    "{} {} is not writable: {}",
+                    name,
+                    dir.display(),
+                    err
+                ));
+            }
+        }
+
+        if self.runtime.trim().is_empty() {
+            problems.push("runtime name is empty".to_string());
+        }
+        if let Err(problem) = Self::resolve_runtime_binary(&self.runtime_path) {
+            problems.push(problem);
+        }
+        if let Err(err) = self.process_defaults.validate() {
+            problems.push(err.to_string());
+        }
+        if let Err(err) = self.protected_paths.validate() {
+            problems.push(err.to_string());
+        }
+        if let Err(err) = self.mount_policy.validate() {
+            problems.push(err.to_string());
+        }
+        if let Err(err) = self.container_log.limits() {
+            problems.push(err.to_string());
+        }
+        if let Err(err) = self.container_log.mirror_level() {
+            problems.push(err.to_string());
+        }
+        if let Err(err) = self.default_dns.validate() {
+            problems.push(err.to_string());
+        }
+        if self.shm_size == 0 {
+            problems.push("shm_size must be greater than 0".to_string());
+        }
+        if let Some(cgroup_root) = self.cgroup_root.as_deref() {
+            if let Err(err) = crate::cgroups::validate_cgroup_root(&self.cgroup_mount, cgroup_root)
+            {
+                problems.push(format!("{:#}", err));
+            }
+        }
+        if let Some(pause_binary) = self.pause_binary.as_ref() {
+            if let Err(err) = crate::pod::validate_pause_binary(pause_binary) {
+                problems.push(format!("{:#}", err));
+            }
+        }
+        if let Some(helper) = self
+            .rootless
+            .as_ref()
+            .and_then(|rootless| rootless.network_mode.helper_binary())
+        {
+            if Self::resolve_runtime_binary(Path::new(helper)).is_err() {
+                problems.push(format!(
+                    "rootless network helper {} was not found in PATH",
+                    helper
+                ));
+            }
+        }
+
+        if problems.is_empty() {
+            return Ok(());
+        }
+        anyhow::bail!(
+            "invalid runtime configuration:\n  - {}",
+            problems.join("\n  - ")
+        )
+    }
+
+    /// 解析 runtime 二进制：含路径分隔符时直接检查，否则在 PATH 中查找
+    fn resolve_runtime_binary(runtime_path: &Path) -> std::result::Result<PathBuf, String> {
+        if runtime_path.as_os_str().is_empty() {
+            return Err("runtime_path is empty".to_string());
+        }
+        let is_executable = |path: &Path| {
+            path.is_file() && nix::unistd::access(path, nix::unistd::AccessFlags::X_OK).is_ok()
+        };
+        if runtime_path.components().count() > 1 || runtime_path.is_absolute() {
+            if is_executable(runtime_path) {
+                return Ok(runtime_path.to_path_buf());
+            }
+            return Err(format!(
+                "runtime_path {} is not an executable file",
+                runtime_path.display()
+            ));
+        }
+        std::env::var_os("PATH")
+            .iter()
+            .flat_map(std::env::split_paths)
+            .map(|dir| dir.join(runtime_path))
+            .find(|candidate| is_executable(candidate))
+            .ok_or_else(|| {
+                format!(
+                    "runtime binary {} was not found in PATH",
+                    runtime_path.display()
+                )
+            })
+    }
 }
 
 impl RuntimeServiceImpl {
@@ -62,8 +320,8 @@ impl RuntimeServiceImpl {
         injected_nri: Option<Arc<dyn NriApi>>,
     ) -> Self {
         let nri_manager_config = NriManagerConfig::from(nri_config.clone());
-        let containers = Arc::new(Mutex::new(HashMap::new()));
-        let pod_sandboxes = Arc::new(Mutex::new(HashMap::new()));
+        let containers = Arc::new(RwLock::new(ContainerStore::default()));
+        let pod_sandboxes = Arc::new(RwLock::new(HashMap::new()));
         let mut config = config;
         let mut handlers = Vec::new();
         for handler in &config.runtime_handlers {
@@ -97,19 +355,38 @@ impl RuntimeServiceImpl {
         };
         let resolved_shim_work_dir = shim_config.work_dir.clone();
 
-        let runtime = RuncRuntime::with_shim_and_image_storage(
+        let mut runtime = RuncRuntime::with_shim_and_image_storage(
             config.runtime_path.clone(),
             config.runtime_root.clone(),
-            config.root_dir.join("storage"),
+            config.image_root.clone(),
             shim_config,
         );
+        runtime.set_process_defaults(config.process_defaults.clone());
+        runtime.set_protected_paths(config.protected_paths.clone());
+        runtime.set_dir_mode(config.dir_mode);
+        runtime.set_cgroup_root(config.cgroup_root.as_deref().unwrap_or_default());
+        runtime.set_kill_wait_timeout(config.kill_timeout);
+        runtime.set_rootless(config.rootless.clone().map(|rootless| {
+            crate::rootless::RootlessManager::new(rootless)
+                .expect("Failed to create rootless manager")
+        }));
 
-        let pod_manager = PodSandboxManager::new(
+        let mut pod_manager = PodSandboxManager::new(
             runtime.clone(),
-            config.root_dir.join("pods"),
+            config.state_dir.join("pods"),
             config.pause_image.clone(),
             config.cni_config.clone(),
         );
+        pod_manager.set_default_dns(&config.default_dns);
+        pod_manager.set_pause_binary(config.pause_binary.clone());
+        pod_manager.set_cgroup_root(config.cgroup_root.clone());
+        pod_manager.set_cgroup_mount(config.cgroup_mount.clone());
+        pod_manager.set_rootless_network(
+            config
+                .rootless
+                .as_ref()
+                .map(|rootless| rootless.network_mode.clone()),
+        );
         let persistence_config = PersistenceConfig {
             db_path: config.root_dir.join("crius.db"),
             enable_recovery: true,
@@ -118,7 +395,7 @@ impl RuntimeServiceImpl {
         let persistence = PersistenceManager::new(persistence_config)
             .expect("Failed to create persistence manager");
         let persistence = Arc::new(Mutex::new(persistence));
-        let (events, _) = tokio::sync::broadcast::channel(256);
+        let (events, _) = tokio::sync::broadcast::channel(CONTAINER_EVENTS_BUFFER);
         let nri: Arc<dyn NriApi> = injected_nri.unwrap_or_else(|| {
             if nri_manager_config.enable {
                 Arc::new(NriManager::with_domain(
@@ -161,6 +438,28 @@ impl RuntimeServiceImpl {
             shim_work_dir: resolved_shim_work_dir,
             runtime_network_config: Arc::new(Mutex::new(runtime_network_config)),
             exit_monitors: Arc::new(Mutex::new(HashSet::new())),
+            tasks: Arc::new(TaskRegistry::default()),
+            container_lifecycle_locks: Arc::new(Mutex::new(HashMap::new())),
+            audit: None,
+            registry_probe: Arc::new(Mutex::new(RegistryProbe::default())),
+            container_stats_cache: Arc::new(Mutex::new(Default::default())),
+            handler_features: Arc::new(std::sync::OnceLock::new()),
+            cgroup_controllers: Arc::new(std::sync::OnceLock::new()),
+        }
+    }
+
+    /// 开启变更类请求的审计日志
+    pub fn set_audit_logger(&mut self, logger: Arc<crate::audit::AuditLogger>) {
+        self.audit = Some(logger);
+    }
+
+    pub(super) fn audit<R>(
+        &self,
+        entry: crate::audit::AuditEntry,
+        result: &Result<Response<R>, Status>,
+    ) {
+        if let Some(audit) = &self.audit {
+            audit.record(entry, result);
         }
     }
 
@@ -190,4 +489,16 @@ impl RuntimeServiceImpl {
         let mut streaming = self.streaming.lock().await;
         *streaming = Some(streaming_server);
     }
+
+    /// 启动 CNI 配置热加载；未配置监听间隔或首次加载失败时返回 `None`
+    pub async fn start_cni_config_watcher(&self) -> Option<tokio::task::JoinHandle<()>> {
+        let mut pod_manager = self.pod_manager.lock().await;
+        match pod_manager.start_cni_config_watcher().await {
+            Ok(handle) => handle,
+            Err(e) => {
+                log::error!("Failed to start CNI config watcher: {}", e);
+                None
+            }
+        }
+    }
 }
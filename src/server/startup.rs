@@ -0,0 +1,82 @@
+//! 启动检查
+//!
+//! 开始监听 CRI 端点前等待运行时二进制、CNI 配置、镜像存储与 pause 镜像就绪（缺少 pause 镜像时
+//! 自动拉取），避免 kubelet 注册一个永远无法运行 Pod 的节点。
+
+use super::*;
+use crate::config::StartupConfig;
+use crate::image::ImageServiceImpl;
+use std::time::Duration;
+
+/// 未通过检查时的重试间隔
+const STARTUP_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+impl RuntimeServiceImpl {
+    /// 执行启用的检查，返回未通过项的诊断信息
+    async fn failed_startup_checks(
+        &self,
+        config: &StartupConfig,
+        image_service: &ImageServiceImpl,
+    ) -> Vec<String> {
+        let mut failures = Vec::new();
+        if config.require_runtime {
+            let (ready, reason, message) = self.runtime_readiness();
+            if !ready {
+                failures.push(format!("runtime: {} ({})", message, reason));
+            }
+        }
+        if config.require_cni && self.config.rootless.is_none() {
+            let (ready, reason, message) = self.network_health();
+            if !ready {
+                failures.push(format!("cni: {} ({})", message, reason));
+            }
+        }
+        if config.require_image_store {
+            let root = &self.config.image_root;
+            let (ready, reason, message) =
+                Self::image_store_condition(root, Self::probe_image_store(root));
+            if !ready {
+                failures.push(format!("image store: {} ({})", message, reason));
+            }
+        }
+        // pause 镜像不在本地时在这里拉取，拉取失败的原因随其他失败项一起报告
+        if config.require_pause_image && self.config.pause_binary.is_none() {
+            if let Err(status) = image_service
+                .ensure_local_image(&self.config.pause_image)
+                .await
+            {
+                failures.push(format!(
+                    "pause image: {} is not in the local image store and could not be pulled: {}",
+                    self.config.pause_image,
+                    status.message()
+                ));
+            }
+        }
+        failures
+    }
+
+    /// 等待启用的启动检查全部通过，超时后返回列出全部失败项的错误
+    pub async fn wait_for_startup_checks(
+        &self,
+        config: &StartupConfig,
+        image_service: &ImageServiceImpl,
+    ) -> anyhow::Result<()> {
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(config.timeout_secs);
+        loop {
+            let failures = self.failed_startup_checks(config, image_service).await;
+            if failures.is_empty() {
+                return Ok(());
+            }
+            let now = tokio::time::Instant::now();
+            if now >= deadline {
+                anyhow::bail!(
+                    "startup checks did not pass within {}s: {}",
+                    config.timeout_secs,
+                    failures.join("; ")
+                );
+            }
+            log::warn!("Waiting for startup dependencies: {}", failures.join("; "));
+            tokio::time::sleep(STARTUP_CHECK_INTERVAL.min(deadline - now)).await;
+        }
+    }
+}
@@ -1,6 +1,152 @@
 use super::*;
 
+/// ListContainerStats 全量扫描的缓存
+#[derive(Debug, Default)]
+pub(super) struct ContainerStatsCache {
+    /// 最近一次扫描完成的时间
+    scanned_at: Option<std::time::Instant>,
+    stats: Arc<Vec<crate::proto::runtime::v1::ContainerStats>>,
+    /// 已执行的全量扫描次数
+    pub(super) scans: u64,
+}
+
+/// 节点级资源汇总，对应 kubelet summary API 在运行时层的视图
+#[derive(Debug, Clone, Default)]
+pub struct NodeSummary {
+    pub timestamp: i64,
+    /// 所有 Pod 的 CPU 累计用量之和
+    pub cpu_usage_core_nano_seconds: u64,
+    /// 所有 Pod 的内存 working set 之和
+    pub memory_working_set_bytes: u64,
+    /// 所有容器可写层占用
+    pub writable_layer_bytes: u64,
+    pub writable_layer_inodes: u64,
+    /// 镜像存储占用
+    pub image_fs: Option<crate::proto::runtime::v1::FilesystemUsage>,
+    /// 逐 Pod 明细，与 ListPodSandboxStats 一致
+    pub pods: Vec<crate::proto::runtime::v1::PodSandboxStats>,
+}
+
+impl NodeSummary {
+    /// `crius summary` 输出的 JSON，逐 Pod 只保留 CPU 与内存用量
+    pub fn to_json(&self) -> serde_json::Value {
+        let value = |v: Option<&crate::proto::runtime::v1::UInt64Value>| v.map(|v| v.value);
+        let pods: Vec<serde_json::Value> = self
+            .pods
+            .iter()
+            .map(|pod| {
+                let attributes = pod.attributes.as_ref();
+                let metadata = attributes.and_then(|attributes| attributes.metadata.as_ref());
+                let linux = pod.linux.as_ref();
+                serde_json::json!({
+                    "id": attributes.map(|attributes| attributes.id.as_str()),
+                    "name": metadata.map(|metadata| metadata.name.as_str()),
+                    "namespace": metadata.map(|metadata| metadata.namespace.as_str()),
+                    "cpu_usage_core_nano_seconds": value(
+                        linux
+                            .and_then(|linux| linux.cpu.as_ref())
+                            .and_then(|cpu| cpu.usage_core_nano_seconds.as_ref())
+                    ),
+                    "memory_working_set_bytes": value(
+                        linux
+                            .and_then(|linux| linux.memory.as_ref())
+                            .and_then(|memory| memory.working_set_bytes.as_ref())
+                    ),
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "timestamp": self.timestamp,
+            "cpu_usage_core_nano_seconds": self.cpu_usage_core_nano_seconds,
+            "memory_working_set_bytes": self.memory_working_set_bytes,
+            "writable_layer_bytes": self.writable_layer_bytes,
+            "writable_layer_inodes": self.writable_layer_inodes,
+            "image_fs": self.image_fs.as_ref().map(|fs| serde_json::json!({
+                "mountpoint": fs.fs_id.as_ref().map(|id| id.mountpoint.as_str()),
+                "used_bytes": value(fs.used_bytes.as_ref()),
+                "inodes_used": value(fs.inodes_used.as_ref()),
+            })),
+            "pods": pods,
+        })
+    }
+}
+
 impl RuntimeServiceImpl {
+    /// 汇总节点上全部 Pod/容器及镜像存储的资源用量
+    ///
+    /// 只在复制 Pod 列表时持有锁，逐个 Pod 采集时各自短暂加锁。
+    pub async fn node_summary(&self) -> NodeSummary {
+        use crate::proto::runtime::v1::{FilesystemIdentifier, FilesystemUsage, UInt64Value};
+
+        self.best_effort_refresh_runtime_state().await;
+        let pods: Vec<(String, crate::proto::runtime::v1::PodSandbox)> = {
+            let pods = self.pod_sandboxes.read().await;
+            pods.iter()
+                .map(|(pod_id, pod)| (pod_id.clone(), pod.clone()))
+                .collect()
+        };
+
+        let mut summary = NodeSummary {
+            timestamp: Self::now_nanos(),
+            ..Default::default()
+        };
+        for (pod_id, pod) in pods {
+            let Some(stats) = self.collect_pod_stats(&pod_id, &pod).await else {
+                continue;
+            };
+            if let Some(linux) = &stats.linux {
+                let cpu = linux
+                    .cpu
+                    .as_ref()
+                    .and_then(|cpu| cpu.usage_core_nano_seconds.as_ref());
+                let memory = linux
+                    .memory
+                    .as_ref()
+                    .and_then(|memory| memory.working_set_bytes.as_ref());
+                summary.cpu_usage_core_nano_seconds = summary
+                    .cpu_usage_core_nano_seconds
+                    .saturating_add(cpu.map(|value| value.value).unwrap_or(0));
+                summary.memory_working_set_bytes = summary
+                    .memory_working_set_bytes
+                    .saturating_add(memory.map(|value| value.value).unwrap_or(0));
+                for layer in linux
+                    .containers
+                    .iter()
+                    .filter_map(|container| container.writable_layer.as_ref())
+                {
+                    summary.writable_layer_bytes = summary
+                        .writable_layer_bytes
+                        .saturating_add(layer.used_bytes.as_ref().map(|v| v.value).unwrap_or(0));
+                    summary.writable_layer_inodes = summary
+                        .writable_layer_inodes
+                        .saturating_add(layer.inodes_used.as_ref().map(|v| v.value).unwrap_or(0));
+                }
+            }
+            summary.pods.push(stats);
+        }
+
+        let images_dir = self.config.image_root.join("images");
+        match Self::collect_path_usage(&images_dir) {
+            Ok((used_bytes, inodes_used)) => {
+                summary.image_fs = Some(FilesystemUsage {
+                    timestamp: summary.timestamp,
+                    fs_id: Some(FilesystemIdentifier {
+                        mountpoint: images_dir.display().to_string(),
+                    }),
+                    used_bytes: Some(UInt64Value { value: used_bytes }),
+                    inodes_used: Some(UInt64Value { value: inodes_used }),
+                });
+            }
+            Err(e) => log::warn!(
+                "Failed to collect image filesystem usage from {}: {}",
+                images_dir.display(),
+                e
+            ),
+        }
+
+        summary
+    }
+
     pub(super) fn convert_to_proto_container_stats(
         &self,
         stats: crate::metrics::ContainerStats,
@@ -12,7 +158,9 @@ impl RuntimeServiceImpl {
         let writable_layer = self.container_writable_layer_usage(&stats.container_id);
         let swap = stats.memory.as_ref().map(|mem| SwapUsage {
             timestamp: stats.timestamp as i64,
-            swap_available_bytes: None,
+            swap_available_bytes: mem.swap_limit.map(|limit| UInt64Value {
+                value: limit.saturating_sub(mem.swap),
+            }),
             swap_usage_bytes: Some(UInt64Value { value: mem.swap }),
         });
 
@@ -58,7 +206,7 @@ impl RuntimeServiceImpl {
             id: container.id.clone(),
             metadata: container.metadata.clone(),
             labels: container.labels.clone(),
-            annotations: Self::external_annotations(&container.annotations),
+            annotations: Self::reported_annotations(&container.annotations),
         });
     }
 
@@ -72,7 +220,6 @@ impl RuntimeServiceImpl {
             PodSandboxAttributes, PodSandboxStats, ProcessUsage, UInt64Value,
         };
 
-        let containers = self.containers.lock().await;
         let mut total_cpu_usage = 0u64;
         let mut total_memory_usage = 0u64;
         let mut total_memory_limit = 0u64;
@@ -87,44 +234,53 @@ impl RuntimeServiceImpl {
         let collector = MetricsCollector::new().ok()?;
         let pod_uid = pod.metadata.as_ref().map(|m| m.uid.clone());
 
-        for (container_id, container) in containers.iter() {
-            let belongs_to_pod = container.pod_sandbox_id == pod_id
-                || pod_uid
-                    .as_ref()
-                    .and_then(|uid| {
-                        container
-                            .annotations
-                            .get("io.kubernetes.pod.uid")
-                            .map(|container_uid| container_uid == uid)
-                    })
-                    .unwrap_or(false);
-
-            if belongs_to_pod {
-                let cgroup_parent = self.container_cgroup_hint(container_id, container).await;
+        // 先复制出属于该 Pod 的容器，读取 cgroup/procfs 时不持有容器表锁
+        let pod_containers: Vec<Container> = {
+            let containers = self.containers.read().await;
+            containers
+                .values()
+                .filter(|container| {
+                    container.pod_sandbox_id == pod_id
+                        || pod_uid
+                            .as_ref()
+                            .and_then(|uid| {
+                                container
+                                    .annotations
+                                    .get("io.kubernetes.pod.uid")
+                                    .map(|container_uid| container_uid == uid)
+                            })
+                            .unwrap_or(false)
+                })
+                .cloned()
+                .collect()
+        };
 
-                if let Ok(stats) = collector.collect_container_stats(container_id, &cgroup_parent) {
-                    if let Some(ref cpu) = stats.cpu {
-                        total_cpu_usage += cpu.usage_total;
-                    }
-                    if let Some(ref mem) = stats.memory {
-                        total_memory_usage += mem.usage;
-                        total_memory_limit += mem.limit;
-                    }
-                    if let Some(ref pids) = stats.pids {
-                        total_pids += pids.current;
-                    }
-                    if let Some(network) = self.container_network_stats(container_id).await {
-                        total_rx_bytes = total_rx_bytes.saturating_add(network.rx_bytes);
-                        total_rx_errors = total_rx_errors.saturating_add(network.rx_errors);
-                        total_tx_bytes = total_tx_bytes.saturating_add(network.tx_bytes);
-                        total_tx_errors = total_tx_errors.saturating_add(network.tx_errors);
-                    }
-                    has_stats = true;
+        for container in &pod_containers {
+            let container_id = &container.id;
+            let cgroup_parent = self.container_cgroup_hint(container_id, container).await;
 
-                    let mut proto_stats = self.convert_to_proto_container_stats(stats);
-                    Self::populate_container_stats_attributes(&mut proto_stats, container);
-                    container_stats_list.push(proto_stats);
+            if let Ok(stats) = collector.collect_container_stats(container_id, &cgroup_parent) {
+                if let Some(ref cpu) = stats.cpu {
+                    total_cpu_usage += cpu.usage_total;
+                }
+                if let Some(ref mem) = stats.memory {
+                    total_memory_usage += mem.usage;
+                    total_memory_limit += mem.limit;
                 }
+                if let Some(ref pids) = stats.pids {
+                    total_pids += pids.current;
+                }
+                if let Some(network) = self.container_network_stats(container_id).await {
+                    total_rx_bytes = total_rx_bytes.saturating_add(network.rx_bytes);
+                    total_rx_errors = total_rx_errors.saturating_add(network.rx_errors);
+                    total_tx_bytes = total_tx_bytes.saturating_add(network.tx_bytes);
+                    total_tx_errors = total_tx_errors.saturating_add(network.tx_errors);
+                }
+                has_stats = true;
+
+                let mut proto_stats = self.convert_to_proto_container_stats(stats);
+                Self::populate_container_stats_attributes(&mut proto_stats, container);
+                container_stats_list.push(proto_stats);
             }
         }
 
@@ -144,10 +300,7 @@ impl RuntimeServiceImpl {
             }
         }
 
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_nanos() as i64;
+        let timestamp = Self::now_nanos();
         let container_count = container_stats_list.len() as u64;
 
         Some(PodSandboxStats {
@@ -155,7 +308,7 @@ impl RuntimeServiceImpl {
                 id: pod_id.to_string(),
                 metadata: pod.metadata.clone(),
                 labels: pod.labels.clone(),
-                annotations: Self::external_annotations(&pod.annotations),
+                annotations: Self::reported_annotations(&pod.annotations),
             }),
             linux: Some(LinuxPodSandboxStats {
                 cpu: Some(CpuUsage {
@@ -223,7 +376,7 @@ impl RuntimeServiceImpl {
         let req = request.into_inner();
         let container_id = self.resolve_container_id(&req.container_id).await?;
 
-        let containers = self.containers.lock().await;
+        let containers = self.containers.read().await;
         let container = containers
             .get(&container_id)
             .ok_or_else(|| Status::not_found("Container not found"))?;
@@ -255,6 +408,69 @@ impl RuntimeServiceImpl {
         Ok(Response::new(ContainerStatsResponse { stats }))
     }
 
+    fn container_reports_stats(container: &Container) -> bool {
+        container.state != ContainerState::ContainerExited as i32
+            && container.state != ContainerState::ContainerUnknown as i32
+    }
+
+    /// 采集全部运行中容器的统计。距上次扫描不足 `[stats].scan_interval_ms` 时直接返回缓存；
+    /// 并发调用在缓存锁上等待，请求到达后完成的扫描结果会被共享。容器按批采集，
+    /// 批次之间释放容器表的读锁并让出执行权
+    async fn scan_container_stats(&self) -> Arc<Vec<crate::proto::runtime::v1::ContainerStats>> {
+        let requested_at = std::time::Instant::now();
+        let interval = std::time::Duration::from_millis(self.config.stats.scan_interval_ms);
+        let mut cache = self.container_stats_cache.lock().await;
+        if cache
+            .scanned_at
+            .is_some_and(|scanned_at| scanned_at >= requested_at || scanned_at.elapsed() < interval)
+        {
+            return cache.stats.clone();
+        }
+
+        let ids: Vec<String> = {
+            let containers = self.containers.read().await;
+            containers
+                .values()
+                .filter(|container| Self::container_reports_stats(container))
+                .map(|container| container.id.clone())
+                .collect()
+        };
+        let mut stats = Vec::with_capacity(ids.len());
+        match MetricsCollector::new() {
+            Ok(collector) => {
+                let batch_size = match self.config.stats.batch_size {
+                    0 => ids.len().max(1),
+                    size => size,
+                };
+                for batch in ids.chunks(batch_size) {
+                    let containers = self.containers.read().await;
+                    for container_id in batch {
+                        let Some(container) = containers.get(container_id) else {
+                            continue;
+                        };
+                        let cgroup_parent =
+                            self.container_cgroup_hint(container_id, container).await;
+                        if let Ok(collected) =
+                            collector.collect_container_stats(container_id, &cgroup_parent)
+                        {
+                            let mut proto_stats = self.convert_to_proto_container_stats(collected);
+                            Self::populate_container_stats_attributes(&mut proto_stats, container);
+                            stats.push(proto_stats);
+                        }
+                    }
+                    drop(containers);
+                    tokio::task::yield_now().await;
+                }
+            }
+            Err(e) => log::warn!("Failed to create MetricsCollector: {}", e),
+        }
+
+        cache.scanned_at = Some(std::time::Instant::now());
+        cache.stats = Arc::new(stats);
+        cache.scans += 1;
+        cache.stats.clone()
+    }
+
     pub(super) async fn list_container_stats(
         &self,
         request: Request<ListContainerStatsRequest>,
@@ -287,39 +503,26 @@ impl RuntimeServiceImpl {
             None
         };
 
-        let containers = self.containers.lock().await;
-        let mut all_stats = Vec::new();
-        let collector = match MetricsCollector::new() {
-            Ok(c) => c,
-            Err(e) => {
-                log::warn!("Failed to create MetricsCollector: {}", e);
-                return Ok(Response::new(ListContainerStatsResponse {
-                    stats: Vec::new(),
-                }));
-            }
-        };
-
-        for (container_id, container) in containers.iter() {
-            if matches!(
-                container.state,
-                x if x == ContainerState::ContainerExited as i32
-                    || x == ContainerState::ContainerUnknown as i32
-            ) {
-                continue;
-            }
-            if let Some(filter) = &filter {
-                if !Self::container_matches_stats_filter(container, filter) {
-                    continue;
-                }
-            }
-
-            let cgroup_parent = self.container_cgroup_hint(container_id, container).await;
-            if let Ok(stats) = collector.collect_container_stats(container_id, &cgroup_parent) {
-                let mut proto_stats = self.convert_to_proto_container_stats(stats);
-                Self::populate_container_stats_attributes(&mut proto_stats, container);
-                all_stats.push(proto_stats);
-            }
-        }
+        let scanned = self.scan_container_stats().await;
+        let containers = self.containers.read().await;
+        let all_stats = scanned
+            .iter()
+            .filter(|stats| {
+                // 缓存期间退出或删除的容器不再返回
+                let Some(container) = stats
+                    .attributes
+                    .as_ref()
+                    .and_then(|attributes| containers.get(&attributes.id))
+                else {
+                    return false;
+                };
+                Self::container_reports_stats(container)
+                    && filter.as_ref().is_none_or(|filter| {
+                        Self::container_matches_stats_filter(container, filter)
+                    })
+            })
+            .cloned()
+            .collect();
 
         Ok(Response::new(ListContainerStatsResponse {
             stats: all_stats,
@@ -334,7 +537,7 @@ impl RuntimeServiceImpl {
         let req = request.into_inner();
         let pod_id = self.resolve_pod_sandbox_id(&req.pod_sandbox_id).await?;
 
-        let pods = self.pod_sandboxes.lock().await;
+        let pods = self.pod_sandboxes.read().await;
         let pod = pods
             .get(&pod_id)
             .ok_or_else(|| Status::not_found("Pod sandbox not found"))?;
@@ -365,7 +568,7 @@ impl RuntimeServiceImpl {
         };
 
         let pods: Vec<(String, crate::proto::runtime::v1::PodSandbox)> = {
-            let pods = self.pod_sandboxes.lock().await;
+            let pods = self.pod_sandboxes.read().await;
             pods.iter()
                 .map(|(pod_id, pod)| (pod_id.clone(), pod.clone()))
                 .collect()
@@ -447,16 +650,12 @@ impl RuntimeServiceImpl {
         use crate::proto::runtime::v1::{
             ContainerMetrics, Metric, MetricType, PodSandboxMetrics, UInt64Value,
         };
-        use std::time::{SystemTime, UNIX_EPOCH};
 
-        let pods = self.pod_sandboxes.lock().await;
-        let containers = self.containers.lock().await;
+        let pods = self.pod_sandboxes.read().await;
+        let containers = self.containers.read().await;
         let mut pod_metrics_list = Vec::new();
 
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_nanos() as i64;
+        let timestamp = Self::now_nanos();
 
         for (pod_id, pod) in pods.iter() {
             let mut metrics = Vec::new();
@@ -168,6 +168,19 @@ impl RuntimeServiceImpl {
             }
         }
 
+        // rootless 模式下控制器通常未委派，runc 会忽略资源设置，不因此判定未就绪
+        let controllers = self.runtime_cgroup_controllers();
+        if !controllers.missing.is_empty() && self.config.rootless.is_none() {
+            return (
+                false,
+                "CgroupControllersMissing".to_string(),
+                format!(
+                    "required cgroup controllers are not available to crius: {}; resource limits and stats would not work",
+                    controllers.missing.join(", ")
+                ),
+            );
+        }
+
         let version = self
             .runtime_binary_version()
             .unwrap_or_else(|| env!("CARGO_PKG_VERSION").to_string());
@@ -232,7 +245,10 @@ impl RuntimeServiceImpl {
             "containerEvents": true,
             "reopenContainerLog": true,
             "updateContainerResources": true,
-            "checkpointContainer": true,
+            "checkpointContainer": self
+                .runtime_handler_features()
+                .get(&self.config.runtime)
+                .is_some_and(|features| features.supports_checkpoint()),
         })
     }
 
@@ -328,6 +344,135 @@ impl RuntimeServiceImpl {
         }
     }
 
+    /// ImageReady 条件：镜像存储可写，且配置的 registry 可达
+    pub(super) async fn image_health(&self) -> (bool, String, String) {
+        let root = &self.config.image_root;
+        let (ready, reason, message) =
+            Self::image_store_condition(root, Self::probe_image_store(root));
+        if !ready {
+            return (ready, reason, message);
+        }
+        let Some(registry) = self.config.image_health_registry.as_deref() else {
+            return (ready, reason, message);
+        };
+        match self.registry_reachability(registry).await {
+            Ok(()) => (
+                true,
+                reason,
+                format!("{}; registry {} is reachable", message, registry),
+            ),
+            Err(err) => (
+                false,
+                "RegistryUnreachable".to_string(),
+                format!("registry {} is unreachable: {}", registry, err),
+            ),
+        }
+    }
+
+    /// 在镜像存储根目录创建并删除一个探测文件；只读或已满的文件系统直接返回对应错误
+    pub(super) fn probe_image_store(root: &Path) -> std::io::Result<()> {
+        let stat = nix::sys::statvfs::statvfs(root)?;
+        if stat.flags().contains(nix::sys::statvfs::FsFlags::ST_RDONLY) {
+            return Err(std::io::Error::from_raw_os_error(
+                nix::errno::Errno::EROFS as i32,
+            ));
+        }
+        if stat.blocks_available() == 0 {
+            return Err(std::io::Error::from_raw_os_error(
+                nix::errno::Errno::ENOSPC as i32,
+            ));
+        }
+        let probe = root.join(".crius-health-check");
+        let written = std::fs::write(&probe, b"ok");
+        let _ = std::fs::remove_file(&probe);
+        written
+    }
+
+    pub(super) fn image_store_condition(
+        root: &Path,
+        probe: std::io::Result<()>,
+    ) -> (bool, String, String) {
+        let err = match probe {
+            Ok(()) => {
+                return (
+                    true,
+                    "ImageIsReady".to_string(),
+                    format!("image store {} is writable", root.display()),
+                )
+            }
+            Err(err) => err,
+        };
+        let errno = err.raw_os_error().map(nix::errno::Errno::from_i32);
+        let reason = match errno {
+            Some(nix::errno::Errno::EROFS) => "ImageStoreReadOnly",
+            Some(nix::errno::Errno::ENOSPC) | Some(nix::errno::Errno::EDQUOT) => "ImageStoreFull",
+            _ => "ImageStoreNotWritable",
+        };
+        (
+            false,
+            reason.to_string(),
+            format!(
+                "image store {} is not writable, image pulls will fail: {}",
+                root.display(),
+                err
+            ),
+        )
+    }
+
+    /// 请求 registry 的 `/v2/`，收到任何 HTTP 响应（包括 401）即视为可达；结果按配置缓存
+    ///
+    /// 探测在后台任务中进行，不持有缓存锁。缓存过期后先返回上次结果并在后台刷新，
+    /// 只有从未探测过时才等待本次结果，Status 不会因 registry 缓慢而阻塞。
+    async fn registry_reachability(&self, registry: &str) -> Result<(), String> {
+        let stale = {
+            let mut probe = self.registry_probe.lock().await;
+            if let Some((checked_at, result)) = probe.last.as_ref() {
+                if checked_at.elapsed() < self.config.image_health_interval || probe.in_flight {
+                    return result.clone();
+                }
+            }
+            probe.in_flight = true;
+            probe.last.as_ref().map(|(_, result)| result.clone())
+        };
+
+        let url = if registry.contains("://") {
+            format!("{}/v2/", registry.trim_end_matches('/'))
+        } else {
+            format!("https://{}/v2/", registry.trim_end_matches('/'))
+        };
+        let cache = self.registry_probe.clone();
+        let refresh = tokio::spawn(async move {
+            let result = Self::probe_registry(&url).await;
+            let mut probe = cache.lock().await;
+            probe.last = Some((std::time::Instant::now(), result.clone()));
+            probe.in_flight = false;
+            result
+        });
+        match stale {
+            Some(result) => result,
+            None => refresh.await.unwrap_or_else(|e| Err(e.to_string())),
+        }
+    }
+
+    async fn probe_registry(url: &str) -> Result<(), String> {
+        let result = match reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(5))
+            .build()
+        {
+            Ok(client) => client
+                .get(url)
+                .send()
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(err) = &result {
+            log::warn!("Registry health check {} failed: {}", url, err);
+        }
+        result
+    }
+
     pub(super) fn container_matches_filter(
         container: &Container,
         filter: &crate::proto::runtime::v1::ContainerFilter,
@@ -398,7 +543,7 @@ impl RuntimeServiceImpl {
         let req = request.into_inner();
         let actual_container_id = self.resolve_container_id(&req.container_id).await?;
         let container = {
-            let containers = self.containers.lock().await;
+            let containers = self.containers.read().await;
             containers.get(&actual_container_id).cloned()
         }
         .ok_or_else(|| Status::not_found("Container not found"))?;
@@ -454,9 +599,10 @@ impl RuntimeServiceImpl {
         } else {
             None
         };
-        let containers = self.containers.lock().await;
+        // 只在持锁期间取共享快照，响应中只复制对外可见的字段，内部状态注解不随每次轮询深拷贝
+        let containers = self.containers.read().await.snapshot();
         let pod_meta_by_id: HashMap<String, (String, String, String)> = {
-            let pod_sandboxes = self.pod_sandboxes.lock().await;
+            let pod_sandboxes = self.pod_sandboxes.read().await;
             pod_sandboxes
                 .iter()
                 .map(|(id, pod)| {
@@ -474,22 +620,27 @@ impl RuntimeServiceImpl {
 
         let runtime = self.runtime.clone();
         let containers_list = containers
-            .values()
-            .cloned()
-            .map(|mut c| {
-                if c.metadata.is_none() {
-                    c.metadata = Some(ContainerMetadata {
-                        name: c.id.clone(),
-                        attempt: 1,
-                    });
-                }
-                if c.image.is_none() {
-                    c.image = Some(ImageSpec {
-                        image: c.image_ref.clone(),
+            .iter()
+            .map(|container| {
+                let mut c = Container {
+                    id: container.id.clone(),
+                    pod_sandbox_id: container.pod_sandbox_id.clone(),
+                    metadata: Some(container.metadata.clone().unwrap_or_else(|| {
+                        ContainerMetadata {
+                            name: container.id.clone(),
+                            attempt: DEFAULT_METADATA_ATTEMPT,
+                        }
+                    })),
+                    image: Some(container.image.clone().unwrap_or_else(|| ImageSpec {
+                        image: container.image_ref.clone(),
                         ..Default::default()
-                    });
-                }
-                c.annotations = Self::external_annotations(&c.annotations);
+                    })),
+                    image_ref: container.image_ref.clone(),
+                    state: container.state,
+                    created_at: container.created_at,
+                    labels: container.labels.clone(),
+                    annotations: Self::reported_annotations(&container.annotations),
+                };
                 if let Some((pod_name, pod_namespace, pod_uid)) =
                     pod_meta_by_id.get(&c.pod_sandbox_id)
                 {
@@ -539,6 +690,7 @@ impl RuntimeServiceImpl {
         let req = request.into_inner();
         let (runtime_ready, runtime_reason, runtime_message) = self.runtime_readiness();
         let (network_ready, network_reason, network_message) = self.network_health();
+        let (image_ready, image_reason, image_message) = self.image_health().await;
         let info = if req.verbose {
             let runtime_network_config = self.runtime_network_config.lock().await.clone();
             let payload = json!({
@@ -548,6 +700,8 @@ impl RuntimeServiceImpl {
                     .unwrap_or_else(|| env!("CARGO_PKG_VERSION").to_string()),
                 "runtimeApiVersion": "v1",
                 "rootDir": self.config.root_dir.display().to_string(),
+                "stateDir": self.config.state_dir.display().to_string(),
+                "imageRoot": self.config.image_root.display().to_string(),
                 "runtime": self.config.runtime.clone(),
                 "runtimePath": self.config.runtime_path.display().to_string(),
                 "runtimeRoot": self.config.runtime_root.display().to_string(),
@@ -555,6 +709,9 @@ impl RuntimeServiceImpl {
                 "pauseImage": self.config.pause_image.clone(),
                 "runtimeHandlers": self.config.runtime_handlers.clone(),
                 "runtimeFeatures": self.runtime_feature_flags(),
+                "runtimeHandlerFeatures": self.runtime_handler_features(),
+                "cniConfigDirs": self.config.cni_config.config_dirs(),
+                "cniPluginDirs": self.config.cni_config.plugin_dirs(),
                 "runtimeNetworkConfig": runtime_network_config.as_ref().map(|cfg| {
                     json!({
                         "podCIDR": cfg.pod_cidr,
@@ -562,7 +719,13 @@ impl RuntimeServiceImpl {
                 }),
                 "networkReady": network_ready,
                 "networkReason": network_reason.clone(),
+                "imageReady": image_ready,
+                "imageReason": image_reason.clone(),
                 "cgroupDriver": self.cgroup_driver().as_str_name(),
+                "cgroupRoot": self.config.cgroup_root.clone(),
+                "cgroupControllers": self.runtime_cgroup_controllers(),
+                "backgroundTasks": self.tasks.active_tasks(),
+                "creationMetrics": crate::metrics::creation_metrics().snapshot(),
                 "recovery": {
                     "enabled": true,
                     "startupReconcile": true,
@@ -596,6 +759,12 @@ impl RuntimeServiceImpl {
                         reason: network_reason,
                         message: network_message,
                     },
+                    RuntimeCondition {
+                        r#type: "ImageReady".to_string(),
+                        status: image_ready,
+                        reason: image_reason,
+                        message: image_message,
+                    },
                 ],
             }),
             info,
@@ -610,7 +779,7 @@ impl RuntimeServiceImpl {
         let req = request.into_inner();
         let resolved_id = self.resolve_pod_sandbox_id(&req.pod_sandbox_id).await?;
         let pod_sandbox = {
-            let pod_sandboxes = self.pod_sandboxes.lock().await;
+            let pod_sandboxes = self.pod_sandboxes.read().await;
             pod_sandboxes.get(&resolved_id).cloned()
         }
         .ok_or_else(|| Status::not_found("Pod sandbox not found"))?;
@@ -649,7 +818,7 @@ impl RuntimeServiceImpl {
         } else {
             None
         };
-        let pod_sandboxes = self.pod_sandboxes.lock().await;
+        let pod_sandboxes = self.pod_sandboxes.read().await;
         let items = pod_sandboxes
             .values()
             .cloned()
@@ -658,7 +827,7 @@ impl RuntimeServiceImpl {
                     &p.annotations,
                     INTERNAL_POD_STATE_KEY,
                 );
-                p.annotations = Self::external_annotations(&p.annotations);
+                p.annotations = Self::reported_annotations(&p.annotations);
                 if p.runtime_handler.is_empty() {
                     p.runtime_handler = pod_state
                         .as_ref()
@@ -666,6 +835,8 @@ impl RuntimeServiceImpl {
                         .filter(|handler| !handler.is_empty())
                         .unwrap_or_else(|| self.config.runtime.clone());
                 }
+                p.runtime_handler =
+                    Self::reported_runtime_handler(&self.config, &p.runtime_handler);
                 p.created_at = Self::normalize_timestamp_nanos(p.created_at);
                 p
             })
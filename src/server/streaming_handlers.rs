@@ -29,6 +29,110 @@ impl RuntimeServiceImpl {
         )))
     }
 
+    /// 根据容器的 exec 覆盖注解生成 runc exec 选项，未设置的部分由 runc 沿用容器配置：
+    /// `io.crius.exec-user` 解析为 `uid:gid`，`io.crius.exec-cwd` 必须是容器内已存在的目录
+    pub(super) async fn exec_options(&self, container_id: &str) -> Result<ExecOptions, Status> {
+        let annotations = {
+            let containers = self.containers.read().await;
+            containers
+                .get(container_id)
+                .map(|container| container.annotations.clone())
+                .unwrap_or_default()
+        };
+        let user = annotations.get(EXEC_USER_ANNOTATION_KEY);
+        let cwd = annotations.get(EXEC_CWD_ANNOTATION_KEY);
+        let mut options = ExecOptions {
+            env: Self::exec_env(&annotations)?,
+            ..Default::default()
+        };
+        if user.is_none() && cwd.is_none() {
+            return Ok(options);
+        }
+        let rootfs = self.container_rootfs_path(container_id)?;
+        if let Some(raw) = user {
+            let (uid, gid) = Self::resolve_container_user(&rootfs, raw).map_err(|e| {
+                Status::failed_precondition(format!(
+                    "exec user {:?} does not resolve in container {}: {}",
+                    raw, container_id, e
+                ))
+            })?;
+            options.user = Some(format!("{}:{}", uid, gid));
+        }
+        if let Some(cwd) = cwd {
+            if !Self::rootfs_dir_exists(&rootfs, cwd) {
+                return Err(Status::failed_precondition(format!(
+                    "exec working directory {} does not exist in container {}",
+                    cwd, container_id
+                )));
+            }
+            options.cwd = Some(cwd.clone());
+        }
+        Ok(options)
+    }
+
+    /// 按容器内 `/etc/passwd`、`/etc/group` 解析 `user[:group]`；
+    /// 数字 uid 不要求存在 passwd 条目，未指定组时取 passwd 中的主组，否则与 uid 相同
+    fn resolve_container_user(rootfs: &Path, spec: &str) -> Result<(u32, u32), String> {
+        let (user, group) = match spec.split_once(':') {
+            Some((user, group)) => (user, Some(group)),
+            None => (spec, None),
+        };
+        let passwd = Self::read_rootfs_file(rootfs, "etc/passwd");
+        let passwd_entry = passwd.lines().find_map(|line| {
+            let fields: Vec<&str> = line.split(':').collect();
+            if fields.len() < 4 {
+                return None;
+            }
+            let uid = fields[2].parse::<u32>().ok()?;
+            let gid = fields[3].parse::<u32>().ok()?;
+            let matches = match user.parse::<u32>() {
+                Ok(numeric) => uid == numeric,
+                Err(_) => fields[0] == user,
+            };
+            matches.then_some((uid, gid))
+        });
+        let (uid, primary_gid) = match (user.parse::<u32>(), passwd_entry) {
+            (_, Some(entry)) => entry,
+            (Ok(uid), None) => (uid, uid),
+            (Err(_), None) => return Err(format!("no user {:?} in /etc/passwd", user)),
+        };
+        let gid = match group {
+            None => primary_gid,
+            Some(group) => match group.parse::<u32>() {
+                Ok(gid) => gid,
+                Err(_) => Self::read_rootfs_file(rootfs, "etc/group")
+                    .lines()
+                    .find_map(|line| {
+                        let fields: Vec<&str> = line.split(':').collect();
+                        (fields.len() >= 3 && fields[0] == group)
+                            .then(|| fields[2].parse::<u32>().ok())
+                            .flatten()
+                    })
+                    .ok_or_else(|| format!("no group {:?} in /etc/group", group))?,
+            },
+        };
+        Ok((uid, gid))
+    }
+
+    /// 解析 rootfs 内的路径，符号链接逃逸出 rootfs 时视为不存在
+    fn rootfs_path(rootfs: &Path, relative: &str) -> Option<PathBuf> {
+        let root = rootfs.canonicalize().ok()?;
+        root.join(relative.trim_start_matches('/'))
+            .canonicalize()
+            .ok()
+            .filter(|path| path.starts_with(&root))
+    }
+
+    fn read_rootfs_file(rootfs: &Path, relative: &str) -> String {
+        Self::rootfs_path(rootfs, relative)
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .unwrap_or_default()
+    }
+
+    fn rootfs_dir_exists(rootfs: &Path, relative: &str) -> bool {
+        Self::rootfs_path(rootfs, relative).is_some_and(|path| path.is_dir())
+    }
+
     pub(super) fn attach_socket_path(&self, container_id: &str) -> PathBuf {
         self.shim_work_dir.join(container_id).join("attach.sock")
     }
@@ -38,11 +142,13 @@ impl RuntimeServiceImpl {
         request: Request<ExecRequest>,
     ) -> Result<Response<ExecResponse>, Status> {
         let mut req = request.into_inner();
+        StreamingServer::validate_exec_request(&req)?;
         req.container_id = self.resolve_container_id(&req.container_id).await?;
         self.ensure_container_is_streamable(&req.container_id, "exec")
             .await?;
+        let options = self.exec_options(&req.container_id).await?;
         let streaming = self.get_streaming_server().await?;
-        let response = streaming.get_exec(&req).await?;
+        let response = streaming.get_exec_with_options(&req, options).await?;
         Ok(Response::new(response))
     }
 
@@ -63,9 +169,21 @@ impl RuntimeServiceImpl {
 
         self.ensure_container_is_streamable(&container_id, "exec_sync")
             .await?;
+        let options = self.exec_options(&container_id).await?;
 
         let mut command = TokioCommand::new(&self.config.runtime_path);
         command.arg("exec");
+        // 与流式 exec 登记在同一处，停止容器或超时后按 pid 文件杀掉容器内的进程
+        let mut exec_guard = self
+            .streaming
+            .lock()
+            .await
+            .as_ref()
+            .map(|streaming| streaming.exec_process_guard(&container_id));
+        if let Some(guard) = &exec_guard {
+            command.arg("--pid-file").arg(guard.pid_file());
+        }
+        command.args(options.runc_args());
         command.arg(&container_id);
         for arg in &cmd {
             command.arg(arg);
@@ -73,10 +191,15 @@ impl RuntimeServiceImpl {
         command.stdin(Stdio::null());
         command.stdout(Stdio::piped());
         command.stderr(Stdio::piped());
+        // 客户端取消请求时 handler future 被丢弃，确保 runc exec 随之终止并被回收
+        command.kill_on_drop(true);
 
         let mut child = command
             .spawn()
             .map_err(|e| Status::internal(format!("Failed to spawn exec process: {}", e)))?;
+        if let Some(guard) = &exec_guard {
+            guard.watch();
+        }
 
         let stdout_task = child.stdout.take().map(|mut stdout| {
             tokio::spawn(async move {
@@ -114,6 +237,10 @@ impl RuntimeServiceImpl {
                 .map_err(|e| Status::internal(format!("Exec failed: {}", e)))?
         };
 
+        if let Some(guard) = exec_guard.as_mut() {
+            guard.disarm();
+        }
+
         let stdout = match stdout_task {
             Some(task) => task
                 .await
@@ -150,7 +277,7 @@ impl RuntimeServiceImpl {
         }
 
         let pod = {
-            let pod_sandboxes = self.pod_sandboxes.lock().await;
+            let pod_sandboxes = self.pod_sandboxes.read().await;
             pod_sandboxes.get(&pod_id).cloned()
         }
         .ok_or_else(|| Status::not_found("Pod sandbox not found"))?;
@@ -197,13 +324,14 @@ impl RuntimeServiceImpl {
         request: Request<AttachRequest>,
     ) -> Result<Response<AttachResponse>, Status> {
         let mut req = request.into_inner();
+        StreamingServer::validate_attach_request(&req)?;
         req.container_id = self.resolve_container_id(&req.container_id).await?;
         self.ensure_container_is_streamable(&req.container_id, "attach")
             .await?;
         let attach_socket_path = self.attach_socket_path(&req.container_id);
         if !attach_socket_path.exists() {
             let should_try_restore = {
-                let containers = self.containers.lock().await;
+                let containers = self.containers.read().await;
                 containers
                     .get(&req.container_id)
                     .and_then(|container| {
@@ -236,7 +364,7 @@ impl RuntimeServiceImpl {
         }
         if !attach_socket_path.exists() {
             let log_path = {
-                let containers = self.containers.lock().await;
+                let containers = self.containers.read().await;
                 containers
                     .get(&req.container_id)
                     .and_then(|container| {
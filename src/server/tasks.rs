@@ -0,0 +1,119 @@
+use super::*;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// 删除容器/Pod 时等待其后台任务退出的上限
+pub(super) const TASK_SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// 后台任务的取消信号
+#[derive(Clone)]
+pub(super) struct TaskCancellation {
+    receiver: watch::Receiver<bool>,
+}
+
+impl TaskCancellation {
+    /// 是否已请求取消
+    pub(super) fn is_cancelled(&self) -> bool {
+        *self.receiver.borrow()
+    }
+
+    /// 等待取消信号；注册表被释放时同样视为取消
+    pub(super) async fn cancelled(&mut self) {
+        while !*self.receiver.borrow() {
+            if self.receiver.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+struct OwnerTasks {
+    cancel: watch::Sender<bool>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+/// 任务结束（正常返回或被 abort）时递减计数
+struct ActiveGuard(Arc<AtomicUsize>);
+
+impl Drop for ActiveGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// 按容器/Pod 归属登记的后台任务（exit monitor、日志、流等）
+///
+/// 删除容器或 Pod 时先发出取消信号，在超时内等待任务退出，仍未退出的直接 abort。
+#[derive(Default)]
+pub(super) struct TaskRegistry {
+    owners: std::sync::Mutex<HashMap<String, OwnerTasks>>,
+    active: Arc<AtomicUsize>,
+}
+
+impl TaskRegistry {
+    /// 在当前 tokio runtime 上为 `owner` 启动任务，不在 runtime 中时返回 false
+    pub(super) fn spawn<F, Fut>(&self, owner: &str, task: F) -> bool
+    where
+        F: FnOnce(TaskCancellation) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            return false;
+        };
+        let mut owners = self.owners.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = owners
+            .entry(owner.to_string())
+            .or_insert_with(|| OwnerTasks {
+                cancel: watch::channel(false).0,
+                handles: Vec::new(),
+            });
+        entry.handles.retain(|handle| !handle.is_finished());
+
+        let cancellation = TaskCancellation {
+            receiver: entry.cancel.subscribe(),
+        };
+        let future = task(cancellation);
+        self.active.fetch_add(1, Ordering::SeqCst);
+        let guard = ActiveGuard(self.active.clone());
+        entry.handles.push(handle.spawn(async move {
+            let _guard = guard;
+            future.await;
+        }));
+        true
+    }
+
+    /// 取消并回收 `owner` 的全部任务
+    pub(super) async fn shutdown(&self, owner: &str, timeout: std::time::Duration) {
+        let Some(tasks) = self
+            .owners
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(owner)
+        else {
+            return;
+        };
+
+        let _ = tasks.cancel.send(true);
+        let deadline = tokio::time::Instant::now() + timeout;
+        for mut handle in tasks.handles {
+            if tokio::time::timeout_at(deadline, &mut handle)
+                .await
+                .is_err()
+            {
+                log::warn!(
+                    "Aborting background task of {} after shutdown timeout",
+                    owner
+                );
+                handle.abort();
+                let _ = handle.await;
+            }
+        }
+    }
+
+    /// 当前仍在运行的任务数
+    pub(super) fn active_tasks(&self) -> usize {
+        self.active.load(Ordering::SeqCst)
+    }
+}
@@ -17,6 +17,10 @@ use tokio_stream::StreamExt;
 
 fn test_runtime_config(root_dir: PathBuf) -> RuntimeConfig {
     RuntimeConfig {
+        state_dir: root_dir.clone(),
+        image_root: root_dir.join("storage"),
+        cgroup_mount: root_dir.join("cgroup"),
+        sysfs_root: root_dir.join("sys"),
         root_dir,
         runtime: "runc".to_string(),
         runtime_handlers: vec!["runc".to_string(), "kata".to_string()],
@@ -25,6 +29,27 @@ fn test_runtime_config(root_dir: PathBuf) -> RuntimeConfig {
         runtime_path: PathBuf::from("/definitely/missing/runc"),
         pause_image: "registry.k8s.io/pause:3.9".to_string(),
         cni_config: crate::network::CniConfig::default(),
+        process_defaults: Default::default(),
+        reconcile_interval: std::time::Duration::ZERO,
+        env_file_dir: None,
+        protected_paths: Default::default(),
+        mount_policy: Default::default(),
+        container_log: Default::default(),
+        expand_env_references: false,
+        default_dns: Default::default(),
+        dir_mode: crate::utils::DEFAULT_DIR_MODE,
+        fs_group_max_entries: crate::storage::ownership::DEFAULT_FS_GROUP_MAX_ENTRIES,
+        pause_binary: None,
+        shm_size: crate::oci::spec::DEFAULT_SHM_SIZE,
+        prepared_rootfs_dir: None,
+        default_env: Vec::new(),
+        cgroup_root: None,
+        remove_grace_period_secs: 30,
+        kill_timeout: crate::runtime::DEFAULT_KILL_WAIT_TIMEOUT,
+        image_health_registry: None,
+        image_health_interval: std::time::Duration::from_secs(60),
+        rootless: None,
+        stats: Default::default(),
     }
 }
 
@@ -184,6 +209,8 @@ fn sanitize_spec_runtime_resources_clears_unsupported_extended_resources() {
             sysctl: None,
             mount_label: None,
             intel_rdt: None,
+            masked_paths: None,
+            readonly_paths: None,
         }),
         annotations: None,
     };
@@ -412,11 +439,72 @@ case "$cmd" in
     fi
     printf '{{"ociVersion":"1.0.2","id":"%s","status":"%s","pid":%s,"bundle":"%s","rootfs":"%s","created":"2024-01-01T00:00:00Z","owner":"root"}}\n' "$id" "$status" "$pid" "$STATE_DIR/bundle" "$STATE_DIR/rootfs"
     ;;
+  list)
+    printf '['
+    sep=""
+    for file in "$STATE_DIR"/*.state; do
+      [ -f "$file" ] || continue
+      id="$(basename "$file" .state)"
+      printf '%s{{"id":"%s","status":"%s"}}' "$sep" "$id" "$(cat "$file")"
+      sep=","
+    done
+    printf ']\n'
+    ;;
   kill)
     id="${{1:-}}"
+    signal="${{2:-TERM}}"
+    echo "$signal" >> "$STATE_DIR/$id.signals"
+    if [ -f "$STATE_DIR/$id.fail-kill" ]; then
+      echo "kill failed: $(cat "$STATE_DIR/$id.fail-kill")" >&2
+      exit 1
+    fi
+    if [ "$signal" = "TERM" ] && [ -f "$STATE_DIR/$id.ignore-term" ]; then
+      exit 0
+    fi
+    if [ "$signal" = "KILL" ] && [ -f "$STATE_DIR/$id.ignore-kill" ]; then
+      exit 0
+    fi
     echo stopped > "$STATE_DIR/$id.state"
     ;;
+  exec)
+    user="0:0"
+    cwd="/"
+    envs=""
+    pid_file=""
+    while [ $# -gt 0 ]; do
+      case "$1" in
+        --user) user="$2"; shift 2 ;;
+        --cwd) cwd="$2"; shift 2 ;;
+        --env) envs="$envs$2
+"; shift 2 ;;
+        --pid-file) pid_file="$2"; shift 2 ;;
+        -t) shift ;;
+        *) break ;;
+      esac
+    done
+    id="${{1:-}}"
+    echo $$ >> "$STATE_DIR/$id.exec-pids"
+    if [ -f "$STATE_DIR/$id.exec-sleep" ]; then
+      if [ -n "$pid_file" ]; then
+        # 与 runc 一样在 pid 文件中记录容器内进程，自身等待其退出
+        sleep "$(cat "$STATE_DIR/$id.exec-sleep")" &
+        echo $! > "$pid_file"
+        echo $! >> "$STATE_DIR/$id.exec-processes"
+        wait $! || true
+      else
+        sleep "$(cat "$STATE_DIR/$id.exec-sleep")"
+      fi
+    fi
+    case "${{2:-}}" in
+      id) echo "uid=${{user%%:*}} gid=${{user#*:}}" ;;
+      pwd) echo "$cwd" ;;
+      printenv) printf '%s' "$envs" | sed -n "s/^${{3:-}}=//p" ;;
+    esac
+    ;;
   delete)
+    if [ "${{1:-}}" = "--force" ]; then
+      shift
+    fi
     id="${{1:-}}"
     if [ -f "$STATE_DIR/$id.state" ]; then
       rm -f "$STATE_DIR/$id.state" "$STATE_DIR/$id.pid"
@@ -559,6 +647,17 @@ if [ -n "$exit_code_file" ]; then
   mkdir -p "$shim_dir"
   : > "$shim_dir/attach.sock"
   : > "$shim_dir/resize.sock"
+  echo start >> "$shim_dir/starts"
+  # 测试通过标记文件模拟 runc 启动失败或容器退出
+  if [ -f "$shim_dir/fail-start" ]; then
+    cp "$shim_dir/fail-start" "$shim_dir/start_error"
+    echo 128 > "$exit_code_file"
+    exit 1
+  fi
+  if [ -f "$shim_dir/exit-with" ]; then
+    cp "$shim_dir/exit-with" "$exit_code_file"
+    exit 0
+  fi
 fi
 if [ -n "$log_file" ]; then
   mkdir -p "$(dirname "$log_file")"
@@ -583,6 +682,8 @@ fn test_service_with_fake_runtime() -> (TempDir, RuntimeServiceImpl) {
     let shim_work_dir = dir.path().join("shims");
     let config = RuntimeConfig {
         root_dir: dir.path().join("root"),
+        state_dir: dir.path().join("root"),
+        image_root: dir.path().join("root").join("storage"),
         runtime: "runc".to_string(),
         runtime_handlers: vec!["runc".to_string()],
         runtime_root: dir.path().join("runtime-root"),
@@ -590,6 +691,29 @@ fn test_service_with_fake_runtime() -> (TempDir, RuntimeServiceImpl) {
         runtime_path,
         pause_image: "registry.k8s.io/pause:3.9".to_string(),
         cni_config: crate::network::CniConfig::default(),
+        process_defaults: Default::default(),
+        reconcile_interval: std::time::Duration::ZERO,
+        env_file_dir: None,
+        protected_paths: Default::default(),
+        mount_policy: Default::default(),
+        container_log: Default::default(),
+        expand_env_references: false,
+        default_dns: Default::default(),
+        dir_mode: crate::utils::DEFAULT_DIR_MODE,
+        fs_group_max_entries: crate::storage::ownership::DEFAULT_FS_GROUP_MAX_ENTRIES,
+        pause_binary: None,
+        shm_size: crate::oci::spec::DEFAULT_SHM_SIZE,
+        prepared_rootfs_dir: None,
+        default_env: Vec::new(),
+        cgroup_root: None,
+        cgroup_mount: dir.path().join("cgroup"),
+        sysfs_root: dir.path().join("sys"),
+        remove_grace_period_secs: 30,
+        kill_timeout: crate::runtime::DEFAULT_KILL_WAIT_TIMEOUT,
+        image_health_registry: None,
+        image_health_interval: std::time::Duration::from_secs(60),
+        rootless: None,
+        stats: Default::default(),
     };
     let nri_config = NriConfig {
         blockio_config_path: write_blockio_config(&dir).display().to_string(),
@@ -637,6 +761,8 @@ fn test_service_with_fake_runtime_and_nri(
     let shim_work_dir = dir.path().join("shims");
     let config = RuntimeConfig {
         root_dir: dir.path().join("root"),
+        state_dir: dir.path().join("root"),
+        image_root: dir.path().join("root").join("storage"),
         runtime: "runc".to_string(),
         runtime_handlers: vec!["runc".to_string()],
         runtime_root: dir.path().join("runtime-root"),
@@ -644,6 +770,29 @@ fn test_service_with_fake_runtime_and_nri(
         runtime_path,
         pause_image: "registry.k8s.io/pause:3.9".to_string(),
         cni_config: crate::network::CniConfig::default(),
+        process_defaults: Default::default(),
+        reconcile_interval: std::time::Duration::ZERO,
+        env_file_dir: None,
+        protected_paths: Default::default(),
+        mount_policy: Default::default(),
+        container_log: Default::default(),
+        expand_env_references: false,
+        default_dns: Default::default(),
+        dir_mode: crate::utils::DEFAULT_DIR_MODE,
+        fs_group_max_entries: crate::storage::ownership::DEFAULT_FS_GROUP_MAX_ENTRIES,
+        pause_binary: None,
+        shm_size: crate::oci::spec::DEFAULT_SHM_SIZE,
+        prepared_rootfs_dir: None,
+        default_env: Vec::new(),
+        cgroup_root: None,
+        cgroup_mount: dir.path().join("cgroup"),
+        sysfs_root: dir.path().join("sys"),
+        remove_grace_period_secs: 30,
+        kill_timeout: crate::runtime::DEFAULT_KILL_WAIT_TIMEOUT,
+        image_health_registry: None,
+        image_health_interval: std::time::Duration::from_secs(60),
+        rootless: None,
+        stats: Default::default(),
     };
     let nri_config = NriConfig {
         enable: true,
@@ -727,7 +876,7 @@ async fn rollback_failed_pod_sandbox_run_undoes_nri_and_local_state() {
         },
     )
     .unwrap();
-    service.pod_sandboxes.lock().await.insert(
+    service.pod_sandboxes.write().await.insert(
         "pod-rollback".to_string(),
         test_pod("pod-rollback", annotations.clone()),
     );
@@ -759,7 +908,7 @@ async fn rollback_failed_pod_sandbox_run_undoes_nri_and_local_state() {
     );
     assert!(!service
         .pod_sandboxes
-        .lock()
+        .read()
         .await
         .contains_key("pod-rollback"));
     let persistence = service.persistence.lock().await;
@@ -783,10 +932,10 @@ async fn rollback_failed_container_create_undoes_nri_and_local_state() {
 
     service
         .pod_sandboxes
-        .lock()
+        .write()
         .await
         .insert(pod_id.clone(), test_pod(&pod_id, HashMap::new()));
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         container_id.clone(),
         test_container(&container_id, &pod_id, HashMap::new()),
     );
@@ -804,7 +953,7 @@ async fn rollback_failed_container_create_undoes_nri_and_local_state() {
             &HashMap::new(),
         )
         .unwrap();
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         sidecar_id.clone(),
         test_container(&sidecar_id, &pod_id, HashMap::new()),
     );
@@ -858,7 +1007,7 @@ async fn rollback_failed_container_create_undoes_nri_and_local_state() {
             "remove_container"
         ]
     );
-    let containers = service.containers.lock().await;
+    let containers = service.containers.read().await;
     assert!(!containers.contains_key(&container_id));
     assert!(containers.contains_key(&sidecar_id));
     drop(containers);
@@ -898,7 +1047,7 @@ async fn update_pod_sandbox_resources_updates_pause_container_and_notifies_nri()
     )
     .unwrap();
 
-    service.pod_sandboxes.lock().await.insert(
+    service.pod_sandboxes.write().await.insert(
         pod_id.clone(),
         crate::proto::runtime::v1::PodSandbox {
             id: pod_id.clone(),
@@ -933,6 +1082,7 @@ async fn update_pod_sandbox_resources_updates_pause_container_and_notifies_nri()
                 cgroup_parent: None,
                 sysctls: HashMap::new(),
                 namespace_options: None,
+                shm_size: crate::oci::spec::DEFAULT_SHM_SIZE,
                 privileged: false,
                 run_as_user: None,
                 run_as_group: None,
@@ -950,6 +1100,10 @@ async fn update_pod_sandbox_resources_updates_pause_container_and_notifies_nri()
             created_at: 0,
             ip: String::new(),
             network_status: None,
+            cgroup_path: None,
+            creation_timings: Default::default(),
+            shm_path: None,
+            network_helper: None,
         });
     }
     service
@@ -972,10 +1126,15 @@ async fn update_pod_sandbox_resources_updates_pause_container_and_notifies_nri()
 
     let overhead = crate::proto::runtime::v1::LinuxContainerResources {
         cpu_shares: 128,
+        cpu_quota: 10_000,
+        cpu_period: 100_000,
+        memory_limit_in_bytes: 1024,
         ..Default::default()
     };
     let resources = crate::proto::runtime::v1::LinuxContainerResources {
         cpu_shares: 512,
+        cpu_quota: 50_000,
+        cpu_period: 50_000,
         memory_limit_in_bytes: 4096,
         ..Default::default()
     };
@@ -994,8 +1153,19 @@ async fn update_pod_sandbox_resources_updates_pause_container_and_notifies_nri()
     let update_payload: serde_json::Value =
         serde_json::from_slice(&fs::read(fake_runtime_update_path(&dir, &pause_id)).unwrap())
             .unwrap();
-    assert_eq!(update_payload["cpu"]["shares"], 512);
-    assert_eq!(update_payload["memory"]["limit"], 4096);
+    // pod cgroup 的限制为容器资源总和加上 overhead，overhead 配额按 pod 周期换算
+    assert_eq!(update_payload["cpu"]["shares"], 640);
+    assert_eq!(update_payload["cpu"]["quota"], 55_000);
+    assert_eq!(update_payload["cpu"]["period"], 50_000);
+    assert_eq!(update_payload["memory"]["limit"], 5120);
+    let pod_manager_resources = service
+        .pod_manager
+        .lock()
+        .await
+        .get_pod_sandbox(&pod_id)
+        .and_then(|pod| pod.config.linux_resources.clone())
+        .unwrap();
+    assert_eq!(pod_manager_resources.memory_limit_in_bytes, 5120);
     assert_eq!(
         fake_nri.calls.lock().await.clone(),
         vec!["update_pod", "post_update_pod"]
@@ -1003,7 +1173,7 @@ async fn update_pod_sandbox_resources_updates_pause_container_and_notifies_nri()
 
     let updated_pod = service
         .pod_sandboxes
-        .lock()
+        .read()
         .await
         .get(&pod_id)
         .cloned()
@@ -1087,7 +1257,7 @@ async fn update_pod_sandbox_resources_succeeds_when_nri_post_update_fails() {
     )
     .unwrap();
 
-    service.pod_sandboxes.lock().await.insert(
+    service.pod_sandboxes.write().await.insert(
         pod_id.clone(),
         crate::proto::runtime::v1::PodSandbox {
             id: pod_id.clone(),
@@ -1122,6 +1292,7 @@ async fn update_pod_sandbox_resources_succeeds_when_nri_post_update_fails() {
                 cgroup_parent: None,
                 sysctls: HashMap::new(),
                 namespace_options: None,
+                shm_size: crate::oci::spec::DEFAULT_SHM_SIZE,
                 privileged: false,
                 run_as_user: None,
                 run_as_group: None,
@@ -1139,6 +1310,10 @@ async fn update_pod_sandbox_resources_succeeds_when_nri_post_update_fails() {
             created_at: 0,
             ip: String::new(),
             network_status: None,
+            cgroup_path: None,
+            creation_timings: Default::default(),
+            shm_path: None,
+            network_helper: None,
         });
     }
 
@@ -1179,7 +1354,7 @@ async fn nri_pod_event_uses_pause_spec_resources_for_linux_resources() {
     )
     .unwrap();
 
-    service.pod_sandboxes.lock().await.insert(
+    service.pod_sandboxes.write().await.insert(
         pod_id.clone(),
         crate::proto::runtime::v1::PodSandbox {
             id: pod_id.clone(),
@@ -1264,7 +1439,7 @@ async fn nri_create_result_applies_adjustments_and_side_effects() {
         }],
     };
 
-    service.pod_sandboxes.lock().await.insert(
+    service.pod_sandboxes.write().await.insert(
         "pod-create".to_string(),
         test_pod("pod-create", HashMap::new()),
     );
@@ -1281,12 +1456,12 @@ async fn nri_create_result_applies_adjustments_and_side_effects() {
     let evict_container = test_container("container-evict", "pod-create", HashMap::new());
     service
         .containers
-        .lock()
+        .write()
         .await
         .insert("container-update".to_string(), update_container.clone());
     service
         .containers
-        .lock()
+        .write()
         .await
         .insert("container-evict".to_string(), evict_container.clone());
     service
@@ -1356,6 +1531,8 @@ async fn nri_create_result_applies_adjustments_and_side_effects() {
         namespace_paths: NamespacePaths::default(),
         linux_resources: None,
         devices: Vec::new(),
+        shm_size: None,
+        shm_path: None,
         rootfs: dir
             .path()
             .join("root")
@@ -1407,7 +1584,7 @@ async fn nri_create_result_applies_adjustments_and_side_effects() {
 
     let updated = service
         .containers
-        .lock()
+        .read()
         .await
         .get("container-update")
         .cloned()
@@ -1426,7 +1603,7 @@ async fn nri_create_result_applies_adjustments_and_side_effects() {
     );
     let evicted = service
         .containers
-        .lock()
+        .read()
         .await
         .get("container-evict")
         .cloned()
@@ -1434,6 +1611,243 @@ async fn nri_create_result_applies_adjustments_and_side_effects() {
     assert_eq!(evicted.state, ContainerState::ContainerExited as i32);
 }
 
+#[test]
+fn enrich_container_annotations_overrides_runtime_owned_keys() {
+    let mut annotations = HashMap::new();
+    annotations.insert(
+        CRIO_CONTAINER_NAME_ANNOTATION.to_string(),
+        "user-value".to_string(),
+    );
+    annotations.insert(
+        CRIO_CONTAINER_TYPE_ANNOTATION.to_string(),
+        "sandbox".to_string(),
+    );
+    annotations.insert("example.com/owner".to_string(), "team-a".to_string());
+
+    RuntimeServiceImpl::enrich_container_annotations(
+        super::annotations::ContainerAnnotationContext {
+            annotations: &mut annotations,
+            container_id: "container-1",
+            pod_sandbox_id: "pod-1",
+            metadata_name: Some("workload"),
+            requested_image: None,
+            resolved_image_name: None,
+            log_path: None,
+            pod_state: None,
+            default_runtime: "runc",
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        annotations
+            .get(CRIO_CONTAINER_NAME_ANNOTATION)
+            .map(String::as_str),
+        Some("workload")
+    );
+    assert_eq!(
+        annotations
+            .get(CRIO_CONTAINER_TYPE_ANNOTATION)
+            .map(String::as_str),
+        Some(CONTAINER_TYPE_CONTAINER)
+    );
+    assert_eq!(
+        annotations
+            .get(CRIO_CONTAINER_ID_ANNOTATION)
+            .map(String::as_str),
+        Some("container-1")
+    );
+
+    // 对外上报时仍返回用户提供的原值
+    let reported = RuntimeServiceImpl::reported_annotations(&annotations);
+    assert_eq!(
+        reported
+            .get(CRIO_CONTAINER_NAME_ANNOTATION)
+            .map(String::as_str),
+        Some("user-value")
+    );
+    assert_eq!(
+        reported
+            .get(CRIO_CONTAINER_TYPE_ANNOTATION)
+            .map(String::as_str),
+        Some("sandbox")
+    );
+    assert_eq!(
+        reported.get("example.com/owner").map(String::as_str),
+        Some("team-a")
+    );
+    assert!(!reported.contains_key(INTERNAL_SHADOWED_ANNOTATIONS_KEY));
+}
+
+#[tokio::test]
+async fn create_container_ignores_spoofed_runtime_annotations_in_spec() {
+    let (_dir, service) = test_service_with_fake_runtime();
+    service
+        .pod_sandboxes
+        .write()
+        .await
+        .insert("pod-1".to_string(), test_pod("pod-1", HashMap::new()));
+
+    let container_id = RuntimeService::create_container(
+        &service,
+        Request::new(CreateContainerRequest {
+            pod_sandbox_id: "pod-1".to_string(),
+            config: Some(crate::proto::runtime::v1::ContainerConfig {
+                metadata: Some(crate::proto::runtime::v1::ContainerMetadata {
+                    name: "workload".to_string(),
+                    attempt: 0,
+                }),
+                annotations: HashMap::from([
+                    (
+                        CRIO_CONTAINER_ID_ANNOTATION.to_string(),
+                        "spoofed-id".to_string(),
+                    ),
+                    (
+                        CRIO_SANDBOX_ID_ANNOTATION.to_string(),
+                        "other-pod".to_string(),
+                    ),
+                ]),
+                ..Default::default()
+            }),
+            sandbox_config: None,
+        }),
+    )
+    .await
+    .unwrap()
+    .into_inner()
+    .container_id;
+
+    let spec = service.runtime_spec_snapshot(&container_id).unwrap();
+    assert_eq!(
+        spec["annotations"][CRIO_CONTAINER_ID_ANNOTATION],
+        container_id.as_str()
+    );
+    assert_eq!(spec["annotations"][CRIO_SANDBOX_ID_ANNOTATION], "pod-1");
+
+    let status = RuntimeService::container_status(
+        &service,
+        Request::new(ContainerStatusRequest {
+            container_id: container_id.clone(),
+            verbose: false,
+        }),
+    )
+    .await
+    .unwrap()
+    .into_inner()
+    .status
+    .unwrap();
+    assert_eq!(
+        status
+            .annotations
+            .get(CRIO_CONTAINER_ID_ANNOTATION)
+            .map(String::as_str),
+        Some("spoofed-id")
+    );
+    assert_eq!(
+        status
+            .annotations
+            .get(CRIO_SANDBOX_ID_ANNOTATION)
+            .map(String::as_str),
+        Some("other-pod")
+    );
+}
+
+#[tokio::test]
+async fn status_returns_annotations_verbatim_after_recovery() {
+    let (dir, service) = test_service_with_fake_runtime();
+    let annotations: HashMap<String, String> = [
+        ("scheduler.example.com/cpu-hint", "2"),
+        ("example.com/team/owner", "team-a"),
+        ("a.b.c/d.e/f", "value/with/slashes"),
+        ("plain", ""),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect();
+
+    {
+        let mut persistence = service.persistence.lock().await;
+        persistence
+            .save_pod_sandbox(
+                "pod-annotations",
+                "ready",
+                "pod-annotations",
+                "default",
+                "uid-annotations",
+                "",
+                &HashMap::new(),
+                &annotations,
+                None,
+                None,
+            )
+            .unwrap();
+        persistence
+            .save_container(
+                "container-annotations",
+                "pod-annotations",
+                crate::runtime::ContainerStatus::Running,
+                "busybox:latest",
+                &Vec::new(),
+                &HashMap::new(),
+                &annotations,
+            )
+            .unwrap();
+    }
+    set_fake_runtime_state(&dir, "container-annotations", "running");
+
+    service.recover_state().await.unwrap();
+
+    let pod_status = RuntimeService::pod_sandbox_status(
+        &service,
+        Request::new(PodSandboxStatusRequest {
+            pod_sandbox_id: "pod-annotations".to_string(),
+            verbose: false,
+        }),
+    )
+    .await
+    .unwrap()
+    .into_inner()
+    .status
+    .unwrap();
+    assert_eq!(pod_status.annotations, annotations);
+
+    let container_status = RuntimeService::container_status(
+        &service,
+        Request::new(ContainerStatusRequest {
+            container_id: "container-annotations".to_string(),
+            verbose: false,
+        }),
+    )
+    .await
+    .unwrap()
+    .into_inner()
+    .status
+    .unwrap();
+    assert_eq!(container_status.annotations, annotations);
+}
+
+#[tokio::test]
+async fn run_pod_sandbox_rejects_reserved_internal_annotations() {
+    let (_dir, service) = test_service_with_fake_runtime();
+    let mut annotations = HashMap::new();
+    annotations.insert(INTERNAL_POD_STATE_KEY.to_string(), "{}".to_string());
+
+    let err = RuntimeService::run_pod_sandbox(
+        &service,
+        Request::new(RunPodSandboxRequest {
+            config: Some(crate::proto::runtime::v1::PodSandboxConfig {
+                annotations,
+                ..Default::default()
+            }),
+            runtime_handler: String::new(),
+        }),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    assert!(service.pod_sandboxes.read().await.is_empty());
+}
+
 #[test]
 fn enrich_container_annotations_adds_upstream_runtime_metadata() {
     let mut annotations = HashMap::new();
@@ -1456,7 +1870,8 @@ fn enrich_container_annotations_adds_upstream_runtime_metadata() {
             pod_state: Some(&pod_state),
             default_runtime: "fallback-runtime",
         },
-    );
+    )
+    .unwrap();
 
     assert_eq!(
         annotations.get("existing").map(String::as_str),
@@ -1903,6 +2318,8 @@ fn build_nri_container_from_proto_preserves_spec_cgroups_path() {
         sysctl: None,
         mount_label: None,
         intel_rdt: None,
+        masked_paths: None,
+        readonly_paths: None,
     });
     service
         .runtime
@@ -2269,11 +2686,11 @@ fn seccomp_profile_from_proto_supports_localhost_and_unconfined() {
 #[tokio::test]
 async fn resolve_container_id_handles_zero_single_and_multiple_matches() {
     let service = test_service();
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "abcdef123456".to_string(),
         test_container("abcdef123456", "pod-1", HashMap::new()),
     );
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "abc999999999".to_string(),
         test_container("abc999999999", "pod-1", HashMap::new()),
     );
@@ -2303,11 +2720,11 @@ async fn resolve_container_id_handles_zero_single_and_multiple_matches() {
 #[tokio::test]
 async fn resolve_pod_sandbox_id_handles_zero_single_and_multiple_matches() {
     let service = test_service();
-    service.pod_sandboxes.lock().await.insert(
+    service.pod_sandboxes.write().await.insert(
         "podabcdef123456".to_string(),
         test_pod("podabcdef123456", HashMap::new()),
     );
-    service.pod_sandboxes.lock().await.insert(
+    service.pod_sandboxes.write().await.insert(
         "podabc999999999".to_string(),
         test_pod("podabc999999999", HashMap::new()),
     );
@@ -2358,6 +2775,62 @@ async fn stop_and_remove_container_are_idempotent_when_missing() {
     assert!(remove.is_ok());
 }
 
+#[tokio::test]
+async fn remove_container_deletes_runc_state_bundle_and_record() {
+    let (dir, service) = test_service_with_fake_runtime();
+    service.containers.write().await.insert(
+        "container-exited".to_string(),
+        test_container("container-exited", "pod-1", HashMap::new()),
+    );
+    service
+        .persistence
+        .lock()
+        .await
+        .save_container(
+            "container-exited",
+            "pod-1",
+            crate::runtime::ContainerStatus::Stopped(0),
+            "busybox:latest",
+            &["true".to_string()],
+            &HashMap::new(),
+            &HashMap::new(),
+        )
+        .unwrap();
+    write_test_bundle_config(&dir, "container-exited", &HashMap::new());
+    set_fake_runtime_state(&dir, "container-exited", "stopped");
+
+    for _ in 0..2 {
+        RuntimeService::remove_container(
+            &service,
+            Request::new(RemoveContainerRequest {
+                container_id: "container-exited".to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+    }
+
+    assert!(!fake_runtime_state_path(&dir, "container-exited").exists());
+    assert!(!dir
+        .path()
+        .join("runtime-root")
+        .join("container-exited")
+        .exists());
+    assert!(!service
+        .containers
+        .read()
+        .await
+        .contains_key("container-exited"));
+    assert!(service
+        .persistence
+        .lock()
+        .await
+        .storage()
+        .get_container("container-exited")
+        .unwrap()
+        .is_none());
+}
+
 #[tokio::test]
 async fn stop_and_remove_pod_are_idempotent_when_missing() {
     let service = test_service();
@@ -2405,7 +2878,7 @@ async fn exec_validates_container_is_streamable() {
     .unwrap_err();
     assert_eq!(missing.code(), tonic::Code::NotFound);
 
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "container-stopped".to_string(),
         test_container("container-stopped", "pod-1", HashMap::new()),
     );
@@ -2425,7 +2898,7 @@ async fn exec_validates_container_is_streamable() {
     .unwrap_err();
     assert_eq!(stopped.code(), tonic::Code::FailedPrecondition);
 
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "container-created".to_string(),
         test_container("container-created", "pod-1", HashMap::new()),
     );
@@ -2447,6 +2920,48 @@ async fn exec_validates_container_is_streamable() {
     assert!(response.url.contains("/exec/"));
 }
 
+#[tokio::test]
+async fn exec_and_attach_reject_invalid_stream_flags_before_lookup() {
+    let (_dir, service) = test_service_with_fake_runtime();
+    service
+        .set_streaming_server(crate::streaming::StreamingServer::for_test(
+            "http://127.0.0.1:12345",
+        ))
+        .await;
+
+    // 参数错误优先于容器不存在
+    for (stdin, stdout, stderr, tty) in [(false, true, true, true), (false, false, false, false)] {
+        let exec = RuntimeService::exec(
+            &service,
+            Request::new(ExecRequest {
+                container_id: "missing".to_string(),
+                cmd: vec!["sh".to_string()],
+                stdin,
+                stdout,
+                stderr,
+                tty,
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(exec.code(), tonic::Code::InvalidArgument);
+
+        let attach = RuntimeService::attach(
+            &service,
+            Request::new(AttachRequest {
+                container_id: "missing".to_string(),
+                stdin,
+                stdout,
+                stderr,
+                tty,
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(attach.code(), tonic::Code::InvalidArgument);
+    }
+}
+
 #[tokio::test]
 async fn exec_sync_validates_container_is_streamable() {
     let (dir, service) = test_service_with_fake_runtime();
@@ -2463,7 +2978,7 @@ async fn exec_sync_validates_container_is_streamable() {
     .unwrap_err();
     assert_eq!(missing.code(), tonic::Code::NotFound);
 
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "container-stopped".to_string(),
         test_container("container-stopped", "pod-1", HashMap::new()),
     );
@@ -2480,7 +2995,7 @@ async fn exec_sync_validates_container_is_streamable() {
     .unwrap_err();
     assert_eq!(stopped.code(), tonic::Code::FailedPrecondition);
 
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "container-running".to_string(),
         test_container("container-running", "pod-1", HashMap::new()),
     );
@@ -2500,38 +3015,450 @@ async fn exec_sync_validates_container_is_streamable() {
 }
 
 #[tokio::test]
-async fn attach_validates_container_is_streamable() {
+async fn exec_sync_runs_as_exec_user_annotation() {
     let (dir, service) = test_service_with_fake_runtime();
-    service
-        .set_streaming_server(crate::streaming::StreamingServer::for_test(
-            "http://127.0.0.1:12345",
-        ))
-        .await;
-
-    let missing = RuntimeService::attach(
-        &service,
-        Request::new(AttachRequest {
-            container_id: "missing".to_string(),
-            stdin: false,
-            stdout: true,
-            stderr: true,
-            tty: false,
-        }),
+    let rootfs = dir.path().join("exec-user-rootfs");
+    fs::create_dir_all(rootfs.join("etc")).unwrap();
+    fs::write(
+        rootfs.join("etc/passwd"),
+        "root:x:0:0:root:/root:/bin/sh\napp:x:1000:1000::/home/app:/bin/sh\n",
     )
-    .await
-    .unwrap_err();
-    assert_eq!(missing.code(), tonic::Code::NotFound);
+    .unwrap();
+    fs::write(rootfs.join("etc/group"), "root:x:0:\nstaff:x:50:app\n").unwrap();
 
-    service.containers.lock().await.insert(
-        "container-stopped".to_string(),
-        test_container("container-stopped", "pod-1", HashMap::new()),
-    );
-    set_fake_runtime_state(&dir, "container-stopped", "stopped");
-    let stopped = RuntimeService::attach(
-        &service,
-        Request::new(AttachRequest {
-            container_id: "container-stopped".to_string(),
-            stdin: false,
+    let exec_id = |id: &str| {
+        RuntimeService::exec_sync(
+            &service,
+            Request::new(ExecSyncRequest {
+                container_id: id.to_string(),
+                cmd: vec!["id".to_string()],
+                timeout: 0,
+            }),
+        )
+    };
+    for (id, user, expected) in [
+        ("exec-default", None, "uid=0 gid=0"),
+        ("exec-name", Some("app"), "uid=1000 gid=1000"),
+        ("exec-group", Some("app:staff"), "uid=1000 gid=50"),
+        ("exec-numeric", Some("2000"), "uid=2000 gid=2000"),
+        ("exec-missing", Some("nobody"), ""),
+    ] {
+        let mut annotations = HashMap::new();
+        if let Some(user) = user {
+            annotations.insert(EXEC_USER_ANNOTATION_KEY.to_string(), user.to_string());
+        }
+        service.containers.write().await.insert(
+            id.to_string(),
+            test_container(id, "pod-1", annotations.clone()),
+        );
+        let bundle_dir = dir.path().join("runtime-root").join(id);
+        fs::create_dir_all(&bundle_dir).unwrap();
+        fs::write(
+            bundle_dir.join("config.json"),
+            serde_json::json!({
+                "ociVersion": "1.0.2",
+                "annotations": annotations,
+                "root": {"path": rootfs},
+            })
+            .to_string(),
+        )
+        .unwrap();
+        set_fake_runtime_state(&dir, id, "running");
+
+        if expected.is_empty() {
+            let err = exec_id(id).await.unwrap_err();
+            assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+            assert!(err.message().contains("nobody"), "{}", err.message());
+            continue;
+        }
+        let response = exec_id(id).await.unwrap().into_inner();
+        assert_eq!(response.exit_code, 0);
+        assert_eq!(String::from_utf8_lossy(&response.stdout).trim(), expected);
+    }
+
+    let err = RuntimeServiceImpl::validate_exec_overrides(&HashMap::from([(
+        EXEC_USER_ANNOTATION_KEY.to_string(),
+        "app:".to_string(),
+    )]))
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn exec_sync_uses_exec_cwd_and_env_annotations() {
+    let (dir, service) = test_service_with_fake_runtime();
+    let rootfs = dir.path().join("exec-cwd-rootfs");
+    fs::create_dir_all(rootfs.join("srv/app")).unwrap();
+    std::os::unix::fs::symlink("/", rootfs.join("escape")).unwrap();
+
+    let exec = |id: &str, cmd: &[&str]| {
+        RuntimeService::exec_sync(
+            &service,
+            Request::new(ExecSyncRequest {
+                container_id: id.to_string(),
+                cmd: cmd.iter().map(|arg| arg.to_string()).collect(),
+                timeout: 0,
+            }),
+        )
+    };
+    for (id, cwd) in [
+        ("exec-cwd", "/srv/app"),
+        ("exec-cwd-missing", "/srv/missing"),
+        ("exec-cwd-escape", "/escape/tmp"),
+    ] {
+        let annotations = HashMap::from([
+            (EXEC_CWD_ANNOTATION_KEY.to_string(), cwd.to_string()),
+            (
+                EXEC_ENV_ANNOTATION_KEY.to_string(),
+                r#"{"PROBE_MODE":"deep","LANG":"C"}"#.to_string(),
+            ),
+        ]);
+        service.containers.write().await.insert(
+            id.to_string(),
+            test_container(id, "pod-1", annotations.clone()),
+        );
+        let bundle_dir = dir.path().join("runtime-root").join(id);
+        fs::create_dir_all(&bundle_dir).unwrap();
+        fs::write(
+            bundle_dir.join("config.json"),
+            serde_json::json!({
+                "ociVersion": "1.0.2",
+                "annotations": annotations,
+                "root": {"path": rootfs},
+            })
+            .to_string(),
+        )
+        .unwrap();
+        set_fake_runtime_state(&dir, id, "running");
+    }
+
+    let pwd = exec("exec-cwd", &["pwd"]).await.unwrap().into_inner();
+    assert_eq!(String::from_utf8_lossy(&pwd.stdout).trim(), "/srv/app");
+    let env = exec("exec-cwd", &["printenv", "PROBE_MODE"])
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(String::from_utf8_lossy(&env.stdout).trim(), "deep");
+
+    for id in ["exec-cwd-missing", "exec-cwd-escape"] {
+        let err = exec(id, &["pwd"]).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::FailedPrecondition, "{}", id);
+    }
+
+    // 未设置覆盖注解时沿用容器自身的设置
+    service.containers.write().await.insert(
+        "exec-plain".to_string(),
+        test_container("exec-plain", "pod-1", HashMap::new()),
+    );
+    set_fake_runtime_state(&dir, "exec-plain", "running");
+    let pwd = exec("exec-plain", &["pwd"]).await.unwrap().into_inner();
+    assert_eq!(String::from_utf8_lossy(&pwd.stdout).trim(), "/");
+
+    for (key, value) in [
+        (EXEC_CWD_ANNOTATION_KEY, "srv/app"),
+        (EXEC_ENV_ANNOTATION_KEY, r#"{"A=B":"c"}"#),
+        (EXEC_ENV_ANNOTATION_KEY, "[]"),
+    ] {
+        let err = RuntimeServiceImpl::validate_exec_overrides(&HashMap::from([(
+            key.to_string(),
+            value.to_string(),
+        )]))
+        .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument, "{}", value);
+    }
+}
+
+/// pid 是否为本进程尚未回收的僵尸子进程
+fn is_zombie_child(pid: u32) -> bool {
+    let Ok(stat) = fs::read_to_string(format!("/proc/{}/stat", pid)) else {
+        return false;
+    };
+    let fields: Vec<&str> = stat
+        .rsplit_once(')')
+        .map(|(_, rest)| rest.split_whitespace().collect())
+        .unwrap_or_default();
+    fields.first() == Some(&"Z") && fields.get(1) == Some(&std::process::id().to_string().as_str())
+}
+
+#[tokio::test]
+async fn exec_sync_leaves_no_zombie_processes() {
+    let (dir, service) = test_service_with_fake_runtime();
+    service.containers.write().await.insert(
+        "container-exec".to_string(),
+        test_container("container-exec", "pod-1", HashMap::new()),
+    );
+    set_fake_runtime_state(&dir, "container-exec", "running");
+    let exec_request = || {
+        Request::new(ExecSyncRequest {
+            container_id: "container-exec".to_string(),
+            cmd: vec!["true".to_string()],
+            timeout: 0,
+        })
+    };
+
+    for _ in 0..32 {
+        let response = RuntimeService::exec_sync(&service, exec_request())
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.exit_code, 0);
+    }
+
+    // 客户端取消的 exec：handler future 被丢弃后 runc exec 也应被杀掉并回收
+    let state_dir = dir.path().join("runtime-state");
+    fs::write(state_dir.join("container-exec.exec-sleep"), "30").unwrap();
+    for _ in 0..4 {
+        let cancelled = tokio::time::timeout(
+            std::time::Duration::from_millis(200),
+            RuntimeService::exec_sync(&service, exec_request()),
+        )
+        .await;
+        assert!(cancelled.is_err());
+    }
+
+    let pids: Vec<u32> = fs::read_to_string(state_dir.join("container-exec.exec-pids"))
+        .unwrap()
+        .lines()
+        .map(|line| line.trim().parse().unwrap())
+        .collect();
+    assert_eq!(pids.len(), 36);
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    loop {
+        let lingering: Vec<u32> = pids
+            .iter()
+            .copied()
+            .filter(|pid| {
+                is_zombie_child(*pid)
+                    || Path::new(&format!("/proc/{}", pid)).exists()
+                        && fs::read_to_string(format!("/proc/{}/cmdline", pid))
+                            .is_ok_and(|cmdline| cmdline.contains("fake-runc"))
+            })
+            .collect();
+        if lingering.is_empty() {
+            break;
+        }
+        assert!(
+            std::time::Instant::now() < deadline,
+            "exec processes not reaped: {:?}",
+            lingering
+        );
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+}
+
+#[tokio::test]
+async fn stop_container_kills_lingering_exec_processes() {
+    let (dir, service) = test_service_with_fake_runtime();
+    service.containers.write().await.insert(
+        "container-exec".to_string(),
+        test_container("container-exec", "pod-1", HashMap::new()),
+    );
+    set_fake_runtime_state(&dir, "container-exec", "running");
+    let state_dir = dir.path().join("runtime-state");
+    fs::write(state_dir.join("container-exec.exec-sleep"), "30").unwrap();
+    service
+        .set_streaming_server(
+            crate::streaming::StreamingServer::start(
+                "127.0.0.1:0",
+                service.config.runtime_path.clone(),
+                &crate::config::StreamingConfig::default(),
+            )
+            .await
+            .unwrap(),
+        )
+        .await;
+
+    let url = RuntimeService::exec(
+        &service,
+        Request::new(ExecRequest {
+            container_id: "container-exec".to_string(),
+            cmd: vec!["sh".to_string()],
+            stdout: true,
+            ..Default::default()
+        }),
+    )
+    .await
+    .unwrap()
+    .into_inner()
+    .url;
+    let address = url.trim_start_matches("http://");
+    let (host_port, path) = address.split_at(address.find('/').unwrap());
+    let mut stream = tokio::net::TcpStream::connect(host_port).await.unwrap();
+    tokio::io::AsyncWriteExt::write_all(
+        &mut stream,
+        format!(
+            "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: Upgrade\r\nUpgrade: websocket\r\n\
+             Sec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+             Sec-WebSocket-Protocol: v4.channel.k8s.io\r\n\r\n",
+            path, host_port
+        )
+        .as_bytes(),
+    )
+    .await
+    .unwrap();
+
+    let processes = state_dir.join("container-exec.exec-processes");
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    let pid: i32 = loop {
+        if let Some(pid) = fs::read_to_string(&processes)
+            .ok()
+            .and_then(|raw| raw.trim().parse().ok())
+        {
+            break pid;
+        }
+        assert!(
+            std::time::Instant::now() < deadline,
+            "exec process did not start"
+        );
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    };
+    // pid 文件由 fake runtime 在记录进程后写入，等待 guard 可以读到
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+    RuntimeService::stop_container(
+        &service,
+        Request::new(StopContainerRequest {
+            container_id: "container-exec".to_string(),
+            timeout: 0,
+        }),
+    )
+    .await
+    .unwrap();
+
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    while Path::new(&format!("/proc/{}", pid)).exists() && !is_zombie_child(pid as u32) {
+        assert!(
+            std::time::Instant::now() < deadline,
+            "exec process {} survived container stop",
+            pid
+        );
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    drop(stream);
+}
+
+/// 等待 fake runtime 记录下 exec 启动的容器内进程
+async fn wait_for_exec_process(processes: &Path) -> i32 {
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    loop {
+        if let Some(pid) = fs::read_to_string(processes)
+            .ok()
+            .and_then(|raw| raw.trim().parse().ok())
+        {
+            // 等 guard 按 pid 文件记录进程身份
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            return pid;
+        }
+        assert!(
+            std::time::Instant::now() < deadline,
+            "exec process did not start"
+        );
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+}
+
+async fn assert_exec_process_killed(pid: i32) {
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    while Path::new(&format!("/proc/{}", pid)).exists() && !is_zombie_child(pid as u32) {
+        assert!(
+            std::time::Instant::now() < deadline,
+            "exec process {} is still running",
+            pid
+        );
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+}
+
+#[tokio::test]
+async fn exec_sync_processes_are_killed_on_timeout_and_container_stop() {
+    let (dir, service) = test_service_with_fake_runtime();
+    service.containers.write().await.insert(
+        "container-exec".to_string(),
+        test_container("container-exec", "pod-1", HashMap::new()),
+    );
+    set_fake_runtime_state(&dir, "container-exec", "running");
+    let state_dir = dir.path().join("runtime-state");
+    fs::write(state_dir.join("container-exec.exec-sleep"), "30").unwrap();
+    service
+        .set_streaming_server(crate::streaming::StreamingServer::for_test(
+            "http://127.0.0.1:12345",
+        ))
+        .await;
+    let processes = state_dir.join("container-exec.exec-processes");
+    let exec_request = |timeout| {
+        Request::new(ExecSyncRequest {
+            container_id: "container-exec".to_string(),
+            cmd: vec!["sleep".to_string()],
+            timeout,
+        })
+    };
+
+    // 超时只杀掉 runc exec 不够，容器内的进程也要结束
+    let err = RuntimeService::exec_sync(&service, exec_request(1))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::DeadlineExceeded);
+    let pid = wait_for_exec_process(&processes).await;
+    assert_exec_process_killed(pid).await;
+
+    // ExecSync 与流式 exec 登记在同一处，停止容器时一并杀掉
+    fs::remove_file(&processes).unwrap();
+    let service = Arc::new(service);
+    let exec = tokio::spawn({
+        let service = service.clone();
+        async move { RuntimeService::exec_sync(&*service, exec_request(0)).await }
+    });
+    let pid = wait_for_exec_process(&processes).await;
+    RuntimeService::stop_container(
+        &*service,
+        Request::new(StopContainerRequest {
+            container_id: "container-exec".to_string(),
+            timeout: 0,
+        }),
+    )
+    .await
+    .unwrap();
+    assert_exec_process_killed(pid).await;
+    tokio::time::timeout(std::time::Duration::from_secs(5), exec)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn attach_validates_container_is_streamable() {
+    let (dir, service) = test_service_with_fake_runtime();
+    service
+        .set_streaming_server(crate::streaming::StreamingServer::for_test(
+            "http://127.0.0.1:12345",
+        ))
+        .await;
+
+    let missing = RuntimeService::attach(
+        &service,
+        Request::new(AttachRequest {
+            container_id: "missing".to_string(),
+            stdin: false,
+            stdout: true,
+            stderr: true,
+            tty: false,
+        }),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(missing.code(), tonic::Code::NotFound);
+
+    service.containers.write().await.insert(
+        "container-stopped".to_string(),
+        test_container("container-stopped", "pod-1", HashMap::new()),
+    );
+    set_fake_runtime_state(&dir, "container-stopped", "stopped");
+    let stopped = RuntimeService::attach(
+        &service,
+        Request::new(AttachRequest {
+            container_id: "container-stopped".to_string(),
+            stdin: false,
             stdout: true,
             stderr: true,
             tty: false,
@@ -2541,7 +3468,7 @@ async fn attach_validates_container_is_streamable() {
     .unwrap_err();
     assert_eq!(stopped.code(), tonic::Code::FailedPrecondition);
 
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "container-running".to_string(),
         test_container("container-running", "pod-1", HashMap::new()),
     );
@@ -2609,7 +3536,7 @@ async fn attach_uses_log_stream_fallback_when_recovered_socket_is_missing() {
             &annotations,
         )
         .unwrap();
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "recover-container".to_string(),
         test_container("recover-container", "pod-1", annotations),
     );
@@ -2659,7 +3586,7 @@ async fn attach_uses_log_stream_fallback_for_read_only_tty_when_socket_is_missin
         },
     )
     .unwrap();
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "recover-container-tty".to_string(),
         test_container("recover-container-tty", "pod-1", annotations),
     );
@@ -2720,6 +3647,8 @@ sleep 1
     let service = RuntimeServiceImpl::new_with_shim_work_dir(
         RuntimeConfig {
             root_dir: dir.path().join("root"),
+            state_dir: dir.path().join("root"),
+            image_root: dir.path().join("root").join("storage"),
             runtime: "runc".to_string(),
             runtime_handlers: vec!["runc".to_string()],
             runtime_root: dir.path().join("runtime-root"),
@@ -2727,6 +3656,29 @@ sleep 1
             runtime_path,
             pause_image: "registry.k8s.io/pause:3.9".to_string(),
             cni_config: crate::network::CniConfig::default(),
+            process_defaults: Default::default(),
+            reconcile_interval: std::time::Duration::ZERO,
+            env_file_dir: None,
+            protected_paths: Default::default(),
+            mount_policy: Default::default(),
+            container_log: Default::default(),
+            expand_env_references: false,
+            default_dns: Default::default(),
+            dir_mode: crate::utils::DEFAULT_DIR_MODE,
+            fs_group_max_entries: crate::storage::ownership::DEFAULT_FS_GROUP_MAX_ENTRIES,
+            pause_binary: None,
+            shm_size: crate::oci::spec::DEFAULT_SHM_SIZE,
+            prepared_rootfs_dir: None,
+            default_env: Vec::new(),
+            cgroup_root: None,
+            cgroup_mount: dir.path().join("cgroup"),
+            sysfs_root: dir.path().join("sys"),
+            remove_grace_period_secs: 30,
+            kill_timeout: crate::runtime::DEFAULT_KILL_WAIT_TIMEOUT,
+            image_health_registry: None,
+            image_health_interval: std::time::Duration::from_secs(60),
+            rootless: None,
+            stats: Default::default(),
         },
         NriConfig::default(),
         shim_work_dir.clone(),
@@ -2763,7 +3715,7 @@ sleep 1
         },
     )
     .unwrap();
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "tty-restore".to_string(),
         Container {
             state: ContainerState::ContainerRunning as i32,
@@ -2801,7 +3753,7 @@ async fn attach_returns_error_when_socket_missing_and_interactive_recovery_is_re
         .await;
 
     set_fake_runtime_state(&dir, "recover-container", "running");
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "recover-container".to_string(),
         test_container("recover-container", "pod-1", HashMap::new()),
     );
@@ -2845,7 +3797,7 @@ async fn container_status_verbose_returns_json_info() {
         },
     )
     .unwrap();
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "container-1".to_string(),
         test_container("container-1", "pod-1", annotations),
     );
@@ -2858,7 +3810,8 @@ async fn container_status_verbose_returns_json_info() {
             "ociVersion": "1.0.2",
             "process": {
                 "terminal": true,
-                "cwd": "/"
+                "cwd": "/",
+                "env": ["PATH=/usr/bin:/bin"]
             }
         })
         .to_string(),
@@ -2883,7 +3836,25 @@ async fn container_status_verbose_returns_json_info() {
     assert_eq!(info["privileged"], true);
     assert_eq!(info["user"], "1000");
     assert_eq!(info["runtimeSpec"]["process"]["cwd"], "/");
+    assert_eq!(info["pid"], std::process::id());
+    assert_eq!(info["config"]["metadata"]["name"], "container-1-name");
+    assert_eq!(info["config"]["image"]["image"], "busybox:latest");
+    assert_eq!(info["config"]["envs"][0]["key"], "PATH");
+    assert_eq!(info["config"]["workingDir"], "/");
+    assert_eq!(info["config"]["tty"], true);
     assert_eq!(response.status.unwrap().mounts.len(), 1);
+
+    let response = RuntimeService::container_status(
+        &service,
+        Request::new(ContainerStatusRequest {
+            container_id: "container-1".to_string(),
+            verbose: false,
+        }),
+    )
+    .await
+    .unwrap()
+    .into_inner();
+    assert!(response.info.is_empty());
 }
 
 #[tokio::test]
@@ -2907,7 +3878,7 @@ async fn pod_sandbox_status_verbose_returns_json_info() {
     .unwrap();
     service
         .pod_sandboxes
-        .lock()
+        .write()
         .await
         .insert("pod-1".to_string(), test_pod("pod-1", annotations));
     set_fake_runtime_state(&dir, "pause-1", "running");
@@ -2946,28 +3917,161 @@ async fn pod_sandbox_status_verbose_returns_json_info() {
 }
 
 #[tokio::test]
-async fn status_verbose_returns_structured_config() {
-    let service = test_service();
+async fn pod_sandbox_status_verbose_reports_network_details() {
+    let (_dir, service) = test_service_with_fake_runtime();
+    let mut annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut annotations,
+        INTERNAL_POD_STATE_KEY,
+        &StoredPodState {
+            netns_path: Some("/var/run/netns/test-pod".to_string()),
+            ip: Some("10.88.0.2".to_string()),
+            network_config: Some(serde_json::json!({
+                "cniVersion": "1.0.0",
+                "name": "test-net",
+                "plugins": [{"type": "bridge"}]
+            })),
+            cni_result: Some(serde_json::json!({
+                "cniVersion": "1.0.0",
+                "ips": [{"address": "10.88.0.2/16"}]
+            })),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    service
+        .pod_sandboxes
+        .write()
+        .await
+        .insert("pod-1".to_string(), test_pod("pod-1", annotations));
+
+    let quiet = RuntimeService::pod_sandbox_status(
+        &service,
+        Request::new(PodSandboxStatusRequest {
+            pod_sandbox_id: "pod-1".to_string(),
+            verbose: false,
+        }),
+    )
+    .await
+    .unwrap()
+    .into_inner();
+    assert!(quiet.info.is_empty());
+
+    let response = RuntimeService::pod_sandbox_status(
+        &service,
+        Request::new(PodSandboxStatusRequest {
+            pod_sandbox_id: "pod-1".to_string(),
+            verbose: true,
+        }),
+    )
+    .await
+    .unwrap()
+    .into_inner();
+
+    let info: serde_json::Value = serde_json::from_str(response.info.get("info").unwrap()).unwrap();
+    assert_eq!(info["netnsPath"], "/var/run/netns/test-pod");
+    assert_eq!(info["ip"], "10.88.0.2");
+    assert_eq!(info["networkConfig"]["name"], "test-net");
+    assert_eq!(info["cniResult"]["ips"][0]["address"], "10.88.0.2/16");
+}
+
+#[tokio::test]
+async fn status_verbose_reports_separate_state_and_image_roots() {
+    let dir = tempdir().unwrap();
+    let service = RuntimeServiceImpl::new(RuntimeConfig {
+        state_dir: dir.path().join("run"),
+        image_root: dir.path().join("images"),
+        ..test_runtime_config(dir.path().join("root"))
+    });
     let response = RuntimeService::status(&service, Request::new(StatusRequest { verbose: true }))
         .await
         .unwrap()
         .into_inner();
 
-    assert!(response.info.contains_key("config"));
     let config: serde_json::Value =
         serde_json::from_str(response.info.get("config").unwrap()).unwrap();
-    assert_eq!(config["runtimeName"], "crius");
-    assert!(!config["runtimeHandlers"].as_array().unwrap().is_empty());
-    assert_eq!(config["recovery"]["startupReconcile"], true);
-    assert_eq!(config["recovery"]["eventReplayOnRecovery"], false);
-    assert_eq!(config["runtimeFeatures"]["updateContainerResources"], true);
-    assert_eq!(config["runtimeFeatures"]["containerStats"], true);
-    assert_eq!(config["runtimeFeatures"]["podSandboxStats"], true);
-    assert_eq!(config["runtimeFeatures"]["podSandboxMetrics"], true);
     assert_eq!(
-        response.status.unwrap().conditions.len(),
-        2,
-        "expected runtime and network conditions"
+        config["rootDir"],
+        dir.path().join("root").display().to_string()
+    );
+    assert_eq!(
+        config["stateDir"],
+        dir.path().join("run").display().to_string()
+    );
+    assert_eq!(
+        config["imageRoot"],
+        dir.path().join("images").display().to_string()
+    );
+    assert!(dir.path().join("root").join("crius.db").exists());
+    assert_eq!(
+        service.mount_manager().root_dir(),
+        dir.path().join("run").join("mounts")
+    );
+}
+
+#[tokio::test]
+async fn node_summary_reports_image_store_usage() {
+    let dir = tempdir().unwrap();
+    let service = RuntimeServiceImpl::new(RuntimeConfig {
+        image_root: dir.path().join("storage"),
+        ..test_runtime_config(dir.path().join("root"))
+    });
+    let image_dir = dir.path().join("storage").join("images").join("abc");
+    std::fs::create_dir_all(&image_dir).unwrap();
+    std::fs::write(image_dir.join("layer.tar"), vec![0u8; 4096]).unwrap();
+
+    let summary = service.node_summary().await;
+    assert!(summary.pods.is_empty());
+    assert_eq!(summary.cpu_usage_core_nano_seconds, 0);
+    assert_eq!(summary.writable_layer_bytes, 0);
+    let image_fs = summary.image_fs.unwrap();
+    assert_eq!(image_fs.used_bytes.unwrap().value, 4096);
+    assert_eq!(image_fs.inodes_used.unwrap().value, 2);
+
+    // 管理服务的 NodeSummary 方法与 `crius summary` 输出同一份汇总
+    let response = service
+        .get_node_summary(Request::new(crate::server::admin::NodeSummaryRequest {}))
+        .await
+        .unwrap()
+        .into_inner();
+    let summary = crate::server::NodeSummary::from(response).to_json();
+    assert_eq!(summary["cpu_usage_core_nano_seconds"], 0);
+    assert_eq!(summary["image_fs"]["used_bytes"], 4096);
+    assert_eq!(summary["image_fs"]["inodes_used"], 2);
+    assert_eq!(
+        summary["image_fs"]["mountpoint"],
+        dir.path()
+            .join("storage")
+            .join("images")
+            .display()
+            .to_string()
+    );
+    assert_eq!(summary["pods"], serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn status_verbose_returns_structured_config() {
+    let service = test_service();
+    let response = RuntimeService::status(&service, Request::new(StatusRequest { verbose: true }))
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert!(response.info.contains_key("config"));
+    let config: serde_json::Value =
+        serde_json::from_str(response.info.get("config").unwrap()).unwrap();
+    assert_eq!(config["runtimeName"], "crius");
+    assert!(!config["runtimeHandlers"].as_array().unwrap().is_empty());
+    assert_eq!(config["recovery"]["startupReconcile"], true);
+    assert_eq!(config["recovery"]["eventReplayOnRecovery"], false);
+    assert_eq!(config["runtimeFeatures"]["updateContainerResources"], true);
+    assert_eq!(config["runtimeFeatures"]["containerStats"], true);
+    assert_eq!(config["runtimeFeatures"]["podSandboxStats"], true);
+    assert_eq!(config["runtimeFeatures"]["podSandboxMetrics"], true);
+    assert_eq!(
+        response.status.unwrap().conditions.len(),
+        3,
+        "expected runtime, network and image conditions"
     );
 }
 
@@ -3007,6 +4111,55 @@ exit 0
     assert_eq!(response.runtime_version, "runc version 1.2.3");
 }
 
+#[tokio::test]
+async fn version_reports_requested_version_support_and_rejects_incompatible_versions() {
+    let dir = tempdir().unwrap();
+    let service = RuntimeServiceImpl::new(test_runtime_config(dir.path().join("root")));
+    let version = |requested: &str| {
+        RuntimeService::version(
+            &service,
+            Request::new(VersionRequest {
+                version: requested.to_string(),
+            }),
+        )
+    };
+    let supported = |response: &Response<VersionResponse>| {
+        response
+            .metadata()
+            .get("crius-requested-version-supported")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+
+    for requested in ["", "0.1.0", "v1"] {
+        let response = version(requested).await.unwrap();
+        assert_eq!(
+            supported(&response).as_deref(),
+            Some("true"),
+            "{}",
+            requested
+        );
+        assert_eq!(response.get_ref().runtime_api_version, "v1");
+        assert_eq!(response.get_ref().version, "0.1.0");
+    }
+
+    let response = version("next-gen").await.unwrap();
+    assert_eq!(supported(&response).as_deref(), Some("false"));
+    assert_eq!(
+        response
+            .metadata()
+            .get("crius-requested-version")
+            .and_then(|value| value.to_str().ok()),
+        Some("next-gen")
+    );
+
+    for requested in ["v1alpha2", "v2", "1.0.0"] {
+        let err = version(requested).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::FailedPrecondition, "{}", requested);
+        assert!(err.message().contains(requested), "{}", err.message());
+    }
+}
+
 #[test]
 fn parse_cgroup_hint_from_procfs_prefers_relevant_controller_or_unified_line() {
     let v1 = "11:hugetlb:/\n10:memory:/kubepods.slice/pod123/container.scope\n9:cpuset:/\n";
@@ -3050,6 +4203,201 @@ async fn status_reports_runtime_not_ready_when_binary_is_not_executable() {
     assert_eq!(runtime_condition.reason, "RuntimeBinaryNotExecutable");
 }
 
+#[tokio::test]
+async fn status_reports_runtime_not_ready_when_memory_controller_is_missing() {
+    let (dir, service) = test_service_with_fake_runtime();
+    let cgroup_mount = dir.path().join("cgroup");
+    fs::create_dir_all(&cgroup_mount).unwrap();
+    fs::write(cgroup_mount.join("cgroup.controllers"), "cpu io pids").unwrap();
+    service
+        .cgroup_controllers
+        .set(crate::cgroups::probe_controllers(
+            &cgroup_mount,
+            None,
+            &crate::cgroups::REQUIRED_CONTROLLERS,
+        ))
+        .unwrap();
+
+    let response = RuntimeService::status(&service, Request::new(StatusRequest { verbose: true }))
+        .await
+        .unwrap()
+        .into_inner();
+    let runtime_condition = response
+        .status
+        .unwrap()
+        .conditions
+        .into_iter()
+        .find(|condition| condition.r#type == "RuntimeReady")
+        .unwrap();
+    assert!(!runtime_condition.status);
+    assert_eq!(runtime_condition.reason, "CgroupControllersMissing");
+    assert!(
+        runtime_condition.message.contains("memory"),
+        "{}",
+        runtime_condition.message
+    );
+    let config: serde_json::Value =
+        serde_json::from_str(response.info.get("config").unwrap()).unwrap();
+    assert_eq!(
+        config["cgroupControllers"]["missing"],
+        serde_json::json!(["memory"])
+    );
+}
+
+#[tokio::test]
+async fn image_health_reports_writable_store_as_ready() {
+    let dir = tempdir().unwrap();
+    let service = RuntimeServiceImpl::new(test_runtime_config(dir.path().join("root")));
+    fs::create_dir_all(&service.config.image_root).unwrap();
+
+    let (ready, reason, _) = service.image_health().await;
+
+    assert!(ready);
+    assert_eq!(reason, "ImageIsReady");
+    assert_eq!(
+        fs::read_dir(&service.config.image_root).unwrap().count(),
+        0,
+        "probe file must be removed"
+    );
+}
+
+#[test]
+fn image_health_reports_read_only_or_unwritable_store() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("storage");
+
+    let (ready, reason, message) = RuntimeServiceImpl::image_store_condition(
+        &root,
+        Err(std::io::Error::from_raw_os_error(
+            nix::errno::Errno::EROFS as i32,
+        )),
+    );
+    assert!(!ready);
+    assert_eq!(reason, "ImageStoreReadOnly");
+    assert!(message.contains("image pulls will fail"));
+
+    let (_, reason, _) = RuntimeServiceImpl::image_store_condition(
+        &root,
+        Err(std::io::Error::from_raw_os_error(
+            nix::errno::Errno::ENOSPC as i32,
+        )),
+    );
+    assert_eq!(reason, "ImageStoreFull");
+
+    fs::write(&root, "not a directory").unwrap();
+    let (ready, reason, _) = RuntimeServiceImpl::image_store_condition(
+        &root,
+        RuntimeServiceImpl::probe_image_store(&root),
+    );
+    assert!(!ready);
+    assert_eq!(reason, "ImageStoreNotWritable");
+}
+
+#[tokio::test]
+async fn image_health_caches_registry_reachability() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::AsyncWriteExt;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let hits = Arc::new(AtomicUsize::new(0));
+    let server_hits = hits.clone();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            server_hits.fetch_add(1, Ordering::SeqCst);
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf).await;
+            let _ = stream
+                .write_all(
+                    b"HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                )
+                .await;
+        }
+    });
+
+    let dir = tempdir().unwrap();
+    let service = RuntimeServiceImpl::new(RuntimeConfig {
+        image_health_registry: Some(format!("http://{}", addr)),
+        image_health_interval: std::time::Duration::from_secs(3600),
+        ..test_runtime_config(dir.path().join("root"))
+    });
+    fs::create_dir_all(&service.config.image_root).unwrap();
+
+    for _ in 0..2 {
+        let (ready, reason, message) = service.image_health().await;
+        assert!(ready, "{}", message);
+        assert_eq!(reason, "ImageIsReady");
+    }
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+    let unreachable = RuntimeServiceImpl::new(RuntimeConfig {
+        image_health_registry: Some("http://127.0.0.1:1".to_string()),
+        ..test_runtime_config(dir.path().join("other"))
+    });
+    fs::create_dir_all(&unreachable.config.image_root).unwrap();
+    let (ready, reason, _) = unreachable.image_health().await;
+    assert!(!ready);
+    assert_eq!(reason, "RegistryUnreachable");
+}
+
+#[tokio::test]
+async fn image_health_refreshes_registry_probe_without_blocking_status() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::AsyncWriteExt;
+
+    // 第一次请求立即应答，之后的请求一直挂起直到客户端超时
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let hits = Arc::new(AtomicUsize::new(0));
+    let server_hits = hits.clone();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            if server_hits.fetch_add(1, Ordering::SeqCst) > 0 {
+                tokio::spawn(async move {
+                    tokio::time::sleep(Duration::from_secs(30)).await;
+                    drop(stream);
+                });
+                continue;
+            }
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf).await;
+            let _ = stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                .await;
+        }
+    });
+
+    let dir = tempdir().unwrap();
+    let service = RuntimeServiceImpl::new(RuntimeConfig {
+        image_health_registry: Some(format!("http://{}", addr)),
+        image_health_interval: Duration::ZERO,
+        ..test_runtime_config(dir.path().join("root"))
+    });
+    fs::create_dir_all(&service.config.image_root).unwrap();
+
+    // 从未探测过时等待结果
+    let (ready, _, message) = service.image_health().await;
+    assert!(ready, "{}", message);
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+    // 缓存过期：返回上次结果，刷新在后台进行且不持有锁
+    for _ in 0..3 {
+        let (ready, _, message) = timeout(Duration::from_secs(1), service.image_health())
+            .await
+            .expect("status must not wait for a slow registry");
+        assert!(ready, "{}", message);
+    }
+    timeout(Duration::from_secs(2), async {
+        while hits.load(Ordering::SeqCst) < 2 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+    assert!(service.registry_probe.lock().await.in_flight);
+    assert_eq!(hits.load(Ordering::SeqCst), 2, "only one refresh in flight");
+}
+
 #[tokio::test]
 async fn runtime_config_reports_detected_cgroup_driver() {
     let service = test_service();
@@ -3064,6 +4412,23 @@ async fn runtime_config_reports_detected_cgroup_driver() {
     );
 }
 
+#[tokio::test]
+async fn status_reports_configured_cgroup_root() {
+    let dir = tempdir().unwrap();
+    let service = RuntimeServiceImpl::new(RuntimeConfig {
+        cgroup_root: Some("/kubepods".to_string()),
+        ..test_runtime_config(dir.path().join("root"))
+    });
+
+    let status = RuntimeService::status(&service, Request::new(StatusRequest { verbose: true }))
+        .await
+        .unwrap()
+        .into_inner();
+    let config: serde_json::Value =
+        serde_json::from_str(status.info.get("config").unwrap()).unwrap();
+    assert_eq!(config["cgroupRoot"], "/kubepods");
+}
+
 #[tokio::test]
 async fn update_runtime_config_persists_network_config_and_exposes_it_via_status() {
     let root_dir = tempdir().unwrap().keep();
@@ -3136,7 +4501,7 @@ async fn run_pod_sandbox_persists_runtime_pod_cidr_in_verbose_info() {
     )
     .unwrap();
 
-    service.pod_sandboxes.lock().await.insert(
+    service.pod_sandboxes.write().await.insert(
         "pod-runtime-cidr".to_string(),
         test_pod("pod-runtime-cidr", annotations),
     );
@@ -3155,6 +4520,61 @@ async fn run_pod_sandbox_persists_runtime_pod_cidr_in_verbose_info() {
     assert_eq!(info["runtimePodCIDR"], "10.88.0.0/16");
 }
 
+#[tokio::test]
+async fn pod_sandbox_status_reports_selected_runtime_handler() {
+    let (_dir, service) = test_service_with_fake_runtime();
+    for (id, handler) in [("pod-kata", "kata"), ("pod-default", "runc")] {
+        let mut annotations = HashMap::new();
+        RuntimeServiceImpl::insert_internal_state(
+            &mut annotations,
+            INTERNAL_POD_STATE_KEY,
+            &StoredPodState {
+                runtime_handler: handler.to_string(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let mut pod = test_pod(id, annotations);
+        pod.runtime_handler = handler.to_string();
+        service
+            .pod_sandboxes
+            .write()
+            .await
+            .insert(id.to_string(), pod);
+    }
+
+    for (id, expected) in [("pod-kata", "kata"), ("pod-default", "")] {
+        let status = RuntimeService::pod_sandbox_status(
+            &service,
+            Request::new(PodSandboxStatusRequest {
+                pod_sandbox_id: id.to_string(),
+                verbose: false,
+            }),
+        )
+        .await
+        .unwrap()
+        .into_inner()
+        .status
+        .unwrap();
+        assert_eq!(status.runtime_handler, expected, "{}", id);
+    }
+
+    let items = RuntimeService::list_pod_sandbox(
+        &service,
+        Request::new(ListPodSandboxRequest { filter: None }),
+    )
+    .await
+    .unwrap()
+    .into_inner()
+    .items;
+    let handlers: HashMap<String, String> = items
+        .into_iter()
+        .map(|pod| (pod.id, pod.runtime_handler))
+        .collect();
+    assert_eq!(handlers["pod-kata"], "kata");
+    assert_eq!(handlers["pod-default"], "");
+}
+
 #[test]
 fn network_health_requires_declared_plugin_binary() {
     let _guard = env_lock().lock().unwrap();
@@ -3181,6 +4601,139 @@ fn network_health_requires_declared_plugin_binary() {
     assert!(message.contains("bridge"));
 }
 
+/// 运行时、CNI、镜像存储与 pause 镜像都已就绪的服务；CNI 配置目录为空，由测试写入
+fn startup_ready_service() -> (TempDir, RuntimeServiceImpl, crate::image::ImageServiceImpl) {
+    let (dir, mut service) = test_service_with_fake_runtime();
+    let config_dir = dir.path().join("cni-conf");
+    let plugin_dir = dir.path().join("cni-bin");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::create_dir_all(&plugin_dir).unwrap();
+    {
+        let _guard = env_lock().lock().unwrap();
+        std::env::set_var("CRIUS_CNI_CONFIG_DIRS", config_dir.display().to_string());
+        std::env::set_var("CRIUS_CNI_PLUGIN_DIRS", plugin_dir.display().to_string());
+        service.config.cni_config = crate::network::CniConfig::from_env();
+        std::env::remove_var("CRIUS_CNI_CONFIG_DIRS");
+        std::env::remove_var("CRIUS_CNI_PLUGIN_DIRS");
+    }
+    fs::create_dir_all(&service.config.cgroup_mount).unwrap();
+    fs::write(
+        service.config.cgroup_mount.join("cgroup.controllers"),
+        "cpu memory\n",
+    )
+    .unwrap();
+    let image_dir = service
+        .config
+        .image_root
+        .join("images")
+        .join("sha256:pause");
+    fs::create_dir_all(&image_dir).unwrap();
+    fs::write(
+        image_dir.join("metadata.json"),
+        serde_json::json!({
+            "id": "sha256:pause",
+            "repo_tags": [service.config.pause_image],
+        })
+        .to_string(),
+    )
+    .unwrap();
+    let image_service = crate::image::ImageServiceImpl::new(&service.config.image_root).unwrap();
+    (dir, service, image_service)
+}
+
+fn all_startup_checks(timeout_secs: u64) -> crate::config::StartupConfig {
+    crate::config::StartupConfig {
+        timeout_secs,
+        require_runtime: true,
+        require_cni: true,
+        require_image_store: true,
+        require_pause_image: true,
+    }
+}
+
+#[tokio::test]
+async fn startup_checks_fail_with_diagnostic_when_runtime_is_missing() {
+    let (dir, mut service, image_service) = startup_ready_service();
+    fs::write(
+        dir.path().join("cni-conf/10-loopback.conf"),
+        r#"{"cniVersion":"0.4.0","name":"lo","type":"loopback"}"#,
+    )
+    .unwrap();
+    fs::write(dir.path().join("cni-bin/loopback"), "#!/bin/sh\n").unwrap();
+    fs::set_permissions(
+        dir.path().join("cni-bin/loopback"),
+        fs::Permissions::from_mode(0o755),
+    )
+    .unwrap();
+    service.config.runtime_path = dir.path().join("missing-runc");
+
+    let err = service
+        .wait_for_startup_checks(&all_startup_checks(0), &image_service)
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("RuntimeBinaryMissing"), "{}", err);
+    assert!(err.contains("missing-runc"), "{}", err);
+    assert!(!err.contains("cni:"), "{}", err);
+    assert!(!err.contains("pause image"), "{}", err);
+
+    // 未启用的检查不影响启动
+    let runtime_skipped = crate::config::StartupConfig {
+        require_runtime: false,
+        ..all_startup_checks(0)
+    };
+    service
+        .wait_for_startup_checks(&runtime_skipped, &image_service)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn startup_checks_wait_until_all_dependencies_are_satisfied() {
+    let (dir, service, image_service) = startup_ready_service();
+    let err = service
+        .wait_for_startup_checks(&all_startup_checks(0), &image_service)
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("CNIConfigMissing"), "{}", err);
+
+    let config_dir = dir.path().join("cni-conf");
+    let writer = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        fs::write(
+            config_dir.join("10-test.conf"),
+            r#"{"cniVersion":"0.4.0","name":"test"}"#,
+        )
+        .unwrap();
+    });
+    service
+        .wait_for_startup_checks(&all_startup_checks(10), &image_service)
+        .await
+        .unwrap();
+    writer.await.unwrap();
+}
+
+#[tokio::test]
+async fn startup_checks_report_pause_image_that_cannot_be_pulled() {
+    let (_dir, mut service, image_service) = startup_ready_service();
+    service.config.pause_image = "127.0.0.1:1/library/pause:3.9".to_string();
+    let pause_only = crate::config::StartupConfig {
+        timeout_secs: 0,
+        require_pause_image: true,
+        ..Default::default()
+    };
+
+    let err = service
+        .wait_for_startup_checks(&pause_only, &image_service)
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("pause image"), "{}", err);
+    assert!(err.contains("127.0.0.1:1/library/pause:3.9"), "{}", err);
+    assert!(err.contains("could not be pulled"), "{}", err);
+}
+
 #[test]
 fn network_health_requires_plugin_to_be_executable() {
     let _guard = env_lock().lock().unwrap();
@@ -3213,30 +4766,153 @@ fn network_health_requires_plugin_to_be_executable() {
     assert!(message.contains("non-executable"));
 }
 
-#[tokio::test]
-async fn checkpoint_container_writes_checkpoint_artifact() {
-    let (dir, service) = test_service_with_fake_runtime();
-    let mut annotations = HashMap::new();
-    RuntimeServiceImpl::insert_internal_state(
-        &mut annotations,
-        INTERNAL_CONTAINER_STATE_KEY,
-        &StoredContainerState {
-            log_path: Some(dir.path().join("logs").join("c1.log").display().to_string()),
-            metadata_name: Some("checkpointed".to_string()),
-            metadata_attempt: Some(2),
-            started_at: Some(10),
-            ..Default::default()
-        },
+fn write_probe_executable(path: &Path, script: &str) {
+    fs::write(path, script).unwrap();
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+fn feature_probe_in(dir: &Path) -> features::FeatureProbe {
+    features::FeatureProbe {
+        runtime_path: dir.join("runc"),
+        search_path: Some(dir.join("bin").into_os_string()),
+        max_user_namespaces: dir.join("max_user_namespaces"),
+        cgroup_root: dir.join("cgroup"),
+    }
+}
+
+#[test]
+fn runtime_handler_probe_reports_supported_features() {
+    let dir = tempdir().unwrap();
+    write_probe_executable(
+        &dir.path().join("runc"),
+        "#!/bin/sh\n[ \"$1\" = checkpoint ] && exit 0\nexit 1\n",
+    );
+    fs::create_dir_all(dir.path().join("bin")).unwrap();
+    write_probe_executable(&dir.path().join("bin/criu"), "#!/bin/sh\nexit 0\n");
+    fs::write(dir.path().join("max_user_namespaces"), "15000\n").unwrap();
+    fs::create_dir_all(dir.path().join("cgroup")).unwrap();
+    fs::write(
+        dir.path().join("cgroup/cgroup.controllers"),
+        "pids memory cpuset cpu io\n",
     )
     .unwrap();
 
-    service.containers.lock().await.insert(
-        "container-running".to_string(),
-        test_container("container-running", "pod-1", annotations),
+    let features = feature_probe_in(dir.path()).probe();
+    assert!(features.supports_checkpoint());
+    assert!(features.user_namespaces);
+    assert_eq!(
+        features.cgroup_controllers,
+        vec!["cpu", "cpuset", "io", "memory", "pids"]
     );
-    set_fake_runtime_state(&dir, "container-running", "running");
+}
 
-    let bundle_dir = dir.path().join("runtime-root").join("container-running");
+#[test]
+fn runtime_handler_probe_reports_missing_features() {
+    let dir = tempdir().unwrap();
+    // 运行时不认识 checkpoint，criu 不可执行，用户命名空间被禁用
+    write_probe_executable(&dir.path().join("runc"), "#!/bin/sh\nexit 1\n");
+    fs::create_dir_all(dir.path().join("bin")).unwrap();
+    fs::write(dir.path().join("bin/criu"), "").unwrap();
+    fs::write(dir.path().join("max_user_namespaces"), "0\n").unwrap();
+    for controller in ["cpu,cpuacct", "memory", "systemd", "unified"] {
+        fs::create_dir_all(dir.path().join("cgroup").join(controller)).unwrap();
+    }
+
+    let features = feature_probe_in(dir.path()).probe();
+    assert!(!features.checkpoint);
+    assert!(!features.criu);
+    assert!(!features.user_namespaces);
+    assert_eq!(
+        features.cgroup_controllers,
+        vec!["cpu", "cpuacct", "memory"]
+    );
+
+    let features = features::FeatureProbe {
+        runtime_path: dir.path().join("missing-runc"),
+        ..feature_probe_in(dir.path())
+    }
+    .probe();
+    assert!(!features.checkpoint);
+}
+
+#[tokio::test]
+async fn checkpoint_container_is_unimplemented_without_criu() {
+    let (dir, service) = test_service_with_fake_runtime();
+    set_handler_features(&service, false);
+    service.containers.write().await.insert(
+        "container-running".to_string(),
+        test_container("container-running", "pod-1", HashMap::new()),
+    );
+    set_fake_runtime_state(&dir, "container-running", "running");
+
+    let err = RuntimeService::checkpoint_container(
+        &service,
+        Request::new(CheckpointContainerRequest {
+            container_id: "container-running".to_string(),
+            location: dir.path().join("checkpoint.json").display().to_string(),
+            timeout: 30,
+        }),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::Unimplemented);
+    assert!(!dir.path().join("checkpoint.json").exists());
+
+    let status = RuntimeService::status(&service, Request::new(StatusRequest { verbose: true }))
+        .await
+        .unwrap()
+        .into_inner();
+    let info: serde_json::Value = serde_json::from_str(&status.info["config"]).unwrap();
+    assert_eq!(info["runtimeFeatures"]["checkpointContainer"], false);
+    assert_eq!(info["runtimeHandlerFeatures"]["runc"]["checkpoint"], true);
+    assert_eq!(info["runtimeHandlerFeatures"]["runc"]["criu"], false);
+}
+
+/// 固定各 handler 的特性，避免依赖宿主机是否安装 CRIU
+fn set_handler_features(service: &RuntimeServiceImpl, criu: bool) {
+    let features = features::RuntimeHandlerFeatures {
+        checkpoint: true,
+        criu,
+        ..Default::default()
+    };
+    service
+        .handler_features
+        .set(
+            service
+                .config
+                .runtime_handlers
+                .iter()
+                .map(|handler| (handler.clone(), features.clone()))
+                .collect(),
+        )
+        .unwrap();
+}
+
+#[tokio::test]
+async fn checkpoint_container_writes_checkpoint_artifact() {
+    let (dir, service) = test_service_with_fake_runtime();
+    set_handler_features(&service, true);
+    let mut annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+        &StoredContainerState {
+            log_path: Some(dir.path().join("logs").join("c1.log").display().to_string()),
+            metadata_name: Some("checkpointed".to_string()),
+            metadata_attempt: Some(2),
+            started_at: Some(10),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    service.containers.write().await.insert(
+        "container-running".to_string(),
+        test_container("container-running", "pod-1", annotations),
+    );
+    set_fake_runtime_state(&dir, "container-running", "running");
+
+    let bundle_dir = dir.path().join("runtime-root").join("container-running");
     fs::create_dir_all(&bundle_dir).unwrap();
     let rootfs_dir = dir.path().join("checkpoint-rootfs-json");
     fs::create_dir_all(&rootfs_dir).unwrap();
@@ -3286,6 +4962,7 @@ async fn checkpoint_container_writes_checkpoint_artifact() {
 #[tokio::test]
 async fn checkpoint_container_writes_tar_export_when_location_is_archive() {
     let (dir, service) = test_service_with_fake_runtime();
+    set_handler_features(&service, true);
     let mut annotations = HashMap::new();
     RuntimeServiceImpl::insert_internal_state(
         &mut annotations,
@@ -3298,7 +4975,7 @@ async fn checkpoint_container_writes_tar_export_when_location_is_archive() {
     )
     .unwrap();
 
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "container-running".to_string(),
         test_container("container-running", "pod-1", annotations),
     );
@@ -3395,7 +5072,7 @@ async fn start_container_restores_from_checkpoint_artifact_and_clears_pending_ma
     )
     .unwrap();
 
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "restore-container".to_string(),
         Container {
             state: ContainerState::ContainerCreated as i32,
@@ -3443,7 +5120,7 @@ async fn start_container_restores_from_checkpoint_artifact_and_clears_pending_ma
 
     let container = service
         .containers
-        .lock()
+        .read()
         .await
         .get("restore-container")
         .cloned()
@@ -3525,11 +5202,11 @@ async fn start_container_notifies_nri_before_and_after_runtime_start() {
     )
     .unwrap();
 
-    service.pod_sandboxes.lock().await.insert(
+    service.pod_sandboxes.write().await.insert(
         "pod-start".to_string(),
         test_pod("pod-start", HashMap::new()),
     );
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "container-start".to_string(),
         test_container("container-start", "pod-start", annotations.clone()),
     );
@@ -3564,7 +5241,7 @@ async fn start_container_notifies_nri_before_and_after_runtime_start() {
     );
     let container = service
         .containers
-        .lock()
+        .read()
         .await
         .get("container-start")
         .cloned()
@@ -3585,6 +5262,222 @@ async fn start_container_notifies_nri_before_and_after_runtime_start() {
     assert!(post_start_event.container.started_at > 0);
 }
 
+#[tokio::test]
+async fn create_then_start_container_marks_it_running() {
+    let (_dir, service) = test_service_with_fake_runtime();
+    service
+        .pod_sandboxes
+        .write()
+        .await
+        .insert("pod-1".to_string(), test_pod("pod-1", HashMap::new()));
+
+    let missing = RuntimeService::start_container(
+        &service,
+        Request::new(StartContainerRequest {
+            container_id: "missing".to_string(),
+        }),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(missing.code(), tonic::Code::NotFound);
+
+    let container_id = RuntimeService::create_container(
+        &service,
+        Request::new(CreateContainerRequest {
+            pod_sandbox_id: "pod-1".to_string(),
+            config: Some(crate::proto::runtime::v1::ContainerConfig {
+                metadata: Some(crate::proto::runtime::v1::ContainerMetadata {
+                    name: "app".to_string(),
+                    attempt: 0,
+                }),
+                ..Default::default()
+            }),
+            sandbox_config: None,
+        }),
+    )
+    .await
+    .unwrap()
+    .into_inner()
+    .container_id;
+    assert_eq!(
+        service.containers.read().await[&container_id].state,
+        ContainerState::ContainerCreated as i32
+    );
+
+    RuntimeService::start_container(
+        &service,
+        Request::new(StartContainerRequest {
+            container_id: container_id.clone(),
+        }),
+    )
+    .await
+    .unwrap();
+
+    let container = service.containers.read().await[&container_id].clone();
+    assert_eq!(container.state, ContainerState::ContainerRunning as i32);
+    let internal_state = RuntimeServiceImpl::read_internal_state::<StoredContainerState>(
+        &container.annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+    )
+    .unwrap();
+    assert!(internal_state.started_at.unwrap() >= container.created_at);
+}
+
+#[tokio::test]
+async fn create_and_start_container_record_phase_timings() {
+    let (_dir, service) = test_service_with_fake_runtime();
+    service
+        .pod_sandboxes
+        .write()
+        .await
+        .insert("pod-1".to_string(), test_pod("pod-1", HashMap::new()));
+
+    let container_id = RuntimeService::create_container(
+        &service,
+        Request::new(CreateContainerRequest {
+            pod_sandbox_id: "pod-1".to_string(),
+            config: Some(crate::proto::runtime::v1::ContainerConfig {
+                metadata: Some(crate::proto::runtime::v1::ContainerMetadata {
+                    name: "app".to_string(),
+                    attempt: 0,
+                }),
+                ..Default::default()
+            }),
+            sandbox_config: None,
+        }),
+    )
+    .await
+    .unwrap()
+    .into_inner()
+    .container_id;
+    RuntimeService::start_container(
+        &service,
+        Request::new(StartContainerRequest {
+            container_id: container_id.clone(),
+        }),
+    )
+    .await
+    .unwrap();
+
+    let container = service.containers.read().await[&container_id].clone();
+    let internal_state = RuntimeServiceImpl::read_internal_state::<StoredContainerState>(
+        &container.annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+    )
+    .unwrap();
+    assert_eq!(
+        internal_state
+            .create_timings
+            .keys()
+            .map(String::as_str)
+            .collect::<Vec<_>>(),
+        vec!["snapshot", "spec", "total"]
+    );
+    assert!(internal_state.create_timings["total"] >= internal_state.create_timings["spec"]);
+    assert!(internal_state.start_timings.contains_key("runtime_start"));
+    assert!(internal_state.start_timings.contains_key("total"));
+
+    let response = RuntimeService::container_status(
+        &service,
+        Request::new(ContainerStatusRequest {
+            container_id: container_id.clone(),
+            verbose: true,
+        }),
+    )
+    .await
+    .unwrap()
+    .into_inner();
+    let info: serde_json::Value = serde_json::from_str(response.info.get("info").unwrap()).unwrap();
+    assert!(info["timings"]["createContainer"]["snapshot"].is_number());
+    assert!(info["timings"]["startContainer"]["runtime_start"].is_number());
+
+    let response = RuntimeService::status(&service, Request::new(StatusRequest { verbose: true }))
+        .await
+        .unwrap()
+        .into_inner();
+    let config: serde_json::Value =
+        serde_json::from_str(response.info.get("config").unwrap()).unwrap();
+    let operations = &config["creationMetrics"]["operations"];
+    assert!(
+        operations["create_container"]["spec"]["count"]
+            .as_u64()
+            .unwrap()
+            >= 1
+    );
+    assert!(
+        operations["start_container"]["runtime_start"]["count"]
+            .as_u64()
+            .unwrap()
+            >= 1
+    );
+    assert_eq!(
+        config["creationMetrics"]["bucketsSecs"]
+            .as_array()
+            .unwrap()
+            .len(),
+        crate::metrics::creation::DURATION_BUCKETS_SECS.len()
+    );
+}
+
+#[tokio::test]
+async fn start_container_twice_starts_runtime_once() {
+    let fake_nri = Arc::new(FakeNri::default());
+    let (dir, service) = test_service_with_fake_runtime_and_nri(fake_nri.clone());
+
+    let mut annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+        &StoredContainerState::default(),
+    )
+    .unwrap();
+    service.pod_sandboxes.write().await.insert(
+        "pod-retry".to_string(),
+        test_pod("pod-retry", HashMap::new()),
+    );
+    service.containers.write().await.insert(
+        "container-retry".to_string(),
+        test_container("container-retry", "pod-retry", annotations.clone()),
+    );
+    write_test_bundle_config(&dir, "container-retry", &annotations);
+
+    let start = || {
+        RuntimeService::start_container(
+            &service,
+            Request::new(StartContainerRequest {
+                container_id: "container-retry".to_string(),
+            }),
+        )
+    };
+    // runc 已创建容器，init 进程存活
+    let state_dir = dir.path().join("runtime-state");
+    fs::write(state_dir.join("container-retry.state"), "created").unwrap();
+    fs::write(
+        state_dir.join("container-retry.pid"),
+        std::process::id().to_string(),
+    )
+    .unwrap();
+    start().await.unwrap();
+    start().await.unwrap();
+
+    let starts_path = dir.path().join("shims/container-retry/starts");
+    assert_eq!(fs::read_to_string(&starts_path).unwrap().lines().count(), 1);
+    assert_eq!(
+        fake_nri.calls.lock().await.clone(),
+        vec!["start_container", "post_start_container"]
+    );
+    assert_eq!(
+        service.containers.read().await["container-retry"].state,
+        ContainerState::ContainerRunning as i32
+    );
+
+    // 进程已不存在的容器不会被再次启动
+    fs::write(state_dir.join("container-retry.pid"), "0").unwrap();
+    let err = start().await.unwrap_err();
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+    assert_eq!(fs::read_to_string(&starts_path).unwrap().lines().count(), 1);
+}
+
 #[tokio::test]
 async fn start_container_succeeds_when_nri_post_start_fails() {
     let fake_nri = Arc::new(FakeNri {
@@ -3601,11 +5494,11 @@ async fn start_container_succeeds_when_nri_post_start_fails() {
     )
     .unwrap();
 
-    service.pod_sandboxes.lock().await.insert(
+    service.pod_sandboxes.write().await.insert(
         "pod-start-post-fail".to_string(),
         test_pod("pod-start-post-fail", HashMap::new()),
     );
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "container-start-post-fail".to_string(),
         test_container(
             "container-start-post-fail",
@@ -3640,7 +5533,7 @@ async fn start_container_succeeds_when_nri_post_start_fails() {
 
     let container = service
         .containers
-        .lock()
+        .read()
         .await
         .get("container-start-post-fail")
         .cloned()
@@ -3665,11 +5558,11 @@ async fn start_container_undoes_nri_when_runtime_start_fails() {
     )
     .unwrap();
 
-    service.pod_sandboxes.lock().await.insert(
+    service.pod_sandboxes.write().await.insert(
         "pod-start-fail".to_string(),
         test_pod("pod-start-fail", HashMap::new()),
     );
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "container-start-fail".to_string(),
         test_container(
             "container-start-fail",
@@ -3708,7 +5601,7 @@ async fn start_container_undoes_nri_when_runtime_start_fails() {
         updates: vec![update],
     });
 
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "container-sidecar".to_string(),
         test_container("container-sidecar", "pod-start-fail", annotations.clone()),
     );
@@ -3745,18 +5638,24 @@ async fn start_container_undoes_nri_when_runtime_start_fails() {
     );
     let container = service
         .containers
-        .lock()
+        .read()
         .await
         .get("container-start-fail")
         .cloned()
         .unwrap();
-    assert_eq!(container.state, ContainerState::ContainerCreated as i32);
+    assert_eq!(container.state, ContainerState::ContainerExited as i32);
     let state = RuntimeServiceImpl::read_internal_state::<StoredContainerState>(
         &container.annotations,
         INTERNAL_CONTAINER_STATE_KEY,
     )
     .unwrap();
     assert!(state.nri_stop_notified);
+    assert_eq!(state.exit_code, Some(START_ERROR_EXIT_CODE));
+    assert_eq!(state.exit_reason.as_deref(), Some(START_ERROR_REASON));
+    assert!(state
+        .exit_message
+        .as_deref()
+        .is_some_and(|message| message.contains("Failed to start shim process")));
 
     let sidecar_update_payload: serde_json::Value = serde_json::from_str(
         &fs::read_to_string(fake_runtime_update_path(&dir, "container-sidecar")).unwrap(),
@@ -3765,15 +5664,13 @@ async fn start_container_undoes_nri_when_runtime_start_fails() {
     assert_eq!(sidecar_update_payload["cpu"]["shares"], 256);
 }
 
-#[tokio::test]
-#[allow(clippy::await_holding_lock)]
-async fn remove_after_failed_start_does_not_repeat_nri_stop() {
-    let _guard = env_lock().lock().unwrap();
-    std::env::set_var("CRIUS_SHIM_PATH", "/definitely/missing/crius-shim");
-    let fake_nri = Arc::new(FakeNri::default());
-    let (dir, service) = test_service_with_fake_runtime_and_nri(fake_nri.clone());
-    std::env::remove_var("CRIUS_SHIM_PATH");
-
+/// 启动容器，等待 ContainerStatus 报告 EXITED
+async fn start_and_wait_for_exit(
+    service: &RuntimeServiceImpl,
+    dir: &TempDir,
+    container_id: &str,
+    marker: (&str, &str),
+) -> CriContainerStatus {
     let mut annotations = HashMap::new();
     RuntimeServiceImpl::insert_internal_state(
         &mut annotations,
@@ -3781,49 +5678,422 @@ async fn remove_after_failed_start_does_not_repeat_nri_stop() {
         &StoredContainerState::default(),
     )
     .unwrap();
-
-    service.pod_sandboxes.lock().await.insert(
-        "pod-start-remove".to_string(),
-        test_pod("pod-start-remove", HashMap::new()),
-    );
-    service.containers.lock().await.insert(
-        "container-start-remove".to_string(),
-        test_container(
-            "container-start-remove",
-            "pod-start-remove",
-            annotations.clone(),
-        ),
+    service.containers.write().await.insert(
+        container_id.to_string(),
+        test_container(container_id, "pod-exit", annotations.clone()),
     );
-    service
-        .persistence
-        .lock()
-        .await
-        .save_container(
-            "container-start-remove",
-            "pod-start-remove",
-            crate::runtime::ContainerStatus::Created,
-            "busybox:latest",
-            &Vec::new(),
-            &HashMap::new(),
-            &annotations,
-        )
-        .unwrap();
-    write_test_bundle_config(&dir, "container-start-remove", &annotations);
+    write_test_bundle_config(dir, container_id, &annotations);
+    let shim_dir = dir.path().join("shims").join(container_id);
+    fs::create_dir_all(&shim_dir).unwrap();
+    fs::write(shim_dir.join(marker.0), marker.1).unwrap();
 
-    let err = RuntimeService::start_container(
-        &service,
+    RuntimeService::start_container(
+        service,
         Request::new(StartContainerRequest {
-            container_id: "container-start-remove".to_string(),
-        }),
-    )
-    .await
-    .expect_err("runtime start failure should be surfaced");
-    assert_eq!(err.code(), tonic::Code::Internal);
-
-    RuntimeService::remove_container(
-        &service,
-        Request::new(RemoveContainerRequest {
-            container_id: "container-start-remove".to_string(),
+            container_id: container_id.to_string(),
+        }),
+    )
+    .await
+    .unwrap();
+    wait_for_container_exit(service, container_id).await
+}
+
+async fn wait_for_container_exit(
+    service: &RuntimeServiceImpl,
+    container_id: &str,
+) -> CriContainerStatus {
+    for _ in 0..50 {
+        let status = RuntimeService::container_status(
+            service,
+            Request::new(ContainerStatusRequest {
+                container_id: container_id.to_string(),
+                verbose: false,
+            }),
+        )
+        .await
+        .unwrap()
+        .into_inner()
+        .status
+        .unwrap();
+        if status.state == ContainerState::ContainerExited as i32 {
+            return status;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("container {} did not exit", container_id);
+}
+
+#[tokio::test]
+async fn container_status_reports_start_error_of_bad_command() {
+    let (dir, service) = test_service_with_fake_runtime_and_nri(Arc::new(FakeNri::default()));
+    service
+        .pod_sandboxes
+        .write()
+        .await
+        .insert("pod-exit".to_string(), test_pod("pod-exit", HashMap::new()));
+
+    let message = r#"exec: "/no/such/cmd": stat /no/such/cmd: no such file or directory"#;
+    let status =
+        start_and_wait_for_exit(&service, &dir, "container-bad-cmd", ("fail-start", message)).await;
+
+    assert_eq!(status.exit_code, START_ERROR_EXIT_CODE);
+    assert_eq!(status.reason, START_ERROR_REASON);
+    assert_eq!(status.message, message);
+    assert_ne!(status.finished_at, 0);
+}
+
+#[tokio::test]
+async fn container_status_reports_real_exit_code_from_shim() {
+    let (dir, service) = test_service_with_fake_runtime_and_nri(Arc::new(FakeNri::default()));
+    service
+        .pod_sandboxes
+        .write()
+        .await
+        .insert("pod-exit".to_string(), test_pod("pod-exit", HashMap::new()));
+
+    let clean =
+        start_and_wait_for_exit(&service, &dir, "container-clean", ("exit-with", "0")).await;
+    assert_eq!(clean.exit_code, 0);
+    assert_eq!(clean.reason, "Completed");
+
+    let failed =
+        start_and_wait_for_exit(&service, &dir, "container-failed", ("exit-with", "3")).await;
+    assert_eq!(failed.exit_code, 3);
+    assert_eq!(failed.reason, "Error");
+    assert_eq!(failed.message, "container exited with code 3");
+}
+
+async fn start_with_termination_message(
+    service: &RuntimeServiceImpl,
+    dir: &TempDir,
+    container_id: &str,
+    termination_message_path: &str,
+    mounts: serde_json::Value,
+    exit_code: i32,
+) -> CriContainerStatus {
+    let mut annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+        &StoredContainerState {
+            termination_message_path: Some(termination_message_path.to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    service.containers.write().await.insert(
+        container_id.to_string(),
+        test_container(container_id, "pod-exit", annotations.clone()),
+    );
+    let bundle_dir = dir.path().join("runtime-root").join(container_id);
+    fs::create_dir_all(&bundle_dir).unwrap();
+    fs::write(
+        bundle_dir.join("config.json"),
+        serde_json::json!({
+            "ociVersion": "1.0.2",
+            "annotations": annotations,
+            "root": {"path": dir.path().join("term-rootfs")},
+            "mounts": mounts,
+        })
+        .to_string(),
+    )
+    .unwrap();
+    let shim_dir = dir.path().join("shims").join(container_id);
+    fs::create_dir_all(&shim_dir).unwrap();
+    fs::write(shim_dir.join("exit-with"), exit_code.to_string()).unwrap();
+
+    RuntimeService::start_container(
+        service,
+        Request::new(StartContainerRequest {
+            container_id: container_id.to_string(),
+        }),
+    )
+    .await
+    .unwrap();
+    wait_for_container_exit(service, container_id).await
+}
+
+#[tokio::test]
+async fn container_status_reports_termination_message_written_by_container() {
+    let (dir, service) = test_service_with_fake_runtime_and_nri(Arc::new(FakeNri::default()));
+    service
+        .pod_sandboxes
+        .write()
+        .await
+        .insert("pod-exit".to_string(), test_pod("pod-exit", HashMap::new()));
+    let rootfs = dir.path().join("term-rootfs");
+    fs::create_dir_all(rootfs.join("dev")).unwrap();
+    let message = format!("{}database unreachable", "x".repeat(5000));
+    fs::write(rootfs.join("dev/termination-log"), &message).unwrap();
+    let host_log = dir.path().join("kubelet-termination-log");
+    fs::write(&host_log, "written through kubelet mount").unwrap();
+    std::os::unix::fs::symlink(&host_log, rootfs.join("dev/escape")).unwrap();
+
+    let from_rootfs = start_with_termination_message(
+        &service,
+        &dir,
+        "container-term-rootfs",
+        "/dev/termination-log",
+        serde_json::json!([]),
+        1,
+    )
+    .await;
+    assert_eq!(from_rootfs.exit_code, 1);
+    assert_eq!(from_rootfs.reason, "Error");
+    assert_eq!(from_rootfs.message.len(), 4096);
+    assert!(message.ends_with(&from_rootfs.message));
+
+    let from_mount = start_with_termination_message(
+        &service,
+        &dir,
+        "container-term-mount",
+        "/dev/kubelet-log",
+        serde_json::json!([{
+            "destination": "/dev/kubelet-log",
+            "source": host_log,
+            "type": "bind",
+        }]),
+        0,
+    )
+    .await;
+    assert_eq!(from_mount.reason, "Completed");
+    assert_eq!(from_mount.message, "written through kubelet mount");
+
+    for (container_id, path) in [
+        ("container-term-missing", "/dev/missing"),
+        ("container-term-escape", "/dev/escape"),
+    ] {
+        let status = start_with_termination_message(
+            &service,
+            &dir,
+            container_id,
+            path,
+            serde_json::json!([]),
+            3,
+        )
+        .await;
+        assert_eq!(status.reason, "Error", "{}", path);
+        assert_eq!(status.message, "container exited with code 3", "{}", path);
+    }
+}
+
+async fn verbose_container_status(
+    service: &RuntimeServiceImpl,
+    container_id: &str,
+) -> ContainerStatusResponse {
+    RuntimeService::container_status(
+        service,
+        Request::new(ContainerStatusRequest {
+            container_id: container_id.to_string(),
+            verbose: true,
+        }),
+    )
+    .await
+    .unwrap()
+    .into_inner()
+}
+
+#[tokio::test]
+async fn admin_pause_and_resume_freeze_and_thaw_running_container() {
+    let (dir, service) = test_service_with_fake_runtime();
+    let mut annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+        &StoredContainerState::default(),
+    )
+    .unwrap();
+    let mut container = test_container("container-freeze", "pod-freeze", annotations);
+    container.state = ContainerState::ContainerRunning as i32;
+    service
+        .containers
+        .write()
+        .await
+        .insert("container-freeze".to_string(), container);
+    set_fake_runtime_state(&dir, "container-freeze", "running");
+
+    service
+        .pause_container(Request::new(crate::server::admin::PauseContainerRequest {
+            container_id: "container-freeze".to_string(),
+        }))
+        .await
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(fake_runtime_state_path(&dir, "container-freeze"))
+            .unwrap()
+            .trim(),
+        "paused"
+    );
+    let paused = verbose_container_status(&service, "container-freeze").await;
+    let paused_status = paused.status.unwrap();
+    assert_eq!(paused_status.state, ContainerState::ContainerRunning as i32);
+    assert_eq!(paused_status.reason, PAUSED_REASON);
+    let info: serde_json::Value = serde_json::from_str(paused.info.get("info").unwrap()).unwrap();
+    assert_eq!(info["paused"], true);
+
+    service
+        .resume_container(Request::new(crate::server::admin::ResumeContainerRequest {
+            container_id: "container-freeze".to_string(),
+        }))
+        .await
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(fake_runtime_state_path(&dir, "container-freeze"))
+            .unwrap()
+            .trim(),
+        "running"
+    );
+    let resumed = verbose_container_status(&service, "container-freeze")
+        .await
+        .status
+        .unwrap();
+    assert_eq!(resumed.state, ContainerState::ContainerRunning as i32);
+    assert_eq!(resumed.reason, "Running");
+
+    set_fake_runtime_state(&dir, "container-freeze", "stopped");
+    let err = service
+        .pause_container(Request::new(crate::server::admin::PauseContainerRequest {
+            container_id: "container-freeze".to_string(),
+        }))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+}
+
+#[tokio::test]
+async fn admin_export_streams_rootfs_tar_without_whiteouts() {
+    use tokio_stream::StreamExt;
+
+    let (dir, service) = test_service_with_fake_runtime();
+    let rootfs = dir.path().join("export-rootfs");
+    fs::create_dir_all(rootfs.join("etc")).unwrap();
+    fs::create_dir_all(rootfs.join("var/cache")).unwrap();
+    fs::write(rootfs.join("etc/hostname"), "demo\n").unwrap();
+    fs::write(rootfs.join("etc/removed.conf"), "stale").unwrap();
+    fs::write(rootfs.join("etc/.wh.removed.conf"), "").unwrap();
+    fs::write(rootfs.join("var/cache/.wh..wh..opq"), "").unwrap();
+    fs::write(rootfs.join("var/cache/data"), "kept").unwrap();
+    let bundle_dir = dir.path().join("runtime-root").join("container-export");
+    fs::create_dir_all(&bundle_dir).unwrap();
+    fs::write(
+        bundle_dir.join("config.json"),
+        serde_json::json!({
+            "ociVersion": "1.0.2",
+            "root": { "path": rootfs.display().to_string() }
+        })
+        .to_string(),
+    )
+    .unwrap();
+    let mut container = test_container("container-export", "pod-export", HashMap::new());
+    container.state = ContainerState::ContainerRunning as i32;
+    service
+        .containers
+        .write()
+        .await
+        .insert("container-export".to_string(), container);
+
+    let mut stream = service
+        .export_container(Request::new(crate::server::admin::ExportContainerRequest {
+            container_id: "container-export".to_string(),
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+    let mut archive = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        archive.extend_from_slice(&chunk.unwrap().data);
+    }
+    let archive_path = dir.path().join("export.tar");
+    fs::write(&archive_path, archive).unwrap();
+
+    let listing = Command::new("tar")
+        .arg("-tf")
+        .arg(&archive_path)
+        .output()
+        .unwrap();
+    assert!(listing.status.success());
+    let listing = String::from_utf8(listing.stdout).unwrap();
+    let entries: Vec<&str> = listing.lines().collect();
+    assert!(entries.contains(&"./etc/hostname"));
+    assert!(entries.contains(&"./var/cache/data"));
+    assert!(!entries.iter().any(|entry| entry.contains(".wh.")));
+    assert!(!entries.contains(&"./etc/removed.conf"));
+
+    let extracted = dir.path().join("extracted");
+    fs::create_dir_all(&extracted).unwrap();
+    let status = Command::new("tar")
+        .arg("-xf")
+        .arg(&archive_path)
+        .arg("-C")
+        .arg(&extracted)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(
+        fs::read_to_string(extracted.join("etc/hostname")).unwrap(),
+        "demo\n"
+    );
+}
+
+#[tokio::test]
+#[allow(clippy::await_holding_lock)]
+async fn remove_after_failed_start_does_not_repeat_nri_stop() {
+    let _guard = env_lock().lock().unwrap();
+    std::env::set_var("CRIUS_SHIM_PATH", "/definitely/missing/crius-shim");
+    let fake_nri = Arc::new(FakeNri::default());
+    let (dir, service) = test_service_with_fake_runtime_and_nri(fake_nri.clone());
+    std::env::remove_var("CRIUS_SHIM_PATH");
+
+    let mut annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+        &StoredContainerState::default(),
+    )
+    .unwrap();
+
+    service.pod_sandboxes.write().await.insert(
+        "pod-start-remove".to_string(),
+        test_pod("pod-start-remove", HashMap::new()),
+    );
+    service.containers.write().await.insert(
+        "container-start-remove".to_string(),
+        test_container(
+            "container-start-remove",
+            "pod-start-remove",
+            annotations.clone(),
+        ),
+    );
+    service
+        .persistence
+        .lock()
+        .await
+        .save_container(
+            "container-start-remove",
+            "pod-start-remove",
+            crate::runtime::ContainerStatus::Created,
+            "busybox:latest",
+            &Vec::new(),
+            &HashMap::new(),
+            &annotations,
+        )
+        .unwrap();
+    write_test_bundle_config(&dir, "container-start-remove", &annotations);
+
+    let err = RuntimeService::start_container(
+        &service,
+        Request::new(StartContainerRequest {
+            container_id: "container-start-remove".to_string(),
+        }),
+    )
+    .await
+    .expect_err("runtime start failure should be surfaced");
+    assert_eq!(err.code(), tonic::Code::Internal);
+
+    RuntimeService::remove_container(
+        &service,
+        Request::new(RemoveContainerRequest {
+            container_id: "container-start-remove".to_string(),
         }),
     )
     .await
@@ -3850,10 +6120,10 @@ async fn stop_container_notifies_nri_only_once_across_repeat_calls() {
 
     service
         .pod_sandboxes
-        .lock()
+        .write()
         .await
         .insert("pod-stop".to_string(), test_pod("pod-stop", HashMap::new()));
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "container-stop".to_string(),
         test_container("container-stop", "pod-stop", annotations.clone()),
     );
@@ -3896,7 +6166,7 @@ async fn stop_container_notifies_nri_only_once_across_repeat_calls() {
     let state = RuntimeServiceImpl::read_internal_state::<StoredContainerState>(
         &service
             .containers
-            .lock()
+            .read()
             .await
             .get("container-stop")
             .unwrap()
@@ -3920,11 +6190,11 @@ async fn stop_container_notifies_nri_with_post_stop_state() {
     )
     .unwrap();
 
-    service.pod_sandboxes.lock().await.insert(
+    service.pod_sandboxes.write().await.insert(
         "pod-stop-state".to_string(),
         test_pod("pod-stop-state", HashMap::new()),
     );
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "container-stop-state".to_string(),
         test_container(
             "container-stop-state",
@@ -3987,11 +6257,11 @@ async fn stop_container_succeeds_when_nri_stop_fails() {
     )
     .unwrap();
 
-    service.pod_sandboxes.lock().await.insert(
+    service.pod_sandboxes.write().await.insert(
         "pod-stop-fail".to_string(),
         test_pod("pod-stop-fail", HashMap::new()),
     );
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "container-stop-fail".to_string(),
         test_container("container-stop-fail", "pod-stop-fail", annotations.clone()),
     );
@@ -4035,7 +6305,7 @@ async fn stop_container_skips_nri_for_already_exited_container() {
     )
     .unwrap();
 
-    service.pod_sandboxes.lock().await.insert(
+    service.pod_sandboxes.write().await.insert(
         "pod-exited".to_string(),
         test_pod("pod-exited", HashMap::new()),
     );
@@ -4043,7 +6313,7 @@ async fn stop_container_skips_nri_for_already_exited_container() {
     container.state = ContainerState::ContainerExited as i32;
     service
         .containers
-        .lock()
+        .write()
         .await
         .insert("container-exited".to_string(), container);
     service
@@ -4088,11 +6358,11 @@ async fn remove_container_notifies_nri_only_once_across_repeat_calls() {
     )
     .unwrap();
 
-    service.pod_sandboxes.lock().await.insert(
+    service.pod_sandboxes.write().await.insert(
         "pod-remove".to_string(),
         test_pod("pod-remove", HashMap::new()),
     );
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "container-remove".to_string(),
         test_container("container-remove", "pod-remove", annotations.clone()),
     );
@@ -4151,11 +6421,11 @@ async fn remove_container_succeeds_when_nri_remove_fails() {
     )
     .unwrap();
 
-    service.pod_sandboxes.lock().await.insert(
+    service.pod_sandboxes.write().await.insert(
         "pod-remove-fail".to_string(),
         test_pod("pod-remove-fail", HashMap::new()),
     );
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "container-remove-fail".to_string(),
         test_container(
             "container-remove-fail",
@@ -4202,11 +6472,11 @@ async fn remove_container_notifies_nri_stop_before_remove_for_running_container(
     )
     .unwrap();
 
-    service.pod_sandboxes.lock().await.insert(
+    service.pod_sandboxes.write().await.insert(
         "pod-remove-running".to_string(),
         test_pod("pod-remove-running", HashMap::new()),
     );
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "container-remove-running".to_string(),
         test_container(
             "container-remove-running",
@@ -4250,11 +6520,11 @@ async fn stop_pod_sandbox_stops_member_containers_before_pod_notification() {
     let fake_nri = Arc::new(FakeNri::default());
     let (dir, service) = test_service_with_fake_runtime_and_nri(fake_nri.clone());
 
-    service.pod_sandboxes.lock().await.insert(
+    service.pod_sandboxes.write().await.insert(
         "pod-stop-order".to_string(),
         test_pod("pod-stop-order", HashMap::new()),
     );
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "container-under-pod-stop".to_string(),
         test_container("container-under-pod-stop", "pod-stop-order", HashMap::new()),
     );
@@ -4295,11 +6565,11 @@ async fn remove_pod_sandbox_removes_member_containers_before_pod_notification()
     let fake_nri = Arc::new(FakeNri::default());
     let (dir, service) = test_service_with_fake_runtime_and_nri(fake_nri.clone());
 
-    service.pod_sandboxes.lock().await.insert(
+    service.pod_sandboxes.write().await.insert(
         "pod-remove-order".to_string(),
         test_pod("pod-remove-order", HashMap::new()),
     );
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "container-under-pod-remove".to_string(),
         test_container(
             "container-under-pod-remove",
@@ -4354,11 +6624,11 @@ async fn start_container_clears_nri_stop_notification_marker() {
     )
     .unwrap();
 
-    service.pod_sandboxes.lock().await.insert(
+    service.pod_sandboxes.write().await.insert(
         "pod-restart".to_string(),
         test_pod("pod-restart", HashMap::new()),
     );
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "container-restart".to_string(),
         test_container("container-restart", "pod-restart", annotations.clone()),
     );
@@ -4432,7 +6702,7 @@ async fn reopen_container_log_validates_running_state_and_log_path() {
         },
     )
     .unwrap();
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "container-stopped".to_string(),
         test_container("container-stopped", "pod-1", stopped_annotations),
     );
@@ -4459,7 +6729,7 @@ async fn reopen_container_log_validates_running_state_and_log_path() {
         },
     )
     .unwrap();
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "container-running".to_string(),
         test_container("container-running", "pod-1", running_annotations),
     );
@@ -4493,7 +6763,7 @@ async fn reopen_container_log_notifies_shim_socket_when_available() {
         },
     )
     .unwrap();
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "container-running".to_string(),
         test_container("container-running", "pod-1", running_annotations),
     );
@@ -4548,7 +6818,7 @@ async fn update_container_resources_validates_state_and_persists_resources() {
     .unwrap_err();
     assert_eq!(missing.code(), tonic::Code::NotFound);
 
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "container-stopped".to_string(),
         test_container("container-stopped", "pod-1", HashMap::new()),
     );
@@ -4576,7 +6846,7 @@ async fn update_container_resources_validates_state_and_persists_resources() {
         &StoredContainerState::default(),
     )
     .unwrap();
-    service.containers.lock().await.insert(
+    service.containers.write().await.insert(
         "container-running".to_string(),
         test_container("container-running", "pod-1", annotations.clone()),
     );
@@ -4662,167 +6932,302 @@ async fn update_container_resources_validates_state_and_persists_resources() {
     )
     .unwrap();
     assert_eq!(update_payload["cpu"]["shares"], 256);
+    assert_eq!(update_payload["cpu"]["cpus"], "0-1");
+    assert_eq!(update_payload["cpu"]["mems"], "0");
     assert_eq!(update_payload["memory"]["limit"], 128 * 1024 * 1024);
 }
 
 #[tokio::test]
-async fn update_container_resources_applies_nri_result_before_post_update() {
-    let fake_nri = Arc::new(FakeNri::default());
-    let (dir, service) = test_service_with_fake_runtime_and_nri(fake_nri.clone());
+async fn linux_resources_cpuset_is_validated_against_configured_sysfs() {
+    let (_dir, service) = test_service_with_fake_runtime();
+    let cpuset = |cpus: &str, mems: &str| crate::proto::runtime::v1::LinuxContainerResources {
+        cpuset_cpus: cpus.to_string(),
+        cpuset_mems: mems.to_string(),
+        ..Default::default()
+    };
 
-    service
-        .pod_sandboxes
-        .lock()
-        .await
-        .insert("pod-1".to_string(), test_pod("pod-1", HashMap::new()));
+    // 读不到在线列表时只校验格式与 NR_CPUS 上限
+    assert!(service
+        .validate_linux_resources(&cpuset("0-63", "0-3"))
+        .is_ok());
+    for (cpus, mems) in [("0,100000", ""), ("0-4294967295", ""), ("", "9000")] {
+        let err = service
+            .validate_linux_resources(&cpuset(cpus, mems))
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+        assert!(
+            err.message().contains("exceeds maximum"),
+            "{}",
+            err.message()
+        );
+    }
 
-    let mut target_annotations = HashMap::new();
-    RuntimeServiceImpl::insert_internal_state(
-        &mut target_annotations,
-        INTERNAL_CONTAINER_STATE_KEY,
-        &StoredContainerState::default(),
+    let sysfs = &service.config.sysfs_root;
+    fs::create_dir_all(sysfs.join("devices/system/cpu")).unwrap();
+    fs::create_dir_all(sysfs.join("devices/system/node")).unwrap();
+    fs::write(sysfs.join("devices/system/cpu/online"), "0-1\n").unwrap();
+    fs::write(sysfs.join("devices/system/node/online"), "0\n").unwrap();
+
+    assert!(service
+        .validate_linux_resources(&cpuset("0-1", "0"))
+        .is_ok());
+    for (cpus, mems, missing) in [("1-2", "", "[2]"), ("", "0-1", "[1]")] {
+        let err = service
+            .validate_linux_resources(&cpuset(cpus, mems))
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+        assert!(err.message().contains(missing), "{}", err.message());
+    }
+}
+
+fn fake_runtime_signals(dir: &TempDir, id: &str) -> Vec<String> {
+    fs::read_to_string(
+        dir.path()
+            .join("runtime-state")
+            .join(format!("{}.signals", id)),
     )
-    .unwrap();
-    service.containers.lock().await.insert(
-        "container-running".to_string(),
-        test_container("container-running", "pod-1", target_annotations.clone()),
+    .unwrap_or_default()
+    .lines()
+    .map(str::to_string)
+    .collect()
+}
+
+#[tokio::test]
+async fn stop_container_with_zero_timeout_kills_immediately() {
+    let (dir, service) = test_service_with_fake_runtime();
+    service.containers.write().await.insert(
+        "container-kill".to_string(),
+        test_container("container-kill", "pod-1", HashMap::new()),
     );
-    set_fake_runtime_state(&dir, "container-running", "running");
-    service
-        .persistence
-        .lock()
-        .await
-        .save_container(
-            "container-running",
-            "pod-1",
-            crate::runtime::ContainerStatus::Running,
-            "busybox:latest",
-            &Vec::new(),
-            &HashMap::new(),
-            &target_annotations,
-        )
-        .unwrap();
+    set_fake_runtime_state(&dir, "container-kill", "running");
+    fs::write(
+        dir.path()
+            .join("runtime-state")
+            .join("container-kill.ignore-term"),
+        "",
+    )
+    .unwrap();
 
-    let mut sidecar_annotations = HashMap::new();
+    let started = std::time::Instant::now();
+    RuntimeService::stop_container(
+        &service,
+        Request::new(StopContainerRequest {
+            container_id: "container-kill".to_string(),
+            timeout: 0,
+        }),
+    )
+    .await
+    .unwrap();
+
+    assert!(started.elapsed() < std::time::Duration::from_secs(1));
+    assert_eq!(fake_runtime_signals(&dir, "container-kill"), vec!["KILL"]);
+    assert_eq!(
+        service
+            .containers
+            .read()
+            .await
+            .get("container-kill")
+            .unwrap()
+            .state,
+        ContainerState::ContainerExited as i32
+    );
+}
+
+#[tokio::test]
+async fn stop_container_waits_for_requested_grace_period_before_kill() {
+    let (dir, service) = test_service_with_fake_runtime();
+    let mut grace_annotations = HashMap::new();
     RuntimeServiceImpl::insert_internal_state(
-        &mut sidecar_annotations,
+        &mut grace_annotations,
         INTERNAL_CONTAINER_STATE_KEY,
         &StoredContainerState::default(),
     )
     .unwrap();
-    service.containers.lock().await.insert(
-        "container-sidecar".to_string(),
-        test_container("container-sidecar", "pod-1", sidecar_annotations.clone()),
+    service.containers.write().await.insert(
+        "container-grace".to_string(),
+        test_container("container-grace", "pod-1", grace_annotations),
     );
-    set_fake_runtime_state(&dir, "container-sidecar", "running");
-    service
-        .persistence
-        .lock()
-        .await
-        .save_container(
-            "container-sidecar",
-            "pod-1",
-            crate::runtime::ContainerStatus::Running,
-            "busybox:latest",
-            &Vec::new(),
-            &HashMap::new(),
-            &sidecar_annotations,
-        )
-        .unwrap();
-
-    let mut target_resources = crate::nri_proto::api::LinuxResources::new();
-    let mut target_cpu = crate::nri_proto::api::LinuxCPU::new();
-    let mut shares = crate::nri_proto::api::OptionalUInt64::new();
-    shares.value = 1024;
-    target_cpu.shares = protobuf::MessageField::some(shares);
-    target_resources.cpu = protobuf::MessageField::some(target_cpu);
-
-    let mut sidecar_update = crate::nri_proto::api::ContainerUpdate::new();
-    sidecar_update.container_id = "container-sidecar".to_string();
-    let mut sidecar_linux = crate::nri_proto::api::LinuxContainerUpdate::new();
-    let mut sidecar_resources = crate::nri_proto::api::LinuxResources::new();
-    let mut sidecar_memory = crate::nri_proto::api::LinuxMemory::new();
-    let mut sidecar_limit = crate::nri_proto::api::OptionalInt64::new();
-    sidecar_limit.value = 64 * 1024 * 1024;
-    sidecar_memory.limit = protobuf::MessageField::some(sidecar_limit);
-    sidecar_resources.memory = protobuf::MessageField::some(sidecar_memory);
-    sidecar_linux.resources = protobuf::MessageField::some(sidecar_resources);
-    sidecar_update.linux = protobuf::MessageField::some(sidecar_linux);
-
-    *fake_nri.update_result.lock().await = Some(NriUpdateContainerResult {
-        linux_resources: Some(target_resources),
-        updates: vec![sidecar_update],
-        evictions: Vec::new(),
-    });
+    service.containers.write().await.insert(
+        "container-graceful".to_string(),
+        test_container("container-graceful", "pod-1", HashMap::new()),
+    );
+    set_fake_runtime_state(&dir, "container-grace", "running");
+    set_fake_runtime_state(&dir, "container-graceful", "running");
+    fs::write(
+        dir.path()
+            .join("runtime-state")
+            .join("container-grace.ignore-term"),
+        "",
+    )
+    .unwrap();
 
-    RuntimeService::update_container_resources(
+    let started = std::time::Instant::now();
+    RuntimeService::stop_container(
         &service,
-        Request::new(UpdateContainerResourcesRequest {
-            container_id: "container-running".to_string(),
-            linux: Some(crate::proto::runtime::v1::LinuxContainerResources {
-                cpu_shares: 256,
-                ..Default::default()
-            }),
-            windows: None,
-            annotations: HashMap::new(),
+        Request::new(StopContainerRequest {
+            container_id: "container-grace".to_string(),
+            timeout: 2,
         }),
     )
     .await
     .unwrap();
+    let elapsed = started.elapsed();
+    assert!(elapsed >= std::time::Duration::from_secs(2));
+    assert!(elapsed < std::time::Duration::from_secs(4));
+    assert_eq!(
+        fake_runtime_signals(&dir, "container-grace"),
+        vec!["TERM", "KILL"]
+    );
+    let escalated = service.containers.read().await["container-grace"].clone();
+    assert_eq!(escalated.state, ContainerState::ContainerExited as i32);
+    let escalated_state = RuntimeServiceImpl::read_internal_state::<StoredContainerState>(
+        &escalated.annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+    )
+    .unwrap();
+    assert!(escalated_state.finished_at.is_some());
+    assert!(escalated_state.exit_code.is_some());
 
-    let target_payload: serde_json::Value = serde_json::from_str(
-        &fs::read_to_string(fake_runtime_update_path(&dir, "container-running")).unwrap(),
+    let started = std::time::Instant::now();
+    RuntimeService::stop_container(
+        &service,
+        Request::new(StopContainerRequest {
+            container_id: "container-graceful".to_string(),
+            timeout: 30,
+        }),
     )
+    .await
     .unwrap();
-    assert_eq!(target_payload["cpu"]["shares"], 1024);
+    assert!(started.elapsed() < std::time::Duration::from_secs(2));
+    assert_eq!(
+        fake_runtime_signals(&dir, "container-graceful"),
+        vec!["TERM"]
+    );
+}
 
-    let sidecar_payload: serde_json::Value = serde_json::from_str(
-        &fs::read_to_string(fake_runtime_update_path(&dir, "container-sidecar")).unwrap(),
+#[tokio::test]
+async fn stop_container_sends_image_stop_signal() {
+    let (dir, service) = test_service_with_fake_runtime();
+    let image_dir = dir
+        .path()
+        .join("root")
+        .join("storage")
+        .join("images")
+        .join("sha256:nginx");
+    fs::create_dir_all(&image_dir).unwrap();
+    fs::write(
+        image_dir.join("metadata.json"),
+        serde_json::json!({
+            "id": "sha256:nginx",
+            "repo_tags": ["docker.io/library/nginx:latest"],
+            "stop_signal": "SIGQUIT",
+        })
+        .to_string(),
     )
     .unwrap();
-    assert_eq!(sidecar_payload["memory"]["limit"], 64 * 1024 * 1024);
 
+    let stop_signal = service
+        .container_stop_signal("docker.io/library/nginx:latest", &HashMap::new())
+        .unwrap();
+    assert_eq!(stop_signal.as_deref(), Some("SIGQUIT"));
+    let override_annotations =
+        HashMap::from([(STOP_SIGNAL_ANNOTATION.to_string(), "SIGINT".to_string())]);
     assert_eq!(
-        fake_nri.calls.lock().await.clone(),
-        vec!["update_container", "post_update_container"]
+        service
+            .container_stop_signal("docker.io/library/nginx:latest", &override_annotations)
+            .unwrap()
+            .as_deref(),
+        Some("SIGINT")
     );
+    let invalid_annotations =
+        HashMap::from([(STOP_SIGNAL_ANNOTATION.to_string(), "SIGNOPE".to_string())]);
+    let err = service
+        .container_stop_signal("docker.io/library/nginx:latest", &invalid_annotations)
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
 
-    let post_update_event = fake_nri
-        .post_update_events
-        .lock()
-        .await
-        .last()
-        .cloned()
-        .expect("post update event should be recorded");
-    let post_update_linux = post_update_event
-        .container
-        .linux
-        .as_ref()
-        .expect("post update container should include linux state");
-    assert_eq!(
-        post_update_linux
-            .resources
-            .as_ref()
-            .and_then(|resources| resources.cpu.as_ref())
-            .and_then(|cpu| cpu.shares.as_ref())
-            .map(|value| value.value),
-        Some(1024)
+    let annotations = HashMap::from([(STOP_SIGNAL_ANNOTATION.to_string(), stop_signal.unwrap())]);
+    service.containers.write().await.insert(
+        "container-quit".to_string(),
+        test_container("container-quit", "pod-1", annotations.clone()),
     );
+    write_test_bundle_config(&dir, "container-quit", &annotations);
+    set_fake_runtime_state(&dir, "container-quit", "running");
+
+    RuntimeService::stop_container(
+        &service,
+        Request::new(StopContainerRequest {
+            container_id: "container-quit".to_string(),
+            timeout: 30,
+        }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(fake_runtime_signals(&dir, "container-quit"), vec!["QUIT"]);
 }
 
 #[tokio::test]
-async fn update_container_resources_succeeds_when_nri_post_update_fails() {
-    let fake_nri = Arc::new(FakeNri {
-        fail_post_update_container: true,
-        ..Default::default()
-    });
-    let (dir, service) = test_service_with_fake_runtime_and_nri(fake_nri.clone());
+async fn remove_container_waits_for_in_flight_stop() {
+    let (dir, service) = test_service_with_fake_runtime();
+    service.containers.write().await.insert(
+        "container-race".to_string(),
+        test_container("container-race", "pod-1", HashMap::new()),
+    );
+    set_fake_runtime_state(&dir, "container-race", "running");
+    fs::write(
+        dir.path()
+            .join("runtime-state")
+            .join("container-race.ignore-term"),
+        "",
+    )
+    .unwrap();
 
-    service.pod_sandboxes.lock().await.insert(
-        "pod-update-post-fail".to_string(),
-        test_pod("pod-update-post-fail", HashMap::new()),
+    let stop = async {
+        RuntimeService::stop_container(
+            &service,
+            Request::new(StopContainerRequest {
+                container_id: "container-race".to_string(),
+                timeout: 1,
+            }),
+        )
+        .await
+        .unwrap();
+        std::time::Instant::now()
+    };
+    let remove = async {
+        RuntimeService::remove_container(
+            &service,
+            Request::new(RemoveContainerRequest {
+                container_id: "container-race".to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+        std::time::Instant::now()
+    };
+    let (stopped_at, removed_at) = tokio::join!(stop, remove);
+
+    assert!(removed_at >= stopped_at);
+    // remove 没有再发起自己的 SIGTERM，而是等在途的 stop 完成
+    assert_eq!(
+        fake_runtime_signals(&dir, "container-race"),
+        vec!["TERM", "KILL"]
     );
+    assert!(!service
+        .containers
+        .read()
+        .await
+        .contains_key("container-race"));
+    assert!(service.container_lifecycle_locks.lock().await.is_empty());
+}
 
+#[tokio::test]
+async fn remove_container_force_removes_container_that_ignores_sigkill() {
+    let (dir, mut service) = test_service_with_fake_runtime();
+    service.config.remove_grace_period_secs = 0;
+    service
+        .runtime
+        .set_kill_wait_timeout(std::time::Duration::from_secs(1));
     let mut annotations = HashMap::new();
     RuntimeServiceImpl::insert_internal_state(
         &mut annotations,
@@ -4830,63 +7235,61 @@ async fn update_container_resources_succeeds_when_nri_post_update_fails() {
         &StoredContainerState::default(),
     )
     .unwrap();
-    service.containers.lock().await.insert(
-        "container-update-post-fail".to_string(),
-        test_container(
-            "container-update-post-fail",
-            "pod-update-post-fail",
-            annotations.clone(),
-        ),
+    service.containers.write().await.insert(
+        "container-wedged".to_string(),
+        test_container("container-wedged", "pod-1", annotations.clone()),
     );
-    set_fake_runtime_state(&dir, "container-update-post-fail", "running");
-    service
-        .persistence
-        .lock()
-        .await
-        .save_container(
-            "container-update-post-fail",
-            "pod-update-post-fail",
-            crate::runtime::ContainerStatus::Running,
-            "busybox:latest",
-            &Vec::new(),
-            &HashMap::new(),
-            &annotations,
-        )
-        .unwrap();
+    write_test_bundle_config(&dir, "container-wedged", &annotations);
+    set_fake_runtime_state(&dir, "container-wedged", "running");
+    fs::write(
+        dir.path()
+            .join("runtime-state")
+            .join("container-wedged.ignore-kill"),
+        "",
+    )
+    .unwrap();
+    let mut events = service.events.subscribe();
 
-    RuntimeService::update_container_resources(
-        &service,
-        Request::new(UpdateContainerResourcesRequest {
-            container_id: "container-update-post-fail".to_string(),
-            linux: Some(crate::proto::runtime::v1::LinuxContainerResources {
-                cpu_shares: 512,
-                ..Default::default()
+    timeout(
+        Duration::from_secs(10),
+        RuntimeService::remove_container(
+            &service,
+            Request::new(RemoveContainerRequest {
+                container_id: "container-wedged".to_string(),
             }),
-            windows: None,
-            annotations: HashMap::new(),
-        }),
+        ),
     )
     .await
-    .expect("post-update failure should not fail resource update");
-
-    let payload: serde_json::Value = serde_json::from_str(
-        &fs::read_to_string(fake_runtime_update_path(&dir, "container-update-post-fail")).unwrap(),
-    )
+    .expect("forced removal should not hang")
     .unwrap();
-    assert_eq!(payload["cpu"]["shares"], 512);
-}
-
-#[tokio::test]
-async fn update_container_resources_applies_extended_nri_resources() {
-    let fake_nri = Arc::new(FakeNri::default());
-    let (dir, service) = test_service_with_fake_runtime_and_nri(fake_nri.clone());
 
-    service
-        .pod_sandboxes
-        .lock()
+    assert_eq!(fake_runtime_signals(&dir, "container-wedged"), vec!["KILL"]);
+    assert!(!service
+        .containers
+        .read()
         .await
-        .insert("pod-1".to_string(), test_pod("pod-1", HashMap::new()));
+        .contains_key("container-wedged"));
+    assert!(!fake_runtime_state_path(&dir, "container-wedged").exists());
+    assert!(!dir
+        .path()
+        .join("runtime-root")
+        .join("container-wedged")
+        .exists());
+    let event = events.recv().await.unwrap();
+    assert_eq!(
+        event.container_event_type,
+        ContainerEventType::ContainerDeletedEvent as i32
+    );
+    let status = &event.containers_statuses[0];
+    assert_eq!(status.state, ContainerState::ContainerExited as i32);
+    assert_eq!(status.reason, FORCE_REMOVED_REASON);
+    assert_eq!(status.exit_code, FORCE_REMOVED_EXIT_CODE);
+}
 
+#[tokio::test]
+async fn remove_container_propagates_stop_failures_other_than_kill_timeout() {
+    let (dir, mut service) = test_service_with_fake_runtime();
+    service.config.remove_grace_period_secs = 0;
     let mut annotations = HashMap::new();
     RuntimeServiceImpl::insert_internal_state(
         &mut annotations,
@@ -4894,1456 +7297,3979 @@ async fn update_container_resources_applies_extended_nri_resources() {
         &StoredContainerState::default(),
     )
     .unwrap();
-    service.containers.lock().await.insert(
-        "container-running".to_string(),
-        test_container("container-running", "pod-1", annotations.clone()),
+    service.containers.write().await.insert(
+        "container-broken".to_string(),
+        test_container("container-broken", "pod-1", annotations.clone()),
     );
-    set_fake_runtime_state(&dir, "container-running", "running");
-    service
-        .persistence
-        .lock()
-        .await
-        .save_container(
-            "container-running",
-            "pod-1",
-            crate::runtime::ContainerStatus::Running,
-            "busybox:latest",
-            &Vec::new(),
-            &HashMap::new(),
-            &annotations,
-        )
-        .unwrap();
-
-    let mut extended = crate::nri_proto::api::LinuxResources::new();
-    let mut memory = crate::nri_proto::api::LinuxMemory::new();
-    let mut reservation = crate::nri_proto::api::OptionalInt64::new();
-    reservation.value = 4096;
-    memory.reservation = protobuf::MessageField::some(reservation);
-    extended.memory = protobuf::MessageField::some(memory);
-    extended.pids = Some(crate::nri_proto::api::LinuxPids {
-        limit: 42,
-        ..Default::default()
-    })
-    .into();
-    extended
-        .devices
-        .push(crate::nri_proto::api::LinuxDeviceCgroup {
-            allow: true,
-            type_: "c".to_string(),
-            access: "rwm".to_string(),
-            major: {
-                let mut value = crate::nri_proto::api::OptionalInt64::new();
-                value.value = 10;
-                protobuf::MessageField::some(value)
-            },
-            minor: {
-                let mut value = crate::nri_proto::api::OptionalInt64::new();
-                value.value = 200;
-                protobuf::MessageField::some(value)
-            },
-            ..Default::default()
-        });
-    let mut blockio_class = crate::nri_proto::api::OptionalString::new();
-    blockio_class.value = "gold".to_string();
-    extended.blockio_class = protobuf::MessageField::some(blockio_class);
-    *fake_nri.update_result.lock().await = Some(NriUpdateContainerResult {
-        linux_resources: Some(extended),
-        updates: Vec::new(),
-        evictions: Vec::new(),
-    });
+    write_test_bundle_config(&dir, "container-broken", &annotations);
+    set_fake_runtime_state(&dir, "container-broken", "running");
+    fs::write(
+        dir.path()
+            .join("runtime-state")
+            .join("container-broken.fail-kill"),
+        "permission denied",
+    )
+    .unwrap();
 
-    RuntimeService::update_container_resources(
+    let err = RuntimeService::remove_container(
         &service,
-        Request::new(UpdateContainerResourcesRequest {
-            container_id: "container-running".to_string(),
-            linux: Some(crate::proto::runtime::v1::LinuxContainerResources {
-                cpu_shares: 256,
-                ..Default::default()
-            }),
-            windows: None,
-            annotations: HashMap::new(),
+        Request::new(RemoveContainerRequest {
+            container_id: "container-broken".to_string(),
         }),
     )
     .await
-    .unwrap();
-
-    let payload: serde_json::Value = serde_json::from_str(
-        &fs::read_to_string(fake_runtime_update_path(&dir, "container-running")).unwrap(),
-    )
-    .unwrap();
-    assert_eq!(payload["cpu"]["shares"], 256);
-    assert_eq!(payload["memory"]["reservation"], 4096);
-    assert_eq!(payload["pids"]["limit"], 42);
-    assert_eq!(payload["devices"][0]["type"], "c");
-    assert_eq!(payload["devices"][0]["major"], 10);
-    assert_eq!(payload["devices"][0]["minor"], 200);
-    assert_eq!(payload["devices"][0]["access"], "rwm");
-    assert_eq!(payload["blockIO"]["weight"], 500);
-    assert_eq!(
-        fake_nri.calls.lock().await.clone(),
-        vec!["update_container", "post_update_container"]
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::Internal);
+    assert!(
+        err.message().contains("permission denied"),
+        "{}",
+        err.message()
     );
 
-    let container = service
-        .containers
-        .lock()
-        .await
-        .get("container-running")
-        .cloned()
-        .unwrap();
+    // 普通的停止失败不会触发强制删除，容器和运行时状态都保留以便重试
+    let containers = service.containers.read().await;
+    let container = &containers["container-broken"];
+    assert_ne!(container.state, ContainerState::ContainerExited as i32);
     let state = RuntimeServiceImpl::read_internal_state::<StoredContainerState>(
         &container.annotations,
         INTERNAL_CONTAINER_STATE_KEY,
     )
     .unwrap();
-    let resources = state.linux_resources.unwrap();
-    assert_eq!(resources.cpu_shares, 256);
-    assert_eq!(resources.memory_reservation_in_bytes, Some(4096));
-    assert_eq!(resources.pids_limit, Some(42));
-    assert_eq!(resources.devices.len(), 1);
-    assert_eq!(resources.devices[0].device_type.as_deref(), Some("c"));
-    assert_eq!(resources.devices[0].major, Some(10));
-    assert_eq!(resources.devices[0].minor, Some(200));
-    assert_eq!(resources.blockio_class.as_deref(), Some("gold"));
+    assert_eq!(state.exit_reason, None);
+    assert!(fake_runtime_state_path(&dir, "container-broken").exists());
 }
 
 #[tokio::test]
-async fn stop_container_applies_nri_update_side_effects() {
-    let fake_nri = Arc::new(FakeNri::default());
-    let (dir, service) = test_service_with_fake_runtime_and_nri(fake_nri.clone());
+async fn update_container_resources_rejects_out_of_range_oom_score_adj() {
+    let (dir, service) = test_service_with_fake_runtime();
+    service.containers.write().await.insert(
+        "container-running".to_string(),
+        test_container("container-running", "pod-1", HashMap::new()),
+    );
+    set_fake_runtime_state(&dir, "container-running", "running");
 
-    let mut target_annotations = HashMap::new();
-    RuntimeServiceImpl::insert_internal_state(
-        &mut target_annotations,
-        INTERNAL_CONTAINER_STATE_KEY,
-        &StoredContainerState::default(),
-    )
-    .unwrap();
-    let mut sidecar_annotations = HashMap::new();
-    RuntimeServiceImpl::insert_internal_state(
-        &mut sidecar_annotations,
-        INTERNAL_CONTAINER_STATE_KEY,
-        &StoredContainerState::default(),
-    )
-    .unwrap();
+    for oom_score_adj in [-1001, 1001] {
+        let err = RuntimeService::update_container_resources(
+            &service,
+            Request::new(UpdateContainerResourcesRequest {
+                container_id: "container-running".to_string(),
+                linux: Some(crate::proto::runtime::v1::LinuxContainerResources {
+                    oom_score_adj,
+                    ..Default::default()
+                }),
+                windows: None,
+                annotations: HashMap::new(),
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+    assert!(!fake_runtime_update_path(&dir, "container-running").exists());
+}
 
+#[tokio::test]
+async fn create_container_rejects_subpath_symlink_escapes() {
+    let (dir, service) = test_service_with_fake_runtime();
     service
         .pod_sandboxes
-        .lock()
+        .write()
         .await
         .insert("pod-1".to_string(), test_pod("pod-1", HashMap::new()));
-    service.containers.lock().await.insert(
-        "container-stop".to_string(),
-        test_container("container-stop", "pod-1", target_annotations.clone()),
-    );
-    service.containers.lock().await.insert(
-        "container-sidecar".to_string(),
-        test_container("container-sidecar", "pod-1", sidecar_annotations.clone()),
-    );
-    set_fake_runtime_state(&dir, "container-stop", "running");
-    set_fake_runtime_state(&dir, "container-sidecar", "running");
-    service
-        .persistence
-        .lock()
-        .await
-        .save_container(
-            "container-stop",
-            "pod-1",
-            crate::runtime::ContainerStatus::Running,
-            "busybox:latest",
-            &Vec::new(),
-            &HashMap::new(),
-            &target_annotations,
+
+    let volume = dir.path().join("volume");
+    let outside = dir.path().join("outside");
+    fs::create_dir_all(volume.join("nested")).unwrap();
+    fs::create_dir_all(&outside).unwrap();
+    std::os::unix::fs::symlink("/", volume.join("host-root")).unwrap();
+    std::os::unix::fs::symlink(&outside, volume.join("nested/escape")).unwrap();
+
+    for sub_path in ["host-root/etc", "nested/escape", "../outside", "/etc"] {
+        let mut annotations = HashMap::new();
+        annotations.insert(
+            MOUNT_SUBPATHS_ANNOTATION_KEY.to_string(),
+            serde_json::json!({ "/data": sub_path }).to_string(),
+        );
+        let err = RuntimeService::create_container(
+            &service,
+            Request::new(CreateContainerRequest {
+                pod_sandbox_id: "pod-1".to_string(),
+                config: Some(crate::proto::runtime::v1::ContainerConfig {
+                    mounts: vec![crate::proto::runtime::v1::Mount {
+                        container_path: "/data".to_string(),
+                        host_path: volume.display().to_string(),
+                        ..Default::default()
+                    }],
+                    annotations,
+                    ..Default::default()
+                }),
+                sandbox_config: None,
+            }),
         )
-        .unwrap();
-    service
-        .persistence
-        .lock()
         .await
-        .save_container(
-            "container-sidecar",
-            "pod-1",
-            crate::runtime::ContainerStatus::Running,
-            "busybox:latest",
-            &Vec::new(),
-            &HashMap::new(),
-            &sidecar_annotations,
-        )
-        .unwrap();
+        .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument, "{}", sub_path);
+    }
+    assert!(service.containers.read().await.is_empty());
+    assert!(!dir.path().join("root/mounts").exists());
+}
 
-    let mut update = crate::nri_proto::api::ContainerUpdate::new();
-    update.container_id = "container-sidecar".to_string();
-    let mut linux = crate::nri_proto::api::LinuxContainerUpdate::new();
-    let mut resources = crate::nri_proto::api::LinuxResources::new();
-    let mut cpu = crate::nri_proto::api::LinuxCPU::new();
-    let mut shares = crate::nri_proto::api::OptionalUInt64::new();
-    shares.value = 2048;
-    cpu.shares = protobuf::MessageField::some(shares);
-    resources.cpu = protobuf::MessageField::some(cpu);
-    linux.resources = protobuf::MessageField::some(resources);
-    update.linux = protobuf::MessageField::some(linux);
-    *fake_nri.stop_result.lock().await = Some(NriStopContainerResult {
-        updates: vec![update],
-    });
+#[tokio::test]
+async fn create_container_uses_prepared_rootfs_from_annotation() {
+    let (dir, mut service) = test_service_with_fake_runtime();
+    service
+        .pod_sandboxes
+        .write()
+        .await
+        .insert("pod-1".to_string(), test_pod("pod-1", HashMap::new()));
+    let rootfs_dir = dir.path().join("prepared");
+    let rootfs = rootfs_dir.join("busybox");
+    fs::create_dir_all(rootfs.join("bin")).unwrap();
+    fs::write(rootfs.join("bin/marker"), "prepared").unwrap();
+    fs::create_dir_all(dir.path().join("outside")).unwrap();
+    let request = |path: &str| {
+        Request::new(CreateContainerRequest {
+            pod_sandbox_id: "pod-1".to_string(),
+            config: Some(crate::proto::runtime::v1::ContainerConfig {
+                metadata: Some(crate::proto::runtime::v1::ContainerMetadata {
+                    name: "prepared".to_string(),
+                    attempt: 0,
+                }),
+                annotations: HashMap::from([(
+                    ROOTFS_PATH_ANNOTATION_KEY.to_string(),
+                    path.to_string(),
+                )]),
+                ..Default::default()
+            }),
+            sandbox_config: None,
+        })
+    };
 
-    RuntimeService::stop_container(
-        &service,
-        Request::new(StopContainerRequest {
-            container_id: "container-stop".to_string(),
-            timeout: 1,
-        }),
-    )
-    .await
-    .unwrap();
+    let err = RuntimeService::create_container(&service, request("busybox"))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    service.config.prepared_rootfs_dir = Some(rootfs_dir.clone());
+    let outside = dir.path().join("outside").display().to_string();
+    for path in [
+        "../outside",
+        outside.as_str(),
+        "missing",
+        "busybox/bin/marker",
+        "",
+    ] {
+        let err = RuntimeService::create_container(&service, request(path))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument, "{:?}", path);
+    }
 
-    assert_eq!(fake_nri.calls.lock().await.clone(), vec!["stop_container"]);
-    let update_payload: serde_json::Value = serde_json::from_str(
-        &fs::read_to_string(fake_runtime_update_path(&dir, "container-sidecar")).unwrap(),
-    )
-    .unwrap();
-    assert_eq!(update_payload["cpu"]["shares"], 2048);
-    let sidecar = service
-        .containers
-        .lock()
+    let container_id = RuntimeService::create_container(&service, request("busybox"))
         .await
-        .get("container-sidecar")
-        .cloned()
-        .unwrap();
-    let state = RuntimeServiceImpl::read_internal_state::<StoredContainerState>(
-        &sidecar.annotations,
-        INTERNAL_CONTAINER_STATE_KEY,
+        .unwrap()
+        .into_inner()
+        .container_id;
+    let spec: serde_json::Value = serde_json::from_slice(
+        &fs::read(
+            dir.path()
+                .join("runtime-root")
+                .join(&container_id)
+                .join("config.json"),
+        )
+        .unwrap(),
     )
     .unwrap();
-    assert_eq!(state.linux_resources.unwrap().cpu_shares, 2048);
+    assert_eq!(
+        spec["root"]["path"],
+        fs::canonicalize(&rootfs).unwrap().display().to_string()
+    );
+    assert_eq!(
+        fs::read_to_string(rootfs.join("bin/marker")).unwrap(),
+        "prepared"
+    );
+    assert!(!dir
+        .path()
+        .join("root/containers")
+        .join(&container_id)
+        .join("rootfs")
+        .exists());
 }
 
 #[tokio::test]
-async fn nri_domain_apply_updates_updates_runtime_and_persistence() {
+async fn create_container_with_missing_image_fails_without_leaving_state() {
     let (dir, service) = test_service_with_fake_runtime();
-    let mut annotations = HashMap::new();
-    RuntimeServiceImpl::insert_internal_state(
-        &mut annotations,
-        INTERNAL_CONTAINER_STATE_KEY,
-        &StoredContainerState::default(),
-    )
-    .unwrap();
-    service.containers.lock().await.insert(
-        "container-running".to_string(),
-        test_container("container-running", "pod-1", annotations.clone()),
-    );
+    fs::create_dir_all(service.config.image_root.join("images")).unwrap();
     service
         .pod_sandboxes
-        .lock()
-        .await
-        .insert("pod-1".to_string(), test_pod("pod-1", annotations.clone()));
-    set_fake_runtime_state(&dir, "container-running", "running");
-    service
-        .persistence
-        .lock()
+        .write()
         .await
-        .save_container(
-            "container-running",
-            "pod-1",
-            crate::runtime::ContainerStatus::Running,
-            "busybox:latest",
-            &Vec::new(),
-            &HashMap::new(),
-            &annotations,
-        )
-        .unwrap();
-
-    let domain = NriRuntimeDomain {
-        containers: service.containers.clone(),
-        pod_sandboxes: service.pod_sandboxes.clone(),
-        config: service.config.clone(),
-        nri_config: service.nri_config.clone(),
-        runtime: service.runtime.clone(),
-        persistence: service.persistence.clone(),
-        events: service.events.clone(),
-    };
-
-    let mut update = crate::nri_proto::api::ContainerUpdate::new();
-    update.container_id = "container-running".to_string();
-    let mut linux_update = crate::nri_proto::api::LinuxContainerUpdate::new();
-    let mut resources = crate::nri_proto::api::LinuxResources::new();
-    let mut cpu = crate::nri_proto::api::LinuxCPU::new();
-    let mut shares = crate::nri_proto::api::OptionalUInt64::new();
-    shares.value = 512;
-    cpu.shares = protobuf::MessageField::some(shares);
-    resources.cpu = protobuf::MessageField::some(cpu);
-    linux_update.resources = protobuf::MessageField::some(resources);
-    update.linux = protobuf::MessageField::some(linux_update);
-
-    let failed = domain.apply_updates(&[update]).await.unwrap();
-    assert!(failed.is_empty());
+        .insert("pod-1".to_string(), test_pod("pod-1", HashMap::new()));
 
-    let update_payload: serde_json::Value = serde_json::from_str(
-        &fs::read_to_string(fake_runtime_update_path(&dir, "container-running")).unwrap(),
+    let err = RuntimeService::create_container(
+        &service,
+        Request::new(CreateContainerRequest {
+            pod_sandbox_id: "pod-1".to_string(),
+            config: Some(crate::proto::runtime::v1::ContainerConfig {
+                metadata: Some(crate::proto::runtime::v1::ContainerMetadata {
+                    name: "app".to_string(),
+                    attempt: 0,
+                }),
+                image: Some(ImageSpec {
+                    image: "registry.example.com/missing:latest".to_string(),
+                    ..Default::default()
+                }),
+                command: vec!["/bin/app".to_string()],
+                ..Default::default()
+            }),
+            sandbox_config: None,
+        }),
     )
-    .unwrap();
-    assert_eq!(update_payload["cpu"]["shares"], 512);
+    .await
+    .unwrap_err();
 
-    let persisted = service
-        .persistence
-        .lock()
-        .await
-        .storage()
-        .get_container("container-running")
-        .unwrap()
-        .unwrap();
-    let persisted_annotations: HashMap<String, String> =
-        serde_json::from_str(&persisted.annotations).unwrap();
-    let persisted_state = RuntimeServiceImpl::read_internal_state::<StoredContainerState>(
-        &persisted_annotations,
-        INTERNAL_CONTAINER_STATE_KEY,
-    )
-    .unwrap();
-    assert_eq!(persisted_state.linux_resources.unwrap().cpu_shares, 512);
+    assert_eq!(err.code(), tonic::Code::Internal);
+    assert!(
+        err.message()
+            .contains("Image not found locally for reference: registry.example.com/missing:latest"),
+        "{}",
+        err.message()
+    );
+    assert!(service.containers.read().await.is_empty());
+    for dir in [
+        dir.path().join("root/containers"),
+        dir.path().join("runtime-root"),
+    ] {
+        let leftovers: Vec<_> = fs::read_dir(&dir)
+            .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+            .unwrap_or_default();
+        assert!(leftovers.is_empty(), "{:?}", leftovers);
+    }
 }
 
 #[tokio::test]
-async fn nri_domain_snapshot_excludes_exited_containers() {
-    let (_dir, service) = test_service_with_fake_runtime();
-    let mut annotations = HashMap::new();
-    RuntimeServiceImpl::insert_internal_state(
-        &mut annotations,
-        INTERNAL_CONTAINER_STATE_KEY,
-        &StoredContainerState::default(),
-    )
-    .unwrap();
-
+async fn create_container_with_missing_image_unmounts_staged_subpaths() {
+    if !nix::unistd::geteuid().is_root() {
+        return;
+    }
+    let (dir, service) = test_service_with_fake_runtime();
+    fs::create_dir_all(service.config.image_root.join("images")).unwrap();
     service
         .pod_sandboxes
-        .lock()
+        .write()
         .await
         .insert("pod-1".to_string(), test_pod("pod-1", HashMap::new()));
+    let volume = dir.path().join("volume");
+    fs::create_dir_all(volume.join("data")).unwrap();
+    let mut annotations = HashMap::new();
+    annotations.insert(
+        MOUNT_SUBPATHS_ANNOTATION_KEY.to_string(),
+        serde_json::json!({ "/data": "data" }).to_string(),
+    );
 
-    let mut running = test_container("container-running", "pod-1", annotations.clone());
-    running.state = ContainerState::ContainerRunning as i32;
-    let mut exited = test_container("container-exited", "pod-1", annotations);
-    exited.state = ContainerState::ContainerExited as i32;
+    let err = RuntimeService::create_container(
+        &service,
+        Request::new(CreateContainerRequest {
+            pod_sandbox_id: "pod-1".to_string(),
+            config: Some(crate::proto::runtime::v1::ContainerConfig {
+                metadata: Some(crate::proto::runtime::v1::ContainerMetadata {
+                    name: "app".to_string(),
+                    attempt: 0,
+                }),
+                image: Some(ImageSpec {
+                    image: "registry.example.com/missing:latest".to_string(),
+                    ..Default::default()
+                }),
+                mounts: vec![crate::proto::runtime::v1::Mount {
+                    container_path: "/data".to_string(),
+                    host_path: volume.display().to_string(),
+                    ..Default::default()
+                }],
+                annotations,
+                ..Default::default()
+            }),
+            sandbox_config: None,
+        }),
+    )
+    .await
+    .unwrap_err();
 
-    let mut containers = service.containers.lock().await;
-    containers.insert(running.id.clone(), running);
-    containers.insert(exited.id.clone(), exited);
-    drop(containers);
+    assert!(
+        err.message().contains("Failed to prepare container rootfs"),
+        "{}",
+        err.message()
+    );
+    let leftovers: Vec<_> = fs::read_dir(dir.path().join("root/mounts"))
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    assert!(leftovers.is_empty(), "{:?}", leftovers);
+}
 
-    let domain = NriRuntimeDomain {
-        containers: service.containers.clone(),
-        pod_sandboxes: service.pod_sandboxes.clone(),
-        config: service.config.clone(),
-        nri_config: service.nri_config.clone(),
-        runtime: service.runtime.clone(),
-        persistence: service.persistence.clone(),
-        events: service.events.clone(),
+#[tokio::test]
+async fn create_container_requires_ready_sandbox_and_joins_its_namespaces() {
+    let (dir, mut service) = test_service_with_fake_runtime();
+    let rootfs_dir = dir.path().join("prepared");
+    fs::create_dir_all(rootfs_dir.join("busybox/bin")).unwrap();
+    service.config.prepared_rootfs_dir = Some(rootfs_dir);
+    let request = |pod_id: &str, namespace_options: Option<NamespaceOption>| {
+        // 名字不同，避免第二个容器被当作第一个的重试
+        let name = if namespace_options.is_some() {
+            "private-pid"
+        } else {
+            "member"
+        };
+        Request::new(CreateContainerRequest {
+            pod_sandbox_id: pod_id.to_string(),
+            config: Some(crate::proto::runtime::v1::ContainerConfig {
+                metadata: Some(crate::proto::runtime::v1::ContainerMetadata {
+                    name: name.to_string(),
+                    attempt: 0,
+                }),
+                annotations: HashMap::from([(
+                    ROOTFS_PATH_ANNOTATION_KEY.to_string(),
+                    "busybox".to_string(),
+                )]),
+                linux: namespace_options.map(|namespace_options| {
+                    crate::proto::runtime::v1::LinuxContainerConfig {
+                        security_context: Some(
+                            crate::proto::runtime::v1::LinuxContainerSecurityContext {
+                                namespace_options: Some(namespace_options),
+                                ..Default::default()
+                            },
+                        ),
+                        ..Default::default()
+                    }
+                }),
+                ..Default::default()
+            }),
+            sandbox_config: None,
+        })
     };
 
-    let snapshot = domain.snapshot().await.unwrap();
-    let ids = snapshot
-        .containers
-        .iter()
-        .map(|container| container.id.as_str())
-        .collect::<Vec<_>>();
-
-    assert_eq!(ids, vec!["container-running"]);
-}
+    let err = RuntimeService::create_container(&service, request("missing", None))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
 
-#[tokio::test]
-async fn nri_domain_snapshot_includes_paused_runtime_containers() {
-    let (dir, service) = test_service_with_fake_runtime();
+    let netns_path = "/var/run/netns/pod-1".to_string();
     let mut annotations = HashMap::new();
     RuntimeServiceImpl::insert_internal_state(
         &mut annotations,
-        INTERNAL_CONTAINER_STATE_KEY,
-        &StoredContainerState::default(),
+        INTERNAL_POD_STATE_KEY,
+        &StoredPodState {
+            pause_container_id: Some("pause-1".to_string()),
+            netns_path: Some(netns_path.clone()),
+            ..Default::default()
+        },
     )
     .unwrap();
-
+    let mut pod = test_pod("pod-1", annotations);
+    pod.state = PodSandboxState::SandboxNotready as i32;
     service
         .pod_sandboxes
-        .lock()
+        .write()
         .await
-        .insert("pod-1".to_string(), test_pod("pod-1", HashMap::new()));
+        .insert("pod-1".to_string(), pod);
+    let err = RuntimeService::create_container(&service, request("pod-1", None))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+    assert!(service.containers.read().await.is_empty());
 
-    let mut paused = test_container("container-paused", "pod-1", annotations);
-    paused.state = ContainerState::ContainerExited as i32;
     service
-        .containers
-        .lock()
+        .pod_sandboxes
+        .write()
         .await
-        .insert(paused.id.clone(), paused);
-    set_fake_runtime_state(&dir, "container-paused", "paused");
-
-    let domain = NriRuntimeDomain {
-        containers: service.containers.clone(),
-        pod_sandboxes: service.pod_sandboxes.clone(),
-        config: service.config.clone(),
-        nri_config: service.nri_config.clone(),
-        runtime: service.runtime.clone(),
-        persistence: service.persistence.clone(),
-        events: service.events.clone(),
+        .get_mut("pod-1")
+        .unwrap()
+        .state = PodSandboxState::SandboxReady as i32;
+    set_fake_runtime_state(&dir, "pause-1", "running");
+    let pause_pid = std::process::id();
+    let namespaces = |container_id: &str| {
+        let spec: serde_json::Value = serde_json::from_slice(
+            &fs::read(
+                dir.path()
+                    .join("runtime-root")
+                    .join(container_id)
+                    .join("config.json"),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        spec["linux"]["namespaces"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|ns| {
+                (
+                    ns["type"].as_str().unwrap().to_string(),
+                    ns["path"].as_str().map(str::to_string),
+                )
+            })
+            .collect::<HashMap<_, _>>()
     };
 
-    let snapshot = domain.snapshot().await.unwrap();
-    assert_eq!(snapshot.containers.len(), 1);
-    assert_eq!(snapshot.containers[0].id, "container-paused");
+    let shared = RuntimeService::create_container(&service, request("pod-1", None))
+        .await
+        .unwrap()
+        .into_inner()
+        .container_id;
     assert_eq!(
-        snapshot.containers[0].state.enum_value().unwrap(),
-        crate::nri_proto::api::ContainerState::CONTAINER_PAUSED
+        service.containers.read().await[&shared].pod_sandbox_id,
+        "pod-1"
+    );
+    let shared_namespaces = namespaces(&shared);
+    assert_eq!(shared_namespaces["network"], Some(netns_path.clone()));
+    assert_eq!(
+        shared_namespaces["ipc"],
+        Some(format!("/proc/{}/ns/ipc", pause_pid))
+    );
+    assert_eq!(
+        shared_namespaces["pid"],
+        Some(format!("/proc/{}/ns/pid", pause_pid))
     );
-}
-
-#[tokio::test]
-async fn nri_domain_apply_updates_ignores_failures_marked_ignore_failure() {
-    let (_dir, service) = test_service_with_fake_runtime();
-    let domain = NriRuntimeDomain {
-        containers: service.containers.clone(),
-        pod_sandboxes: service.pod_sandboxes.clone(),
-        config: service.config.clone(),
-        nri_config: service.nri_config.clone(),
-        runtime: service.runtime.clone(),
-        persistence: service.persistence.clone(),
-        events: service.events.clone(),
-    };
-
-    let mut update = crate::nri_proto::api::ContainerUpdate::new();
-    update.container_id = "missing".to_string();
-    update.ignore_failure = true;
 
-    let failed = domain.apply_updates(&[update.clone()]).await.unwrap();
-    assert!(failed.is_empty());
+    let private_pid = RuntimeService::create_container(
+        &service,
+        request(
+            "pod-1",
+            Some(NamespaceOption {
+                pid: NamespaceMode::Container as i32,
+                ..Default::default()
+            }),
+        ),
+    )
+    .await
+    .unwrap()
+    .into_inner()
+    .container_id;
+    let private_namespaces = namespaces(&private_pid);
+    assert_eq!(private_namespaces["network"], Some(netns_path));
+    assert_eq!(
+        private_namespaces["ipc"],
+        Some(format!("/proc/{}/ns/ipc", pause_pid))
+    );
+    assert_eq!(private_namespaces["pid"], None);
 }
 
 #[tokio::test]
-async fn nri_domain_apply_updates_ignores_missing_containers() {
-    let (_dir, service) = test_service_with_fake_runtime();
-    let domain = NriRuntimeDomain {
-        containers: service.containers.clone(),
-        pod_sandboxes: service.pod_sandboxes.clone(),
-        config: service.config.clone(),
-        nri_config: service.nri_config.clone(),
-        runtime: service.runtime.clone(),
-        persistence: service.persistence.clone(),
-        events: service.events.clone(),
+async fn containers_sharing_pod_ipc_mount_sandbox_shm() {
+    let (dir, mut service) = test_service_with_fake_runtime();
+    let rootfs_dir = dir.path().join("prepared");
+    fs::create_dir_all(rootfs_dir.join("busybox/bin")).unwrap();
+    service.config.prepared_rootfs_dir = Some(rootfs_dir);
+
+    let shm_path = dir.path().join("root/pods/pod-1/shm");
+    fs::create_dir_all(&shm_path).unwrap();
+    let mut annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut annotations,
+        INTERNAL_POD_STATE_KEY,
+        &StoredPodState {
+            pause_container_id: Some("pause-1".to_string()),
+            shm_path: Some(shm_path.display().to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    service
+        .pod_sandboxes
+        .write()
+        .await
+        .insert("pod-1".to_string(), test_pod("pod-1", annotations));
+    set_fake_runtime_state(&dir, "pause-1", "running");
+
+    let create = |name: &str, ipc: NamespaceMode| {
+        let service = &service;
+        let name = name.to_string();
+        async move {
+            RuntimeService::create_container(
+                service,
+                Request::new(CreateContainerRequest {
+                    pod_sandbox_id: "pod-1".to_string(),
+                    config: Some(crate::proto::runtime::v1::ContainerConfig {
+                        metadata: Some(crate::proto::runtime::v1::ContainerMetadata {
+                            name,
+                            attempt: 0,
+                        }),
+                        annotations: HashMap::from([(
+                            ROOTFS_PATH_ANNOTATION_KEY.to_string(),
+                            "busybox".to_string(),
+                        )]),
+                        linux: Some(crate::proto::runtime::v1::LinuxContainerConfig {
+                            security_context: Some(
+                                crate::proto::runtime::v1::LinuxContainerSecurityContext {
+                                    namespace_options: Some(NamespaceOption {
+                                        ipc: ipc as i32,
+                                        ..Default::default()
+                                    }),
+                                    ..Default::default()
+                                },
+                            ),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    }),
+                    sandbox_config: None,
+                }),
+            )
+            .await
+            .unwrap()
+            .into_inner()
+            .container_id
+        }
+    };
+    let shm_mount = |container_id: &str| {
+        let spec = service.runtime_spec_snapshot(container_id).unwrap();
+        let mounts: Vec<_> = spec["mounts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|mount| mount["destination"] == "/dev/shm")
+            .cloned()
+            .collect();
+        assert_eq!(mounts.len(), 1, "{:?}", mounts);
+        mounts.into_iter().next().unwrap()
     };
 
-    let mut update = crate::nri_proto::api::ContainerUpdate::new();
-    update.container_id = "missing".to_string();
-    let mut linux_update = crate::nri_proto::api::LinuxContainerUpdate::new();
-    linux_update.resources =
-        protobuf::MessageField::some(crate::nri_proto::api::LinuxResources::new());
-    update.linux = protobuf::MessageField::some(linux_update);
+    let first = create("first", NamespaceMode::Pod).await;
+    let second = create("second", NamespaceMode::Pod).await;
+    for container_id in [&first, &second] {
+        let mount = shm_mount(container_id);
+        assert_eq!(mount["type"], "bind");
+        assert_eq!(mount["source"], shm_path.display().to_string());
+    }
 
-    let failed = domain.apply_updates(&[update.clone()]).await.unwrap();
-    assert!(failed.is_empty());
+    // 独立 IPC 命名空间的容器仍使用自己的 tmpfs
+    let isolated = create("isolated", NamespaceMode::Container).await;
+    let mount = shm_mount(&isolated);
+    assert_eq!(mount["type"], "tmpfs");
+    assert_eq!(mount["source"], "tmpfs");
 }
 
 #[tokio::test]
-async fn nri_domain_apply_updates_accepts_short_container_ids() {
+async fn create_container_nests_cgroup_under_pod_cgroup() {
     let (dir, service) = test_service_with_fake_runtime();
     let mut annotations = HashMap::new();
     RuntimeServiceImpl::insert_internal_state(
         &mut annotations,
-        INTERNAL_CONTAINER_STATE_KEY,
-        &StoredContainerState::default(),
+        INTERNAL_POD_STATE_KEY,
+        &StoredPodState {
+            cgroup_parent: Some("kubepods/pod-uid-1".to_string()),
+            cgroup_path: Some("kubepods/pod-uid-1/crius-pod-pod-1".to_string()),
+            ..Default::default()
+        },
     )
     .unwrap();
-    service.containers.lock().await.insert(
-        "container-running-long".to_string(),
-        test_container("container-running-long", "pod-1", annotations.clone()),
-    );
-    set_fake_runtime_state(&dir, "container-running-long", "running");
     service
-        .persistence
-        .lock()
+        .pod_sandboxes
+        .write()
         .await
-        .save_container(
-            "container-running-long",
-            "pod-1",
-            crate::runtime::ContainerStatus::Running,
-            "busybox:latest",
-            &Vec::new(),
-            &HashMap::new(),
-            &annotations,
-        )
-        .unwrap();
-
-    let domain = NriRuntimeDomain {
-        containers: service.containers.clone(),
-        pod_sandboxes: service.pod_sandboxes.clone(),
-        config: service.config.clone(),
-        nri_config: service.nri_config.clone(),
-        runtime: service.runtime.clone(),
-        persistence: service.persistence.clone(),
-        events: service.events.clone(),
-    };
-
-    let mut update = crate::nri_proto::api::ContainerUpdate::new();
-    update.container_id = "container-run".to_string();
-    let mut linux_update = crate::nri_proto::api::LinuxContainerUpdate::new();
-    let mut resources = crate::nri_proto::api::LinuxResources::new();
-    let mut cpu = crate::nri_proto::api::LinuxCPU::new();
-    let mut shares = crate::nri_proto::api::OptionalUInt64::new();
-    shares.value = 768;
-    cpu.shares = protobuf::MessageField::some(shares);
-    resources.cpu = protobuf::MessageField::some(cpu);
-    linux_update.resources = protobuf::MessageField::some(resources);
-    update.linux = protobuf::MessageField::some(linux_update);
+        .insert("pod-1".to_string(), test_pod("pod-1", annotations));
 
-    let failed = domain.apply_updates(&[update]).await.unwrap();
-    assert!(failed.is_empty());
+    let container_id = RuntimeService::create_container(
+        &service,
+        Request::new(CreateContainerRequest {
+            pod_sandbox_id: "pod-1".to_string(),
+            config: Some(crate::proto::runtime::v1::ContainerConfig {
+                metadata: Some(crate::proto::runtime::v1::ContainerMetadata {
+                    name: "member".to_string(),
+                    attempt: 0,
+                }),
+                ..Default::default()
+            }),
+            sandbox_config: None,
+        }),
+    )
+    .await
+    .unwrap()
+    .into_inner()
+    .container_id;
 
-    let update_payload: serde_json::Value = serde_json::from_str(
-        &fs::read_to_string(fake_runtime_update_path(&dir, "container-running-long")).unwrap(),
+    let spec: serde_json::Value = serde_json::from_slice(
+        &fs::read(
+            dir.path()
+                .join("runtime-root")
+                .join(&container_id)
+                .join("config.json"),
+        )
+        .unwrap(),
     )
     .unwrap();
-    assert_eq!(update_payload["cpu"]["shares"], 768);
+    assert_eq!(
+        spec["linux"]["cgroupsPath"],
+        format!("kubepods/pod-uid-1/crius-pod-pod-1/{}", container_id)
+    );
 }
 
 #[tokio::test]
-async fn nri_domain_apply_updates_ignores_non_mutable_containers() {
-    let (dir, service) = test_service_with_fake_runtime();
+async fn create_container_deduplicates_retries_of_the_same_attempt() {
+    let (dir, mut service) = test_service_with_fake_runtime();
+    let rootfs_dir = dir.path().join("prepared");
+    fs::create_dir_all(rootfs_dir.join("busybox/bin")).unwrap();
+    service.config.prepared_rootfs_dir = Some(rootfs_dir);
     let mut annotations = HashMap::new();
     RuntimeServiceImpl::insert_internal_state(
         &mut annotations,
-        INTERNAL_CONTAINER_STATE_KEY,
-        &StoredContainerState::default(),
+        INTERNAL_POD_STATE_KEY,
+        &StoredPodState {
+            pause_container_id: Some("pause-1".to_string()),
+            ..Default::default()
+        },
     )
     .unwrap();
-    service.containers.lock().await.insert(
-        "container-exited".to_string(),
-        test_container("container-exited", "pod-1", annotations),
-    );
-    set_fake_runtime_state(&dir, "container-exited", "stopped");
-
-    let domain = NriRuntimeDomain {
-        containers: service.containers.clone(),
-        pod_sandboxes: service.pod_sandboxes.clone(),
-        config: service.config.clone(),
-        nri_config: service.nri_config.clone(),
-        runtime: service.runtime.clone(),
-        persistence: service.persistence.clone(),
-        events: service.events.clone(),
+    service
+        .pod_sandboxes
+        .write()
+        .await
+        .insert("pod-1".to_string(), test_pod("pod-1", annotations));
+    set_fake_runtime_state(&dir, "pause-1", "running");
+    let create = |attempt: u32| {
+        RuntimeService::create_container(
+            &service,
+            Request::new(CreateContainerRequest {
+                pod_sandbox_id: "pod-1".to_string(),
+                config: Some(crate::proto::runtime::v1::ContainerConfig {
+                    metadata: Some(crate::proto::runtime::v1::ContainerMetadata {
+                        name: "app".to_string(),
+                        attempt,
+                    }),
+                    annotations: HashMap::from([(
+                        ROOTFS_PATH_ANNOTATION_KEY.to_string(),
+                        "busybox".to_string(),
+                    )]),
+                    ..Default::default()
+                }),
+                sandbox_config: None,
+            }),
+        )
     };
 
-    let mut update = crate::nri_proto::api::ContainerUpdate::new();
-    update.container_id = "container-exited".to_string();
-    let mut linux_update = crate::nri_proto::api::LinuxContainerUpdate::new();
-    linux_update.resources =
-        protobuf::MessageField::some(crate::nri_proto::api::LinuxResources::new());
-    update.linux = protobuf::MessageField::some(linux_update);
-
-    let failed = domain.apply_updates(&[update]).await.unwrap();
-    assert!(failed.is_empty());
+    let first = create(0).await.unwrap().into_inner().container_id;
+    let (retry, concurrent) = tokio::join!(create(0), create(0));
+    assert_eq!(retry.unwrap().into_inner().container_id, first);
+    assert_eq!(concurrent.unwrap().into_inner().container_id, first);
+    assert_eq!(service.containers.read().await.len(), 1);
+
+    let restarted = create(1).await.unwrap().into_inner().container_id;
+    assert_ne!(restarted, first);
+    assert_eq!(service.containers.read().await.len(), 2);
+    for (container_id, attempt) in [(&first, 0), (&restarted, 1)] {
+        let status = RuntimeService::container_status(
+            &service,
+            Request::new(ContainerStatusRequest {
+                container_id: container_id.clone(),
+                verbose: false,
+            }),
+        )
+        .await
+        .unwrap()
+        .into_inner()
+        .status
+        .unwrap();
+        let metadata = status.metadata.unwrap();
+        assert_eq!(metadata.name, "app");
+        assert_eq!(metadata.attempt, attempt);
+    }
 }
 
 #[tokio::test]
-async fn nri_domain_apply_updates_accepts_extended_resources() {
-    let (dir, service) = test_service_with_fake_runtime();
+async fn create_container_resolves_relative_log_path_under_pod_log_directory() {
+    let (dir, mut service) = test_service_with_fake_runtime();
+    let rootfs_dir = dir.path().join("prepared");
+    fs::create_dir_all(rootfs_dir.join("busybox/bin")).unwrap();
+    service.config.prepared_rootfs_dir = Some(rootfs_dir);
+    let log_directory = dir.path().join("pod-logs");
     let mut annotations = HashMap::new();
     RuntimeServiceImpl::insert_internal_state(
         &mut annotations,
-        INTERNAL_CONTAINER_STATE_KEY,
-        &StoredContainerState::default(),
+        INTERNAL_POD_STATE_KEY,
+        &StoredPodState {
+            pause_container_id: Some("pause-1".to_string()),
+            log_directory: Some(log_directory.display().to_string()),
+            ..Default::default()
+        },
     )
     .unwrap();
-    service.containers.lock().await.insert(
-        "container-running".to_string(),
-        test_container("container-running", "pod-1", annotations.clone()),
-    );
-    set_fake_runtime_state(&dir, "container-running", "running");
     service
-        .persistence
-        .lock()
+        .pod_sandboxes
+        .write()
         .await
-        .save_container(
-            "container-running",
-            "pod-1",
-            crate::runtime::ContainerStatus::Running,
-            "busybox:latest",
-            &Vec::new(),
-            &HashMap::new(),
-            &annotations,
+        .insert("pod-1".to_string(), test_pod("pod-1", annotations));
+    set_fake_runtime_state(&dir, "pause-1", "running");
+    let create = |name: &str, log_path: String| {
+        RuntimeService::create_container(
+            &service,
+            Request::new(CreateContainerRequest {
+                pod_sandbox_id: "pod-1".to_string(),
+                config: Some(crate::proto::runtime::v1::ContainerConfig {
+                    metadata: Some(crate::proto::runtime::v1::ContainerMetadata {
+                        name: name.to_string(),
+                        attempt: 0,
+                    }),
+                    log_path,
+                    annotations: HashMap::from([(
+                        ROOTFS_PATH_ANNOTATION_KEY.to_string(),
+                        "busybox".to_string(),
+                    )]),
+                    ..Default::default()
+                }),
+                sandbox_config: None,
+            }),
         )
-        .unwrap();
-
-    let domain = NriRuntimeDomain {
-        containers: service.containers.clone(),
-        pod_sandboxes: service.pod_sandboxes.clone(),
-        config: service.config.clone(),
-        nri_config: service.nri_config.clone(),
-        runtime: service.runtime.clone(),
-        persistence: service.persistence.clone(),
-        events: service.events.clone(),
     };
 
-    let mut update = crate::nri_proto::api::ContainerUpdate::new();
-    update.container_id = "container-running".to_string();
-    let mut linux_update = crate::nri_proto::api::LinuxContainerUpdate::new();
-    let mut resources = crate::nri_proto::api::LinuxResources::new();
-    let mut cpu = crate::nri_proto::api::LinuxCPU::new();
-    let mut shares = crate::nri_proto::api::OptionalUInt64::new();
-    shares.value = 512;
-    cpu.shares = protobuf::MessageField::some(shares);
-    resources.cpu = protobuf::MessageField::some(cpu);
-    resources.pids = Some(crate::nri_proto::api::LinuxPids {
-        limit: 7,
-        ..Default::default()
-    })
-    .into();
-    resources
-        .devices
-        .push(crate::nri_proto::api::LinuxDeviceCgroup {
-            allow: false,
-            type_: "b".to_string(),
-            access: "rw".to_string(),
-            major: {
-                let mut value = crate::nri_proto::api::OptionalInt64::new();
-                value.value = 8;
-                protobuf::MessageField::some(value)
-            },
-            minor: {
-                let mut value = crate::nri_proto::api::OptionalInt64::new();
-                value.value = 0;
-                protobuf::MessageField::some(value)
-            },
-            ..Default::default()
-        });
-    let mut blockio_class = crate::nri_proto::api::OptionalString::new();
-    blockio_class.value = "gold".to_string();
-    resources.blockio_class = protobuf::MessageField::some(blockio_class);
-    linux_update.resources = protobuf::MessageField::some(resources);
-    update.linux = protobuf::MessageField::some(linux_update);
-
-    let failed = domain.apply_updates(&[update]).await.unwrap();
-    assert!(failed.is_empty());
-
-    let payload: serde_json::Value = serde_json::from_str(
-        &fs::read_to_string(fake_runtime_update_path(&dir, "container-running")).unwrap(),
+    let container_id = create("app", "app/./0.log".to_string())
+        .await
+        .unwrap()
+        .into_inner()
+        .container_id;
+    let expected = log_directory.join("app").join("0.log");
+    assert!(log_directory.join("app").is_dir());
+    let status = RuntimeService::container_status(
+        &service,
+        Request::new(ContainerStatusRequest {
+            container_id,
+            verbose: false,
+        }),
     )
+    .await
+    .unwrap()
+    .into_inner()
+    .status
     .unwrap();
-    assert_eq!(payload["cpu"]["shares"], 512);
-    assert_eq!(payload["pids"]["limit"], 7);
-    assert_eq!(payload["devices"][0]["type"], "b");
-    assert_eq!(payload["devices"][0]["allow"], false);
-    assert_eq!(payload["devices"][0]["major"], 8);
-    assert_eq!(payload["blockIO"]["weight"], 500);
+    assert_eq!(status.log_path, expected.display().to_string());
+
+    for (name, log_path) in [
+        ("escape", "../escape.log".to_string()),
+        (
+            "absolute",
+            dir.path().join("outside.log").display().to_string(),
+        ),
+        ("directory", "app/..".to_string()),
+    ] {
+        let err = create(name, log_path).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument, "{}", name);
+    }
+    std::os::unix::fs::symlink(dir.path(), log_directory.join("link")).unwrap();
+    let err = create("symlink", "link/escape.log".to_string())
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    assert_eq!(service.containers.read().await.len(), 1);
 }
 
 #[tokio::test]
-async fn nri_domain_evict_stops_container_and_persists_state() {
-    let (dir, service) = test_service_with_fake_runtime();
-    let mut annotations = HashMap::new();
-    RuntimeServiceImpl::insert_internal_state(
-        &mut annotations,
-        INTERNAL_CONTAINER_STATE_KEY,
-        &StoredContainerState::default(),
-    )
-    .unwrap();
-    let mut container = test_container("container-running", "pod-1", annotations.clone());
-    container.state = ContainerState::ContainerRunning as i32;
-    service
-        .containers
-        .lock()
-        .await
-        .insert("container-running".to_string(), container);
+async fn run_pod_sandbox_deduplicates_retries_of_the_same_attempt() {
+    let (_dir, service) = test_service_with_fake_runtime();
     service
         .pod_sandboxes
-        .lock()
+        .write()
         .await
         .insert("pod-1".to_string(), test_pod("pod-1", HashMap::new()));
-    set_fake_runtime_state(&dir, "container-running", "running");
-    service
-        .persistence
-        .lock()
-        .await
-        .save_container(
-            "container-running",
-            "pod-1",
-            crate::runtime::ContainerStatus::Running,
-            "busybox:latest",
-            &Vec::new(),
-            &HashMap::new(),
-            &annotations,
+    let run = |attempt: u32| {
+        RuntimeService::run_pod_sandbox(
+            &service,
+            Request::new(RunPodSandboxRequest {
+                config: Some(crate::proto::runtime::v1::PodSandboxConfig {
+                    metadata: Some(PodSandboxMetadata {
+                        name: "pod-1-pod".to_string(),
+                        uid: "pod-1-uid".to_string(),
+                        namespace: "default".to_string(),
+                        attempt,
+                    }),
+                    ..Default::default()
+                }),
+                runtime_handler: String::new(),
+            }),
         )
-        .unwrap();
+    };
 
-    let domain = NriRuntimeDomain {
-        containers: service.containers.clone(),
-        pod_sandboxes: service.pod_sandboxes.clone(),
-        config: service.config.clone(),
-        nri_config: service.nri_config.clone(),
-        runtime: service.runtime.clone(),
-        persistence: service.persistence.clone(),
-        events: service.events.clone(),
-    };
+    let retry = run(1).await.unwrap().into_inner();
+    assert_eq!(retry.pod_sandbox_id, "pod-1");
+    assert_eq!(service.pod_sandboxes.read().await.len(), 1);
 
-    domain.evict("container-running", "policy").await.unwrap();
+    // 更高的 attempt 不会命中已有 sandbox，而是走完整的创建流程
+    let restarted = run(2)
+        .await
+        .map(|response| response.into_inner().pod_sandbox_id);
+    assert_ne!(restarted.ok().as_deref(), Some("pod-1"));
+}
+
+#[test]
+fn shm_size_annotation_overrides_configured_default() {
+    let dir = tempdir().unwrap();
+    let mut config = test_runtime_config(dir.path().to_path_buf());
+    config.shm_size = 128 << 20;
+    let service = RuntimeServiceImpl::new(config);
+    let annotations =
+        |value: &str| HashMap::from([(CRIO_SHM_SIZE_ANNOTATION.to_string(), value.to_string())]);
+
+    assert_eq!(service.shm_size(&HashMap::new()).unwrap(), 128 << 20);
+    assert_eq!(service.shm_size(&annotations("1Gi")).unwrap(), 1 << 30);
+    assert_eq!(service.shm_size(&annotations("65536")).unwrap(), 65536);
+    for value in ["", "0", "-64Mi", "64MB", "lots"] {
+        let err = service.shm_size(&annotations(value)).unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument, "{:?}", value);
+    }
+}
+
+#[tokio::test]
+async fn fs_group_annotation_is_numeric_and_only_touches_writable_pod_volumes() {
+    use std::os::unix::fs::MetadataExt;
 
-    let containers = service.containers.lock().await;
+    let annotations =
+        |value: &str| HashMap::from([(FS_GROUP_ANNOTATION_KEY.to_string(), value.to_string())]);
+    assert_eq!(RuntimeServiceImpl::fs_group(&HashMap::new()).unwrap(), None);
     assert_eq!(
-        containers.get("container-running").unwrap().state,
-        ContainerState::ContainerExited as i32
+        RuntimeServiceImpl::fs_group(&annotations("2000")).unwrap(),
+        Some(2000)
     );
-    drop(containers);
+    for value in ["", "-1", "staff", "4294967296"] {
+        let err = RuntimeServiceImpl::fs_group(&annotations(value)).unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument, "{:?}", value);
+    }
 
-    let persisted = service
-        .persistence
-        .lock()
+    let dir = tempdir().unwrap();
+    let volumes = dir
+        .path()
+        .join("pods/uid-1/volumes/kubernetes.io~empty-dir");
+    let writable = volumes.join("cache");
+    let readonly = volumes.join("config");
+    let host_path = dir.path().join("host");
+    for path in [&writable, &readonly, &host_path] {
+        fs::create_dir_all(path).unwrap();
+        fs::set_permissions(path, fs::Permissions::from_mode(0o700)).unwrap();
+    }
+    let mount = |host: &Path, readonly: bool| crate::proto::runtime::v1::Mount {
+        host_path: host.display().to_string(),
+        container_path: "/data".to_string(),
+        readonly,
+        ..Default::default()
+    };
+    let service = RuntimeServiceImpl::new(test_runtime_config(dir.path().join("root")));
+    let gid = nix::unistd::getegid().as_raw();
+    service
+        .apply_fs_group_to_mounts(
+            &[
+                mount(&writable, false),
+                mount(&readonly, true),
+                mount(&host_path, false),
+            ],
+            gid,
+        )
         .await
-        .storage()
-        .get_container("container-running")
-        .unwrap()
         .unwrap();
-    assert_eq!(persisted.state, "stopped");
-    let persisted_annotations: HashMap<String, String> =
-        serde_json::from_str(&persisted.annotations).unwrap();
-    let persisted_state = RuntimeServiceImpl::read_internal_state::<StoredContainerState>(
-        &persisted_annotations,
-        INTERNAL_CONTAINER_STATE_KEY,
+
+    let mode = |path: &Path| fs::metadata(path).unwrap().mode() & 0o7777;
+    assert_eq!(mode(&writable), 0o2770);
+    assert_eq!(mode(&readonly), 0o700);
+    assert_eq!(mode(&host_path), 0o700);
+}
+
+fn mount_policy_service(dir: &Path) -> RuntimeServiceImpl {
+    let allowed = dir.join("allowed");
+    fs::create_dir_all(allowed.join("data")).unwrap();
+    fs::create_dir_all(allowed.join("secrets")).unwrap();
+    fs::create_dir_all(allowed.join("private")).unwrap();
+    fs::create_dir_all(dir.join("outside")).unwrap();
+    RuntimeServiceImpl::new(RuntimeConfig {
+        mount_policy: MountPolicyConfig {
+            allowed_host_paths: vec![allowed.display().to_string()],
+            denied_host_paths: vec![allowed.join("private").display().to_string()],
+            readonly_host_paths: vec![allowed.join("secrets").display().to_string()],
+        },
+        ..test_runtime_config(dir.join("root"))
+    })
+}
+
+fn host_mount_config(host_path: &Path) -> crate::proto::runtime::v1::ContainerConfig {
+    crate::proto::runtime::v1::ContainerConfig {
+        mounts: vec![crate::proto::runtime::v1::Mount {
+            container_path: "/mnt".to_string(),
+            host_path: host_path.display().to_string(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn mount_policy_allows_host_path_under_allowed_prefix() {
+    let dir = tempdir().unwrap();
+    let service = mount_policy_service(dir.path());
+
+    let mut config = host_mount_config(&dir.path().join("allowed").join("data"));
+    service.apply_mount_policy(&mut config).unwrap();
+    assert!(!config.mounts[0].readonly);
+}
+
+#[test]
+fn mount_policy_denies_disallowed_host_paths() {
+    let dir = tempdir().unwrap();
+    let service = mount_policy_service(dir.path());
+    std::os::unix::fs::symlink(
+        dir.path().join("outside"),
+        dir.path().join("allowed").join("escape"),
     )
     .unwrap();
-    assert!(persisted_state.nri_stop_notified);
+
+    for host_path in [
+        dir.path().join("outside"),
+        dir.path().join("allowed").join("private"),
+        dir.path().join("allowed").join("escape"),
+        dir.path().join("allowed").join("..").join("outside"),
+    ] {
+        let err = service
+            .apply_mount_policy(&mut host_mount_config(&host_path))
+            .unwrap_err();
+        assert_eq!(
+            err.code(),
+            tonic::Code::PermissionDenied,
+            "{}",
+            host_path.display()
+        );
+    }
 }
 
-#[tokio::test]
-async fn inspect_and_info_endpoints_return_consistent_snapshots() {
-    let (dir, service) = test_service_with_fake_runtime();
-    let netns_path = dir.path().join("inspect.netns");
-    fs::write(&netns_path, "netns").unwrap();
-    let log_path = dir.path().join("logs").join("inspect.log");
+#[test]
+fn mount_policy_forces_readonly_host_paths() {
+    let dir = tempdir().unwrap();
+    let service = mount_policy_service(dir.path());
 
-    let mut pod_annotations = HashMap::new();
-    RuntimeServiceImpl::insert_internal_state(
-        &mut pod_annotations,
-        INTERNAL_POD_STATE_KEY,
-        &StoredPodState {
-            runtime_handler: "runc".to_string(),
-            netns_path: Some(netns_path.display().to_string()),
-            pause_container_id: Some("pause-inspect".to_string()),
-            ip: Some("10.88.0.10".to_string()),
-            additional_ips: vec!["fd00::10".to_string()],
-            ..Default::default()
-        },
+    let mut config = host_mount_config(&dir.path().join("allowed").join("secrets"));
+    service.apply_mount_policy(&mut config).unwrap();
+    assert!(config.mounts[0].readonly);
+}
+
+fn env_file_container_config(path: &str) -> crate::proto::runtime::v1::ContainerConfig {
+    crate::proto::runtime::v1::ContainerConfig {
+        annotations: HashMap::from([(ENV_FILE_ANNOTATION_KEY.to_string(), path.to_string())]),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn env_file_is_merged_below_cri_env() {
+    let dir = tempdir().unwrap();
+    let env_dir = dir.path().join("env");
+    fs::create_dir_all(&env_dir).unwrap();
+    fs::write(
+        env_dir.join("app.env"),
+        "# defaults\nSHARED=from-file\n\nFILE_ONLY=a=b\n",
     )
     .unwrap();
-    service.pod_sandboxes.lock().await.insert(
-        "pod-inspect".to_string(),
-        test_pod("pod-inspect", pod_annotations),
+    let service = RuntimeServiceImpl::new(RuntimeConfig {
+        env_file_dir: Some(env_dir.clone()),
+        ..test_runtime_config(dir.path().join("root"))
+    });
+
+    let file_env = service
+        .load_env_file(&env_file_container_config("app.env"))
+        .unwrap();
+    assert_eq!(
+        file_env,
+        vec![
+            ("SHARED".to_string(), "from-file".to_string()),
+            ("FILE_ONLY".to_string(), "a=b".to_string()),
+        ]
     );
 
-    let mut container_annotations = HashMap::new();
-    RuntimeServiceImpl::insert_internal_state(
-        &mut container_annotations,
-        INTERNAL_CONTAINER_STATE_KEY,
-        &StoredContainerState {
-            log_path: Some(log_path.display().to_string()),
-            run_as_user: Some("1000".to_string()),
-            mounts: vec![StoredMount {
-                container_path: "/data".to_string(),
-                host_path: "/host/data".to_string(),
-                readonly: false,
-                selinux_relabel: false,
-                propagation: crate::proto::runtime::v1::MountPropagation::PropagationPrivate as i32,
-            }],
-            ..Default::default()
-        },
+    let merged = service.merge_container_env(
+        "",
+        "",
+        file_env,
+        &[
+            crate::proto::runtime::v1::KeyValue {
+                key: "SHARED".to_string(),
+                value: "from-cri".to_string(),
+            },
+            crate::proto::runtime::v1::KeyValue {
+                key: "CRI_ONLY".to_string(),
+                value: "1".to_string(),
+            },
+        ],
+    );
+    assert_eq!(
+        merged,
+        vec![
+            ("SHARED".to_string(), "from-cri".to_string()),
+            ("FILE_ONLY".to_string(), "a=b".to_string()),
+            ("CRI_ONLY".to_string(), "1".to_string()),
+            (
+                "PATH".to_string(),
+                crate::oci::spec::DEFAULT_PATH_ENV.to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn container_env_gets_default_path_when_unspecified() {
+    let dir = tempdir().unwrap();
+    let service = RuntimeServiceImpl::new(test_runtime_config(dir.path().join("root")));
+
+    let merged = service.merge_container_env("", "web-0", Vec::new(), &[]);
+
+    assert_eq!(
+        merged,
+        vec![
+            ("HOSTNAME".to_string(), "web-0".to_string()),
+            (
+                "PATH".to_string(),
+                crate::oci::spec::DEFAULT_PATH_ENV.to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn container_env_layers_default_image_hostname_and_cri_env() {
+    let dir = tempdir().unwrap();
+    let image_dir = dir
+        .path()
+        .join("root")
+        .join("storage")
+        .join("images")
+        .join("sha256:app");
+    fs::create_dir_all(&image_dir).unwrap();
+    fs::write(
+        image_dir.join("metadata.json"),
+        serde_json::json!({
+            "id": "sha256:app",
+            "repo_tags": ["docker.io/library/app:latest"],
+            "env": ["PATH=/app/bin:/usr/bin", "LANG=C.UTF-8", "HOSTNAME=image"],
+        })
+        .to_string(),
     )
     .unwrap();
-    service.containers.lock().await.insert(
-        "container-inspect".to_string(),
-        test_container("container-inspect", "pod-inspect", container_annotations),
+    let service = RuntimeServiceImpl::new(RuntimeConfig {
+        default_env: vec![
+            ("TZ".to_string(), "UTC".to_string()),
+            ("LANG".to_string(), "en_US.UTF-8".to_string()),
+        ],
+        ..test_runtime_config(dir.path().join("root"))
+    });
+
+    let merged = service.merge_container_env(
+        "docker.io/library/app:latest",
+        "web-0",
+        Vec::new(),
+        &[crate::proto::runtime::v1::KeyValue {
+            key: "TZ".to_string(),
+            value: "Asia/Shanghai".to_string(),
+        }],
     );
-    set_fake_runtime_state(&dir, "container-inspect", "running");
 
-    let status = RuntimeService::status(&service, Request::new(StatusRequest { verbose: true }))
-        .await
-        .unwrap()
-        .into_inner();
-    let container = RuntimeService::container_status(
-        &service,
-        Request::new(ContainerStatusRequest {
-            container_id: "container-inspect".to_string(),
-            verbose: true,
-        }),
-    )
-    .await
-    .unwrap()
-    .into_inner();
-    let pod = RuntimeService::pod_sandbox_status(
-        &service,
-        Request::new(PodSandboxStatusRequest {
-            pod_sandbox_id: "pod-inspect".to_string(),
-            verbose: true,
-        }),
+    assert_eq!(
+        merged,
+        vec![
+            ("TZ".to_string(), "Asia/Shanghai".to_string()),
+            ("LANG".to_string(), "C.UTF-8".to_string()),
+            ("PATH".to_string(), "/app/bin:/usr/bin".to_string()),
+            ("HOSTNAME".to_string(), "web-0".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn container_env_file_sits_between_default_env_and_image_env() {
+    let dir = tempdir().unwrap();
+    let image_dir = dir
+        .path()
+        .join("root")
+        .join("storage")
+        .join("images")
+        .join("sha256:app");
+    fs::create_dir_all(&image_dir).unwrap();
+    fs::write(
+        image_dir.join("metadata.json"),
+        serde_json::json!({
+            "id": "sha256:app",
+            "repo_tags": ["docker.io/library/app:latest"],
+            "env": ["PATH=/app/bin:/usr/bin", "LANG=C.UTF-8"],
+        })
+        .to_string(),
     )
-    .await
-    .unwrap()
-    .into_inner();
-    let containers =
-        RuntimeService::list_containers(&service, Request::new(ListContainersRequest::default()))
-            .await
-            .unwrap()
-            .into_inner();
-    let pods =
-        RuntimeService::list_pod_sandbox(&service, Request::new(ListPodSandboxRequest::default()))
-            .await
-            .unwrap()
-            .into_inner();
+    .unwrap();
+    let service = RuntimeServiceImpl::new(RuntimeConfig {
+        default_env: vec![
+            ("TZ".to_string(), "UTC".to_string()),
+            ("LANG".to_string(), "en_US.UTF-8".to_string()),
+        ],
+        ..test_runtime_config(dir.path().join("root"))
+    });
 
-    let config: serde_json::Value =
-        serde_json::from_str(status.info.get("config").unwrap()).unwrap();
-    assert_eq!(config["runtimeFeatures"]["containerEvents"], true);
-    assert_eq!(container.status.as_ref().unwrap().reason, "Running");
-    assert_eq!(container.status.as_ref().unwrap().mounts.len(), 1);
-    assert!(container.info.contains_key("info"));
+    let merged = service.merge_container_env(
+        "docker.io/library/app:latest",
+        "web-0",
+        vec![
+            ("TZ".to_string(), "Europe/Berlin".to_string()),
+            ("LANG".to_string(), "de_DE.UTF-8".to_string()),
+            ("PATH".to_string(), "/file/bin".to_string()),
+            ("HOSTNAME".to_string(), "from-file".to_string()),
+        ],
+        &[],
+    );
+
+    // env 文件覆盖 default_env，但镜像 Env 与 HOSTNAME 覆盖 env 文件
     assert_eq!(
-        pod.status.as_ref().unwrap().network.as_ref().unwrap().ip,
-        "10.88.0.10"
+        merged,
+        vec![
+            ("TZ".to_string(), "Europe/Berlin".to_string()),
+            ("LANG".to_string(), "C.UTF-8".to_string()),
+            ("PATH".to_string(), "/app/bin:/usr/bin".to_string()),
+            ("HOSTNAME".to_string(), "web-0".to_string()),
+        ]
     );
-    assert_eq!(pod.containers_statuses.len(), 1);
-    assert_eq!(containers.containers.len(), 1);
-    assert_eq!(pods.items.len(), 1);
 }
 
-#[tokio::test]
-async fn list_container_stats_applies_id_pod_and_label_filters() {
-    let (dir, service) = test_service_with_fake_runtime();
-
-    let mut container_a = test_container("container-alpha-123", "pod-alpha", HashMap::new());
-    container_a
-        .labels
-        .insert("app".to_string(), "api".to_string());
-    let mut container_b = test_container("container-bravo-456", "pod-bravo", HashMap::new());
-    container_b
-        .labels
-        .insert("app".to_string(), "worker".to_string());
+#[test]
+fn env_file_outside_allowed_dir_is_rejected() {
+    let dir = tempdir().unwrap();
+    let env_dir = dir.path().join("env");
+    fs::create_dir_all(&env_dir).unwrap();
+    fs::write(dir.path().join("secret.env"), "A=1\n").unwrap();
+    fs::write(env_dir.join("bad.env"), "not a pair\n").unwrap();
+    std::os::unix::fs::symlink(dir.path().join("secret.env"), env_dir.join("link.env")).unwrap();
+    let outside = dir.path().join("secret.env").display().to_string();
+
+    let disabled = RuntimeServiceImpl::new(test_runtime_config(dir.path().join("root")));
+    let err = disabled
+        .load_env_file(&env_file_container_config("app.env"))
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
 
-    {
-        let mut pods = service.pod_sandboxes.lock().await;
-        pods.insert(
-            "pod-alpha".to_string(),
-            test_pod("pod-alpha", HashMap::new()),
-        );
-        pods.insert(
-            "pod-bravo".to_string(),
-            test_pod("pod-bravo", HashMap::new()),
-        );
+    let service = RuntimeServiceImpl::new(RuntimeConfig {
+        env_file_dir: Some(env_dir),
+        ..test_runtime_config(dir.path().join("root"))
+    });
+    for path in ["../secret.env", "link.env", outside.as_str(), "bad.env"] {
+        let err = service
+            .load_env_file(&env_file_container_config(path))
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument, "{}", path);
     }
-    {
-        let mut containers = service.containers.lock().await;
-        containers.insert(container_a.id.clone(), container_a);
-        containers.insert(container_b.id.clone(), container_b);
+}
+
+#[test]
+fn env_references_expand_nested_and_keep_unknown_literal() {
+    let mut env = vec![
+        ("BASE".to_string(), "/srv".to_string()),
+        ("APP".to_string(), "$(BASE)/app".to_string()),
+        ("DATA".to_string(), "$(APP)/data:$(LATER)".to_string()),
+        ("LATER".to_string(), "late".to_string()),
+        ("PRICE".to_string(), "$$(BASE) costs $5 $(".to_string()),
+    ];
+    let mut command = vec!["/bin/run".to_string(), "--root=$(DATA)".to_string()];
+    let mut args = vec!["$(LATER)".to_string(), "$(MISSING)".to_string()];
+
+    RuntimeServiceImpl::expand_container_env_references(&mut env, &mut command, &mut args);
+
+    assert_eq!(env[1].1, "/srv/app");
+    assert_eq!(env[2].1, "/srv/app/data:$(LATER)");
+    assert_eq!(env[4].1, "$(BASE) costs $5 $(");
+    assert_eq!(command[1], "--root=/srv/app/data:$(LATER)");
+    assert_eq!(args, vec!["late".to_string(), "$(MISSING)".to_string()]);
+}
+
+#[tokio::test]
+async fn container_log_mirror_forwards_output_to_subscriber_with_rate_limit() {
+    #[derive(Clone, Default)]
+    struct Capture(Arc<StdMutex<Vec<u8>>>);
+    impl std::io::Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
     }
-    set_fake_runtime_state(&dir, "container-alpha-123", "running");
-    set_fake_runtime_state(&dir, "container-bravo-456", "running");
+    let capture = Capture::default();
+    let writer = capture.clone();
+    let _subscriber = tracing::subscriber::set_default(
+        tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .with_max_level(tracing::Level::TRACE)
+            .finish(),
+    );
+    let captured = || String::from_utf8(capture.0.lock().unwrap().clone()).unwrap();
 
-    let mut selector = HashMap::new();
-    selector.insert("app".to_string(), "api".to_string());
-    let response = RuntimeService::list_container_stats(
+    let service = test_service();
+    let dir = tempdir().unwrap();
+    let log_path = dir.path().join("c1.log");
+    fs::write(&log_path, "2024-01-01T00:00:00Z stdout F before-mirror\n").unwrap();
+    let mut annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+        &StoredContainerState {
+            log_path: Some(log_path.display().to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    service
+        .containers
+        .write()
+        .await
+        .insert("c1".to_string(), test_container("c1", "pod-1", annotations));
+
+    let mut mirror = log_mirror::ContainerLogMirror::new(tracing::Level::DEBUG, 2);
+    mirror.poll(&service.containers).await;
+    let mut file = fs::OpenOptions::new().append(true).open(&log_path).unwrap();
+    std::io::Write::write_all(
+        &mut file,
+        b"2024-01-01T00:00:01Z stdout F hello-mirror\n\
+          2024-01-01T00:00:01Z stderr F oops\n\
+          2024-01-01T00:00:01Z stdout F over-the-limit\n\
+          2024-01-01T00:00:01Z stdout P half-written",
+    )
+    .unwrap();
+    mirror.poll(&service.containers).await;
+
+    let output = captured();
+    assert!(output.contains("DEBUG"), "{}", output);
+    assert!(output.contains("[c1 stdout] hello-mirror"), "{}", output);
+    assert!(output.contains("[c1 stderr] oops"), "{}", output);
+    assert!(!output.contains("before-mirror"), "{}", output);
+    assert!(!output.contains("over-the-limit"), "{}", output);
+    assert!(!output.contains("half-written"), "{}", output);
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    mirror.poll(&service.containers).await;
+    assert!(
+        captured().contains("[c1 mirror] suppressed 1 log lines"),
+        "{}",
+        captured()
+    );
+}
+
+#[test]
+fn container_log_limits_prefer_annotations_over_global_defaults() {
+    let dir = tempdir().unwrap();
+    let service = RuntimeServiceImpl::new(RuntimeConfig {
+        container_log: crate::config::ContainerLogConfig {
+            max_size: "10Mi".to_string(),
+            max_files: 5,
+            ..Default::default()
+        },
+        ..test_runtime_config(dir.path().join("root"))
+    });
+
+    assert_eq!(
+        service.container_log_limits(&HashMap::new()).unwrap(),
+        (10 << 20, 5)
+    );
+    let overrides = HashMap::from([
+        (
+            CONTAINER_LOG_MAX_SIZE_ANNOTATION.to_string(),
+            "512Ki".to_string(),
+        ),
+        (
+            CONTAINER_LOG_MAX_FILES_ANNOTATION.to_string(),
+            "2".to_string(),
+        ),
+    ]);
+    assert_eq!(
+        service.container_log_limits(&overrides).unwrap(),
+        (512 << 10, 2)
+    );
+
+    for (key, value) in [
+        (CONTAINER_LOG_MAX_SIZE_ANNOTATION, "10MB"),
+        (CONTAINER_LOG_MAX_FILES_ANNOTATION, "0"),
+    ] {
+        let err = service
+            .container_log_limits(&HashMap::from([(key.to_string(), value.to_string())]))
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument, "{}", value);
+    }
+}
+
+#[tokio::test]
+async fn update_container_resources_applies_nri_result_before_post_update() {
+    let fake_nri = Arc::new(FakeNri::default());
+    let (dir, service) = test_service_with_fake_runtime_and_nri(fake_nri.clone());
+
+    service
+        .pod_sandboxes
+        .write()
+        .await
+        .insert("pod-1".to_string(), test_pod("pod-1", HashMap::new()));
+
+    let mut target_annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut target_annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+        &StoredContainerState::default(),
+    )
+    .unwrap();
+    service.containers.write().await.insert(
+        "container-running".to_string(),
+        test_container("container-running", "pod-1", target_annotations.clone()),
+    );
+    set_fake_runtime_state(&dir, "container-running", "running");
+    service
+        .persistence
+        .lock()
+        .await
+        .save_container(
+            "container-running",
+            "pod-1",
+            crate::runtime::ContainerStatus::Running,
+            "busybox:latest",
+            &Vec::new(),
+            &HashMap::new(),
+            &target_annotations,
+        )
+        .unwrap();
+
+    let mut sidecar_annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut sidecar_annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+        &StoredContainerState::default(),
+    )
+    .unwrap();
+    service.containers.write().await.insert(
+        "container-sidecar".to_string(),
+        test_container("container-sidecar", "pod-1", sidecar_annotations.clone()),
+    );
+    set_fake_runtime_state(&dir, "container-sidecar", "running");
+    service
+        .persistence
+        .lock()
+        .await
+        .save_container(
+            "container-sidecar",
+            "pod-1",
+            crate::runtime::ContainerStatus::Running,
+            "busybox:latest",
+            &Vec::new(),
+            &HashMap::new(),
+            &sidecar_annotations,
+        )
+        .unwrap();
+
+    let mut target_resources = crate::nri_proto::api::LinuxResources::new();
+    let mut target_cpu = crate::nri_proto::api::LinuxCPU::new();
+    let mut shares = crate::nri_proto::api::OptionalUInt64::new();
+    shares.value = 1024;
+    target_cpu.shares = protobuf::MessageField::some(shares);
+    target_resources.cpu = protobuf::MessageField::some(target_cpu);
+
+    let mut sidecar_update = crate::nri_proto::api::ContainerUpdate::new();
+    sidecar_update.container_id = "container-sidecar".to_string();
+    let mut sidecar_linux = crate::nri_proto::api::LinuxContainerUpdate::new();
+    let mut sidecar_resources = crate::nri_proto::api::LinuxResources::new();
+    let mut sidecar_memory = crate::nri_proto::api::LinuxMemory::new();
+    let mut sidecar_limit = crate::nri_proto::api::OptionalInt64::new();
+    sidecar_limit.value = 64 * 1024 * 1024;
+    sidecar_memory.limit = protobuf::MessageField::some(sidecar_limit);
+    sidecar_resources.memory = protobuf::MessageField::some(sidecar_memory);
+    sidecar_linux.resources = protobuf::MessageField::some(sidecar_resources);
+    sidecar_update.linux = protobuf::MessageField::some(sidecar_linux);
+
+    *fake_nri.update_result.lock().await = Some(NriUpdateContainerResult {
+        linux_resources: Some(target_resources),
+        updates: vec![sidecar_update],
+        evictions: Vec::new(),
+    });
+
+    RuntimeService::update_container_resources(
         &service,
-        Request::new(ListContainerStatsRequest {
-            filter: Some(crate::proto::runtime::v1::ContainerStatsFilter {
-                id: "container-alpha".to_string(),
-                pod_sandbox_id: "pod-alpha".to_string(),
-                label_selector: selector,
+        Request::new(UpdateContainerResourcesRequest {
+            container_id: "container-running".to_string(),
+            linux: Some(crate::proto::runtime::v1::LinuxContainerResources {
+                cpu_shares: 256,
+                ..Default::default()
             }),
+            windows: None,
+            annotations: HashMap::new(),
         }),
     )
-    .await
-    .unwrap()
-    .into_inner();
+    .await
+    .unwrap();
+
+    let target_payload: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(fake_runtime_update_path(&dir, "container-running")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(target_payload["cpu"]["shares"], 1024);
+
+    let sidecar_payload: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(fake_runtime_update_path(&dir, "container-sidecar")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(sidecar_payload["memory"]["limit"], 64 * 1024 * 1024);
+
+    assert_eq!(
+        fake_nri.calls.lock().await.clone(),
+        vec!["update_container", "post_update_container"]
+    );
+
+    let post_update_event = fake_nri
+        .post_update_events
+        .lock()
+        .await
+        .last()
+        .cloned()
+        .expect("post update event should be recorded");
+    let post_update_linux = post_update_event
+        .container
+        .linux
+        .as_ref()
+        .expect("post update container should include linux state");
+    assert_eq!(
+        post_update_linux
+            .resources
+            .as_ref()
+            .and_then(|resources| resources.cpu.as_ref())
+            .and_then(|cpu| cpu.shares.as_ref())
+            .map(|value| value.value),
+        Some(1024)
+    );
+
+    // status 返回 NRI 调整后实际下发的值，而不是请求中的 256
+    for (container_id, expected_shares, expected_memory) in [
+        ("container-running", 1024, 0),
+        ("container-sidecar", 0, 64 * 1024 * 1024),
+    ] {
+        let linux = RuntimeService::container_status(
+            &service,
+            Request::new(ContainerStatusRequest {
+                container_id: container_id.to_string(),
+                verbose: false,
+            }),
+        )
+        .await
+        .unwrap()
+        .into_inner()
+        .status
+        .and_then(|status| status.resources)
+        .and_then(|resources| resources.linux)
+        .expect("status should report applied linux resources");
+        assert_eq!(linux.cpu_shares, expected_shares);
+        assert_eq!(linux.memory_limit_in_bytes, expected_memory);
+    }
+}
+
+#[tokio::test]
+async fn update_container_resources_succeeds_when_nri_post_update_fails() {
+    let fake_nri = Arc::new(FakeNri {
+        fail_post_update_container: true,
+        ..Default::default()
+    });
+    let (dir, service) = test_service_with_fake_runtime_and_nri(fake_nri.clone());
+
+    service.pod_sandboxes.write().await.insert(
+        "pod-update-post-fail".to_string(),
+        test_pod("pod-update-post-fail", HashMap::new()),
+    );
+
+    let mut annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+        &StoredContainerState::default(),
+    )
+    .unwrap();
+    service.containers.write().await.insert(
+        "container-update-post-fail".to_string(),
+        test_container(
+            "container-update-post-fail",
+            "pod-update-post-fail",
+            annotations.clone(),
+        ),
+    );
+    set_fake_runtime_state(&dir, "container-update-post-fail", "running");
+    service
+        .persistence
+        .lock()
+        .await
+        .save_container(
+            "container-update-post-fail",
+            "pod-update-post-fail",
+            crate::runtime::ContainerStatus::Running,
+            "busybox:latest",
+            &Vec::new(),
+            &HashMap::new(),
+            &annotations,
+        )
+        .unwrap();
+
+    RuntimeService::update_container_resources(
+        &service,
+        Request::new(UpdateContainerResourcesRequest {
+            container_id: "container-update-post-fail".to_string(),
+            linux: Some(crate::proto::runtime::v1::LinuxContainerResources {
+                cpu_shares: 512,
+                ..Default::default()
+            }),
+            windows: None,
+            annotations: HashMap::new(),
+        }),
+    )
+    .await
+    .expect("post-update failure should not fail resource update");
+
+    let payload: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(fake_runtime_update_path(&dir, "container-update-post-fail")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(payload["cpu"]["shares"], 512);
+}
+
+#[tokio::test]
+async fn update_container_resources_applies_extended_nri_resources() {
+    let fake_nri = Arc::new(FakeNri::default());
+    let (dir, service) = test_service_with_fake_runtime_and_nri(fake_nri.clone());
+
+    service
+        .pod_sandboxes
+        .write()
+        .await
+        .insert("pod-1".to_string(), test_pod("pod-1", HashMap::new()));
+
+    let mut annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+        &StoredContainerState::default(),
+    )
+    .unwrap();
+    service.containers.write().await.insert(
+        "container-running".to_string(),
+        test_container("container-running", "pod-1", annotations.clone()),
+    );
+    set_fake_runtime_state(&dir, "container-running", "running");
+    service
+        .persistence
+        .lock()
+        .await
+        .save_container(
+            "container-running",
+            "pod-1",
+            crate::runtime::ContainerStatus::Running,
+            "busybox:latest",
+            &Vec::new(),
+            &HashMap::new(),
+            &annotations,
+        )
+        .unwrap();
+
+    let mut extended = crate::nri_proto::api::LinuxResources::new();
+    let mut memory = crate::nri_proto::api::LinuxMemory::new();
+    let mut reservation = crate::nri_proto::api::OptionalInt64::new();
+    reservation.value = 4096;
+    memory.reservation = protobuf::MessageField::some(reservation);
+    extended.memory = protobuf::MessageField::some(memory);
+    extended.pids = Some(crate::nri_proto::api::LinuxPids {
+        limit: 42,
+        ..Default::default()
+    })
+    .into();
+    extended
+        .devices
+        .push(crate::nri_proto::api::LinuxDeviceCgroup {
+            allow: true,
+            type_: "c".to_string(),
+            access: "rwm".to_string(),
+            major: {
+                let mut value = crate::nri_proto::api::OptionalInt64::new();
+                value.value = 10;
+                protobuf::MessageField::some(value)
+            },
+            minor: {
+                let mut value = crate::nri_proto::api::OptionalInt64::new();
+                value.value = 200;
+                protobuf::MessageField::some(value)
+            },
+            ..Default::default()
+        });
+    let mut blockio_class = crate::nri_proto::api::OptionalString::new();
+    blockio_class.value = "gold".to_string();
+    extended.blockio_class = protobuf::MessageField::some(blockio_class);
+    *fake_nri.update_result.lock().await = Some(NriUpdateContainerResult {
+        linux_resources: Some(extended),
+        updates: Vec::new(),
+        evictions: Vec::new(),
+    });
+
+    RuntimeService::update_container_resources(
+        &service,
+        Request::new(UpdateContainerResourcesRequest {
+            container_id: "container-running".to_string(),
+            linux: Some(crate::proto::runtime::v1::LinuxContainerResources {
+                cpu_shares: 256,
+                ..Default::default()
+            }),
+            windows: None,
+            annotations: HashMap::new(),
+        }),
+    )
+    .await
+    .unwrap();
+
+    let payload: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(fake_runtime_update_path(&dir, "container-running")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(payload["cpu"]["shares"], 256);
+    assert_eq!(payload["memory"]["reservation"], 4096);
+    assert_eq!(payload["pids"]["limit"], 42);
+    assert_eq!(payload["devices"][0]["type"], "c");
+    assert_eq!(payload["devices"][0]["major"], 10);
+    assert_eq!(payload["devices"][0]["minor"], 200);
+    assert_eq!(payload["devices"][0]["access"], "rwm");
+    assert_eq!(payload["blockIO"]["weight"], 500);
+    assert_eq!(
+        fake_nri.calls.lock().await.clone(),
+        vec!["update_container", "post_update_container"]
+    );
+
+    let container = service
+        .containers
+        .read()
+        .await
+        .get("container-running")
+        .cloned()
+        .unwrap();
+    let state = RuntimeServiceImpl::read_internal_state::<StoredContainerState>(
+        &container.annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+    )
+    .unwrap();
+    let resources = state.linux_resources.unwrap();
+    assert_eq!(resources.cpu_shares, 256);
+    assert_eq!(resources.memory_reservation_in_bytes, Some(4096));
+    assert_eq!(resources.pids_limit, Some(42));
+    assert_eq!(resources.devices.len(), 1);
+    assert_eq!(resources.devices[0].device_type.as_deref(), Some("c"));
+    assert_eq!(resources.devices[0].major, Some(10));
+    assert_eq!(resources.devices[0].minor, Some(200));
+    assert_eq!(resources.blockio_class.as_deref(), Some("gold"));
+}
+
+#[tokio::test]
+async fn stop_container_applies_nri_update_side_effects() {
+    let fake_nri = Arc::new(FakeNri::default());
+    let (dir, service) = test_service_with_fake_runtime_and_nri(fake_nri.clone());
+
+    let mut target_annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut target_annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+        &StoredContainerState::default(),
+    )
+    .unwrap();
+    let mut sidecar_annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut sidecar_annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+        &StoredContainerState::default(),
+    )
+    .unwrap();
+
+    service
+        .pod_sandboxes
+        .write()
+        .await
+        .insert("pod-1".to_string(), test_pod("pod-1", HashMap::new()));
+    service.containers.write().await.insert(
+        "container-stop".to_string(),
+        test_container("container-stop", "pod-1", target_annotations.clone()),
+    );
+    service.containers.write().await.insert(
+        "container-sidecar".to_string(),
+        test_container("container-sidecar", "pod-1", sidecar_annotations.clone()),
+    );
+    set_fake_runtime_state(&dir, "container-stop", "running");
+    set_fake_runtime_state(&dir, "container-sidecar", "running");
+    service
+        .persistence
+        .lock()
+        .await
+        .save_container(
+            "container-stop",
+            "pod-1",
+            crate::runtime::ContainerStatus::Running,
+            "busybox:latest",
+            &Vec::new(),
+            &HashMap::new(),
+            &target_annotations,
+        )
+        .unwrap();
+    service
+        .persistence
+        .lock()
+        .await
+        .save_container(
+            "container-sidecar",
+            "pod-1",
+            crate::runtime::ContainerStatus::Running,
+            "busybox:latest",
+            &Vec::new(),
+            &HashMap::new(),
+            &sidecar_annotations,
+        )
+        .unwrap();
+
+    let mut update = crate::nri_proto::api::ContainerUpdate::new();
+    update.container_id = "container-sidecar".to_string();
+    let mut linux = crate::nri_proto::api::LinuxContainerUpdate::new();
+    let mut resources = crate::nri_proto::api::LinuxResources::new();
+    let mut cpu = crate::nri_proto::api::LinuxCPU::new();
+    let mut shares = crate::nri_proto::api::OptionalUInt64::new();
+    shares.value = 2048;
+    cpu.shares = protobuf::MessageField::some(shares);
+    resources.cpu = protobuf::MessageField::some(cpu);
+    linux.resources = protobuf::MessageField::some(resources);
+    update.linux = protobuf::MessageField::some(linux);
+    *fake_nri.stop_result.lock().await = Some(NriStopContainerResult {
+        updates: vec![update],
+    });
+
+    RuntimeService::stop_container(
+        &service,
+        Request::new(StopContainerRequest {
+            container_id: "container-stop".to_string(),
+            timeout: 1,
+        }),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(fake_nri.calls.lock().await.clone(), vec!["stop_container"]);
+    let update_payload: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(fake_runtime_update_path(&dir, "container-sidecar")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(update_payload["cpu"]["shares"], 2048);
+    let sidecar = service
+        .containers
+        .read()
+        .await
+        .get("container-sidecar")
+        .cloned()
+        .unwrap();
+    let state = RuntimeServiceImpl::read_internal_state::<StoredContainerState>(
+        &sidecar.annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+    )
+    .unwrap();
+    assert_eq!(state.linux_resources.unwrap().cpu_shares, 2048);
+}
+
+#[tokio::test]
+async fn nri_domain_apply_updates_updates_runtime_and_persistence() {
+    let (dir, service) = test_service_with_fake_runtime();
+    let mut annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+        &StoredContainerState::default(),
+    )
+    .unwrap();
+    service.containers.write().await.insert(
+        "container-running".to_string(),
+        test_container("container-running", "pod-1", annotations.clone()),
+    );
+    service
+        .pod_sandboxes
+        .write()
+        .await
+        .insert("pod-1".to_string(), test_pod("pod-1", annotations.clone()));
+    set_fake_runtime_state(&dir, "container-running", "running");
+    service
+        .persistence
+        .lock()
+        .await
+        .save_container(
+            "container-running",
+            "pod-1",
+            crate::runtime::ContainerStatus::Running,
+            "busybox:latest",
+            &Vec::new(),
+            &HashMap::new(),
+            &annotations,
+        )
+        .unwrap();
+
+    let domain = NriRuntimeDomain {
+        containers: service.containers.clone(),
+        pod_sandboxes: service.pod_sandboxes.clone(),
+        config: service.config.clone(),
+        nri_config: service.nri_config.clone(),
+        runtime: service.runtime.clone(),
+        persistence: service.persistence.clone(),
+        events: service.events.clone(),
+    };
+
+    let mut update = crate::nri_proto::api::ContainerUpdate::new();
+    update.container_id = "container-running".to_string();
+    let mut linux_update = crate::nri_proto::api::LinuxContainerUpdate::new();
+    let mut resources = crate::nri_proto::api::LinuxResources::new();
+    let mut cpu = crate::nri_proto::api::LinuxCPU::new();
+    let mut shares = crate::nri_proto::api::OptionalUInt64::new();
+    shares.value = 512;
+    cpu.shares = protobuf::MessageField::some(shares);
+    resources.cpu = protobuf::MessageField::some(cpu);
+    linux_update.resources = protobuf::MessageField::some(resources);
+    update.linux = protobuf::MessageField::some(linux_update);
+
+    let failed = domain.apply_updates(&[update]).await.unwrap();
+    assert!(failed.is_empty());
+
+    let update_payload: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(fake_runtime_update_path(&dir, "container-running")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(update_payload["cpu"]["shares"], 512);
+
+    let persisted = service
+        .persistence
+        .lock()
+        .await
+        .storage()
+        .get_container("container-running")
+        .unwrap()
+        .unwrap();
+    let persisted_annotations: HashMap<String, String> =
+        serde_json::from_str(&persisted.annotations).unwrap();
+    let persisted_state = RuntimeServiceImpl::read_internal_state::<StoredContainerState>(
+        &persisted_annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+    )
+    .unwrap();
+    assert_eq!(persisted_state.linux_resources.unwrap().cpu_shares, 512);
+}
+
+#[tokio::test]
+async fn nri_domain_snapshot_excludes_exited_containers() {
+    let (_dir, service) = test_service_with_fake_runtime();
+    let mut annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+        &StoredContainerState::default(),
+    )
+    .unwrap();
+
+    service
+        .pod_sandboxes
+        .write()
+        .await
+        .insert("pod-1".to_string(), test_pod("pod-1", HashMap::new()));
+
+    let mut running = test_container("container-running", "pod-1", annotations.clone());
+    running.state = ContainerState::ContainerRunning as i32;
+    let mut exited = test_container("container-exited", "pod-1", annotations);
+    exited.state = ContainerState::ContainerExited as i32;
+
+    let mut containers = service.containers.write().await;
+    containers.insert(running.id.clone(), running);
+    containers.insert(exited.id.clone(), exited);
+    drop(containers);
+
+    let domain = NriRuntimeDomain {
+        containers: service.containers.clone(),
+        pod_sandboxes: service.pod_sandboxes.clone(),
+        config: service.config.clone(),
+        nri_config: service.nri_config.clone(),
+        runtime: service.runtime.clone(),
+        persistence: service.persistence.clone(),
+        events: service.events.clone(),
+    };
+
+    let snapshot = domain.snapshot().await.unwrap();
+    let ids = snapshot
+        .containers
+        .iter()
+        .map(|container| container.id.as_str())
+        .collect::<Vec<_>>();
+
+    assert_eq!(ids, vec!["container-running"]);
+}
+
+#[tokio::test]
+async fn nri_domain_snapshot_includes_paused_runtime_containers() {
+    let (dir, service) = test_service_with_fake_runtime();
+    let mut annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+        &StoredContainerState::default(),
+    )
+    .unwrap();
+
+    service
+        .pod_sandboxes
+        .write()
+        .await
+        .insert("pod-1".to_string(), test_pod("pod-1", HashMap::new()));
+
+    let mut paused = test_container("container-paused", "pod-1", annotations);
+    paused.state = ContainerState::ContainerExited as i32;
+    service
+        .containers
+        .write()
+        .await
+        .insert(paused.id.clone(), paused);
+    set_fake_runtime_state(&dir, "container-paused", "paused");
+
+    let domain = NriRuntimeDomain {
+        containers: service.containers.clone(),
+        pod_sandboxes: service.pod_sandboxes.clone(),
+        config: service.config.clone(),
+        nri_config: service.nri_config.clone(),
+        runtime: service.runtime.clone(),
+        persistence: service.persistence.clone(),
+        events: service.events.clone(),
+    };
+
+    let snapshot = domain.snapshot().await.unwrap();
+    assert_eq!(snapshot.containers.len(), 1);
+    assert_eq!(snapshot.containers[0].id, "container-paused");
+    assert_eq!(
+        snapshot.containers[0].state.enum_value().unwrap(),
+        crate::nri_proto::api::ContainerState::CONTAINER_PAUSED
+    );
+}
+
+#[tokio::test]
+async fn nri_domain_apply_updates_ignores_failures_marked_ignore_failure() {
+    let (_dir, service) = test_service_with_fake_runtime();
+    let domain = NriRuntimeDomain {
+        containers: service.containers.clone(),
+        pod_sandboxes: service.pod_sandboxes.clone(),
+        config: service.config.clone(),
+        nri_config: service.nri_config.clone(),
+        runtime: service.runtime.clone(),
+        persistence: service.persistence.clone(),
+        events: service.events.clone(),
+    };
+
+    let mut update = crate::nri_proto::api::ContainerUpdate::new();
+    update.container_id = "missing".to_string();
+    update.ignore_failure = true;
+
+    let failed = domain.apply_updates(&[update.clone()]).await.unwrap();
+    assert!(failed.is_empty());
+}
+
+#[tokio::test]
+async fn nri_domain_apply_updates_ignores_missing_containers() {
+    let (_dir, service) = test_service_with_fake_runtime();
+    let domain = NriRuntimeDomain {
+        containers: service.containers.clone(),
+        pod_sandboxes: service.pod_sandboxes.clone(),
+        config: service.config.clone(),
+        nri_config: service.nri_config.clone(),
+        runtime: service.runtime.clone(),
+        persistence: service.persistence.clone(),
+        events: service.events.clone(),
+    };
+
+    let mut update = crate::nri_proto::api::ContainerUpdate::new();
+    update.container_id = "missing".to_string();
+    let mut linux_update = crate::nri_proto::api::LinuxContainerUpdate::new();
+    linux_update.resources =
+        protobuf::MessageField::some(crate::nri_proto::api::LinuxResources::new());
+    update.linux = protobuf::MessageField::some(linux_update);
+
+    let failed = domain.apply_updates(&[update.clone()]).await.unwrap();
+    assert!(failed.is_empty());
+}
+
+#[tokio::test]
+async fn nri_domain_apply_updates_accepts_short_container_ids() {
+    let (dir, service) = test_service_with_fake_runtime();
+    let mut annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+        &StoredContainerState::default(),
+    )
+    .unwrap();
+    service.containers.write().await.insert(
+        "container-running-long".to_string(),
+        test_container("container-running-long", "pod-1", annotations.clone()),
+    );
+    set_fake_runtime_state(&dir, "container-running-long", "running");
+    service
+        .persistence
+        .lock()
+        .await
+        .save_container(
+            "container-running-long",
+            "pod-1",
+            crate::runtime::ContainerStatus::Running,
+            "busybox:latest",
+            &Vec::new(),
+            &HashMap::new(),
+            &annotations,
+        )
+        .unwrap();
+
+    let domain = NriRuntimeDomain {
+        containers: service.containers.clone(),
+        pod_sandboxes: service.pod_sandboxes.clone(),
+        config: service.config.clone(),
+        nri_config: service.nri_config.clone(),
+        runtime: service.runtime.clone(),
+        persistence: service.persistence.clone(),
+        events: service.events.clone(),
+    };
+
+    let mut update = crate::nri_proto::api::ContainerUpdate::new();
+    update.container_id = "container-run".to_string();
+    let mut linux_update = crate::nri_proto::api::LinuxContainerUpdate::new();
+    let mut resources = crate::nri_proto::api::LinuxResources::new();
+    let mut cpu = crate::nri_proto::api::LinuxCPU::new();
+    let mut shares = crate::nri_proto::api::OptionalUInt64::new();
+    shares.value = 768;
+    cpu.shares = protobuf::MessageField::some(shares);
+    resources.cpu = protobuf::MessageField::some(cpu);
+    linux_update.resources = protobuf::MessageField::some(resources);
+    update.linux = protobuf::MessageField::some(linux_update);
+
+    let failed = domain.apply_updates(&[update]).await.unwrap();
+    assert!(failed.is_empty());
+
+    let update_payload: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(fake_runtime_update_path(&dir, "container-running-long")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(update_payload["cpu"]["shares"], 768);
+}
+
+#[tokio::test]
+async fn nri_domain_apply_updates_ignores_non_mutable_containers() {
+    let (dir, service) = test_service_with_fake_runtime();
+    let mut annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+        &StoredContainerState::default(),
+    )
+    .unwrap();
+    service.containers.write().await.insert(
+        "container-exited".to_string(),
+        test_container("container-exited", "pod-1", annotations),
+    );
+    set_fake_runtime_state(&dir, "container-exited", "stopped");
+
+    let domain = NriRuntimeDomain {
+        containers: service.containers.clone(),
+        pod_sandboxes: service.pod_sandboxes.clone(),
+        config: service.config.clone(),
+        nri_config: service.nri_config.clone(),
+        runtime: service.runtime.clone(),
+        persistence: service.persistence.clone(),
+        events: service.events.clone(),
+    };
+
+    let mut update = crate::nri_proto::api::ContainerUpdate::new();
+    update.container_id = "container-exited".to_string();
+    let mut linux_update = crate::nri_proto::api::LinuxContainerUpdate::new();
+    linux_update.resources =
+        protobuf::MessageField::some(crate::nri_proto::api::LinuxResources::new());
+    update.linux = protobuf::MessageField::some(linux_update);
+
+    let failed = domain.apply_updates(&[update]).await.unwrap();
+    assert!(failed.is_empty());
+}
+
+#[tokio::test]
+async fn nri_domain_apply_updates_accepts_extended_resources() {
+    let (dir, service) = test_service_with_fake_runtime();
+    let mut annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+        &StoredContainerState::default(),
+    )
+    .unwrap();
+    service.containers.write().await.insert(
+        "container-running".to_string(),
+        test_container("container-running", "pod-1", annotations.clone()),
+    );
+    set_fake_runtime_state(&dir, "container-running", "running");
+    service
+        .persistence
+        .lock()
+        .await
+        .save_container(
+            "container-running",
+            "pod-1",
+            crate::runtime::ContainerStatus::Running,
+            "busybox:latest",
+            &Vec::new(),
+            &HashMap::new(),
+            &annotations,
+        )
+        .unwrap();
+
+    let domain = NriRuntimeDomain {
+        containers: service.containers.clone(),
+        pod_sandboxes: service.pod_sandboxes.clone(),
+        config: service.config.clone(),
+        nri_config: service.nri_config.clone(),
+        runtime: service.runtime.clone(),
+        persistence: service.persistence.clone(),
+        events: service.events.clone(),
+    };
+
+    let mut update = crate::nri_proto::api::ContainerUpdate::new();
+    update.container_id = "container-running".to_string();
+    let mut linux_update = crate::nri_proto::api::LinuxContainerUpdate::new();
+    let mut resources = crate::nri_proto::api::LinuxResources::new();
+    let mut cpu = crate::nri_proto::api::LinuxCPU::new();
+    let mut shares = crate::nri_proto::api::OptionalUInt64::new();
+    shares.value = 512;
+    cpu.shares = protobuf::MessageField::some(shares);
+    resources.cpu = protobuf::MessageField::some(cpu);
+    resources.pids = Some(crate::nri_proto::api::LinuxPids {
+        limit: 7,
+        ..Default::default()
+    })
+    .into();
+    resources
+        .devices
+        .push(crate::nri_proto::api::LinuxDeviceCgroup {
+            allow: false,
+            type_: "b".to_string(),
+            access: "rw".to_string(),
+            major: {
+                let mut value = crate::nri_proto::api::OptionalInt64::new();
+                value.value = 8;
+                protobuf::MessageField::some(value)
+            },
+            minor: {
+                let mut value = crate::nri_proto::api::OptionalInt64::new();
+                value.value = 0;
+                protobuf::MessageField::some(value)
+            },
+            ..Default::default()
+        });
+    let mut blockio_class = crate::nri_proto::api::OptionalString::new();
+    blockio_class.value = "gold".to_string();
+    resources.blockio_class = protobuf::MessageField::some(blockio_class);
+    linux_update.resources = protobuf::MessageField::some(resources);
+    update.linux = protobuf::MessageField::some(linux_update);
+
+    let failed = domain.apply_updates(&[update]).await.unwrap();
+    assert!(failed.is_empty());
+
+    let payload: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(fake_runtime_update_path(&dir, "container-running")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(payload["cpu"]["shares"], 512);
+    assert_eq!(payload["pids"]["limit"], 7);
+    assert_eq!(payload["devices"][0]["type"], "b");
+    assert_eq!(payload["devices"][0]["allow"], false);
+    assert_eq!(payload["devices"][0]["major"], 8);
+    assert_eq!(payload["blockIO"]["weight"], 500);
+}
+
+#[tokio::test]
+async fn nri_domain_evict_stops_container_and_persists_state() {
+    let (dir, service) = test_service_with_fake_runtime();
+    let mut annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+        &StoredContainerState::default(),
+    )
+    .unwrap();
+    let mut container = test_container("container-running", "pod-1", annotations.clone());
+    container.state = ContainerState::ContainerRunning as i32;
+    service
+        .containers
+        .write()
+        .await
+        .insert("container-running".to_string(), container);
+    service
+        .pod_sandboxes
+        .write()
+        .await
+        .insert("pod-1".to_string(), test_pod("pod-1", HashMap::new()));
+    set_fake_runtime_state(&dir, "container-running", "running");
+    service
+        .persistence
+        .lock()
+        .await
+        .save_container(
+            "container-running",
+            "pod-1",
+            crate::runtime::ContainerStatus::Running,
+            "busybox:latest",
+            &Vec::new(),
+            &HashMap::new(),
+            &annotations,
+        )
+        .unwrap();
+
+    let domain = NriRuntimeDomain {
+        containers: service.containers.clone(),
+        pod_sandboxes: service.pod_sandboxes.clone(),
+        config: service.config.clone(),
+        nri_config: service.nri_config.clone(),
+        runtime: service.runtime.clone(),
+        persistence: service.persistence.clone(),
+        events: service.events.clone(),
+    };
+
+    domain.evict("container-running", "policy").await.unwrap();
+
+    let containers = service.containers.read().await;
+    assert_eq!(
+        containers.get("container-running").unwrap().state,
+        ContainerState::ContainerExited as i32
+    );
+    drop(containers);
+
+    let persisted = service
+        .persistence
+        .lock()
+        .await
+        .storage()
+        .get_container("container-running")
+        .unwrap()
+        .unwrap();
+    assert_eq!(persisted.state, "stopped");
+    let persisted_annotations: HashMap<String, String> =
+        serde_json::from_str(&persisted.annotations).unwrap();
+    let persisted_state = RuntimeServiceImpl::read_internal_state::<StoredContainerState>(
+        &persisted_annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+    )
+    .unwrap();
+    assert!(persisted_state.nri_stop_notified);
+}
+
+#[tokio::test]
+async fn inspect_and_info_endpoints_return_consistent_snapshots() {
+    let (dir, service) = test_service_with_fake_runtime();
+    let netns_path = dir.path().join("inspect.netns");
+    fs::write(&netns_path, "netns").unwrap();
+    let log_path = dir.path().join("logs").join("inspect.log");
+
+    let mut pod_annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut pod_annotations,
+        INTERNAL_POD_STATE_KEY,
+        &StoredPodState {
+            runtime_handler: "runc".to_string(),
+            netns_path: Some(netns_path.display().to_string()),
+            pause_container_id: Some("pause-inspect".to_string()),
+            ip: Some("10.88.0.10".to_string()),
+            additional_ips: vec!["fd00::10".to_string()],
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    service.pod_sandboxes.write().await.insert(
+        "pod-inspect".to_string(),
+        test_pod("pod-inspect", pod_annotations),
+    );
+
+    let mut container_annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut container_annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+        &StoredContainerState {
+            log_path: Some(log_path.display().to_string()),
+            run_as_user: Some("1000".to_string()),
+            mounts: vec![StoredMount {
+                container_path: "/data".to_string(),
+                host_path: "/host/data".to_string(),
+                readonly: false,
+                selinux_relabel: false,
+                propagation: crate::proto::runtime::v1::MountPropagation::PropagationPrivate as i32,
+            }],
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    service.containers.write().await.insert(
+        "container-inspect".to_string(),
+        test_container("container-inspect", "pod-inspect", container_annotations),
+    );
+    set_fake_runtime_state(&dir, "container-inspect", "running");
+
+    let status = RuntimeService::status(&service, Request::new(StatusRequest { verbose: true }))
+        .await
+        .unwrap()
+        .into_inner();
+    let container = RuntimeService::container_status(
+        &service,
+        Request::new(ContainerStatusRequest {
+            container_id: "container-inspect".to_string(),
+            verbose: true,
+        }),
+    )
+    .await
+    .unwrap()
+    .into_inner();
+    let pod = RuntimeService::pod_sandbox_status(
+        &service,
+        Request::new(PodSandboxStatusRequest {
+            pod_sandbox_id: "pod-inspect".to_string(),
+            verbose: true,
+        }),
+    )
+    .await
+    .unwrap()
+    .into_inner();
+    let containers =
+        RuntimeService::list_containers(&service, Request::new(ListContainersRequest::default()))
+            .await
+            .unwrap()
+            .into_inner();
+    let pods =
+        RuntimeService::list_pod_sandbox(&service, Request::new(ListPodSandboxRequest::default()))
+            .await
+            .unwrap()
+            .into_inner();
+
+    let config: serde_json::Value =
+        serde_json::from_str(status.info.get("config").unwrap()).unwrap();
+    assert_eq!(config["runtimeFeatures"]["containerEvents"], true);
+    assert_eq!(container.status.as_ref().unwrap().reason, "Running");
+    assert_eq!(container.status.as_ref().unwrap().mounts.len(), 1);
+    assert!(container.info.contains_key("info"));
+    assert_eq!(
+        pod.status.as_ref().unwrap().network.as_ref().unwrap().ip,
+        "10.88.0.10"
+    );
+    assert_eq!(pod.containers_statuses.len(), 1);
+    assert_eq!(containers.containers.len(), 1);
+    assert_eq!(pods.items.len(), 1);
+}
+
+#[tokio::test]
+async fn list_container_stats_applies_id_pod_and_label_filters() {
+    let (dir, service) = test_service_with_fake_runtime();
+
+    let mut container_a = test_container("container-alpha-123", "pod-alpha", HashMap::new());
+    container_a
+        .labels
+        .insert("app".to_string(), "api".to_string());
+    let mut container_b = test_container("container-bravo-456", "pod-bravo", HashMap::new());
+    container_b
+        .labels
+        .insert("app".to_string(), "worker".to_string());
+
+    {
+        let mut pods = service.pod_sandboxes.write().await;
+        pods.insert(
+            "pod-alpha".to_string(),
+            test_pod("pod-alpha", HashMap::new()),
+        );
+        pods.insert(
+            "pod-bravo".to_string(),
+            test_pod("pod-bravo", HashMap::new()),
+        );
+    }
+    {
+        let mut containers = service.containers.write().await;
+        containers.insert(container_a.id.clone(), container_a);
+        containers.insert(container_b.id.clone(), container_b);
+    }
+    set_fake_runtime_state(&dir, "container-alpha-123", "running");
+    set_fake_runtime_state(&dir, "container-bravo-456", "running");
+
+    let mut selector = HashMap::new();
+    selector.insert("app".to_string(), "api".to_string());
+    let response = RuntimeService::list_container_stats(
+        &service,
+        Request::new(ListContainerStatsRequest {
+            filter: Some(crate::proto::runtime::v1::ContainerStatsFilter {
+                id: "container-alpha".to_string(),
+                pod_sandbox_id: "pod-alpha".to_string(),
+                label_selector: selector,
+            }),
+        }),
+    )
+    .await
+    .unwrap()
+    .into_inner();
+
+    assert_eq!(response.stats.len(), 1);
+    assert_eq!(
+        response.stats[0]
+            .attributes
+            .as_ref()
+            .expect("container stats should include attributes")
+            .id,
+        "container-alpha-123"
+    );
+}
+
+#[tokio::test]
+async fn list_container_stats_coalesces_concurrent_scans_within_interval() {
+    let (dir, mut service) = test_service_with_fake_runtime();
+    service.config.stats = crate::config::StatsConfig {
+        scan_interval_ms: 60_000,
+        batch_size: 2,
+    };
+    {
+        let mut containers = service.containers.write().await;
+        for index in 0..5 {
+            let id = format!("container-stats-{}", index);
+            containers.insert(id.clone(), test_container(&id, "pod-1", HashMap::new()));
+        }
+    }
+    for index in 0..5 {
+        set_fake_runtime_state(&dir, &format!("container-stats-{}", index), "running");
+    }
+    let list = || {
+        RuntimeService::list_container_stats(
+            &service,
+            Request::new(ListContainerStatsRequest { filter: None }),
+        )
+    };
+
+    let responses = futures::future::join_all((0..32).map(|_| list())).await;
+    for response in responses {
+        assert_eq!(response.unwrap().into_inner().stats.len(), 5);
+    }
+    assert_eq!(service.container_stats_cache.lock().await.scans, 1);
+
+    // 间隔内的后续请求直接使用缓存，但已删除的容器不再返回
+    service.containers.write().await.remove("container-stats-0");
+    let cached = list().await.unwrap().into_inner();
+    assert_eq!(cached.stats.len(), 4);
+    assert_eq!(service.container_stats_cache.lock().await.scans, 1);
+
+    service.config.stats.scan_interval_ms = 0;
+    let list = || {
+        RuntimeService::list_container_stats(
+            &service,
+            Request::new(ListContainerStatsRequest { filter: None }),
+        )
+    };
+    list().await.unwrap();
+    list().await.unwrap();
+    assert_eq!(service.container_stats_cache.lock().await.scans, 3);
+}
+
+#[tokio::test]
+async fn list_containers_returns_empty_when_short_id_filter_is_ambiguous() {
+    let service = test_service();
+
+    {
+        let mut containers = service.containers.write().await;
+        containers.insert(
+            "container-ambiguous-a".to_string(),
+            test_container("container-ambiguous-a", "pod-a", HashMap::new()),
+        );
+        containers.insert(
+            "container-ambiguous-b".to_string(),
+            test_container("container-ambiguous-b", "pod-b", HashMap::new()),
+        );
+    }
+
+    let response = RuntimeService::list_containers(
+        &service,
+        Request::new(ListContainersRequest {
+            filter: Some(crate::proto::runtime::v1::ContainerFilter {
+                id: "container-ambiguous".to_string(),
+                ..Default::default()
+            }),
+        }),
+    )
+    .await
+    .unwrap()
+    .into_inner();
+
+    assert!(
+        response.containers.is_empty(),
+        "ambiguous short container ids should behave like cri-o filters and return no entries"
+    );
+}
+
+#[tokio::test]
+async fn container_stats_resolves_short_id_and_list_container_stats_skips_exited() {
+    let (dir, service) = test_service_with_fake_runtime();
+
+    {
+        let mut containers = service.containers.write().await;
+        containers.insert(
+            "container-running-123".to_string(),
+            test_container("container-running-123", "pod-1", HashMap::new()),
+        );
+        containers.insert(
+            "container-stopped-456".to_string(),
+            test_container("container-stopped-456", "pod-1", HashMap::new()),
+        );
+    }
+    set_fake_runtime_state(&dir, "container-running-123", "running");
+    set_fake_runtime_state(&dir, "container-stopped-456", "stopped");
+
+    let stats = RuntimeService::container_stats(
+        &service,
+        Request::new(ContainerStatsRequest {
+            container_id: "container-running".to_string(),
+        }),
+    )
+    .await
+    .unwrap()
+    .into_inner()
+    .stats
+    .expect("short container id should resolve to stats");
+    assert_eq!(
+        stats
+            .attributes
+            .as_ref()
+            .expect("container stats should include attributes")
+            .id,
+        "container-running-123"
+    );
+
+    let list = RuntimeService::list_container_stats(
+        &service,
+        Request::new(ListContainerStatsRequest { filter: None }),
+    )
+    .await
+    .unwrap()
+    .into_inner();
+
+    let ids = list
+        .stats
+        .iter()
+        .filter_map(|stat| {
+            stat.attributes
+                .as_ref()
+                .map(|attributes| attributes.id.clone())
+        })
+        .collect::<Vec<_>>();
+    assert!(
+        ids.iter().any(|id| id == "container-running-123"),
+        "running containers should remain visible in list stats"
+    );
+    assert!(
+        !ids.iter().any(|id| id == "container-stopped-456"),
+        "exited containers should be filtered out from list stats by default"
+    );
+}
+
+#[tokio::test]
+async fn list_pod_sandbox_stats_applies_id_and_label_filters() {
+    let service = test_service();
+
+    let mut pod_alpha = test_pod("pod-alpha", HashMap::new());
+    pod_alpha
+        .labels
+        .insert("tier".to_string(), "frontend".to_string());
+    let pod_alpha_uid = pod_alpha
+        .metadata
+        .as_ref()
+        .expect("test pod should include metadata")
+        .uid
+        .clone();
+
+    let mut pod_bravo = test_pod("pod-bravo", HashMap::new());
+    pod_bravo
+        .labels
+        .insert("tier".to_string(), "backend".to_string());
+    let pod_bravo_uid = pod_bravo
+        .metadata
+        .as_ref()
+        .expect("test pod should include metadata")
+        .uid
+        .clone();
+
+    {
+        let mut pods = service.pod_sandboxes.write().await;
+        pods.insert("pod-alpha".to_string(), pod_alpha);
+        pods.insert("pod-bravo".to_string(), pod_bravo);
+    }
+
+    let mut container_alpha_annotations = HashMap::new();
+    container_alpha_annotations.insert("io.kubernetes.pod.uid".to_string(), pod_alpha_uid);
+    let mut container_bravo_annotations = HashMap::new();
+    container_bravo_annotations.insert("io.kubernetes.pod.uid".to_string(), pod_bravo_uid);
+    {
+        let mut containers = service.containers.write().await;
+        containers.insert(
+            "container-alpha".to_string(),
+            test_container("container-alpha", "pod-alpha", container_alpha_annotations),
+        );
+        containers.insert(
+            "container-bravo".to_string(),
+            test_container("container-bravo", "pod-bravo", container_bravo_annotations),
+        );
+    }
+
+    let mut selector = HashMap::new();
+    selector.insert("tier".to_string(), "frontend".to_string());
+    let response = RuntimeService::list_pod_sandbox_stats(
+        &service,
+        Request::new(ListPodSandboxStatsRequest {
+            filter: Some(crate::proto::runtime::v1::PodSandboxStatsFilter {
+                id: "pod-al".to_string(),
+                label_selector: selector,
+            }),
+        }),
+    )
+    .await
+    .unwrap()
+    .into_inner();
+
+    assert_eq!(response.stats.len(), 1);
+    assert_eq!(
+        response.stats[0]
+            .attributes
+            .as_ref()
+            .expect("pod stats should include attributes")
+            .id,
+        "pod-alpha"
+    );
+}
+
+#[tokio::test]
+async fn pod_sandbox_stats_resolves_short_id_and_hides_internal_annotations() {
+    let (dir, service) = test_service_with_fake_runtime();
+
+    let mut pod_annotations = HashMap::new();
+    pod_annotations.insert("visible".to_string(), "true".to_string());
+    RuntimeServiceImpl::insert_internal_state(
+        &mut pod_annotations,
+        INTERNAL_POD_STATE_KEY,
+        &StoredPodState {
+            pause_container_id: Some("pause-pod-short-123".to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    service.pod_sandboxes.write().await.insert(
+        "pod-short-123".to_string(),
+        test_pod("pod-short-123", pod_annotations),
+    );
+    service.containers.write().await.insert(
+        "container-pod-short-123".to_string(),
+        test_container("container-pod-short-123", "pod-short-123", HashMap::new()),
+    );
+    set_fake_runtime_state(&dir, "container-pod-short-123", "running");
+    set_fake_runtime_state(&dir, "pause-pod-short-123", "running");
+
+    let response = RuntimeService::pod_sandbox_stats(
+        &service,
+        Request::new(PodSandboxStatsRequest {
+            pod_sandbox_id: "pod-short".to_string(),
+        }),
+    )
+    .await
+    .unwrap()
+    .into_inner();
+    let stats = response
+        .stats
+        .expect("short pod id should resolve to pod sandbox stats");
+    let attributes = stats
+        .attributes
+        .expect("pod stats should include attributes");
+    assert_eq!(attributes.id, "pod-short-123");
+    assert_eq!(
+        attributes.annotations.get("visible").map(String::as_str),
+        Some("true")
+    );
+    assert!(
+        !attributes.annotations.contains_key(INTERNAL_POD_STATE_KEY),
+        "internal pod annotations should not leak through statsp responses"
+    );
+}
+
+#[tokio::test]
+async fn list_pod_sandbox_stats_returns_empty_when_short_id_filter_is_ambiguous() {
+    let service = test_service();
+
+    {
+        let mut pods = service.pod_sandboxes.write().await;
+        pods.insert(
+            "pod-ambiguous-a".to_string(),
+            test_pod("pod-ambiguous-a", HashMap::new()),
+        );
+        pods.insert(
+            "pod-ambiguous-b".to_string(),
+            test_pod("pod-ambiguous-b", HashMap::new()),
+        );
+    }
+
+    let response = RuntimeService::list_pod_sandbox_stats(
+        &service,
+        Request::new(ListPodSandboxStatsRequest {
+            filter: Some(crate::proto::runtime::v1::PodSandboxStatsFilter {
+                id: "pod-ambiguous".to_string(),
+                ..Default::default()
+            }),
+        }),
+    )
+    .await
+    .unwrap()
+    .into_inner();
+
+    assert!(
+        response.stats.is_empty(),
+        "ambiguous short pod ids should return no stats entries"
+    );
+}
+
+#[tokio::test]
+async fn list_metric_descriptors_returns_non_empty_supported_names() {
+    let service = test_service();
+    let response = RuntimeService::list_metric_descriptors(
+        &service,
+        Request::new(ListMetricDescriptorsRequest {}),
+    )
+    .await
+    .unwrap()
+    .into_inner();
+
+    assert!(
+        !response.descriptors.is_empty(),
+        "metric descriptors should not be empty"
+    );
+    let names: HashSet<String> = response
+        .descriptors
+        .into_iter()
+        .map(|descriptor| descriptor.name)
+        .collect();
+    assert!(names.contains("container_cpu_usage_seconds_total"));
+    assert!(names.contains("container_memory_working_set_bytes"));
+    assert!(names.contains("container_memory_usage_bytes"));
+    assert!(names.contains("container_spec_memory_limit_bytes"));
+}
+
+#[tokio::test]
+async fn list_pod_sandbox_metrics_returns_pod_and_container_entries() {
+    let service = test_service();
+
+    let pod = test_pod("pod-metrics", HashMap::new());
+    let pod_uid = pod
+        .metadata
+        .as_ref()
+        .expect("test pod should include metadata")
+        .uid
+        .clone();
+    service
+        .pod_sandboxes
+        .write()
+        .await
+        .insert("pod-metrics".to_string(), pod);
+
+    let mut container_annotations = HashMap::new();
+    container_annotations.insert("io.kubernetes.pod.uid".to_string(), pod_uid);
+    service.containers.write().await.insert(
+        "container-metrics".to_string(),
+        test_container("container-metrics", "pod-metrics", container_annotations),
+    );
+
+    let response = RuntimeService::list_pod_sandbox_metrics(
+        &service,
+        Request::new(ListPodSandboxMetricsRequest {}),
+    )
+    .await
+    .unwrap()
+    .into_inner();
+
+    assert_eq!(response.pod_metrics.len(), 1);
+    let pod_metrics = &response.pod_metrics[0];
+    assert_eq!(pod_metrics.pod_sandbox_id, "pod-metrics");
+    assert!(
+        !pod_metrics.metrics.is_empty(),
+        "pod-level metrics should not be empty"
+    );
+    assert_eq!(pod_metrics.container_metrics.len(), 1);
+    assert_eq!(
+        pod_metrics.container_metrics[0].container_id,
+        "container-metrics"
+    );
+    assert!(
+        !pod_metrics.container_metrics[0].metrics.is_empty(),
+        "container-level metrics should not be empty"
+    );
+}
+
+#[tokio::test]
+async fn get_container_events_streams_broadcast_events() {
+    let service = test_service();
+    let mut stream =
+        RuntimeService::get_container_events(&service, Request::new(GetEventsRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+
+    service.publish_event(ContainerEventResponse {
+        container_id: "container-1".to_string(),
+        container_event_type: ContainerEventType::ContainerCreatedEvent as i32,
+        created_at: 1,
+        pod_sandbox_status: None,
+        containers_statuses: Vec::new(),
+    });
+
+    let event = stream.next().await.unwrap().unwrap();
+    assert_eq!(event.container_id, "container-1");
+    assert_eq!(
+        event.container_event_type,
+        ContainerEventType::ContainerCreatedEvent as i32
+    );
+}
+
+#[tokio::test]
+async fn get_container_events_broadcasts_to_multiple_subscribers() {
+    let service = test_service();
+    let mut stream_a =
+        RuntimeService::get_container_events(&service, Request::new(GetEventsRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+    let mut stream_b =
+        RuntimeService::get_container_events(&service, Request::new(GetEventsRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+
+    service.publish_event(ContainerEventResponse {
+        container_id: "shared".to_string(),
+        container_event_type: ContainerEventType::ContainerStartedEvent as i32,
+        created_at: 2,
+        pod_sandbox_status: None,
+        containers_statuses: Vec::new(),
+    });
+
+    let event_a = stream_a.next().await.unwrap().unwrap();
+    let event_b = stream_b.next().await.unwrap().unwrap();
+    assert_eq!(event_a.container_id, "shared");
+    assert_eq!(event_b.container_id, "shared");
+    assert_eq!(event_a.container_event_type, event_b.container_event_type);
+}
+
+#[tokio::test]
+async fn get_container_events_preserves_publish_order() {
+    let service = test_service();
+    let mut stream =
+        RuntimeService::get_container_events(&service, Request::new(GetEventsRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+
+    for (id, event_type) in [
+        ("created", ContainerEventType::ContainerCreatedEvent),
+        ("started", ContainerEventType::ContainerStartedEvent),
+        ("stopped", ContainerEventType::ContainerStoppedEvent),
+        ("deleted", ContainerEventType::ContainerDeletedEvent),
+    ] {
+        service.publish_event(ContainerEventResponse {
+            container_id: id.to_string(),
+            container_event_type: event_type as i32,
+            created_at: RuntimeServiceImpl::now_nanos(),
+            pod_sandbox_status: None,
+            containers_statuses: Vec::new(),
+        });
+    }
+
+    let mut received = Vec::new();
+    for _ in 0..4 {
+        let event = stream.next().await.unwrap().unwrap();
+        received.push((event.container_id, event.container_event_type));
+    }
+
+    assert_eq!(
+        received,
+        vec![
+            (
+                "created".to_string(),
+                ContainerEventType::ContainerCreatedEvent as i32
+            ),
+            (
+                "started".to_string(),
+                ContainerEventType::ContainerStartedEvent as i32
+            ),
+            (
+                "stopped".to_string(),
+                ContainerEventType::ContainerStoppedEvent as i32
+            ),
+            (
+                "deleted".to_string(),
+                ContainerEventType::ContainerDeletedEvent as i32
+            ),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn pod_events_use_pause_container_id_when_available_and_preserve_order() {
+    let service = test_service();
+    let mut annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut annotations,
+        INTERNAL_POD_STATE_KEY,
+        &StoredPodState {
+            pause_container_id: Some("pause-event-1".to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let pod = test_pod("pod-event-1", annotations);
+
+    let mut stream =
+        RuntimeService::get_container_events(&service, Request::new(GetEventsRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+
+    service
+        .emit_pod_event(ContainerEventType::ContainerCreatedEvent, &pod, Vec::new())
+        .await;
+    service
+        .emit_pod_event(ContainerEventType::ContainerStartedEvent, &pod, Vec::new())
+        .await;
+
+    let created = stream.next().await.unwrap().unwrap();
+    let started = stream.next().await.unwrap().unwrap();
+    assert_eq!(created.container_id, "pause-event-1");
+    assert_eq!(started.container_id, "pause-event-1");
+    assert_eq!(
+        created.container_event_type,
+        ContainerEventType::ContainerCreatedEvent as i32
+    );
+    assert_eq!(
+        started.container_event_type,
+        ContainerEventType::ContainerStartedEvent as i32
+    );
+    assert_eq!(
+        started
+            .pod_sandbox_status
+            .expect("pod status should be included")
+            .id,
+        "pod-event-1"
+    );
+}
+
+#[tokio::test]
+async fn background_refresh_marks_vanished_containers_exited_and_emits_event() {
+    let (dir, service) = test_service_with_fake_runtime();
+    let mut annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+        &StoredContainerState {
+            started_at: Some(RuntimeServiceImpl::now_nanos()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    {
+        let mut containers = service.containers.write().await;
+        let mut vanished = test_container("container-vanished", "pod-1", annotations.clone());
+        vanished.state = ContainerState::ContainerRunning as i32;
+        containers.insert("container-vanished".to_string(), vanished);
+        let mut alive = test_container("container-alive", "pod-1", annotations);
+        alive.state = ContainerState::ContainerRunning as i32;
+        containers.insert("container-alive".to_string(), alive);
+    }
+    set_fake_runtime_state(&dir, "container-alive", "running");
+    let mut events = service.events.subscribe();
+
+    RuntimeServiceImpl::refresh_runtime_state_and_publish_events(
+        &service.runtime,
+        &service.config,
+        &service.containers,
+        &service.pod_sandboxes,
+        &service.persistence,
+        &service.events,
+    )
+    .await;
+
+    let containers = service.containers.read().await;
+    let vanished = containers.get("container-vanished").unwrap();
+    assert_eq!(vanished.state, ContainerState::ContainerExited as i32);
+    let state = RuntimeServiceImpl::read_internal_state::<StoredContainerState>(
+        &vanished.annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+    )
+    .unwrap();
+    assert_eq!(state.exit_code, Some(VANISHED_CONTAINER_EXIT_CODE));
+    assert!(state.finished_at.is_some());
+    assert_eq!(
+        containers.get("container-alive").unwrap().state,
+        ContainerState::ContainerRunning as i32
+    );
+    drop(containers);
+
+    let event = events.try_recv().unwrap();
+    assert_eq!(event.container_id, "container-vanished");
+    assert_eq!(
+        event.container_event_type,
+        ContainerEventType::ContainerStoppedEvent as i32
+    );
+    assert!(events.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn state_reconciler_is_disabled_for_zero_interval() {
+    let (_dir, service) = test_service_with_fake_runtime();
+    assert!(service.start_state_reconciler().is_none());
+}
+
+#[tokio::test]
+async fn publish_event_without_subscribers_does_not_panic() {
+    let service = test_service();
+    service.publish_event(ContainerEventResponse {
+        container_id: "orphan".to_string(),
+        container_event_type: ContainerEventType::ContainerCreatedEvent as i32,
+        created_at: 1,
+        pod_sandbox_status: None,
+        containers_statuses: Vec::new(),
+    });
+}
+
+#[tokio::test]
+async fn get_container_events_reports_lagged_consumers() {
+    let service = test_service();
+    let mut stream =
+        RuntimeService::get_container_events(&service, Request::new(GetEventsRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+
+    for idx in 0..600 {
+        service.publish_event(ContainerEventResponse {
+            container_id: format!("container-{}", idx),
+            container_event_type: ContainerEventType::ContainerCreatedEvent as i32,
+            created_at: idx,
+            pod_sandbox_status: None,
+            containers_statuses: Vec::new(),
+        });
+    }
+
+    let mut saw_lagged = false;
+    for _ in 0..260 {
+        match timeout(Duration::from_millis(200), stream.next()).await {
+            Ok(Some(Err(status))) if status.code() == tonic::Code::ResourceExhausted => {
+                saw_lagged = true;
+                break;
+            }
+            Ok(Some(_)) => continue,
+            _ => break,
+        }
+    }
+
+    assert!(saw_lagged, "expected lagged consumer error");
+}
+
+#[tokio::test]
+async fn stalled_event_consumer_does_not_block_container_lifecycle() {
+    let (dir, mut service) = test_service_with_fake_runtime();
+    let rootfs_dir = dir.path().join("prepared");
+    fs::create_dir_all(rootfs_dir.join("busybox/bin")).unwrap();
+    service.config.prepared_rootfs_dir = Some(rootfs_dir);
+    let mut annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut annotations,
+        INTERNAL_POD_STATE_KEY,
+        &StoredPodState {
+            pause_container_id: Some("pause-1".to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    service
+        .pod_sandboxes
+        .write()
+        .await
+        .insert("pod-1".to_string(), test_pod("pod-1", annotations));
+    set_fake_runtime_state(&dir, "pause-1", "running");
+
+    // 订阅后从不读取，转发缓冲区与广播缓冲区都被占满
+    let mut stalled =
+        RuntimeService::get_container_events(&service, Request::new(GetEventsRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+    for idx in 0..(CONTAINER_EVENTS_BUFFER + CONTAINER_EVENTS_STREAM_BUFFER) * 2 {
+        service.publish_event(ContainerEventResponse {
+            container_id: format!("container-{}", idx),
+            container_event_type: ContainerEventType::ContainerCreatedEvent as i32,
+            created_at: idx as i64,
+            pod_sandbox_status: None,
+            containers_statuses: Vec::new(),
+        });
+    }
+
+    let lifecycle = async {
+        let container_id = RuntimeService::create_container(
+            &service,
+            Request::new(CreateContainerRequest {
+                pod_sandbox_id: "pod-1".to_string(),
+                config: Some(crate::proto::runtime::v1::ContainerConfig {
+                    metadata: Some(crate::proto::runtime::v1::ContainerMetadata {
+                        name: "app".to_string(),
+                        attempt: 0,
+                    }),
+                    annotations: HashMap::from([(
+                        ROOTFS_PATH_ANNOTATION_KEY.to_string(),
+                        "busybox".to_string(),
+                    )]),
+                    ..Default::default()
+                }),
+                sandbox_config: None,
+            }),
+        )
+        .await
+        .unwrap()
+        .into_inner()
+        .container_id;
+        RuntimeService::stop_container(
+            &service,
+            Request::new(StopContainerRequest {
+                container_id,
+                timeout: 0,
+            }),
+        )
+        .await
+        .unwrap();
+    };
+    timeout(Duration::from_secs(5), lifecycle)
+        .await
+        .expect("lifecycle operations must not wait for a stalled event consumer");
+
+    // 慢订阅者收到溢出提示后流结束，需要重新订阅
+    let mut saw_overflow = false;
+    while let Ok(Some(item)) = timeout(Duration::from_secs(1), stalled.next()).await {
+        if let Err(status) = item {
+            assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+            assert!(status.message().contains("resync"));
+            saw_overflow = true;
+        }
+    }
+    assert!(
+        saw_overflow,
+        "expected overflow signal for stalled consumer"
+    );
+    assert!(timeout(Duration::from_secs(1), stalled.next())
+        .await
+        .is_ok_and(|item| item.is_none()));
+}
+
+#[tokio::test]
+async fn exit_monitor_publishes_async_stop_events() {
+    let fake_nri = Arc::new(FakeNri::default());
+    let (dir, service) = test_service_with_fake_runtime_and_nri(fake_nri.clone());
+    let mut annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+        &StoredContainerState::default(),
+    )
+    .unwrap();
+    service.containers.write().await.insert(
+        "async-stop".to_string(),
+        Container {
+            state: ContainerState::ContainerRunning as i32,
+            ..test_container("async-stop", "pod-1", annotations)
+        },
+    );
+    service
+        .persistence
+        .lock()
+        .await
+        .save_container(
+            "async-stop",
+            "pod-1",
+            crate::runtime::ContainerStatus::Running,
+            "busybox:latest",
+            &Vec::new(),
+            &HashMap::new(),
+            &service
+                .containers
+                .read()
+                .await
+                .get("async-stop")
+                .unwrap()
+                .annotations,
+        )
+        .unwrap();
+    service.ensure_exit_monitor_registered("async-stop");
+
+    let mut stream =
+        RuntimeService::get_container_events(&service, Request::new(GetEventsRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+
+    let exit_code_path = dir
+        .path()
+        .join("shims")
+        .join("async-stop")
+        .join("exit_code");
+    fs::create_dir_all(exit_code_path.parent().unwrap()).unwrap();
+    fs::write(&exit_code_path, "17").unwrap();
+
+    let event = timeout(Duration::from_secs(3), async {
+        loop {
+            if let Some(Ok(event)) = stream.next().await {
+                if event.container_id == "async-stop"
+                    && event.container_event_type
+                        == ContainerEventType::ContainerStoppedEvent as i32
+                {
+                    return event;
+                }
+            }
+        }
+    })
+    .await
+    .expect("timed out waiting for async stop event");
+
+    assert_eq!(event.container_id, "async-stop");
+    let container = service
+        .containers
+        .read()
+        .await
+        .get("async-stop")
+        .cloned()
+        .unwrap();
+    assert_eq!(container.state, ContainerState::ContainerExited as i32);
+    let state = RuntimeServiceImpl::read_internal_state::<StoredContainerState>(
+        &container.annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+    )
+    .unwrap();
+    assert_eq!(state.exit_code, Some(17));
+    assert!(state.nri_stop_notified);
+    assert_eq!(fake_nri.calls.lock().await.clone(), vec!["stop_container"]);
 
-    assert_eq!(response.stats.len(), 1);
-    assert_eq!(
-        response.stats[0]
-            .attributes
-            .as_ref()
-            .expect("container stats should include attributes")
-            .id,
-        "container-alpha-123"
-    );
+    let persisted = service
+        .persistence
+        .lock()
+        .await
+        .storage()
+        .get_container("async-stop")
+        .unwrap()
+        .unwrap();
+    let persisted_annotations: HashMap<String, String> =
+        serde_json::from_str(&persisted.annotations).unwrap();
+    let persisted_state = RuntimeServiceImpl::read_internal_state::<StoredContainerState>(
+        &persisted_annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+    )
+    .unwrap();
+    assert!(persisted_state.nri_stop_notified);
 }
 
 #[tokio::test]
-async fn list_containers_returns_empty_when_short_id_filter_is_ambiguous() {
+async fn port_forward_validates_pod_state() {
     let service = test_service();
+    service
+        .pod_sandboxes
+        .write()
+        .await
+        .insert("pod-1".to_string(), test_pod("pod-1", HashMap::new()));
 
-    {
-        let mut containers = service.containers.lock().await;
-        containers.insert(
-            "container-ambiguous-a".to_string(),
-            test_container("container-ambiguous-a", "pod-a", HashMap::new()),
-        );
-        containers.insert(
-            "container-ambiguous-b".to_string(),
-            test_container("container-ambiguous-b", "pod-b", HashMap::new()),
-        );
-    }
-
-    let response = RuntimeService::list_containers(
+    let mut not_ready_pod = test_pod("pod-2", HashMap::new());
+    not_ready_pod.state = PodSandboxState::SandboxNotready as i32;
+    service
+        .pod_sandboxes
+        .write()
+        .await
+        .insert("pod-2".to_string(), not_ready_pod);
+    let not_ready = RuntimeService::port_forward(
         &service,
-        Request::new(ListContainersRequest {
-            filter: Some(crate::proto::runtime::v1::ContainerFilter {
-                id: "container-ambiguous".to_string(),
-                ..Default::default()
-            }),
+        Request::new(PortForwardRequest {
+            pod_sandbox_id: "pod-2".to_string(),
+            port: Vec::new(),
         }),
     )
     .await
-    .unwrap()
-    .into_inner();
-
-    assert!(
-        response.containers.is_empty(),
-        "ambiguous short container ids should behave like cri-o filters and return no entries"
-    );
+    .unwrap_err();
+    assert_eq!(not_ready.code(), tonic::Code::FailedPrecondition);
 }
 
 #[tokio::test]
-async fn container_stats_resolves_short_id_and_list_container_stats_skips_exited() {
-    let (dir, service) = test_service_with_fake_runtime();
-
-    {
-        let mut containers = service.containers.lock().await;
-        containers.insert(
-            "container-running-123".to_string(),
-            test_container("container-running-123", "pod-1", HashMap::new()),
-        );
-        containers.insert(
-            "container-stopped-456".to_string(),
-            test_container("container-stopped-456", "pod-1", HashMap::new()),
-        );
-    }
-    set_fake_runtime_state(&dir, "container-running-123", "running");
-    set_fake_runtime_state(&dir, "container-stopped-456", "stopped");
-
-    let stats = RuntimeService::container_stats(
-        &service,
-        Request::new(ContainerStatsRequest {
-            container_id: "container-running".to_string(),
-        }),
+async fn port_forward_requires_existing_netns_and_returns_stream_url() {
+    let service = test_service();
+    let mut missing_netns_annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut missing_netns_annotations,
+        INTERNAL_POD_STATE_KEY,
+        &StoredPodState {
+            runtime_handler: "runc".to_string(),
+            ..Default::default()
+        },
     )
-    .await
-    .unwrap()
-    .into_inner()
-    .stats
-    .expect("short container id should resolve to stats");
-    assert_eq!(
-        stats
-            .attributes
-            .as_ref()
-            .expect("container stats should include attributes")
-            .id,
-        "container-running-123"
+    .unwrap();
+    service.pod_sandboxes.write().await.insert(
+        "pod-missing-netns".to_string(),
+        test_pod("pod-missing-netns", missing_netns_annotations),
     );
-
-    let list = RuntimeService::list_container_stats(
+    let missing_netns = RuntimeService::port_forward(
         &service,
-        Request::new(ListContainerStatsRequest { filter: None }),
+        Request::new(PortForwardRequest {
+            pod_sandbox_id: "pod-missing-netns".to_string(),
+            port: vec![80],
+        }),
     )
     .await
-    .unwrap()
-    .into_inner();
+    .unwrap_err();
+    assert_eq!(missing_netns.code(), tonic::Code::FailedPrecondition);
 
-    let ids = list
-        .stats
-        .iter()
-        .filter_map(|stat| {
-            stat.attributes
-                .as_ref()
-                .map(|attributes| attributes.id.clone())
-        })
-        .collect::<Vec<_>>();
-    assert!(
-        ids.iter().any(|id| id == "container-running-123"),
-        "running containers should remain visible in list stats"
-    );
-    assert!(
-        !ids.iter().any(|id| id == "container-stopped-456"),
-        "exited containers should be filtered out from list stats by default"
+    let netns_dir = tempdir().unwrap();
+    let netns_path = netns_dir.path().join("netns");
+    fs::write(&netns_path, "placeholder").unwrap();
+    let mut ready_annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut ready_annotations,
+        INTERNAL_POD_STATE_KEY,
+        &StoredPodState {
+            netns_path: Some(netns_path.display().to_string()),
+            runtime_handler: "runc".to_string(),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    service.pod_sandboxes.write().await.insert(
+        "pod-ready".to_string(),
+        test_pod("pod-ready", ready_annotations),
     );
-}
-
-#[tokio::test]
-async fn list_pod_sandbox_stats_applies_id_and_label_filters() {
-    let service = test_service();
-
-    let mut pod_alpha = test_pod("pod-alpha", HashMap::new());
-    pod_alpha
-        .labels
-        .insert("tier".to_string(), "frontend".to_string());
-    let pod_alpha_uid = pod_alpha
-        .metadata
-        .as_ref()
-        .expect("test pod should include metadata")
-        .uid
-        .clone();
-
-    let mut pod_bravo = test_pod("pod-bravo", HashMap::new());
-    pod_bravo
-        .labels
-        .insert("tier".to_string(), "backend".to_string());
-    let pod_bravo_uid = pod_bravo
-        .metadata
-        .as_ref()
-        .expect("test pod should include metadata")
-        .uid
-        .clone();
-
-    {
-        let mut pods = service.pod_sandboxes.lock().await;
-        pods.insert("pod-alpha".to_string(), pod_alpha);
-        pods.insert("pod-bravo".to_string(), pod_bravo);
-    }
-
-    let mut container_alpha_annotations = HashMap::new();
-    container_alpha_annotations.insert("io.kubernetes.pod.uid".to_string(), pod_alpha_uid);
-    let mut container_bravo_annotations = HashMap::new();
-    container_bravo_annotations.insert("io.kubernetes.pod.uid".to_string(), pod_bravo_uid);
-    {
-        let mut containers = service.containers.lock().await;
-        containers.insert(
-            "container-alpha".to_string(),
-            test_container("container-alpha", "pod-alpha", container_alpha_annotations),
-        );
-        containers.insert(
-            "container-bravo".to_string(),
-            test_container("container-bravo", "pod-bravo", container_bravo_annotations),
-        );
-    }
-
-    let mut selector = HashMap::new();
-    selector.insert("tier".to_string(), "frontend".to_string());
-    let response = RuntimeService::list_pod_sandbox_stats(
+    service
+        .set_streaming_server(crate::streaming::StreamingServer::for_test(
+            "http://127.0.0.1:12345",
+        ))
+        .await;
+    let response = RuntimeService::port_forward(
         &service,
-        Request::new(ListPodSandboxStatsRequest {
-            filter: Some(crate::proto::runtime::v1::PodSandboxStatsFilter {
-                id: "pod-al".to_string(),
-                label_selector: selector,
-            }),
+        Request::new(PortForwardRequest {
+            pod_sandbox_id: "pod-ready".to_string(),
+            port: vec![80],
         }),
     )
     .await
     .unwrap()
     .into_inner();
-
-    assert_eq!(response.stats.len(), 1);
-    assert_eq!(
-        response.stats[0]
-            .attributes
-            .as_ref()
-            .expect("pod stats should include attributes")
-            .id,
-        "pod-alpha"
-    );
+    assert!(response.url.contains("/portforward/"));
 }
 
 #[tokio::test]
-async fn pod_sandbox_stats_resolves_short_id_and_hides_internal_annotations() {
+async fn port_forward_refreshes_stale_pause_container_state() {
     let (dir, service) = test_service_with_fake_runtime();
+    set_fake_runtime_state(&dir, "pause-portforward", "stopped");
 
-    let mut pod_annotations = HashMap::new();
-    pod_annotations.insert("visible".to_string(), "true".to_string());
+    let netns_dir = tempdir().unwrap();
+    let netns_path = netns_dir.path().join("netns");
+    fs::write(&netns_path, "placeholder").unwrap();
+    let mut annotations = HashMap::new();
     RuntimeServiceImpl::insert_internal_state(
-        &mut pod_annotations,
+        &mut annotations,
         INTERNAL_POD_STATE_KEY,
         &StoredPodState {
-            pause_container_id: Some("pause-pod-short-123".to_string()),
+            netns_path: Some(netns_path.display().to_string()),
+            runtime_handler: "runc".to_string(),
+            pause_container_id: Some("pause-portforward".to_string()),
             ..Default::default()
         },
     )
     .unwrap();
-
-    service.pod_sandboxes.lock().await.insert(
-        "pod-short-123".to_string(),
-        test_pod("pod-short-123", pod_annotations),
-    );
-    service.containers.lock().await.insert(
-        "container-pod-short-123".to_string(),
-        test_container("container-pod-short-123", "pod-short-123", HashMap::new()),
+    service.pod_sandboxes.write().await.insert(
+        "pod-stale-ready".to_string(),
+        crate::proto::runtime::v1::PodSandbox {
+            state: PodSandboxState::SandboxReady as i32,
+            ..test_pod("pod-stale-ready", annotations)
+        },
     );
-    set_fake_runtime_state(&dir, "container-pod-short-123", "running");
-    set_fake_runtime_state(&dir, "pause-pod-short-123", "running");
+    service
+        .set_streaming_server(crate::streaming::StreamingServer::for_test(
+            "http://127.0.0.1:12345",
+        ))
+        .await;
 
-    let response = RuntimeService::pod_sandbox_stats(
+    let err = RuntimeService::port_forward(
         &service,
-        Request::new(PodSandboxStatsRequest {
-            pod_sandbox_id: "pod-short".to_string(),
+        Request::new(PortForwardRequest {
+            pod_sandbox_id: "pod-stale-ready".to_string(),
+            port: vec![80],
         }),
     )
     .await
-    .unwrap()
-    .into_inner();
-    let stats = response
-        .stats
-        .expect("short pod id should resolve to pod sandbox stats");
-    let attributes = stats
-        .attributes
-        .expect("pod stats should include attributes");
-    assert_eq!(attributes.id, "pod-short-123");
-    assert_eq!(
-        attributes.annotations.get("visible").map(String::as_str),
-        Some("true")
-    );
-    assert!(
-        !attributes.annotations.contains_key(INTERNAL_POD_STATE_KEY),
-        "internal pod annotations should not leak through statsp responses"
-    );
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+    assert!(err.message().contains("not ready"));
 }
 
 #[tokio::test]
-async fn list_pod_sandbox_stats_returns_empty_when_short_id_filter_is_ambiguous() {
-    let service = test_service();
-
-    {
-        let mut pods = service.pod_sandboxes.lock().await;
-        pods.insert(
-            "pod-ambiguous-a".to_string(),
-            test_pod("pod-ambiguous-a", HashMap::new()),
-        );
-        pods.insert(
-            "pod-ambiguous-b".to_string(),
-            test_pod("pod-ambiguous-b", HashMap::new()),
-        );
-    }
+async fn stop_and_remove_existing_container_support_repeat_calls() {
+    let (dir, service) = test_service_with_fake_runtime();
+    set_fake_runtime_state(&dir, "container-1", "running");
+    service.containers.write().await.insert(
+        "container-1".to_string(),
+        test_container("container-1", "pod-1", HashMap::new()),
+    );
 
-    let response = RuntimeService::list_pod_sandbox_stats(
+    RuntimeService::stop_container(
         &service,
-        Request::new(ListPodSandboxStatsRequest {
-            filter: Some(crate::proto::runtime::v1::PodSandboxStatsFilter {
-                id: "pod-ambiguous".to_string(),
-                ..Default::default()
-            }),
+        Request::new(StopContainerRequest {
+            container_id: "container-1".to_string(),
+            timeout: 1,
         }),
     )
     .await
-    .unwrap()
-    .into_inner();
+    .unwrap();
+    RuntimeService::stop_container(
+        &service,
+        Request::new(StopContainerRequest {
+            container_id: "container-1".to_string(),
+            timeout: 1,
+        }),
+    )
+    .await
+    .unwrap();
 
-    assert!(
-        response.stats.is_empty(),
-        "ambiguous short pod ids should return no stats entries"
+    set_fake_runtime_state(&dir, "container-2", "running");
+    service.containers.write().await.insert(
+        "container-2".to_string(),
+        test_container("container-2", "pod-1", HashMap::new()),
     );
+    RuntimeService::remove_container(
+        &service,
+        Request::new(RemoveContainerRequest {
+            container_id: "container-2".to_string(),
+        }),
+    )
+    .await
+    .unwrap();
+    RuntimeService::remove_container(
+        &service,
+        Request::new(RemoveContainerRequest {
+            container_id: "container-2".to_string(),
+        }),
+    )
+    .await
+    .unwrap();
 }
 
 #[tokio::test]
-async fn list_metric_descriptors_returns_non_empty_supported_names() {
-    let service = test_service();
-    let response = RuntimeService::list_metric_descriptors(
+async fn stop_and_remove_existing_pod_support_repeat_calls() {
+    let (dir, service) = test_service_with_fake_runtime();
+
+    let pod = test_pod("pod-1", HashMap::new());
+    service
+        .pod_sandboxes
+        .write()
+        .await
+        .insert("pod-1".to_string(), pod);
+    RuntimeService::stop_pod_sandbox(
         &service,
-        Request::new(ListMetricDescriptorsRequest {}),
+        Request::new(StopPodSandboxRequest {
+            pod_sandbox_id: "pod-1".to_string(),
+        }),
     )
     .await
-    .unwrap()
-    .into_inner();
+    .unwrap();
+    RuntimeService::stop_pod_sandbox(
+        &service,
+        Request::new(StopPodSandboxRequest {
+            pod_sandbox_id: "pod-1".to_string(),
+        }),
+    )
+    .await
+    .unwrap();
 
-    assert!(
-        !response.descriptors.is_empty(),
-        "metric descriptors should not be empty"
+    set_fake_runtime_state(&dir, "container-under-pod", "running");
+    service.containers.write().await.insert(
+        "container-under-pod".to_string(),
+        test_container("container-under-pod", "pod-2", HashMap::new()),
     );
-    let names: HashSet<String> = response
-        .descriptors
-        .into_iter()
-        .map(|descriptor| descriptor.name)
-        .collect();
-    assert!(names.contains("container_cpu_usage_seconds_total"));
-    assert!(names.contains("container_memory_working_set_bytes"));
-    assert!(names.contains("container_memory_usage_bytes"));
-    assert!(names.contains("container_spec_memory_limit_bytes"));
+    service
+        .pod_sandboxes
+        .write()
+        .await
+        .insert("pod-2".to_string(), test_pod("pod-2", HashMap::new()));
+    RuntimeService::remove_pod_sandbox(
+        &service,
+        Request::new(RemovePodSandboxRequest {
+            pod_sandbox_id: "pod-2".to_string(),
+        }),
+    )
+    .await
+    .unwrap();
+    RuntimeService::remove_pod_sandbox(
+        &service,
+        Request::new(RemovePodSandboxRequest {
+            pod_sandbox_id: "pod-2".to_string(),
+        }),
+    )
+    .await
+    .unwrap();
 }
 
 #[tokio::test]
-async fn list_pod_sandbox_metrics_returns_pod_and_container_entries() {
-    let service = test_service();
+async fn recover_state_reconciles_running_container_to_exited() {
+    let (dir, service) = test_service_with_fake_runtime();
+    set_fake_runtime_state(&dir, "recover-container", "stopped");
+
+    let mut annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+        &StoredContainerState {
+            metadata_name: Some("recover".to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
 
-    let pod = test_pod("pod-metrics", HashMap::new());
-    let pod_uid = pod
-        .metadata
-        .as_ref()
-        .expect("test pod should include metadata")
-        .uid
-        .clone();
     service
-        .pod_sandboxes
+        .persistence
         .lock()
         .await
-        .insert("pod-metrics".to_string(), pod);
+        .save_container(
+            "recover-container",
+            "pod-1",
+            crate::runtime::ContainerStatus::Running,
+            "busybox:latest",
+            &Vec::new(),
+            &HashMap::new(),
+            &annotations,
+        )
+        .unwrap();
 
-    let mut container_annotations = HashMap::new();
-    container_annotations.insert("io.kubernetes.pod.uid".to_string(), pod_uid);
-    service.containers.lock().await.insert(
-        "container-metrics".to_string(),
-        test_container("container-metrics", "pod-metrics", container_annotations),
-    );
+    service.recover_state().await.unwrap();
 
-    let response = RuntimeService::list_pod_sandbox_metrics(
-        &service,
-        Request::new(ListPodSandboxMetricsRequest {}),
+    let container = service
+        .containers
+        .read()
+        .await
+        .get("recover-container")
+        .cloned()
+        .unwrap();
+    assert_eq!(container.state, ContainerState::ContainerExited as i32);
+    let state = RuntimeServiceImpl::read_internal_state::<StoredContainerState>(
+        &container.annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
     )
-    .await
-    .unwrap()
-    .into_inner();
-
-    assert_eq!(response.pod_metrics.len(), 1);
-    let pod_metrics = &response.pod_metrics[0];
-    assert_eq!(pod_metrics.pod_sandbox_id, "pod-metrics");
-    assert!(
-        !pod_metrics.metrics.is_empty(),
-        "pod-level metrics should not be empty"
-    );
-    assert_eq!(pod_metrics.container_metrics.len(), 1);
-    assert_eq!(
-        pod_metrics.container_metrics[0].container_id,
-        "container-metrics"
-    );
-    assert!(
-        !pod_metrics.container_metrics[0].metrics.is_empty(),
-        "container-level metrics should not be empty"
-    );
+    .unwrap();
+    assert_eq!(state.exit_code, Some(0));
+    assert!(state.finished_at.is_some());
+
+    let persisted = service
+        .persistence
+        .lock()
+        .await
+        .storage()
+        .get_container("recover-container")
+        .unwrap()
+        .unwrap();
+    assert_eq!(persisted.state, "stopped");
 }
 
 #[tokio::test]
-async fn get_container_events_streams_broadcast_events() {
-    let service = test_service();
-    let mut stream =
-        RuntimeService::get_container_events(&service, Request::new(GetEventsRequest {}))
-            .await
-            .unwrap()
-            .into_inner();
+async fn containers_survive_service_restart_and_reconcile_with_runtime() {
+    let (dir, service) = test_service_with_fake_runtime();
+    service
+        .pod_sandboxes
+        .write()
+        .await
+        .insert("pod-1".to_string(), test_pod("pod-1", HashMap::new()));
 
-    service.publish_event(ContainerEventResponse {
-        container_id: "container-1".to_string(),
-        container_event_type: ContainerEventType::ContainerCreatedEvent as i32,
-        created_at: 1,
-        pod_sandbox_status: None,
-        containers_statuses: Vec::new(),
-    });
+    let mut container_ids = Vec::new();
+    for name in ["survivor", "died-while-down"] {
+        let container_id = RuntimeService::create_container(
+            &service,
+            Request::new(CreateContainerRequest {
+                pod_sandbox_id: "pod-1".to_string(),
+                config: Some(crate::proto::runtime::v1::ContainerConfig {
+                    metadata: Some(crate::proto::runtime::v1::ContainerMetadata {
+                        name: name.to_string(),
+                        attempt: 2,
+                    }),
+                    ..Default::default()
+                }),
+                sandbox_config: None,
+            }),
+        )
+        .await
+        .unwrap()
+        .into_inner()
+        .container_id;
+        RuntimeService::start_container(
+            &service,
+            Request::new(StartContainerRequest {
+                container_id: container_id.clone(),
+            }),
+        )
+        .await
+        .unwrap();
+        container_ids.push(container_id);
+    }
 
-    let event = stream.next().await.unwrap().unwrap();
-    assert_eq!(event.container_id, "container-1");
-    assert_eq!(
-        event.container_event_type,
-        ContainerEventType::ContainerCreatedEvent as i32
-    );
-}
+    // 模拟 crius 重启：丢弃内存中的服务，期间第二个容器退出，shim 记录退出码
+    let config = service.config.clone();
+    drop(service);
+    set_fake_runtime_state(&dir, &container_ids[1], "stopped");
+    fs::write(
+        dir.path()
+            .join("shims")
+            .join(&container_ids[1])
+            .join("exit_code"),
+        "3",
+    )
+    .unwrap();
 
-#[tokio::test]
-async fn get_container_events_broadcasts_to_multiple_subscribers() {
-    let service = test_service();
-    let mut stream_a =
-        RuntimeService::get_container_events(&service, Request::new(GetEventsRequest {}))
-            .await
-            .unwrap()
-            .into_inner();
-    let mut stream_b =
-        RuntimeService::get_container_events(&service, Request::new(GetEventsRequest {}))
-            .await
-            .unwrap()
-            .into_inner();
+    let restarted = RuntimeServiceImpl::new_with_shim_work_dir(
+        config,
+        NriConfig {
+            blockio_config_path: write_blockio_config(&dir).display().to_string(),
+            ..Default::default()
+        },
+        dir.path().join("shims"),
+    );
+    assert!(restarted.containers.read().await.is_empty());
+    restarted.recover_state().await.unwrap();
 
-    service.publish_event(ContainerEventResponse {
-        container_id: "shared".to_string(),
-        container_event_type: ContainerEventType::ContainerStartedEvent as i32,
-        created_at: 2,
-        pod_sandbox_status: None,
-        containers_statuses: Vec::new(),
-    });
+    let containers = restarted.containers.read().await.clone();
+    let survivor = &containers[&container_ids[0]];
+    assert_eq!(survivor.pod_sandbox_id, "pod-1");
+    assert_eq!(survivor.state, ContainerState::ContainerRunning as i32);
+    let metadata = survivor.metadata.as_ref().unwrap();
+    assert_eq!(metadata.name, "survivor");
+    assert_eq!(metadata.attempt, 2);
 
-    let event_a = stream_a.next().await.unwrap().unwrap();
-    let event_b = stream_b.next().await.unwrap().unwrap();
-    assert_eq!(event_a.container_id, "shared");
-    assert_eq!(event_b.container_id, "shared");
-    assert_eq!(event_a.container_event_type, event_b.container_event_type);
+    let died = &containers[&container_ids[1]];
+    assert_eq!(died.state, ContainerState::ContainerExited as i32);
+    let state = RuntimeServiceImpl::read_internal_state::<StoredContainerState>(
+        &died.annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+    )
+    .unwrap();
+    assert!(state.started_at.is_some());
+    assert!(state.finished_at.is_some());
+    assert_eq!(state.exit_code, Some(3));
 }
 
 #[tokio::test]
-async fn get_container_events_preserves_publish_order() {
-    let service = test_service();
-    let mut stream =
-        RuntimeService::get_container_events(&service, Request::new(GetEventsRequest {}))
-            .await
-            .unwrap()
-            .into_inner();
-
-    for (id, event_type) in [
-        ("created", ContainerEventType::ContainerCreatedEvent),
-        ("started", ContainerEventType::ContainerStartedEvent),
-        ("stopped", ContainerEventType::ContainerStoppedEvent),
-        ("deleted", ContainerEventType::ContainerDeletedEvent),
+async fn recovered_pod_network_helpers_are_killed_when_stale_or_removed() {
+    let (dir, service) = test_service_with_fake_runtime();
+    let netns_path = dir.path().join("pause-netns");
+    fs::write(&netns_path, "netns").unwrap();
+    let mut helpers = Vec::new();
+    for (pod_id, pause_id, pause_state) in [
+        ("pod-stale", "pause-stale", "stopped"),
+        ("pod-live", "pause-live", "running"),
     ] {
-        service.publish_event(ContainerEventResponse {
-            container_id: id.to_string(),
-            container_event_type: event_type as i32,
-            created_at: RuntimeServiceImpl::now_nanos(),
-            pod_sandbox_status: None,
-            containers_statuses: Vec::new(),
-        });
+        set_fake_runtime_state(&dir, pause_id, pause_state);
+        // 模拟上一个 crius 进程启动的 slirp4netns
+        let child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let helper = crate::rootless::NetworkHelper::of(&child).unwrap();
+        let mut annotations = HashMap::new();
+        RuntimeServiceImpl::insert_internal_state(
+            &mut annotations,
+            INTERNAL_POD_STATE_KEY,
+            &StoredPodState {
+                runtime_handler: "runc".to_string(),
+                netns_path: Some(netns_path.display().to_string()),
+                pause_container_id: Some(pause_id.to_string()),
+                network_helper: Some(helper),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        service
+            .persistence
+            .lock()
+            .await
+            .storage_mut()
+            .save_pod_sandbox(&PodSandboxRecord {
+                id: pod_id.to_string(),
+                state: "ready".to_string(),
+                name: pod_id.to_string(),
+                namespace: "default".to_string(),
+                uid: format!("uid-{}", pod_id),
+                created_at: RuntimeServiceImpl::now_nanos(),
+                netns_path: netns_path.display().to_string(),
+                labels: "{}".to_string(),
+                annotations: serde_json::to_string(&annotations).unwrap(),
+                pause_container_id: Some(pause_id.to_string()),
+                ip: None,
+            })
+            .unwrap();
+        helpers.push((child, helper));
     }
 
-    let mut received = Vec::new();
-    for _ in 0..4 {
-        let event = stream.next().await.unwrap().unwrap();
-        received.push((event.container_id, event.container_event_type));
-    }
+    service.recover_state().await.unwrap();
 
+    // pause 已停止的 Pod 在恢复时回收辅助进程，仍在运行的 Pod 保留
+    let (mut stale_child, _) = helpers.remove(0);
+    let (mut live_child, live) = helpers.remove(0);
     assert_eq!(
-        received,
-        vec![
-            (
-                "created".to_string(),
-                ContainerEventType::ContainerCreatedEvent as i32
-            ),
-            (
-                "started".to_string(),
-                ContainerEventType::ContainerStartedEvent as i32
-            ),
-            (
-                "stopped".to_string(),
-                ContainerEventType::ContainerStoppedEvent as i32
-            ),
-            (
-                "deleted".to_string(),
-                ContainerEventType::ContainerDeletedEvent as i32
-            ),
-        ]
+        std::os::unix::process::ExitStatusExt::signal(&stale_child.wait().unwrap()),
+        Some(9)
+    );
+    assert!(live.is_running());
+    assert!(live_child.try_wait().unwrap().is_none());
+
+    RuntimeService::remove_pod_sandbox(
+        &service,
+        Request::new(RemovePodSandboxRequest {
+            pod_sandbox_id: "pod-live".to_string(),
+        }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        std::os::unix::process::ExitStatusExt::signal(&live_child.wait().unwrap()),
+        Some(9)
     );
 }
 
 #[tokio::test]
-async fn pod_events_use_pause_container_id_when_available_and_preserve_order() {
-    let service = test_service();
+async fn recover_state_marks_ready_pod_notready_when_pause_is_stopped() {
+    let (dir, service) = test_service_with_fake_runtime();
+    set_fake_runtime_state(&dir, "pause-1", "stopped");
+    let netns_path = dir.path().join("pause-netns");
+    fs::write(&netns_path, "netns").unwrap();
+
     let mut annotations = HashMap::new();
     RuntimeServiceImpl::insert_internal_state(
         &mut annotations,
         INTERNAL_POD_STATE_KEY,
         &StoredPodState {
-            pause_container_id: Some("pause-event-1".to_string()),
+            runtime_handler: "runc".to_string(),
+            netns_path: Some(netns_path.display().to_string()),
+            pause_container_id: Some("pause-1".to_string()),
             ..Default::default()
         },
     )
     .unwrap();
-    let pod = test_pod("pod-event-1", annotations);
-
-    let mut stream =
-        RuntimeService::get_container_events(&service, Request::new(GetEventsRequest {}))
-            .await
-            .unwrap()
-            .into_inner();
 
     service
-        .emit_pod_event(ContainerEventType::ContainerCreatedEvent, &pod, Vec::new())
-        .await;
-    service
-        .emit_pod_event(ContainerEventType::ContainerStartedEvent, &pod, Vec::new())
-        .await;
+        .persistence
+        .lock()
+        .await
+        .storage_mut()
+        .save_pod_sandbox(&PodSandboxRecord {
+            id: "pod-recover".to_string(),
+            state: "ready".to_string(),
+            name: "pod-recover".to_string(),
+            namespace: "default".to_string(),
+            uid: "uid-1".to_string(),
+            created_at: RuntimeServiceImpl::now_nanos(),
+            netns_path: netns_path.display().to_string(),
+            labels: "{}".to_string(),
+            annotations: serde_json::to_string(&annotations).unwrap(),
+            pause_container_id: Some("pause-1".to_string()),
+            ip: None,
+        })
+        .unwrap();
 
-    let created = stream.next().await.unwrap().unwrap();
-    let started = stream.next().await.unwrap().unwrap();
-    assert_eq!(created.container_id, "pause-event-1");
-    assert_eq!(started.container_id, "pause-event-1");
-    assert_eq!(
-        created.container_event_type,
-        ContainerEventType::ContainerCreatedEvent as i32
-    );
-    assert_eq!(
-        started.container_event_type,
-        ContainerEventType::ContainerStartedEvent as i32
-    );
+    service.recover_state().await.unwrap();
+
+    let pod = service
+        .pod_sandboxes
+        .read()
+        .await
+        .get("pod-recover")
+        .cloned()
+        .unwrap();
+    assert_eq!(pod.state, PodSandboxState::SandboxNotready as i32);
+    // 未记录 attempt 时与 ListPodSandbox/ListContainers 的默认值一致
     assert_eq!(
-        started
-            .pod_sandbox_status
-            .expect("pod status should be included")
-            .id,
-        "pod-event-1"
+        pod.metadata.as_ref().unwrap().attempt,
+        DEFAULT_METADATA_ATTEMPT
     );
+    let persisted = service
+        .persistence
+        .lock()
+        .await
+        .storage()
+        .get_pod_sandbox("pod-recover")
+        .unwrap()
+        .unwrap();
+    assert_eq!(persisted.state, "notready");
 }
 
 #[tokio::test]
-async fn publish_event_without_subscribers_does_not_panic() {
-    let service = test_service();
-    service.publish_event(ContainerEventResponse {
-        container_id: "orphan".to_string(),
-        container_event_type: ContainerEventType::ContainerCreatedEvent as i32,
-        created_at: 1,
-        pod_sandbox_status: None,
-        containers_statuses: Vec::new(),
-    });
-}
+async fn recover_state_does_not_replay_historical_events() {
+    let (dir, service) = test_service_with_fake_runtime();
+    set_fake_runtime_state(&dir, "recover-container", "stopped");
+
+    service
+        .persistence
+        .lock()
+        .await
+        .storage_mut()
+        .save_container(&ContainerRecord {
+            id: "recover-container".to_string(),
+            pod_id: "pod-1".to_string(),
+            state: "running".to_string(),
+            image: "busybox:latest".to_string(),
+            command: String::new(),
+            created_at: RuntimeServiceImpl::now_nanos(),
+            labels: "{}".to_string(),
+            annotations: "{}".to_string(),
+            exit_code: None,
+            exit_time: None,
+        })
+        .unwrap();
 
-#[tokio::test]
-async fn get_container_events_reports_lagged_consumers() {
-    let service = test_service();
     let mut stream =
         RuntimeService::get_container_events(&service, Request::new(GetEventsRequest {}))
             .await
             .unwrap()
             .into_inner();
 
-    for idx in 0..600 {
-        service.publish_event(ContainerEventResponse {
-            container_id: format!("container-{}", idx),
-            container_event_type: ContainerEventType::ContainerCreatedEvent as i32,
-            created_at: idx,
-            pod_sandbox_status: None,
-            containers_statuses: Vec::new(),
-        });
-    }
+    service.recover_state().await.unwrap();
 
-    let mut saw_lagged = false;
-    for _ in 0..260 {
-        match timeout(Duration::from_millis(200), stream.next()).await {
-            Ok(Some(Err(status))) if status.code() == tonic::Code::ResourceExhausted => {
-                saw_lagged = true;
-                break;
-            }
-            Ok(Some(_)) => continue,
-            _ => break,
-        }
+    assert!(
+        timeout(Duration::from_millis(100), stream.next())
+            .await
+            .is_err(),
+        "recover_state should not replay historical events"
+    );
+}
+
+#[tokio::test]
+async fn recover_state_converts_legacy_second_timestamps_to_nanos() {
+    let (dir, service) = test_service_with_fake_runtime();
+    set_fake_runtime_state(&dir, "legacy-container", "stopped");
+    set_fake_runtime_state(&dir, "legacy-pause", "stopped");
+    // 旧版本数据库中 created_at/exit_time 以秒为单位
+    let legacy_seconds = 1_700_000_000_i64;
+    let legacy_nanos = legacy_seconds * 1_000_000_000;
+
+    let mut container_annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut container_annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+        &StoredContainerState {
+            metadata_name: Some("legacy-container".to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let mut pod_annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut pod_annotations,
+        INTERNAL_POD_STATE_KEY,
+        &StoredPodState {
+            runtime_handler: "runc".to_string(),
+            pause_container_id: Some("legacy-pause".to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    {
+        let mut persistence = service.persistence.lock().await;
+        persistence
+            .storage_mut()
+            .save_container(&ContainerRecord {
+                id: "legacy-container".to_string(),
+                pod_id: "legacy-pod".to_string(),
+                state: "exited".to_string(),
+                image: "busybox:latest".to_string(),
+                command: String::new(),
+                created_at: legacy_seconds,
+                labels: "{}".to_string(),
+                annotations: serde_json::to_string(&container_annotations).unwrap(),
+                exit_code: Some(0),
+                exit_time: Some(legacy_seconds + 5),
+            })
+            .unwrap();
+        persistence
+            .storage_mut()
+            .save_pod_sandbox(&PodSandboxRecord {
+                id: "legacy-pod".to_string(),
+                state: "notready".to_string(),
+                name: "legacy-pod".to_string(),
+                namespace: "default".to_string(),
+                uid: "uid-legacy".to_string(),
+                created_at: legacy_seconds,
+                netns_path: String::new(),
+                labels: "{}".to_string(),
+                annotations: serde_json::to_string(&pod_annotations).unwrap(),
+                pause_container_id: Some("legacy-pause".to_string()),
+                ip: None,
+            })
+            .unwrap();
     }
 
-    assert!(saw_lagged, "expected lagged consumer error");
+    service.recover_state().await.unwrap();
+
+    let container = service
+        .containers
+        .read()
+        .await
+        .get("legacy-container")
+        .cloned()
+        .unwrap();
+    assert_eq!(container.created_at, legacy_nanos);
+    let state = RuntimeServiceImpl::read_internal_state::<StoredContainerState>(
+        &container.annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+    )
+    .unwrap();
+    assert_eq!(state.finished_at, Some(legacy_nanos + 5_000_000_000));
+
+    let pod = service
+        .pod_sandboxes
+        .read()
+        .await
+        .get("legacy-pod")
+        .cloned()
+        .unwrap();
+    assert_eq!(pod.created_at, legacy_nanos);
+    let internal_pod = service
+        .pod_manager
+        .lock()
+        .await
+        .get_pod_sandbox_cloned("legacy-pod")
+        .unwrap();
+    assert_eq!(internal_pod.created_at, legacy_nanos);
 }
 
 #[tokio::test]
-async fn exit_monitor_publishes_async_stop_events() {
+async fn recover_state_re_registers_exit_monitor_for_running_container() {
     let fake_nri = Arc::new(FakeNri::default());
     let (dir, service) = test_service_with_fake_runtime_and_nri(fake_nri.clone());
+    set_fake_runtime_state(&dir, "recover-running", "running");
+
     let mut annotations = HashMap::new();
     RuntimeServiceImpl::insert_internal_state(
         &mut annotations,
         INTERNAL_CONTAINER_STATE_KEY,
-        &StoredContainerState::default(),
+        &StoredContainerState {
+            metadata_name: Some("recover-running".to_string()),
+            ..Default::default()
+        },
     )
     .unwrap();
-    service.containers.lock().await.insert(
-        "async-stop".to_string(),
-        Container {
-            state: ContainerState::ContainerRunning as i32,
-            ..test_container("async-stop", "pod-1", annotations)
-        },
-    );
     service
         .persistence
         .lock()
         .await
-        .save_container(
-            "async-stop",
-            "pod-1",
-            crate::runtime::ContainerStatus::Running,
-            "busybox:latest",
-            &Vec::new(),
-            &HashMap::new(),
-            &service
-                .containers
-                .lock()
-                .await
-                .get("async-stop")
-                .unwrap()
-                .annotations,
-        )
+        .storage_mut()
+        .save_container(&ContainerRecord {
+            id: "recover-running".to_string(),
+            pod_id: "pod-1".to_string(),
+            state: "running".to_string(),
+            image: "busybox:latest".to_string(),
+            command: String::new(),
+            created_at: RuntimeServiceImpl::now_nanos(),
+            labels: "{}".to_string(),
+            annotations: serde_json::to_string(&annotations).unwrap(),
+            exit_code: None,
+            exit_time: None,
+        })
         .unwrap();
-    service.ensure_exit_monitor_registered("async-stop");
 
     let mut stream =
         RuntimeService::get_container_events(&service, Request::new(GetEventsRequest {}))
@@ -6351,18 +11277,20 @@ async fn exit_monitor_publishes_async_stop_events() {
             .unwrap()
             .into_inner();
 
+    service.recover_state().await.unwrap();
+
     let exit_code_path = dir
         .path()
         .join("shims")
-        .join("async-stop")
+        .join("recover-running")
         .join("exit_code");
     fs::create_dir_all(exit_code_path.parent().unwrap()).unwrap();
-    fs::write(&exit_code_path, "17").unwrap();
+    fs::write(&exit_code_path, "23").unwrap();
 
     let event = timeout(Duration::from_secs(3), async {
         loop {
             if let Some(Ok(event)) = stream.next().await {
-                if event.container_id == "async-stop"
+                if event.container_id == "recover-running"
                     && event.container_event_type
                         == ContainerEventType::ContainerStoppedEvent as i32
                 {
@@ -6372,363 +11300,271 @@ async fn exit_monitor_publishes_async_stop_events() {
         }
     })
     .await
-    .expect("timed out waiting for async stop event");
-
-    assert_eq!(event.container_id, "async-stop");
-    let container = service
-        .containers
-        .lock()
-        .await
-        .get("async-stop")
-        .cloned()
-        .unwrap();
-    assert_eq!(container.state, ContainerState::ContainerExited as i32);
-    let state = RuntimeServiceImpl::read_internal_state::<StoredContainerState>(
-        &container.annotations,
-        INTERNAL_CONTAINER_STATE_KEY,
-    )
-    .unwrap();
-    assert_eq!(state.exit_code, Some(17));
-    assert!(state.nri_stop_notified);
-    assert_eq!(fake_nri.calls.lock().await.clone(), vec!["stop_container"]);
-
-    let persisted = service
-        .persistence
-        .lock()
-        .await
-        .storage()
-        .get_container("async-stop")
-        .unwrap()
-        .unwrap();
-    let persisted_annotations: HashMap<String, String> =
-        serde_json::from_str(&persisted.annotations).unwrap();
-    let persisted_state = RuntimeServiceImpl::read_internal_state::<StoredContainerState>(
-        &persisted_annotations,
-        INTERNAL_CONTAINER_STATE_KEY,
-    )
-    .unwrap();
-    assert!(persisted_state.nri_stop_notified);
-}
-
-#[tokio::test]
-async fn port_forward_validates_pod_state() {
-    let service = test_service();
-    service
-        .pod_sandboxes
-        .lock()
-        .await
-        .insert("pod-1".to_string(), test_pod("pod-1", HashMap::new()));
-
-    let mut not_ready_pod = test_pod("pod-2", HashMap::new());
-    not_ready_pod.state = PodSandboxState::SandboxNotready as i32;
-    service
-        .pod_sandboxes
-        .lock()
-        .await
-        .insert("pod-2".to_string(), not_ready_pod);
-    let not_ready = RuntimeService::port_forward(
-        &service,
-        Request::new(PortForwardRequest {
-            pod_sandbox_id: "pod-2".to_string(),
-            port: Vec::new(),
-        }),
-    )
-    .await
-    .unwrap_err();
-    assert_eq!(not_ready.code(), tonic::Code::FailedPrecondition);
-}
-
-#[tokio::test]
-async fn port_forward_requires_existing_netns_and_returns_stream_url() {
-    let service = test_service();
-    let mut missing_netns_annotations = HashMap::new();
-    RuntimeServiceImpl::insert_internal_state(
-        &mut missing_netns_annotations,
-        INTERNAL_POD_STATE_KEY,
-        &StoredPodState {
-            runtime_handler: "runc".to_string(),
-            ..Default::default()
-        },
-    )
-    .unwrap();
-    service.pod_sandboxes.lock().await.insert(
-        "pod-missing-netns".to_string(),
-        test_pod("pod-missing-netns", missing_netns_annotations),
-    );
-    let missing_netns = RuntimeService::port_forward(
-        &service,
-        Request::new(PortForwardRequest {
-            pod_sandbox_id: "pod-missing-netns".to_string(),
-            port: vec![80],
-        }),
-    )
-    .await
-    .unwrap_err();
-    assert_eq!(missing_netns.code(), tonic::Code::FailedPrecondition);
+    .expect("timed out waiting for recovered container stop event");
 
-    let netns_dir = tempdir().unwrap();
-    let netns_path = netns_dir.path().join("netns");
-    fs::write(&netns_path, "placeholder").unwrap();
-    let mut ready_annotations = HashMap::new();
-    RuntimeServiceImpl::insert_internal_state(
-        &mut ready_annotations,
-        INTERNAL_POD_STATE_KEY,
-        &StoredPodState {
-            netns_path: Some(netns_path.display().to_string()),
-            runtime_handler: "runc".to_string(),
-            ..Default::default()
-        },
-    )
-    .unwrap();
-    service.pod_sandboxes.lock().await.insert(
-        "pod-ready".to_string(),
-        test_pod("pod-ready", ready_annotations),
-    );
-    service
-        .set_streaming_server(crate::streaming::StreamingServer::for_test(
-            "http://127.0.0.1:12345",
-        ))
-        .await;
-    let response = RuntimeService::port_forward(
-        &service,
-        Request::new(PortForwardRequest {
-            pod_sandbox_id: "pod-ready".to_string(),
-            port: vec![80],
-        }),
+    assert_eq!(event.container_id, "recover-running");
+    let container = service
+        .containers
+        .read()
+        .await
+        .get("recover-running")
+        .cloned()
+        .unwrap();
+    assert_eq!(container.state, ContainerState::ContainerExited as i32);
+    let state = RuntimeServiceImpl::read_internal_state::<StoredContainerState>(
+        &container.annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
     )
-    .await
-    .unwrap()
-    .into_inner();
-    assert!(response.url.contains("/portforward/"));
+    .unwrap();
+    assert_eq!(state.exit_code, Some(23));
+    assert!(state.nri_stop_notified);
+    assert_eq!(fake_nri.calls.lock().await.clone(), vec!["stop_container"]);
 }
 
 #[tokio::test]
-async fn port_forward_refreshes_stale_pause_container_state() {
+async fn recover_state_supports_inspect_and_list_after_restart() {
     let (dir, service) = test_service_with_fake_runtime();
-    set_fake_runtime_state(&dir, "pause-portforward", "stopped");
+    set_fake_runtime_state(&dir, "recover-container", "running");
+    set_fake_runtime_state(&dir, "pause-recover", "running");
+    let netns_path = dir.path().join("recover.netns");
+    fs::write(&netns_path, "netns").unwrap();
 
-    let netns_dir = tempdir().unwrap();
-    let netns_path = netns_dir.path().join("netns");
-    fs::write(&netns_path, "placeholder").unwrap();
-    let mut annotations = HashMap::new();
+    let mut pod_annotations = HashMap::new();
     RuntimeServiceImpl::insert_internal_state(
-        &mut annotations,
+        &mut pod_annotations,
         INTERNAL_POD_STATE_KEY,
         &StoredPodState {
-            netns_path: Some(netns_path.display().to_string()),
             runtime_handler: "runc".to_string(),
-            pause_container_id: Some("pause-portforward".to_string()),
+            netns_path: Some(netns_path.display().to_string()),
+            pause_container_id: Some("pause-recover".to_string()),
+            ip: Some("10.88.0.20".to_string()),
             ..Default::default()
         },
     )
     .unwrap();
-    service.pod_sandboxes.lock().await.insert(
-        "pod-stale-ready".to_string(),
-        crate::proto::runtime::v1::PodSandbox {
-            state: PodSandboxState::SandboxReady as i32,
-            ..test_pod("pod-stale-ready", annotations)
-        },
-    );
     service
-        .set_streaming_server(crate::streaming::StreamingServer::for_test(
-            "http://127.0.0.1:12345",
-        ))
-        .await;
-
-    let err = RuntimeService::port_forward(
-        &service,
-        Request::new(PortForwardRequest {
-            pod_sandbox_id: "pod-stale-ready".to_string(),
-            port: vec![80],
-        }),
-    )
-    .await
-    .unwrap_err();
-    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
-    assert!(err.message().contains("not ready"));
-}
-
-#[tokio::test]
-async fn stop_and_remove_existing_container_support_repeat_calls() {
-    let (dir, service) = test_service_with_fake_runtime();
-    set_fake_runtime_state(&dir, "container-1", "running");
-    service.containers.lock().await.insert(
-        "container-1".to_string(),
-        test_container("container-1", "pod-1", HashMap::new()),
-    );
-
-    RuntimeService::stop_container(
-        &service,
-        Request::new(StopContainerRequest {
-            container_id: "container-1".to_string(),
-            timeout: 1,
-        }),
-    )
-    .await
-    .unwrap();
-    RuntimeService::stop_container(
-        &service,
-        Request::new(StopContainerRequest {
-            container_id: "container-1".to_string(),
-            timeout: 1,
-        }),
-    )
-    .await
-    .unwrap();
+        .persistence
+        .lock()
+        .await
+        .storage_mut()
+        .save_pod_sandbox(&PodSandboxRecord {
+            id: "pod-recover".to_string(),
+            state: "ready".to_string(),
+            name: "pod-recover".to_string(),
+            namespace: "default".to_string(),
+            uid: "uid-recover".to_string(),
+            created_at: RuntimeServiceImpl::now_nanos(),
+            netns_path: netns_path.display().to_string(),
+            labels: "{}".to_string(),
+            annotations: serde_json::to_string(&pod_annotations).unwrap(),
+            pause_container_id: Some("pause-recover".to_string()),
+            ip: Some("10.88.0.20".to_string()),
+        })
+        .unwrap();
 
-    set_fake_runtime_state(&dir, "container-2", "running");
-    service.containers.lock().await.insert(
-        "container-2".to_string(),
-        test_container("container-2", "pod-1", HashMap::new()),
-    );
-    RuntimeService::remove_container(
-        &service,
-        Request::new(RemoveContainerRequest {
-            container_id: "container-2".to_string(),
-        }),
-    )
-    .await
-    .unwrap();
-    RuntimeService::remove_container(
-        &service,
-        Request::new(RemoveContainerRequest {
-            container_id: "container-2".to_string(),
-        }),
+    let mut container_annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut container_annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+        &StoredContainerState {
+            metadata_name: Some("recover-container".to_string()),
+            log_path: Some(
+                dir.path()
+                    .join("logs")
+                    .join("recover.log")
+                    .display()
+                    .to_string(),
+            ),
+            ..Default::default()
+        },
     )
-    .await
     .unwrap();
-}
-
-#[tokio::test]
-async fn stop_and_remove_existing_pod_support_repeat_calls() {
-    let (dir, service) = test_service_with_fake_runtime();
-
-    let pod = test_pod("pod-1", HashMap::new());
     service
-        .pod_sandboxes
+        .persistence
         .lock()
         .await
-        .insert("pod-1".to_string(), pod);
-    RuntimeService::stop_pod_sandbox(
+        .save_container(
+            "recover-container",
+            "pod-recover",
+            crate::runtime::ContainerStatus::Running,
+            "busybox:latest",
+            &Vec::new(),
+            &HashMap::new(),
+            &container_annotations,
+        )
+        .unwrap();
+
+    service.recover_state().await.unwrap();
+
+    let container_status = RuntimeService::container_status(
         &service,
-        Request::new(StopPodSandboxRequest {
-            pod_sandbox_id: "pod-1".to_string(),
+        Request::new(ContainerStatusRequest {
+            container_id: "recover-container".to_string(),
+            verbose: true,
         }),
     )
     .await
-    .unwrap();
-    RuntimeService::stop_pod_sandbox(
+    .unwrap()
+    .into_inner();
+    let pod_status = RuntimeService::pod_sandbox_status(
         &service,
-        Request::new(StopPodSandboxRequest {
-            pod_sandbox_id: "pod-1".to_string(),
+        Request::new(PodSandboxStatusRequest {
+            pod_sandbox_id: "pod-recover".to_string(),
+            verbose: true,
         }),
     )
     .await
-    .unwrap();
+    .unwrap()
+    .into_inner();
+    let containers =
+        RuntimeService::list_containers(&service, Request::new(ListContainersRequest::default()))
+            .await
+            .unwrap()
+            .into_inner();
+    let pods =
+        RuntimeService::list_pod_sandbox(&service, Request::new(ListPodSandboxRequest::default()))
+            .await
+            .unwrap()
+            .into_inner();
 
-    set_fake_runtime_state(&dir, "container-under-pod", "running");
-    service.containers.lock().await.insert(
-        "container-under-pod".to_string(),
-        test_container("container-under-pod", "pod-2", HashMap::new()),
+    assert_eq!(
+        container_status.status.as_ref().unwrap().state,
+        ContainerState::ContainerRunning as i32
     );
-    service
-        .pod_sandboxes
-        .lock()
-        .await
-        .insert("pod-2".to_string(), test_pod("pod-2", HashMap::new()));
-    RuntimeService::remove_pod_sandbox(
-        &service,
-        Request::new(RemovePodSandboxRequest {
-            pod_sandbox_id: "pod-2".to_string(),
-        }),
-    )
-    .await
-    .unwrap();
-    RuntimeService::remove_pod_sandbox(
-        &service,
-        Request::new(RemovePodSandboxRequest {
-            pod_sandbox_id: "pod-2".to_string(),
-        }),
-    )
-    .await
-    .unwrap();
+    assert_eq!(
+        pod_status.status.as_ref().unwrap().state,
+        PodSandboxState::SandboxReady as i32
+    );
+    assert_eq!(containers.containers.len(), 1);
+    assert_eq!(containers.containers[0].pod_sandbox_id, "pod-recover");
+    assert_eq!(pods.items.len(), 1);
+    let info: serde_json::Value =
+        serde_json::from_str(container_status.info.get("info").unwrap()).unwrap();
+    assert_eq!(info["sandboxID"], "pod-recover");
 }
 
 #[tokio::test]
-async fn recover_state_reconciles_running_container_to_exited() {
+async fn recover_state_supports_stop_and_remove_after_restart() {
     let (dir, service) = test_service_with_fake_runtime();
-    set_fake_runtime_state(&dir, "recover-container", "stopped");
+    set_fake_runtime_state(&dir, "recover-container", "running");
+    set_fake_runtime_state(&dir, "pause-recover", "running");
+    let netns_path = dir.path().join("recover-remove.netns");
+    fs::write(&netns_path, "netns").unwrap();
 
-    let mut annotations = HashMap::new();
+    let mut pod_annotations = HashMap::new();
     RuntimeServiceImpl::insert_internal_state(
-        &mut annotations,
-        INTERNAL_CONTAINER_STATE_KEY,
-        &StoredContainerState {
-            metadata_name: Some("recover".to_string()),
+        &mut pod_annotations,
+        INTERNAL_POD_STATE_KEY,
+        &StoredPodState {
+            runtime_handler: "runc".to_string(),
+            netns_path: Some(netns_path.display().to_string()),
+            pause_container_id: Some("pause-recover".to_string()),
             ..Default::default()
         },
     )
     .unwrap();
-
+    service
+        .persistence
+        .lock()
+        .await
+        .storage_mut()
+        .save_pod_sandbox(&PodSandboxRecord {
+            id: "pod-recover".to_string(),
+            state: "ready".to_string(),
+            name: "pod-recover".to_string(),
+            namespace: "default".to_string(),
+            uid: "uid-recover".to_string(),
+            created_at: RuntimeServiceImpl::now_nanos(),
+            netns_path: netns_path.display().to_string(),
+            labels: "{}".to_string(),
+            annotations: serde_json::to_string(&pod_annotations).unwrap(),
+            pause_container_id: Some("pause-recover".to_string()),
+            ip: None,
+        })
+        .unwrap();
     service
         .persistence
         .lock()
         .await
         .save_container(
             "recover-container",
-            "pod-1",
+            "pod-recover",
             crate::runtime::ContainerStatus::Running,
             "busybox:latest",
             &Vec::new(),
             &HashMap::new(),
-            &annotations,
+            &HashMap::new(),
         )
         .unwrap();
 
     service.recover_state().await.unwrap();
 
-    let container = service
-        .containers
-        .lock()
-        .await
-        .get("recover-container")
-        .cloned()
-        .unwrap();
-    assert_eq!(container.state, ContainerState::ContainerExited as i32);
-    let state = RuntimeServiceImpl::read_internal_state::<StoredContainerState>(
-        &container.annotations,
-        INTERNAL_CONTAINER_STATE_KEY,
+    RuntimeService::stop_container(
+        &service,
+        Request::new(StopContainerRequest {
+            container_id: "recover-container".to_string(),
+            timeout: 1,
+        }),
     )
+    .await
+    .unwrap();
+    RuntimeService::remove_container(
+        &service,
+        Request::new(RemoveContainerRequest {
+            container_id: "recover-container".to_string(),
+        }),
+    )
+    .await
+    .unwrap();
+    RuntimeService::stop_pod_sandbox(
+        &service,
+        Request::new(StopPodSandboxRequest {
+            pod_sandbox_id: "pod-recover".to_string(),
+        }),
+    )
+    .await
+    .unwrap();
+    RuntimeService::remove_pod_sandbox(
+        &service,
+        Request::new(RemovePodSandboxRequest {
+            pod_sandbox_id: "pod-recover".to_string(),
+        }),
+    )
+    .await
     .unwrap();
-    assert_eq!(state.exit_code, Some(0));
-    assert!(state.finished_at.is_some());
 
-    let persisted = service
+    assert!(service
         .persistence
         .lock()
         .await
         .storage()
         .get_container("recover-container")
         .unwrap()
-        .unwrap();
-    assert_eq!(persisted.state, "stopped");
+        .is_none());
+    assert!(service
+        .persistence
+        .lock()
+        .await
+        .storage()
+        .get_pod_sandbox("pod-recover")
+        .unwrap()
+        .is_none());
 }
 
 #[tokio::test]
-async fn recover_state_marks_ready_pod_notready_when_pause_is_stopped() {
+async fn recover_state_marks_ready_pod_notready_when_netns_is_missing() {
     let (dir, service) = test_service_with_fake_runtime();
-    set_fake_runtime_state(&dir, "pause-1", "stopped");
-    let netns_path = dir.path().join("pause-netns");
-    fs::write(&netns_path, "netns").unwrap();
+    set_fake_runtime_state(&dir, "pause-missing-netns", "running");
 
+    let missing_netns_path = dir.path().join("missing.netns");
     let mut annotations = HashMap::new();
     RuntimeServiceImpl::insert_internal_state(
         &mut annotations,
         INTERNAL_POD_STATE_KEY,
         &StoredPodState {
             runtime_handler: "runc".to_string(),
-            netns_path: Some(netns_path.display().to_string()),
-            pause_container_id: Some("pause-1".to_string()),
+            netns_path: Some(missing_netns_path.display().to_string()),
+            pause_container_id: Some("pause-missing-netns".to_string()),
             ..Default::default()
         },
     )
@@ -6740,17 +11576,17 @@ async fn recover_state_marks_ready_pod_notready_when_pause_is_stopped() {
         .await
         .storage_mut()
         .save_pod_sandbox(&PodSandboxRecord {
-            id: "pod-recover".to_string(),
+            id: "pod-missing-netns".to_string(),
             state: "ready".to_string(),
-            name: "pod-recover".to_string(),
+            name: "pod-missing-netns".to_string(),
             namespace: "default".to_string(),
-            uid: "uid-1".to_string(),
+            uid: "uid-missing-netns".to_string(),
             created_at: RuntimeServiceImpl::now_nanos(),
-            netns_path: netns_path.display().to_string(),
+            netns_path: missing_netns_path.display().to_string(),
             labels: "{}".to_string(),
             annotations: serde_json::to_string(&annotations).unwrap(),
-            pause_container_id: Some("pause-1".to_string()),
-            ip: None,
+            pause_container_id: Some("pause-missing-netns".to_string()),
+            ip: Some("10.88.0.21".to_string()),
         })
         .unwrap();
 
@@ -6758,75 +11594,70 @@ async fn recover_state_marks_ready_pod_notready_when_pause_is_stopped() {
 
     let pod = service
         .pod_sandboxes
-        .lock()
+        .read()
         .await
-        .get("pod-recover")
+        .get("pod-missing-netns")
         .cloned()
         .unwrap();
     assert_eq!(pod.state, PodSandboxState::SandboxNotready as i32);
+
     let persisted = service
         .persistence
         .lock()
         .await
         .storage()
-        .get_pod_sandbox("pod-recover")
+        .get_pod_sandbox("pod-missing-netns")
         .unwrap()
         .unwrap();
     assert_eq!(persisted.state, "notready");
 }
 
 #[tokio::test]
-async fn recover_state_does_not_replay_historical_events() {
+async fn remove_pod_sandbox_cascades_recovered_containers_after_restart() {
     let (dir, service) = test_service_with_fake_runtime();
-    set_fake_runtime_state(&dir, "recover-container", "stopped");
+    set_fake_runtime_state(&dir, "recover-container", "running");
+    set_fake_runtime_state(&dir, "pause-recover", "running");
+    let netns_path = dir.path().join("recover-cascade.netns");
+    fs::write(&netns_path, "netns").unwrap();
 
+    let mut pod_annotations = HashMap::new();
+    RuntimeServiceImpl::insert_internal_state(
+        &mut pod_annotations,
+        INTERNAL_POD_STATE_KEY,
+        &StoredPodState {
+            runtime_handler: "runc".to_string(),
+            netns_path: Some(netns_path.display().to_string()),
+            pause_container_id: Some("pause-recover".to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
     service
         .persistence
         .lock()
         .await
         .storage_mut()
-        .save_container(&ContainerRecord {
-            id: "recover-container".to_string(),
-            pod_id: "pod-1".to_string(),
-            state: "running".to_string(),
-            image: "busybox:latest".to_string(),
-            command: String::new(),
+        .save_pod_sandbox(&PodSandboxRecord {
+            id: "pod-recover".to_string(),
+            state: "ready".to_string(),
+            name: "pod-recover".to_string(),
+            namespace: "default".to_string(),
+            uid: "uid-recover".to_string(),
             created_at: RuntimeServiceImpl::now_nanos(),
+            netns_path: netns_path.display().to_string(),
             labels: "{}".to_string(),
-            annotations: "{}".to_string(),
-            exit_code: None,
-            exit_time: None,
+            annotations: serde_json::to_string(&pod_annotations).unwrap(),
+            pause_container_id: Some("pause-recover".to_string()),
+            ip: None,
         })
         .unwrap();
 
-    let mut stream =
-        RuntimeService::get_container_events(&service, Request::new(GetEventsRequest {}))
-            .await
-            .unwrap()
-            .into_inner();
-
-    service.recover_state().await.unwrap();
-
-    assert!(
-        timeout(Duration::from_millis(100), stream.next())
-            .await
-            .is_err(),
-        "recover_state should not replay historical events"
-    );
-}
-
-#[tokio::test]
-async fn recover_state_re_registers_exit_monitor_for_running_container() {
-    let fake_nri = Arc::new(FakeNri::default());
-    let (dir, service) = test_service_with_fake_runtime_and_nri(fake_nri.clone());
-    set_fake_runtime_state(&dir, "recover-running", "running");
-
-    let mut annotations = HashMap::new();
+    let mut container_annotations = HashMap::new();
     RuntimeServiceImpl::insert_internal_state(
-        &mut annotations,
+        &mut container_annotations,
         INTERNAL_CONTAINER_STATE_KEY,
         &StoredContainerState {
-            metadata_name: Some("recover-running".to_string()),
+            metadata_name: Some("recover-container".to_string()),
             ..Default::default()
         },
     )
@@ -6835,580 +11666,1026 @@ async fn recover_state_re_registers_exit_monitor_for_running_container() {
         .persistence
         .lock()
         .await
-        .storage_mut()
-        .save_container(&ContainerRecord {
-            id: "recover-running".to_string(),
-            pod_id: "pod-1".to_string(),
-            state: "running".to_string(),
-            image: "busybox:latest".to_string(),
-            command: String::new(),
-            created_at: RuntimeServiceImpl::now_nanos(),
-            labels: "{}".to_string(),
-            annotations: serde_json::to_string(&annotations).unwrap(),
-            exit_code: None,
-            exit_time: None,
-        })
+        .save_container(
+            "recover-container",
+            "pod-recover",
+            crate::runtime::ContainerStatus::Running,
+            "busybox:latest",
+            &Vec::new(),
+            &HashMap::new(),
+            &container_annotations,
+        )
         .unwrap();
 
-    let mut stream =
-        RuntimeService::get_container_events(&service, Request::new(GetEventsRequest {}))
-            .await
-            .unwrap()
-            .into_inner();
-
     service.recover_state().await.unwrap();
 
-    let exit_code_path = dir
-        .path()
-        .join("shims")
-        .join("recover-running")
-        .join("exit_code");
-    fs::create_dir_all(exit_code_path.parent().unwrap()).unwrap();
-    fs::write(&exit_code_path, "23").unwrap();
-
-    let event = timeout(Duration::from_secs(3), async {
-        loop {
-            if let Some(Ok(event)) = stream.next().await {
-                if event.container_id == "recover-running"
-                    && event.container_event_type
-                        == ContainerEventType::ContainerStoppedEvent as i32
-                {
-                    return event;
-                }
-            }
-        }
-    })
+    RuntimeService::remove_pod_sandbox(
+        &service,
+        Request::new(RemovePodSandboxRequest {
+            pod_sandbox_id: "pod-recover".to_string(),
+        }),
+    )
     .await
-    .expect("timed out waiting for recovered container stop event");
+    .unwrap();
 
-    assert_eq!(event.container_id, "recover-running");
-    let container = service
+    assert!(service
         .containers
+        .read()
+        .await
+        .get("recover-container")
+        .is_none());
+    assert!(service
+        .persistence
         .lock()
         .await
-        .get("recover-running")
-        .cloned()
-        .unwrap();
-    assert_eq!(container.state, ContainerState::ContainerExited as i32);
-    let state = RuntimeServiceImpl::read_internal_state::<StoredContainerState>(
-        &container.annotations,
-        INTERNAL_CONTAINER_STATE_KEY,
+        .storage()
+        .get_container("recover-container")
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn container_status_refreshes_stale_runtime_state_on_query() {
+    let (dir, service) = test_service_with_fake_runtime();
+    set_fake_runtime_state(&dir, "refresh-container", "stopped");
+
+    service.containers.write().await.insert(
+        "refresh-container".to_string(),
+        Container {
+            state: ContainerState::ContainerRunning as i32,
+            ..test_container("refresh-container", "pod-1", HashMap::new())
+        },
+    );
+
+    let response = RuntimeService::container_status(
+        &service,
+        Request::new(ContainerStatusRequest {
+            container_id: "refresh-container".to_string(),
+            verbose: false,
+        }),
     )
-    .unwrap();
-    assert_eq!(state.exit_code, Some(23));
-    assert!(state.nri_stop_notified);
-    assert_eq!(fake_nri.calls.lock().await.clone(), vec!["stop_container"]);
+    .await
+    .unwrap()
+    .into_inner();
+
+    assert_eq!(
+        response.status.unwrap().state,
+        ContainerState::ContainerExited as i32
+    );
 }
 
 #[tokio::test]
-async fn recover_state_supports_inspect_and_list_after_restart() {
+async fn pod_queries_refresh_pause_container_state_on_query() {
     let (dir, service) = test_service_with_fake_runtime();
-    set_fake_runtime_state(&dir, "recover-container", "running");
-    set_fake_runtime_state(&dir, "pause-recover", "running");
-    let netns_path = dir.path().join("recover.netns");
-    fs::write(&netns_path, "netns").unwrap();
+    set_fake_runtime_state(&dir, "pause-refresh", "stopped");
 
-    let mut pod_annotations = HashMap::new();
+    let mut annotations = HashMap::new();
     RuntimeServiceImpl::insert_internal_state(
-        &mut pod_annotations,
+        &mut annotations,
         INTERNAL_POD_STATE_KEY,
         &StoredPodState {
             runtime_handler: "runc".to_string(),
-            netns_path: Some(netns_path.display().to_string()),
-            pause_container_id: Some("pause-recover".to_string()),
-            ip: Some("10.88.0.20".to_string()),
+            pause_container_id: Some("pause-refresh".to_string()),
             ..Default::default()
         },
     )
     .unwrap();
+
+    service.pod_sandboxes.write().await.insert(
+        "pod-refresh".to_string(),
+        crate::proto::runtime::v1::PodSandbox {
+            state: PodSandboxState::SandboxReady as i32,
+            ..test_pod("pod-refresh", annotations)
+        },
+    );
+
+    let list =
+        RuntimeService::list_pod_sandbox(&service, Request::new(ListPodSandboxRequest::default()))
+            .await
+            .unwrap()
+            .into_inner();
+    assert_eq!(list.items.len(), 1);
+    assert_eq!(list.items[0].state, PodSandboxState::SandboxNotready as i32);
+
+    let inspect = RuntimeService::pod_sandbox_status(
+        &service,
+        Request::new(PodSandboxStatusRequest {
+            pod_sandbox_id: "pod-refresh".to_string(),
+            verbose: false,
+        }),
+    )
+    .await
+    .unwrap()
+    .into_inner();
+    assert_eq!(
+        inspect.status.unwrap().state,
+        PodSandboxState::SandboxNotready as i32
+    );
+}
+
+#[tokio::test]
+async fn recover_state_ignores_stale_shim_metadata_artifacts() {
+    let (dir, service) = test_service_with_fake_runtime();
+    set_fake_runtime_state(&dir, "recover-container", "running");
+
+    let stale_dir = dir.path().join("shims").join("orphan");
+    fs::create_dir_all(&stale_dir).unwrap();
+    fs::write(stale_dir.join("attach.sock"), "stale").unwrap();
+    fs::write(
+        stale_dir.join("shim.json"),
+        serde_json::to_vec_pretty(&crate::runtime::ShimProcess {
+            container_id: "orphan".to_string(),
+            shim_pid: 999_999,
+            exit_code_file: stale_dir.join("exit_code"),
+            log_file: stale_dir.join("shim.log"),
+            socket_path: stale_dir.join("attach.sock"),
+            bundle_path: dir.path().join("bundles").join("orphan"),
+        })
+        .unwrap(),
+    )
+    .unwrap();
+
     service
         .persistence
         .lock()
         .await
-        .storage_mut()
-        .save_pod_sandbox(&PodSandboxRecord {
-            id: "pod-recover".to_string(),
-            state: "ready".to_string(),
-            name: "pod-recover".to_string(),
-            namespace: "default".to_string(),
-            uid: "uid-recover".to_string(),
-            created_at: RuntimeServiceImpl::now_nanos(),
-            netns_path: netns_path.display().to_string(),
-            labels: "{}".to_string(),
-            annotations: serde_json::to_string(&pod_annotations).unwrap(),
-            pause_container_id: Some("pause-recover".to_string()),
-            ip: Some("10.88.0.20".to_string()),
-        })
+        .save_container(
+            "recover-container",
+            "pod-1",
+            crate::runtime::ContainerStatus::Running,
+            "busybox:latest",
+            &Vec::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+        )
         .unwrap();
 
-    let mut container_annotations = HashMap::new();
+    service.recover_state().await.unwrap();
+
+    let containers =
+        RuntimeService::list_containers(&service, Request::new(ListContainersRequest::default()))
+            .await
+            .unwrap()
+            .into_inner();
+    assert_eq!(containers.containers.len(), 1);
+    assert!(
+        !stale_dir.exists(),
+        "recover_state should clean orphaned shim artifacts"
+    );
+}
+
+#[tokio::test]
+async fn lifecycle_timestamps_are_nonzero_and_ordered() {
+    let (dir, service) = test_service_with_fake_runtime();
+
+    let mut annotations = HashMap::new();
     RuntimeServiceImpl::insert_internal_state(
-        &mut container_annotations,
+        &mut annotations,
         INTERNAL_CONTAINER_STATE_KEY,
-        &StoredContainerState {
-            metadata_name: Some("recover-container".to_string()),
-            log_path: Some(
-                dir.path()
-                    .join("logs")
-                    .join("recover.log")
-                    .display()
-                    .to_string(),
-            ),
-            ..Default::default()
-        },
+        &StoredContainerState::default(),
     )
     .unwrap();
+
+    service
+        .pod_sandboxes
+        .write()
+        .await
+        .insert("pod-ts".to_string(), test_pod("pod-ts", HashMap::new()));
+    service.containers.write().await.insert(
+        "container-ts".to_string(),
+        test_container("container-ts", "pod-ts", annotations.clone()),
+    );
     service
         .persistence
         .lock()
         .await
         .save_container(
-            "recover-container",
-            "pod-recover",
-            crate::runtime::ContainerStatus::Running,
+            "container-ts",
+            "pod-ts",
+            crate::runtime::ContainerStatus::Created,
             "busybox:latest",
             &Vec::new(),
             &HashMap::new(),
-            &container_annotations,
+            &annotations,
         )
         .unwrap();
+    write_test_bundle_config(&dir, "container-ts", &annotations);
 
-    service.recover_state().await.unwrap();
+    RuntimeService::start_container(
+        &service,
+        Request::new(StartContainerRequest {
+            container_id: "container-ts".to_string(),
+        }),
+    )
+    .await
+    .unwrap();
+    set_fake_runtime_state(&dir, "container-ts", "running");
+    RuntimeService::stop_container(
+        &service,
+        Request::new(StopContainerRequest {
+            container_id: "container-ts".to_string(),
+            timeout: 0,
+        }),
+    )
+    .await
+    .unwrap();
 
-    let container_status = RuntimeService::container_status(
+    let status = RuntimeService::container_status(
         &service,
         Request::new(ContainerStatusRequest {
-            container_id: "recover-container".to_string(),
-            verbose: true,
+            container_id: "container-ts".to_string(),
+            verbose: false,
         }),
     )
     .await
     .unwrap()
-    .into_inner();
+    .into_inner()
+    .status
+    .unwrap();
+    assert!(status.created_at > 0);
+    assert!(status.started_at > status.created_at);
+    assert!(status.finished_at > status.started_at);
+
     let pod_status = RuntimeService::pod_sandbox_status(
         &service,
         Request::new(PodSandboxStatusRequest {
-            pod_sandbox_id: "pod-recover".to_string(),
-            verbose: true,
+            pod_sandbox_id: "pod-ts".to_string(),
+            verbose: false,
         }),
     )
     .await
     .unwrap()
     .into_inner();
-    let containers =
-        RuntimeService::list_containers(&service, Request::new(ListContainersRequest::default()))
-            .await
-            .unwrap()
-            .into_inner();
-    let pods =
-        RuntimeService::list_pod_sandbox(&service, Request::new(ListPodSandboxRequest::default()))
+    let sandbox = pod_status.status.unwrap();
+    assert!(sandbox.created_at > 0);
+    assert!(pod_status.timestamp >= sandbox.created_at);
+}
+
+#[test]
+fn runtime_config_validate_accepts_usable_paths() {
+    let dir = tempdir().unwrap();
+    let runtime_path = dir.path().join("bin").join("runc");
+    fs::create_dir_all(runtime_path.parent().unwrap()).unwrap();
+    fs::write(&runtime_path, "#!/bin/sh\n").unwrap();
+    fs::set_permissions(&runtime_path, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let config = RuntimeConfig {
+        root_dir: dir.path().join("root"),
+        state_dir: dir.path().join("state"),
+        image_root: dir.path().join("storage"),
+        runtime_root: dir.path().join("runtime-root"),
+        log_dir: dir.path().join("logs"),
+        runtime_path,
+        ..RuntimeConfig::default()
+    };
+
+    config.validate().unwrap();
+    assert!(dir.path().join("state").is_dir());
+    assert!(dir.path().join("logs").is_dir());
+}
+
+#[test]
+fn runtime_config_validate_reports_every_problem() {
+    let dir = tempdir().unwrap();
+    let blocker = dir.path().join("blocker");
+    fs::write(&blocker, "").unwrap();
+
+    let config = RuntimeConfig {
+        root_dir: blocker.join("root"),
+        state_dir: dir.path().join("state"),
+        image_root: blocker.join("storage"),
+        runtime_root: dir.path().join("runtime-root"),
+        log_dir: dir.path().join("logs"),
+        runtime_path: dir.path().join("missing-runc"),
+        ..RuntimeConfig::default()
+    };
+
+    let message = config.validate().unwrap_err().to_string();
+    assert!(message.contains("root_dir"), "{}", message);
+    assert!(message.contains("image_root"), "{}", message);
+    assert!(message.contains("missing-runc"), "{}", message);
+    assert!(!message.contains("state_dir"), "{}", message);
+}
+
+#[tokio::test]
+async fn sandbox_container_index_groups_containers_by_pod() {
+    let (dir, service) = test_service_with_fake_runtime();
+    for pod_id in ["pod-a", "pod-b", "pod-c"] {
+        service
+            .pod_sandboxes
+            .write()
             .await
-            .unwrap()
-            .into_inner();
+            .insert(pod_id.to_string(), test_pod(pod_id, HashMap::new()));
+    }
+    for (container_id, pod_id) in [
+        ("container-a1", "pod-a"),
+        ("container-a2", "pod-a"),
+        ("container-b1", "pod-b"),
+    ] {
+        service.containers.write().await.insert(
+            container_id.to_string(),
+            test_container(container_id, pod_id, HashMap::new()),
+        );
+        set_fake_runtime_state(&dir, container_id, "running");
+    }
 
-    assert_eq!(
-        container_status.status.as_ref().unwrap().state,
-        ContainerState::ContainerRunning as i32
+    let mut expected = HashMap::new();
+    expected.insert("pod-a", vec!["container-a1", "container-a2"]);
+    expected.insert("pod-b", vec!["container-b1"]);
+    expected.insert("pod-c", Vec::new());
+    for (pod_id, expected_ids) in &expected {
+        let response = RuntimeService::pod_sandbox_status(
+            &service,
+            Request::new(PodSandboxStatusRequest {
+                pod_sandbox_id: pod_id.to_string(),
+                verbose: false,
+            }),
+        )
+        .await
+        .unwrap()
+        .into_inner();
+        let mut ids: Vec<String> = response
+            .containers_statuses
+            .into_iter()
+            .map(|status| status.id)
+            .collect();
+        ids.sort();
+        assert_eq!(&ids, expected_ids, "containers of {}", pod_id);
+    }
+
+    let mut containers = service.containers.write().await;
+    containers.remove("container-a1");
+    containers.insert(
+        "container-b1".to_string(),
+        test_container("container-b1", "pod-c", HashMap::new()),
     );
+    assert_eq!(containers.ids_in_sandbox("pod-a"), vec!["container-a2"]);
+    assert!(containers.ids_in_sandbox("pod-b").is_empty());
+    assert_eq!(containers.ids_in_sandbox("pod-c"), vec!["container-b1"]);
+}
+
+#[tokio::test]
+async fn list_containers_shares_snapshot_until_containers_change() {
+    let (dir, service) = test_service_with_fake_runtime();
+    for container_id in ["container-1", "container-2"] {
+        let mut annotations = HashMap::new();
+        annotations.insert("example.com/owner".to_string(), "team-a".to_string());
+        annotations.insert(INTERNAL_CONTAINER_STATE_KEY.to_string(), "{}".to_string());
+        service.containers.write().await.insert(
+            container_id.to_string(),
+            test_container(container_id, "pod-1", annotations),
+        );
+        set_fake_runtime_state(&dir, container_id, "running");
+    }
+
+    let list = || async {
+        let mut containers = RuntimeService::list_containers(
+            &service,
+            Request::new(ListContainersRequest::default()),
+        )
+        .await
+        .unwrap()
+        .into_inner()
+        .containers;
+        containers.sort_by(|left, right| left.id.cmp(&right.id));
+        containers
+    };
+    // 第一次刷新会写入运行时状态，之后容器不变，快照保持共享
+    let first = list().await;
+    let first_snapshot = service.containers.read().await.snapshot();
+    let second = list().await;
+    assert_eq!(first, second);
+    assert!(Arc::ptr_eq(
+        &first_snapshot,
+        &service.containers.read().await.snapshot()
+    ));
+    assert_eq!(first.len(), 2);
+    assert_eq!(first[0].state, ContainerState::ContainerRunning as i32);
     assert_eq!(
-        pod_status.status.as_ref().unwrap().state,
-        PodSandboxState::SandboxReady as i32
+        first[0].annotations,
+        HashMap::from([("example.com/owner".to_string(), "team-a".to_string())])
+    );
+
+    service
+        .containers
+        .write()
+        .await
+        .get_mut("container-2")
+        .unwrap()
+        .labels
+        .insert("tier".to_string(), "web".to_string());
+    assert!(!Arc::ptr_eq(
+        &first_snapshot,
+        &service.containers.read().await.snapshot()
+    ));
+    let updated = list().await;
+    assert_eq!(
+        updated[1].labels.get("tier").map(String::as_str),
+        Some("web")
     );
-    assert_eq!(containers.containers.len(), 1);
-    assert_eq!(containers.containers[0].pod_sandbox_id, "pod-recover");
-    assert_eq!(pods.items.len(), 1);
-    let info: serde_json::Value =
-        serde_json::from_str(container_status.info.get("info").unwrap()).unwrap();
-    assert_eq!(info["sandboxID"], "pod-recover");
+
+    service.containers.write().await.remove("container-1");
+    let ids: Vec<String> = list()
+        .await
+        .into_iter()
+        .map(|container| container.id)
+        .collect();
+    assert_eq!(ids, vec!["container-2"]);
 }
 
 #[tokio::test]
-async fn recover_state_supports_stop_and_remove_after_restart() {
+async fn remove_container_shuts_down_background_tasks() {
     let (dir, service) = test_service_with_fake_runtime();
-    set_fake_runtime_state(&dir, "recover-container", "running");
-    set_fake_runtime_state(&dir, "pause-recover", "running");
-    let netns_path = dir.path().join("recover-remove.netns");
-    fs::write(&netns_path, "netns").unwrap();
+    service.pod_sandboxes.write().await.insert(
+        "pod-tasks".to_string(),
+        test_pod("pod-tasks", HashMap::new()),
+    );
+
+    for index in 0..20 {
+        let container_id = format!("container-tasks-{}", index);
+        service.containers.write().await.insert(
+            container_id.clone(),
+            Container {
+                state: ContainerState::ContainerCreated as i32,
+                ..test_container(&container_id, "pod-tasks", HashMap::new())
+            },
+        );
+        set_fake_runtime_state(&dir, &container_id, "created");
 
-    let mut pod_annotations = HashMap::new();
-    RuntimeServiceImpl::insert_internal_state(
-        &mut pod_annotations,
-        INTERNAL_POD_STATE_KEY,
-        &StoredPodState {
-            runtime_handler: "runc".to_string(),
-            netns_path: Some(netns_path.display().to_string()),
-            pause_container_id: Some("pause-recover".to_string()),
-            ..Default::default()
-        },
-    )
-    .unwrap();
-    service
-        .persistence
-        .lock()
-        .await
-        .storage_mut()
-        .save_pod_sandbox(&PodSandboxRecord {
-            id: "pod-recover".to_string(),
-            state: "ready".to_string(),
-            name: "pod-recover".to_string(),
-            namespace: "default".to_string(),
-            uid: "uid-recover".to_string(),
-            created_at: RuntimeServiceImpl::now_nanos(),
-            netns_path: netns_path.display().to_string(),
-            labels: "{}".to_string(),
-            annotations: serde_json::to_string(&pod_annotations).unwrap(),
-            pause_container_id: Some("pause-recover".to_string()),
-            ip: None,
+        service.ensure_exit_monitor_registered(&container_id);
+        service
+            .tasks
+            .spawn(&container_id, |mut cancellation| async move {
+                cancellation.cancelled().await;
+            });
+        timeout(Duration::from_secs(2), async {
+            while service.tasks.active_tasks() < 2 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
         })
-        .unwrap();
-    service
-        .persistence
-        .lock()
         .await
-        .save_container(
-            "recover-container",
-            "pod-recover",
-            crate::runtime::ContainerStatus::Running,
-            "busybox:latest",
-            &Vec::new(),
-            &HashMap::new(),
-            &HashMap::new(),
+        .expect("exit monitor and log task should start");
+
+        RuntimeService::remove_container(
+            &service,
+            Request::new(RemoveContainerRequest {
+                container_id: container_id.clone(),
+            }),
         )
+        .await
         .unwrap();
+        assert_eq!(service.tasks.active_tasks(), 0, "iteration {}", index);
+    }
 
-    service.recover_state().await.unwrap();
-
-    RuntimeService::stop_container(
-        &service,
-        Request::new(StopContainerRequest {
-            container_id: "recover-container".to_string(),
-            timeout: 1,
-        }),
-    )
-    .await
-    .unwrap();
-    RuntimeService::remove_container(
-        &service,
-        Request::new(RemoveContainerRequest {
-            container_id: "recover-container".to_string(),
-        }),
-    )
-    .await
-    .unwrap();
-    RuntimeService::stop_pod_sandbox(
-        &service,
-        Request::new(StopPodSandboxRequest {
-            pod_sandbox_id: "pod-recover".to_string(),
-        }),
-    )
-    .await
-    .unwrap();
+    service
+        .tasks
+        .spawn("pod-tasks", |mut cancellation| async move {
+            cancellation.cancelled().await;
+        });
     RuntimeService::remove_pod_sandbox(
         &service,
         Request::new(RemovePodSandboxRequest {
-            pod_sandbox_id: "pod-recover".to_string(),
+            pod_sandbox_id: "pod-tasks".to_string(),
         }),
     )
     .await
     .unwrap();
-
-    assert!(service
-        .persistence
-        .lock()
-        .await
-        .storage()
-        .get_container("recover-container")
-        .unwrap()
-        .is_none());
-    assert!(service
-        .persistence
-        .lock()
-        .await
-        .storage()
-        .get_pod_sandbox("pod-recover")
-        .unwrap()
-        .is_none());
+    assert_eq!(service.tasks.active_tasks(), 0);
 }
 
 #[tokio::test]
-async fn recover_state_marks_ready_pod_notready_when_netns_is_missing() {
+async fn container_labels_default_to_image_config_labels() {
     let (dir, service) = test_service_with_fake_runtime();
-    set_fake_runtime_state(&dir, "pause-missing-netns", "running");
+    let image_dir = dir
+        .path()
+        .join("root")
+        .join("storage")
+        .join("images")
+        .join("sha256:labeled");
+    fs::create_dir_all(&image_dir).unwrap();
+    fs::write(
+        image_dir.join("metadata.json"),
+        serde_json::json!({
+            "id": "sha256:labeled",
+            "repo_tags": ["docker.io/library/nginx:latest"],
+            "annotations": {
+                "maintainer": "nginx",
+                "tier": "image",
+            },
+            "exposed_ports": ["80/tcp"],
+        })
+        .to_string(),
+    )
+    .unwrap();
 
-    let missing_netns_path = dir.path().join("missing.netns");
+    let cri_labels = HashMap::from([
+        ("tier".to_string(), "frontend".to_string()),
+        ("app".to_string(), "web".to_string()),
+    ]);
+    let labels = service.container_labels_with_image_defaults(
+        "container-labels",
+        "docker.io/library/nginx:latest",
+        &cri_labels,
+    );
+    assert_eq!(labels.get("maintainer").map(String::as_str), Some("nginx"));
+    assert_eq!(labels.get("tier").map(String::as_str), Some("frontend"));
+    assert_eq!(labels.get("app").map(String::as_str), Some("web"));
+
+    let labels = service.container_labels_with_image_defaults(
+        "container-labels",
+        "missing:latest",
+        &cri_labels,
+    );
+    assert_eq!(labels, cri_labels);
+}
+
+#[test]
+fn record_applied_linux_resources_merges_nri_adjustment_into_state() {
     let mut annotations = HashMap::new();
     RuntimeServiceImpl::insert_internal_state(
         &mut annotations,
-        INTERNAL_POD_STATE_KEY,
-        &StoredPodState {
-            runtime_handler: "runc".to_string(),
-            netns_path: Some(missing_netns_path.display().to_string()),
-            pause_container_id: Some("pause-missing-netns".to_string()),
+        INTERNAL_CONTAINER_STATE_KEY,
+        &StoredContainerState {
+            linux_resources: Some(StoredLinuxResources::from(
+                &crate::proto::runtime::v1::LinuxContainerResources {
+                    cpu_shares: 256,
+                    memory_limit_in_bytes: 128 * 1024 * 1024,
+                    ..Default::default()
+                },
+            )),
             ..Default::default()
         },
     )
     .unwrap();
 
-    service
-        .persistence
-        .lock()
-        .await
-        .storage_mut()
-        .save_pod_sandbox(&PodSandboxRecord {
-            id: "pod-missing-netns".to_string(),
-            state: "ready".to_string(),
-            name: "pod-missing-netns".to_string(),
-            namespace: "default".to_string(),
-            uid: "uid-missing-netns".to_string(),
-            created_at: RuntimeServiceImpl::now_nanos(),
-            netns_path: missing_netns_path.display().to_string(),
-            labels: "{}".to_string(),
-            annotations: serde_json::to_string(&annotations).unwrap(),
-            pause_container_id: Some("pause-missing-netns".to_string()),
-            ip: Some("10.88.0.21".to_string()),
-        })
-        .unwrap();
+    let mut adjustment = crate::nri_proto::api::ContainerAdjustment::new();
+    let mut linux = crate::nri_proto::api::LinuxContainerAdjustment::new();
+    let mut resources = crate::nri_proto::api::LinuxResources::new();
+    let mut memory = crate::nri_proto::api::LinuxMemory::new();
+    let mut limit = crate::nri_proto::api::OptionalInt64::new();
+    limit.value = 64 * 1024 * 1024;
+    memory.limit = protobuf::MessageField::some(limit);
+    resources.memory = protobuf::MessageField::some(memory);
+    linux.resources = protobuf::MessageField::some(resources);
+    adjustment.linux = protobuf::MessageField::some(linux);
 
-    service.recover_state().await.unwrap();
+    RuntimeServiceImpl::record_applied_linux_resources(
+        &mut annotations,
+        &adjustment,
+        &NriConfig::default(),
+    )
+    .unwrap();
 
-    let pod = service
-        .pod_sandboxes
-        .lock()
-        .await
-        .get("pod-missing-netns")
-        .cloned()
-        .unwrap();
-    assert_eq!(pod.state, PodSandboxState::SandboxNotready as i32);
+    let applied = RuntimeServiceImpl::read_internal_state::<StoredContainerState>(
+        &annotations,
+        INTERNAL_CONTAINER_STATE_KEY,
+    )
+    .and_then(|state| state.linux_resources)
+    .unwrap()
+    .to_proto();
+    assert_eq!(applied.cpu_shares, 256);
+    assert_eq!(applied.memory_limit_in_bytes, 64 * 1024 * 1024);
+}
+
+fn validation_pod(
+    namespaces: NamespaceOption,
+    privileged: bool,
+    sysctls: &[(&str, &str)],
+) -> crate::proto::runtime::v1::PodSandboxConfig {
+    crate::proto::runtime::v1::PodSandboxConfig {
+        linux: Some(crate::proto::runtime::v1::LinuxPodSandboxConfig {
+            security_context: Some(crate::proto::runtime::v1::LinuxSandboxSecurityContext {
+                namespace_options: Some(namespaces),
+                privileged,
+                ..Default::default()
+            }),
+            sysctls: sysctls
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
 
-    let persisted = service
-        .persistence
-        .lock()
-        .await
-        .storage()
-        .get_pod_sandbox("pod-missing-netns")
-        .unwrap()
-        .unwrap();
-    assert_eq!(persisted.state, "notready");
+fn validation_pod_userns() -> NamespaceOption {
+    let mapping = crate::proto::runtime::v1::IdMapping {
+        host_id: 100000,
+        container_id: 0,
+        length: 65536,
+    };
+    NamespaceOption {
+        userns_options: Some(crate::proto::runtime::v1::UserNamespace {
+            mode: NamespaceMode::Pod as i32,
+            uids: vec![mapping.clone()],
+            gids: vec![mapping],
+        }),
+        ..Default::default()
+    }
 }
 
-#[tokio::test]
-async fn remove_pod_sandbox_cascades_recovered_containers_after_restart() {
-    let (dir, service) = test_service_with_fake_runtime();
-    set_fake_runtime_state(&dir, "recover-container", "running");
-    set_fake_runtime_state(&dir, "pause-recover", "running");
-    let netns_path = dir.path().join("recover-cascade.netns");
-    fs::write(&netns_path, "netns").unwrap();
+fn validation_container(
+    security: crate::proto::runtime::v1::LinuxContainerSecurityContext,
+    mounts: Vec<crate::proto::runtime::v1::Mount>,
+) -> crate::proto::runtime::v1::ContainerConfig {
+    crate::proto::runtime::v1::ContainerConfig {
+        linux: Some(crate::proto::runtime::v1::LinuxContainerConfig {
+            security_context: Some(security),
+            ..Default::default()
+        }),
+        mounts,
+        ..Default::default()
+    }
+}
 
-    let mut pod_annotations = HashMap::new();
-    RuntimeServiceImpl::insert_internal_state(
-        &mut pod_annotations,
-        INTERNAL_POD_STATE_KEY,
-        &StoredPodState {
-            runtime_handler: "runc".to_string(),
-            netns_path: Some(netns_path.display().to_string()),
-            pause_container_id: Some("pause-recover".to_string()),
+fn assert_invalid_spec(result: Result<(), Status>, needle: &str) {
+    let err = result.unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    assert!(err.message().contains(needle), "{}", err.message());
+}
+
+#[test]
+fn pod_sandbox_spec_rejects_namespace_and_privilege_conflicts() {
+    RuntimeServiceImpl::validate_pod_sandbox_spec(&validation_pod(
+        validation_pod_userns(),
+        false,
+        &[],
+    ))
+    .unwrap();
+    assert_invalid_spec(
+        RuntimeServiceImpl::validate_pod_sandbox_spec(&validation_pod(
+            validation_pod_userns(),
+            true,
+            &[],
+        )),
+        "privileged pod sandbox cannot use a user namespace",
+    );
+    assert_invalid_spec(
+        RuntimeServiceImpl::validate_pod_sandbox_spec(&validation_pod(
+            NamespaceOption {
+                network: NamespaceMode::Node as i32,
+                ..validation_pod_userns()
+            },
+            false,
+            &[],
+        )),
+        "host network namespace",
+    );
+
+    let host_network = NamespaceOption {
+        network: NamespaceMode::Node as i32,
+        ..Default::default()
+    };
+    RuntimeServiceImpl::validate_pod_sandbox_spec(&validation_pod(
+        host_network.clone(),
+        false,
+        &[("kernel.shmmax", "1024")],
+    ))
+    .unwrap();
+    assert_invalid_spec(
+        RuntimeServiceImpl::validate_pod_sandbox_spec(&validation_pod(
+            host_network,
+            false,
+            &[("net.ipv4.ip_forward", "1")],
+        )),
+        "sysctl net.ipv4.ip_forward",
+    );
+    assert_invalid_spec(
+        RuntimeServiceImpl::validate_pod_sandbox_spec(&validation_pod(
+            NamespaceOption {
+                ipc: NamespaceMode::Node as i32,
+                ..Default::default()
+            },
+            false,
+            &[("fs.mqueue.msg_max", "10")],
+        )),
+        "host ipc namespace",
+    );
+
+    let mut bad_dns = validation_pod(NamespaceOption::default(), false, &[]);
+    bad_dns.dns_config = Some(crate::proto::runtime::v1::DnsConfig {
+        servers: vec!["dns.example.com".to_string()],
+        ..Default::default()
+    });
+    assert_invalid_spec(
+        RuntimeServiceImpl::validate_pod_sandbox_spec(&bad_dns),
+        "not an IP address",
+    );
+
+    let mut bad_overhead = validation_pod(NamespaceOption::default(), false, &[]);
+    bad_overhead.linux.as_mut().unwrap().overhead =
+        Some(crate::proto::runtime::v1::LinuxContainerResources {
+            memory_limit_in_bytes: -1,
+            ..Default::default()
+        });
+    assert_invalid_spec(
+        RuntimeServiceImpl::validate_pod_sandbox_spec(&bad_overhead),
+        "pod overhead memory_limit_in_bytes must not be negative",
+    );
+    bad_overhead.linux.as_mut().unwrap().overhead =
+        Some(crate::proto::runtime::v1::LinuxContainerResources {
+            cpuset_cpus: "0".to_string(),
+            ..Default::default()
+        });
+    assert_invalid_spec(
+        RuntimeServiceImpl::validate_pod_sandbox_spec(&bad_overhead),
+        "cannot set cpuset",
+    );
+}
+
+#[test]
+fn container_spec_rejects_privilege_and_mount_conflicts() {
+    let privileged = crate::proto::runtime::v1::LinuxContainerSecurityContext {
+        privileged: true,
+        ..Default::default()
+    };
+    RuntimeServiceImpl::validate_container_spec(
+        &validation_container(privileged.clone(), Vec::new()),
+        Some(true),
+        None,
+    )
+    .unwrap();
+    assert_invalid_spec(
+        RuntimeServiceImpl::validate_container_spec(
+            &validation_container(privileged.clone(), Vec::new()),
+            Some(false),
+            None,
+        ),
+        "requires a privileged pod sandbox",
+    );
+    assert_invalid_spec(
+        RuntimeServiceImpl::validate_container_spec(
+            &validation_container(
+                crate::proto::runtime::v1::LinuxContainerSecurityContext {
+                    no_new_privs: true,
+                    ..privileged.clone()
+                },
+                Vec::new(),
+            ),
+            Some(true),
+            None,
+        ),
+        "no_new_privs",
+    );
+    assert_invalid_spec(
+        RuntimeServiceImpl::validate_container_spec(
+            &validation_container(privileged, Vec::new()),
+            Some(true),
+            Some(&validation_pod_userns()),
+        ),
+        "user namespace",
+    );
+    assert_invalid_spec(
+        RuntimeServiceImpl::validate_container_spec(
+            &validation_container(
+                crate::proto::runtime::v1::LinuxContainerSecurityContext {
+                    run_as_user: Some(crate::proto::runtime::v1::Int64Value { value: 1000 }),
+                    run_as_username: "app".to_string(),
+                    ..Default::default()
+                },
+                Vec::new(),
+            ),
+            Some(false),
+            None,
+        ),
+        "mutually exclusive",
+    );
+    for security in [
+        crate::proto::runtime::v1::LinuxContainerSecurityContext {
+            supplemental_groups: vec![1000, -1],
             ..Default::default()
         },
-    )
-    .unwrap();
-    service
-        .persistence
-        .lock()
-        .await
-        .storage_mut()
-        .save_pod_sandbox(&PodSandboxRecord {
-            id: "pod-recover".to_string(),
-            state: "ready".to_string(),
-            name: "pod-recover".to_string(),
-            namespace: "default".to_string(),
-            uid: "uid-recover".to_string(),
-            created_at: RuntimeServiceImpl::now_nanos(),
-            netns_path: netns_path.display().to_string(),
-            labels: "{}".to_string(),
-            annotations: serde_json::to_string(&pod_annotations).unwrap(),
-            pause_container_id: Some("pause-recover".to_string()),
-            ip: None,
-        })
-        .unwrap();
-
-    let mut container_annotations = HashMap::new();
-    RuntimeServiceImpl::insert_internal_state(
-        &mut container_annotations,
-        INTERNAL_CONTAINER_STATE_KEY,
-        &StoredContainerState {
-            metadata_name: Some("recover-container".to_string()),
+        crate::proto::runtime::v1::LinuxContainerSecurityContext {
+            run_as_group: Some(crate::proto::runtime::v1::Int64Value {
+                value: i64::from(u32::MAX) + 1,
+            }),
             ..Default::default()
         },
+    ] {
+        assert_invalid_spec(
+            RuntimeServiceImpl::validate_container_spec(
+                &validation_container(security, Vec::new()),
+                Some(false),
+                None,
+            ),
+            "not a valid gid",
+        );
+    }
+
+    let readonly = crate::proto::runtime::v1::LinuxContainerSecurityContext {
+        readonly_rootfs: true,
+        ..Default::default()
+    };
+    let mount = |path: &str, readonly: bool| crate::proto::runtime::v1::Mount {
+        container_path: path.to_string(),
+        host_path: "/tmp".to_string(),
+        readonly,
+        ..Default::default()
+    };
+    RuntimeServiceImpl::validate_container_spec(
+        &validation_container(readonly.clone(), vec![mount("/data", false)]),
+        Some(false),
+        None,
     )
     .unwrap();
-    service
-        .persistence
-        .lock()
-        .await
-        .save_container(
-            "recover-container",
-            "pod-recover",
-            crate::runtime::ContainerStatus::Running,
-            "busybox:latest",
-            &Vec::new(),
-            &HashMap::new(),
-            &container_annotations,
-        )
-        .unwrap();
+    assert_invalid_spec(
+        RuntimeServiceImpl::validate_container_spec(
+            &validation_container(readonly.clone(), vec![mount("/", false)]),
+            Some(false),
+            None,
+        ),
+        "writable mount on /",
+    );
+    assert_invalid_spec(
+        RuntimeServiceImpl::validate_container_spec(
+            &validation_container(readonly, vec![mount("/data", false), mount("/data/", true)]),
+            Some(false),
+            None,
+        ),
+        "more than once",
+    );
+    assert_invalid_spec(
+        RuntimeServiceImpl::validate_container_spec(
+            &validation_container(Default::default(), vec![mount("data", false)]),
+            Some(false),
+            None,
+        ),
+        "absolute path",
+    );
+}
 
-    service.recover_state().await.unwrap();
+#[tokio::test]
+async fn rootless_mode_rejects_privileged_and_host_namespace_workloads() {
+    let dir = tempdir().unwrap();
+    let service = RuntimeServiceImpl::new(RuntimeConfig {
+        rootless: Some(
+            crate::rootless::RootlessConfig::new()
+                .with_network_mode(crate::rootless::NetworkMode::None)
+                .enable(),
+        ),
+        ..test_runtime_config(dir.path().join("root"))
+    });
+    let host_network = NamespaceOption {
+        network: NamespaceMode::Node as i32,
+        ..Default::default()
+    };
 
-    RuntimeService::remove_pod_sandbox(
-        &service,
-        Request::new(RemovePodSandboxRequest {
-            pod_sandbox_id: "pod-recover".to_string(),
+    for (pod, needle) in [
+        (
+            validation_pod(NamespaceOption::default(), true, &[]),
+            "privileged pod sandbox",
+        ),
+        (
+            validation_pod(host_network.clone(), false, &[]),
+            "host network namespace",
+        ),
+        (
+            validation_pod(validation_pod_userns(), false, &[]),
+            "user namespace option",
+        ),
+    ] {
+        let err = RuntimeService::run_pod_sandbox(
+            &service,
+            Request::new(RunPodSandboxRequest {
+                config: Some(pod),
+                runtime_handler: String::new(),
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+        assert!(err.message().contains(needle), "{}", err.message());
+        assert!(err.message().contains("rootless mode"), "{}", err.message());
+    }
+    assert!(service.pod_sandboxes.read().await.is_empty());
+
+    let err = RuntimeServiceImpl::validate_rootless_security(
+        "container",
+        false,
+        Some(&NamespaceOption {
+            pid: NamespaceMode::Node as i32,
+            ..Default::default()
         }),
     )
-    .await
-    .unwrap();
-
-    assert!(service
-        .containers
-        .lock()
-        .await
-        .get("recover-container")
-        .is_none());
-    assert!(service
-        .persistence
-        .lock()
-        .await
-        .storage()
-        .get_container("recover-container")
-        .unwrap()
-        .is_none());
+    .unwrap_err();
+    assert!(
+        err.message().contains("host pid namespace"),
+        "{}",
+        err.message()
+    );
+    RuntimeServiceImpl::validate_rootless_security("container", false, None).unwrap();
 }
 
 #[tokio::test]
-async fn container_status_refreshes_stale_runtime_state_on_query() {
-    let (dir, service) = test_service_with_fake_runtime();
-    set_fake_runtime_state(&dir, "refresh-container", "stopped");
+async fn windows_only_configs_are_rejected_as_unimplemented() {
+    let (_dir, service) = test_service_with_fake_runtime();
+    let assert_unimplemented = |result: Result<(), Status>| {
+        let err = result.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::Unimplemented);
+        assert!(
+            err.message().contains("only supports Linux containers"),
+            "{}",
+            err.message()
+        );
+    };
 
-    service.containers.lock().await.insert(
-        "refresh-container".to_string(),
-        Container {
-            state: ContainerState::ContainerRunning as i32,
-            ..test_container("refresh-container", "pod-1", HashMap::new())
-        },
+    let mut pod = validation_pod(NamespaceOption::default(), false, &[]);
+    pod.windows = Some(crate::proto::runtime::v1::WindowsPodSandboxConfig::default());
+    assert_unimplemented(
+        RuntimeService::run_pod_sandbox(
+            &service,
+            Request::new(RunPodSandboxRequest {
+                config: Some(pod),
+                runtime_handler: String::new(),
+            }),
+        )
+        .await
+        .map(|_| ()),
     );
+    assert!(service.pod_sandboxes.read().await.is_empty());
 
-    let response = RuntimeService::container_status(
-        &service,
-        Request::new(ContainerStatusRequest {
-            container_id: "refresh-container".to_string(),
-            verbose: false,
+    let mut container = validation_container(Default::default(), Vec::new());
+    container.windows = Some(crate::proto::runtime::v1::WindowsContainerConfig {
+        resources: Some(crate::proto::runtime::v1::WindowsContainerResources {
+            cpu_count: 2,
+            ..Default::default()
         }),
-    )
-    .await
-    .unwrap()
-    .into_inner();
+        ..Default::default()
+    });
+    assert_unimplemented(RuntimeServiceImpl::validate_container_spec(
+        &container, None, None,
+    ));
 
-    assert_eq!(
-        response.status.unwrap().state,
-        ContainerState::ContainerExited as i32
+    assert_unimplemented(
+        RuntimeService::update_container_resources(
+            &service,
+            Request::new(UpdateContainerResourcesRequest {
+                container_id: "missing".to_string(),
+                linux: None,
+                windows: Some(crate::proto::runtime::v1::WindowsContainerResources::default()),
+                annotations: HashMap::new(),
+            }),
+        )
+        .await
+        .map(|_| ()),
     );
 }
 
 #[tokio::test]
-async fn pod_queries_refresh_pause_container_state_on_query() {
-    let (dir, service) = test_service_with_fake_runtime();
-    set_fake_runtime_state(&dir, "pause-refresh", "stopped");
+async fn audit_log_records_mutating_requests_only() {
+    let audit_dir = tempdir().unwrap();
+    let audit_path = audit_dir.path().join("audit.log");
+    let mut service = test_service();
+    service.set_audit_logger(Arc::new(
+        crate::audit::AuditLogger::open(&audit_path).unwrap(),
+    ));
 
-    let mut annotations = HashMap::new();
-    RuntimeServiceImpl::insert_internal_state(
-        &mut annotations,
-        INTERNAL_POD_STATE_KEY,
-        &StoredPodState {
-            runtime_handler: "runc".to_string(),
-            pause_container_id: Some("pause-refresh".to_string()),
-            ..Default::default()
-        },
+    RuntimeService::stop_container(
+        &service,
+        Request::new(StopContainerRequest {
+            container_id: "missing".to_string(),
+            timeout: 0,
+        }),
     )
+    .await
     .unwrap();
-
-    service.pod_sandboxes.lock().await.insert(
-        "pod-refresh".to_string(),
-        crate::proto::runtime::v1::PodSandbox {
-            state: PodSandboxState::SandboxReady as i32,
-            ..test_pod("pod-refresh", annotations)
-        },
-    );
-
-    let list =
-        RuntimeService::list_pod_sandbox(&service, Request::new(ListPodSandboxRequest::default()))
-            .await
-            .unwrap()
-            .into_inner();
-    assert_eq!(list.items.len(), 1);
-    assert_eq!(list.items[0].state, PodSandboxState::SandboxNotready as i32);
-
-    let inspect = RuntimeService::pod_sandbox_status(
+    let start = RuntimeService::start_container(
         &service,
-        Request::new(PodSandboxStatusRequest {
-            pod_sandbox_id: "pod-refresh".to_string(),
+        Request::new(StartContainerRequest {
+            container_id: "missing".to_string(),
+        }),
+    )
+    .await;
+    assert!(start.is_err());
+    let _ = RuntimeService::container_status(
+        &service,
+        Request::new(ContainerStatusRequest {
+            container_id: "missing".to_string(),
             verbose: false,
         }),
     )
-    .await
-    .unwrap()
-    .into_inner();
+    .await;
+
+    let records: Vec<crate::audit::AuditRecord> = fs::read_to_string(&audit_path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(records.len(), 2);
     assert_eq!(
-        inspect.status.unwrap().state,
-        PodSandboxState::SandboxNotready as i32
+        records[0].method,
+        "/runtime.v1.RuntimeService/StopContainer"
     );
-}
-
-#[tokio::test]
-async fn recover_state_ignores_stale_shim_metadata_artifacts() {
-    let (dir, service) = test_service_with_fake_runtime();
-    set_fake_runtime_state(&dir, "recover-container", "running");
-
-    let stale_dir = dir.path().join("shims").join("orphan");
-    fs::create_dir_all(&stale_dir).unwrap();
-    fs::write(stale_dir.join("attach.sock"), "stale").unwrap();
-    fs::write(
-        stale_dir.join("shim.json"),
-        serde_json::to_vec_pretty(&crate::runtime::ShimProcess {
-            container_id: "orphan".to_string(),
-            shim_pid: 999_999,
-            exit_code_file: stale_dir.join("exit_code"),
-            log_file: stale_dir.join("shim.log"),
-            socket_path: stale_dir.join("attach.sock"),
-            bundle_path: dir.path().join("bundles").join("orphan"),
-        })
-        .unwrap(),
-    )
-    .unwrap();
-
-    service
-        .persistence
-        .lock()
-        .await
-        .save_container(
-            "recover-container",
-            "pod-1",
-            crate::runtime::ContainerStatus::Running,
-            "busybox:latest",
-            &Vec::new(),
-            &HashMap::new(),
-            &HashMap::new(),
-        )
-        .unwrap();
-
-    service.recover_state().await.unwrap();
-
-    let containers =
-        RuntimeService::list_containers(&service, Request::new(ListContainersRequest::default()))
-            .await
-            .unwrap()
-            .into_inner();
-    assert_eq!(containers.containers.len(), 1);
-    assert!(
-        !stale_dir.exists(),
-        "recover_state should clean orphaned shim artifacts"
+    assert_eq!(records[0].ids["container_id"], "missing");
+    assert_eq!(records[0].result, "OK");
+    assert_eq!(records[0].peer, None);
+    assert_eq!(
+        records[1].method,
+        "/runtime.v1.RuntimeService/StartContainer"
+    );
+    assert_eq!(
+        records[1].result,
+        format!("{:?}", start.unwrap_err().code())
     );
 }
@@ -0,0 +1,167 @@
+//! gRPC 传输层设置
+//!
+//! 按 `[grpc]` 配置设置 HTTP/2 keepalive 与并发 stream 上限，限制同时保持的连接数，
+//! 并按 SO_PEERCRED 拒绝未授权用户的 unix socket 连接。
+
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::{Stream, StreamExt};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::UnixStream;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tonic::transport::server::Connected;
+use tonic::transport::Server;
+
+use crate::config::GrpcConfig;
+
+/// 按配置创建 gRPC server builder
+pub fn server_builder(config: &GrpcConfig) -> Server {
+    let secs = |value: u64| (value > 0).then(|| Duration::from_secs(value));
+    Server::builder()
+        .http2_keepalive_interval(secs(config.keepalive_interval_secs))
+        .http2_keepalive_timeout(secs(config.keepalive_timeout_secs))
+        .max_concurrent_streams(
+            (config.max_concurrent_streams > 0).then_some(config.max_concurrent_streams),
+        )
+}
+
+/// 只放行对端 uid 在 `allowed_uids` 或 gid 在 `allowed_gids` 中的 unix socket 连接
+///
+/// 两个列表均为空时不做检查；被拒绝或无法读取对端凭据的连接在 accept 后直接关闭。
+pub fn authorize_peers<S, E>(
+    incoming: S,
+    config: &GrpcConfig,
+) -> impl Stream<Item = Result<UnixStream, E>>
+where
+    S: Stream<Item = Result<UnixStream, E>>,
+{
+    let allowed_uids = config.allowed_uids.clone();
+    let allowed_gids = config.allowed_gids.clone();
+    incoming.filter_map(move |connection| {
+        let authorized = match &connection {
+            Err(_) => true,
+            Ok(_) if allowed_uids.is_empty() && allowed_gids.is_empty() => true,
+            Ok(stream) => match stream.peer_cred() {
+                Ok(cred) if allowed_uids.contains(&cred.uid()) => true,
+                Ok(cred) if allowed_gids.contains(&cred.gid()) => true,
+                Ok(cred) => {
+                    log::warn!(
+                        "Rejecting gRPC connection from uid {} gid {} pid {}: peer is not allowed",
+                        cred.uid(),
+                        cred.gid(),
+                        cred.pid()
+                            .map(|pid| pid.to_string())
+                            .unwrap_or_else(|| "unknown".to_string())
+                    );
+                    false
+                }
+                Err(err) => {
+                    log::warn!(
+                        "Rejecting gRPC connection: failed to read peer credentials: {}",
+                        err
+                    );
+                    false
+                }
+            },
+        };
+        futures::future::ready(authorized.then_some(connection))
+    })
+}
+
+/// 限制 `incoming` 同时保持的连接数，`max_connections` 为 0 时不限制
+///
+/// 超出上限的新连接被直接关闭；连接断开（[`LimitedConnection`] 被释放）后名额归还。
+pub fn limit_connections<S, IO, E>(
+    incoming: S,
+    max_connections: usize,
+) -> impl Stream<Item = Result<LimitedConnection<IO>, E>>
+where
+    S: Stream<Item = Result<IO, E>>,
+{
+    let permits = Arc::new(Semaphore::new(if max_connections == 0 {
+        Semaphore::MAX_PERMITS
+    } else {
+        max_connections
+    }));
+    incoming.filter_map(move |connection| {
+        let permits = permits.clone();
+        async move {
+            let io = match connection {
+                Ok(io) => io,
+                Err(err) => return Some(Err(err)),
+            };
+            match permits.try_acquire_owned() {
+                Ok(permit) => Some(Ok(LimitedConnection {
+                    inner: io,
+                    _permit: permit,
+                })),
+                Err(_) => {
+                    log::warn!(
+                        "Rejecting gRPC connection: {} connections already open",
+                        max_connections
+                    );
+                    None
+                }
+            }
+        }
+    })
+}
+
+/// 占用一个连接名额的连接，释放时归还名额
+#[derive(Debug)]
+pub struct LimitedConnection<IO> {
+    inner: IO,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl<IO: Connected> Connected for LimitedConnection<IO> {
+    type ConnectInfo = IO::ConnectInfo;
+
+    fn connect_info(&self) -> Self::ConnectInfo {
+        self.inner.connect_info()
+    }
+}
+
+impl<IO: AsyncRead + Unpin> AsyncRead for LimitedConnection<IO> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<IO: AsyncWrite + Unpin> AsyncWrite for LimitedConnection<IO> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
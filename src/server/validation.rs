@@ -0,0 +1,290 @@
+//! CRI 配置的前置一致性校验
+//!
+//! 在创建 bundle、命名空间之前拒绝相互矛盾的配置，让本会在 runc 深处失败的请求
+//! 直接以 `InvalidArgument` 返回具体冲突。
+
+use super::*;
+
+/// 宿主机网络命名空间下 runc 拒绝设置的 sysctl 前缀
+const NET_SYSCTL_PREFIX: &str = "net.";
+/// 属于 IPC 命名空间的 sysctl，宿主机 IPC 命名空间下同样被拒绝
+const IPC_SYSCTLS: &[&str] = &[
+    "kernel.msgmax",
+    "kernel.msgmnb",
+    "kernel.msgmni",
+    "kernel.sem",
+    "kernel.shmall",
+    "kernel.shmmax",
+    "kernel.shmmni",
+    "kernel.shm_rmid_forced",
+];
+const IPC_SYSCTL_PREFIX: &str = "fs.mqueue.";
+
+fn is_node(mode: i32) -> bool {
+    mode == NamespaceMode::Node as i32
+}
+
+fn uses_user_namespace(options: Option<&NamespaceOption>) -> bool {
+    options
+        .and_then(|options| options.userns_options.as_ref())
+        .is_some_and(|userns| userns.mode == NamespaceMode::Pod as i32)
+}
+
+fn conflict(message: String) -> Status {
+    Status::invalid_argument(format!("conflicting configuration: {}", message))
+}
+
+/// 请求携带 Windows 专属配置时返回的错误
+pub(super) fn windows_unsupported(field: &str) -> Status {
+    Status::unimplemented(format!(
+        "{} is Windows-only; crius only supports Linux containers",
+        field
+    ))
+}
+
+impl RuntimeServiceImpl {
+    /// RunPodSandbox 前检查命名空间、特权、sysctl 与 DNS 配置是否自洽
+    pub(super) fn validate_pod_sandbox_spec(
+        config: &crate::proto::runtime::v1::PodSandboxConfig,
+    ) -> Result<(), Status> {
+        if config.windows.is_some() {
+            return Err(windows_unsupported("PodSandboxConfig.windows"));
+        }
+        let linux = config.linux.as_ref();
+        let security = linux.and_then(|linux| linux.security_context.as_ref());
+        let namespaces = security.and_then(|security| security.namespace_options.as_ref());
+        let privileged = security.is_some_and(|security| security.privileged);
+
+        if let Some(userns) = namespaces.and_then(|options| options.userns_options.as_ref()) {
+            let has_mappings = !userns.uids.is_empty() || !userns.gids.is_empty();
+            if userns.mode == NamespaceMode::Pod as i32 {
+                if privileged {
+                    return Err(conflict(
+                        "privileged pod sandbox cannot use a user namespace".to_string(),
+                    ));
+                }
+                if userns.uids.is_empty() || userns.gids.is_empty() {
+                    return Err(conflict(
+                        "user namespace requires both uid and gid mappings".to_string(),
+                    ));
+                }
+                let options = namespaces.expect("userns options come from namespace options");
+                for (name, mode) in [
+                    ("network", options.network),
+                    ("pid", options.pid),
+                    ("ipc", options.ipc),
+                ] {
+                    if is_node(mode) {
+                        return Err(conflict(format!(
+                            "user namespace cannot be combined with host {} namespace",
+                            name
+                        )));
+                    }
+                }
+            } else if has_mappings {
+                return Err(conflict(
+                    "uid/gid mappings require user namespace mode POD".to_string(),
+                ));
+            }
+        }
+
+        let host_network = namespaces.is_some_and(|options| is_node(options.network));
+        let host_ipc = namespaces.is_some_and(|options| is_node(options.ipc));
+        let mut sysctls: Vec<&String> = linux
+            .map(|linux| linux.sysctls.keys().collect())
+            .unwrap_or_default();
+        sysctls.sort();
+        for key in sysctls {
+            if host_network && key.starts_with(NET_SYSCTL_PREFIX) {
+                return Err(conflict(format!(
+                    "sysctl {} cannot be set with host network namespace",
+                    key
+                )));
+            }
+            if host_ipc
+                && (IPC_SYSCTLS.contains(&key.as_str()) || key.starts_with(IPC_SYSCTL_PREFIX))
+            {
+                return Err(conflict(format!(
+                    "sysctl {} cannot be set with host ipc namespace",
+                    key
+                )));
+            }
+        }
+
+        if let Some(overhead) = linux.and_then(|linux| linux.overhead.as_ref()) {
+            Self::validate_pod_overhead(overhead)?;
+        }
+
+        if let Some(dns) = config.dns_config.as_ref() {
+            for server in &dns.servers {
+                if server.trim().parse::<IpAddr>().is_err() {
+                    return Err(Status::invalid_argument(format!(
+                        "DNS server {:?} is not an IP address",
+                        server
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// RuntimeClass overhead 只能是非负的附加量，不能限制 cpuset 或写入 unified
+    pub(super) fn validate_pod_overhead(
+        overhead: &crate::proto::runtime::v1::LinuxContainerResources,
+    ) -> Result<(), Status> {
+        for (name, value) in [
+            ("cpu_period", overhead.cpu_period),
+            ("cpu_quota", overhead.cpu_quota),
+            ("cpu_shares", overhead.cpu_shares),
+            ("memory_limit_in_bytes", overhead.memory_limit_in_bytes),
+            (
+                "memory_swap_limit_in_bytes",
+                overhead.memory_swap_limit_in_bytes,
+            ),
+        ] {
+            if value < 0 {
+                return Err(Status::invalid_argument(format!(
+                    "pod overhead {} must not be negative, got {}",
+                    name, value
+                )));
+            }
+        }
+        if !overhead.cpuset_cpus.is_empty()
+            || !overhead.cpuset_mems.is_empty()
+            || !overhead.unified.is_empty()
+        {
+            return Err(Status::invalid_argument(
+                "pod overhead cannot set cpuset or unified resources",
+            ));
+        }
+        let supported = crate::cgroups::host_hugepage_sizes();
+        for limit in &overhead.hugepage_limits {
+            crate::cgroups::validate_hugepage_size(&limit.page_size, &supported)
+                .map_err(|e| Status::invalid_argument(format!("pod overhead: {}", e)))?;
+        }
+        Ok(())
+    }
+
+    /// CreateContainer 前检查容器安全上下文、挂载与所属 sandbox 是否自洽
+    ///
+    /// `sandbox_privileged` 与 `sandbox_namespaces` 描述容器所在的 sandbox，未知时为 `None`。
+    pub(super) fn validate_container_spec(
+        config: &crate::proto::runtime::v1::ContainerConfig,
+        sandbox_privileged: Option<bool>,
+        sandbox_namespaces: Option<&NamespaceOption>,
+    ) -> Result<(), Status> {
+        if config.windows.is_some() {
+            return Err(windows_unsupported("ContainerConfig.windows"));
+        }
+        let security = config
+            .linux
+            .as_ref()
+            .and_then(|linux| linux.security_context.as_ref());
+
+        if let Some(security) = security {
+            let namespaces = security.namespace_options.as_ref();
+            if security.privileged {
+                if sandbox_privileged == Some(false) {
+                    return Err(conflict(
+                        "privileged container requires a privileged pod sandbox".to_string(),
+                    ));
+                }
+                if security.no_new_privs {
+                    return Err(conflict(
+                        "privileged container cannot set no_new_privs".to_string(),
+                    ));
+                }
+                if uses_user_namespace(namespaces) || uses_user_namespace(sandbox_namespaces) {
+                    return Err(conflict(
+                        "privileged container cannot use a user namespace".to_string(),
+                    ));
+                }
+            }
+            let groups = security
+                .run_as_group
+                .iter()
+                .map(|group| group.value)
+                .chain(security.supplemental_groups.iter().copied());
+            for gid in groups {
+                if u32::try_from(gid).is_err() {
+                    return Err(Status::invalid_argument(format!(
+                        "group id {} is not a valid gid",
+                        gid
+                    )));
+                }
+            }
+            if security.run_as_user.is_some() && !security.run_as_username.is_empty() {
+                return Err(conflict(
+                    "run_as_user and run_as_username are mutually exclusive".to_string(),
+                ));
+            }
+            if let (Some(container), Some(sandbox)) = (namespaces, sandbox_namespaces) {
+                if is_node(container.network) != is_node(sandbox.network) {
+                    return Err(conflict(
+                        "container network namespace mode differs from its pod sandbox".to_string(),
+                    ));
+                }
+            }
+        }
+
+        let readonly_rootfs = security.is_some_and(|security| security.readonly_rootfs);
+        let mut destinations = HashSet::new();
+        for mount in &config.mounts {
+            let destination = Path::new(&mount.container_path);
+            if !destination.is_absolute() {
+                return Err(Status::invalid_argument(format!(
+                    "mount destination {:?} must be an absolute path",
+                    mount.container_path
+                )));
+            }
+            if readonly_rootfs && !mount.readonly && destination == Path::new("/") {
+                return Err(conflict(
+                    "readonly rootfs cannot be combined with a writable mount on /".to_string(),
+                ));
+            }
+            if !destinations.insert(destination.components().collect::<PathBuf>()) {
+                return Err(conflict(format!(
+                    "mount destination {} is specified more than once",
+                    mount.container_path
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// rootless 模式下拒绝需要宿主机特权的配置：特权、宿主机命名空间以及 CRI 用户命名空间
+    ///
+    /// `kind` 为错误信息中的对象名，如 `pod sandbox`、`container`。
+    pub(super) fn validate_rootless_security(
+        kind: &str,
+        privileged: bool,
+        namespaces: Option<&NamespaceOption>,
+    ) -> Result<(), Status> {
+        let unsupported = |what: String| {
+            Status::failed_precondition(format!("{} is not supported in rootless mode", what))
+        };
+        if privileged {
+            return Err(unsupported(format!("privileged {}", kind)));
+        }
+        if let Some(options) = namespaces {
+            for (name, mode) in [
+                ("network", options.network),
+                ("pid", options.pid),
+                ("ipc", options.ipc),
+            ] {
+                if is_node(mode) {
+                    return Err(unsupported(format!(
+                        "{} with host {} namespace",
+                        kind, name
+                    )));
+                }
+            }
+        }
+        if uses_user_namespace(namespaces) {
+            return Err(unsupported(format!("{} user namespace option", kind)));
+        }
+        Ok(())
+    }
+}
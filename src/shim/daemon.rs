@@ -27,6 +27,7 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use super::io::{IoConfig, IoManager};
+use crate::runtime::shim_manager::{START_ERROR_EXIT_CODE, START_ERROR_FILE};
 
 const INTERNAL_CONTAINER_STATE_KEY: &str = "io.crius.internal/container-state";
 
@@ -47,6 +48,10 @@ struct ShimStoredContainerState {
     tty: bool,
     stdin: bool,
     stdin_once: bool,
+    #[serde(default)]
+    log_max_size: u64,
+    #[serde(default)]
+    log_max_files: u32,
 }
 
 /// Shim守护进程
@@ -96,11 +101,18 @@ impl Daemon {
 
         // 4. 创建并运行容器
         let exit_code = if self.is_terminal()? {
-            let container_pid = self.create_terminal_container()?;
-            info!("Container created with PID: {}", container_pid);
-            self.monitor_container(container_pid)?
+            match self.create_terminal_container() {
+                Ok(container_pid) => {
+                    info!("Container created with PID: {}", container_pid);
+                    self.monitor_container(container_pid)?
+                }
+                Err(e) => self.record_start_error(&e)?,
+            }
         } else {
-            self.run_non_terminal_container()?
+            match self.run_non_terminal_container() {
+                Ok(exit_code) => exit_code,
+                Err(e) => self.record_start_error(&e)?,
+            }
         };
 
         // 6. 记录退出码
@@ -221,7 +233,11 @@ impl Daemon {
                 .as_ref()
                 .and_then(|process| process.terminal)
                 .unwrap_or(container_state.tty),
+            stdin_once: container_state.stdin_once,
             attach_socket: Some(self.shim_dir().join("attach.sock")),
+            attach_stdin_socket: container_state
+                .stdin
+                .then(|| self.shim_dir().join("attach-stdin.sock")),
             resize_socket: bundle_config
                 .process
                 .as_ref()
@@ -232,6 +248,8 @@ impl Daemon {
                 .log_path
                 .as_ref()
                 .map(|_| self.shim_dir().join("reopen.sock")),
+            log_max_size: container_state.log_max_size,
+            log_max_files: container_state.log_max_files,
         };
         self.io_manager.configure(io_config)?;
         self.io_manager.start_attach_server()?;
@@ -422,14 +440,16 @@ impl Daemon {
             let io_manager = self.io_manager.clone();
             std::thread::spawn(move || loop {
                 match io_manager.read_stdin() {
-                    Ok(data) if !data.is_empty() => {
+                    Ok(Some(data)) if !data.is_empty() => {
                         if let Err(e) = std::io::Write::write_all(&mut stdin, &data) {
                             debug!("stdin pump stopped: {}", e);
                             break;
                         }
                         let _ = std::io::Write::flush(&mut stdin);
                     }
-                    Ok(_) => std::thread::sleep(std::time::Duration::from_millis(25)),
+                    Ok(Some(_)) => std::thread::sleep(std::time::Duration::from_millis(25)),
+                    // stdin_once：退出循环即关闭管道，容器读到 EOF
+                    Ok(None) => break,
                     Err(e) => {
                         debug!("stdin pump stopped: {}", e);
                         break;
@@ -509,6 +529,15 @@ impl Daemon {
         Ok(())
     }
 
+    /// 记录容器启动失败的原因，返回对应的退出码
+    fn record_start_error(&self, err: &anyhow::Error) -> Result<i32> {
+        error!("Failed to start container {}: {:#}", self.container_id, err);
+        let path = self.shim_dir().join(START_ERROR_FILE);
+        fs::create_dir_all(self.shim_dir())?;
+        fs::write(&path, format!("{:#}", err)).context("Failed to write start error file")?;
+        Ok(START_ERROR_EXIT_CODE)
+    }
+
     /// 记录退出码
     fn record_exit_code(&self, exit_code: i32) -> Result<()> {
         if let Some(path) = &self.exit_code_file {
@@ -669,7 +698,7 @@ esac
         fs::set_permissions(&runtime_path, fs::Permissions::from_mode(0o755)).unwrap();
 
         let exit_code_file = temp_dir.path().join("shim").join("exit_code");
-        let attach_socket_path = exit_code_file.parent().unwrap().join("attach.sock");
+        let attach_socket_path = exit_code_file.parent().unwrap().join("attach-stdin.sock");
         fs::create_dir_all(exit_code_file.parent().unwrap()).unwrap();
         let mut daemon = Daemon::new(
             "test-container".to_string(),
@@ -701,4 +730,134 @@ esac
         assert!(log_content.contains("stdin:hello-from-attach"));
         assert!(log_content.contains("stderr:attached"));
     }
+
+    /// 客户端按顺序 attach，各自发送给定输入后断开，返回容器读到的两行输入
+    ///
+    /// `sessions` 中的布尔值表示客户端是否携带 stdin
+    fn run_attach_sessions(stdin_once: bool, sessions: &[(bool, &str)]) -> String {
+        let temp_dir = tempdir().unwrap();
+        let bundle_dir = temp_dir.path().join("bundle");
+        fs::create_dir_all(&bundle_dir).unwrap();
+
+        let log_path = temp_dir.path().join("logs").join("container.log");
+        let internal_state = json!({
+            "log_path": log_path.to_string_lossy(),
+            "tty": false,
+            "stdin": true,
+            "stdin_once": stdin_once,
+        });
+        let config = json!({
+            "process": {
+                "terminal": false
+            },
+            "annotations": {
+                INTERNAL_CONTAINER_STATE_KEY: internal_state.to_string()
+            }
+        });
+        fs::write(
+            bundle_dir.join("config.json"),
+            serde_json::to_vec(&config).unwrap(),
+        )
+        .unwrap();
+
+        let runtime_path = temp_dir.path().join("fake-runtime.sh");
+        fs::write(
+            &runtime_path,
+            r#"#!/bin/sh
+set -eu
+
+cmd="$1"
+shift || true
+
+case "$cmd" in
+  run)
+    IFS= read first || first=eof
+    IFS= read second || second=eof
+    echo "first:$first second:$second"
+    ;;
+  delete)
+    exit 0
+    ;;
+  *)
+    exit 1
+    ;;
+esac
+"#,
+        )
+        .unwrap();
+        fs::set_permissions(&runtime_path, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let exit_code_file = temp_dir.path().join("shim").join("exit_code");
+        fs::create_dir_all(exit_code_file.parent().unwrap()).unwrap();
+        let mut daemon = Daemon::new(
+            "test-container".to_string(),
+            bundle_dir.clone(),
+            runtime_path,
+            Some(exit_code_file),
+        );
+        let container_state = daemon
+            .load_container_state(&daemon.load_bundle_config().unwrap())
+            .unwrap();
+        daemon
+            .io_manager
+            .configure(IoConfig {
+                stdout: Some(log_path.clone()),
+                stdin_once: container_state.stdin_once,
+                ..Default::default()
+            })
+            .unwrap();
+
+        let mut clients = Vec::new();
+        for (stdin, input) in sessions {
+            let (mut client, server) = UnixStream::pair().unwrap();
+            daemon.io_manager.attach_client(server, *stdin);
+            client.write_all(input.as_bytes()).unwrap();
+            client.shutdown(Shutdown::Write).unwrap();
+            clients.push(client);
+        }
+
+        let exit_code = daemon.run_non_terminal_container().unwrap();
+        assert_eq!(exit_code, 0);
+        fs::read_to_string(&log_path).unwrap()
+    }
+
+    #[test]
+    fn test_stdin_once_closes_stdin_after_first_attach_detaches() {
+        let log_content = run_attach_sessions(true, &[(true, "one\n"), (true, "two\n")]);
+        assert!(
+            log_content.contains("first:one second:eof"),
+            "{}",
+            log_content
+        );
+    }
+
+    #[test]
+    fn test_stdin_stays_open_for_reattach_without_stdin_once() {
+        let log_content = run_attach_sessions(false, &[(true, "one\n"), (true, "two\n")]);
+        assert!(
+            log_content.contains("first:one second:two"),
+            "{}",
+            log_content
+        );
+    }
+
+    #[test]
+    fn test_stdin_once_closes_stdin_after_first_attach_without_input() {
+        let log_content = run_attach_sessions(true, &[(true, ""), (true, "two\n")]);
+        assert!(
+            log_content.contains("first:eof second:eof"),
+            "{}",
+            log_content
+        );
+    }
+
+    #[test]
+    fn test_output_only_attach_does_not_consume_stdin_once() {
+        let log_content = run_attach_sessions(true, &[(false, ""), (true, "one\n")]);
+        assert!(
+            log_content.contains("first:one second:eof"),
+            "{}",
+            log_content
+        );
+    }
 }
@@ -15,6 +15,7 @@ use std::io::{BufRead, BufReader, Read, Write};
 use std::os::unix::io::AsRawFd;
 use std::os::unix::net::{UnixListener, UnixStream};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 
 const CRI_LOG_STREAM_STDOUT: &str = "stdout";
@@ -42,12 +43,20 @@ pub struct IoConfig {
     pub stderr: Option<PathBuf>,
     /// TTY模式
     pub terminal: bool,
-    /// attach socket地址
+    /// 首个 attach 会话结束后关闭容器 stdin，不再接受重新 attach 的输入
+    pub stdin_once: bool,
+    /// attach socket地址，连接到这里的客户端只接收输出
     pub attach_socket: Option<PathBuf>,
+    /// 同时转发 stdin 的 attach socket 地址
+    pub attach_stdin_socket: Option<PathBuf>,
     /// resize socket地址
     pub resize_socket: Option<PathBuf>,
     /// reopen log socket地址
     pub reopen_socket: Option<PathBuf>,
+    /// 日志文件大小上限，0 表示不轮转
+    pub log_max_size: u64,
+    /// 轮转后保留的日志文件数（含当前文件）
+    pub log_max_files: u32,
 }
 
 /// IO管理器
@@ -62,6 +71,10 @@ pub struct IoManager {
     pending_logs: Arc<Mutex<PendingLogBytes>>,
     /// 当前TTY控制台文件，用于resize
     console_file: Arc<Mutex<Option<File>>>,
+    /// 下一个attach客户端的编号
+    next_client_id: Arc<AtomicUsize>,
+    /// stdin_once 模式下容器 stdin 已关闭
+    stdin_closed: Arc<AtomicBool>,
 }
 
 /// 客户端连接
@@ -69,6 +82,10 @@ pub struct IoManager {
 struct ClientConnection {
     id: usize,
     stream: UnixStream,
+    /// 客户端通过 stdin socket 连接，其输入转发给容器
+    stdin: bool,
+    /// 客户端的 stdin 已结束（半关闭或断开）
+    stdin_done: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -181,16 +198,61 @@ impl IoManager {
             return Ok(());
         }
 
-        if let Some(file) = &mut *self.log_file.lock().unwrap() {
-            for record in records {
+        let mut log_file = self.log_file.lock().unwrap();
+        if log_file.is_none() {
+            return Ok(());
+        }
+        for record in records {
+            if self.log_needs_rotation(log_file.as_ref(), record.len())? {
+                *log_file = Some(self.rotate_log_file()?);
+            }
+            if let Some(file) = log_file.as_mut() {
                 file.write_all(record)?;
             }
+        }
+        if let Some(file) = log_file.as_mut() {
             file.flush()?;
         }
 
         Ok(())
     }
 
+    fn log_needs_rotation(&self, file: Option<&File>, incoming: usize) -> Result<bool> {
+        let max_size = self.config.log_max_size;
+        let Some(file) = file.filter(|_| max_size > 0) else {
+            return Ok(false);
+        };
+        let current = file.metadata()?.len();
+        Ok(current > 0 && current + incoming as u64 > max_size)
+    }
+
+    /// 按 `<log>.1` ... `<log>.N-1` 依次后移旧文件，并打开新的当前日志文件
+    fn rotate_log_file(&self) -> Result<File> {
+        let stdout = self
+            .config
+            .stdout
+            .as_ref()
+            .context("log rotation requires a log path")?;
+        let rotated = |index: u32| PathBuf::from(format!("{}.{}", stdout.display(), index));
+        let keep = self.config.log_max_files.max(1) - 1;
+        if keep == 0 {
+            std::fs::remove_file(stdout)
+                .with_context(|| format!("Failed to remove log file {}", stdout.display()))?;
+        } else {
+            let _ = std::fs::remove_file(rotated(keep));
+            for index in (1..keep).rev() {
+                let from = rotated(index);
+                if from.exists() {
+                    std::fs::rename(&from, rotated(index + 1))?;
+                }
+            }
+            std::fs::rename(stdout, rotated(1))
+                .with_context(|| format!("Failed to rotate log file {}", stdout.display()))?;
+        }
+        debug!("Rotated log file {:?}", stdout);
+        Self::open_output_file(stdout)
+    }
+
     fn remove_clients(&self, disconnected_ids: &[usize]) {
         if disconnected_ids.is_empty() {
             return;
@@ -232,15 +294,39 @@ impl IoManager {
         self.broadcast(&frame)
     }
 
-    fn first_client_stream(&self) -> Option<(usize, UnixStream)> {
+    fn first_stdin_client(&self) -> Option<(usize, UnixStream)> {
         let clients = self.clients.lock().unwrap();
-        clients.iter().find_map(|client| {
-            client
-                .stream
-                .try_clone()
-                .ok()
-                .map(|stream| (client.id, stream))
-        })
+        clients
+            .iter()
+            .filter(|client| client.stdin && !client.stdin_done)
+            .find_map(|client| {
+                client
+                    .stream
+                    .try_clone()
+                    .ok()
+                    .map(|stream| (client.id, stream))
+            })
+    }
+
+    /// 标记客户端的 stdin 已结束
+    fn finish_client_stdin(&self, id: usize) {
+        let mut clients = self.clients.lock().unwrap();
+        if let Some(client) = clients.iter_mut().find(|client| client.id == id) {
+            client.stdin_done = true;
+        }
+    }
+
+    /// 注册一个attach客户端，`stdin` 为 true 时转发它的输入
+    pub fn attach_client(&self, stream: UnixStream, stdin: bool) {
+        let id = self.next_client_id.fetch_add(1, Ordering::SeqCst);
+        debug!("New attach client connected: {} (stdin={})", id, stdin);
+        let mut clients = self.clients.lock().unwrap();
+        clients.push(ClientConnection {
+            id,
+            stream,
+            stdin,
+            stdin_done: false,
+        });
     }
 
     /// 创建新的IO管理器
@@ -251,6 +337,8 @@ impl IoManager {
             log_file: Arc::new(Mutex::new(None)),
             pending_logs: Arc::new(Mutex::new(PendingLogBytes::default())),
             console_file: Arc::new(Mutex::new(None)),
+            next_client_id: Arc::new(AtomicUsize::new(0)),
+            stdin_closed: Arc::new(AtomicBool::new(false)),
         }
     }
 
@@ -272,23 +360,26 @@ impl IoManager {
 
     /// 启动attach服务器
     pub fn start_attach_server(&mut self) -> Result<()> {
-        if let Some(socket) = &self.config.attach_socket {
+        let sockets = [
+            (self.config.attach_socket.clone(), false),
+            (self.config.attach_stdin_socket.clone(), true),
+        ];
+        for (socket, stdin) in sockets {
+            let Some(socket) = socket else {
+                continue;
+            };
             // 删除已存在的socket文件
-            let _ = std::fs::remove_file(socket);
+            let _ = std::fs::remove_file(&socket);
 
-            let listener = UnixListener::bind(socket)?;
-            info!("Attach server listening on {:?}", socket);
+            let listener = UnixListener::bind(&socket)?;
+            info!("Attach server listening on {:?} (stdin={})", socket, stdin);
 
-            let clients = self.clients.clone();
+            let io_manager = self.clone();
 
             std::thread::spawn(move || {
-                for (id, stream) in listener.incoming().enumerate() {
+                for stream in listener.incoming() {
                     match stream {
-                        Ok(stream) => {
-                            debug!("New attach client connected: {}", id);
-                            let mut clients = clients.lock().unwrap();
-                            clients.push(ClientConnection { id, stream });
-                        }
+                        Ok(stream) => io_manager.attach_client(stream, stdin),
                         Err(e) => {
                             error!("Failed to accept client: {}", e);
                         }
@@ -493,20 +584,37 @@ impl IoManager {
         self.broadcast_output(ATTACH_PIPE_STDERR, data)
     }
 
-    /// 读取stdin
-    pub fn read_stdin(&self) -> Result<Vec<u8>> {
-        if let Some((id, mut stream)) = self.first_client_stream() {
-            let mut buffer = [0u8; 1024];
-            match stream.read(&mut buffer) {
-                Ok(n) if n > 0 => return Ok(buffer[..n].to_vec()),
-                Ok(_) => {}
-                Err(e) => {
-                    debug!("Client {} disconnected: {}", id, e);
-                    self.remove_clients(&[id]);
-                }
+    /// 读取stdin，返回 `None` 表示 stdin 已按 stdin_once 关闭
+    ///
+    /// 客户端的 stdin 结束后改为读取下一个客户端；stdin_once 模式下第一个携带 stdin
+    /// 的会话一旦结束（无论是否发送过数据），容器 stdin 随之关闭，之后 attach 的客户端
+    /// 只能接收输出
+    pub fn read_stdin(&self) -> Result<Option<Vec<u8>>> {
+        if self.stdin_closed.load(Ordering::SeqCst) {
+            return Ok(None);
+        }
+        let Some((id, mut stream)) = self.first_stdin_client() else {
+            return Ok(Some(Vec::new()));
+        };
+        let mut buffer = [0u8; 1024];
+        match stream.read(&mut buffer) {
+            Ok(n) if n > 0 => return Ok(Some(buffer[..n].to_vec())),
+            Ok(_) => {
+                debug!("Client {} closed stdin", id);
+                self.finish_client_stdin(id);
             }
+            Err(e) => {
+                debug!("Client {} disconnected: {}", id, e);
+                self.finish_client_stdin(id);
+                self.remove_clients(&[id]);
+            }
+        }
+        if self.config.stdin_once {
+            info!("Closing container stdin after first attach session (stdin_once)");
+            self.stdin_closed.store(true, Ordering::SeqCst);
+            return Ok(None);
         }
-        Ok(Vec::new())
+        Ok(Some(Vec::new()))
     }
 
     /// 启动控制台转发
@@ -546,16 +654,22 @@ impl IoManager {
             let mut writer = writer;
             loop {
                 match io_for_input.read_stdin() {
-                    Ok(data) if !data.is_empty() => {
+                    Ok(Some(data)) if !data.is_empty() => {
                         if let Err(e) = writer.write_all(&data) {
                             debug!("Console input pump stopped: {}", e);
                             break;
                         }
                         let _ = writer.flush();
                     }
-                    Ok(_) => {
+                    Ok(Some(_)) => {
                         std::thread::sleep(std::time::Duration::from_millis(25));
                     }
+                    // TTY 无法单独关闭输入端，发送 EOF 控制字符（Ctrl-D）
+                    Ok(None) => {
+                        let _ = writer.write_all(&[0x04]);
+                        let _ = writer.flush();
+                        break;
+                    }
                     Err(e) => {
                         debug!("Console input pump stopped: {}", e);
                         break;
@@ -818,6 +932,39 @@ mod tests {
         assert_eq!(records[1].3, "after");
     }
 
+    #[test]
+    fn test_write_stdout_rotates_log_at_configured_size() {
+        let temp_dir = tempdir().unwrap();
+        let log_path = temp_dir.path().join("stdout.log");
+        let mut manager = IoManager::new();
+        manager
+            .configure(IoConfig {
+                stdout: Some(log_path.clone()),
+                log_max_size: 80,
+                log_max_files: 3,
+                ..Default::default()
+            })
+            .unwrap();
+
+        for line in ["one", "two", "three", "four"] {
+            manager
+                .write_stdout(format!("{}\n", line).as_bytes())
+                .unwrap();
+        }
+
+        let read = |path: PathBuf| {
+            parse_cri_log_lines(&std::fs::read_to_string(path).unwrap())
+                .into_iter()
+                .map(|record| record.3)
+                .collect::<Vec<_>>()
+        };
+        let rotated = |index: u32| PathBuf::from(format!("{}.{}", log_path.display(), index));
+        assert_eq!(read(log_path.clone()), vec!["four"]);
+        assert_eq!(read(rotated(1)), vec!["three"]);
+        assert_eq!(read(rotated(2)), vec!["two"]);
+        assert!(!rotated(3).exists());
+    }
+
     #[test]
     fn test_apply_terminal_resize_requires_console() {
         let manager = IoManager::new();
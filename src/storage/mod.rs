@@ -7,7 +7,11 @@ use log::{debug, info};
 use rusqlite::{Connection, OptionalExtension};
 use std::path::Path;
 
+pub mod mount;
+pub mod overlay;
+pub mod ownership;
 pub mod volume;
+pub use mount::{resolve_subpath, MountManager, StagedMounts};
 pub use volume::{MountedVolume, VolumeConfig, VolumeManager, VolumeType};
 
 /// 存储管理器
@@ -150,12 +154,17 @@ impl StorageManager {
         Ok(())
     }
 
-    /// 保存容器记录
+    /// 保存容器记录（已存在时保留首次写入的 created_at）
     pub fn save_container(&mut self, record: &ContainerRecord) -> Result<()> {
         self.conn.execute(
-            "INSERT OR REPLACE INTO containers 
+            "INSERT INTO containers 
              (id, pod_id, state, image, command, created_at, labels, annotations, exit_code, exit_time)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+             ON CONFLICT(id) DO UPDATE SET
+                pod_id = excluded.pod_id, state = excluded.state, image = excluded.image,
+                command = excluded.command, labels = excluded.labels,
+                annotations = excluded.annotations, exit_code = excluded.exit_code,
+                exit_time = excluded.exit_time",
             rusqlite::params![
                 &record.id,
                 &record.pod_id,
@@ -195,7 +204,7 @@ impl StorageManager {
             .optional()?;
 
         let exit_time = if exit_code.is_some() {
-            Some(chrono::Utc::now().timestamp())
+            Some(crate::utils::now_nanos())
         } else {
             None
         };
@@ -312,12 +321,17 @@ impl StorageManager {
         Ok(())
     }
 
-    /// 保存Pod沙箱记录
+    /// 保存Pod沙箱记录（已存在时保留首次写入的 created_at）
     pub fn save_pod_sandbox(&mut self, record: &PodSandboxRecord) -> Result<()> {
         self.conn.execute(
-            "INSERT OR REPLACE INTO pod_sandboxes 
+            "INSERT INTO pod_sandboxes 
              (id, state, name, namespace, uid, created_at, netns_path, labels, annotations, pause_container_id, ip)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+             ON CONFLICT(id) DO UPDATE SET
+                state = excluded.state, name = excluded.name, namespace = excluded.namespace,
+                uid = excluded.uid, netns_path = excluded.netns_path, labels = excluded.labels,
+                annotations = excluded.annotations,
+                pause_container_id = excluded.pause_container_id, ip = excluded.ip",
             rusqlite::params![
                 &record.id,
                 &record.state,
@@ -620,4 +634,50 @@ mod tests {
         let deleted = manager.get_pod_sandbox("pod-1").unwrap();
         assert!(deleted.is_none());
     }
+
+    #[test]
+    fn test_resave_preserves_created_at() {
+        let temp_dir = tempdir().unwrap();
+        let mut manager = StorageManager::new(temp_dir.path().join("test.db")).unwrap();
+        let labels = std::collections::HashMap::new();
+
+        let first = persistence::container_to_record(
+            "container-1",
+            "pod-1",
+            crate::runtime::ContainerStatus::Created,
+            "test:latest",
+            &[],
+            &labels,
+            &labels,
+        );
+        manager.save_container(&first).unwrap();
+        let second = persistence::container_to_record(
+            "container-1",
+            "pod-1",
+            crate::runtime::ContainerStatus::Stopped(0),
+            "test:latest",
+            &[],
+            &labels,
+            &labels,
+        );
+        assert!(second.created_at > first.created_at);
+        manager.save_container(&second).unwrap();
+
+        let stored = manager.get_container("container-1").unwrap().unwrap();
+        assert_eq!(stored.state, "stopped");
+        assert_eq!(stored.created_at, first.created_at);
+        assert!(stored.exit_time.unwrap() > stored.created_at);
+
+        let pod = persistence::pod_to_record(
+            "pod-1", "ready", "pod", "default", "uid", "", &labels, &labels, None, None,
+        );
+        manager.save_pod_sandbox(&pod).unwrap();
+        let resaved = persistence::pod_to_record(
+            "pod-1", "notready", "pod", "default", "uid", "", &labels, &labels, None, None,
+        );
+        manager.save_pod_sandbox(&resaved).unwrap();
+        let stored = manager.get_pod_sandbox("pod-1").unwrap().unwrap();
+        assert_eq!(stored.state, "notready");
+        assert_eq!(stored.created_at, pod.created_at);
+    }
 }
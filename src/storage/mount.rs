@@ -0,0 +1,566 @@
+//! 容器挂载管理模块
+//!
+//! 负责 subPath 与共享卷的安全挂载：
+//! - 在卷根目录内逐级解析 subPath，拒绝经由符号链接逃逸
+//! - 挂载前从卷根目录 fd 出发以 `O_PATH|O_NOFOLLOW` 逐级重新打开，
+//!   bind `/proc/self/fd/N` 而非路径，挂载后再核对 inode，避免解析与挂载之间被替换
+//! - 将解析结果 bind 到容器专属的暂存目录，再交给 runc 挂入 rootfs
+//! - 容器删除时卸载并清理暂存目录
+
+use anyhow::{Context, Result};
+use log::{debug, warn};
+use nix::errno::Errno;
+use nix::fcntl::{open, openat, OFlag};
+use nix::mount::{mount, umount2, MntFlags, MsFlags};
+use nix::sys::stat::{fstat, mkdirat, stat, FileStat, Mode, SFlag};
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fs;
+use std::io::ErrorKind;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::path::{Component, Path, PathBuf};
+
+/// 拆分 subPath，拒绝 `..` 与绝对路径
+fn subpath_components(sub_path: &str) -> Result<Vec<&OsStr>> {
+    let mut names = Vec::new();
+    for component in Path::new(sub_path).components() {
+        match component {
+            Component::Normal(name) => names.push(name),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                anyhow::bail!("subPath {:?} must not contain '..'", sub_path)
+            }
+            Component::RootDir | Component::Prefix(_) => {
+                anyhow::bail!("subPath {:?} must be relative", sub_path)
+            }
+        }
+    }
+    Ok(names)
+}
+
+/// 在卷根目录内解析 subPath，返回宿主机上的真实路径
+///
+/// 只做校验，不修改宿主机：尚不存在的部分按字面拼接，留到挂载时再创建。
+/// `..`、绝对路径以及指向卷外的符号链接都会被拒绝。
+pub fn resolve_subpath(volume_root: &Path, sub_path: &str) -> Result<PathBuf> {
+    let root = fs::canonicalize(volume_root)
+        .with_context(|| format!("Failed to resolve volume root {:?}", volume_root))?;
+    resolve_within(&root, volume_root, sub_path)
+}
+
+fn resolve_within(root: &Path, volume_root: &Path, sub_path: &str) -> Result<PathBuf> {
+    let mut names = subpath_components(sub_path)?.into_iter();
+    let mut current = root.to_path_buf();
+    for name in names.by_ref() {
+        let next = current.join(name);
+        match fs::symlink_metadata(&next) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                let resolved = fs::canonicalize(&next).with_context(|| {
+                    format!("subPath {:?} contains unresolvable symlink", sub_path)
+                })?;
+                if !resolved.starts_with(root) {
+                    anyhow::bail!(
+                        "subPath {:?} escapes volume root {:?} via symlink",
+                        sub_path,
+                        volume_root
+                    );
+                }
+                current = resolved;
+            }
+            Ok(_) => current = next,
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                current = next;
+                break;
+            }
+            Err(err) => {
+                return Err(err).with_context(|| format!("Failed to inspect subPath {:?}", next))
+            }
+        }
+    }
+    // 缺失的一层之后不可能再有已存在的路径，剩余部分直接拼接
+    current.extend(names);
+    Ok(current)
+}
+
+fn is_file_type(stat: &FileStat, kind: SFlag) -> bool {
+    SFlag::from_bits_truncate(stat.st_mode) & SFlag::S_IFMT == kind
+}
+
+/// 从卷根目录 fd 出发，以 `O_PATH|O_NOFOLLOW` 逐级打开 `relative`
+///
+/// 校验之后任何一层被替换为符号链接都会失败；`create` 为真时按层创建缺失的目录。
+fn open_beneath(root: &Path, relative: &Path, create: bool) -> Result<OwnedFd> {
+    let flags = OFlag::O_PATH | OFlag::O_NOFOLLOW | OFlag::O_CLOEXEC;
+    let fd = open(root, flags | OFlag::O_DIRECTORY, Mode::empty())
+        .with_context(|| format!("Failed to open volume root {:?}", root))?;
+    let mut current = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    for component in relative.components() {
+        let Component::Normal(name) = component else {
+            anyhow::bail!("Unexpected component in resolved subPath {:?}", relative);
+        };
+        let fd = match openat(current.as_raw_fd(), name, flags, Mode::empty()) {
+            Err(Errno::ENOENT) if create => {
+                match mkdirat(current.as_raw_fd(), name, Mode::from_bits_truncate(0o755)) {
+                    Ok(()) | Err(Errno::EEXIST) => {}
+                    Err(err) => {
+                        return Err(err).with_context(|| {
+                            format!("Failed to create subPath directory {:?}", name)
+                        })
+                    }
+                }
+                openat(current.as_raw_fd(), name, flags, Mode::empty())
+            }
+            result => result,
+        }
+        .with_context(|| format!("Failed to open {:?} under {:?}", name, root))?;
+        let next = unsafe { OwnedFd::from_raw_fd(fd) };
+        if is_file_type(&fstat(next.as_raw_fd())?, SFlag::S_IFLNK) {
+            anyhow::bail!(
+                "subPath component {:?} under {:?} became a symlink",
+                name,
+                root
+            );
+        }
+        current = next;
+    }
+    Ok(current)
+}
+
+/// 挂载管理器
+///
+/// 每个容器在 `root_dir/<container_id>` 下拥有独立的暂存挂载点，
+/// 因此多个容器共享同一个卷时互不影响彼此的清理。
+#[derive(Debug, Clone)]
+pub struct MountManager {
+    /// 暂存挂载点根目录
+    root_dir: PathBuf,
+}
+
+impl MountManager {
+    /// 创建新的挂载管理器
+    pub fn new(root_dir: impl AsRef<Path>) -> Self {
+        Self {
+            root_dir: root_dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// 获取暂存挂载点根目录
+    pub fn root_dir(&self) -> &Path {
+        &self.root_dir
+    }
+
+    fn container_dir(&self, container_id: &str) -> PathBuf {
+        self.root_dir.join(container_id)
+    }
+
+    /// 解析 subPath 并 bind 到容器暂存目录，返回可交给 OCI spec 的挂载源
+    pub fn bind_subpath(
+        &self,
+        container_id: &str,
+        index: usize,
+        volume_root: &Path,
+        sub_path: &str,
+        read_only: bool,
+    ) -> Result<PathBuf> {
+        let root = fs::canonicalize(volume_root)
+            .with_context(|| format!("Failed to resolve volume root {:?}", volume_root))?;
+        let source = resolve_within(&root, volume_root, sub_path)?;
+        let relative = source
+            .strip_prefix(&root)
+            .with_context(|| format!("subPath {:?} resolved outside {:?}", sub_path, root))?;
+        let source_fd = open_beneath(&root, relative, true)?;
+        let source_stat = fstat(source_fd.as_raw_fd())?;
+
+        let target = self.container_dir(container_id).join(index.to_string());
+        fs::create_dir_all(self.container_dir(container_id))?;
+        if is_file_type(&source_stat, SFlag::S_IFDIR) {
+            fs::create_dir_all(&target)?;
+        } else if !target.exists() {
+            fs::File::create(&target)?;
+        }
+
+        // bind 已打开的 fd 而不是路径，挂载的必然是刚才逐级校验过的 inode
+        let fd_path = PathBuf::from(format!("/proc/self/fd/{}", source_fd.as_raw_fd()));
+        mount(
+            Some(&fd_path),
+            &target,
+            None::<&str>,
+            MsFlags::MS_BIND | MsFlags::MS_REC,
+            None::<&str>,
+        )
+        .with_context(|| format!("Failed to bind mount {:?} to {:?}", source, target))?;
+        let mounted = stat(&target).with_context(|| format!("Failed to stat {:?}", target))?;
+        if mounted.st_dev != source_stat.st_dev || mounted.st_ino != source_stat.st_ino {
+            if let Err(err) = umount2(&target, MntFlags::MNT_DETACH) {
+                warn!("Failed to unmount mismatched mount {:?}: {}", target, err);
+            }
+            anyhow::bail!(
+                "subPath {:?} changed while being mounted to {:?}",
+                sub_path,
+                target
+            );
+        }
+        if read_only {
+            // MS_REMOUNT 只作用于单个挂载点，递归 bind 带入的子挂载需要逐个重新挂载为只读
+            let mut submounts: Vec<PathBuf> = current_mount_points()?
+                .into_iter()
+                .filter(|path| path.starts_with(&target))
+                .collect();
+            submounts.sort();
+            submounts.dedup();
+            if submounts.is_empty() {
+                submounts.push(target.clone());
+            }
+            for path in &submounts {
+                mount(
+                    None::<&str>,
+                    path,
+                    None::<&str>,
+                    MsFlags::MS_REMOUNT | MsFlags::MS_BIND | MsFlags::MS_REC | MsFlags::MS_RDONLY,
+                    None::<&str>,
+                )
+                .with_context(|| format!("Failed to remount {:?} as readonly", path))?;
+            }
+        }
+
+        debug!(
+            "Staged subPath {:?} of {:?} for container {} at {:?}",
+            sub_path, volume_root, container_id, target
+        );
+        Ok(target)
+    }
+
+    /// 返回在 drop 时清理容器暂存挂载点的守卫，创建成功后调用 [`StagedMounts::keep`] 保留
+    pub fn stage(&self, container_id: &str) -> StagedMounts {
+        StagedMounts {
+            manager: self.clone(),
+            container_id: container_id.to_string(),
+            keep: false,
+        }
+    }
+
+    /// 卸载并删除容器的全部暂存挂载点
+    pub fn cleanup_container(&self, container_id: &str) -> Result<()> {
+        let dir = self.container_dir(container_id);
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err).with_context(|| format!("Failed to read {:?}", dir)),
+        };
+        let mount_points = current_mount_points()?;
+
+        for entry in entries {
+            let path = entry?.path();
+            if mount_points.contains(&path) {
+                if let Err(err) = umount2(&path, MntFlags::MNT_DETACH) {
+                    // 仍处于挂载状态时不能删除，以免误删卷内数据
+                    warn!("Failed to unmount staged mount {:?}: {}", path, err);
+                    continue;
+                }
+            }
+            // 只删除空目录或占位文件，绝不递归删除
+            let removed = if path.is_dir() {
+                fs::remove_dir(&path)
+            } else {
+                fs::remove_file(&path)
+            };
+            if let Err(err) = removed {
+                warn!("Failed to remove staged mount {:?}: {}", path, err);
+            }
+        }
+
+        fs::remove_dir(&dir).with_context(|| format!("Failed to remove {:?}", dir))?;
+        debug!("Cleaned up staged mounts for container {}", container_id);
+        Ok(())
+    }
+}
+
+/// 容器创建过程中已暂存的 subPath 挂载
+///
+/// 创建在登记容器前的任一步失败（包括请求被取消）时，drop 会卸载并删除暂存目录。
+#[derive(Debug)]
+pub struct StagedMounts {
+    manager: MountManager,
+    container_id: String,
+    keep: bool,
+}
+
+impl StagedMounts {
+    /// 容器已登记，暂存挂载改由 RemoveContainer 清理
+    pub fn keep(mut self) {
+        self.keep = true;
+    }
+}
+
+impl Drop for StagedMounts {
+    fn drop(&mut self) {
+        if self.keep {
+            return;
+        }
+        if let Err(err) = self.manager.cleanup_container(&self.container_id) {
+            warn!(
+                "Failed to clean up staged mounts of {}: {:#}",
+                self.container_id, err
+            );
+        }
+    }
+}
+
+/// 读取当前 mount namespace 中的挂载点
+fn current_mount_points() -> Result<HashSet<PathBuf>> {
+    let content = fs::read_to_string("/proc/self/mountinfo")
+        .context("Failed to read /proc/self/mountinfo")?;
+    Ok(content
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(4))
+        .map(|field| PathBuf::from(unescape_mountinfo(field)))
+        .collect())
+}
+
+/// mountinfo 中空白等字符以 `\ooo` 八进制转义
+fn unescape_mountinfo(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes[i] == b'\\'
+            && i + 3 < bytes.len()
+            && (b'0'..=b'3').contains(&bytes[i + 1])
+            && bytes[i + 2..i + 4]
+                .iter()
+                .all(|b| (b'0'..=b'7').contains(b));
+        if escaped {
+            out.push(
+                (bytes[i + 1] - b'0') * 64 + (bytes[i + 2] - b'0') * 8 + (bytes[i + 3] - b'0'),
+            );
+            i += 4;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_resolve_subpath_within_volume() {
+        let temp_dir = tempdir().unwrap();
+        let volume = temp_dir.path().join("volume");
+        fs::create_dir_all(volume.join("data/app")).unwrap();
+        symlink("data", volume.join("alias")).unwrap();
+
+        let root = fs::canonicalize(&volume).unwrap();
+        assert_eq!(
+            resolve_subpath(&volume, "data/app").unwrap(),
+            root.join("data/app")
+        );
+        assert_eq!(
+            resolve_subpath(&volume, "./alias/app").unwrap(),
+            root.join("data/app")
+        );
+        assert_eq!(
+            resolve_subpath(&volume, "logs/new").unwrap(),
+            root.join("logs/new")
+        );
+        // 校验阶段不得修改宿主机
+        assert!(!root.join("logs").exists());
+    }
+
+    #[test]
+    fn test_open_beneath_rejects_component_swapped_for_symlink() {
+        let temp_dir = tempdir().unwrap();
+        let volume = temp_dir.path().join("volume");
+        let outside = temp_dir.path().join("outside");
+        fs::create_dir_all(volume.join("data/app")).unwrap();
+        fs::create_dir_all(outside.join("app")).unwrap();
+        let root = fs::canonicalize(&volume).unwrap();
+
+        let source = resolve_subpath(&volume, "data/app").unwrap();
+        let relative = source.strip_prefix(&root).unwrap();
+        assert!(open_beneath(&root, relative, false).is_ok());
+
+        // 解析之后、挂载之前把中间目录换成指向卷外的符号链接
+        fs::remove_dir_all(volume.join("data")).unwrap();
+        symlink(&outside, volume.join("data")).unwrap();
+        assert!(open_beneath(&root, relative, false).is_err());
+        assert!(open_beneath(&root, relative, true).is_err());
+    }
+
+    #[test]
+    fn test_open_beneath_creates_missing_directories() {
+        let temp_dir = tempdir().unwrap();
+        let volume = temp_dir.path().join("volume");
+        fs::create_dir_all(&volume).unwrap();
+        let root = fs::canonicalize(&volume).unwrap();
+
+        assert!(open_beneath(&root, Path::new("logs/new"), false).is_err());
+        let fd = open_beneath(&root, Path::new("logs/new"), true).unwrap();
+        assert!(is_file_type(
+            &fstat(fd.as_raw_fd()).unwrap(),
+            SFlag::S_IFDIR
+        ));
+        assert!(root.join("logs/new").is_dir());
+    }
+
+    #[test]
+    fn test_bind_subpath_mounts_resolved_inode() {
+        // bind mount 需要 CAP_SYS_ADMIN
+        if !nix::unistd::geteuid().is_root() {
+            return;
+        }
+        let temp_dir = tempdir().unwrap();
+        let volume = temp_dir.path().join("volume");
+        fs::create_dir_all(&volume).unwrap();
+        let manager = MountManager::new(temp_dir.path().join("mounts"));
+
+        let target = match manager.bind_subpath("ctr-1", 0, &volume, "logs/new", false) {
+            Ok(target) => target,
+            // 受限容器内即使是 root 也可能没有挂载权限
+            Err(err) if format!("{:#}", err).contains("EPERM") => return,
+            Err(err) => panic!("bind_subpath failed: {:#}", err),
+        };
+        fs::write(target.join("marker"), "ok").unwrap();
+        assert_eq!(
+            fs::read_to_string(volume.join("logs/new/marker")).unwrap(),
+            "ok"
+        );
+
+        manager.cleanup_container("ctr-1").unwrap();
+        assert!(!target.exists());
+        assert!(volume.join("logs/new/marker").exists());
+    }
+
+    #[test]
+    fn test_resolve_subpath_rejects_traversal() {
+        let temp_dir = tempdir().unwrap();
+        let volume = temp_dir.path().join("volume");
+        fs::create_dir_all(&volume).unwrap();
+
+        assert!(resolve_subpath(&volume, "../outside").is_err());
+        assert!(resolve_subpath(&volume, "data/../../outside").is_err());
+        assert!(resolve_subpath(&volume, "/etc").is_err());
+        assert!(!volume.join("data").exists());
+    }
+
+    #[test]
+    fn test_resolve_subpath_rejects_malicious_symlinks() {
+        let temp_dir = tempdir().unwrap();
+        let volume = temp_dir.path().join("volume");
+        let outside = temp_dir.path().join("outside");
+        fs::create_dir_all(volume.join("nested")).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(outside.join("secret"), "secret").unwrap();
+
+        symlink("/", volume.join("host-root")).unwrap();
+        symlink("../outside", volume.join("relative-escape")).unwrap();
+        symlink("../../outside", volume.join("nested/deep-escape")).unwrap();
+        symlink(&outside, volume.join("chain-target")).unwrap();
+        symlink("chain-target", volume.join("chain")).unwrap();
+        symlink("missing", volume.join("dangling")).unwrap();
+
+        for sub_path in [
+            "host-root",
+            "host-root/etc",
+            "relative-escape/secret",
+            "nested/deep-escape",
+            "chain",
+            "dangling/child",
+        ] {
+            assert!(
+                resolve_subpath(&volume, sub_path).is_err(),
+                "subPath {} should be rejected",
+                sub_path
+            );
+        }
+        assert!(!outside.join("child").exists());
+    }
+
+    #[test]
+    fn test_bind_subpath_readonly_covers_nested_mounts() {
+        if !nix::unistd::geteuid().is_root() {
+            return;
+        }
+        let temp_dir = tempdir().unwrap();
+        let volume = temp_dir.path().join("volume");
+        let nested_source = temp_dir.path().join("nested");
+        fs::create_dir_all(volume.join("data/nested")).unwrap();
+        fs::create_dir_all(&nested_source).unwrap();
+        if mount(
+            Some(&nested_source),
+            &volume.join("data/nested"),
+            None::<&str>,
+            MsFlags::MS_BIND,
+            None::<&str>,
+        )
+        .is_err()
+        {
+            return;
+        }
+        let manager = MountManager::new(temp_dir.path().join("mounts"));
+
+        let result = manager.bind_subpath("ctr-1", 0, &volume, "data", true);
+        let target = match result {
+            Ok(target) => target,
+            Err(err) => {
+                let _ = umount2(&volume.join("data/nested"), MntFlags::MNT_DETACH);
+                panic!("bind_subpath failed: {:#}", err);
+            }
+        };
+        let nested_write = fs::write(target.join("nested/marker"), "x");
+        let top_write = fs::write(target.join("marker"), "x");
+
+        manager.cleanup_container("ctr-1").unwrap();
+        umount2(&volume.join("data/nested"), MntFlags::MNT_DETACH).unwrap();
+        assert_eq!(
+            top_write.unwrap_err().raw_os_error(),
+            Some(Errno::EROFS as i32)
+        );
+        assert_eq!(
+            nested_write.unwrap_err().raw_os_error(),
+            Some(Errno::EROFS as i32)
+        );
+        assert!(!nested_source.join("marker").exists());
+    }
+
+    #[test]
+    fn test_staged_mounts_cleanup_unless_kept() {
+        let temp_dir = tempdir().unwrap();
+        let manager = MountManager::new(temp_dir.path().join("mounts"));
+        let staging = manager.root_dir().join("ctr-1");
+
+        fs::create_dir_all(staging.join("0")).unwrap();
+        drop(manager.stage("ctr-1"));
+        assert!(!staging.exists());
+
+        fs::create_dir_all(staging.join("0")).unwrap();
+        manager.stage("ctr-1").keep();
+        assert!(staging.join("0").exists());
+    }
+
+    #[test]
+    fn test_cleanup_container_removes_unmounted_staging() {
+        let temp_dir = tempdir().unwrap();
+        let manager = MountManager::new(temp_dir.path().join("mounts"));
+        let staging = manager.root_dir().join("ctr-1");
+        fs::create_dir_all(staging.join("0")).unwrap();
+        fs::write(staging.join("1"), "").unwrap();
+
+        manager.cleanup_container("ctr-1").unwrap();
+        assert!(!staging.exists());
+        manager.cleanup_container("ctr-1").unwrap();
+    }
+
+    #[test]
+    fn test_unescape_mountinfo() {
+        assert_eq!(unescape_mountinfo("/var/lib/a\\040b"), "/var/lib/a b");
+        assert_eq!(unescape_mountinfo("/plain"), "/plain");
+        assert_eq!(unescape_mountinfo("/trailing\\04"), "/trailing\\04");
+    }
+}
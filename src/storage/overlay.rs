@@ -0,0 +1,468 @@
+//! overlay 存储后端探测
+//!
+//! 启动时在镜像存储目录下做一次探测挂载，确认内核是否支持 overlay 以及
+//! metacopy、redirect_dir、userxattr 等选项，再按内核能力选择后端：
+//! 原生 overlay → fuse-overlayfs → 逐层解包复制。结果目前只写入启动日志，
+//! rootfs 准备仍逐层解包，不依赖这里选出的后端。
+
+use anyhow::{anyhow, Context, Result};
+use log::{debug, info, warn};
+use nix::mount::{mount, umount2, MntFlags, MsFlags};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// `[image] driver` 可选值
+pub const DRIVER_OVERLAY: &str = "overlay";
+pub const DRIVER_FUSE_OVERLAYFS: &str = "fuse-overlayfs";
+pub const DRIVER_VFS: &str = "vfs";
+
+/// 内核版本，仅比较主次版本号
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct KernelVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl KernelVersion {
+    pub const fn new(major: u32, minor: u32) -> Self {
+        Self { major, minor }
+    }
+
+    /// 解析 `uname -r` 形式的版本号，如 `5.15.0-91-generic`
+    pub fn parse(release: &str) -> Option<Self> {
+        let mut parts = release.split(|c: char| !c.is_ascii_digit());
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next()?.parse().ok()?;
+        Some(Self { major, minor })
+    }
+
+    /// 当前运行的内核版本
+    pub fn current() -> Option<Self> {
+        let uts = nix::sys::utsname::uname().ok()?;
+        Self::parse(&uts.release().to_string_lossy())
+    }
+}
+
+impl fmt::Display for KernelVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// overlay 可选特性及其最低内核版本
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlayOption {
+    /// 仅复制元数据的 copy-up
+    Metacopy,
+    /// 目录重命名不再整体复制
+    RedirectDir,
+    /// 用户命名空间内使用 `user.overlay.*` xattr
+    UserXattr,
+}
+
+impl OverlayOption {
+    pub const ALL: [OverlayOption; 3] = [
+        OverlayOption::Metacopy,
+        OverlayOption::RedirectDir,
+        OverlayOption::UserXattr,
+    ];
+
+    /// 挂载参数
+    pub fn as_mount_option(self) -> &'static str {
+        match self {
+            OverlayOption::Metacopy => "metacopy=on",
+            OverlayOption::RedirectDir => "redirect_dir=on",
+            OverlayOption::UserXattr => "userxattr",
+        }
+    }
+
+    /// 引入该选项的内核版本，更低的内核直接跳过探测
+    pub fn min_kernel(self) -> KernelVersion {
+        match self {
+            OverlayOption::Metacopy => KernelVersion::new(4, 19),
+            OverlayOption::RedirectDir => KernelVersion::new(4, 10),
+            OverlayOption::UserXattr => KernelVersion::new(5, 11),
+        }
+    }
+}
+
+/// 探测得到的 overlay 能力
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OverlayProbe {
+    /// 基础 overlay 挂载是否成功
+    pub native: bool,
+    /// 单独挂载成功的可选特性
+    pub options: Vec<OverlayOption>,
+    /// 是否运行在非初始用户命名空间
+    pub user_namespace: bool,
+    /// fuse-overlayfs 是否可用
+    pub fuse_overlayfs: bool,
+}
+
+/// 选定的存储后端
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OverlayBackend {
+    /// 内核 overlay，附带选定的挂载选项
+    Native { options: Vec<&'static str> },
+    /// 用户态 fuse-overlayfs
+    FuseOverlayfs,
+    /// 逐层解包到独立目录，写入直接落在副本上
+    Copy,
+}
+
+impl OverlayBackend {
+    pub fn name(&self) -> &'static str {
+        match self {
+            OverlayBackend::Native { .. } => "overlay",
+            OverlayBackend::FuseOverlayfs => "fuse-overlayfs",
+            OverlayBackend::Copy => "vfs",
+        }
+    }
+
+    /// 组装 overlay 挂载数据
+    pub fn mount_data(&self, lower_dirs: &[&Path], upper: &Path, work: &Path) -> String {
+        let lower = lower_dirs
+            .iter()
+            .map(|dir| dir.display().to_string())
+            .collect::<Vec<_>>()
+            .join(":");
+        let mut data = format!(
+            "lowerdir={},upperdir={},workdir={}",
+            lower,
+            upper.display(),
+            work.display()
+        );
+        if let OverlayBackend::Native { options } = self {
+            for option in options {
+                data.push(',');
+                data.push_str(option);
+            }
+        }
+        data
+    }
+}
+
+impl fmt::Display for OverlayBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OverlayBackend::Native { options } if !options.is_empty() => {
+                write!(f, "overlay ({})", options.join(","))
+            }
+            backend => f.write_str(backend.name()),
+        }
+    }
+}
+
+/// 按配置的 driver 与探测结果选择后端
+///
+/// 用户命名空间内必须使用 userxattr，而内核不允许它与 metacopy、redirect_dir
+/// 同时开启；metacopy 又依赖 redirect_dir。缺少 userxattr 时用户命名空间内的
+/// 原生 overlay 无法正确记录 whiteout，转而使用 fuse-overlayfs 或复制。
+pub fn select_backend(driver: &str, probe: &OverlayProbe) -> Result<OverlayBackend> {
+    let fallback = || {
+        if probe.fuse_overlayfs {
+            OverlayBackend::FuseOverlayfs
+        } else {
+            OverlayBackend::Copy
+        }
+    };
+    match driver {
+        DRIVER_VFS => Ok(OverlayBackend::Copy),
+        DRIVER_FUSE_OVERLAYFS => {
+            if probe.fuse_overlayfs {
+                Ok(OverlayBackend::FuseOverlayfs)
+            } else {
+                Err(anyhow!(
+                    "fuse-overlayfs driver selected but not found in PATH"
+                ))
+            }
+        }
+        "" | DRIVER_OVERLAY => {
+            if !probe.native {
+                return Ok(fallback());
+            }
+            let supports = |option| probe.options.contains(&option);
+            if probe.user_namespace {
+                if supports(OverlayOption::UserXattr) {
+                    return Ok(OverlayBackend::Native {
+                        options: vec![OverlayOption::UserXattr.as_mount_option()],
+                    });
+                }
+                return Ok(fallback());
+            }
+            let mut options = Vec::new();
+            if supports(OverlayOption::RedirectDir) {
+                options.push(OverlayOption::RedirectDir.as_mount_option());
+                if supports(OverlayOption::Metacopy) {
+                    options.push(OverlayOption::Metacopy.as_mount_option());
+                }
+            }
+            Ok(OverlayBackend::Native { options })
+        }
+        other => Err(anyhow!(
+            "unsupported image driver {:?}, expected one of {}, {}, {}",
+            other,
+            DRIVER_OVERLAY,
+            DRIVER_FUSE_OVERLAYFS,
+            DRIVER_VFS
+        )),
+    }
+}
+
+/// 探测 overlay 能力并选择后端，探测目录建在 `storage_root` 下以匹配实际文件系统
+pub fn detect_backend(driver: &str, storage_root: &Path) -> Result<OverlayBackend> {
+    let probe = if driver == DRIVER_VFS {
+        OverlayProbe::default()
+    } else {
+        probe_overlay(storage_root)
+    };
+    let backend = select_backend(driver, &probe)?;
+    info!(
+        "Selected {} storage backend (driver {:?}, kernel {}, native overlay: {}, user namespace: {})",
+        backend,
+        driver,
+        KernelVersion::current()
+            .map(|version| version.to_string())
+            .unwrap_or_else(|| "unknown".to_string()),
+        probe.native,
+        probe.user_namespace
+    );
+    Ok(backend)
+}
+
+/// 在临时目录中逐项试挂载，记录内核接受的 overlay 选项
+pub fn probe_overlay(storage_root: &Path) -> OverlayProbe {
+    let mut probe = OverlayProbe {
+        user_namespace: in_user_namespace(),
+        fuse_overlayfs: Command::new("which")
+            .arg("fuse-overlayfs")
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false),
+        ..Default::default()
+    };
+    if !overlay_in_proc_filesystems() {
+        debug!("overlay filesystem not listed in /proc/filesystems");
+        return probe;
+    }
+
+    let probe_dir = match fs::create_dir_all(storage_root).and_then(|_| {
+        tempfile::Builder::new()
+            .prefix("overlay-probe-")
+            .tempdir_in(storage_root)
+    }) {
+        Ok(dir) => dir,
+        Err(e) => {
+            warn!(
+                "Failed to create overlay probe directory in {}: {}",
+                storage_root.display(),
+                e
+            );
+            return probe;
+        }
+    };
+
+    let base = if probe.user_namespace {
+        vec![OverlayOption::UserXattr.as_mount_option()]
+    } else {
+        Vec::new()
+    };
+    match try_mount(probe_dir.path(), &base) {
+        Ok(()) => probe.native = true,
+        Err(e) => {
+            debug!("Native overlay probe mount failed: {:#}", e);
+            return probe;
+        }
+    }
+    if probe.user_namespace {
+        probe.options.push(OverlayOption::UserXattr);
+    }
+
+    let kernel = KernelVersion::current();
+    for option in OverlayOption::ALL {
+        if probe.options.contains(&option) {
+            continue;
+        }
+        if kernel.is_some_and(|kernel| kernel < option.min_kernel()) {
+            debug!(
+                "Skipping overlay option {} on kernel older than {}",
+                option.as_mount_option(),
+                option.min_kernel()
+            );
+            continue;
+        }
+        let mut options = base.clone();
+        options.push(option.as_mount_option());
+        match try_mount(probe_dir.path(), &options) {
+            Ok(()) => probe.options.push(option),
+            Err(e) => debug!(
+                "Overlay option {} not supported: {:#}",
+                option.as_mount_option(),
+                e
+            ),
+        }
+    }
+    probe
+}
+
+fn try_mount(probe_dir: &Path, options: &[&'static str]) -> Result<()> {
+    let attempt = tempfile::Builder::new()
+        .prefix("attempt-")
+        .tempdir_in(probe_dir)
+        .context("Failed to create overlay probe attempt directory")?;
+    let root = attempt.path();
+    let (lower, upper, work, merged) = (
+        root.join("lower"),
+        root.join("upper"),
+        root.join("work"),
+        root.join("merged"),
+    );
+    for dir in [&lower, &upper, &work, &merged] {
+        fs::create_dir(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let data = OverlayBackend::Native {
+        options: options.to_vec(),
+    }
+    .mount_data(&[lower.as_path()], &upper, &work);
+    mount(
+        Some("overlay"),
+        &merged,
+        Some("overlay"),
+        MsFlags::empty(),
+        Some(data.as_str()),
+    )
+    .with_context(|| format!("overlay mount with {:?} failed", data))?;
+    if let Err(e) = umount2(&merged, MntFlags::MNT_DETACH) {
+        warn!(
+            "Failed to unmount overlay probe {}: {}",
+            merged.display(),
+            e
+        );
+    }
+    Ok(())
+}
+
+fn overlay_in_proc_filesystems() -> bool {
+    fs::read_to_string("/proc/filesystems")
+        .map(|content| {
+            content
+                .lines()
+                .any(|line| line.split_whitespace().last() == Some("overlay"))
+        })
+        .unwrap_or(false)
+}
+
+/// 初始用户命名空间的 uid_map 为 `0 0 4294967295`
+fn in_user_namespace() -> bool {
+    fs::read_to_string("/proc/self/uid_map")
+        .map(|content| content.split_whitespace().collect::<Vec<_>>() != ["0", "0", "4294967295"])
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probe(native: bool, options: &[OverlayOption], user_namespace: bool) -> OverlayProbe {
+        OverlayProbe {
+            native,
+            options: options.to_vec(),
+            user_namespace,
+            fuse_overlayfs: false,
+        }
+    }
+
+    #[test]
+    fn test_kernel_version_parse() {
+        assert_eq!(
+            KernelVersion::parse("5.15.0-91-generic"),
+            Some(KernelVersion::new(5, 15))
+        );
+        assert_eq!(
+            KernelVersion::parse("4.19.90+"),
+            Some(KernelVersion::new(4, 19))
+        );
+        assert_eq!(KernelVersion::parse("garbage"), None);
+        assert!(KernelVersion::new(5, 4) < OverlayOption::UserXattr.min_kernel());
+    }
+
+    #[test]
+    fn test_select_backend_picks_options_per_kernel_features() {
+        use OverlayOption::*;
+
+        let full = select_backend("overlay", &probe(true, &[Metacopy, RedirectDir], false));
+        assert_eq!(
+            full.unwrap(),
+            OverlayBackend::Native {
+                options: vec!["redirect_dir=on", "metacopy=on"]
+            }
+        );
+
+        // metacopy 依赖 redirect_dir，缺少后者时两者都不启用
+        let old = select_backend("overlay", &probe(true, &[Metacopy], false));
+        assert_eq!(old.unwrap(), OverlayBackend::Native { options: vec![] });
+
+        let userns = select_backend(
+            "overlay",
+            &probe(true, &[UserXattr, Metacopy, RedirectDir], true),
+        );
+        assert_eq!(
+            userns.unwrap(),
+            OverlayBackend::Native {
+                options: vec!["userxattr"]
+            }
+        );
+    }
+
+    #[test]
+    fn test_select_backend_falls_back_without_native_overlay() {
+        let mut no_native = probe(false, &[], false);
+        assert_eq!(
+            select_backend("overlay", &no_native).unwrap(),
+            OverlayBackend::Copy
+        );
+        no_native.fuse_overlayfs = true;
+        assert_eq!(
+            select_backend("overlay", &no_native).unwrap(),
+            OverlayBackend::FuseOverlayfs
+        );
+
+        // 用户命名空间内内核不支持 userxattr
+        let mut old_userns = probe(true, &[], true);
+        assert_eq!(
+            select_backend("overlay", &old_userns).unwrap(),
+            OverlayBackend::Copy
+        );
+        old_userns.fuse_overlayfs = true;
+        assert_eq!(
+            select_backend("overlay", &old_userns).unwrap(),
+            OverlayBackend::FuseOverlayfs
+        );
+
+        assert_eq!(
+            select_backend("vfs", &probe(true, &[], false)).unwrap(),
+            OverlayBackend::Copy
+        );
+        assert!(select_backend("fuse-overlayfs", &probe(true, &[], false)).is_err());
+        assert!(select_backend("btrfs", &probe(true, &[], false)).is_err());
+    }
+
+    #[test]
+    fn test_mount_data_appends_selected_options() {
+        let backend = OverlayBackend::Native {
+            options: vec!["userxattr"],
+        };
+        assert_eq!(
+            backend.mount_data(
+                &[Path::new("/l1"), Path::new("/l2")],
+                Path::new("/upper"),
+                Path::new("/work")
+            ),
+            "lowerdir=/l1:/l2,upperdir=/upper,workdir=/work,userxattr"
+        );
+        assert_eq!(backend.to_string(), "overlay (userxattr)");
+        assert_eq!(OverlayBackend::Copy.to_string(), "vfs");
+    }
+}
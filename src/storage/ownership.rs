@@ -0,0 +1,170 @@
+//! fsGroup 卷属组设置
+//!
+//! 与 kubelet 的 SetVolumeOwnership 一致：卷内文件属组改为 fsGroup 并补上组读写权限，
+//! 目录额外设置 setgid 位，使之后新建的文件继承属组。符号链接不跟随，
+//! 只处理 kubelet 管理的 Pod 卷，不会改动 hostPath 指向的宿主机目录。
+
+use anyhow::{Context, Result};
+use nix::unistd::{fchownat, FchownatFlags, Gid};
+use std::fs;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Component, Path};
+
+/// 卷条目超过该数量时跳过属组设置，0 表示不限制
+pub const DEFAULT_FS_GROUP_MAX_ENTRIES: u64 = 100_000;
+
+/// 属组设置结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsGroupOutcome {
+    /// 已处理的条目数
+    Applied(u64),
+    /// 卷根已属于该组且设置了 setgid，视为此前已处理
+    AlreadyOwned,
+    /// 条目数超过上限，未做任何修改
+    TooLarge,
+}
+
+/// 是否为 kubelet 管理的 Pod 卷（`.../pods/<uid>/volumes/<plugin>/<name>` 及其子路径）
+pub fn is_pod_volume(path: &Path) -> bool {
+    let names: Vec<&std::ffi::OsStr> = path
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name),
+            _ => None,
+        })
+        .collect();
+    names
+        .windows(5)
+        .any(|window| window[0] == "pods" && window[2] == "volumes")
+}
+
+/// 递归把 `volume` 的属组设为 `gid`
+///
+/// `max_entries` 大于 0 且卷内条目超过该数量时返回 [`FsGroupOutcome::TooLarge`]。
+pub fn apply_fs_group(volume: &Path, gid: u32, max_entries: u64) -> Result<FsGroupOutcome> {
+    let root = fs::symlink_metadata(volume)
+        .with_context(|| format!("Failed to stat volume {}", volume.display()))?;
+    if root.is_dir() && root.gid() == gid && root.mode() & 0o2000 != 0 {
+        return Ok(FsGroupOutcome::AlreadyOwned);
+    }
+
+    let mut entries = Vec::new();
+    let mut pending = vec![volume.to_path_buf()];
+    while let Some(path) = pending.pop() {
+        let metadata = fs::symlink_metadata(&path)
+            .with_context(|| format!("Failed to stat {}", path.display()))?;
+        if metadata.file_type().is_symlink() {
+            continue;
+        }
+        if metadata.is_dir() {
+            for entry in fs::read_dir(&path)
+                .with_context(|| format!("Failed to read directory {}", path.display()))?
+            {
+                pending.push(entry?.path());
+            }
+        }
+        entries.push((path, metadata));
+        if max_entries > 0 && entries.len() as u64 > max_entries {
+            return Ok(FsGroupOutcome::TooLarge);
+        }
+    }
+
+    for (path, metadata) in &entries {
+        set_group(path, metadata, gid)?;
+    }
+    Ok(FsGroupOutcome::Applied(entries.len() as u64))
+}
+
+fn set_group(path: &Path, metadata: &fs::Metadata, gid: u32) -> Result<()> {
+    if metadata.gid() != gid {
+        fchownat(
+            None,
+            path,
+            None,
+            Some(Gid::from_raw(gid)),
+            FchownatFlags::NoFollowSymlink,
+        )
+        .with_context(|| format!("Failed to change group of {} to {}", path.display(), gid))?;
+    }
+
+    let mode = metadata.mode() & 0o7777;
+    let wanted = if metadata.is_dir() {
+        mode | 0o2070
+    } else if mode & 0o100 != 0 {
+        mode | 0o070
+    } else {
+        mode | 0o060
+    };
+    if wanted != mode {
+        fs::set_permissions(path, fs::Permissions::from_mode(wanted))
+            .with_context(|| format!("Failed to set permissions of {}", path.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    use tempfile::tempdir;
+
+    fn mode_of(path: &Path) -> u32 {
+        fs::symlink_metadata(path).unwrap().mode() & 0o7777
+    }
+
+    #[test]
+    fn test_is_pod_volume() {
+        assert!(is_pod_volume(Path::new(
+            "/var/lib/kubelet/pods/0a1b/volumes/kubernetes.io~empty-dir/cache"
+        )));
+        assert!(is_pod_volume(Path::new(
+            "/var/lib/kubelet/pods/0a1b/volumes/kubernetes.io~csi/pvc/mount/data"
+        )));
+        assert!(!is_pod_volume(Path::new(
+            "/var/lib/kubelet/pods/0a1b/volumes"
+        )));
+        assert!(!is_pod_volume(Path::new("/srv/data")));
+        assert!(!is_pod_volume(Path::new("/etc")));
+    }
+
+    #[test]
+    fn test_apply_fs_group_sets_group_permissions_and_setgid() {
+        let temp_dir = tempdir().unwrap();
+        let volume = temp_dir.path().join("volume");
+        fs::create_dir_all(volume.join("sub")).unwrap();
+        fs::write(volume.join("sub/data"), "data").unwrap();
+        fs::write(volume.join("run.sh"), "#!/bin/sh").unwrap();
+        fs::set_permissions(volume.join("sub/data"), fs::Permissions::from_mode(0o600)).unwrap();
+        fs::set_permissions(volume.join("run.sh"), fs::Permissions::from_mode(0o700)).unwrap();
+        fs::set_permissions(volume.join("sub"), fs::Permissions::from_mode(0o700)).unwrap();
+        fs::write(temp_dir.path().join("outside"), "").unwrap();
+        fs::set_permissions(
+            temp_dir.path().join("outside"),
+            fs::Permissions::from_mode(0o600),
+        )
+        .unwrap();
+        symlink(temp_dir.path().join("outside"), volume.join("link")).unwrap();
+        let gid = nix::unistd::getegid().as_raw();
+
+        assert_eq!(
+            apply_fs_group(&volume, gid, 3).unwrap(),
+            FsGroupOutcome::TooLarge
+        );
+        assert_eq!(mode_of(&volume.join("sub/data")), 0o600);
+
+        assert_eq!(
+            apply_fs_group(&volume, gid, DEFAULT_FS_GROUP_MAX_ENTRIES).unwrap(),
+            FsGroupOutcome::Applied(4)
+        );
+        assert_eq!(mode_of(&volume.join("sub")), 0o2770);
+        assert_eq!(mode_of(&volume.join("sub/data")), 0o660);
+        assert_eq!(mode_of(&volume.join("run.sh")), 0o770);
+        assert_ne!(mode_of(&volume) & 0o2000, 0);
+        assert_eq!(mode_of(&temp_dir.path().join("outside")), 0o600);
+
+        assert_eq!(
+            apply_fs_group(&volume, gid, 0).unwrap(),
+            FsGroupOutcome::AlreadyOwned
+        );
+    }
+}
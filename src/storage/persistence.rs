@@ -27,11 +27,11 @@ pub fn container_to_record(
                 state: "stopped".to_string(),
                 image: image.to_string(),
                 command: command.join(" "),
-                created_at: chrono::Utc::now().timestamp(),
+                created_at: crate::utils::now_nanos(),
                 labels: serde_json::to_string(labels).unwrap_or_default(),
                 annotations: serde_json::to_string(annotations).unwrap_or_default(),
                 exit_code: Some(code),
-                exit_time: Some(chrono::Utc::now().timestamp()),
+                exit_time: Some(crate::utils::now_nanos()),
             }
         }
         ContainerStatus::Unknown => "unknown",
@@ -43,7 +43,7 @@ pub fn container_to_record(
         state: state_str.to_string(),
         image: image.to_string(),
         command: command.join(" "),
-        created_at: chrono::Utc::now().timestamp(),
+        created_at: crate::utils::now_nanos(),
         labels: serde_json::to_string(labels).unwrap_or_default(),
         annotations: serde_json::to_string(annotations).unwrap_or_default(),
         exit_code: None,
@@ -81,7 +81,7 @@ pub fn pod_to_record(
         name: name.to_string(),
         namespace: namespace.to_string(),
         uid: uid.to_string(),
-        created_at: chrono::Utc::now().timestamp(),
+        created_at: crate::utils::now_nanos(),
         netns_path: netns_path.to_string(),
         labels: serde_json::to_string(labels).unwrap_or_default(),
         annotations: serde_json::to_string(annotations).unwrap_or_default(),
@@ -3,14 +3,18 @@ pub mod spdy;
 use std::collections::{HashMap, HashSet};
 use std::convert::Infallible;
 use std::fs::File;
+use std::future::Future;
 use std::io::{Read, Write};
 use std::net::{TcpListener, TcpStream as StdTcpStream, ToSocketAddrs};
 use std::os::unix::fs::MetadataExt;
 use std::os::unix::io::{AsRawFd, FromRawFd};
 use std::path::Path;
 use std::path::PathBuf;
+use std::pin::Pin;
 use std::process::Stdio;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::task::{Context, Poll, Waker};
 use std::time::{Duration, Instant};
 
 use base64::Engine;
@@ -24,12 +28,13 @@ use hyper::{Method, Request, Response, Server, StatusCode};
 use nix::pty::openpty;
 use nix::sched::CloneFlags;
 use serde::Deserialize;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
 use tokio::net::{TcpStream, UnixStream};
 use tokio::process::Command as TokioCommand;
 use tokio::sync::Mutex;
 
 use crate::attach::{AttachOutputDecoder, ATTACH_PIPE_STDERR, ATTACH_PIPE_STDOUT};
+use crate::config::StreamingConfig;
 use crate::proto::runtime::v1::{
     AttachRequest, AttachResponse, ExecRequest, ExecResponse, PortForwardRequest,
     PortForwardResponse,
@@ -45,12 +50,14 @@ const WS_CHANNEL_STDERR: u8 = 2;
 const WS_CHANNEL_ERROR: u8 = 3;
 const WS_CHANNEL_RESIZE: u8 = 4;
 const STREAMING_REQUEST_TTL: Duration = Duration::from_secs(30);
+/// 后台清理过期 token 的间隔
+const STREAMING_SWEEP_INTERVAL: Duration = Duration::from_secs(10);
 const PORT_FORWARD_STREAM_CREATION_TIMEOUT: Duration = Duration::from_secs(30);
 const PORT_FORWARD_STREAM_IDLE_TIMEOUT: Duration = Duration::from_secs(4 * 60 * 60);
 
 #[derive(Debug, Clone)]
 enum StreamingRequest {
-    Exec(ExecRequest),
+    Exec(ExecRequestContext),
     Attach(AttachRequest),
     AttachLog(AttachLogRequestContext),
     PortForward(PortForwardRequestContext),
@@ -62,6 +69,43 @@ struct CachedStreamingRequest {
     request: StreamingRequest,
 }
 
+#[derive(Debug, Clone)]
+struct ExecRequestContext {
+    req: ExecRequest,
+    options: ExecOptions,
+}
+
+/// exec 进程相对容器配置的覆盖项，未设置的部分沿用容器自身的设置
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecOptions {
+    /// 传给 `runc exec --user` 的 `uid:gid`
+    pub user: Option<String>,
+    /// 传给 `runc exec --cwd` 的容器内工作目录
+    pub cwd: Option<String>,
+    /// 追加的 `KEY=VALUE` 环境变量，同名时覆盖容器环境
+    pub env: Vec<String>,
+}
+
+impl ExecOptions {
+    /// 转换为 `runc exec` 的参数，位于容器 id 之前
+    pub fn runc_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(user) = &self.user {
+            args.push("--user".to_string());
+            args.push(user.clone());
+        }
+        if let Some(cwd) = &self.cwd {
+            args.push("--cwd".to_string());
+            args.push(cwd.clone());
+        }
+        for env in &self.env {
+            args.push("--env".to_string());
+            args.push(env.clone());
+        }
+        args
+    }
+}
+
 #[derive(Debug, Clone)]
 struct PortForwardRequestContext {
     req: PortForwardRequest,
@@ -74,9 +118,334 @@ struct AttachLogRequestContext {
     log_path: PathBuf,
 }
 
+/// 已签发的 token 与已连接的流式会话
+#[derive(Debug)]
+struct SessionRegistry {
+    requests: Mutex<HashMap<String, CachedStreamingRequest>>,
+    /// 已连接、尚未结束的会话数
+    active: AtomicUsize,
+    /// token 与已连接会话的总数上限，0 表示不限制
+    max_sessions: usize,
+    /// 已连接会话的空闲超时，0 表示不超时
+    idle_timeout: Duration,
+    /// 各容器中仍在运行的 exec 进程：pid 文件及 runc 写出后记录的进程身份
+    exec_processes:
+        std::sync::Mutex<HashMap<String, HashMap<PathBuf, Option<ExecProcessIdentity>>>>,
+    /// 停止容器时是否杀掉其中的 exec 进程
+    kill_exec_on_stop: bool,
+}
+
+impl SessionRegistry {
+    fn new(config: &StreamingConfig) -> Self {
+        Self {
+            requests: Mutex::new(HashMap::new()),
+            active: AtomicUsize::new(0),
+            max_sessions: config.max_sessions,
+            idle_timeout: Duration::from_secs(config.idle_timeout_secs),
+            exec_processes: std::sync::Mutex::new(HashMap::new()),
+            kill_exec_on_stop: config.kill_exec_on_stop,
+        }
+    }
+
+    fn track_exec(&self, container_id: &str, pid_file: &Path) {
+        if let Ok(mut processes) = self.exec_processes.lock() {
+            processes
+                .entry(container_id.to_string())
+                .or_default()
+                .insert(pid_file.to_path_buf(), None);
+        }
+    }
+
+    /// 记录 pid 文件对应的进程身份，返回是否还需要继续等待 pid 文件
+    fn pin_exec(&self, container_id: &str, pid_file: &Path) -> bool {
+        let Ok(mut processes) = self.exec_processes.lock() else {
+            return false;
+        };
+        let Some(identity) = processes
+            .get_mut(container_id)
+            .and_then(|pid_files| pid_files.get_mut(pid_file))
+        else {
+            return false;
+        };
+        if identity.is_none() {
+            *identity = ExecProcessIdentity::read(pid_file);
+        }
+        identity.is_none()
+    }
+
+    /// 已记录的进程身份；尚未记录时按当前 pid 文件读取
+    fn exec_identity(&self, container_id: &str, pid_file: &Path) -> Option<ExecProcessIdentity> {
+        let pinned = self
+            .exec_processes
+            .lock()
+            .ok()
+            .and_then(|processes| {
+                processes
+                    .get(container_id)
+                    .and_then(|pid_files| pid_files.get(pid_file).copied())
+            })
+            .flatten();
+        pinned.or_else(|| ExecProcessIdentity::read(pid_file))
+    }
+
+    fn untrack_exec(&self, container_id: &str, pid_file: &Path) {
+        if let Ok(mut processes) = self.exec_processes.lock() {
+            if let Some(pid_files) = processes.get_mut(container_id) {
+                pid_files.remove(pid_file);
+                if pid_files.is_empty() {
+                    processes.remove(container_id);
+                }
+            }
+        }
+    }
+
+    /// SIGKILL 容器中仍在运行的 exec 进程，返回发送信号的进程数
+    fn kill_exec_processes(&self, container_id: &str) -> usize {
+        let pid_files: Vec<PathBuf> = self
+            .exec_processes
+            .lock()
+            .ok()
+            .and_then(|processes| {
+                processes
+                    .get(container_id)
+                    .map(|m| m.keys().cloned().collect())
+            })
+            .unwrap_or_default();
+        pid_files
+            .iter()
+            .filter_map(|pid_file| self.exec_identity(container_id, pid_file))
+            .filter(|identity| identity.kill())
+            .count()
+    }
+
+    /// 签发 token；会话数达到上限时返回 `ResourceExhausted`
+    async fn insert(&self, request: StreamingRequest) -> Result<String, tonic::Status> {
+        let mut requests = self.requests.lock().await;
+        Self::prune_expired_locked(&mut requests);
+        if self.max_sessions > 0
+            && requests.len() + self.active.load(Ordering::SeqCst) >= self.max_sessions
+        {
+            return Err(tonic::Status::resource_exhausted(format!(
+                "streaming session limit of {} reached",
+                self.max_sessions
+            )));
+        }
+        let token = uuid::Uuid::new_v4().to_string();
+        requests.insert(
+            token.clone(),
+            CachedStreamingRequest {
+                created_at: Instant::now(),
+                request,
+            },
+        );
+        Ok(token)
+    }
+
+    /// 取出 token 对应的请求，token 只能使用一次
+    async fn take(&self, token: &str) -> Option<CachedStreamingRequest> {
+        let mut requests = self.requests.lock().await;
+        Self::prune_expired_locked(&mut requests);
+        requests.remove(token)
+    }
+
+    /// 清理过期未连接的 token，返回清理数量
+    async fn sweep(&self) -> usize {
+        let mut requests = self.requests.lock().await;
+        let before = requests.len();
+        Self::prune_expired_locked(&mut requests);
+        before - requests.len()
+    }
+
+    fn prune_expired_locked(requests: &mut HashMap<String, CachedStreamingRequest>) {
+        let now = Instant::now();
+        requests.retain(|_, entry| now.duration_since(entry.created_at) <= STREAMING_REQUEST_TTL);
+    }
+
+    /// 定期清理过期 token，registry 释放后退出
+    fn start_sweeper(self: &Arc<Self>) {
+        let registry = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(STREAMING_SWEEP_INTERVAL);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                let Some(registry) = Weak::upgrade(&registry) else {
+                    break;
+                };
+                let expired = registry.sweep().await;
+                if expired > 0 {
+                    log::debug!("Expired {} unused streaming token(s)", expired);
+                }
+            }
+        });
+    }
+
+    /// 接受连接升级并登记为活跃会话
+    fn connect(self: &Arc<Self>, req: Request<Body>) -> (SessionUpgrade, ConnectedSession) {
+        self.active.fetch_add(1, Ordering::SeqCst);
+        let activity = new_stream_activity(Instant::now());
+        let control = Arc::new(SessionControl::default());
+        let on_upgrade = hyper::upgrade::on(req);
+        let upgrade = {
+            let activity = activity.clone();
+            let control = control.clone();
+            Box::pin(async move {
+                on_upgrade.await.map(|inner| ActivityIo {
+                    inner,
+                    activity,
+                    control,
+                })
+            })
+        };
+        (
+            upgrade,
+            ConnectedSession {
+                registry: self.clone(),
+                activity,
+                control,
+            },
+        )
+    }
+}
+
+/// 一个已连接的会话，结束时释放名额
+struct ConnectedSession {
+    registry: Arc<SessionRegistry>,
+    activity: StreamActivity,
+    control: Arc<SessionControl>,
+}
+
+impl ConnectedSession {
+    /// 运行会话；空闲超时后关闭连接并丢弃会话，exec 进程随之被杀掉
+    async fn run(self, serve: impl Future<Output = anyhow::Result<()>>) -> anyhow::Result<()> {
+        let idle_timeout = self.registry.idle_timeout;
+        if idle_timeout.is_zero() {
+            return serve.await;
+        }
+        tokio::pin!(serve);
+        loop {
+            let wait = next_stream_idle_timeout(&self.activity, Instant::now(), idle_timeout);
+            tokio::select! {
+                result = &mut serve => return result,
+                _ = tokio::time::sleep(wait) => {
+                    if stream_is_idle(&self.activity, Instant::now(), idle_timeout) {
+                        self.control.close();
+                        return Err(anyhow::anyhow!(
+                            "streaming session idle for more than {:?}",
+                            idle_timeout
+                        ));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Drop for ConnectedSession {
+    fn drop(&mut self) {
+        self.control.close();
+        self.registry.active.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// 会话关闭标记；关闭后连接读到 EOF、写入失败，会话内的各个任务随之退出
+#[derive(Debug, Default)]
+struct SessionControl {
+    closed: AtomicBool,
+    wakers: std::sync::Mutex<Vec<Waker>>,
+}
+
+impl SessionControl {
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        if let Ok(mut wakers) = self.wakers.lock() {
+            wakers.drain(..).for_each(Waker::wake);
+        }
+    }
+
+    /// 登记阻塞中的读写，关闭时唤醒；返回登记后是否已关闭
+    fn register(&self, waker: &Waker) -> bool {
+        if let Ok(mut wakers) = self.wakers.lock() {
+            if !wakers.iter().any(|registered| registered.will_wake(waker)) {
+                wakers.push(waker.clone());
+            }
+        }
+        self.is_closed()
+    }
+}
+
+/// 记录最近一次收发数据时间的连接
+struct ActivityIo<T> {
+    inner: T,
+    activity: StreamActivity,
+    control: Arc<SessionControl>,
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for ActivityIo<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        if self.control.is_closed() {
+            return Poll::Ready(Ok(()));
+        }
+        let filled = buf.filled().len();
+        match Pin::new(&mut self.inner).poll_read(cx, buf) {
+            Poll::Ready(Ok(())) => {
+                if buf.filled().len() > filled {
+                    mark_stream_activity(&self.activity, Instant::now());
+                }
+                Poll::Ready(Ok(()))
+            }
+            Poll::Pending if self.control.register(cx.waker()) => Poll::Ready(Ok(())),
+            other => other,
+        }
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for ActivityIo<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        if self.control.is_closed() {
+            return Poll::Ready(Err(std::io::ErrorKind::BrokenPipe.into()));
+        }
+        match Pin::new(&mut self.inner).poll_write(cx, buf) {
+            Poll::Ready(Ok(written)) => {
+                if written > 0 {
+                    mark_stream_activity(&self.activity, Instant::now());
+                }
+                Poll::Ready(Ok(written))
+            }
+            Poll::Pending if self.control.register(cx.waker()) => {
+                Poll::Ready(Err(std::io::ErrorKind::BrokenPipe.into()))
+            }
+            other => other,
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+type UpgradedIo = ActivityIo<hyper::upgrade::Upgraded>;
+type SessionUpgrade = Pin<Box<dyn Future<Output = hyper::Result<UpgradedIo>> + Send>>;
+
 #[derive(Debug, Clone)]
 pub struct StreamingServer {
-    cache: Arc<Mutex<HashMap<String, CachedStreamingRequest>>>,
+    sessions: Arc<SessionRegistry>,
     base_url: String,
 }
 
@@ -84,27 +453,32 @@ impl StreamingServer {
     #[cfg(test)]
     pub(crate) fn for_test(base_url: impl Into<String>) -> Self {
         Self {
-            cache: Arc::new(Mutex::new(HashMap::new())),
+            sessions: Arc::new(SessionRegistry::new(&StreamingConfig::default())),
             base_url: base_url.into(),
         }
     }
 
-    pub async fn start(bind_addr: &str, runtime_path: PathBuf) -> anyhow::Result<Self> {
+    pub async fn start(
+        bind_addr: &str,
+        runtime_path: PathBuf,
+        config: &StreamingConfig,
+    ) -> anyhow::Result<Self> {
         let listener = TcpListener::bind(bind_addr)?;
         listener.set_nonblocking(true)?;
         let local_addr = listener.local_addr()?;
-        let cache = Arc::new(Mutex::new(HashMap::new()));
-        let service_cache = cache.clone();
+        let sessions = Arc::new(SessionRegistry::new(config));
+        sessions.start_sweeper();
+        let service_sessions = sessions.clone();
         let service_runtime_path = runtime_path.clone();
 
         let make_service = make_service_fn(move |_| {
-            let cache = service_cache.clone();
+            let sessions = service_sessions.clone();
             let runtime_path = service_runtime_path.clone();
             async move {
                 Ok::<_, Infallible>(service_fn(move |req| {
-                    let cache = cache.clone();
+                    let sessions = sessions.clone();
                     let runtime_path = runtime_path.clone();
-                    async move { Ok::<_, Infallible>(handle_request(cache, runtime_path, req).await) }
+                    async move { Ok::<_, Infallible>(handle_request(sessions, runtime_path, req).await) }
                 }))
             }
         });
@@ -117,7 +491,7 @@ impl StreamingServer {
         });
 
         Ok(Self {
-            cache,
+            sessions,
             base_url: format!("http://{}", local_addr),
         })
     }
@@ -127,10 +501,23 @@ impl StreamingServer {
     }
 
     pub async fn get_exec(&self, req: &ExecRequest) -> Result<ExecResponse, tonic::Status> {
+        self.get_exec_with_options(req, ExecOptions::default())
+            .await
+    }
+
+    /// 带用户、工作目录和环境变量覆盖的 exec，默认选项等同 [`Self::get_exec`]
+    pub async fn get_exec_with_options(
+        &self,
+        req: &ExecRequest,
+        options: ExecOptions,
+    ) -> Result<ExecResponse, tonic::Status> {
         Self::validate_exec_request(req)?;
         let token = self
-            .insert_request(StreamingRequest::Exec(req.clone()))
-            .await;
+            .insert_request(StreamingRequest::Exec(ExecRequestContext {
+                req: req.clone(),
+                options,
+            }))
+            .await?;
         Ok(ExecResponse {
             url: format!("{}/exec/{}", self.base_url, token),
         })
@@ -140,7 +527,7 @@ impl StreamingServer {
         Self::validate_attach_request(req)?;
         let token = self
             .insert_request(StreamingRequest::Attach(req.clone()))
-            .await;
+            .await?;
         Ok(AttachResponse {
             url: format!("{}/attach/{}", self.base_url, token),
         })
@@ -157,7 +544,7 @@ impl StreamingServer {
                 req: req.clone(),
                 log_path,
             }))
-            .await;
+            .await?;
         Ok(AttachResponse {
             url: format!("{}/attach/{}", self.base_url, token),
         })
@@ -174,35 +561,34 @@ impl StreamingServer {
                 req: req.clone(),
                 netns_path,
             }))
-            .await;
+            .await?;
         Ok(PortForwardResponse {
             url: format!("{}/portforward/{}", self.base_url, token),
         })
     }
 
-    async fn insert_request(&self, request: StreamingRequest) -> String {
-        let token = uuid::Uuid::new_v4().to_string();
-        let mut cache = self.cache.lock().await;
-        Self::prune_expired_requests_locked(&mut cache);
-        cache.insert(
-            token.clone(),
-            CachedStreamingRequest {
-                created_at: Instant::now(),
-                request,
-            },
-        );
-        token
+    async fn insert_request(&self, request: StreamingRequest) -> Result<String, tonic::Status> {
+        self.sessions.insert(request).await
     }
 
-    fn prune_expired_requests_locked(cache: &mut HashMap<String, CachedStreamingRequest>) {
-        let now = Instant::now();
-        cache.retain(|_, entry| now.duration_since(entry.created_at) <= STREAMING_REQUEST_TTL);
+    /// 为 exec 进程创建 pid 文件并登记，停止容器时与流式 exec 一起被杀掉
+    pub(crate) fn exec_process_guard(&self, container_id: &str) -> ExecProcessGuard {
+        ExecProcessGuard::new(&self.sessions, container_id)
+    }
+
+    /// 容器停止时杀掉经 exec / ExecSync 启动、仍在运行的进程，返回杀掉的数量；
+    /// 配置 `kill_exec_on_stop = false` 时保留这些进程
+    pub fn kill_exec_processes(&self, container_id: &str) -> usize {
+        if !self.sessions.kill_exec_on_stop {
+            return 0;
+        }
+        self.sessions.kill_exec_processes(container_id)
     }
 
     #[cfg(test)]
     async fn insert_request_for_test(&self, request: StreamingRequest, age: Duration) -> String {
         let token = uuid::Uuid::new_v4().to_string();
-        let mut cache = self.cache.lock().await;
+        let mut cache = self.sessions.requests.lock().await;
         cache.insert(
             token.clone(),
             CachedStreamingRequest {
@@ -213,7 +599,8 @@ impl StreamingServer {
         token
     }
 
-    fn validate_exec_request(req: &ExecRequest) -> Result<(), tonic::Status> {
+    /// 校验 exec 请求的流选择：至少选择一路流，tty 模式下 stderr 合并到 stdout 不能单独请求
+    pub(crate) fn validate_exec_request(req: &ExecRequest) -> Result<(), tonic::Status> {
         if req.container_id.is_empty() {
             return Err(tonic::Status::invalid_argument(
                 "missing required container_id",
@@ -235,7 +622,8 @@ impl StreamingServer {
         Ok(())
     }
 
-    fn validate_attach_request(req: &AttachRequest) -> Result<(), tonic::Status> {
+    /// 校验 attach 请求的流选择，规则同 exec
+    pub(crate) fn validate_attach_request(req: &AttachRequest) -> Result<(), tonic::Status> {
         if req.container_id.is_empty() {
             return Err(tonic::Status::invalid_argument(
                 "missing required container_id",
@@ -277,7 +665,7 @@ impl StreamingServer {
 }
 
 async fn handle_request(
-    cache: Arc<Mutex<HashMap<String, CachedStreamingRequest>>>,
+    sessions: Arc<SessionRegistry>,
     runtime_path: PathBuf,
     req: Request<Body>,
 ) -> Response<Body> {
@@ -296,11 +684,7 @@ async fn handle_request(
         _ => return response(StatusCode::NOT_FOUND, "streaming token not found"),
     };
 
-    let request = {
-        let mut cache = cache.lock().await;
-        StreamingServer::prune_expired_requests_locked(&mut cache);
-        cache.remove(token)
-    };
+    let request = sessions.take(token).await;
 
     match (action, request.map(|entry| entry.request)) {
         ("exec", Some(StreamingRequest::Exec(exec_req))) => {
@@ -313,10 +697,17 @@ async fn handle_request(
                 };
 
                 let response = websocket_switching_response(&req, protocol);
-                let on_upgrade = hyper::upgrade::on(req);
+                let (on_upgrade, session) = sessions.connect(req);
                 tokio::spawn(async move {
-                    if let Err(e) =
-                        serve_exec_websocket(on_upgrade, exec_req, runtime_path, protocol).await
+                    if let Err(e) = session
+                        .run(serve_exec_websocket(
+                            on_upgrade,
+                            sessions,
+                            exec_req,
+                            runtime_path,
+                            protocol,
+                        ))
+                        .await
                     {
                         log::error!("Exec websocket session failed: {}", e);
                     }
@@ -338,9 +729,17 @@ async fn handle_request(
                 );
             };
 
-            let on_upgrade = hyper::upgrade::on(req);
+            let (on_upgrade, session) = sessions.connect(req);
             tokio::spawn(async move {
-                if let Err(e) = serve_exec_spdy(on_upgrade, exec_req, runtime_path, protocol).await
+                if let Err(e) = session
+                    .run(serve_exec_spdy(
+                        on_upgrade,
+                        sessions,
+                        exec_req,
+                        runtime_path,
+                        protocol,
+                    ))
+                    .await
                 {
                     log::error!("Exec SPDY session failed: {}", e);
                 }
@@ -364,9 +763,12 @@ async fn handle_request(
                 };
 
                 let response = websocket_switching_response(&req, protocol);
-                let on_upgrade = hyper::upgrade::on(req);
+                let (on_upgrade, session) = sessions.connect(req);
                 tokio::spawn(async move {
-                    if let Err(e) = serve_attach_websocket(on_upgrade, attach_req, protocol).await {
+                    if let Err(e) = session
+                        .run(serve_attach_websocket(on_upgrade, attach_req, protocol))
+                        .await
+                    {
                         log::error!("Attach websocket session failed: {}", e);
                     }
                 });
@@ -387,9 +789,12 @@ async fn handle_request(
                 );
             };
 
-            let on_upgrade = hyper::upgrade::on(req);
+            let (on_upgrade, session) = sessions.connect(req);
             tokio::spawn(async move {
-                if let Err(e) = serve_attach_spdy(on_upgrade, attach_req, protocol).await {
+                if let Err(e) = session
+                    .run(serve_attach_spdy(on_upgrade, attach_req, protocol))
+                    .await
+                {
                     log::error!("Attach SPDY session failed: {}", e);
                 }
             });
@@ -412,10 +817,15 @@ async fn handle_request(
                 };
 
                 let response = websocket_switching_response(&req, protocol);
-                let on_upgrade = hyper::upgrade::on(req);
+                let (on_upgrade, session) = sessions.connect(req);
                 tokio::spawn(async move {
-                    if let Err(e) =
-                        serve_attach_log_websocket(on_upgrade, attach_log_ctx, protocol).await
+                    if let Err(e) = session
+                        .run(serve_attach_log_websocket(
+                            on_upgrade,
+                            attach_log_ctx,
+                            protocol,
+                        ))
+                        .await
                     {
                         log::error!("Attach log websocket session failed: {}", e);
                     }
@@ -437,9 +847,12 @@ async fn handle_request(
                 );
             };
 
-            let on_upgrade = hyper::upgrade::on(req);
+            let (on_upgrade, session) = sessions.connect(req);
             tokio::spawn(async move {
-                if let Err(e) = serve_attach_log_spdy(on_upgrade, attach_log_ctx, protocol).await {
+                if let Err(e) = session
+                    .run(serve_attach_log_spdy(on_upgrade, attach_log_ctx, protocol))
+                    .await
+                {
                     log::error!("Attach log fallback session failed: {}", e);
                 }
             });
@@ -462,10 +875,15 @@ async fn handle_request(
                 };
 
                 let response = websocket_switching_response(&req, protocol);
-                let on_upgrade = hyper::upgrade::on(req);
+                let (on_upgrade, session) = sessions.connect(req);
                 tokio::spawn(async move {
-                    if let Err(e) =
-                        serve_portforward_websocket(on_upgrade, port_forward_ctx, protocol).await
+                    if let Err(e) = session
+                        .run(serve_portforward_websocket(
+                            on_upgrade,
+                            port_forward_ctx,
+                            protocol,
+                        ))
+                        .await
                     {
                         log::error!("Port-forward websocket session failed: {}", e);
                     }
@@ -487,9 +905,12 @@ async fn handle_request(
                 );
             };
 
-            let on_upgrade = hyper::upgrade::on(req);
+            let (on_upgrade, session) = sessions.connect(req);
             tokio::spawn(async move {
-                if let Err(e) = serve_portforward_spdy(on_upgrade, port_forward_ctx).await {
+                if let Err(e) = session
+                    .run(serve_portforward_spdy(on_upgrade, port_forward_ctx))
+                    .await
+                {
                     log::error!("Port-forward SPDY session failed: {}", e);
                 }
             });
@@ -580,6 +1001,113 @@ enum PortForwardStreamRegistration {
     },
 }
 
+/// exec 进程的 pid 及其启动时间，发送信号前据此确认 pid 没有被复用
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ExecProcessIdentity {
+    pid: i32,
+    start_time: u64,
+}
+
+impl ExecProcessIdentity {
+    fn read(pid_file: &Path) -> Option<Self> {
+        let pid = read_exec_pid(pid_file)?;
+        Some(Self {
+            pid,
+            start_time: crate::utils::process_start_time(pid)?,
+        })
+    }
+
+    /// pid 仍属于同一进程时发送 SIGKILL，返回是否发送
+    fn kill(&self) -> bool {
+        if crate::utils::process_start_time(self.pid) != Some(self.start_time) {
+            log::debug!(
+                "Exec process {} already exited, not signalling its pid",
+                self.pid
+            );
+            return false;
+        }
+        match nix::sys::signal::kill(
+            nix::unistd::Pid::from_raw(self.pid),
+            nix::sys::signal::Signal::SIGKILL,
+        ) {
+            Ok(()) => true,
+            Err(e) => {
+                log::debug!("Failed to kill exec process {}: {}", self.pid, e);
+                false
+            }
+        }
+    }
+}
+
+/// exec 会话在容器内启动的进程；会话提前结束时 SIGKILL 该进程
+pub(crate) struct ExecProcessGuard {
+    pid_file: PathBuf,
+    armed: bool,
+    registry: Arc<SessionRegistry>,
+    container_id: String,
+}
+
+impl ExecProcessGuard {
+    /// 创建 pid 文件路径并登记到 registry，停止容器时据此杀掉 exec 进程
+    fn new(registry: &Arc<SessionRegistry>, container_id: &str) -> Self {
+        let pid_file =
+            std::env::temp_dir().join(format!("crius-exec-{}.pid", uuid::Uuid::new_v4()));
+        registry.track_exec(container_id, &pid_file);
+        Self {
+            pid_file,
+            armed: true,
+            registry: registry.clone(),
+            container_id: container_id.to_string(),
+        }
+    }
+
+    pub(crate) fn pid_file(&self) -> &Path {
+        &self.pid_file
+    }
+
+    /// runc 启动后调用：runc 写出 pid 文件时立即记录进程身份。runc 是该进程的父进程，
+    /// 在 runc 回收它之前 pid 不会被复用，之后的 SIGKILL 只发给同一个进程
+    pub(crate) fn watch(&self) {
+        let registry = self.registry.clone();
+        let container_id = self.container_id.clone();
+        let pid_file = self.pid_file.clone();
+        tokio::spawn(async move {
+            while registry.pin_exec(&container_id, &pid_file) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        });
+    }
+
+    /// 进程已正常退出，不再需要清理
+    pub(crate) fn disarm(&mut self) {
+        self.armed = false;
+    }
+}
+
+/// runc 写入的 exec 进程 pid
+fn read_exec_pid(pid_file: &Path) -> Option<i32> {
+    std::fs::read_to_string(pid_file)
+        .ok()
+        .and_then(|raw| raw.trim().parse::<i32>().ok())
+        .filter(|pid| *pid > 0)
+}
+
+impl Drop for ExecProcessGuard {
+    fn drop(&mut self) {
+        if self.armed {
+            if let Some(identity) = self
+                .registry
+                .exec_identity(&self.container_id, &self.pid_file)
+            {
+                identity.kill();
+            }
+        }
+        self.registry
+            .untrack_exec(&self.container_id, &self.pid_file);
+        let _ = std::fs::remove_file(&self.pid_file);
+    }
+}
+
 type StreamActivity = Arc<std::sync::Mutex<Instant>>;
 
 #[derive(Debug, Deserialize)]
@@ -762,6 +1290,19 @@ fn shim_socket_path(container_id: &str, socket_name: &str) -> PathBuf {
     shim_work_dir().join(container_id).join(socket_name)
 }
 
+/// attach 请求连接的 shim socket：携带 stdin 时使用 attach-stdin.sock
+///
+/// 旧版本 shim 只有 attach.sock，并转发其上所有客户端的输入
+fn attach_socket_path(req: &AttachRequest) -> PathBuf {
+    if req.stdin {
+        let stdin_socket = shim_socket_path(&req.container_id, "attach-stdin.sock");
+        if stdin_socket.exists() {
+            return stdin_socket;
+        }
+    }
+    shim_socket_path(&req.container_id, "attach.sock")
+}
+
 fn parse_terminal_size(payload: &[u8]) -> anyhow::Result<(u16, u16)> {
     let size: TerminalSizePayload = serde_json::from_slice(payload)
         .map_err(|e| anyhow::anyhow!("invalid resize payload: {}", e))?;
@@ -791,7 +1332,7 @@ fn apply_terminal_resize(tty: &File, width: u16, height: u16) -> anyhow::Result<
 }
 
 async fn write_error_stream(
-    writer: &Arc<Mutex<spdy::AsyncSpdyWriter<tokio::io::WriteHalf<hyper::upgrade::Upgraded>>>>,
+    writer: &Arc<Mutex<spdy::AsyncSpdyWriter<tokio::io::WriteHalf<UpgradedIo>>>>,
     error_stream: Option<spdy::StreamId>,
     message: &str,
 ) -> anyhow::Result<()> {
@@ -1489,7 +2030,7 @@ async fn connect_to_port_in_netns(netns_path: PathBuf, port: u16) -> anyhow::Res
 }
 
 async fn write_portforward_error(
-    writer: &Arc<Mutex<spdy::AsyncSpdyWriter<tokio::io::WriteHalf<hyper::upgrade::Upgraded>>>>,
+    writer: &Arc<Mutex<spdy::AsyncSpdyWriter<tokio::io::WriteHalf<UpgradedIo>>>>,
     activity: &StreamActivity,
     data_stream: spdy::StreamId,
     error_stream: spdy::StreamId,
@@ -1508,7 +2049,7 @@ async fn write_portforward_error(
 }
 
 async fn write_portforward_stream_error(
-    writer: &Arc<Mutex<spdy::AsyncSpdyWriter<tokio::io::WriteHalf<hyper::upgrade::Upgraded>>>>,
+    writer: &Arc<Mutex<spdy::AsyncSpdyWriter<tokio::io::WriteHalf<UpgradedIo>>>>,
     activity: &StreamActivity,
     stream_id: spdy::StreamId,
     message: &str,
@@ -1525,7 +2066,7 @@ async fn write_portforward_stream_error(
 }
 
 async fn write_portforward_pair_setup_error(
-    writer: &Arc<Mutex<spdy::AsyncSpdyWriter<tokio::io::WriteHalf<hyper::upgrade::Upgraded>>>>,
+    writer: &Arc<Mutex<spdy::AsyncSpdyWriter<tokio::io::WriteHalf<UpgradedIo>>>>,
     activity: &StreamActivity,
     pair: PortForwardPair,
     extra_stream: Option<spdy::StreamId>,
@@ -1556,7 +2097,7 @@ async fn write_portforward_pair_setup_error(
 }
 
 async fn serve_portforward_pair(
-    writer: Arc<Mutex<spdy::AsyncSpdyWriter<tokio::io::WriteHalf<hyper::upgrade::Upgraded>>>>,
+    writer: Arc<Mutex<spdy::AsyncSpdyWriter<tokio::io::WriteHalf<UpgradedIo>>>>,
     activity: StreamActivity,
     netns_path: PathBuf,
     port: u16,
@@ -1643,11 +2184,13 @@ async fn serve_portforward_pair(
 }
 
 async fn serve_exec_spdy(
-    on_upgrade: hyper::upgrade::OnUpgrade,
-    req: ExecRequest,
+    on_upgrade: SessionUpgrade,
+    sessions: Arc<SessionRegistry>,
+    exec: ExecRequestContext,
     runtime_path: PathBuf,
     _protocol: &'static str,
 ) -> anyhow::Result<()> {
+    let ExecRequestContext { req, options } = exec;
     let upgraded = on_upgrade.await?;
     let (read_half, write_half) = tokio::io::split(upgraded);
     let writer = Arc::new(Mutex::new(spdy::AsyncSpdyWriter::new(write_half)));
@@ -1731,9 +2274,15 @@ async fn serve_exec_spdy(
 
     let mut command = TokioCommand::new(&runtime_path);
     command.arg("exec");
+    // 提前返回或连接断开时杀掉 runc exec，子进程由 tokio 回收，不会残留
+    command.kill_on_drop(true);
+    // 杀掉 runc 不会结束容器内的进程，由 guard 按 pid 文件清理
+    let mut exec_guard = ExecProcessGuard::new(&sessions, &req.container_id);
+    command.arg("--pid-file").arg(exec_guard.pid_file());
     if req.tty {
         command.arg("-t");
     }
+    command.args(options.runc_args());
     command.arg(&req.container_id);
     for arg in &req.cmd {
         command.arg(arg);
@@ -1803,6 +2352,7 @@ async fn serve_exec_spdy(
             return Err(e.into());
         }
     };
+    exec_guard.watch();
 
     let stdout_task;
     let stderr_task;
@@ -2053,6 +2603,7 @@ async fn serve_exec_spdy(
     });
 
     let status = child.wait().await?;
+    exec_guard.disarm();
 
     if let Some(tx) = console_stdin_tx.as_ref() {
         let _ = tx.send(None).await;
@@ -2114,20 +2665,28 @@ async fn serve_exec_spdy(
 }
 
 async fn serve_exec_websocket(
-    on_upgrade: hyper::upgrade::OnUpgrade,
-    req: ExecRequest,
+    on_upgrade: SessionUpgrade,
+    sessions: Arc<SessionRegistry>,
+    exec: ExecRequestContext,
     runtime_path: PathBuf,
     _protocol: &'static str,
 ) -> anyhow::Result<()> {
+    let ExecRequestContext { req, options } = exec;
     let upgraded = on_upgrade.await?;
     let (mut reader, writer) = tokio::io::split(upgraded);
     let writer = Arc::new(Mutex::new(writer));
 
     let mut command = TokioCommand::new(&runtime_path);
     command.arg("exec");
+    // 提前返回或连接断开时杀掉 runc exec，子进程由 tokio 回收，不会残留
+    command.kill_on_drop(true);
+    // 杀掉 runc 不会结束容器内的进程，由 guard 按 pid 文件清理
+    let mut exec_guard = ExecProcessGuard::new(&sessions, &req.container_id);
+    command.arg("--pid-file").arg(exec_guard.pid_file());
     if req.tty {
         command.arg("-t");
     }
+    command.args(options.runc_args());
     command.arg(&req.container_id);
     for arg in &req.cmd {
         command.arg(arg);
@@ -2180,6 +2739,7 @@ async fn serve_exec_websocket(
     }
 
     let mut child = command.spawn()?;
+    exec_guard.watch();
     let stdout_task;
     let stderr_task;
     let mut console_input_task = None;
@@ -2367,6 +2927,7 @@ async fn serve_exec_websocket(
     });
 
     let status = child.wait().await?;
+    exec_guard.disarm();
 
     if let Some(tx) = console_stdin_tx.as_ref() {
         let _ = tx.send(None).await;
@@ -2397,7 +2958,7 @@ async fn serve_exec_websocket(
 }
 
 async fn serve_portforward_spdy(
-    on_upgrade: hyper::upgrade::OnUpgrade,
+    on_upgrade: SessionUpgrade,
     ctx: PortForwardRequestContext,
 ) -> anyhow::Result<()> {
     let upgraded = on_upgrade.await?;
@@ -2629,7 +3190,7 @@ async fn serve_portforward_spdy(
 }
 
 async fn serve_attach_spdy(
-    on_upgrade: hyper::upgrade::OnUpgrade,
+    on_upgrade: SessionUpgrade,
     req: AttachRequest,
     _protocol: &'static str,
 ) -> anyhow::Result<()> {
@@ -2719,7 +3280,7 @@ async fn serve_attach_spdy(
         }
     }
 
-    let attach_socket_path = shim_socket_path(&req.container_id, "attach.sock");
+    let attach_socket_path = attach_socket_path(&req);
     let shim = match UnixStream::connect(&attach_socket_path).await {
         Ok(shim) => shim,
         Err(e) => {
@@ -2923,7 +3484,7 @@ async fn serve_attach_spdy(
 }
 
 async fn serve_portforward_websocket(
-    on_upgrade: hyper::upgrade::OnUpgrade,
+    on_upgrade: SessionUpgrade,
     ctx: PortForwardRequestContext,
     protocol: &'static str,
 ) -> anyhow::Result<()> {
@@ -3098,7 +3659,7 @@ async fn serve_portforward_websocket(
 }
 
 async fn serve_attach_log_spdy(
-    on_upgrade: hyper::upgrade::OnUpgrade,
+    on_upgrade: SessionUpgrade,
     ctx: AttachLogRequestContext,
     _protocol: &'static str,
 ) -> anyhow::Result<()> {
@@ -3268,7 +3829,7 @@ async fn serve_attach_log_spdy(
 }
 
 async fn serve_attach_websocket(
-    on_upgrade: hyper::upgrade::OnUpgrade,
+    on_upgrade: SessionUpgrade,
     req: AttachRequest,
     _protocol: &'static str,
 ) -> anyhow::Result<()> {
@@ -3276,7 +3837,7 @@ async fn serve_attach_websocket(
     let (mut reader, writer) = tokio::io::split(upgraded);
     let writer = Arc::new(Mutex::new(writer));
 
-    let attach_socket_path = shim_socket_path(&req.container_id, "attach.sock");
+    let attach_socket_path = attach_socket_path(&req);
     let shim = UnixStream::connect(&attach_socket_path)
         .await
         .map_err(|e| {
@@ -3414,7 +3975,7 @@ async fn serve_attach_websocket(
 }
 
 async fn serve_attach_log_websocket(
-    on_upgrade: hyper::upgrade::OnUpgrade,
+    on_upgrade: SessionUpgrade,
     ctx: AttachLogRequestContext,
     _protocol: &'static str,
 ) -> anyhow::Result<()> {
@@ -3570,6 +4131,43 @@ mod tests {
         assert_eq!(err.code(), tonic::Code::InvalidArgument);
     }
 
+    #[test]
+    fn test_validate_stream_flags_for_each_combination() {
+        for bits in 0..16u8 {
+            let (stdin, stdout, stderr, tty) =
+                (bits & 1 != 0, bits & 2 != 0, bits & 4 != 0, bits & 8 != 0);
+            let valid = (stdin || stdout || stderr) && !(tty && stderr);
+
+            let exec = ExecRequest {
+                container_id: "abc".to_string(),
+                cmd: vec!["sh".to_string()],
+                stdin,
+                stdout,
+                stderr,
+                tty,
+            };
+            let attach = AttachRequest {
+                container_id: "abc".to_string(),
+                stdin,
+                stdout,
+                stderr,
+                tty,
+            };
+            for result in [
+                StreamingServer::validate_exec_request(&exec),
+                StreamingServer::validate_attach_request(&attach),
+            ] {
+                match result {
+                    Ok(()) => assert!(valid, "flags {:04b} should be rejected", bits),
+                    Err(err) => {
+                        assert!(!valid, "flags {:04b} should be accepted", bits);
+                        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+                    }
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_expected_exec_roles_omit_stderr_for_tty() {
         let req = ExecRequest {
@@ -3610,23 +4208,31 @@ mod tests {
     async fn test_exec_transport_explicitly_rejects_non_spdy_requests() {
         let server = StreamingServer::for_test("http://127.0.0.1:12345");
         let token = server
-            .insert_request(StreamingRequest::Exec(ExecRequest {
-                container_id: "abc".to_string(),
-                cmd: vec!["sh".to_string()],
-                stdin: true,
-                stdout: true,
-                stderr: false,
-                tty: true,
+            .insert_request(StreamingRequest::Exec(ExecRequestContext {
+                req: ExecRequest {
+                    container_id: "abc".to_string(),
+                    cmd: vec!["sh".to_string()],
+                    stdin: true,
+                    stdout: true,
+                    stderr: false,
+                    tty: true,
+                },
+                options: ExecOptions::default(),
             }))
-            .await;
+            .await
+            .unwrap();
         let request = Request::builder()
             .method(Method::GET)
             .uri(format!("/exec/{}", token))
             .body(Body::empty())
             .unwrap();
 
-        let response =
-            handle_request(server.cache.clone(), PathBuf::from("/bin/false"), request).await;
+        let response = handle_request(
+            server.sessions.clone(),
+            PathBuf::from("/bin/false"),
+            request,
+        )
+        .await;
         assert_eq!(response.status(), StatusCode::BAD_REQUEST);
         assert!(response_body_string(response)
             .await
@@ -3637,15 +4243,19 @@ mod tests {
     async fn test_exec_transport_accepts_websocket_upgrade_requests() {
         let server = StreamingServer::for_test("http://127.0.0.1:12345");
         let token = server
-            .insert_request(StreamingRequest::Exec(ExecRequest {
-                container_id: "abc".to_string(),
-                cmd: vec!["sh".to_string()],
-                stdin: true,
-                stdout: true,
-                stderr: false,
-                tty: true,
+            .insert_request(StreamingRequest::Exec(ExecRequestContext {
+                req: ExecRequest {
+                    container_id: "abc".to_string(),
+                    cmd: vec!["sh".to_string()],
+                    stdin: true,
+                    stdout: true,
+                    stderr: false,
+                    tty: true,
+                },
+                options: ExecOptions::default(),
             }))
-            .await;
+            .await
+            .unwrap();
         let request = Request::builder()
             .method(Method::GET)
             .uri(format!("/exec/{}", token))
@@ -3657,8 +4267,12 @@ mod tests {
             .body(Body::empty())
             .unwrap();
 
-        let response =
-            handle_request(server.cache.clone(), PathBuf::from("/bin/false"), request).await;
+        let response = handle_request(
+            server.sessions.clone(),
+            PathBuf::from("/bin/false"),
+            request,
+        )
+        .await;
         assert_eq!(response.status(), StatusCode::SWITCHING_PROTOCOLS);
         assert_eq!(
             response
@@ -3680,15 +4294,20 @@ mod tests {
                 stderr: true,
                 tty: false,
             }))
-            .await;
+            .await
+            .unwrap();
         let request = Request::builder()
             .method(Method::GET)
             .uri(format!("/attach/{}", token))
             .body(Body::empty())
             .unwrap();
 
-        let response =
-            handle_request(server.cache.clone(), PathBuf::from("/bin/false"), request).await;
+        let response = handle_request(
+            server.sessions.clone(),
+            PathBuf::from("/bin/false"),
+            request,
+        )
+        .await;
         assert_eq!(response.status(), StatusCode::BAD_REQUEST);
         assert!(response_body_string(response)
             .await
@@ -3725,7 +4344,8 @@ mod tests {
                 stderr: true,
                 tty: false,
             }))
-            .await;
+            .await
+            .unwrap();
         let request = Request::builder()
             .method(Method::GET)
             .uri(format!("/attach/{}", token))
@@ -3737,8 +4357,12 @@ mod tests {
             .body(Body::empty())
             .unwrap();
 
-        let response =
-            handle_request(server.cache.clone(), PathBuf::from("/bin/false"), request).await;
+        let response = handle_request(
+            server.sessions.clone(),
+            PathBuf::from("/bin/false"),
+            request,
+        )
+        .await;
         assert_eq!(response.status(), StatusCode::SWITCHING_PROTOCOLS);
         assert_eq!(
             response
@@ -3763,7 +4387,8 @@ mod tests {
                 },
                 log_path: PathBuf::from("/var/log/pods/abc.log"),
             }))
-            .await;
+            .await
+            .unwrap();
         let request = Request::builder()
             .method(Method::GET)
             .uri(format!("/attach/{}", token))
@@ -3775,8 +4400,12 @@ mod tests {
             .body(Body::empty())
             .unwrap();
 
-        let response =
-            handle_request(server.cache.clone(), PathBuf::from("/bin/false"), request).await;
+        let response = handle_request(
+            server.sessions.clone(),
+            PathBuf::from("/bin/false"),
+            request,
+        )
+        .await;
         assert_eq!(response.status(), StatusCode::SWITCHING_PROTOCOLS);
         assert_eq!(
             response
@@ -3814,15 +4443,20 @@ mod tests {
                 },
                 netns_path: PathBuf::from("/proc/thread-self/ns/net"),
             }))
-            .await;
+            .await
+            .unwrap();
         let request = Request::builder()
             .method(Method::POST)
             .uri(format!("/portforward/{}", token))
             .body(Body::empty())
             .unwrap();
 
-        let response =
-            handle_request(server.cache.clone(), PathBuf::from("/bin/false"), request).await;
+        let response = handle_request(
+            server.sessions.clone(),
+            PathBuf::from("/bin/false"),
+            request,
+        )
+        .await;
         assert_eq!(response.status(), StatusCode::BAD_REQUEST);
         assert!(response_body_string(response)
             .await
@@ -3840,7 +4474,8 @@ mod tests {
                 },
                 netns_path: PathBuf::from("/proc/thread-self/ns/net"),
             }))
-            .await;
+            .await
+            .unwrap();
         let request = Request::builder()
             .method(Method::GET)
             .uri(format!("/portforward/{}", token))
@@ -3852,8 +4487,12 @@ mod tests {
             .body(Body::empty())
             .unwrap();
 
-        let response =
-            handle_request(server.cache.clone(), PathBuf::from("/bin/false"), request).await;
+        let response = handle_request(
+            server.sessions.clone(),
+            PathBuf::from("/bin/false"),
+            request,
+        )
+        .await;
         assert_eq!(response.status(), StatusCode::SWITCHING_PROTOCOLS);
         assert_eq!(
             response
@@ -3882,7 +4521,8 @@ mod tests {
                 },
                 netns_path: PathBuf::from("/proc/thread-self/ns/net"),
             }))
-            .await;
+            .await
+            .unwrap();
         let request = Request::builder()
             .method(Method::POST)
             .uri(format!("/portforward/{}", token))
@@ -3891,8 +4531,12 @@ mod tests {
             .body(Body::empty())
             .unwrap();
 
-        let response =
-            handle_request(server.cache.clone(), PathBuf::from("/bin/false"), request).await;
+        let response = handle_request(
+            server.sessions.clone(),
+            PathBuf::from("/bin/false"),
+            request,
+        )
+        .await;
         assert_eq!(response.status(), StatusCode::FORBIDDEN);
         assert!(response_body_string(response)
             .await
@@ -3903,23 +4547,31 @@ mod tests {
     async fn test_portforward_route_rejects_token_kind_mismatch() {
         let server = StreamingServer::for_test("http://127.0.0.1:12345");
         let token = server
-            .insert_request(StreamingRequest::Exec(ExecRequest {
-                container_id: "abc".to_string(),
-                cmd: vec!["sh".to_string()],
-                stdin: true,
-                stdout: true,
-                stderr: false,
-                tty: true,
+            .insert_request(StreamingRequest::Exec(ExecRequestContext {
+                req: ExecRequest {
+                    container_id: "abc".to_string(),
+                    cmd: vec!["sh".to_string()],
+                    stdin: true,
+                    stdout: true,
+                    stderr: false,
+                    tty: true,
+                },
+                options: ExecOptions::default(),
             }))
-            .await;
+            .await
+            .unwrap();
         let request = Request::builder()
             .method(Method::POST)
             .uri(format!("/portforward/{}", token))
             .body(Body::empty())
             .unwrap();
 
-        let response =
-            handle_request(server.cache.clone(), PathBuf::from("/bin/false"), request).await;
+        let response = handle_request(
+            server.sessions.clone(),
+            PathBuf::from("/bin/false"),
+            request,
+        )
+        .await;
         assert_eq!(response.status(), StatusCode::BAD_REQUEST);
         assert!(response_body_string(response)
             .await
@@ -3931,13 +4583,16 @@ mod tests {
         let server = StreamingServer::for_test("http://127.0.0.1:12345");
         let token = server
             .insert_request_for_test(
-                StreamingRequest::Exec(ExecRequest {
-                    container_id: "abc".to_string(),
-                    cmd: vec!["sh".to_string()],
-                    stdin: false,
-                    stdout: true,
-                    stderr: true,
-                    tty: false,
+                StreamingRequest::Exec(ExecRequestContext {
+                    req: ExecRequest {
+                        container_id: "abc".to_string(),
+                        cmd: vec!["sh".to_string()],
+                        stdin: false,
+                        stdout: true,
+                        stderr: true,
+                        tty: false,
+                    },
+                    options: ExecOptions::default(),
                 }),
                 STREAMING_REQUEST_TTL + Duration::from_secs(1),
             )
@@ -3948,14 +4603,76 @@ mod tests {
             .body(Body::empty())
             .unwrap();
 
-        let response =
-            handle_request(server.cache.clone(), PathBuf::from("/bin/false"), request).await;
+        let response = handle_request(
+            server.sessions.clone(),
+            PathBuf::from("/bin/false"),
+            request,
+        )
+        .await;
         assert_eq!(response.status(), StatusCode::NOT_FOUND);
         assert!(response_body_string(response)
             .await
             .contains("streaming token not found"));
     }
 
+    fn limited_server(max_sessions: usize) -> StreamingServer {
+        StreamingServer {
+            sessions: Arc::new(SessionRegistry::new(&StreamingConfig {
+                max_sessions,
+                ..StreamingConfig::default()
+            })),
+            base_url: "http://127.0.0.1:12345".to_string(),
+        }
+    }
+
+    fn test_exec_request() -> ExecRequest {
+        ExecRequest {
+            container_id: "abc".to_string(),
+            cmd: vec!["sh".to_string()],
+            stdin: false,
+            stdout: true,
+            stderr: true,
+            tty: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sweep_expires_stale_tokens_and_frees_session_slots() {
+        let server = limited_server(1);
+        let stale = server
+            .insert_request_for_test(
+                StreamingRequest::Exec(ExecRequestContext {
+                    req: test_exec_request(),
+                    options: ExecOptions::default(),
+                }),
+                STREAMING_REQUEST_TTL + Duration::from_secs(1),
+            )
+            .await;
+
+        assert_eq!(server.sessions.sweep().await, 1);
+        assert!(server.sessions.take(&stale).await.is_none());
+        assert_eq!(server.sessions.sweep().await, 0);
+        server.get_exec(&test_exec_request()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_session_limit_returns_resource_exhausted() {
+        let server = limited_server(1);
+        let response = server.get_exec(&test_exec_request()).await.unwrap();
+
+        let err = server.get_exec(&test_exec_request()).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::ResourceExhausted);
+
+        // token 被使用后，已连接的会话仍占用名额，直到会话结束
+        let token = response.url.rsplit('/').next().unwrap().to_string();
+        assert!(server.sessions.take(&token).await.is_some());
+        let (_upgrade, session) = server.sessions.connect(Request::new(Body::empty()));
+        let err = server.get_exec(&test_exec_request()).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::ResourceExhausted);
+        drop(session);
+        server.get_exec(&test_exec_request()).await.unwrap();
+    }
+
     #[test]
     fn test_validate_portforward_request_rejects_invalid_values() {
         let missing_pod = PortForwardRequest {
@@ -4433,6 +5150,32 @@ mod tests {
         assert!(err.to_string().contains("greater than zero"));
     }
 
+    #[test]
+    fn test_exec_process_identity_skips_reused_pid() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let dir = tempdir().unwrap();
+        let pid_file = dir.path().join("exec.pid");
+        fs::write(&pid_file, child.id().to_string()).unwrap();
+
+        let identity = ExecProcessIdentity::read(&pid_file).unwrap();
+        // 启动时间不同说明 pid 已属于另一个进程，不发送信号
+        let reused = ExecProcessIdentity {
+            start_time: identity.start_time + 1,
+            ..identity
+        };
+        assert!(!reused.kill());
+        assert!(child.try_wait().unwrap().is_none());
+
+        assert!(identity.kill());
+        assert_eq!(
+            std::os::unix::process::ExitStatusExt::signal(&child.wait().unwrap()),
+            Some(9)
+        );
+    }
+
     #[test]
     fn test_shim_socket_path_honors_env_override() {
         std::env::set_var("CRIUS_SHIM_DIR", "/tmp/crius-shims");
@@ -4441,6 +5184,27 @@ mod tests {
             socket_path,
             PathBuf::from("/tmp/crius-shims/abc123/attach.sock")
         );
+
+        // 携带 stdin 的 attach 优先连接 attach-stdin.sock，旧 shim 没有时回退
+        let shim_root = tempdir().unwrap();
+        std::env::set_var("CRIUS_SHIM_DIR", shim_root.path());
+        let mut req = AttachRequest {
+            container_id: "abc123".to_string(),
+            stdin: true,
+            stdout: true,
+            stderr: false,
+            tty: false,
+        };
+        let container_dir = shim_root.path().join("abc123");
+        assert_eq!(attach_socket_path(&req), container_dir.join("attach.sock"));
+        fs::create_dir_all(&container_dir).unwrap();
+        fs::write(container_dir.join("attach-stdin.sock"), "").unwrap();
+        assert_eq!(
+            attach_socket_path(&req),
+            container_dir.join("attach-stdin.sock")
+        );
+        req.stdin = false;
+        assert_eq!(attach_socket_path(&req), container_dir.join("attach.sock"));
         std::env::remove_var("CRIUS_SHIM_DIR");
     }
 
@@ -4456,9 +5220,13 @@ mod tests {
             stream.write_all(&buffer).unwrap();
         });
 
-        let server = StreamingServer::start("127.0.0.1:0", PathBuf::from("/bin/false"))
-            .await
-            .unwrap();
+        let server = StreamingServer::start(
+            "127.0.0.1:0",
+            PathBuf::from("/bin/false"),
+            &StreamingConfig::default(),
+        )
+        .await
+        .unwrap();
         let response = server
             .get_port_forward(
                 &PortForwardRequest {
@@ -4572,9 +5340,13 @@ mod tests {
             stream.write_all(&reply).unwrap();
         });
 
-        let server = StreamingServer::start("127.0.0.1:0", PathBuf::from("/bin/false"))
-            .await
-            .unwrap();
+        let server = StreamingServer::start(
+            "127.0.0.1:0",
+            PathBuf::from("/bin/false"),
+            &StreamingConfig::default(),
+        )
+        .await
+        .unwrap();
         let response = server
             .get_port_forward(
                 &PortForwardRequest {
@@ -4723,9 +5495,12 @@ set -eu
 cmd="${1:-}"
 if [ "$cmd" = "exec" ]; then
   shift
-  if [ "${1:-}" = "-t" ]; then
+  while [ "${1:-}" = "--pid-file" ] || [ "${1:-}" = "-t" ]; do
+    if [ "$1" = "--pid-file" ]; then
+      shift
+    fi
     shift
-  fi
+  done
   shift
   exec "$@"
 fi
@@ -4742,9 +5517,10 @@ exit 1
         }
         fs::set_permissions(&runtime_path, perms).unwrap();
 
-        let server = StreamingServer::start("127.0.0.1:0", runtime_path)
-            .await
-            .unwrap();
+        let server =
+            StreamingServer::start("127.0.0.1:0", runtime_path, &StreamingConfig::default())
+                .await
+                .unwrap();
         let response = server
             .get_exec(&ExecRequest {
                 container_id: "container-1".to_string(),
@@ -4902,9 +5678,13 @@ exit 1
             stream.write_all(&output).unwrap();
         });
 
-        let server = StreamingServer::start("127.0.0.1:0", PathBuf::from("/bin/false"))
-            .await
-            .unwrap();
+        let server = StreamingServer::start(
+            "127.0.0.1:0",
+            PathBuf::from("/bin/false"),
+            &StreamingConfig::default(),
+        )
+        .await
+        .unwrap();
         let response = server
             .get_attach(&AttachRequest {
                 container_id: "container-1".to_string(),
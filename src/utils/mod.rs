@@ -2,6 +2,8 @@ use std::path::{Path, PathBuf};
 
 use crate::prelude::*;
 
+pub mod snapshot;
+
 /// 规范化路径
 pub fn normalize_path<P: AsRef<Path>>(path: P) -> PathBuf {
     let path = path.as_ref();
@@ -23,6 +25,39 @@ pub fn ensure_dir<P: AsRef<Path>>(path: P) -> Result<()> {
     Ok(())
 }
 
+/// 状态目录默认权限：仅属主可访问
+pub const DEFAULT_DIR_MODE: u32 = 0o700;
+
+/// 逐级创建目录，新建的各级目录权限为 `mode`（不受 umask 影响）
+///
+/// 已存在的目录保持原有权限。
+pub fn create_dir_all_with_mode<P: AsRef<Path>>(path: P, mode: u32) -> std::io::Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    let path = path.as_ref();
+    let missing: Vec<&Path> = path
+        .ancestors()
+        .take_while(|dir| !dir.as_os_str().is_empty() && !dir.exists())
+        .collect();
+    for dir in missing.into_iter().rev() {
+        match std::fs::DirBuilder::new().mode(mode).create(dir) {
+            Ok(()) => std::fs::set_permissions(dir, std::fs::Permissions::from_mode(mode))?,
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists && dir.is_dir() => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+/// 创建目录并把目录自身权限设为 `mode`，用于镜像存储、bundle 等敏感目录
+pub fn ensure_private_dir<P: AsRef<Path>>(path: P, mode: u32) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = path.as_ref();
+    create_dir_all_with_mode(path, mode)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+}
+
 /// 生成随机ID
 pub fn generate_id(prefix: &str) -> String {
     use rand::Rng;
@@ -35,3 +70,66 @@ pub fn generate_id(prefix: &str) -> String {
 pub fn file_exists<P: AsRef<Path>>(path: P) -> bool {
     Path::new(path.as_ref()).exists()
 }
+
+/// 当前墙钟时间（自 epoch 起的纳秒）
+///
+/// 进程内严格递增：系统时间回拨或同一纳秒内多次调用时，返回上一次结果加一，
+/// 保证 created_at/started_at/finished_at 等时间戳的先后顺序。
+pub fn now_nanos() -> i64 {
+    use std::sync::atomic::{AtomicI64, Ordering};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    static LAST: AtomicI64 = AtomicI64::new(0);
+    let wall = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| i64::try_from(elapsed.as_nanos()).unwrap_or(i64::MAX))
+        .unwrap_or_default();
+    let previous = LAST
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| {
+            Some(wall.max(last.saturating_add(1)))
+        })
+        .unwrap_or_default();
+    wall.max(previous.saturating_add(1))
+}
+
+/// `/proc/<pid>/stat` 中的进程启动时间（开机后的时钟滴答数），进程不存在时返回 `None`
+///
+/// 与 pid 一起标识进程：pid 被复用后启动时间不同，发送信号前据此核对。
+pub fn process_start_time(pid: i32) -> Option<u64> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // comm 可能包含空格和括号，从最后一个 ')' 之后开始按字段切分；starttime 是第 22 个字段
+    let (_, fields) = stat.rsplit_once(')')?;
+    fields.split_whitespace().nth(19)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn mode_of(path: &Path) -> u32 {
+        std::fs::metadata(path).unwrap().permissions().mode() & 0o7777
+    }
+
+    #[test]
+    fn created_directories_use_requested_mode_regardless_of_umask() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let existing = temp_dir.path().join("existing");
+        std::fs::create_dir(&existing).unwrap();
+        std::fs::set_permissions(&existing, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let nested = existing.join("a").join("b");
+        create_dir_all_with_mode(&nested, 0o750).unwrap();
+        assert_eq!(mode_of(&existing), 0o755);
+        assert_eq!(mode_of(&existing.join("a")), 0o750);
+        assert_eq!(mode_of(&nested), 0o750);
+        create_dir_all_with_mode(&nested, 0o750).unwrap();
+
+        ensure_private_dir(&existing, DEFAULT_DIR_MODE).unwrap();
+        assert_eq!(mode_of(&existing), 0o700);
+
+        let file = temp_dir.path().join("file");
+        std::fs::write(&file, b"").unwrap();
+        assert!(create_dir_all_with_mode(file.join("child"), 0o700).is_err());
+    }
+}
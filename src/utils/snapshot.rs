@@ -0,0 +1,110 @@
+//! 只读快照缓存
+//!
+//! List 类请求被频繁轮询，每次都深拷贝全部记录代价很高。快照在第一次读取时构建一次，
+//! 之后的读取只克隆 `Arc`；数据变更时作废，下次读取再重建。
+
+use std::sync::{Arc, OnceLock};
+
+/// 记录集合的共享快照
+pub type Snapshot<T> = Arc<[Arc<T>]>;
+
+/// 快照缓存，与被缓存的数据处于同一把锁下，修改数据时必须调用 [`SnapshotCache::invalidate`]
+#[derive(Debug)]
+pub struct SnapshotCache<T> {
+    snapshot: OnceLock<Snapshot<T>>,
+}
+
+impl<T> Default for SnapshotCache<T> {
+    fn default() -> Self {
+        Self {
+            snapshot: OnceLock::new(),
+        }
+    }
+}
+
+/// 克隆出的数据副本重新构建自己的快照
+impl<T> Clone for SnapshotCache<T> {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl<T: Clone> SnapshotCache<T> {
+    /// 返回当前快照，缓存失效时用 `records` 重建
+    pub fn get_or_build<'a, I>(&self, records: impl FnOnce() -> I) -> Snapshot<T>
+    where
+        I: IntoIterator<Item = &'a T>,
+        T: 'a,
+    {
+        self.snapshot
+            .get_or_init(|| records().into_iter().cloned().map(Arc::new).collect())
+            .clone()
+    }
+
+    pub fn invalidate(&mut self) {
+        self.snapshot.take();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_snapshot_is_shared_until_invalidated() {
+        let mut records = vec!["a".to_string(), "b".to_string()];
+        let mut cache = SnapshotCache::default();
+
+        let first = cache.get_or_build(|| records.iter());
+        let second = cache.get_or_build(|| records.iter());
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(Arc::ptr_eq(&first[0], &second[0]));
+
+        records.push("c".to_string());
+        cache.invalidate();
+        let third = cache.get_or_build(|| records.iter());
+        assert!(!Arc::ptr_eq(&first, &third));
+        assert_eq!(third.len(), 3);
+        // 旧快照在持有者释放前保持不变
+        assert_eq!(first.len(), 2);
+    }
+
+    /// 克隆时计数的记录
+    #[derive(Debug)]
+    struct CountingRecord {
+        clones: Arc<AtomicUsize>,
+    }
+
+    impl Clone for CountingRecord {
+        fn clone(&self) -> Self {
+            self.clones.fetch_add(1, Ordering::SeqCst);
+            Self {
+                clones: self.clones.clone(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_repeated_reads_clone_each_record_once_per_rebuild() {
+        let clones = Arc::new(AtomicUsize::new(0));
+        let records: Vec<CountingRecord> = (0..1000)
+            .map(|_| CountingRecord {
+                clones: clones.clone(),
+            })
+            .collect();
+        let mut cache = SnapshotCache::default();
+
+        // 模拟 kubelet 轮询：多次 List 只在第一次复制记录
+        for _ in 0..100 {
+            assert_eq!(cache.get_or_build(|| records.iter()).len(), 1000);
+        }
+        assert_eq!(clones.load(Ordering::SeqCst), 1000);
+
+        cache.invalidate();
+        for _ in 0..100 {
+            cache.get_or_build(|| records.iter());
+        }
+        assert_eq!(clones.load(Ordering::SeqCst), 2000);
+    }
+}
@@ -3,6 +3,7 @@
 //! 本文件统一承载两类测试：
 //! 1. 系统级集成测试：直接依赖 `runc`、rootfs、root 权限。
 //! 2. 跨模块集成测试：验证 `storage` / 持久化恢复 / pod-container 关联流程。
+//! 3. 守护进程接口测试：在 Unix socket 上启动 CRI 服务，经 `crius::client` 调用。
 
 use crius::storage::{ContainerRecord, PodSandboxRecord, StorageManager};
 
@@ -156,6 +157,8 @@ mod system_runtime {
             namespace_paths: NamespacePaths::default(),
             linux_resources: None,
             devices: vec![],
+            shm_size: None,
+            shm_path: None,
             rootfs: rootfs.clone(),
         };
 
@@ -248,6 +251,8 @@ mod system_runtime {
             namespace_paths: NamespacePaths::default(),
             linux_resources: None,
             devices: vec![],
+            shm_size: None,
+            shm_path: None,
             rootfs: rootfs.clone(),
         };
 
@@ -502,3 +507,385 @@ mod cross_module {
         assert_eq!(recovered_pods[0].ip.as_deref(), Some("10.88.0.20"));
     }
 }
+
+mod daemon_client {
+    use super::temp_dir;
+    use crius::client::CriusClient;
+    use crius::config::GrpcConfig;
+    use crius::image::ImageServiceImpl;
+    use crius::proto::runtime::v1::image_service_server::ImageServiceServer;
+    use crius::proto::runtime::v1::runtime_service_server::RuntimeServiceServer;
+    use crius::proto::runtime::v1::{
+        ContainerStatusRequest, ImageSpec, ImageStatusRequest, PodSandboxStatusRequest,
+        RemoveContainerRequest, RemoveImageRequest, VersionRequest,
+    };
+    use crius::server::{
+        authorize_peers, limit_connections, server_builder, CatchPanicLayer, ReadOnlyLayer,
+        RuntimeConfig, RuntimeServiceImpl,
+    };
+    use tokio::net::UnixListener;
+    use tokio::sync::oneshot;
+    use tokio::task::JoinHandle;
+    use tokio_stream::wrappers::UnixListenerStream;
+    use tonic::Code;
+
+    /// 携带该 header 的请求会在 handler 中 panic，用于验证 panic 边界
+    const PANIC_TRIGGER_HEADER: &str = "x-crius-test-panic";
+
+    /// 在临时 Unix socket 上运行的 RuntimeService/ImageService
+    struct TestDaemon {
+        _temp_dir: tempfile::TempDir,
+        socket: String,
+        shutdown: oneshot::Sender<()>,
+        server: JoinHandle<Result<(), tonic::transport::Error>>,
+    }
+
+    impl TestDaemon {
+        fn start() -> Self {
+            Self::start_with(GrpcConfig::default(), false)
+        }
+
+        fn start_with(grpc: GrpcConfig, read_only: bool) -> Self {
+            let temp_dir = temp_dir();
+            let root_dir = temp_dir.path().join("root");
+            let config = RuntimeConfig {
+                state_dir: root_dir.clone(),
+                image_root: root_dir.join("storage"),
+                runtime_root: temp_dir.path().join("runtime-root"),
+                log_dir: temp_dir.path().join("logs"),
+                root_dir,
+                reconcile_interval: std::time::Duration::ZERO,
+                ..RuntimeConfig::default()
+            };
+            let runtime_service = RuntimeServiceImpl::new(config.clone());
+            let image_service = ImageServiceImpl::new(&config.image_root).unwrap();
+
+            let socket_path = temp_dir.path().join("crius.sock");
+            let listener = UnixListener::bind(&socket_path).unwrap();
+            let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+            let server = tokio::spawn(
+                server_builder(&grpc)
+                    .layer(CatchPanicLayer)
+                    .layer(ReadOnlyLayer::new(read_only))
+                    .layer(tower::util::MapRequestLayer::new(
+                        |request: tonic::codegen::http::Request<tonic::transport::Body>| {
+                            if request.headers().contains_key(PANIC_TRIGGER_HEADER) {
+                                panic!("handler panic triggered by test");
+                            }
+                            request
+                        },
+                    ))
+                    .add_service(RuntimeServiceServer::new(runtime_service))
+                    .add_service(ImageServiceServer::new(image_service))
+                    .serve_with_incoming_shutdown(
+                        limit_connections(
+                            authorize_peers(UnixListenerStream::new(listener), &grpc),
+                            grpc.max_connections,
+                        ),
+                        async {
+                            let _ = shutdown_rx.await;
+                        },
+                    ),
+            );
+
+            Self {
+                socket: format!("unix://{}", socket_path.display()),
+                _temp_dir: temp_dir,
+                shutdown,
+                server,
+            }
+        }
+
+        async fn client(&self) -> CriusClient {
+            CriusClient::connect(&self.socket).await.unwrap()
+        }
+
+        async fn stop(self) {
+            let _ = self.shutdown.send(());
+            self.server.await.unwrap().unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_client_talks_to_daemon_over_unix_socket() {
+        let daemon = TestDaemon::start();
+        let mut client = daemon.client().await;
+
+        let version = client.version().await.unwrap();
+        assert_eq!(version.runtime_name, "runc");
+        assert_eq!(version.runtime_api_version, "v1");
+        assert!(client.list_pod_sandbox(None).await.unwrap().is_empty());
+        assert!(client.list_containers(None).await.unwrap().is_empty());
+        assert!(client.list_images().await.unwrap().is_empty());
+        assert!(client.start_container("missing").await.is_err());
+
+        daemon.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_dump_collects_daemon_state_over_unix_socket() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let daemon = TestDaemon::start();
+        let mut client = daemon.client().await;
+        let config_path = std::path::PathBuf::from("/etc/crius/crius.conf");
+        let config = crius::config::Config::default();
+
+        let dump = crius::dump::collect(&mut client, Some((&config_path, &config)), false)
+            .await
+            .unwrap();
+        let output = daemon._temp_dir.path().join("dump.json");
+        crius::dump::write(&output, &dump).unwrap();
+
+        let written: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&output).unwrap()).unwrap();
+        assert_eq!(written, dump);
+        assert_eq!(
+            std::fs::metadata(&output).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+        assert_eq!(written["version"]["runtimeName"], "runc");
+        assert_eq!(written["runtime"]["conditions"][0]["type"], "RuntimeReady");
+        assert!(written["runtime"]["info"]["config"]["cniConfigDirs"].is_array());
+        assert_eq!(
+            written["configFile"]["config"]["runtime"]["runtime_type"],
+            config.runtime.runtime_type
+        );
+        for section in ["podSandboxes", "containers", "images", "cniConfigs"] {
+            assert!(written[section].is_array(), "{}", section);
+        }
+        assert!(written["podSandboxes"].as_array().unwrap().is_empty());
+
+        daemon.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_image_service_contract_over_unix_socket() {
+        let daemon = TestDaemon::start();
+        let mut client = daemon.client().await;
+
+        let status = client
+            .image()
+            .image_status(ImageStatusRequest {
+                image: Some(ImageSpec {
+                    image: "missing:latest".to_string(),
+                    ..Default::default()
+                }),
+                verbose: false,
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(status.image.is_none());
+
+        let err = client
+            .image()
+            .image_status(ImageStatusRequest {
+                image: None,
+                verbose: false,
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+
+        // 删除不存在的镜像同样幂等成功
+        client
+            .image()
+            .remove_image(RemoveImageRequest {
+                image: Some(ImageSpec {
+                    image: "missing:latest".to_string(),
+                    ..Default::default()
+                }),
+            })
+            .await
+            .unwrap();
+
+        daemon.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_runtime_service_error_codes_over_unix_socket() {
+        let daemon = TestDaemon::start();
+        let mut client = daemon.client().await;
+
+        let err = client
+            .runtime()
+            .container_status(ContainerStatusRequest {
+                container_id: "missing".to_string(),
+                verbose: false,
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::NotFound);
+
+        let err = client
+            .runtime()
+            .pod_sandbox_status(PodSandboxStatusRequest {
+                pod_sandbox_id: "missing".to_string(),
+                verbose: false,
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::NotFound);
+
+        let err = client.start_container("missing").await.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<tonic::Status>()
+                .map(|status| status.code()),
+            Some(Code::NotFound)
+        );
+
+        // CRI 要求 RemoveContainer/StopContainer 对不存在的容器幂等成功
+        client
+            .runtime()
+            .remove_container(RemoveContainerRequest {
+                container_id: "missing".to_string(),
+            })
+            .await
+            .unwrap();
+        client.stop_container("missing", 0).await.unwrap();
+
+        daemon.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_handler_panic_returns_internal_and_connection_survives() {
+        let daemon = TestDaemon::start();
+        let mut client = daemon.client().await;
+
+        let mut request = tonic::Request::new(VersionRequest {
+            version: "v1".to_string(),
+        });
+        request
+            .metadata_mut()
+            .insert(PANIC_TRIGGER_HEADER, "1".parse().unwrap());
+        let err = client.runtime().version(request).await.unwrap_err();
+        assert_eq!(err.code(), Code::Internal);
+        assert!(!err.message().contains("triggered by test"));
+
+        // 同一连接上的后续请求不受影响
+        assert_eq!(client.version().await.unwrap().runtime_api_version, "v1");
+        assert!(client.list_containers(None).await.unwrap().is_empty());
+
+        daemon.stop().await;
+    }
+
+    /// 新建一个原始连接，判断它是否立即被服务端关闭
+    async fn connection_rejected(socket: &str) -> bool {
+        use tokio::io::AsyncReadExt;
+
+        let path = socket.trim_start_matches("unix://");
+        let mut stream = tokio::net::UnixStream::connect(path).await.unwrap();
+        let mut buf = [0u8; 64];
+        matches!(
+            tokio::time::timeout(std::time::Duration::from_millis(500), stream.read(&mut buf))
+                .await,
+            Ok(Ok(0)) | Ok(Err(_))
+        )
+    }
+
+    #[tokio::test]
+    async fn test_connections_over_limit_are_rejected() {
+        let daemon = TestDaemon::start_with(
+            GrpcConfig {
+                max_connections: 1,
+                ..GrpcConfig::default()
+            },
+            false,
+        );
+        let mut first = daemon.client().await;
+        first.version().await.unwrap();
+
+        // 名额被占满时新连接被关闭，已有连接不受影响
+        assert!(connection_rejected(&daemon.socket).await);
+        assert_eq!(first.version().await.unwrap().runtime_api_version, "v1");
+
+        // 第一个连接断开后名额归还
+        drop(first);
+        let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(5);
+        while connection_rejected(&daemon.socket).await {
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "connection slot was not released"
+            );
+        }
+        let mut client = daemon.client().await;
+        assert_eq!(client.version().await.unwrap().runtime_api_version, "v1");
+        drop(client);
+
+        daemon.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_unix_socket_peers_are_authorized_by_uid_and_gid() {
+        let uid = nix::unistd::geteuid().as_raw();
+        let gid = nix::unistd::getegid().as_raw();
+
+        let allowed = TestDaemon::start_with(
+            GrpcConfig {
+                allowed_uids: vec![uid],
+                ..GrpcConfig::default()
+            },
+            false,
+        );
+        let mut client = allowed.client().await;
+        assert_eq!(client.version().await.unwrap().runtime_api_version, "v1");
+        drop(client);
+        allowed.stop().await;
+
+        let allowed_by_group = TestDaemon::start_with(
+            GrpcConfig {
+                allowed_uids: vec![uid.wrapping_add(1)],
+                allowed_gids: vec![gid],
+                ..GrpcConfig::default()
+            },
+            false,
+        );
+        assert!(!connection_rejected(&allowed_by_group.socket).await);
+        allowed_by_group.stop().await;
+
+        let denied = TestDaemon::start_with(
+            GrpcConfig {
+                allowed_uids: vec![uid.wrapping_add(1)],
+                allowed_gids: vec![gid.wrapping_add(1)],
+                ..GrpcConfig::default()
+            },
+            false,
+        );
+        assert!(connection_rejected(&denied.socket).await);
+        denied.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_read_only_endpoint_rejects_mutating_calls() {
+        let daemon = TestDaemon::start_with(GrpcConfig::default(), true);
+        let mut client = daemon.client().await;
+
+        assert_eq!(client.version().await.unwrap().runtime_api_version, "v1");
+        assert!(client.list_containers(None).await.unwrap().is_empty());
+        assert!(client.list_images().await.unwrap().is_empty());
+
+        let err = client
+            .runtime()
+            .remove_container(RemoveContainerRequest {
+                container_id: "missing".to_string(),
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::PermissionDenied);
+        let err = client
+            .image()
+            .remove_image(RemoveImageRequest {
+                image: Some(ImageSpec {
+                    image: "busybox:latest".to_string(),
+                    ..Default::default()
+                }),
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::PermissionDenied);
+        drop(client);
+
+        daemon.stop().await;
+    }
+}